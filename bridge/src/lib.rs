@@ -0,0 +1,355 @@
+//! Moving tokens between this chain and a sibling Tuxedo chain.
+//!
+//! Each token that crosses the bridge is native to one of the two chains. Sending a native
+//! token to the sibling locks it on this chain, and the sibling mints a wrapped coin that
+//! represents it. Sending the wrapped coin back burns it on the sibling, and this chain
+//! releases the same amount of the locked token. Wrapped coins are ordinary `Cash`, so the
+//! dex can list pairs that trade them against local tokens.
+//!
+//! Either way, the sending side spends its coins into an [`Outbound`] transfer UTXO, and
+//! the receiving side creates coins after checking a proof that the transfer exists in the
+//! sibling's UTXO set. The proof is checked against the UTXO set commitment of a sibling
+//! block header. Constraint checkers cannot follow the sibling's consensus, so the bridge
+//! trusts a set of relayers to tell it about sibling headers instead. The latest relayed
+//! header is kept in a UTXO protected by the relayers' verifier, and each commitment they
+//! relay is recorded in storage as a side effect, in the same way that the governance piece
+//! records parameters. This makes it a light bridge: it is only as trustworthy as the
+//! relayers.
+//!
+//! The receiving side records every transfer it has paid out, so that each transfer is
+//! only received once. A chain also keeps track of how much of each native token is
+//! locked, and never releases more than that.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    traits::Cash,
+    types::{Output, OutputRef},
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The prefix of the storage keys under which relayed sibling commitments are recorded.
+pub const SIBLING_COMMITMENT_PREFIX: &[u8] = b"bridge_sibling_commitment";
+
+/// The prefix of the storage keys that mark sibling transfers as received.
+pub const RECEIVED_PREFIX: &[u8] = b"bridge_received";
+
+/// The prefix of the storage keys under which the locked amount of each native token is
+/// recorded.
+pub const LOCKED_PREFIX: &[u8] = b"bridge_locked";
+
+/// The UTXO set commitment of the sibling block with the given number, if it was relayed.
+pub fn sibling_commitment(number: u32) -> Option<H256> {
+    sp_io::storage::get(&(SIBLING_COMMITMENT_PREFIX, number).encode())
+        .and_then(|encoded| H256::decode(&mut &encoded[..]).ok())
+}
+
+/// Whether the sibling transfer with the given reference has already been received.
+pub fn is_received(transfer: &OutputRef) -> bool {
+    sp_io::storage::exists(&(RECEIVED_PREFIX, transfer).encode())
+}
+
+/// How much of the native token with the given id is locked on this chain.
+pub fn locked(token: u8) -> u128 {
+    sp_io::storage::get(&(LOCKED_PREFIX, token).encode())
+        .and_then(|encoded| u128::decode(&mut &encoded[..]).ok())
+        .unwrap_or_default()
+}
+
+fn set_locked(token: u8, amount: u128) {
+    sp_io::storage::set(&(LOCKED_PREFIX, token).encode(), &amount.encode());
+}
+
+/// The configuration of one token that crosses the bridge.
+pub trait BridgeConfig {
+    /// The type of verifiers that can receive transfers.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The coin that represents the token on this chain.
+    type Token: Cash + UtxoData + From<u128>;
+
+    /// The id of the coin that represents the same token on the sibling.
+    const SIBLING_TOKEN: u8;
+
+    /// Whether the token is native to this chain, so that it is locked when sent and
+    /// released when received. Otherwise it is a wrapped token that is burned when sent
+    /// and minted when received.
+    const NATIVE: bool;
+
+    /// Whether the proof shows the output to be part of the sibling's UTXO set with the
+    /// given commitment, under the given reference.
+    fn is_included(
+        commitment: H256,
+        output_ref: &OutputRef,
+        output: &Output<Self::Verifier>,
+        proof: &InclusionProof,
+    ) -> bool;
+}
+
+/// The path from a UTXO's leaf to the root of the sibling's UTXO set commitment.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default, TypeInfo)]
+pub struct InclusionProof {
+    /// The position of the UTXO's leaf among all the leaves
+    pub leaf_index: u32,
+    /// The total number of leaves in the tree
+    pub leaf_count: u32,
+    /// The siblings of the nodes on the path from the leaf to the root, bottom first
+    pub siblings: Vec<H256>,
+}
+
+/// The type id of transfers of the token that has the given coin id on the sending chain.
+pub const fn outbound_type_id(token: u8) -> [u8; 4] {
+    [b'b', b'r', b'g', token]
+}
+
+/// Tokens on their way to the sibling chain.
+///
+/// Once a transfer is created, the sibling may pay it out to the recipient, and the
+/// transfer itself is of no further use.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct Outbound<T: BridgeConfig> {
+    /// The amount of the token that is sent
+    pub amount: u128,
+    /// The verifier that will protect the coins on the sibling
+    pub recipient: T::Verifier,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: BridgeConfig> UtxoData for Outbound<T> {
+    const TYPE_ID: [u8; 4] = outbound_type_id(T::Token::ID);
+}
+
+/// The latest sibling block header that the relayers told the bridge about.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default, TypeInfo)]
+pub struct SiblingHeader {
+    /// The number of the sibling block
+    pub number: u32,
+    /// The commitment to the sibling's UTXO set in that block's header
+    pub utxo_commitment: H256,
+}
+
+impl UtxoData for SiblingHeader {
+    const TYPE_ID: [u8; 4] = *b"sibh";
+}
+
+/// All the things that can go wrong while crossing the bridge.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum BridgeError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// The total amount of some coins does not fit in a `u128`.
+    AmountOverflow,
+    /// A transfer must consume at least one coin.
+    NothingSent,
+    /// A transfer must create exactly one output, which is the transfer itself.
+    TransferMissing,
+    /// The amount of a transfer must be exactly the value of the coins it consumes.
+    TransferAmountMismatch,
+    /// Receiving a transfer creates coins, and must not consume anything.
+    UnexpectedInputs,
+    /// The relayers have not relayed the sibling block that the proof is against.
+    UnknownSiblingBlock,
+    /// The proof does not show the output to be in the sibling's UTXO set.
+    InvalidInclusionProof,
+    /// The proven sibling output is not a transfer of this token.
+    NotATransfer,
+    /// The transfer has already been received.
+    AlreadyReceived,
+    /// Receiving a transfer must create exactly one output, which is the received coin.
+    PayoutMissing,
+    /// The received coin is not worth the transferred amount.
+    PayoutAmountMismatch,
+    /// The received coin is not protected by the transfer's recipient.
+    PayoutVerifierMismatch,
+    /// Releasing the transfer would release more of a native token than is locked.
+    InsufficientLocked,
+    /// Relaying a header must consume exactly one input, which is the latest header.
+    MissingLatestHeader,
+    /// Relaying a header must create exactly one output, which is the new header.
+    MissingNewHeader,
+    /// Relayed headers must be newer than the latest one.
+    HeaderNotNewer,
+    /// The new header must stay under the control of the same relayers.
+    RelayersChanged,
+}
+
+impl From<DynamicTypingError> for BridgeError {
+    fn from(_value: DynamicTypingError) -> Self {
+        BridgeError::TypeError
+    }
+}
+
+/// Send coins to the sibling chain, locking them if the token is native here and burning
+/// them otherwise.
+///
+/// All inputs are coins. The only output is the [`Outbound`] transfer, which must be for
+/// exactly the value of the coins.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct SendToSibling<T: BridgeConfig>(pub PhantomData<T>);
+
+impl<T: BridgeConfig> ConstraintChecker<T::Verifier> for SendToSibling<T> {
+    type Error = BridgeError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), BridgeError::NothingSent);
+        let mut total = 0u128;
+        for input in inputs {
+            let coin: T::Token = input.payload.extract()?;
+            total = total
+                .checked_add(coin.value())
+                .ok_or(BridgeError::AmountOverflow)?;
+        }
+
+        ensure!(outputs.len() == 1, BridgeError::TransferMissing);
+        let transfer: Outbound<T> = outputs[0].payload.extract()?;
+        ensure!(
+            transfer.amount == total,
+            BridgeError::TransferAmountMismatch
+        );
+
+        if T::NATIVE {
+            let locked = locked(T::Token::ID)
+                .checked_add(total)
+                .ok_or(BridgeError::AmountOverflow)?;
+            // SIDE EFFECT: Remember how much may be released again
+            set_locked(T::Token::ID, locked);
+        }
+
+        Ok(0)
+    }
+}
+
+/// Receive a transfer from the sibling chain, releasing locked coins if the token is
+/// native here and minting wrapped ones otherwise.
+///
+/// There are no inputs. The only output is the received coin, which must be worth the
+/// transferred amount and be protected by the transfer's recipient.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct ReceiveFromSibling<T: BridgeConfig> {
+    /// The number of the relayed sibling block that the proof is against
+    pub sibling_block: u32,
+    /// The reference of the transfer on the sibling
+    pub transfer: OutputRef,
+    /// The transfer as it is stored on the sibling
+    pub sibling_output: Output<T::Verifier>,
+    /// The proof that the transfer is part of the sibling's UTXO set
+    pub proof: InclusionProof,
+}
+
+impl<T: BridgeConfig> ConstraintChecker<T::Verifier> for ReceiveFromSibling<T> {
+    type Error = BridgeError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.is_empty(), BridgeError::UnexpectedInputs);
+
+        let commitment =
+            sibling_commitment(self.sibling_block).ok_or(BridgeError::UnknownSiblingBlock)?;
+        ensure!(
+            T::is_included(
+                commitment,
+                &self.transfer,
+                &self.sibling_output,
+                &self.proof
+            ),
+            BridgeError::InvalidInclusionProof
+        );
+
+        // The transfer is typed by the sibling's coin id, not ours.
+        let payload = &self.sibling_output.payload;
+        ensure!(
+            payload.type_id == outbound_type_id(T::SIBLING_TOKEN),
+            BridgeError::NotATransfer
+        );
+        let transfer =
+            Outbound::<T>::decode(&mut &payload.data[..]).map_err(|_| BridgeError::NotATransfer)?;
+        ensure!(!is_received(&self.transfer), BridgeError::AlreadyReceived);
+
+        ensure!(outputs.len() == 1, BridgeError::PayoutMissing);
+        let coin: T::Token = outputs[0].payload.extract()?;
+        ensure!(
+            coin.value() == transfer.amount,
+            BridgeError::PayoutAmountMismatch
+        );
+        ensure!(
+            outputs[0].verifier == transfer.recipient,
+            BridgeError::PayoutVerifierMismatch
+        );
+
+        if T::NATIVE {
+            let locked = locked(T::Token::ID)
+                .checked_sub(transfer.amount)
+                .ok_or(BridgeError::InsufficientLocked)?;
+            // SIDE EFFECT: Released coins may not be released again
+            set_locked(T::Token::ID, locked);
+        }
+
+        // SIDE EFFECT: Each transfer is only received once
+        sp_io::storage::set(&(RECEIVED_PREFIX, &self.transfer).encode(), &[]);
+
+        Ok(0)
+    }
+}
+
+/// Tell the bridge about a newer sibling block header.
+///
+/// The only input is the latest header and the only output is the new one, which must be
+/// newer and be protected by the same verifier.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default, TypeInfo)]
+pub struct RelaySiblingHeader;
+
+impl<V: Verifier + PartialEq> ConstraintChecker<V> for RelaySiblingHeader {
+    type Error = BridgeError;
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        outputs: &[Output<V>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, BridgeError::MissingLatestHeader);
+        ensure!(outputs.len() == 1, BridgeError::MissingNewHeader);
+
+        let latest: SiblingHeader = inputs[0].payload.extract()?;
+        let new: SiblingHeader = outputs[0].payload.extract()?;
+        ensure!(new.number > latest.number, BridgeError::HeaderNotNewer);
+        ensure!(
+            outputs[0].verifier == inputs[0].verifier,
+            BridgeError::RelayersChanged
+        );
+
+        // SIDE EFFECT: Record the commitment so that transfers can be proven against it
+        sp_io::storage::set(
+            &(SIBLING_COMMITMENT_PREFIX, new.number).encode(),
+            &new.utxo_commitment.encode(),
+        );
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,287 @@
+//! Unit tests for the bridge piece
+
+use super::*;
+use money::Coin;
+use sp_io::TestExternalities;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+/// A token native to this chain, which is wrapped as coin 2 on the sibling.
+#[derive(PartialEq, Eq, TypeInfo)]
+struct NativeConfig;
+impl BridgeConfig for NativeConfig {
+    type Verifier = TestVerifier;
+    type Token = Coin<0>;
+    const SIBLING_TOKEN: u8 = 2;
+    const NATIVE: bool = true;
+
+    fn is_included(
+        commitment: H256,
+        output_ref: &OutputRef,
+        output: &Output<TestVerifier>,
+        proof: &InclusionProof,
+    ) -> bool {
+        single_leaf_commitment(output_ref, output) == commitment && proof.leaf_count == 1
+    }
+}
+
+/// A token native to the sibling, where it is coin 0, and wrapped as coin 2 here.
+#[derive(PartialEq, Eq, TypeInfo)]
+struct WrappedConfig;
+impl BridgeConfig for WrappedConfig {
+    type Verifier = TestVerifier;
+    type Token = Coin<2>;
+    const SIBLING_TOKEN: u8 = 0;
+    const NATIVE: bool = false;
+
+    fn is_included(
+        commitment: H256,
+        output_ref: &OutputRef,
+        output: &Output<TestVerifier>,
+        proof: &InclusionProof,
+    ) -> bool {
+        NativeConfig::is_included(commitment, output_ref, output, proof)
+    }
+}
+
+/// The commitment to a UTXO set that holds nothing but the given output.
+fn single_leaf_commitment(output_ref: &OutputRef, output: &Output<TestVerifier>) -> H256 {
+    sp_io::hashing::blake2_256(&(output_ref, output).encode()).into()
+}
+
+fn output(payload: impl Into<DynamicallyTypedData>, verifies: bool) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies },
+    }
+}
+
+fn transfer_ref() -> OutputRef {
+    OutputRef {
+        tx_hash: H256::repeat_byte(7),
+        index: 0,
+    }
+}
+
+fn outbound<T: BridgeConfig<Verifier = TestVerifier>>(amount: u128) -> Outbound<T> {
+    Outbound {
+        amount,
+        recipient: TestVerifier { verifies: true },
+        _ph_data: Default::default(),
+    }
+}
+
+fn send<T: BridgeConfig<Verifier = TestVerifier>>(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, BridgeError> {
+    SendToSibling::<T>::default().check(inputs, outputs)
+}
+
+/// Relay a sibling block whose UTXO set only holds the given transfer, and build the
+/// checker that receives it.
+fn relay_transfer<T: BridgeConfig<Verifier = TestVerifier>>(
+    sibling_output: Output<TestVerifier>,
+) -> ReceiveFromSibling<T> {
+    let header = SiblingHeader {
+        number: 1,
+        utxo_commitment: single_leaf_commitment(&transfer_ref(), &sibling_output),
+    };
+    let relayed = RelaySiblingHeader.check(
+        &[output(SiblingHeader::default(), true)],
+        &[output(header, true)],
+    );
+    assert_eq!(relayed, Ok(0));
+
+    ReceiveFromSibling {
+        sibling_block: 1,
+        transfer: transfer_ref(),
+        sibling_output,
+        proof: InclusionProof {
+            leaf_index: 0,
+            leaf_count: 1,
+            siblings: vec![],
+        },
+    }
+}
+
+/// The sibling's transfer of the token that it calls coin `ID`.
+fn sibling_transfer<const ID: u8>(amount: u128) -> Output<TestVerifier> {
+    Output {
+        payload: DynamicallyTypedData {
+            data: outbound::<NativeConfig>(amount).encode(),
+            type_id: outbound_type_id(ID),
+        },
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+#[test]
+fn sending_native_tokens_locks_them() {
+    TestExternalities::default().execute_with(|| {
+        let result = send::<NativeConfig>(
+            &[output(Coin::<0>(3), true), output(Coin::<0>(4), true)],
+            &[output(outbound::<NativeConfig>(7), true)],
+        );
+        assert_eq!(result, Ok(0));
+        assert_eq!(locked(0), 7);
+    });
+}
+
+#[test]
+fn sending_wrapped_tokens_burns_them() {
+    TestExternalities::default().execute_with(|| {
+        let result = send::<WrappedConfig>(
+            &[output(Coin::<2>(5), true)],
+            &[output(outbound::<WrappedConfig>(5), true)],
+        );
+        assert_eq!(result, Ok(0));
+        assert_eq!(locked(2), 0);
+    });
+}
+
+#[test]
+fn sending_must_transfer_exactly_the_inputs() {
+    TestExternalities::default().execute_with(|| {
+        let result = send::<NativeConfig>(
+            &[output(Coin::<0>(3), true)],
+            &[output(outbound::<NativeConfig>(4), true)],
+        );
+        assert_eq!(result, Err(BridgeError::TransferAmountMismatch));
+    });
+}
+
+#[test]
+fn sending_other_coins_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = send::<NativeConfig>(
+            &[output(Coin::<1>(3), true)],
+            &[output(outbound::<NativeConfig>(3), true)],
+        );
+        assert_eq!(result, Err(BridgeError::TypeError));
+    });
+}
+
+#[test]
+fn receiving_wrapped_tokens_mints_them_once() {
+    TestExternalities::default().execute_with(|| {
+        let receive = relay_transfer::<WrappedConfig>(sibling_transfer::<0>(10));
+        let payout = [output(Coin::<2>(10), true)];
+
+        assert_eq!(receive.check(&[], &payout), Ok(0));
+        assert!(is_received(&transfer_ref()));
+        assert_eq!(
+            receive.check(&[], &payout),
+            Err(BridgeError::AlreadyReceived)
+        );
+    });
+}
+
+#[test]
+fn receiving_native_tokens_releases_locked_ones() {
+    TestExternalities::default().execute_with(|| {
+        send::<NativeConfig>(
+            &[output(Coin::<0>(10), true)],
+            &[output(outbound::<NativeConfig>(10), true)],
+        )
+        .unwrap();
+
+        let receive = relay_transfer::<NativeConfig>(sibling_transfer::<2>(6));
+        assert_eq!(receive.check(&[], &[output(Coin::<0>(6), true)]), Ok(0));
+        assert_eq!(locked(0), 4);
+    });
+}
+
+#[test]
+fn releasing_more_than_is_locked_fails() {
+    TestExternalities::default().execute_with(|| {
+        let receive = relay_transfer::<NativeConfig>(sibling_transfer::<2>(6));
+        assert_eq!(
+            receive.check(&[], &[output(Coin::<0>(6), true)]),
+            Err(BridgeError::InsufficientLocked)
+        );
+    });
+}
+
+#[test]
+fn receiving_against_unrelayed_block_fails() {
+    TestExternalities::default().execute_with(|| {
+        let mut receive = relay_transfer::<WrappedConfig>(sibling_transfer::<0>(10));
+        receive.sibling_block = 2;
+        assert_eq!(
+            receive.check(&[], &[output(Coin::<2>(10), true)]),
+            Err(BridgeError::UnknownSiblingBlock)
+        );
+    });
+}
+
+#[test]
+fn receiving_a_tampered_transfer_fails() {
+    TestExternalities::default().execute_with(|| {
+        let mut receive = relay_transfer::<WrappedConfig>(sibling_transfer::<0>(10));
+        receive.sibling_output = sibling_transfer::<0>(1_000);
+        assert_eq!(
+            receive.check(&[], &[output(Coin::<2>(1_000), true)]),
+            Err(BridgeError::InvalidInclusionProof)
+        );
+    });
+}
+
+#[test]
+fn receiving_a_transfer_of_another_token_fails() {
+    TestExternalities::default().execute_with(|| {
+        let receive = relay_transfer::<WrappedConfig>(sibling_transfer::<1>(10));
+        assert_eq!(
+            receive.check(&[], &[output(Coin::<2>(10), true)]),
+            Err(BridgeError::NotATransfer)
+        );
+    });
+}
+
+#[test]
+fn receiving_must_pay_the_recipient() {
+    TestExternalities::default().execute_with(|| {
+        let receive = relay_transfer::<WrappedConfig>(sibling_transfer::<0>(10));
+        assert_eq!(
+            receive.check(&[], &[output(Coin::<2>(9), true)]),
+            Err(BridgeError::PayoutAmountMismatch)
+        );
+        assert_eq!(
+            receive.check(&[], &[output(Coin::<2>(10), false)]),
+            Err(BridgeError::PayoutVerifierMismatch)
+        );
+    });
+}
+
+#[test]
+fn relaying_older_headers_fails() {
+    TestExternalities::default().execute_with(|| {
+        let latest = SiblingHeader {
+            number: 5,
+            utxo_commitment: H256::repeat_byte(1),
+        };
+        let older = SiblingHeader {
+            number: 5,
+            utxo_commitment: H256::repeat_byte(2),
+        };
+        assert_eq!(
+            RelaySiblingHeader.check(&[output(latest, true)], &[output(older, true)]),
+            Err(BridgeError::HeaderNotNewer)
+        );
+        assert_eq!(sibling_commitment(5), None);
+    });
+}
+
+#[test]
+fn relayers_cannot_hand_over_the_header() {
+    TestExternalities::default().execute_with(|| {
+        let latest = SiblingHeader::default();
+        let new = SiblingHeader {
+            number: 1,
+            utxo_commitment: H256::repeat_byte(1),
+        };
+        assert_eq!(
+            RelaySiblingHeader.check(&[output(latest, true)], &[output(new, false)]),
+            Err(BridgeError::RelayersChanged)
+        );
+    });
+}
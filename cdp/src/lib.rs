@@ -0,0 +1,378 @@
+//! Collateralized debt positions, which mint a debt token against locked collateral.
+//!
+//! A user opens a [`Vault`] by locking coins of the collateral token in it, and may then
+//! mint coins of the debt token up to a fraction of the collateral's value. The value is
+//! read from a price feed, a UTXO holding the price of the collateral in the debt token
+//! that an oracle updates through the governance piece. Constraint checkers cannot read
+//! UTXOs that their transaction does not consume, so the runtime reads the current price
+//! from where the governance piece records it and hands it to this piece through
+//! [`CdpConfig::collateral_price`].
+//!
+//! Every vault must keep collateral worth at least [`CdpConfig::MIN_COLLATERAL_PERCENT`]
+//! of its debt. When the price falls so far that a vault no longer does, anyone may
+//! liquidate it by repaying its debt, and take collateral worth the debt plus a
+//! [`CdpConfig::LIQUIDATION_PENALTY_PERCENT`] penalty. The rest goes back to the owner.
+//! Liquidators who would rather hold the debt token can sell the collateral they take in
+//! the dex straight away.
+//!
+//! Vaults have to be spendable by anyone so that they can be liquidated. The owner is
+//! therefore recorded in the vault itself, and authorizes changes to it by also spending
+//! a UTXO that is protected by the owner's verifier.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use dex::{price::mul_div, Price, Rounding};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    traits::Cash,
+    types::Output,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The configuration of a debt token and the collateral it is minted against.
+pub trait CdpConfig {
+    /// The type of verifiers that can own vaults.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The token that is locked as collateral
+    type Collateral: Cash + UtxoData;
+    /// The token that is minted as debt
+    type Debt: Cash + UtxoData;
+
+    /// The least value of collateral that a vault must hold, in percent of its debt.
+    const MIN_COLLATERAL_PERCENT: u32 = 150;
+
+    /// The collateral that a liquidator may take beyond the value of the repaid debt,
+    /// in percent of that value.
+    const LIQUIDATION_PENALTY_PERCENT: u32 = 10;
+
+    /// The verifier that protects every vault. Anyone must be able to satisfy it, or
+    /// vaults could not be liquidated.
+    fn vault_verifier() -> Self::Verifier;
+
+    /// The current price of the collateral, in debt token per unit of collateral, if the
+    /// oracle has published one.
+    fn collateral_price() -> Option<Price>;
+}
+
+/// Collateral locked against minted debt.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct Vault<T: CdpConfig> {
+    /// The amount of collateral locked in the vault
+    pub collateral: u128,
+    /// The amount of debt token minted against it
+    pub debt: u128,
+    /// The verifier whose UTXOs must be spent to change the vault, and that receives
+    /// what is left of the collateral after a liquidation
+    pub owner: T::Verifier,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: CdpConfig> UtxoData for Vault<T> {
+    const TYPE_ID: [u8; 4] = [b'c', b'd', T::Collateral::ID, T::Debt::ID];
+}
+
+/// The price of the collateral in the debt token, as published by the oracle.
+///
+/// Runtimes keep it in a UTXO that the oracle updates with the governance piece.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct PriceFeed<T: CdpConfig> {
+    pub price: Price,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: CdpConfig> UtxoData for PriceFeed<T> {
+    const TYPE_ID: [u8; 4] = [b'p', b'f', T::Collateral::ID, T::Debt::ID];
+}
+
+/// All the things that can go wrong with vaults.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum CdpError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// The total amount of some coins does not fit in a `u128`.
+    AmountOverflow,
+    /// The first input must be the vault.
+    VaultMissing,
+    /// Opening a vault must lock some collateral.
+    CollateralMissing,
+    /// The new vault is not protected by the configured vault verifier, so it could not
+    /// be liquidated.
+    VaultNotLiquidatable,
+    /// The vault's owner may not change.
+    OwnerChanged,
+    /// The transaction does not spend any UTXO protected by the vault's owner.
+    NotAuthorizedByOwner,
+    /// The collateral that is locked, added and withdrawn does not add up.
+    CollateralMismatch,
+    /// The debt that is owed, repaid and minted does not add up.
+    DebtMismatch,
+    /// The oracle has not published a price for the collateral.
+    PriceUnavailable,
+    /// The vault's collateral would be worth less than the minimum share of its debt.
+    Undercollateralized,
+    /// Only vaults whose collateral is worth less than the minimum share of their debt
+    /// may be liquidated.
+    VaultHealthy,
+    /// A liquidation must repay all of the vault's debt.
+    DebtNotRepaid,
+    /// A liquidation only pays out collateral and repays debt.
+    UnexpectedCoins,
+    /// The liquidator takes collateral worth more than the debt plus the penalty.
+    LiquidatorTakesTooMuch,
+    /// The collateral that the liquidator does not take must go back to the owner.
+    OwnerNotRefunded,
+}
+
+impl From<DynamicTypingError> for CdpError {
+    fn from(_value: DynamicTypingError) -> Self {
+        CdpError::TypeError
+    }
+}
+
+/// The total collateral and debt coins among the given UTXOs, which must all be one or
+/// the other.
+fn coin_totals<T: CdpConfig>(utxos: &[Output<T::Verifier>]) -> Result<(u128, u128), CdpError> {
+    let (mut collateral, mut debt) = (0u128, 0u128);
+    for utxo in utxos {
+        if let Ok(coin) = utxo.payload.extract::<T::Collateral>() {
+            collateral = collateral
+                .checked_add(coin.value())
+                .ok_or(CdpError::AmountOverflow)?;
+        } else {
+            let coin: T::Debt = utxo.payload.extract()?;
+            debt = debt
+                .checked_add(coin.value())
+                .ok_or(CdpError::AmountOverflow)?;
+        }
+    }
+    Ok((collateral, debt))
+}
+
+/// Whether the vault holds enough collateral for its debt at the current price.
+pub fn is_healthy<T: CdpConfig>(vault: &Vault<T>) -> Result<bool, CdpError> {
+    if vault.debt == 0 {
+        return Ok(true);
+    }
+    let price = T::collateral_price().ok_or(CdpError::PriceUnavailable)?;
+    let required = mul_div(
+        vault.debt,
+        T::MIN_COLLATERAL_PERCENT.into(),
+        100,
+        Rounding::Up,
+    )
+    .ok_or(CdpError::AmountOverflow)?;
+    // Collateral worth more than a `u128` of debt token covers any debt.
+    Ok(price
+        .amount_b_for(vault.collateral, Rounding::Down)
+        .map_or(true, |value| value >= required))
+}
+
+/// Check that a new vault is protected by the vault verifier, and return it.
+fn new_vault<T: CdpConfig>(output: &Output<T::Verifier>) -> Result<Vault<T>, CdpError> {
+    let vault: Vault<T> = output.payload.extract()?;
+    ensure!(
+        output.verifier == T::vault_verifier(),
+        CdpError::VaultNotLiquidatable
+    );
+    Ok(vault)
+}
+
+/// Open a vault, locking collateral in it and minting debt against it.
+///
+/// All inputs are collateral coins, at least one of which is protected by the owner. The
+/// first output is the vault, which holds exactly the collateral, and all further outputs
+/// are the minted debt coins.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct OpenVault<T: CdpConfig>(pub PhantomData<T>);
+
+impl<T: CdpConfig> ConstraintChecker<T::Verifier> for OpenVault<T> {
+    type Error = CdpError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), CdpError::CollateralMissing);
+        let mut collateral = 0u128;
+        for input in inputs {
+            let coin: T::Collateral = input.payload.extract()?;
+            collateral = collateral
+                .checked_add(coin.value())
+                .ok_or(CdpError::AmountOverflow)?;
+        }
+
+        ensure!(!outputs.is_empty(), CdpError::VaultMissing);
+        let vault = new_vault::<T>(&outputs[0])?;
+        ensure!(
+            inputs.iter().any(|input| input.verifier == vault.owner),
+            CdpError::NotAuthorizedByOwner
+        );
+        ensure!(vault.collateral == collateral, CdpError::CollateralMismatch);
+
+        let mut minted = 0u128;
+        for output in &outputs[1..] {
+            let coin: T::Debt = output.payload.extract()?;
+            minted = minted
+                .checked_add(coin.value())
+                .ok_or(CdpError::AmountOverflow)?;
+        }
+        ensure!(vault.debt == minted, CdpError::DebtMismatch);
+        ensure!(is_healthy(&vault)?, CdpError::Undercollateralized);
+
+        Ok(0)
+    }
+}
+
+/// Add or withdraw collateral, and mint or repay debt, with the owner's approval.
+///
+/// The first input is the vault and all further inputs are added collateral and repaid
+/// debt coins, at least one of which is protected by the owner. The first output is the
+/// new vault, and all further outputs are withdrawn collateral and minted debt coins. If
+/// the first output is not a vault, the vault is closed and must end up with neither
+/// collateral nor debt.
+///
+/// The new vault must hold enough collateral for its debt, unless the change only adds
+/// collateral or repays debt.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct AdjustVault<T: CdpConfig>(pub PhantomData<T>);
+
+impl<T: CdpConfig> ConstraintChecker<T::Verifier> for AdjustVault<T> {
+    type Error = CdpError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), CdpError::VaultMissing);
+        let old: Vault<T> = inputs[0].payload.extract()?;
+        ensure!(
+            inputs[1..].iter().any(|input| input.verifier == old.owner),
+            CdpError::NotAuthorizedByOwner
+        );
+
+        let new = match outputs.first() {
+            Some(output) if output.payload.extract::<Vault<T>>().is_ok() => {
+                Some(new_vault::<T>(output)?)
+            }
+            _ => None,
+        };
+        let paid_out = if new.is_some() {
+            &outputs[1..]
+        } else {
+            outputs
+        };
+        let (new_collateral, new_debt) = new
+            .as_ref()
+            .map_or((0, 0), |vault| (vault.collateral, vault.debt));
+
+        let (added, repaid) = coin_totals::<T>(&inputs[1..])?;
+        let (withdrawn, minted) = coin_totals::<T>(paid_out)?;
+        ensure!(
+            old.collateral.checked_add(added) == new_collateral.checked_add(withdrawn),
+            CdpError::CollateralMismatch
+        );
+        ensure!(
+            old.debt.checked_add(minted) == new_debt.checked_add(repaid),
+            CdpError::DebtMismatch
+        );
+
+        if let Some(new) = new {
+            ensure!(new.owner == old.owner, CdpError::OwnerChanged);
+            let only_safer = new.collateral >= old.collateral && new.debt <= old.debt;
+            ensure!(
+                only_safer || is_healthy(&new)?,
+                CdpError::Undercollateralized
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+/// Repay the debt of a vault that no longer holds enough collateral, and take its
+/// collateral.
+///
+/// The first input is the vault and all further inputs are debt coins worth at least
+/// its debt, which are burned. All outputs are collateral coins. Those protected by the
+/// owner are the owner's refund, and the liquidator takes the others, worth at most the
+/// debt plus the liquidation penalty. Whatever the liquidator may not take must be
+/// refunded.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Liquidate<T: CdpConfig>(pub PhantomData<T>);
+
+impl<T: CdpConfig> ConstraintChecker<T::Verifier> for Liquidate<T> {
+    type Error = CdpError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), CdpError::VaultMissing);
+        let vault: Vault<T> = inputs[0].payload.extract()?;
+        ensure!(!is_healthy(&vault)?, CdpError::VaultHealthy);
+
+        let (collateral_in, repaid) = coin_totals::<T>(&inputs[1..])?;
+        ensure!(collateral_in == 0, CdpError::UnexpectedCoins);
+        ensure!(repaid >= vault.debt, CdpError::DebtNotRepaid);
+
+        let (paid_out, debt_out) = coin_totals::<T>(outputs)?;
+        ensure!(debt_out == 0, CdpError::UnexpectedCoins);
+        ensure!(paid_out <= vault.collateral, CdpError::CollateralMismatch);
+
+        let mut refunded = 0u128;
+        for output in outputs
+            .iter()
+            .filter(|output| output.verifier == vault.owner)
+        {
+            let coin: T::Collateral = output.payload.extract()?;
+            refunded = refunded
+                .checked_add(coin.value())
+                .ok_or(CdpError::AmountOverflow)?;
+        }
+
+        // An unhealthy vault has a price, or `is_healthy` would have failed.
+        let price = T::collateral_price().ok_or(CdpError::PriceUnavailable)?;
+        let max_taken = price
+            .amount_a_for(vault.debt, Rounding::Up)
+            .and_then(|value| {
+                mul_div(
+                    value,
+                    (100 + T::LIQUIDATION_PENALTY_PERCENT).into(),
+                    100,
+                    Rounding::Up,
+                )
+            })
+            .map_or(vault.collateral, |max| max.min(vault.collateral));
+        ensure!(
+            paid_out - refunded <= max_taken,
+            CdpError::LiquidatorTakesTooMuch
+        );
+        ensure!(
+            refunded >= vault.collateral - max_taken,
+            CdpError::OwnerNotRefunded
+        );
+
+        Ok(0)
+    }
+}
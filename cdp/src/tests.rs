@@ -0,0 +1,239 @@
+//! Unit tests for the collateralized debt piece
+
+use super::*;
+use money::Coin;
+use std::cell::Cell;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+thread_local! {
+    static PRICE: Cell<Option<Price>> = Cell::new(Some(Price::ONE));
+}
+
+fn set_price(price: Option<Price>) {
+    PRICE.with(|p| p.set(price));
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct TestConfig;
+impl CdpConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type Collateral = Coin<0>;
+    type Debt = Coin<1>;
+
+    fn vault_verifier() -> TestVerifier {
+        ANYONE
+    }
+
+    fn collateral_price() -> Option<Price> {
+        PRICE.with(|p| p.get())
+    }
+}
+
+/// The verifier that protects vaults, and that liquidators use for their takings.
+const ANYONE: TestVerifier = TestVerifier { verifies: true };
+/// The verifier of the vault owner.
+const OWNER: TestVerifier = TestVerifier { verifies: false };
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn vault(collateral: u128, debt: u128) -> Output<TestVerifier> {
+    output(
+        Vault::<TestConfig> {
+            collateral,
+            debt,
+            owner: OWNER,
+            _ph_data: Default::default(),
+        },
+        ANYONE,
+    )
+}
+
+fn collateral(amount: u128, verifier: TestVerifier) -> Output<TestVerifier> {
+    output(Coin::<0>(amount), verifier)
+}
+
+fn debt(amount: u128, verifier: TestVerifier) -> Output<TestVerifier> {
+    output(Coin::<1>(amount), verifier)
+}
+
+fn open(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, CdpError> {
+    OpenVault::<TestConfig>::default().check(inputs, outputs)
+}
+
+fn adjust(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, CdpError> {
+    AdjustVault::<TestConfig>::default().check(inputs, outputs)
+}
+
+fn liquidate(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, CdpError> {
+    Liquidate::<TestConfig>::default().check(inputs, outputs)
+}
+
+#[test]
+fn opening_a_vault_within_the_ratio_works() {
+    set_price(Some(Price::ONE));
+    let result = open(
+        &[collateral(100, OWNER), collateral(50, ANYONE)],
+        &[vault(150, 100), debt(60, OWNER), debt(40, OWNER)],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn opening_a_vault_beyond_the_ratio_fails() {
+    set_price(Some(Price::ONE));
+    let result = open(
+        &[collateral(149, OWNER)],
+        &[vault(149, 100), debt(100, OWNER)],
+    );
+    assert_eq!(result, Err(CdpError::Undercollateralized));
+}
+
+#[test]
+fn opening_a_vault_without_a_price_fails() {
+    set_price(None);
+    let result = open(&[collateral(150, OWNER)], &[vault(150, 1), debt(1, OWNER)]);
+    assert_eq!(result, Err(CdpError::PriceUnavailable));
+}
+
+#[test]
+fn opening_a_vault_for_someone_else_fails() {
+    set_price(Some(Price::ONE));
+    let result = open(&[collateral(150, ANYONE)], &[vault(150, 0)]);
+    assert_eq!(result, Err(CdpError::NotAuthorizedByOwner));
+}
+
+#[test]
+fn vaults_must_be_liquidatable() {
+    set_price(Some(Price::ONE));
+    let mut protected = vault(150, 0);
+    protected.verifier = OWNER;
+    let result = open(&[collateral(150, OWNER)], &[protected]);
+    assert_eq!(result, Err(CdpError::VaultNotLiquidatable));
+}
+
+#[test]
+fn minting_more_debt_must_stay_within_the_ratio() {
+    set_price(Some(Price::ONE));
+    assert_eq!(
+        adjust(
+            &[vault(300, 100), collateral(0, OWNER)],
+            &[vault(300, 200), debt(100, OWNER)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        adjust(
+            &[vault(300, 100), collateral(0, OWNER)],
+            &[vault(300, 201), debt(101, OWNER)],
+        ),
+        Err(CdpError::Undercollateralized)
+    );
+}
+
+#[test]
+fn adjusting_must_add_up() {
+    set_price(Some(Price::ONE));
+    assert_eq!(
+        adjust(&[vault(300, 100), debt(50, OWNER)], &[vault(300, 60)]),
+        Err(CdpError::DebtMismatch)
+    );
+    assert_eq!(
+        adjust(
+            &[vault(300, 100), collateral(10, OWNER)],
+            &[vault(300, 100), collateral(20, OWNER)],
+        ),
+        Err(CdpError::CollateralMismatch)
+    );
+}
+
+#[test]
+fn unhealthy_vaults_may_still_be_repaid() {
+    set_price(Some(Price(Price::ONE.0 / 2)));
+    let result = adjust(&[vault(150, 100), debt(10, OWNER)], &[vault(150, 90)]);
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn repaid_vaults_may_be_closed() {
+    set_price(None);
+    let result = adjust(
+        &[vault(150, 100), debt(100, OWNER)],
+        &[collateral(150, OWNER)],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn adjusting_without_the_owner_fails() {
+    set_price(Some(Price::ONE));
+    let result = adjust(
+        &[vault(300, 100), collateral(0, ANYONE)],
+        &[vault(200, 100), collateral(100, ANYONE)],
+    );
+    assert_eq!(result, Err(CdpError::NotAuthorizedByOwner));
+}
+
+#[test]
+fn healthy_vaults_cannot_be_liquidated() {
+    set_price(Some(Price::ONE));
+    let result = liquidate(
+        &[vault(150, 100), debt(100, ANYONE)],
+        &[collateral(150, ANYONE)],
+    );
+    assert_eq!(result, Err(CdpError::VaultHealthy));
+}
+
+#[test]
+fn liquidators_take_the_debt_plus_penalty_and_refund_the_rest() {
+    // The collateral is worth 120 of debt, when 150 would be needed.
+    set_price(Some(Price(Price::ONE.0 * 4 / 5)));
+    let vault = vault(150, 100);
+
+    // Repaying 100 of debt buys 125 of collateral, and with the 10% penalty 137.5,
+    // which rounds up to 138.
+    assert_eq!(
+        liquidate(
+            &[vault.clone(), debt(100, ANYONE)],
+            &[collateral(138, ANYONE), collateral(12, OWNER)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        liquidate(
+            &[vault.clone(), debt(100, ANYONE)],
+            &[collateral(139, ANYONE), collateral(11, OWNER)],
+        ),
+        Err(CdpError::LiquidatorTakesTooMuch)
+    );
+    assert_eq!(
+        liquidate(
+            &[vault.clone(), debt(100, ANYONE)],
+            &[collateral(138, ANYONE)],
+        ),
+        Err(CdpError::OwnerNotRefunded)
+    );
+    assert_eq!(
+        liquidate(
+            &[vault, debt(99, ANYONE)],
+            &[collateral(138, ANYONE), collateral(12, OWNER)],
+        ),
+        Err(CdpError::DebtNotRepaid)
+    );
+}
@@ -0,0 +1,331 @@
+//! A C interface for building dex transactions from other languages.
+//!
+//! Market making bots are often written in Python or TypeScript, where the runtime types
+//! are not available. This module exposes the [`transactions`](crate::transactions)
+//! builders as C functions that this crate's `cdylib` exports, callable through `ctypes`
+//! in Python or `ffi-napi` in Node.
+//!
+//! Every function takes a JSON request as a nul terminated string and returns a JSON
+//! response, which the caller must release with [`dex_free_string`]. A failed request
+//! returns `{"error": "<reason>"}`. Byte strings, including hashes, are `0x` prefixed hex,
+//! and output refs are written as `{"tx_hash": "0x..", "index": 0}`.
+//!
+//! The builders return the SCALE encoded transaction together with its signing payload,
+//! which is bound to the chain whose `"genesisHash"` the request names. The bot signs the
+//! payload with its own sr25519 key, passes the signature to [`dex_attach_signature`], and
+//! submits the result with `author_submitExtrinsic`.
+
+use std::ffi::{c_char, CStr, CString};
+
+use dex::Side;
+use node_template_runtime::Transaction;
+use parity_scale_codec::{DecodeAll, Encode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sp_core::{Bytes, H256};
+use tuxedo_core::types::{Output, OutputRef};
+
+use crate::transactions::{self, signed_by};
+
+/// The request of [`dex_make_order`].
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MakeOrderRequest {
+    coins: Vec<OutputRef>,
+    side: Side,
+    offer_amount: u128,
+    ask_amount: u128,
+    owner: H256,
+    genesis_hash: H256,
+}
+
+/// One payout of a [`dex_match_orders`] request, which pays coins to a key.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Payout {
+    token: u8,
+    amount: u128,
+    owner: H256,
+}
+
+/// The request of [`dex_match_orders`].
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchOrdersRequest {
+    orders: Vec<OutputRef>,
+    payouts: Vec<Payout>,
+    referrer: Option<H256>,
+    genesis_hash: H256,
+}
+
+/// The request of [`dex_cancel_order`].
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelOrderRequest {
+    order: OutputRef,
+    side: Side,
+    offer_amount: u128,
+    owner: H256,
+    genesis_hash: H256,
+}
+
+/// The request of [`dex_attach_signature`].
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AttachSignatureRequest {
+    transaction: Bytes,
+    signature: Bytes,
+}
+
+/// A transaction that still needs to be signed.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Unsigned {
+    transaction: Bytes,
+    signing_payload: Bytes,
+}
+
+impl Unsigned {
+    /// The transaction and its signing payload on the chain with the given genesis hash.
+    fn new(transaction: Transaction, genesis_hash: H256) -> Self {
+        Self {
+            signing_payload: transactions::bounded_signing_payload(&transaction, genesis_hash)
+                .into(),
+            transaction: transaction.encode().into(),
+        }
+    }
+}
+
+/// A transaction that is ready to submit.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Signed {
+    transaction: Bytes,
+}
+
+/// Read a JSON request from a C string.
+///
+/// # Safety
+///
+/// The pointer must be null or point to a nul terminated string.
+unsafe fn parse<T: DeserializeOwned>(request: *const c_char) -> Result<T, String> {
+    if request.is_null() {
+        return Err("The request is null".into());
+    }
+    let request = CStr::from_ptr(request)
+        .to_str()
+        .map_err(|e| format!("The request is not UTF-8: {}", e))?;
+    serde_json::from_str(request).map_err(|e| format!("The request is malformed: {}", e))
+}
+
+/// Write a JSON response to a C string owned by the caller.
+fn respond<T: Serialize>(response: Result<T, String>) -> *mut c_char {
+    let json = match response.and_then(|r| serde_json::to_value(r).map_err(|e| e.to_string())) {
+        Ok(value) => value,
+        Err(error) => serde_json::json!({ "error": error }),
+    };
+    CString::new(json.to_string())
+        .expect("JSON never contains nul bytes")
+        .into_raw()
+}
+
+/// Build a transaction that opens an order. See [`transactions::make_order`].
+///
+/// The request is `{"coins": [..], "side": "OffersA" | "OffersB", "offerAmount": ..,
+/// "askAmount": .., "owner": "0x..", "genesisHash": "0x.."}`, and the response is
+/// `{"transaction": "0x..", "signingPayload": "0x.."}`.
+///
+/// # Safety
+///
+/// The request must be null or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dex_make_order(request: *const c_char) -> *mut c_char {
+    respond(parse(request).map(|r: MakeOrderRequest| {
+        let transaction =
+            transactions::make_order(r.coins, r.side, r.offer_amount, r.ask_amount, r.owner);
+        Unsigned::new(transaction, r.genesis_hash)
+    }))
+}
+
+/// Build a transaction that matches orders with coin payouts. See
+/// [`transactions::match_orders`].
+///
+/// The request is `{"orders": [..], "payouts": [{"token": 0 | 1, "amount": .., "owner":
+/// "0x.."}, ..], "genesisHash": "0x.."}`, and the response is like that of
+/// [`dex_make_order`]. Front-ends may add `"referrer": "0x.."` to name their key as the
+/// match's referrer, see [`transactions::referred_match_orders`].
+///
+/// # Safety
+///
+/// The request must be null or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dex_match_orders(request: *const c_char) -> *mut c_char {
+    respond(parse(request).and_then(|r: MatchOrdersRequest| {
+        let outputs = r
+            .payouts
+            .into_iter()
+            .map(|payout| {
+                let payload = match payout.token {
+                    0 => money::Coin::<0>(payout.amount).into(),
+                    1 => money::Coin::<1>(payout.amount).into(),
+                    token => return Err(format!("Token {} is not traded", token)),
+                };
+                Ok(Output {
+                    verifier: signed_by(payout.owner),
+                    payload,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let transaction = match r.referrer {
+            None => transactions::match_orders(r.orders, outputs),
+            Some(referrer) => transactions::referred_match_orders(r.orders, outputs, referrer),
+        };
+        Ok(Unsigned::new(transaction, r.genesis_hash))
+    }))
+}
+
+/// Build a transaction that cancels an order. See [`transactions::cancel_order`].
+///
+/// The request is `{"order": {..}, "side": "OffersA" | "OffersB", "offerAmount": ..,
+/// "owner": "0x..", "genesisHash": "0x.."}`, and the response is like that of
+/// [`dex_make_order`].
+///
+/// # Safety
+///
+/// The request must be null or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dex_cancel_order(request: *const c_char) -> *mut c_char {
+    respond(parse(request).map(|r: CancelOrderRequest| {
+        let transaction = transactions::cancel_order(r.order, r.side, r.offer_amount, r.owner);
+        Unsigned::new(transaction, r.genesis_hash)
+    }))
+}
+
+/// Attach an sr25519 signature over the signing payload to every unsigned input of a
+/// transaction.
+///
+/// The request is `{"transaction": "0x..", "signature": "0x.."}`, and the response is
+/// `{"transaction": "0x.."}`, ready to submit.
+///
+/// # Safety
+///
+/// The request must be null or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dex_attach_signature(request: *const c_char) -> *mut c_char {
+    respond(parse(request).and_then(|r: AttachSignatureRequest| {
+        let mut transaction = Transaction::decode_all(&mut &r.transaction[..])
+            .map_err(|e| format!("The transaction does not decode: {}", e))?;
+        if r.signature.len() != 64 {
+            return Err("An sr25519 signature is 64 bytes long".into());
+        }
+        transactions::attach_redeemer(&mut transaction, &r.signature);
+        Ok(Signed {
+            transaction: transaction.encode().into(),
+        })
+    }))
+}
+
+/// Release a response returned by any function of this module.
+///
+/// # Safety
+///
+/// The pointer must be null or have been returned by this module, and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn dex_free_string(response: *mut c_char) {
+    if !response.is_null() {
+        drop(CString::from_raw(response));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use sp_core::{sr25519, Pair};
+
+    /// Call an exported function with a JSON request, and read back its response.
+    fn call(function: unsafe extern "C" fn(*const c_char) -> *mut c_char, request: Value) -> Value {
+        let request = CString::new(request.to_string()).unwrap();
+        unsafe {
+            let response = function(request.as_ptr());
+            let json = CStr::from_ptr(response).to_str().unwrap().to_owned();
+            dex_free_string(response);
+            serde_json::from_str(&json).unwrap()
+        }
+    }
+
+    fn bytes(value: &Value) -> Bytes {
+        serde_json::from_value(value.clone()).unwrap()
+    }
+
+    #[test]
+    fn orders_built_from_json_can_be_signed_and_submitted() {
+        let pair = sr25519::Pair::from_seed(&[7; 32]);
+        let owner = H256::from(pair.public().0);
+        let genesis_hash = H256::repeat_byte(3);
+        let coin = json!({ "tx_hash": H256::repeat_byte(1), "index": 0 });
+
+        let unsigned = call(
+            dex_make_order,
+            json!({
+                "coins": [coin],
+                "side": "OffersA",
+                "offerAmount": 10,
+                "askAmount": 20,
+                "owner": owner,
+                "genesisHash": genesis_hash,
+            }),
+        );
+        let payload = bytes(&unsigned["signingPayload"]);
+        let signature = pair.sign(&payload);
+        let signed = call(
+            dex_attach_signature,
+            json!({
+                "transaction": unsigned["transaction"],
+                "signature": Bytes(signature.0.to_vec()),
+            }),
+        );
+
+        let transaction = Transaction::decode_all(&mut &bytes(&signed["transaction"])[..]).unwrap();
+        let expected = transactions::make_order(
+            vec![OutputRef {
+                tx_hash: H256::repeat_byte(1),
+                index: 0,
+            }],
+            Side::OffersA,
+            10,
+            20,
+            owner,
+        );
+        assert_eq!(
+            transactions::signing_payload(&transaction),
+            transactions::signing_payload(&expected)
+        );
+        assert_eq!(
+            payload.0,
+            transactions::bounded_signing_payload(&expected, genesis_hash)
+        );
+        assert_eq!(transaction.inputs[0].redeemer, signature.0.to_vec());
+    }
+
+    #[test]
+    fn payouts_in_untraded_tokens_are_refused() {
+        let response = call(
+            dex_match_orders,
+            json!({
+                "orders": [],
+                "payouts": [{ "token": 2, "amount": 10, "owner": H256::zero() }],
+                "genesisHash": H256::zero(),
+            }),
+        );
+        assert_eq!(response, json!({ "error": "Token 2 is not traded" }));
+    }
+
+    #[test]
+    fn malformed_requests_are_refused() {
+        let response = call(dex_cancel_order, json!({ "order": 5 }));
+        assert!(response["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("The request is malformed"));
+    }
+}
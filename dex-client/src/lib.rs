@@ -0,0 +1,261 @@
+//! Typed Rust bindings for trading on the template runtime's dex.
+//!
+//! Bots that trade on the dex need to build transactions from the runtime's own types,
+//! sign them, submit them, and read the book. This crate does all of that over a node's
+//! JSON-RPC interface, so that bot authors can depend on it instead of vendoring the
+//! runtime types and hand-writing the codecs.
+//!
+//! Tuxedo transactions are not FRAME extrinsics and the runtime does not serve FRAME
+//! metadata, so generic clients like subxt cannot build them. The transactions here are
+//! built with the runtime types directly, and submitted as their plain SCALE encoding.
+//! Clients in other languages can learn the same types from `DexMetadataApi`.
+//!
+//! See the [`transactions`] module for building and signing, and [`DexClient`] for talking
+//! to the node. Bots in other languages can build transactions through the C interface in
+//! the [`ffi`] module.
+
+use std::fmt;
+
+use jsonrpsee::{
+    core::client::ClientT,
+    http_client::{HttpClient, HttpClientBuilder},
+    rpc_params,
+};
+use node_template_runtime::{
+    faucet_utxo_key, opaque, order_book, BlockNumber, Header, OuterVerifier, Transaction,
+};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use sp_core::{
+    storage::{StorageData, StorageKey},
+    Bytes, H256,
+};
+use sp_runtime::{generic::SignedBlock, traits::Header as _};
+use tuxedo_core::types::{Output, OutputRef};
+
+pub mod ffi;
+pub mod transactions;
+
+pub use dex::Side;
+pub use transactions::{
+    attach_redeemer, bounded_signing_payload, cancel_order, drip, make_order, match_orders,
+    observe_twap, referred_match_orders, sign, sign_unbounded, signed_by, signing_payload,
+};
+
+/// How many storage keys are requested at once while scanning for open orders.
+const KEYS_PAGE_SIZE: u32 = 1000;
+
+/// All the things that can go wrong while talking to a node.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The node could not be reached, or refused the request.
+    Rpc(jsonrpsee::core::Error),
+    /// The node returned data that does not decode as the runtime types.
+    Decode(parity_scale_codec::Error),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rpc(e) => write!(f, "RPC request failed: {}", e),
+            Self::Decode(e) => write!(f, "Failed to decode the node's response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<jsonrpsee::core::Error> for ClientError {
+    fn from(e: jsonrpsee::core::Error) -> Self {
+        Self::Rpc(e)
+    }
+}
+
+impl From<parity_scale_codec::Error> for ClientError {
+    fn from(e: parity_scale_codec::Error) -> Self {
+        Self::Decode(e)
+    }
+}
+
+/// A connection to a node's JSON-RPC interface.
+pub struct DexClient {
+    rpc: HttpClient,
+}
+
+impl DexClient {
+    /// Connect to the node at the given HTTP URL, like `http://localhost:9933`.
+    pub fn new(url: &str) -> Result<Self, ClientError> {
+        Ok(Self {
+            rpc: HttpClientBuilder::default().build(url)?,
+        })
+    }
+
+    /// Submit a signed transaction to the node's pool, and return its hash.
+    pub async fn submit(&self, transaction: &Transaction) -> Result<H256, ClientError> {
+        let encoded = Bytes(transaction.encode());
+        Ok(self
+            .rpc
+            .request("author_submitExtrinsic", rpc_params![encoded])
+            .await?)
+    }
+
+    /// The hash of the node's best block.
+    pub async fn best_block(&self) -> Result<H256, ClientError> {
+        Ok(self
+            .rpc
+            .request("chain_getBlockHash", rpc_params![])
+            .await?)
+    }
+
+    /// The hash of the node's latest finalized block.
+    pub async fn finalized_head(&self) -> Result<H256, ClientError> {
+        Ok(self
+            .rpc
+            .request("chain_getFinalizedHead", rpc_params![])
+            .await?)
+    }
+
+    /// The hash of the chain's genesis block, which [`bounded_signing_payload`]s are bound to.
+    pub async fn genesis_hash(&self) -> Result<H256, ClientError> {
+        Ok(self
+            .rpc
+            .request("chain_getBlockHash", rpc_params![0])
+            .await?)
+    }
+
+    /// The hash of the block with the given number on the node's best chain, if it has one.
+    pub async fn block_hash(&self, number: BlockNumber) -> Result<Option<H256>, ClientError> {
+        Ok(self
+            .rpc
+            .request("chain_getBlockHash", rpc_params![number])
+            .await?)
+    }
+
+    /// The header and the transactions of the given block, if the node has it.
+    ///
+    /// Extrinsics that do not decode as transactions are skipped.
+    pub async fn block(
+        &self,
+        hash: H256,
+    ) -> Result<Option<(Header, Vec<Transaction>)>, ClientError> {
+        let block: Option<SignedBlock<opaque::Block>> = self
+            .rpc
+            .request("chain_getBlock", rpc_params![hash])
+            .await?;
+        Ok(block.map(|signed| {
+            let transactions = signed
+                .block
+                .extrinsics
+                .iter()
+                .filter_map(|extrinsic| Transaction::decode(&mut &extrinsic.encode()[..]).ok())
+                .collect();
+            (signed.block.header, transactions)
+        }))
+    }
+
+    /// The unspent output stored at the given ref at some block, if there is one.
+    pub async fn utxo(
+        &self,
+        output_ref: &OutputRef,
+        at: H256,
+    ) -> Result<Option<Output<OuterVerifier>>, ClientError> {
+        let key = StorageKey(output_ref.encode());
+        let data: Option<StorageData> = self
+            .rpc
+            .request("state_getStorage", rpc_params![key, at])
+            .await?;
+        Ok(data
+            .map(|data| Output::decode_all(&mut &data.0[..]))
+            .transpose()?)
+    }
+
+    /// The faucet of the given token at the best block, and where it is stored, if the
+    /// chain has one.
+    pub async fn faucet(
+        &self,
+        token: u8,
+    ) -> Result<Option<(OutputRef, Output<OuterVerifier>)>, ClientError> {
+        let at = self.best_block().await?;
+        let key = StorageKey(faucet_utxo_key(token));
+        let data: Option<StorageData> = self
+            .rpc
+            .request("state_getStorage", rpc_params![key, at])
+            .await?;
+        let Some(data) = data else {
+            return Ok(None);
+        };
+        let faucet_ref = OutputRef::decode_all(&mut &data.0[..])?;
+        Ok(self
+            .utxo(&faucet_ref, at)
+            .await?
+            .map(|faucet| (faucet_ref, faucet)))
+    }
+
+    /// Ask the faucet of the given token for test coins for the given key, and return the
+    /// hash of the drip, or `None` if the chain has no faucet for that token.
+    ///
+    /// The drip is built for the block after the best one. Should it miss that block, it
+    /// becomes invalid and must be asked for again.
+    pub async fn request_from_faucet(
+        &self,
+        token: u8,
+        amount: u128,
+        recipient: H256,
+    ) -> Result<Option<H256>, ClientError> {
+        let Some((faucet_ref, faucet)) = self.faucet(token).await? else {
+            return Ok(None);
+        };
+        let header: Header = self.rpc.request("chain_getHeader", rpc_params![]).await?;
+        let Some(transaction) = drip(faucet_ref, &faucet, amount, recipient, header.number() + 1)
+        else {
+            return Ok(None);
+        };
+        Ok(Some(self.submit(&transaction).await?))
+    }
+
+    /// All the open orders between the given two tokens, in either direction, at some
+    /// block, or at the best block if none is given.
+    ///
+    /// This scans every storage key, so it is slow on a busy chain. Bots that follow the
+    /// book should read it once and then follow the node's order book subscription.
+    pub async fn open_orders(
+        &self,
+        pair: (u8, u8),
+        at: Option<H256>,
+    ) -> Result<Vec<(OutputRef, Output<OuterVerifier>)>, ClientError> {
+        let at = match at {
+            Some(at) => at,
+            None => self.best_block().await?,
+        };
+
+        let mut orders = Vec::new();
+        let mut start_key: Option<StorageKey> = None;
+        loop {
+            let keys: Vec<StorageKey> = self
+                .rpc
+                .request(
+                    "state_getKeysPaged",
+                    rpc_params![StorageKey(vec![]), KEYS_PAGE_SIZE, start_key.clone(), at],
+                )
+                .await?;
+
+            // Every UTXO is stored directly under its encoded output ref. Other runtime
+            // storage items have keys of different shapes, so they fail to decode as a whole.
+            for key in &keys {
+                let Ok(output_ref) = OutputRef::decode_all(&mut &key.0[..]) else {
+                    continue;
+                };
+                let Some(output) = self.utxo(&output_ref, at).await? else {
+                    continue;
+                };
+                if order_book::order_terms(&output).map_or(false, |terms| terms.is_in_pair(pair)) {
+                    orders.push((output_ref, output));
+                }
+            }
+
+            if keys.len() < KEYS_PAGE_SIZE as usize {
+                return Ok(orders);
+            }
+            start_key = keys.last().cloned();
+        }
+    }
+}
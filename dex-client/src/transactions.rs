@@ -0,0 +1,440 @@
+//! Building and signing dex transactions.
+//!
+//! These functions only build transactions. They do not check that the referenced UTXOs
+//! exist or that the amounts add up; the chain does that when the transaction is submitted.
+
+use dex::Side;
+use node_template_runtime::{
+    verifiers::{self, Sr25519SigCheck},
+    BlockNumber, DexConfig01, OuterConstraintChecker, OuterData, OuterVerifier, Transaction,
+};
+use parity_scale_codec::Encode;
+use sp_core::{sr25519, Pair, H256};
+use tuxedo_core::{
+    types::{Input, Output, OutputRef},
+    verifier::UpForGrabs,
+};
+
+/// An input spending the given output, with no redeemer yet.
+fn unsigned_input(output_ref: OutputRef) -> Input {
+    Input {
+        output_ref,
+        redeemer: Vec::new(),
+    }
+}
+
+/// The verifier that only the given sr25519 key can satisfy, by signing the
+/// [`bounded_signing_payload`]. See [`sign`].
+pub fn signed_by(owner: H256) -> OuterVerifier {
+    OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+        owner_pubkey: owner,
+    })
+}
+
+/// A transaction that opens an order on the given side of the token 0 / token 1 pair.
+///
+/// The coins are spent as collateral and should be worth exactly the offered amount, since
+/// any excess is not returned. The order is owned by, and pays out to, the given key, which
+/// must also be the key that signs the coins.
+pub fn make_order(
+    coins: Vec<OutputRef>,
+    side: Side,
+    offer_amount: u128,
+    ask_amount: u128,
+    owner: H256,
+) -> Transaction {
+    let (payload, checker) = match side {
+        Side::OffersA => (
+            dex::Order::<DexConfig01> {
+                owner,
+                ..dex::Order::new(offer_amount, ask_amount, signed_by(owner))
+            }
+            .into(),
+            OuterConstraintChecker::MakeOrder01(Default::default()),
+        ),
+        Side::OffersB => (
+            dex::Order::<dex::OppositeSide<DexConfig01>> {
+                owner,
+                ..dex::Order::new(offer_amount, ask_amount, signed_by(owner))
+            }
+            .into(),
+            OuterConstraintChecker::MakeOrder10(Default::default()),
+        ),
+    };
+
+    Transaction {
+        inputs: coins.into_iter().map(unsigned_input).collect(),
+        // Orders are up for grabs so that anyone can match them.
+        outputs: vec![Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload,
+        }],
+        checker,
+    }
+}
+
+/// A transaction that matches the given orders, paying out the given outputs.
+///
+/// The outputs are the payouts in the order of the orders they pay, followed by whatever
+/// else the match must create, like rebate outputs when taker fees are on.
+///
+/// The runtime only accepts matches whose orders come in ascending order of their refs, so
+/// sort them with [`node_template_runtime::canonical::sort`] before lining up the payouts.
+pub fn match_orders(orders: Vec<OutputRef>, outputs: Vec<Output<OuterVerifier>>) -> Transaction {
+    Transaction {
+        inputs: orders.into_iter().map(unsigned_input).collect(),
+        outputs,
+        checker: OuterConstraintChecker::MatchOrders(Default::default()),
+    }
+}
+
+/// A transaction like [`match_orders`] that names the given key as its referrer, such as
+/// the key of the front-end that built it.
+///
+/// When taker fees are on, the outputs must pay the referrer its share of the fee out of the
+/// match's surplus, in coins right after the rebate outputs.
+pub fn referred_match_orders(
+    orders: Vec<OutputRef>,
+    outputs: Vec<Output<OuterVerifier>>,
+    referrer: H256,
+) -> Transaction {
+    Transaction {
+        checker: OuterConstraintChecker::MatchOrders(dex::MatchOrders {
+            referrer: Some(signed_by(referrer)),
+            _ph_data: Default::default(),
+        }),
+        ..match_orders(orders, outputs)
+    }
+}
+
+/// A transaction that cancels an order made by [`make_order`] and refunds its whole offer
+/// to its owner.
+pub fn cancel_order(order: OutputRef, side: Side, offer_amount: u128, owner: H256) -> Transaction {
+    let (payload, checker) = match side {
+        Side::OffersA => (
+            money::Coin::<0>(offer_amount).into(),
+            OuterConstraintChecker::CancelOrder01(Default::default()),
+        ),
+        Side::OffersB => (
+            money::Coin::<1>(offer_amount).into(),
+            OuterConstraintChecker::CancelOrder10(Default::default()),
+        ),
+    };
+
+    Transaction {
+        inputs: vec![unsigned_input(order)],
+        outputs: vec![Output {
+            verifier: signed_by(owner),
+            payload,
+        }],
+        checker,
+    }
+}
+
+/// A transaction that drips the given amount of test coins from a faucet to the given key,
+/// or `None` if the given UTXO is not a faucet.
+///
+/// The faucet records the block that it drips in, so the transaction is only valid in the
+/// given block. Build it for the block after the best one, and build it again should it
+/// miss that block. The faucet turns away keys that it dripped to recently, and amounts
+/// above its cap.
+pub fn drip(
+    faucet_ref: OutputRef,
+    faucet: &Output<OuterVerifier>,
+    amount: u128,
+    recipient: H256,
+    block: BlockNumber,
+) -> Option<Transaction> {
+    let (next, coin, checker) = match OuterData::try_from(&faucet.payload).ok()? {
+        OuterData::Faucet0(faucet) => (
+            faucet.after_drip(signed_by(recipient), block).into(),
+            money::Coin::<0>(amount).into(),
+            OuterConstraintChecker::Drip0(Default::default()),
+        ),
+        OuterData::Faucet1(faucet) => (
+            faucet.after_drip(signed_by(recipient), block).into(),
+            money::Coin::<1>(amount).into(),
+            OuterConstraintChecker::Drip1(Default::default()),
+        ),
+        _ => return None,
+    };
+
+    Some(Transaction {
+        inputs: vec![unsigned_input(faucet_ref)],
+        outputs: vec![
+            // Faucets are up for grabs so that anyone can drip from them.
+            Output {
+                verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                payload: next,
+            },
+            Output {
+                verifier: signed_by(recipient),
+                payload: coin,
+            },
+        ],
+        checker,
+    })
+}
+
+/// A transaction that observes the average price of token 0 in token 1 at the given time,
+/// or `None` if the given UTXO is not its oracle, or the accumulator had a trade after then.
+///
+/// The accumulator is the one that the runtime records at `twap::accumulator_key`. Observe
+/// at the timestamp of the best block, which is not in the future for the pool nor for the
+/// next block. The observation becomes invalid should a trade be applied before it.
+pub fn observe_twap(
+    oracle_ref: OutputRef,
+    oracle: &Output<OuterVerifier>,
+    accumulator: &twap::Accumulator,
+    timestamp: u64,
+) -> Option<Transaction> {
+    let OuterData::TwapOracle01(oracle) = OuterData::try_from(&oracle.payload).ok()? else {
+        return None;
+    };
+    let next = oracle.after_observing(accumulator.observe(timestamp)?);
+
+    Some(Transaction {
+        inputs: vec![unsigned_input(oracle_ref)],
+        // Oracles are up for grabs so that anyone can observe in them.
+        outputs: vec![Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: next.into(),
+        }],
+        checker: OuterConstraintChecker::ObserveTwap01(Default::default()),
+    })
+}
+
+/// The bytes that verifiers check signatures over: the transaction with all redeemers stripped.
+pub fn signing_payload(transaction: &Transaction) -> Vec<u8> {
+    let mut stripped = transaction.clone();
+    for input in stripped.inputs.iter_mut() {
+        input.redeemer = Vec::new();
+    }
+    stripped.encode()
+}
+
+/// The bytes that the runtime's own verifiers, like [`signed_by`], check signatures over on
+/// the chain with the given genesis hash.
+///
+/// It is the [`signing_payload`], tagged and bound to the chain, and hashed if it is long,
+/// so that it is never longer than a hardware wallet can sign. Hand it to the wallet, and
+/// give the signature it returns to [`attach_redeemer`].
+pub fn bounded_signing_payload(transaction: &Transaction, genesis_hash: H256) -> Vec<u8> {
+    verifiers::signing_payload_on(genesis_hash, &signing_payload(transaction))
+}
+
+/// Use the given redeemer for every input of the transaction that does not have one yet.
+pub fn attach_redeemer(transaction: &mut Transaction, redeemer: &[u8]) {
+    for input in transaction.inputs.iter_mut() {
+        if input.redeemer.is_empty() {
+            input.redeemer = redeemer.to_vec();
+        }
+    }
+}
+
+/// Sign every input of the transaction that does not have a redeemer yet with the given key,
+/// over the [`bounded_signing_payload`] for the chain with the given genesis hash.
+///
+/// This satisfies [`signed_by`]. The payload is over the [`signing_payload`], so inputs may
+/// be signed by several keys in turn. Inputs that need no signature, like orders, may be
+/// signed all the same.
+pub fn sign(transaction: &mut Transaction, pair: &sr25519::Pair, genesis_hash: H256) {
+    let signature = pair.sign(&bounded_signing_payload(transaction, genesis_hash));
+    attach_redeemer(transaction, &signature.encode());
+}
+
+/// Sign every input of the transaction that does not have a redeemer yet with the given key,
+/// over the [`signing_payload`] itself.
+///
+/// This satisfies Tuxedo core's `SigCheck`, which is not bound to any chain. Only use it to
+/// spend outputs that are still protected by one.
+pub fn sign_unbounded(transaction: &mut Transaction, pair: &sr25519::Pair) {
+    let signature = pair.sign(&signing_payload(transaction));
+    attach_redeemer(transaction, &signature.encode());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use node_template_runtime::{FaucetConfig1, TwapConfig01};
+    use parity_scale_codec::Decode;
+    use tuxedo_core::{verifier::SigCheck, Verifier};
+
+    const GENESIS_HASH: H256 = H256::repeat_byte(3);
+
+    /// Whether the given input's redeemer is the key's signature over the bounded payload.
+    fn is_signed_by(transaction: &Transaction, index: usize, pair: &sr25519::Pair) -> bool {
+        let redeemer = &transaction.inputs[index].redeemer;
+        sr25519::Signature::decode(&mut &redeemer[..]).map_or(false, |signature| {
+            sr25519::Pair::verify(
+                &signature,
+                bounded_signing_payload(transaction, GENESIS_HASH),
+                &pair.public(),
+            )
+        })
+    }
+
+    fn output_ref(index: u32) -> OutputRef {
+        OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index,
+        }
+    }
+
+    #[test]
+    fn orders_are_made_on_the_requested_side() {
+        let owner = H256::repeat_byte(2);
+        let transaction = make_order(vec![output_ref(0)], Side::OffersB, 10, 20, owner);
+
+        assert_eq!(
+            transaction.checker,
+            OuterConstraintChecker::MakeOrder10(Default::default())
+        );
+        match OuterData::try_from(&transaction.outputs[0].payload) {
+            Ok(OuterData::Order10(order)) => {
+                assert_eq!((order.offer_amount, order.ask_amount), (10, 20));
+                assert_eq!(order.owner, owner);
+                assert_eq!(order.payout_verifier, signed_by(owner));
+            }
+            _ => panic!("the output is an order offering token 1"),
+        }
+    }
+
+    #[test]
+    fn drips_record_the_recipient_in_the_faucet() {
+        let recipient = H256::repeat_byte(2);
+        let faucet = Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: faucet::Faucet::<FaucetConfig1>::default().into(),
+        };
+        let transaction = drip(output_ref(0), &faucet, 500, recipient, 7).unwrap();
+
+        assert_eq!(
+            transaction.checker,
+            OuterConstraintChecker::Drip1(Default::default())
+        );
+        match OuterData::try_from(&transaction.outputs[0].payload) {
+            Ok(OuterData::Faucet1(next)) => {
+                assert_eq!(next.recent, vec![(signed_by(recipient), 7)]);
+            }
+            _ => panic!("the first output is the faucet of token 1"),
+        }
+        assert_eq!(transaction.outputs[1].verifier, signed_by(recipient));
+
+        let coin = Output {
+            verifier: signed_by(recipient),
+            payload: money::Coin::<1>(500).into(),
+        };
+        assert_eq!(drip(output_ref(0), &coin, 500, recipient, 7), None);
+    }
+
+    #[test]
+    fn observations_record_the_accumulator_in_the_oracle() {
+        let oracle = Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: twap::TwapOracle::<TwapConfig01>::default().into(),
+        };
+        let accumulator = twap::Accumulator {
+            cumulative: 100,
+            price: dex::Price(2),
+            updated_at: 1000,
+        };
+        let transaction = observe_twap(output_ref(0), &oracle, &accumulator, 1500).unwrap();
+
+        assert_eq!(
+            transaction.checker,
+            OuterConstraintChecker::ObserveTwap01(Default::default())
+        );
+        match OuterData::try_from(&transaction.outputs[0].payload) {
+            Ok(OuterData::TwapOracle01(next)) => {
+                assert_eq!(
+                    next.observations,
+                    vec![twap::Observation {
+                        timestamp: 1500,
+                        cumulative: 1100
+                    }]
+                );
+            }
+            _ => panic!("the output is the oracle"),
+        }
+
+        // A trade after the observation makes it stale.
+        assert_eq!(
+            observe_twap(output_ref(0), &oracle, &accumulator, 999),
+            None
+        );
+    }
+
+    #[test]
+    fn signing_satisfies_the_signers_verifier() {
+        let pair = sr25519::Pair::from_seed(&[7; 32]);
+        let owner = H256::from(pair.public().0);
+        let mut transaction = make_order(
+            vec![output_ref(0), output_ref(1)],
+            Side::OffersA,
+            10,
+            20,
+            owner,
+        );
+        sign(&mut transaction, &pair, GENESIS_HASH);
+
+        for index in 0..transaction.inputs.len() {
+            assert!(is_signed_by(&transaction, index, &pair));
+        }
+    }
+
+    #[test]
+    fn unbounded_signing_satisfies_sig_checks() {
+        let pair = sr25519::Pair::from_seed(&[7; 32]);
+        let owner = H256::from(pair.public().0);
+        let mut transaction = match_orders(vec![output_ref(0)], vec![]);
+        sign_unbounded(&mut transaction, &pair);
+
+        let sig_check = SigCheck {
+            owner_pubkey: owner,
+        };
+        let simplified_tx = signing_payload(&transaction);
+        assert!(sig_check.verify(&simplified_tx, &transaction.inputs[0].redeemer));
+    }
+
+    #[test]
+    fn signing_keeps_existing_redeemers() {
+        let pair = sr25519::Pair::from_seed(&[7; 32]);
+        let mut transaction = match_orders(vec![output_ref(0), output_ref(1)], vec![]);
+        transaction.inputs[0].redeemer = vec![1, 2, 3];
+        sign(&mut transaction, &pair, GENESIS_HASH);
+
+        assert_eq!(transaction.inputs[0].redeemer, vec![1, 2, 3]);
+        assert!(is_signed_by(&transaction, 1, &pair));
+    }
+
+    #[test]
+    fn referred_matches_name_the_referrer() {
+        let referrer = H256::repeat_byte(9);
+        let transaction = referred_match_orders(vec![output_ref(0)], vec![], referrer);
+
+        assert_eq!(
+            transaction.inputs,
+            match_orders(vec![output_ref(0)], vec![]).inputs
+        );
+        match transaction.checker {
+            OuterConstraintChecker::MatchOrders(checker) => {
+                assert_eq!(checker.referrer, Some(signed_by(referrer)));
+            }
+            _ => panic!("Referred matches are checked as matches"),
+        }
+    }
+
+    #[test]
+    fn bounded_payloads_of_large_matches_stay_short() {
+        let pair = sr25519::Pair::from_seed(&[7; 32]);
+        let orders = (0..50).map(output_ref).collect();
+        let mut transaction = match_orders(orders, vec![]);
+        sign(&mut transaction, &pair, GENESIS_HASH);
+
+        let payload = bounded_signing_payload(&transaction, GENESIS_HASH);
+        assert!(signing_payload(&transaction).len() > payload.len());
+        assert!(payload.starts_with(verifiers::SIGNING_PAYLOAD_TAG));
+        assert!(is_signed_by(&transaction, 0, &pair));
+    }
+}
@@ -0,0 +1,133 @@
+//! A small HTTP API over the indexed trading history.
+//!
+//! Every endpoint answers `GET` requests with JSON:
+//!
+//! * `/trades?pair=0-1&limit=100` lists the most recent trades in a market, newest first.
+//!   The limit defaults to 100, and is capped at [`MAX_TRADES`].
+//! * `/candles?pair=0-1&interval=1h&from=0&to=1700000000000` lists the candles of a market
+//!   at one of the [`INTERVALS`](crate::INTERVALS), oldest first. `from` and `to` are in
+//!   milliseconds since the unix epoch, and default to the whole history.
+//!
+//! Pairs are written as the token ids of a market's base and quote, like the runtime's
+//! `TRADED_PAIRS`.
+
+use std::{collections::HashMap, path::Path};
+
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{Store, INTERVALS};
+
+/// The most trades that one request may list.
+pub const MAX_TRADES: u32 = 1000;
+
+/// The number of trades listed when a request does not say.
+const DEFAULT_TRADES: u32 = 100;
+
+/// A response to a request, before it is encoded.
+type Reply = Result<String, (u16, String)>;
+
+/// A request that could not be understood.
+fn bad_request(message: impl Into<String>) -> (u16, String) {
+    (400, message.into())
+}
+
+/// The pair in the query, written like `0-1`.
+fn pair(query: &HashMap<&str, &str>) -> Result<(u8, u8), (u16, String)> {
+    let parse = |text: &str| {
+        let (base, quote) = text.split_once('-')?;
+        Some((base.parse().ok()?, quote.parse().ok()?))
+    };
+    query
+        .get("pair")
+        .and_then(|text| parse(text))
+        .ok_or_else(|| bad_request("Expected a pair like `pair=0-1`"))
+}
+
+/// The query parameters of a URL.
+fn query(url: &str) -> HashMap<&str, &str> {
+    url.split_once('?')
+        .map(|(_, query)| query)
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|parameter| parameter.split_once('='))
+        .collect()
+}
+
+/// Encode the response to a successful request.
+fn json(value: &impl Serialize) -> Reply {
+    serde_json::to_string(value).map_err(|e| (500, e.to_string()))
+}
+
+/// Answer a request for the given URL.
+fn route(store: &Store, url: &str) -> Reply {
+    let path = url.split('?').next().unwrap_or_default();
+    let query = query(url);
+
+    match path {
+        "/trades" => {
+            let pair = pair(&query)?;
+            let limit = match query.get("limit") {
+                Some(limit) => limit
+                    .parse()
+                    .map_err(|_| bad_request("Expected a number of trades"))?,
+                None => DEFAULT_TRADES,
+            };
+            let trades = store
+                .trades(pair, limit.min(MAX_TRADES))
+                .map_err(|e| (500, e.to_string()))?;
+            json(&trades)
+        }
+        "/candles" => {
+            let pair = pair(&query)?;
+            let interval = query
+                .get("interval")
+                .and_then(|name| INTERVALS.iter().find(|(known, _)| known == name))
+                .map(|&(_, interval)| interval)
+                .ok_or_else(|| bad_request("Expected an interval of 1m, 1h or 1d"))?;
+            let time = |name: &str, default: u64| match query.get(name) {
+                Some(time) => time
+                    .parse()
+                    .map_err(|_| bad_request(format!("Expected `{}` in milliseconds", name))),
+                None => Ok(default),
+            };
+            // Sqlite integers are signed, so this is the latest time it can store.
+            let latest = i64::MAX as u64;
+            let candles = store
+                .candles(pair, interval, time("from", 0)?, time("to", latest)?)
+                .map_err(|e| (500, e.to_string()))?;
+            json(&candles)
+        }
+        _ => Err((404, format!("There is nothing at {}", path))),
+    }
+}
+
+/// Answer one request.
+fn respond(store: &Store, request: Request) {
+    let reply = match request.method() {
+        Method::Get => route(store, request.url()),
+        _ => Err((405, "Only GET requests are served".into())),
+    };
+    let response = match reply {
+        Ok(body) => Response::from_string(body).with_header(
+            Header::from_bytes("Content-Type", "application/json").expect("The header is valid"),
+        ),
+        Err((status, message)) => Response::from_string(message).with_status_code(status),
+    };
+    // The client may already be gone, and there is no one else to tell.
+    let _ = request.respond(response);
+}
+
+/// Serve the API at the given address, like `127.0.0.1:8080`, from the database at the
+/// given path, until the process exits.
+///
+/// The database is opened separately from the indexer's, so requests are answered while
+/// blocks are being indexed.
+pub fn serve(address: &str, database: impl AsRef<Path>) -> Result<(), String> {
+    let store = Store::open(database).map_err(|e| e.to_string())?;
+    let server = Server::http(address).map_err(|e| e.to_string())?;
+    for request in server.incoming_requests() {
+        respond(&store, request);
+    }
+    Ok(())
+}
@@ -0,0 +1,314 @@
+//! An off-chain indexer of the dex's trading history.
+//!
+//! The node's trade subscription only reports new blocks, and the runtime's tickers only
+//! cover the trailing day, so charts and trade histories need an indexer. This one follows
+//! the node's finalized blocks over JSON-RPC with a [`DexClient`], and finds the orders
+//! that each block's `MatchOrders` transactions executed. Every executed order is recorded
+//! as a [`Trade`] in a sqlite [`Store`], and added to the OHLCV [`Candle`]s of its market
+//! at each of the [`INTERVALS`]. The [`api`] module serves both over HTTP.
+//!
+//! Only finalized blocks are indexed, so the history never has to be rolled back after a
+//! reorg. The orders that a block executed are read from the state of its parent block, so
+//! the node must keep the state of every block that the indexer has yet to catch up on.
+//! When indexing a long chain from scratch, run the node with `--state-pruning archive`.
+
+use std::fmt;
+
+use dex::{Price, Rounding};
+use dex_client::{ClientError, DexClient};
+use node_template_runtime::{
+    order_book::{self, Fill, TRADED_PAIRS},
+    BlockNumber, OuterConstraintChecker, Output, OutputRefs, Transaction,
+};
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use tuxedo_core::types::OutputRef;
+
+pub mod api;
+pub mod store;
+
+pub use store::Store;
+
+/// The length of a minute, in milliseconds.
+pub const MINUTE: u64 = 60 * 1000;
+
+/// The length of an hour, in milliseconds.
+pub const HOUR: u64 = 60 * MINUTE;
+
+/// The length of a day, in milliseconds.
+pub const DAY: u64 = 24 * HOUR;
+
+/// The intervals that candles are kept at, by name and length in milliseconds.
+pub const INTERVALS: &[(&str, u64)] = &[("1m", MINUTE), ("1h", HOUR), ("1d", DAY)];
+
+/// All the things that can go wrong while indexing.
+#[derive(Debug)]
+pub enum IndexerError {
+    /// The node could not be reached, or returned something unexpected.
+    Client(ClientError),
+    /// The database could not be read or written.
+    Database(rusqlite::Error),
+    /// The node does not have a block that it reported as finalized.
+    MissingBlock(H256),
+}
+
+impl fmt::Display for IndexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Client(e) => write!(f, "{}", e),
+            Self::Database(e) => write!(f, "Database error: {}", e),
+            Self::MissingBlock(hash) => write!(f, "The node does not have block {:?}", hash),
+        }
+    }
+}
+
+impl std::error::Error for IndexerError {}
+
+impl From<ClientError> for IndexerError {
+    fn from(e: ClientError) -> Self {
+        Self::Client(e)
+    }
+}
+
+impl From<rusqlite::Error> for IndexerError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+/// A single executed order, as seen from its market.
+///
+/// A market is one of the runtime's `TRADED_PAIRS`. Its first token is the base in which
+/// sizes are measured, and its second token is the quote in which prices are given.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Trade {
+    /// The number of the block that executed the order
+    pub block_number: BlockNumber,
+    /// The timestamp of that block, in milliseconds since the unix epoch
+    pub timestamp: u64,
+    /// Where the executed order was stored
+    pub order_ref: OutputRef,
+    /// The market the order was in
+    pub pair: (u8, u8),
+    /// Whether the executed order offered the base token
+    pub sold_base: bool,
+    /// The amount of the base token that changed hands
+    pub size: u128,
+    /// The amount of the quote token that changed hands
+    pub quote_amount: u128,
+    /// The amount of the quote token paid for each unit of the base token, with
+    /// `Price::DECIMALS` decimal places, or `None` if no base token changed hands
+    pub price: Option<Price>,
+}
+
+impl Trade {
+    /// The trade made by a fill in the given market, if the fill was in that market.
+    pub fn new(
+        block_number: BlockNumber,
+        timestamp: u64,
+        fill: &Fill,
+        pair: (u8, u8),
+    ) -> Option<Self> {
+        let (size, quote_amount) = fill.amounts_in(pair)?;
+        Some(Self {
+            block_number,
+            timestamp,
+            order_ref: fill.order_ref.clone(),
+            pair,
+            sold_base: fill.terms.offer_token == pair.0,
+            size,
+            quote_amount,
+            price: Price::from_amounts(quote_amount, size, Rounding::Down),
+        })
+    }
+}
+
+/// The trading in one market during one interval.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Candle {
+    /// The market that was traded in
+    pub pair: (u8, u8),
+    /// The length of the interval, in milliseconds
+    pub interval: u64,
+    /// The start of the interval, in milliseconds since the unix epoch
+    pub start: u64,
+    /// The price of the first trade in the interval
+    pub open: Price,
+    /// The highest price traded at
+    pub high: Price,
+    /// The lowest price traded at
+    pub low: Price,
+    /// The price of the last trade in the interval
+    pub close: Price,
+    /// The amount of the base token that changed hands
+    pub base_volume: u128,
+    /// The amount of the quote token that changed hands
+    pub quote_volume: u128,
+    /// The number of trades
+    pub trades: u64,
+}
+
+impl Candle {
+    /// The candle of the given interval length that a trade opens.
+    ///
+    /// Returns `None` if the trade has no price.
+    pub fn open(trade: &Trade, interval: u64) -> Option<Self> {
+        let price = trade.price?;
+        Some(Self {
+            pair: trade.pair,
+            interval,
+            start: trade.timestamp - trade.timestamp % interval,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            base_volume: trade.size,
+            quote_volume: trade.quote_amount,
+            trades: 1,
+        })
+    }
+
+    /// Add a later trade in the same market and interval to this candle.
+    ///
+    /// Trades without a price are ignored.
+    pub fn add(&mut self, trade: &Trade) {
+        let Some(price) = trade.price else {
+            return;
+        };
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.base_volume = self.base_volume.saturating_add(trade.size);
+        self.quote_volume = self.quote_volume.saturating_add(trade.quote_amount);
+        self.trades += 1;
+    }
+}
+
+/// Everything the indexer records about one block.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct IndexedBlock {
+    /// The number of the block
+    pub number: BlockNumber,
+    /// The hash of the block
+    pub hash: H256,
+    /// The timestamp of the block, in milliseconds since the unix epoch
+    pub timestamp: u64,
+    /// The orders the block executed, in the order they were executed
+    pub trades: Vec<Trade>,
+}
+
+/// The timestamp that the given block's transactions set, or zero if they set none.
+pub fn block_timestamp(transactions: &[Transaction]) -> u64 {
+    transactions
+        .iter()
+        .find_map(|transaction| match transaction.checker {
+            OuterConstraintChecker::Timestamp(_) => transaction
+                .outputs
+                .first()
+                .and_then(|output| output.payload.extract::<timestamp::Timestamp>().ok()),
+            _ => None,
+        })
+        .map_or(0, |timestamp| timestamp.time)
+}
+
+/// The trades made by the given block's transactions, given the outputs that they spent.
+pub fn trades_in(
+    number: BlockNumber,
+    timestamp: u64,
+    transactions: &[Transaction],
+    spent: &[(OutputRef, Output)],
+) -> Vec<Trade> {
+    let fills = order_book::fills(transactions, spent);
+    fills
+        .iter()
+        .flat_map(|fill| {
+            TRADED_PAIRS
+                .iter()
+                .filter_map(move |&pair| Trade::new(number, timestamp, fill, pair))
+        })
+        .collect()
+}
+
+/// The outputs that the matches in a block spent.
+///
+/// Orders made earlier in the same block are found among its transactions, and all
+/// others are read from the state of the parent block.
+async fn spent_by_matches(
+    client: &DexClient,
+    parent: H256,
+    transactions: &[Transaction],
+) -> Result<Vec<(OutputRef, Output)>, ClientError> {
+    let created: Vec<(OutputRef, &Output)> = transactions
+        .iter()
+        .flat_map(|transaction| {
+            transaction
+                .output_refs()
+                .into_iter()
+                .zip(&transaction.outputs)
+        })
+        .collect();
+
+    let mut spent = Vec::new();
+    for transaction in transactions {
+        if !matches!(transaction.checker, OuterConstraintChecker::MatchOrders(_)) {
+            continue;
+        }
+        for input in &transaction.inputs {
+            let output_ref = &input.output_ref;
+            let made_in_block = created
+                .iter()
+                .find(|(created_ref, _)| created_ref == output_ref);
+            let output = match made_in_block {
+                Some((_, output)) => Some((*output).clone()),
+                None => client.utxo(output_ref, parent).await?,
+            };
+            if let Some(output) = output {
+                spent.push((output_ref.clone(), output));
+            }
+        }
+    }
+    Ok(spent)
+}
+
+/// Read the trades of the given block from the node.
+pub async fn index_block(client: &DexClient, hash: H256) -> Result<IndexedBlock, IndexerError> {
+    let (header, transactions) = client
+        .block(hash)
+        .await?
+        .ok_or(IndexerError::MissingBlock(hash))?;
+    let spent = spent_by_matches(client, header.parent_hash, &transactions).await?;
+    let timestamp = block_timestamp(&transactions);
+
+    Ok(IndexedBlock {
+        number: header.number,
+        hash,
+        timestamp,
+        trades: trades_in(header.number, timestamp, &transactions, &spent),
+    })
+}
+
+/// Index every finalized block that the store does not have yet, oldest first.
+///
+/// Returns the number of the latest finalized block.
+pub async fn catch_up(client: &DexClient, store: &mut Store) -> Result<BlockNumber, IndexerError> {
+    let finalized = client.finalized_head().await?;
+    let (header, _) = client
+        .block(finalized)
+        .await?
+        .ok_or(IndexerError::MissingBlock(finalized))?;
+
+    // The genesis block has no transactions, so indexing starts after it.
+    let first = store.last_indexed()?.map_or(1, |last| last + 1);
+    for number in first..=header.number {
+        // Finalized blocks are on the best chain, so this is the finalized block at that height.
+        let Some(hash) = client.block_hash(number).await? else {
+            break;
+        };
+        let block = index_block(client, hash).await?;
+        store.record(&block)?;
+    }
+
+    Ok(header.number)
+}
@@ -0,0 +1,59 @@
+//! Index the dex trades of a node's finalized blocks, and serve them over HTTP.
+//!
+//! Usage: `dex-indexer <node-url> <database> [listen-address]`, like
+//! `dex-indexer http://localhost:9933 dex.sqlite 127.0.0.1:8080`. Indexing resumes where the
+//! database left off. See the `api` module for the endpoints.
+
+use std::{env, process::ExitCode, thread, time::Duration};
+
+use dex_client::DexClient;
+use dex_indexer::{api, catch_up, Store};
+
+/// How long to wait before checking for newly finalized blocks, about one block time.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The address the HTTP API listens on if none is given.
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    let (Some(url), Some(database)) = (args.get(1), args.get(2)) else {
+        eprintln!("Usage: dex-indexer <node-url> <database> [listen-address]");
+        return ExitCode::FAILURE;
+    };
+    let address = args.get(3).map_or(DEFAULT_ADDRESS, String::as_str);
+
+    let client = match DexClient::new(url) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Failed to connect to {}: {}", url, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut store = match Store::open(database) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Failed to open {}: {}", database, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (api_address, api_database) = (address.to_owned(), database.clone());
+    thread::spawn(move || {
+        if let Err(e) = api::serve(&api_address, api_database) {
+            eprintln!("Failed to serve the API at {}: {}", api_address, e);
+        }
+    });
+    println!("Serving the indexed trades at http://{}", address);
+
+    loop {
+        // A node that is briefly unreachable should not stop the indexer, so errors are
+        // reported and indexing is retried.
+        match catch_up(&client, &mut store).await {
+            Ok(finalized) => println!("Indexed up to block {}", finalized),
+            Err(e) => eprintln!("Failed to index: {}", e),
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
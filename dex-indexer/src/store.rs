@@ -0,0 +1,242 @@
+//! The sqlite database that the indexer records trades and candles in.
+//!
+//! Amounts and prices are `u128`s, which do not fit in sqlite's integers, so they are stored
+//! as decimal text. Output refs are stored in their SCALE encoding.
+
+use std::path::Path;
+
+use dex::Price;
+use node_template_runtime::BlockNumber;
+use parity_scale_codec::{Decode, Encode};
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+use tuxedo_core::types::OutputRef;
+
+use crate::{Candle, IndexedBlock, Trade, INTERVALS};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS blocks (
+        number INTEGER PRIMARY KEY,
+        hash BLOB NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS trades (
+        block_number INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        timestamp INTEGER NOT NULL,
+        order_ref BLOB NOT NULL,
+        base INTEGER NOT NULL,
+        quote INTEGER NOT NULL,
+        sold_base INTEGER NOT NULL,
+        size TEXT NOT NULL,
+        quote_amount TEXT NOT NULL,
+        price TEXT,
+        PRIMARY KEY (block_number, position)
+    );
+    CREATE INDEX IF NOT EXISTS trades_by_market ON trades (base, quote, block_number);
+    CREATE TABLE IF NOT EXISTS candles (
+        base INTEGER NOT NULL,
+        quote INTEGER NOT NULL,
+        interval INTEGER NOT NULL,
+        start INTEGER NOT NULL,
+        open TEXT NOT NULL,
+        high TEXT NOT NULL,
+        low TEXT NOT NULL,
+        close TEXT NOT NULL,
+        base_volume TEXT NOT NULL,
+        quote_volume TEXT NOT NULL,
+        trades INTEGER NOT NULL,
+        PRIMARY KEY (base, quote, interval, start)
+    );
+";
+
+/// Parse a `u128` that was stored as decimal text in the given column.
+fn parse(text: &str, index: usize) -> rusqlite::Result<u128> {
+    text.parse()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+/// Read a `u128` that was stored as decimal text.
+fn amount(row: &Row, index: usize) -> rusqlite::Result<u128> {
+    parse(&row.get::<_, String>(index)?, index)
+}
+
+/// Read a price that was stored as decimal text.
+fn price(row: &Row, index: usize) -> rusqlite::Result<Price> {
+    amount(row, index).map(Price)
+}
+
+/// Read a price that was stored as decimal text, or as null if there was none.
+fn optional_price(row: &Row, index: usize) -> rusqlite::Result<Option<Price>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|text| parse(&text, index).map(Price))
+        .transpose()
+}
+
+/// Read an output ref that was stored in its SCALE encoding.
+fn output_ref(row: &Row, index: usize) -> rusqlite::Result<OutputRef> {
+    let encoded: Vec<u8> = row.get(index)?;
+    OutputRef::decode(&mut &encoded[..])
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Blob, Box::new(e)))
+}
+
+/// Read a candle from a row of the candles table.
+fn candle(row: &Row) -> rusqlite::Result<Candle> {
+    Ok(Candle {
+        pair: (row.get(0)?, row.get(1)?),
+        interval: row.get(2)?,
+        start: row.get(3)?,
+        open: price(row, 4)?,
+        high: price(row, 5)?,
+        low: price(row, 6)?,
+        close: price(row, 7)?,
+        base_volume: amount(row, 8)?,
+        quote_volume: amount(row, 9)?,
+        trades: row.get(10)?,
+    })
+}
+
+/// The recorded trading history.
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    /// Open the database at the given path, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        // Let the HTTP API read while the indexer writes.
+        connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        Self::new(connection)
+    }
+
+    /// Open a database that only lives in memory, for tests.
+    pub fn in_memory() -> rusqlite::Result<Self> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// The number of the latest block that was recorded, if any was.
+    pub fn last_indexed(&self) -> rusqlite::Result<Option<BlockNumber>> {
+        self.connection
+            .query_row("SELECT MAX(number) FROM blocks", [], |row| row.get(0))
+    }
+
+    /// Record an indexed block, its trades, and what they add to each candle.
+    ///
+    /// Blocks must be recorded in order. Everything is written in one database transaction,
+    /// so a block is either recorded entirely or not at all.
+    pub fn record(&mut self, block: &IndexedBlock) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO blocks (number, hash, timestamp) VALUES (?1, ?2, ?3)",
+            params![block.number, block.hash.as_bytes(), block.timestamp],
+        )?;
+
+        for (position, trade) in block.trades.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO trades
+                    (block_number, position, timestamp, order_ref, base, quote, sold_base, size,
+                     quote_amount, price)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    trade.block_number,
+                    position,
+                    trade.timestamp,
+                    trade.order_ref.encode(),
+                    trade.pair.0,
+                    trade.pair.1,
+                    trade.sold_base,
+                    trade.size.to_string(),
+                    trade.quote_amount.to_string(),
+                    trade.price.map(|price| price.0.to_string()),
+                ],
+            )?;
+
+            for &(_, interval) in INTERVALS {
+                let Some(opened) = Candle::open(trade, interval) else {
+                    continue;
+                };
+                let existing = transaction
+                    .query_row(
+                        "SELECT * FROM candles
+                            WHERE base = ?1 AND quote = ?2 AND interval = ?3 AND start = ?4",
+                        params![opened.pair.0, opened.pair.1, interval, opened.start],
+                        candle,
+                    )
+                    .optional()?;
+                let updated = match existing {
+                    Some(mut candle) => {
+                        candle.add(trade);
+                        candle
+                    }
+                    None => opened,
+                };
+                transaction.execute(
+                    "INSERT OR REPLACE INTO candles VALUES
+                        (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    params![
+                        updated.pair.0,
+                        updated.pair.1,
+                        updated.interval,
+                        updated.start,
+                        updated.open.0.to_string(),
+                        updated.high.0.to_string(),
+                        updated.low.0.to_string(),
+                        updated.close.0.to_string(),
+                        updated.base_volume.to_string(),
+                        updated.quote_volume.to_string(),
+                        updated.trades,
+                    ],
+                )?;
+            }
+        }
+
+        transaction.commit()
+    }
+
+    /// The most recent trades in the given market, newest first.
+    pub fn trades(&self, pair: (u8, u8), limit: u32) -> rusqlite::Result<Vec<Trade>> {
+        let mut statement = self.connection.prepare(
+            "SELECT block_number, timestamp, order_ref, sold_base, size, quote_amount, price
+                FROM trades WHERE base = ?1 AND quote = ?2
+                ORDER BY block_number DESC, position DESC LIMIT ?3",
+        )?;
+        let trades = statement.query_map(params![pair.0, pair.1, limit], |row| {
+            Ok(Trade {
+                block_number: row.get(0)?,
+                timestamp: row.get(1)?,
+                order_ref: output_ref(row, 2)?,
+                pair,
+                sold_base: row.get(3)?,
+                size: amount(row, 4)?,
+                quote_amount: amount(row, 5)?,
+                price: optional_price(row, 6)?,
+            })
+        })?;
+        trades.collect()
+    }
+
+    /// The candles of the given market and interval that start in the given time range,
+    /// in milliseconds since the unix epoch, oldest first.
+    ///
+    /// Intervals without trading have no candle.
+    pub fn candles(
+        &self,
+        pair: (u8, u8),
+        interval: u64,
+        from: u64,
+        to: u64,
+    ) -> rusqlite::Result<Vec<Candle>> {
+        let mut statement = self.connection.prepare(
+            "SELECT * FROM candles
+                WHERE base = ?1 AND quote = ?2 AND interval = ?3 AND start >= ?4 AND start < ?5
+                ORDER BY start",
+        )?;
+        let candles = statement.query_map(params![pair.0, pair.1, interval, from, to], candle)?;
+        candles.collect()
+    }
+}
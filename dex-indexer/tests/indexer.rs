@@ -0,0 +1,176 @@
+use dex::{OppositeSide, Order, Price, Rounding};
+use dex_indexer::*;
+use node_template_runtime::{
+    DexConfig01, OuterConstraintChecker, OuterVerifier, Output, Transaction,
+};
+use sp_core::H256;
+use tuxedo_core::{
+    types::{Input, OutputRef},
+    verifier::UpForGrabs,
+};
+
+fn order_ref(index: u32) -> OutputRef {
+    OutputRef {
+        tx_hash: H256::repeat_byte(1),
+        index,
+    }
+}
+
+fn order<T: dex::DexConfig<Verifier = OuterVerifier>>(
+    offer_amount: u128,
+    ask_amount: u128,
+) -> Output {
+    let order = Order::<T>::new(
+        offer_amount,
+        ask_amount,
+        OuterVerifier::UpForGrabs(UpForGrabs),
+    );
+    Output {
+        payload: order.into(),
+        verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+    }
+}
+
+/// A match of the orders at the given refs.
+fn match_orders(order_refs: &[OutputRef]) -> Transaction {
+    Transaction {
+        inputs: order_refs
+            .iter()
+            .map(|output_ref| Input {
+                output_ref: output_ref.clone(),
+                redeemer: Vec::new(),
+            })
+            .collect(),
+        outputs: vec![],
+        checker: OuterConstraintChecker::MatchOrders(Default::default()),
+    }
+}
+
+fn price(quote: u128, base: u128) -> Price {
+    Price::from_amounts(quote, base, Rounding::Down).unwrap()
+}
+
+/// A trade of `size` of token 0 for `quote_amount` of token 1 at the given time.
+fn trade(block_number: u32, timestamp: u64, size: u128, quote_amount: u128) -> Trade {
+    Trade {
+        block_number,
+        timestamp,
+        order_ref: order_ref(block_number),
+        pair: (0, 1),
+        sold_base: true,
+        size,
+        quote_amount,
+        price: Price::from_amounts(quote_amount, size, Rounding::Down),
+    }
+}
+
+fn block(number: u32, timestamp: u64, trades: Vec<Trade>) -> IndexedBlock {
+    IndexedBlock {
+        number,
+        hash: H256::repeat_byte(number as u8),
+        timestamp,
+        trades,
+    }
+}
+
+#[test]
+fn matched_orders_are_trades_in_their_market() {
+    let spent = vec![
+        (order_ref(0), order::<DexConfig01>(10, 20)),
+        (order_ref(1), order::<OppositeSide<DexConfig01>>(30, 10)),
+    ];
+    let transactions = vec![match_orders(&[order_ref(0), order_ref(1)])];
+
+    let trades = trades_in(7, 1_000, &transactions, &spent);
+    assert_eq!(
+        trades,
+        vec![
+            Trade {
+                block_number: 7,
+                timestamp: 1_000,
+                order_ref: order_ref(0),
+                pair: (0, 1),
+                sold_base: true,
+                size: 10,
+                quote_amount: 20,
+                price: Some(price(20, 10)),
+            },
+            Trade {
+                block_number: 7,
+                timestamp: 1_000,
+                order_ref: order_ref(1),
+                pair: (0, 1),
+                sold_base: false,
+                size: 10,
+                quote_amount: 30,
+                price: Some(price(30, 10)),
+            },
+        ]
+    );
+}
+
+#[test]
+fn blocks_without_a_timestamp_are_at_zero() {
+    assert_eq!(block_timestamp(&[match_orders(&[order_ref(0)])]), 0);
+}
+
+#[test]
+fn candles_add_up_the_trades_in_each_interval() {
+    let mut store = Store::in_memory().unwrap();
+    store
+        .record(&block(1, 0, vec![trade(1, 0, 10, 20), trade(1, 0, 10, 50)]))
+        .unwrap();
+    store
+        .record(&block(2, MINUTE + 5, vec![trade(2, MINUTE + 5, 20, 30)]))
+        .unwrap();
+    assert_eq!(store.last_indexed().unwrap(), Some(2));
+
+    let minutes = store.candles((0, 1), MINUTE, 0, HOUR).unwrap();
+    assert_eq!(minutes.len(), 2);
+    assert_eq!(
+        minutes[0],
+        Candle {
+            pair: (0, 1),
+            interval: MINUTE,
+            start: 0,
+            open: price(20, 10),
+            high: price(50, 10),
+            low: price(20, 10),
+            close: price(50, 10),
+            base_volume: 20,
+            quote_volume: 70,
+            trades: 2,
+        }
+    );
+    assert_eq!(minutes[1].start, MINUTE);
+
+    let hours = store.candles((0, 1), HOUR, 0, HOUR).unwrap();
+    assert_eq!(hours.len(), 1);
+    assert_eq!(hours[0].open, price(20, 10));
+    assert_eq!(hours[0].low, price(30, 20));
+    assert_eq!(hours[0].close, price(30, 20));
+    assert_eq!(hours[0].base_volume, 40);
+    assert_eq!(hours[0].trades, 3);
+
+    assert!(store.candles((0, 1), MINUTE, HOUR, DAY).unwrap().is_empty());
+}
+
+#[test]
+fn recent_trades_come_newest_first() {
+    let mut store = Store::in_memory().unwrap();
+    store
+        .record(&block(1, 0, vec![trade(1, 0, 10, 20)]))
+        .unwrap();
+    store.record(&block(2, 3_000, vec![])).unwrap();
+    store
+        .record(&block(3, 6_000, vec![trade(3, 6_000, u128::MAX, 1)]))
+        .unwrap();
+
+    let trades = store.trades((0, 1), 10).unwrap();
+    assert_eq!(
+        trades,
+        vec![trade(3, 6_000, u128::MAX, 1), trade(1, 0, 10, 20)]
+    );
+    assert_eq!(store.trades((0, 1), 1).unwrap().len(), 1);
+    assert!(store.trades((1, 0), 10).unwrap().is_empty());
+}
@@ -0,0 +1,149 @@
+//! Build blocks with the real template runtime, to test whole trading sessions on the dex.
+//!
+//! Unlike the mock UTXO set of `tuxedo-test-utils`, a [`Chain`] runs every transaction
+//! through the runtime exactly as the block builder of a node does: each block starts with
+//! a timestamp inherent, and every transaction goes through the runtime-level checks and
+//! the post-application hooks. Tests built on it are the regression baseline for the
+//! runtime as a whole, so they should keep passing, with the same final state, as the
+//! checkers evolve.
+
+use node_template_runtime::{
+    try_state::{self, TryStateError},
+    verifiers::Sr25519SigCheck,
+    BlockNumber, GenesisConfig, Header, OuterVerifier, Output, Runtime, Transaction,
+};
+use parity_scale_codec::DecodeAll;
+use sp_core::H256;
+use sp_runtime::{traits::Header as _, BuildStorage};
+use tuxedo_core::types::OutputRef;
+
+/// The time between the timestamps of consecutive blocks, in milliseconds.
+pub const BLOCK_TIME: u64 = 3000;
+
+/// A chain that starts from the default genesis state and grows one block at a time.
+pub struct Chain {
+    ext: sp_io::TestExternalities,
+    genesis_hash: H256,
+    best: Header,
+}
+
+impl Default for Chain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chain {
+    /// A chain with only the default genesis block.
+    pub fn new() -> Self {
+        let ext = GenesisConfig::default()
+            .build_storage()
+            .expect("System builds valid default genesis config")
+            .into();
+        let best = Header::new(
+            0,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        Self {
+            ext,
+            genesis_hash: best.hash(),
+            best,
+        }
+    }
+
+    /// The hash of the genesis block, which transactions on this chain are signed for.
+    pub fn genesis_hash(&self) -> H256 {
+        self.genesis_hash
+    }
+
+    /// The number of the latest block.
+    pub fn best_number(&self) -> BlockNumber {
+        self.best.number
+    }
+
+    /// Build the next block, with its timestamp inherent followed by the given transactions.
+    ///
+    /// Panics naming the transaction if the runtime rejects any of them, so that a test
+    /// fails right where its session went wrong.
+    pub fn build_block(&mut self, transactions: Vec<Transaction>) -> Header {
+        let header = Header::new(
+            self.best.number + 1,
+            Default::default(),
+            Default::default(),
+            self.best.hash(),
+            Default::default(),
+        );
+        let time = BLOCK_TIME * u64::from(header.number);
+
+        let best = self.ext.execute_with(|| {
+            Runtime::initialize_block(&header);
+            let inherent =
+                Runtime::timestamp_inherent(time).expect("Every block records its timestamp");
+            assert_eq!(
+                Runtime::apply_extrinsic(inherent),
+                Ok(Ok(())),
+                "Timestamp inherent of block {} was rejected",
+                header.number
+            );
+            for (index, transaction) in transactions.into_iter().enumerate() {
+                let result = Runtime::apply_extrinsic(transaction);
+                assert_eq!(
+                    result,
+                    Ok(Ok(())),
+                    "Transaction {} of block {} was rejected",
+                    index,
+                    header.number
+                );
+            }
+            Runtime::finalize_block()
+        });
+        self.best = best.clone();
+        best
+    }
+
+    /// Run the given closure against the state after the latest block.
+    pub fn execute_with<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        self.ext.execute_with(f)
+    }
+
+    /// Every UTXO in the state after the latest block, by its ref.
+    pub fn utxos(&mut self) -> Vec<(OutputRef, Output)> {
+        self.execute_with(|| {
+            let mut utxos = Vec::new();
+            let mut key = Vec::new();
+            while let Some(next) = sp_io::storage::next_key(&key) {
+                // Other storage items have keys or values of other shapes.
+                let utxo = OutputRef::decode_all(&mut &next[..]).ok().zip(
+                    sp_io::storage::get(&next)
+                        .and_then(|encoded| Output::decode_all(&mut &encoded[..]).ok()),
+                );
+                utxos.extend(utxo);
+                key = next;
+            }
+            utxos
+        })
+    }
+
+    /// How much of token 0 and token 1 the given sr25519 key holds in plain coins.
+    pub fn balances(&mut self, owner: H256) -> (u128, u128) {
+        let verifier = OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+            owner_pubkey: owner,
+        });
+        self.utxos()
+            .into_iter()
+            .filter(|(_, utxo)| utxo.verifier == verifier)
+            .fold((0, 0), |(a, b), (_, utxo)| {
+                let a_coin = utxo.payload.extract::<money::Coin<0>>().map_or(0, |c| c.0);
+                let b_coin = utxo.payload.extract::<money::Coin<1>>().map_or(0, |c| c.0);
+                (a + a_coin, b + b_coin)
+            })
+    }
+
+    /// Every way the state after the latest block breaks the invariants of the dex.
+    pub fn try_state(&mut self) -> Vec<TryStateError> {
+        self.execute_with(try_state::try_state)
+    }
+}
@@ -0,0 +1,256 @@
+//! A whole trading session on the dex, built block by block with the real runtime.
+//!
+//! Ten traders mint coins, open fifty orders on both sides of the pair, a matcher fills
+//! what it can, and the traders cancel what is left. The final balances are written out
+//! exactly, so that any change to the checkers that changes the outcome of a session fails
+//! here first.
+
+use dex::Side;
+use dex_client::transactions::{cancel_order, make_order, match_orders, sign, signed_by};
+use dex_integration_tests::Chain;
+use dex_sim::{Matcher, OpenOrder, PairwiseMatcher};
+use node_template_runtime::{
+    canonical, genesis_output_ref, OuterConstraintChecker, OuterVerifier, OutputRefs, Transaction,
+};
+use sp_core::{sr25519, Pair, H256};
+use tuxedo_core::{
+    types::{Input, Output},
+    verifier::UpForGrabs,
+};
+
+/// The number of traders in the session.
+const TRADERS: u32 = 10;
+/// The number of orders the traders open.
+const ORDERS: usize = 50;
+
+/// The key of the trader with the given index.
+fn trader(index: u32) -> sr25519::Pair {
+    sr25519::Pair::from_string(&format!("//Trader{}", index), None).expect("Valid dev seed")
+}
+
+/// The key of the matcher, who keeps the surplus of every match.
+fn matcher() -> sr25519::Pair {
+    sr25519::Pair::from_string("//Matcher", None).expect("Valid dev seed")
+}
+
+fn owner(pair: &sr25519::Pair) -> H256 {
+    H256::from(pair.public().0)
+}
+
+/// The terms of the order with the given index: who opens it, on which side, offering how
+/// much for how much.
+///
+/// Every trader opens five orders, alternating between the sides, and the amounts are
+/// spread out so that some orders cross and some do not.
+fn terms(index: usize) -> (u32, Side, u128, u128) {
+    let trader = (index % TRADERS as usize) as u32;
+    let side = if (index / TRADERS as usize + index) % 2 == 0 {
+        Side::OffersA
+    } else {
+        Side::OffersB
+    };
+    let offer_amount = 100 + (index as u128 * 37) % 41;
+    let ask_amount = 80 + (index as u128 * 53) % 61;
+    (trader, side, offer_amount, ask_amount)
+}
+
+/// A transaction that spends the genesis mint authority of a token to mint the offers of
+/// all the orders on the given side, one coin for each, in the order of the orders.
+fn mint_offers(side: Side) -> Transaction {
+    let (authority, new_authority, checker) = match side {
+        Side::OffersA => (
+            genesis_output_ref(2),
+            money::MintAuthority::<0>.into(),
+            OuterConstraintChecker::Money(money::MoneyConstraintChecker::Mint),
+        ),
+        Side::OffersB => (
+            genesis_output_ref(3),
+            money::MintAuthority::<1>.into(),
+            OuterConstraintChecker::SecondToken(money::MoneyConstraintChecker::Mint),
+        ),
+    };
+    let coins = (0..ORDERS)
+        .map(terms)
+        .filter(|(_, order_side, _, _)| *order_side == side)
+        .map(|(trader_index, _, offer_amount, _)| Output {
+            verifier: signed_by(owner(&trader(trader_index))),
+            payload: match side {
+                Side::OffersA => money::Coin::<0>(offer_amount).into(),
+                Side::OffersB => money::Coin::<1>(offer_amount).into(),
+            },
+        });
+
+    Transaction {
+        inputs: vec![Input {
+            output_ref: authority,
+            redeemer: Vec::new(),
+        }],
+        outputs: [Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: new_authority,
+        }]
+        .into_iter()
+        .chain(coins)
+        .collect(),
+        checker,
+    }
+}
+
+/// A match of the given orders that pays each of them exactly what it asks, and the
+/// surplus to the matcher.
+fn pay_exactly(mut orders: Vec<OpenOrder>) -> Transaction {
+    orders.sort_by_key(|order| canonical::sort_key(&order.output_ref));
+
+    // What the orders offer and ask for in total, of token 0 and token 1.
+    let (mut offered, mut asked) = ((0u128, 0u128), (0u128, 0u128));
+    let mut outputs = Vec::new();
+    for order in &orders {
+        let payout_verifier = signed_by(owner(&trader(order.trader)));
+        let payout = match order.side {
+            Side::OffersA => {
+                offered.0 += order.offer_amount;
+                asked.1 += order.ask_amount;
+                money::Coin::<1>(order.ask_amount).into()
+            }
+            Side::OffersB => {
+                offered.1 += order.offer_amount;
+                asked.0 += order.ask_amount;
+                money::Coin::<0>(order.ask_amount).into()
+            }
+        };
+        outputs.push(Output {
+            verifier: payout_verifier,
+            payload: payout,
+        });
+    }
+    let matcher_verifier = signed_by(owner(&matcher()));
+    if offered.0 > asked.0 {
+        outputs.push(Output {
+            verifier: matcher_verifier.clone(),
+            payload: money::Coin::<0>(offered.0 - asked.0).into(),
+        });
+    }
+    if offered.1 > asked.1 {
+        outputs.push(Output {
+            verifier: matcher_verifier,
+            payload: money::Coin::<1>(offered.1 - asked.1).into(),
+        });
+    }
+
+    match_orders(
+        orders.into_iter().map(|order| order.output_ref).collect(),
+        outputs,
+    )
+}
+
+#[test]
+fn trading_session_ends_with_exact_balances() {
+    let mut chain = Chain::new();
+
+    // Block 1: mint the coins that the orders will offer.
+    let mints = [mint_offers(Side::OffersA), mint_offers(Side::OffersB)];
+    let mut minted = [mints[0].output_refs(), mints[1].output_refs()].map(|refs| {
+        // The first output is the new mint authority.
+        refs.into_iter().skip(1)
+    });
+    chain.build_block(mints.to_vec());
+
+    // Block 2: every trader opens their orders, each with the coin minted for it.
+    let mut book = Vec::new();
+    let mut makes = Vec::new();
+    for row in 0..ORDERS {
+        let (trader_index, side, offer_amount, ask_amount) = terms(row);
+        let coin = match side {
+            Side::OffersA => minted[0].next(),
+            Side::OffersB => minted[1].next(),
+        }
+        .expect("A coin was minted for every order");
+        let pair = trader(trader_index);
+        let mut make = make_order(vec![coin], side, offer_amount, ask_amount, owner(&pair));
+        sign(&mut make, &pair, chain.genesis_hash());
+        book.push(OpenOrder {
+            row,
+            output_ref: make.output_ref(0),
+            trader: trader_index,
+            side,
+            offer_amount,
+            ask_amount,
+        });
+        makes.push(make);
+    }
+    chain.build_block(makes);
+    assert_eq!(chain.try_state(), vec![]);
+
+    // Block 3: the matcher fills every pair of orders that cross.
+    let proposals = PairwiseMatcher.propose(&book);
+    assert_eq!(proposals.len(), 21);
+    let mut filled = vec![false; book.len()];
+    let matches = proposals
+        .into_iter()
+        .map(|proposal| {
+            for &index in &proposal {
+                filled[index] = true;
+            }
+            pay_exactly(
+                proposal
+                    .into_iter()
+                    .map(|index| book[index].clone())
+                    .collect(),
+            )
+        })
+        .collect();
+    chain.build_block(matches);
+
+    // Block 4: the traders cancel the orders that are left.
+    let leftovers: Vec<OpenOrder> = book
+        .into_iter()
+        .zip(filled)
+        .filter_map(|(order, filled)| (!filled).then_some(order))
+        .collect();
+    assert_eq!(
+        leftovers.iter().map(|order| order.row).collect::<Vec<_>>(),
+        vec![23, 31, 39, 40, 41, 46, 47, 49]
+    );
+    let cancels = leftovers
+        .into_iter()
+        .map(|order| {
+            cancel_order(
+                order.output_ref,
+                order.side,
+                order.offer_amount,
+                owner(&trader(order.trader)),
+            )
+        })
+        .collect();
+    chain.build_block(cancels);
+
+    let expected = [
+        (310, 183),
+        (368, 214),
+        (235, 322),
+        (219, 350),
+        (203, 335),
+        (311, 187),
+        (353, 209),
+        (193, 333),
+        (200, 361),
+        (330, 220),
+    ];
+    for (index, balances) in expected.into_iter().enumerate() {
+        assert_eq!(
+            chain.balances(owner(&trader(index as u32))),
+            balances,
+            "Trader {} ends with the wrong balances",
+            index
+        );
+    }
+    assert_eq!(chain.balances(owner(&matcher())), (290, 278));
+
+    // Nothing was burned, and no order is left open.
+    let total = expected
+        .into_iter()
+        .chain([(290, 278)])
+        .fold((0, 0), |(a, b), (x, y)| (a + x, b + y));
+    assert_eq!(total, (3012, 2992));
+    assert_eq!(chain.try_state(), vec![]);
+}
@@ -0,0 +1,449 @@
+//! A deterministic simulator of the dex order book.
+//!
+//! The simulator replays a list of orders, usually read from CSV with [`parse_csv`],
+//! through the real `MakeOrder` and `MatchOrders` constraint checkers. Everything happens
+//! in a [`MockUtxoSet`], so no node is needed and every run of the same order flow gives
+//! the same result.
+//!
+//! The orders are grouped into blocks. In each block, every order is made from a fresh coin
+//! of its trader, and then a [`Matcher`] proposes matches against the whole book. Each
+//! proposed match pays every order exactly what it asks, and is applied like any other
+//! transaction. The [`Report`] counts how many orders were filled, and lists every
+//! [`Violation`]: matches that the checkers rejected, and accepted transactions that paid
+//! out more of either token than they consumed.
+//!
+//! The simulator checks the dex logic, not signatures. Every trader's verifier is
+//! satisfied by any transaction.
+
+use std::{cell::Cell, fmt};
+
+use dex::{
+    testing::value_in, ConstraintCheckingContext, DexConfig, DexError, MakeOrder, MatchOrders,
+    OppositeSide, Order, Side,
+};
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData,
+    types::{Input, Output, OutputRef, Transaction},
+    ConstraintChecker, Verifier,
+};
+use tuxedo_test_utils::{ApplyError, MockUtxoSet};
+
+thread_local! {
+    static BLOCK_NUMBER: Cell<u32> = Cell::new(0);
+}
+
+/// The verifier of everything a trader owns.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Trader {
+    pub id: u32,
+}
+
+impl Verifier for Trader {
+    fn verify(&self, _simplified_tx: &[u8], _redeemer: &[u8]) -> bool {
+        true
+    }
+}
+
+/// The simulated dex, which trades token 0 for token 1.
+#[derive(PartialEq, Eq, TypeInfo)]
+pub struct SimConfig;
+impl DexConfig for SimConfig {
+    type Verifier = Trader;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext {
+            block_number: BLOCK_NUMBER.with(|n| n.get()),
+            ..Default::default()
+        }
+    }
+}
+
+/// The constraint checkers the simulator uses.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum SimChecker {
+    MakeOrder01(MakeOrder<SimConfig>),
+    MakeOrder10(MakeOrder<OppositeSide<SimConfig>>),
+    MatchOrders(MatchOrders<SimConfig>),
+}
+
+impl ConstraintChecker<Trader> for SimChecker {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<Trader>],
+        outputs: &[Output<Trader>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        match self {
+            Self::MakeOrder01(checker) => {
+                ConstraintChecker::<Trader>::check(checker, inputs, outputs)
+            }
+            Self::MakeOrder10(checker) => {
+                ConstraintChecker::<Trader>::check(checker, inputs, outputs)
+            }
+            Self::MatchOrders(checker) => checker.check(inputs, outputs),
+        }
+    }
+}
+
+/// One order of the simulated order flow.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OrderRow {
+    /// The block the order is made in
+    pub block: u32,
+    /// The trader who makes the order and receives its payout
+    pub trader: u32,
+    /// Which token the order offers
+    pub side: Side,
+    pub offer_amount: u128,
+    pub ask_amount: u128,
+}
+
+/// A line of order flow that could not be read.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    /// The line number, starting from one
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Read order flow from CSV with the columns `block,trader,side,offer_amount,ask_amount`.
+///
+/// The side is `A` for orders offering token 0 and `B` for orders offering token 1. An
+/// optional header line, blank lines, and lines starting with `#` are skipped. Blocks must
+/// not decrease from one order to the next.
+pub fn parse_csv(csv: &str) -> Result<Vec<OrderRow>, ParseError> {
+    let mut rows: Vec<OrderRow> = Vec::new();
+    for (index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (index == 0 && line.starts_with("block")) {
+            continue;
+        }
+        let error = |reason: String| ParseError {
+            line: index + 1,
+            reason,
+        };
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [block, trader, side, offer_amount, ask_amount] = fields[..] else {
+            return Err(error(format!("expected 5 fields, found {}", fields.len())));
+        };
+        let number = |field: &str, name: &str| {
+            field
+                .parse::<u128>()
+                .map_err(|e| error(format!("invalid {}: {}", name, e)))
+        };
+        let row = OrderRow {
+            block: block
+                .parse()
+                .map_err(|e| error(format!("invalid block: {}", e)))?,
+            trader: trader
+                .parse()
+                .map_err(|e| error(format!("invalid trader: {}", e)))?,
+            side: match side {
+                "A" => Side::OffersA,
+                "B" => Side::OffersB,
+                other => return Err(error(format!("invalid side: {}", other))),
+            },
+            offer_amount: number(offer_amount, "offer amount")?,
+            ask_amount: number(ask_amount, "ask amount")?,
+        };
+        if rows.last().map_or(false, |last| last.block > row.block) {
+            return Err(error("blocks must not decrease".into()));
+        }
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// An order that is resting on the simulated book.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OpenOrder {
+    /// The index of the order in the order flow
+    pub row: usize,
+    /// Where the order is stored
+    pub output_ref: OutputRef,
+    pub trader: u32,
+    pub side: Side,
+    pub offer_amount: u128,
+    pub ask_amount: u128,
+}
+
+/// A matching strategy.
+pub trait Matcher {
+    /// Propose matches against the book, as lists of indices into it.
+    ///
+    /// The simulator builds a `MatchOrders` transaction for each proposed match, with the
+    /// orders in the given order and each of them paid exactly what it asks.
+    fn propose(&mut self, book: &[OpenOrder]) -> Vec<Vec<usize>>;
+}
+
+/// A strategy that matches each order offering token 0 with the oldest order offering token 1
+/// that it crosses with, oldest orders first.
+#[derive(Debug, Default)]
+pub struct PairwiseMatcher;
+
+impl Matcher for PairwiseMatcher {
+    fn propose(&mut self, book: &[OpenOrder]) -> Vec<Vec<usize>> {
+        let mut taken = vec![false; book.len()];
+        let mut matches = Vec::new();
+        for (i, a) in book.iter().enumerate() {
+            if a.side != Side::OffersA || taken[i] {
+                continue;
+            }
+            let crossing = book.iter().enumerate().find(|(j, b)| {
+                !taken[*j]
+                    && b.side == Side::OffersB
+                    && b.offer_amount >= a.ask_amount
+                    && a.offer_amount >= b.ask_amount
+            });
+            if let Some((j, _)) = crossing {
+                taken[i] = true;
+                taken[j] = true;
+                matches.push(vec![i, j]);
+            }
+        }
+        matches
+    }
+}
+
+/// Something the checkers should not have allowed, or a strategy should not have done.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Violation {
+    /// The checkers rejected a proposed match of the orders from the given rows.
+    RejectedMatch {
+        block: u32,
+        rows: Vec<usize>,
+        error: ApplyError<DexError>,
+    },
+    /// The checkers accepted a transaction for the given rows that paid out more of either
+    /// token than it consumed.
+    ValueCreated { block: u32, rows: Vec<usize> },
+}
+
+/// The outcome of a simulation.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Report {
+    /// How many orders were in the order flow
+    pub orders: usize,
+    /// The orders that could not be made, by row, and why
+    pub rejected_orders: Vec<(usize, ApplyError<DexError>)>,
+    /// How many matches were applied
+    pub matches: usize,
+    /// How many orders were filled by those matches
+    pub filled_orders: usize,
+    /// How many orders were still open at the end
+    pub open_orders: usize,
+    pub violations: Vec<Violation>,
+}
+
+impl Report {
+    /// The share of the orders that were made and then filled.
+    pub fn fill_rate(&self) -> f64 {
+        let made = self.orders - self.rejected_orders.len();
+        if made == 0 {
+            return 0.0;
+        }
+        self.filled_orders as f64 / made as f64
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "orders:          {}", self.orders)?;
+        writeln!(f, "rejected orders: {}", self.rejected_orders.len())?;
+        writeln!(f, "matches:         {}", self.matches)?;
+        writeln!(f, "filled orders:   {}", self.filled_orders)?;
+        writeln!(f, "open orders:     {}", self.open_orders)?;
+        writeln!(f, "fill rate:       {:.1}%", self.fill_rate() * 100.0)?;
+        for (row, error) in &self.rejected_orders {
+            writeln!(f, "order {} rejected: {:?}", row, error)?;
+        }
+        for violation in &self.violations {
+            writeln!(f, "violation: {:?}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+fn output(trader: u32, payload: impl Into<DynamicallyTypedData>) -> Output<Trader> {
+    Output {
+        payload: payload.into(),
+        verifier: Trader { id: trader },
+    }
+}
+
+fn spend(output_refs: impl IntoIterator<Item = OutputRef>) -> Vec<Input> {
+    output_refs
+        .into_iter()
+        .map(|output_ref| Input {
+            output_ref,
+            redeemer: Vec::new(),
+        })
+        .collect()
+}
+
+/// The replayed state of the book.
+struct Simulation {
+    utxo_set: MockUtxoSet<Trader>,
+    book: Vec<OpenOrder>,
+    report: Report,
+}
+
+impl Simulation {
+    /// Apply a transaction, and note a violation if it creates value.
+    fn apply(
+        &mut self,
+        transaction: Transaction<Trader, SimChecker>,
+        block: u32,
+        rows: Vec<usize>,
+    ) -> Result<Vec<OutputRef>, ApplyError<DexError>> {
+        let consumed: Vec<Output<Trader>> = transaction
+            .inputs
+            .iter()
+            .filter_map(|input| self.utxo_set.get(&input.output_ref).cloned())
+            .collect();
+        let output_refs = self.utxo_set.apply(&transaction)?;
+
+        let (in_a, in_b) = value_in::<SimConfig>(&consumed);
+        let (out_a, out_b) = value_in::<SimConfig>(&transaction.outputs);
+        if out_a > in_a || out_b > in_b {
+            self.report
+                .violations
+                .push(Violation::ValueCreated { block, rows });
+        }
+        Ok(output_refs)
+    }
+
+    /// Make the order of the given row from a fresh coin of its trader.
+    fn make_order(&mut self, row: usize, order: &OrderRow) {
+        let (coin, payload, checker) = match order.side {
+            Side::OffersA => (
+                output(order.trader, Coin::<0>(order.offer_amount)),
+                Order::<SimConfig>::new(
+                    order.offer_amount,
+                    order.ask_amount,
+                    Trader { id: order.trader },
+                )
+                .into(),
+                SimChecker::MakeOrder01(Default::default()),
+            ),
+            Side::OffersB => (
+                output(order.trader, Coin::<1>(order.offer_amount)),
+                Order::<OppositeSide<SimConfig>>::new(
+                    order.offer_amount,
+                    order.ask_amount,
+                    Trader { id: order.trader },
+                )
+                .into(),
+                SimChecker::MakeOrder10(Default::default()),
+            ),
+        };
+        let coin_ref = self.utxo_set.insert_genesis(coin);
+        let transaction = Transaction {
+            inputs: spend([coin_ref]),
+            outputs: vec![Output {
+                payload,
+                verifier: Trader { id: order.trader },
+            }],
+            checker,
+        };
+
+        match self.apply(transaction, order.block, vec![row]) {
+            Ok(output_refs) => self.book.push(OpenOrder {
+                row,
+                output_ref: output_refs[0].clone(),
+                trader: order.trader,
+                side: order.side,
+                offer_amount: order.offer_amount,
+                ask_amount: order.ask_amount,
+            }),
+            Err(error) => self.report.rejected_orders.push((row, error)),
+        }
+    }
+
+    /// Apply every match the strategy proposes, and take the filled orders off the book.
+    fn run_matcher(&mut self, block: u32, matcher: &mut impl Matcher) {
+        let mut filled = vec![false; self.book.len()];
+        for proposal in matcher.propose(&self.book) {
+            let orders: Vec<OpenOrder> = proposal
+                .iter()
+                .filter_map(|&index| self.book.get(index).cloned())
+                .collect();
+            let proposal_rows: Vec<usize> = orders.iter().map(|order| order.row).collect();
+            let transaction = Transaction {
+                inputs: spend(orders.iter().map(|order| order.output_ref.clone())),
+                outputs: orders
+                    .iter()
+                    .map(|order| match order.side {
+                        Side::OffersA => output(order.trader, Coin::<1>(order.ask_amount)),
+                        Side::OffersB => output(order.trader, Coin::<0>(order.ask_amount)),
+                    })
+                    .collect(),
+                checker: SimChecker::MatchOrders(Default::default()),
+            };
+
+            match self.apply(transaction, block, proposal_rows.clone()) {
+                Ok(_) => {
+                    self.report.matches += 1;
+                    self.report.filled_orders += orders.len();
+                    for index in proposal {
+                        if let Some(filled) = filled.get_mut(index) {
+                            *filled = true;
+                        }
+                    }
+                }
+                Err(error) => self.report.violations.push(Violation::RejectedMatch {
+                    block,
+                    rows: proposal_rows,
+                    error,
+                }),
+            }
+        }
+
+        let mut filled = filled.into_iter();
+        self.book.retain(|_| !filled.next().unwrap_or_default());
+    }
+}
+
+/// Replay the given order flow with the given matching strategy.
+pub fn simulate(rows: &[OrderRow], matcher: &mut impl Matcher) -> Report {
+    let mut simulation = Simulation {
+        utxo_set: MockUtxoSet::new(),
+        book: Vec::new(),
+        report: Report {
+            orders: rows.len(),
+            ..Default::default()
+        },
+    };
+
+    let mut next = 0;
+    while let Some(first) = rows.get(next) {
+        let block = first.block;
+        BLOCK_NUMBER.with(|n| n.set(block));
+        while let Some(order) = rows.get(next).filter(|order| order.block == block) {
+            simulation.make_order(next, order);
+            next += 1;
+        }
+        simulation.run_matcher(block, matcher);
+    }
+
+    simulation.report.open_orders = simulation.book.len();
+    simulation.report
+}
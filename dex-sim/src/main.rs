@@ -0,0 +1,39 @@
+//! Replay a CSV of order flow through the dex checkers and print a report.
+//!
+//! Usage: `dex-sim <order-flow.csv>`. See the library docs for the CSV format. The
+//! process exits with a failure if the checkers allowed or rejected anything they should
+//! not have.
+
+use std::{env, fs, process::ExitCode};
+
+use dex_sim::{parse_csv, simulate, PairwiseMatcher};
+
+fn main() -> ExitCode {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("Usage: dex-sim <order-flow.csv>");
+        return ExitCode::FAILURE;
+    };
+    let csv = match fs::read_to_string(&path) {
+        Ok(csv) => csv,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let rows = match parse_csv(&csv) {
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Failed to parse {}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = simulate(&rows, &mut PairwiseMatcher);
+    print!("{}", report);
+
+    if report.violations.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,99 @@
+use dex::{DexError, Side};
+use dex_sim::*;
+use tuxedo_test_utils::ApplyError;
+
+fn row(block: u32, trader: u32, side: Side, offer_amount: u128, ask_amount: u128) -> OrderRow {
+    OrderRow {
+        block,
+        trader,
+        side,
+        offer_amount,
+        ask_amount,
+    }
+}
+
+/// A strategy that matches the first two orders on the book, whether they cross or not.
+struct FirstTwoMatcher;
+impl Matcher for FirstTwoMatcher {
+    fn propose(&mut self, book: &[OpenOrder]) -> Vec<Vec<usize>> {
+        if book.len() < 2 {
+            return vec![];
+        }
+        vec![vec![0, 1]]
+    }
+}
+
+#[test]
+fn sample_order_flow_is_replayed() {
+    let rows = parse_csv(include_str!("../order_flow.csv")).unwrap();
+    let report = simulate(&rows, &mut PairwiseMatcher);
+
+    assert_eq!(report.orders, 6);
+    assert_eq!(
+        report.rejected_orders,
+        vec![(5, ApplyError::CheckerFailed(DexError::ZeroAmountOrder))]
+    );
+    assert_eq!(report.matches, 2);
+    assert_eq!(report.filled_orders, 4);
+    assert_eq!(report.open_orders, 1);
+    assert_eq!(report.fill_rate(), 0.8);
+    assert!(report.violations.is_empty());
+}
+
+#[test]
+fn orders_rest_until_they_cross() {
+    let rows = vec![
+        row(1, 1, Side::OffersA, 100, 300),
+        row(1, 2, Side::OffersB, 200, 100),
+    ];
+    let report = simulate(&rows, &mut PairwiseMatcher);
+
+    assert_eq!(report.matches, 0);
+    assert_eq!(report.open_orders, 2);
+}
+
+#[test]
+fn matches_that_do_not_cross_are_reported() {
+    let rows = vec![
+        row(1, 1, Side::OffersA, 100, 300),
+        row(1, 2, Side::OffersB, 200, 100),
+    ];
+    let report = simulate(&rows, &mut FirstTwoMatcher);
+
+    assert_eq!(
+        report.violations,
+        vec![Violation::RejectedMatch {
+            block: 1,
+            rows: vec![0, 1],
+            error: ApplyError::CheckerFailed(DexError::InsufficientTokenBForMatch),
+        }]
+    );
+    assert_eq!(report.open_orders, 2);
+}
+
+#[test]
+fn replays_are_deterministic() {
+    let rows = parse_csv(include_str!("../order_flow.csv")).unwrap();
+    assert_eq!(
+        simulate(&rows, &mut PairwiseMatcher),
+        simulate(&rows, &mut PairwiseMatcher)
+    );
+}
+
+#[test]
+fn malformed_lines_are_reported_with_their_number() {
+    let csv = "block,trader,side,offer_amount,ask_amount\n1,1,A,100,150\n1,2,C,150,100\n";
+    assert_eq!(
+        parse_csv(csv),
+        Err(ParseError {
+            line: 3,
+            reason: "invalid side: C".into(),
+        })
+    );
+}
+
+#[test]
+fn blocks_must_not_go_back() {
+    let csv = "2,1,A,100,150\n1,2,B,150,100\n";
+    assert_eq!(parse_csv(csv).map_err(|e| e.line), Err(2));
+}
@@ -0,0 +1,104 @@
+//! Benchmarks for the dex constraint checkers.
+//!
+//! Both checkers loop over every input they are given, so their cost grows linearly with
+//! the size of the transaction. These benchmarks measure that growth, which is what the
+//! runtime's transaction length limit has to keep in check.
+//!
+//! Run them with `cargo bench -p dex`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use dex::*;
+use money::Coin;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+struct BenchConfig;
+impl DexConfig for BenchConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+/// Open a single order backed by the given number of collateral coins.
+fn make_order(c: &mut Criterion) {
+    let mut group = c.benchmark_group("make_order");
+    for input_count in [1u128, 10, 100, 1_000] {
+        let inputs: Vec<Output<TestVerifier>> = (0..input_count)
+            .map(|_| output_from(Coin::<0>(1)))
+            .collect();
+        let outputs = vec![output_from(Order::<BenchConfig>::new(
+            input_count,
+            input_count,
+            TestVerifier { verifies: true },
+        ))];
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(input_count),
+            &(inputs, outputs),
+            |b, (inputs, outputs)| {
+                b.iter(|| {
+                    <MakeOrder<BenchConfig> as ConstraintChecker<TestVerifier>>::check(
+                        &Default::default(),
+                        inputs,
+                        outputs,
+                    )
+                    .expect("Order is fully collateralized")
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Match the given number of orders, half on each side of the pair.
+fn match_orders(c: &mut Criterion) {
+    let mut group = c.benchmark_group("match_orders");
+    for order_count in [2usize, 10, 100, 500] {
+        let mut inputs = Vec::with_capacity(order_count);
+        let mut outputs = Vec::with_capacity(order_count);
+        for i in 0..order_count {
+            if i % 2 == 0 {
+                inputs.push(output_from(Order::<BenchConfig>::new(
+                    10,
+                    10,
+                    TestVerifier { verifies: true },
+                )));
+                outputs.push(output_from(Coin::<1>(10)));
+            } else {
+                inputs.push(output_from(Order::<OppositeSide<BenchConfig>>::new(
+                    10,
+                    10,
+                    TestVerifier { verifies: true },
+                )));
+                outputs.push(output_from(Coin::<0>(10)));
+            }
+        }
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(order_count),
+            &(inputs, outputs),
+            |b, (inputs, outputs)| {
+                b.iter(|| {
+                    <MatchOrders<BenchConfig> as ConstraintChecker<TestVerifier>>::check(
+                        &Default::default(),
+                        inputs,
+                        outputs,
+                    )
+                    .expect("Orders match")
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, make_order, match_orders);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+//! Run the simple dex constraint checkers on arbitrary transactions along both paths.
+//!
+//! The bytes are decoded as the full inputs and outputs of a transaction. Each simple
+//! checker must give the same result on their payloads alone as it does on the whole
+//! outputs, however the verifiers are set. `MakeOrder` checks who owns the collateral, so
+//! it is a full checker and is left to the property tests in `tests/checker_paths.rs`.
+//!
+//! Run with `cargo fuzz run checker_paths` from the `dex` directory.
+
+#![no_main]
+
+use dex::*;
+use libfuzzer_sys::fuzz_target;
+use money::Coin;
+use parity_scale_codec::Decode;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+    SimpleConstraintChecker,
+};
+
+struct FuzzConfig;
+impl DexConfig for FuzzConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+/// Check the transaction with a simple checker on both paths, and require them to agree.
+fn assert_paths_agree<C: SimpleConstraintChecker<Error = DexError> + Default>(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) {
+    let payloads = |outputs: &[Output<TestVerifier>]| -> Vec<DynamicallyTypedData> {
+        outputs
+            .iter()
+            .map(|output| output.payload.clone())
+            .collect()
+    };
+    let simple =
+        SimpleConstraintChecker::check(&C::default(), &payloads(inputs), &payloads(outputs));
+    let full = <C as ConstraintChecker<TestVerifier>>::check(&C::default(), inputs, outputs);
+    assert_eq!(simple, full);
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((inputs, outputs)) =
+        <(Vec<Output<TestVerifier>>, Vec<Output<TestVerifier>>)>::decode(&mut &data[..])
+    else {
+        return;
+    };
+
+    assert_paths_agree::<MakeIceberg<FuzzConfig>>(&inputs, &outputs);
+    assert_paths_agree::<CommitOrder<FuzzConfig>>(&inputs, &outputs);
+    assert_paths_agree::<RevealOrder<FuzzConfig>>(&inputs, &outputs);
+    assert_paths_agree::<ReclaimCommitment<FuzzConfig>>(&inputs, &outputs);
+});
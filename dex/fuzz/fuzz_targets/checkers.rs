@@ -0,0 +1,88 @@
+//! Feed arbitrary bytes to the dex constraint checkers.
+//!
+//! The bytes are decoded as the inputs and outputs of a transaction, verifiers included.
+//! The checkers must never panic, whatever the data turns out to be, and a match they
+//! accept must never pay out more of either token than its orders offered.
+//!
+//! Run with `cargo fuzz run checkers` from the `dex` directory.
+
+#![no_main]
+
+use dex::*;
+use libfuzzer_sys::fuzz_target;
+use money::Coin;
+use parity_scale_codec::Decode;
+use tuxedo_core::{traits::Cash, types::Output, verifier::TestVerifier, ConstraintChecker};
+
+struct FuzzConfig;
+impl DexConfig for FuzzConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+/// The total amount of the given token held as coins in the given outputs.
+fn coin_total<const ID: u8>(outputs: &[Output<TestVerifier>]) -> Option<u128> {
+    outputs.iter().try_fold(0u128, |total, output| {
+        match output.payload.extract::<Coin<ID>>() {
+            Ok(coin) => total.checked_add(coin.value()),
+            Err(_) => Some(total),
+        }
+    })
+}
+
+/// The total amount of the given token offered by the orders in the given outputs.
+fn offered_total<const ID: u8>(outputs: &[Output<TestVerifier>]) -> Option<u128> {
+    outputs.iter().try_fold(0u128, |total, output| {
+        let offered = if let Ok(order) = output.payload.extract::<Order<FuzzConfig>>() {
+            if ID == 0 {
+                order.offer_amount
+            } else {
+                0
+            }
+        } else if let Ok(order) = output.payload.extract::<Order<OppositeSide<FuzzConfig>>>() {
+            if ID == 1 {
+                order.offer_amount
+            } else {
+                0
+            }
+        } else {
+            0
+        };
+        total.checked_add(offered)
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((inputs, outputs)) =
+        <(Vec<Output<TestVerifier>>, Vec<Output<TestVerifier>>)>::decode(&mut &data[..])
+    else {
+        return;
+    };
+
+    let _ = <MakeOrder<FuzzConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    );
+
+    let accepted = <MatchOrders<FuzzConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    )
+    .is_ok();
+
+    if accepted {
+        if let (Some(paid_a), Some(offered_a)) =
+            (coin_total::<0>(&outputs), offered_total::<0>(&inputs))
+        {
+            assert!(paid_a <= offered_a);
+        }
+        if let (Some(paid_b), Some(offered_b)) =
+            (coin_total::<1>(&outputs), offered_total::<1>(&inputs))
+        {
+            assert!(paid_b <= offered_b);
+        }
+    }
+});
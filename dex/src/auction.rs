@@ -0,0 +1,128 @@
+//! Dutch auctions, which sell a lot of token A at a price that falls until someone buys it.
+//!
+//! An order at a fixed price sells only if the market comes to it. A seller who must sell,
+//! such as a liquidation, would rather find the best price that someone pays now. A
+//! [`DutchAuction`] starts at its start price and falls block by block to its floor price
+//! over its duration, then stays at the floor. Anyone may [`BuyAuction`] the whole lot at
+//! the current price, paid in plain coins to the auction's payout verifier, so the lot
+//! sells as soon as its price reaches what the best buyer will pay.
+//!
+//! Auctions are bought directly rather than matched against orders, and their terms follow
+//! from whatever opened them, so they skip the dex's lot and tick sizes and its fees.
+
+use super::*;
+use crate::price::mul_div;
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+/// A lot of token A, auctioned for token B at a falling price.
+pub struct DutchAuction<T: DexConfig> {
+    /// The amount of token A for sale
+    pub lot: u128,
+    /// The price the auction starts at
+    pub start_price: Price,
+    /// The lowest price the auction falls to
+    pub floor_price: Price,
+    /// The block in which the auction started
+    pub started_at: u32,
+    /// The number of blocks it takes the price to fall from the start to the floor
+    pub duration: u32,
+    /// The verifier that will protect the proceeds
+    pub payout_verifier: T::Verifier,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: DexConfig> UtxoData for DutchAuction<T> {
+    const TYPE_ID: [u8; 4] = book_type_id::<T>(b'd');
+}
+
+impl<T: DexConfig> DutchAuction<T> {
+    /// The price of the lot in the given block.
+    ///
+    /// It falls linearly from the start price to the floor price, rounding down, and never
+    /// below the floor.
+    pub fn price_at(&self, block_number: u32) -> Price {
+        let elapsed = block_number.saturating_sub(self.started_at);
+        if elapsed >= self.duration {
+            return self.floor_price;
+        }
+        let range = self.start_price.0.saturating_sub(self.floor_price.0);
+        let fallen =
+            mul_div(range, elapsed.into(), self.duration.into(), Rounding::Up).unwrap_or(range);
+        Price(self.start_price.0 - fallen).max(self.floor_price)
+    }
+
+    /// What the lot costs in the given block, rounded up, or `None` if it does not fit in
+    /// a `u128`.
+    pub fn ask_at(&self, block_number: u32) -> Option<u128> {
+        self.price_at(block_number)
+            .amount_b_for(self.lot, Rounding::Up)
+    }
+}
+
+/// Buy the whole lot of a Dutch auction at its current price.
+///
+/// The first input is the auction, and the rest are the buyer's coins of token B, which
+/// must be worth at least the current ask. The first output is the lot, as a single coin
+/// of token A protected by whatever verifier the buyer chooses. The second is the payout,
+/// worth exactly the ask and protected by the auction's payout verifier. The rest are the
+/// buyer's change, in token B.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct BuyAuction<T: DexConfig>(pub PhantomData<T>);
+
+impl<T: DexConfig> ConstraintChecker<T::Verifier> for BuyAuction<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!T::trading_paused(), DexError::TradingPaused);
+        ensure_distinct_tokens::<T>()?;
+        ensure!(!inputs.is_empty(), DexError::OrderMissing);
+        let auction: DutchAuction<T> = inputs[0].payload.extract()?;
+        let ask = auction
+            .ask_at(T::context().block_number)
+            .ok_or(DexError::AmountOverflow)?;
+
+        let mut total_payment = 0u128;
+        for input in &inputs[1..] {
+            let coin: T::B = input.payload.extract()?;
+            total_payment = total_payment
+                .checked_add(coin.value())
+                .ok_or(DexError::AmountOverflow)?;
+        }
+        ensure!(total_payment >= ask, DexError::AuctionUnderpaid);
+
+        // The buyer receives the lot
+        ensure!(!outputs.is_empty(), DexError::LotNotDelivered);
+        let lot: T::A = outputs[0].payload.extract()?;
+        ensure!(lot.value() == auction.lot, DexError::LotNotDelivered);
+
+        // The seller receives the payment
+        ensure!(outputs.len() >= 2, DexError::PayoutDoesNotSatisfyOrder);
+        let payout: T::B = outputs[1].payload.extract()?;
+        ensure!(payout.value() == ask, DexError::PayoutDoesNotSatisfyOrder);
+        ensure!(
+            outputs[1].verifier == auction.payout_verifier,
+            DexError::VerifierMismatchForTrade
+        );
+
+        // The buyer may take back some change
+        let mut total_change = 0u128;
+        for output in &outputs[2..] {
+            let coin: T::B = output.payload.extract()?;
+            total_change = total_change
+                .checked_add(coin.value())
+                .ok_or(DexError::AmountOverflow)?;
+        }
+        ensure!(
+            total_change <= total_payment - ask,
+            DexError::ChangeExceedsLeftoverPayment
+        );
+
+        Ok(0)
+    }
+}
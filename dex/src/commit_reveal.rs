@@ -0,0 +1,199 @@
+//! Opening orders in two phases, so that their terms are hidden until they are final.
+//!
+//! When an order is submitted in the open, the block author sees its terms before anyone
+//! else and may trade against the market ahead of it. To make that harder, a maker can
+//! first lock up collateral in an [`OrderCommitment`] with [`CommitOrder`]. The commitment
+//! only holds a salted hash of the order. In a later block, but within the configured
+//! [`DexConfig::REVEAL_WINDOW`], the maker turns it into the real [`Order`] with
+//! [`RevealOrder`], which carries the salt.
+//!
+//! A commitment that is not revealed in time can no longer become an order. Its owner
+//! may take the collateral back with [`ReclaimCommitment`].
+//!
+//! Neither committing nor revealing is allowed while trading is paused, but reclaiming is.
+
+use super::*;
+use sp_core::H256;
+
+/// The commitment to an order with the given salt.
+pub fn order_commitment<T: DexConfig>(order: &Order<T>, salt: &H256) -> H256 {
+    sp_io::hashing::blake2_256(&(order, salt).encode()).into()
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+/// Collateral locked up for an order whose terms are not revealed yet.
+pub struct OrderCommitment<T: DexConfig> {
+    /// The salted hash of the order. See [`order_commitment`].
+    pub commitment: H256,
+    /// The amount of token A locked up for the order
+    pub collateral: u128,
+    /// The number of the block in which the commitment was made
+    pub committed_at: u32,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: DexConfig> UtxoData for OrderCommitment<T> {
+    const TYPE_ID: [u8; 4] = book_type_id::<T>(b'c');
+}
+
+impl<T: DexConfig> OrderCommitment<T> {
+    /// The last block in which this commitment can be revealed.
+    pub fn reveal_deadline(&self) -> u32 {
+        self.committed_at.saturating_add(T::REVEAL_WINDOW)
+    }
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for committing to a hidden order.
+///
+/// The inputs are coins of token A worth at least the collateral, and the only output is
+/// the commitment, which must record the current block.
+pub struct CommitOrder<T: DexConfig>(pub PhantomData<T>);
+
+impl<T: DexConfig> SimpleConstraintChecker for CommitOrder<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!T::trading_paused(), DexError::TradingPaused);
+        ensure!(T::pair_listed(), DexError::PairNotListed);
+        ensure_distinct_tokens::<T>()?;
+        ensure!(!output_data.is_empty(), DexError::OrderMissing);
+        ensure!(
+            output_data.len() == 1,
+            DexError::TooManyOutputsWhenMakingOrder
+        );
+        let commitment: OrderCommitment<T> = output_data[0].extract()?;
+        ensure!(
+            commitment.committed_at == T::context().block_number,
+            DexError::CommitmentForWrongBlock
+        );
+
+        let mut total_collateral = 0u128;
+        for input in input_data {
+            let coin: T::A = input.extract()?;
+            total_collateral = total_collateral
+                .checked_add(coin.value())
+                .ok_or(DexError::AmountOverflow)?;
+        }
+        ensure!(
+            total_collateral >= commitment.collateral,
+            DexError::NotEnoughCollateralToOpenOrder
+        );
+
+        Ok(0)
+    }
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for revealing a committed order.
+///
+/// The only input is the commitment and the only output is the order. The order, together
+/// with the salt carried here, must hash to the commitment, must pass the same amount checks
+/// as any new order, and must not offer more than the collateral. The reveal must happen
+/// after the block of the commitment, and no later than its deadline.
+pub struct RevealOrder<T: DexConfig> {
+    /// The salt the order was committed to with
+    pub salt: H256,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: DexConfig> SimpleConstraintChecker for RevealOrder<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!T::trading_paused(), DexError::TradingPaused);
+        ensure_distinct_tokens::<T>()?;
+        ensure!(input_data.len() == 1, DexError::CommitmentMissing);
+        ensure!(!output_data.is_empty(), DexError::OrderMissing);
+        ensure!(
+            output_data.len() == 1,
+            DexError::TooManyOutputsWhenMakingOrder
+        );
+        let commitment: OrderCommitment<T> = input_data[0].extract()?;
+        let order: Order<T> = output_data[0].extract()?;
+
+        let block_number = T::context().block_number;
+        ensure!(
+            block_number > commitment.committed_at,
+            DexError::RevealTooEarly
+        );
+        ensure!(
+            block_number <= commitment.reveal_deadline(),
+            DexError::RevealWindowExpired
+        );
+        ensure!(
+            order_commitment(&order, &self.salt) == commitment.commitment,
+            DexError::RevealDoesNotMatchCommitment
+        );
+        order.ensure_not_zero()?;
+        order.ensure_on_grid()?;
+        order.ensure_not_dust(T::parameters().dust_threshold)?;
+        order.ensure_not_expired(block_number)?;
+        ensure!(order.bond == T::ORDER_BOND, DexError::WrongOrderBond);
+        ensure!(
+            order.made_at <= block_number,
+            DexError::OrderMadeInTheFuture
+        );
+        let locked = order
+            .offer_amount
+            .checked_add(order.bond)
+            .ok_or(DexError::AmountOverflow)?;
+        ensure!(
+            locked <= commitment.collateral,
+            DexError::NotEnoughCollateralToOpenOrder
+        );
+
+        Ok(0)
+    }
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for taking back the collateral of a commitment that was not
+/// revealed in time.
+///
+/// The only input is the expired commitment, and the outputs are coins of token A worth
+/// no more than its collateral.
+pub struct ReclaimCommitment<T: DexConfig>(pub PhantomData<T>);
+
+impl<T: DexConfig> SimpleConstraintChecker for ReclaimCommitment<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(input_data.len() == 1, DexError::CommitmentMissing);
+        let commitment: OrderCommitment<T> = input_data[0].extract()?;
+        ensure!(
+            T::context().block_number > commitment.reveal_deadline(),
+            DexError::CommitmentNotExpired
+        );
+
+        let mut total_reclaimed = 0u128;
+        for output in output_data {
+            let coin: T::A = output.extract()?;
+            total_reclaimed = total_reclaimed
+                .checked_add(coin.value())
+                .ok_or(DexError::AmountOverflow)?;
+        }
+        ensure!(
+            total_reclaimed <= commitment.collateral,
+            DexError::ReclaimExceedsCollateral
+        );
+
+        Ok(0)
+    }
+}
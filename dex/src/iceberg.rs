@@ -0,0 +1,234 @@
+//! Iceberg orders, which show only part of their size at a time.
+//!
+//! A maker who wants to trade a large amount may not want the whole order sitting in the
+//! book. An [`IcebergOrder`] exposes only a visible tranche, which is matched like an
+//! ordinary order. It also carries a commitment to the next tranche: the hash of that
+//! tranche's terms together with a salt. When the visible tranche is matched, the match
+//! must create the next tranche as an output, and its terms and salt must hash to the
+//! commitment. So the maker decides all the tranches up front, but reveals each one only
+//! when the one before it has been taken. Matchers get the next tranche's preimage from
+//! the maker.
+//!
+//! The full collateral is locked in every tranche and is visible on chain. What stays
+//! hidden is how it is split up and at what prices the later tranches trade.
+
+use super::*;
+use sp_core::H256;
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+/// One tranche of an iceberg order, offering token A in exchange for token B.
+pub struct IcebergOrder<T: DexConfig> {
+    /// The amount of token A in the visible tranche
+    pub offer_amount: u128,
+    /// The amount of token B asked for the visible tranche
+    pub ask_amount: u128,
+    /// The amount of token A backing this tranche and all the hidden ones after it
+    pub collateral: u128,
+    /// The verifier that will protect the payout coin of every tranche.
+    pub payout_verifier: T::Verifier,
+    /// The commitment to the next tranche, or `None` if this is the last one.
+    pub next_tranche: Option<H256>,
+    /// The salt that this tranche's terms were committed to with.
+    pub salt: H256,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: DexConfig> UtxoData for IcebergOrder<T> {
+    const TYPE_ID: [u8; 4] = book_type_id::<T>(b'i');
+}
+
+impl<T: DexConfig> IcebergOrder<T> {
+    /// The commitment to this tranche that the previous tranche must carry.
+    pub fn commitment(&self) -> H256 {
+        tranche_commitment(
+            self.offer_amount,
+            self.ask_amount,
+            self.next_tranche,
+            self.salt,
+        )
+    }
+
+    /// Whether the visible tranche and the collateral are consistent.
+    ///
+    /// The last tranche must offer exactly the remaining collateral, and every earlier one
+    /// must leave some for the tranches after it.
+    fn is_well_formed(&self) -> bool {
+        match self.next_tranche {
+            None => self.offer_amount == self.collateral,
+            Some(_) => self.offer_amount < self.collateral,
+        }
+    }
+}
+
+/// The commitment to a tranche with the given terms.
+///
+/// Makers use this to commit to each tranche before they open the order, starting from the
+/// last tranche and working backwards.
+pub fn tranche_commitment(
+    offer_amount: u128,
+    ask_amount: u128,
+    next_tranche: Option<H256>,
+    salt: H256,
+) -> H256 {
+    sp_io::hashing::blake2_256(&(offer_amount, ask_amount, next_tranche, salt).encode()).into()
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for opening a new iceberg order.
+///
+/// The inputs are coins of token A worth at least the order's collateral, and the only
+/// output is the first tranche.
+pub struct MakeIceberg<T: DexConfig>(pub PhantomData<T>);
+
+impl<T: DexConfig> SimpleConstraintChecker for MakeIceberg<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!T::trading_paused(), DexError::TradingPaused);
+        ensure!(T::pair_listed(), DexError::PairNotListed);
+        ensure_distinct_tokens::<T>()?;
+        ensure!(!output_data.is_empty(), DexError::OrderMissing);
+        ensure!(
+            output_data.len() == 1,
+            DexError::TooManyOutputsWhenMakingOrder
+        );
+        let order: IcebergOrder<T> = output_data[0].extract()?;
+        ensure!(order.is_well_formed(), DexError::MalformedIcebergTranche);
+
+        let mut total_collateral = 0u128;
+        for input in input_data {
+            let coin: T::A = input.extract()?;
+            total_collateral = total_collateral
+                .checked_add(coin.value())
+                .ok_or(DexError::AmountOverflow)?;
+        }
+        ensure!(
+            total_collateral >= order.collateral,
+            DexError::NotEnoughCollateralToOpenOrder
+        );
+
+        Ok(0)
+    }
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for matching the visible tranche of an iceberg order.
+///
+/// The first input is the iceberg order and the remaining inputs are ordinary orders on the
+/// opposite side of the pair. The first output pays the iceberg's maker the tranche's ask,
+/// and the following outputs pay each of the opposite orders their ask, in the same order
+/// as the inputs. They are followed by a refund of what is left of the bond of each opposite
+/// order that has some left, in the same order again. If the iceberg has another tranche, it is the final output.
+pub struct MatchIceberg<T: DexConfig>(pub PhantomData<T>);
+
+impl<T: DexConfig> ConstraintChecker<T::Verifier> for MatchIceberg<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!T::trading_paused(), DexError::TradingPaused);
+        ensure_distinct_tokens::<T>()?;
+        ensure!(!inputs.is_empty(), DexError::OrderMissing);
+        let iceberg: IcebergOrder<T> = inputs[0].payload.extract()?;
+
+        let payout_count = inputs.len();
+        ensure!(
+            outputs.len() >= payout_count,
+            DexError::OrderAndPayoutCountDiffer
+        );
+        ensure!(
+            payout_count <= T::parameters().max_orders_per_match as usize,
+            DexError::TooManyOrdersInMatch
+        );
+
+        // The iceberg's maker is paid for the visible tranche.
+        let payout: T::B = outputs[0].payload.extract()?;
+        let (index, side) = (0, Side::OffersA);
+        ensure!(
+            payout.value() == iceberg.ask_amount,
+            DexError::PayoutMismatch { index, side }
+        );
+        ensure!(
+            outputs[0].verifier == iceberg.payout_verifier,
+            DexError::PayoutVerifierMismatch { index, side }
+        );
+
+        // Each opposite order is paid out of the visible tranche.
+        let mut a_required = 0u128;
+        let mut b_so_far = 0u128;
+        let block_number = T::context().block_number;
+        let mut rest = &outputs[payout_count..];
+        let paired = inputs.iter().zip(&outputs[..payout_count]).enumerate();
+        for (index, (input, output)) in paired.skip(1) {
+            let (index, side) = (index as u32, Side::OffersB);
+            let order: Order<OppositeSide<T>> = input.payload.extract()?;
+            order.ensure_not_expired(block_number)?;
+            // Iceberg matches take no credentials, so they only fill orders that anyone
+            // may match.
+            order.ensure_matcher_authorized(&[])?;
+            b_so_far = b_so_far
+                .checked_add(order.offer_amount)
+                .ok_or(DexError::AmountOverflow)?;
+            a_required = a_required
+                .checked_add(order.ask_amount)
+                .ok_or(DexError::AmountOverflow)?;
+
+            let paid = order
+                .payout_kind
+                .amount_paid::<T::A>(&output.payload)?;
+            ensure!(
+                paid == order.ask_amount,
+                DexError::PayoutMismatch { index, side }
+            );
+            ensure!(
+                output.verifier == order.payout_verifier,
+                DexError::PayoutVerifierMismatch { index, side }
+            );
+            rest = order.take_bond_refund(rest, block_number)?;
+        }
+        let expected_rest = match iceberg.next_tranche {
+            Some(_) => 1,
+            None => 0,
+        };
+        ensure!(
+            rest.len() == expected_rest,
+            DexError::OrderAndPayoutCountDiffer
+        );
+        ensure!(
+            iceberg.offer_amount >= a_required,
+            DexError::InsufficientTokenAForMatch
+        );
+        ensure!(
+            b_so_far >= iceberg.ask_amount,
+            DexError::InsufficientTokenBForMatch
+        );
+
+        // The next tranche is revealed, and carries on with the rest of the collateral.
+        if let Some(commitment) = iceberg.next_tranche {
+            let next: IcebergOrder<T> = rest[0].payload.extract()?;
+            ensure!(
+                next.commitment() == commitment,
+                DexError::TrancheDoesNotMatchCommitment
+            );
+            ensure!(
+                iceberg.collateral.checked_sub(iceberg.offer_amount) == Some(next.collateral)
+                    && next.payout_verifier == iceberg.payout_verifier
+                    && rest[0].verifier == inputs[0].verifier,
+                DexError::TrancheDoesNotMatchCommitment
+            );
+            ensure!(next.is_well_formed(), DexError::MalformedIcebergTranche);
+        }
+
+        Ok(0)
+    }
+}
@@ -1,14 +1,46 @@
 //! An Order Book Decentralized Exchange.
-//! 
+//!
 //! Allows users to place trade orders offering a certain amount of
 //! one token asking a certain amount of another token in exchange.
-//! 
+//!
 //! Also allows matching sets of compatible orders together.
 //! Orders can be matched as long as every ask is fulfilled.
-//! 
+//!
 //! This piece is instantiable and parameterized in two tokens.
 //! If you want multiple trading pairs, then you will need multiple
 //! instances of this piece.
+//!
+//! Non-fungible items can also be listed for a fixed price in a fungible
+//! token. See the [`nft`] module.
+//!
+//! Orders between two tokens without direct liquidity can be filled through a
+//! third token. See the [`route`] module.
+//!
+//! Large orders can be split into tranches that are revealed one at a time.
+//! See the [`iceberg`] module.
+//!
+//! Orders can be committed to before their terms are revealed, which makes them
+//! harder to front-run. See the [`commit_reveal`] module.
+//!
+//! Prices are fixed-point numbers with explicit rounding. See the [`price`] module.
+//!
+//! Matches can pay a fee that is rebated to makers. See the [`rebate`] module.
+//!
+//! An admin can pause trading on a pair during an incident. While it is paused,
+//! orders can be cancelled but not made or matched. See [`TradingStatus`].
+//!
+//! Orders can ask to be paid in coins that unlock over a schedule, such as for token
+//! sales with lockups. See [`PayoutKind`].
+//!
+//! Operators can curate which pairs are open for trading while the chain is running.
+//! See the [`listing`] module.
+//!
+//! Dexes can require each order to lock a bond, which is forfeited if the order is left
+//! to expire, to discourage spamming the book. Resting orders can pay rent out of their
+//! bond, and be swept off the book once it runs out. See the [`rent`] module.
+//!
+//! Orders can expire. Their encoding is versioned, so that orders made before a runtime
+//! upgrade stay valid after it. See the [`versioning`] module.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -16,24 +48,1414 @@ use parity_scale_codec::{Decode, Encode};
 use scale_info::TypeInfo;
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
+use sp_core::H256;
 use sp_runtime::transaction_validity::TransactionPriority;
-use sp_std::prelude::*;
+use sp_std::{marker::PhantomData, prelude::*};
 use tuxedo_core::{
     Verifier,
     dynamic_typing::{DynamicallyTypedData, DynamicTypingError, UtxoData},
     ensure,
     traits::Cash,
+    types::Output,
+    ConstraintChecker,
     SimpleConstraintChecker,
     support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
 };
+use vesting::{Vested, VestingSchedule};
 
-// TODO Order type
+pub mod nft;
+pub use nft::{BuyNft, ListNft, NftDexConfig, NftOrder};
 
+pub mod route;
+pub use route::{RouteConfig, RouteMatch};
 
-// TODO Error Type
+pub mod iceberg;
+pub use iceberg::{IcebergOrder, MakeIceberg, MatchIceberg};
 
+pub mod commit_reveal;
+pub use commit_reveal::{CommitOrder, OrderCommitment, ReclaimCommitment, RevealOrder};
 
-// TODO MakeOrder SimpleConstraintChecker
+pub mod price;
+pub use price::{Price, Rounding};
 
+pub mod rebate;
+pub use rebate::{ClaimRebate, MakerCredit, RebatePool};
 
-// TODO MatchOrder ConstraintChecker
+pub mod listing;
+pub use listing::{ListPair, PairRegistry};
+
+pub mod versioning;
+pub use versioning::Versioned;
+
+pub mod rent;
+pub use rent::SweepExpired;
+
+pub mod auction;
+pub use auction::{BuyAuction, DutchAuction};
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+#[cfg(test)]
+mod tests;
+
+/// The state of the chain at the time a transaction is being checked.
+///
+/// Constraint checkers cannot read this themselves, so the runtime supplies it
+/// through the dex configuration.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, Default, TypeInfo)]
+pub struct ConstraintCheckingContext {
+    /// The number of the block the transaction is being checked in
+    pub block_number: u32,
+    /// The time of the most recent block, in milliseconds since the unix epoch
+    pub timestamp: u64,
+}
+
+/// The dex parameters that may change while the chain is running.
+///
+/// Runtimes that let governance tune the dex store these in a UTXO and hand the current
+/// values to the checkers through [`DexConfig::parameters`].
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, Default, TypeInfo)]
+pub struct DexParameters {
+    /// The fee charged on matches, in millionths. See [`DexConfig::TAKER_FEE_PPM`].
+    pub taker_fee_ppm: u32,
+    /// The smallest amount an order may offer or ask for. Smaller orders are dust.
+    pub dust_threshold: u128,
+    /// The most orders a single match may consume. See [`DexConfig::MAX_ORDERS_PER_MATCH`].
+    pub max_orders_per_match: u32,
+}
+
+impl UtxoData for DexParameters {
+    const TYPE_ID: [u8; 4] = *b"$prm";
+}
+
+impl DexParameters {
+    /// The parameters given by the compile-time constants of a dex configuration,
+    /// with no dust threshold.
+    pub fn defaults<T: DexConfig>() -> Self {
+        Self {
+            taker_fee_ppm: T::TAKER_FEE_PPM,
+            dust_threshold: 0,
+            max_orders_per_match: T::MAX_ORDERS_PER_MATCH.try_into().unwrap_or(u32::MAX),
+        }
+    }
+}
+
+/// Whether trading on a pair is currently allowed.
+///
+/// Runtimes keep one of these per pair in a UTXO protected by an admin's verifier, and
+/// record the current status in storage whenever the admin updates it, typically with the
+/// governance piece. The checkers learn the status through [`DexConfig::trading_paused`].
+/// While trading is paused, no orders can be made or matched, but makers can still take
+/// their collateral back with [`CancelOrder`].
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct TradingStatus<T: DexConfig> {
+    /// Whether trading is paused
+    pub paused: bool,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: DexConfig> UtxoData for TradingStatus<T> {
+    const TYPE_ID: [u8; 4] = book_type_id::<T>(b's');
+}
+
+/// A Configuration for a Decentralized Exchange.
+pub trait DexConfig {
+    /// The type of verifiers that can be used in dex payouts.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The first token in the Dex's pair
+    ///
+    /// The tokens are usually coin types that are fixed at compile time. A pair can also
+    /// be chosen in the genesis state, with token types whose decoding only accepts coins
+    /// of the asset that the runtime registered for them, such as `money::Registered`.
+    type A: Cash + UtxoData;
+    /// The second token in the Dex's pair
+    type B: Cash + UtxoData;
+
+    /// The most orders that a single `MatchOrders` transaction may match.
+    ///
+    /// Checking a match takes time linear in the number of orders, so this keeps
+    /// any one transaction from taking too long to check. Governance may change it,
+    /// see [`DexConfig::parameters`].
+    const MAX_ORDERS_PER_MATCH: usize = 100;
+
+    /// How many blocks after committing to an order the maker has to reveal it.
+    /// See the [`commit_reveal`] module.
+    const REVEAL_WINDOW: u32 = 10;
+
+    /// The lot size. Orders may only offer or ask for amounts of token A that are
+    /// multiples of it.
+    ///
+    /// It must not be zero, or no orders can be made at all.
+    const LOT_SIZE: u128 = 1;
+
+    /// The tick size. Orders may only offer or ask for amounts of token B that are
+    /// multiples of it.
+    ///
+    /// It must not be zero, or no orders can be made at all.
+    const TICK_SIZE: u128 = 1;
+
+    /// The fee charged on matches, in millionths of each token paid out to the orders.
+    /// It all goes to the makers. See the [`rebate`] module.
+    ///
+    /// Zero disables fees, and matches then do not touch the rebate pool. Governance may
+    /// change it, see [`DexConfig::parameters`].
+    const TAKER_FEE_PPM: u32 = 0;
+
+    /// How many blocks each rebate epoch lasts.
+    const REBATE_EPOCH_LENGTH: u32 = 100;
+
+    /// The share of the taker fee, in millionths of the fee, that the matcher pays the
+    /// referrer of a match out of its surplus, on top of the fee. See [`MatchOrders::referrer`].
+    ///
+    /// Zero disables referrals, and matches that name a referrer pay it nothing.
+    const REFERRAL_SHARE_PPM: u32 = 0;
+
+    /// Whether orders may ask for nothing in exchange, giving their offer away to whoever
+    /// matches them. This allows donations and airdrops through the book.
+    ///
+    /// Orders that offer nothing are always rejected.
+    const ALLOW_GIVEAWAYS: bool = false;
+
+    /// The bond, in token A, that each order must lock on top of its offer. It is refunded
+    /// to the maker when the order is matched or cancelled, but forfeited if the order is
+    /// left to expire, which makes spamming the book with orders nobody fills costly.
+    ///
+    /// Zero disables bonds.
+    const ORDER_BOND: u128 = 0;
+
+    /// The rent, in token A, that a resting order pays out of its bond for each block since
+    /// it was made. Orders whose bond has run out can be swept. See the [`rent`] module.
+    ///
+    /// Zero disables rent. Rent only applies to orders with a bond.
+    const RENT_PER_BLOCK: u128 = 0;
+
+    /// The share of a swept order's bond, in millionths, that the sweeper may take as a
+    /// reward. The rest of the bond is burned. See [`SweepExpired`].
+    const SWEEPER_REWARD_PPM: u32 = 0;
+
+    /// Tells apart the books of configurations that trade the same pair, for example one
+    /// with taker fees and one without. It goes into the type id of every order and other
+    /// data of the book, see [`book_type_id`], so the checkers of one book never mistake the
+    /// data of another for their own.
+    ///
+    /// Each configuration that a runtime installs over the same pair needs an instance of
+    /// its own. The default, zero, leaves the type ids unsalted.
+    const INSTANCE: u8 = 0;
+
+    /// The chain context in which transactions are currently being checked.
+    ///
+    /// The default implementation knows nothing about the chain and always reports
+    /// block zero at time zero.
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext::default()
+    }
+
+    /// The parameters that apply to transactions being checked now.
+    ///
+    /// The default implementation always returns [`DexParameters::defaults`], so the
+    /// compile-time constants apply. Runtimes with governance return the current
+    /// parameters instead. Matches need the open rebate pool once the taker fee is not
+    /// zero, so a runtime whose governance may turn fees on must have one from genesis.
+    fn parameters() -> DexParameters
+    where
+        Self: Sized,
+    {
+        DexParameters::defaults::<Self>()
+    }
+
+    /// Whether trading on this pair is paused. See [`TradingStatus`].
+    ///
+    /// The default implementation never pauses trading.
+    fn trading_paused() -> bool {
+        false
+    }
+
+    /// Whether this pair is listed for trading. See the [`listing`] module.
+    ///
+    /// The default implementation lists every pair, so markets are not curated.
+    fn pair_listed() -> bool {
+        true
+    }
+
+    /// Whether coins protected by the given verifier may back an order that pays out to
+    /// the given payout verifier. See [`MakeOrder`].
+    ///
+    /// Cancelling refunds an order's collateral to its payout verifier, so this keeps the
+    /// coins that anyone can spend, like those of a shared multisig, from backing an order
+    /// that pays out to just one of their spenders. The default implementation only lets
+    /// coins back orders that pay out to the very verifier that protects them. Runtimes
+    /// whose owners have several verifiers recognize all of them, and configurations that
+    /// do not need the binding opt out by always returning `true`.
+    fn owns_collateral(coin_verifier: &Self::Verifier, payout_verifier: &Self::Verifier) -> bool {
+        coin_verifier == payout_verifier
+    }
+
+    /// Whether both tokens of the pair are currently one and the same, which would make
+    /// every trade on it degenerate.
+    ///
+    /// Configurations whose tokens share a `Cash` id already fail to compile, so the
+    /// default implementation returns `false`. Runtimes that choose their tokens at run
+    /// time, like registered assets, check here that the choice is sound.
+    fn same_token() -> bool {
+        false
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// This type represents a configuration that has the tokens swapped from
+/// some original configuration.
+///
+/// When opening orders, we want to allow orders for both sides of the trade.
+/// Similarly, when matching orders we have to be sure that the matched orders are on
+/// opposite sides of the same trading pair. This type allows us to conveniently
+/// express "same pair, but opposite side".
+pub struct OppositeSide<T: DexConfig>(PhantomData<T>);
+
+impl<T: DexConfig> DexConfig for OppositeSide<T> {
+    type Verifier = T::Verifier;
+    type A = T::B;
+    type B = T::A;
+
+    const MAX_ORDERS_PER_MATCH: usize = T::MAX_ORDERS_PER_MATCH;
+    const REVEAL_WINDOW: u32 = T::REVEAL_WINDOW;
+    // The tokens are swapped, so their sizes are too.
+    const LOT_SIZE: u128 = T::TICK_SIZE;
+    const TICK_SIZE: u128 = T::LOT_SIZE;
+    const TAKER_FEE_PPM: u32 = T::TAKER_FEE_PPM;
+    const REBATE_EPOCH_LENGTH: u32 = T::REBATE_EPOCH_LENGTH;
+    const REFERRAL_SHARE_PPM: u32 = T::REFERRAL_SHARE_PPM;
+    const ALLOW_GIVEAWAYS: bool = T::ALLOW_GIVEAWAYS;
+    // Orders on both sides lock the same bond, each in the token it offers.
+    const ORDER_BOND: u128 = T::ORDER_BOND;
+    const RENT_PER_BLOCK: u128 = T::RENT_PER_BLOCK;
+    const SWEEPER_REWARD_PPM: u32 = T::SWEEPER_REWARD_PPM;
+    // Both sides of the pair belong to the same book.
+    const INSTANCE: u8 = T::INSTANCE;
+
+    fn context() -> ConstraintCheckingContext {
+        T::context()
+    }
+
+    fn parameters() -> DexParameters {
+        T::parameters()
+    }
+
+    fn trading_paused() -> bool {
+        T::trading_paused()
+    }
+
+    fn pair_listed() -> bool {
+        T::pair_listed()
+    }
+
+    fn owns_collateral(coin_verifier: &Self::Verifier, payout_verifier: &Self::Verifier) -> bool {
+        T::owns_collateral(coin_verifier, payout_verifier)
+    }
+
+    fn same_token() -> bool {
+        T::same_token()
+    }
+}
+
+/// The type id of the data of the given kind that belongs to the book of a configuration.
+///
+/// The kind byte tells apart orders, trading statuses and the other data of the book. The
+/// first byte is salted with [`DexConfig::INSTANCE`], and the last two are the `Cash` ids
+/// of the pair's tokens.
+pub const fn book_type_id<T: DexConfig>(kind: u8) -> [u8; 4] {
+    [b'$'.wrapping_add(T::INSTANCE), kind, T::A::ID, T::B::ID]
+}
+
+/// Fails to evaluate, and so to compile, for configurations that trade a token for itself.
+struct DistinctTokens<T: DexConfig>(PhantomData<T>);
+
+impl<T: DexConfig> DistinctTokens<T> {
+    const ASSERT: () = assert!(
+        <T::A as Cash>::ID != <T::B as Cash>::ID,
+        "A dex cannot trade a token for itself"
+    );
+}
+
+/// Ensure that the pair trades two different tokens.
+///
+/// Using this with a configuration whose tokens share a `Cash` id is a compile error, and
+/// [`DexConfig::same_token`] catches tokens that are only chosen at run time.
+fn ensure_distinct_tokens<T: DexConfig>() -> Result<(), DexError> {
+    let () = DistinctTokens::<T>::ASSERT;
+    ensure!(!T::same_token(), DexError::SameToken);
+    Ok(())
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, TypeInfo)]
+/// An order in the order book represents a binding collateralized
+/// offer to make a trade.
+///
+/// The user who opens this order must put up a corresponding amount of
+/// token A. This order can be matched with other orders so long as
+/// the ask amount of token B may be paid to this user.
+///
+/// When a match is made, the payment token will be protected with the
+/// verifier contained in this order, in the form given by its payout kind.
+///
+/// Orders are encoded with a leading version byte, so that they can gain fields in
+/// runtime upgrades. See the [`versioning`] module.
+pub struct Order<T: DexConfig> {
+    /// The amount of token A in this order
+    pub offer_amount: u128,
+    /// The amount of token B in this order
+    pub ask_amount: u128,
+    /// The verifier that will protect the payout coin
+    /// in the event of a successful match.
+    pub payout_verifier: T::Verifier,
+    /// The form in which the order is paid out.
+    pub payout_kind: PayoutKind,
+    /// An identifier of the account that opened this order, such as its public key.
+    ///
+    /// The dex itself does not interpret it. Runtimes may use it to limit how many
+    /// orders each account can have open at once.
+    pub owner: H256,
+    /// The last block in which this order may be made or matched. After it, the order
+    /// can only be cancelled. Orders without an expiry stay open until they are matched
+    /// or cancelled.
+    pub expiry: Option<u32>,
+    /// The verifier that must approve cancelling this order, when that should be someone
+    /// other than whoever can spend the order, such as a custodian's operator.
+    ///
+    /// Orders are usually left up for grabs so that anyone can match them, so the order's
+    /// own verifier cannot restrict who cancels it. Cancelling an order with a cancel
+    /// verifier must therefore spend a second UTXO that is protected by it. Either way, the
+    /// refund goes to the payout verifier.
+    pub cancel_verifier: Option<T::Verifier>,
+    /// The verifier that must approve matching this order, so that only trusted matchers
+    /// can fill it, as in private OTC flows. A set of allowed relayers can be expressed
+    /// with a verifier that any of their keys satisfies.
+    ///
+    /// Like cancelling, this cannot be enforced by the order's own verifier. A
+    /// [`MatchOrders`] that fills an order with a matcher verifier must also spend a
+    /// UTXO that is protected by it, after the orders. The other matching checkers do
+    /// not take such inputs, so they never fill these orders.
+    pub matcher_verifier: Option<T::Verifier>,
+    /// The amount of token A locked with the order on top of its offer. See
+    /// [`DexConfig::ORDER_BOND`].
+    ///
+    /// Whatever matches the order refunds what is left of the bond, after rent, to the
+    /// payout verifier right after the payouts, and cancelling it refunds that along with
+    /// the offer. Cancelling an order that has expired only refunds the offer.
+    pub bond: u128,
+    /// The block in which the order was made, from which it pays rent. It may be earlier
+    /// than the block the order lands in, which only makes it pay more. See the [`rent`]
+    /// module.
+    pub made_at: u32,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: DexConfig> UtxoData for Order<T> {
+    const TYPE_ID: [u8; 4] = book_type_id::<T>(b'$');
+}
+
+impl<T: DexConfig> Order<T> {
+    /// An order offering the given amount of token A for the given amount of token B, paid
+    /// out in coins to the given verifier.
+    ///
+    /// It has no owner, expiry, cancel or matcher verifier, or bond, and was made at genesis.
+    /// Orders that need any of those can set them with struct update syntax.
+    pub fn new(offer_amount: u128, ask_amount: u128, payout_verifier: T::Verifier) -> Self {
+        Self {
+            offer_amount,
+            ask_amount,
+            payout_verifier,
+            payout_kind: PayoutKind::Coin,
+            owner: Default::default(),
+            expiry: None,
+            cancel_verifier: None,
+            matcher_verifier: None,
+            bond: 0,
+            made_at: 0,
+            _ph_data: PhantomData,
+        }
+    }
+
+    /// Make sure the order has not expired by the given block.
+    pub fn ensure_not_expired(&self, block_number: u32) -> Result<(), DexError> {
+        ensure!(
+            self.expiry.map_or(true, |expiry| block_number <= expiry),
+            DexError::OrderExpired
+        );
+        Ok(())
+    }
+
+    /// Make sure the match may fill this order, given the inputs it spends besides the
+    /// orders. Orders with a matcher verifier need one of them to be protected by it.
+    pub fn ensure_matcher_authorized(
+        &self,
+        credentials: &[Output<T::Verifier>],
+    ) -> Result<(), DexError> {
+        ensure!(
+            self.matcher_verifier.as_ref().map_or(true, |matcher| {
+                credentials
+                    .iter()
+                    .any(|credential| credential.verifier == *matcher)
+            }),
+            DexError::MatcherNotAuthorized
+        );
+        Ok(())
+    }
+
+    /// The rent that this order owes for the blocks from when it was made until the given
+    /// one. It never exceeds the bond it is paid out of.
+    pub fn rent_due(&self, block_number: u32) -> u128 {
+        let blocks = block_number.saturating_sub(self.made_at);
+        T::RENT_PER_BLOCK
+            .saturating_mul(blocks.into())
+            .min(self.bond)
+    }
+
+    /// What is left of this order's bond in the given block, after rent.
+    pub fn bond_left(&self, block_number: u32) -> u128 {
+        self.bond - self.rent_due(block_number)
+    }
+
+    /// Whether the order may be swept off the book in the given block, because it has
+    /// expired or because rent has used up its bond. See [`SweepExpired`].
+    pub fn is_evictable(&self, block_number: u32) -> bool {
+        self.ensure_not_expired(block_number).is_err()
+            || (self.bond > 0 && self.bond_left(block_number) == 0)
+    }
+
+    /// Make sure the given outputs start with the refund of what is left of this order's
+    /// bond in the given block, if anything, and return the outputs after it.
+    ///
+    /// The refund is a coin of token A worth exactly what is left, protected by the order's
+    /// payout verifier.
+    pub fn take_bond_refund<'a>(
+        &self,
+        outputs: &'a [Output<T::Verifier>],
+        block_number: u32,
+    ) -> Result<&'a [Output<T::Verifier>], DexError> {
+        let left = self.bond_left(block_number);
+        if left == 0 {
+            return Ok(outputs);
+        }
+        let (refund, rest) = outputs.split_first().ok_or(DexError::BondNotRefunded)?;
+        let refunded = refund
+            .payload
+            .extract::<T::A>()
+            .map_or(false, |coin| coin.value() == left);
+        ensure!(
+            refunded && refund.verifier == self.payout_verifier,
+            DexError::BondNotRefunded
+        );
+        Ok(rest)
+    }
+
+    /// Make sure the order offers something, and asks for something unless the dex
+    /// allows giveaways.
+    ///
+    /// Orders for nothing pass every other check trivially, and would only add noise to
+    /// the matching totals.
+    pub fn ensure_not_zero(&self) -> Result<(), DexError> {
+        ensure!(
+            self.offer_amount > 0 && (self.ask_amount > 0 || T::ALLOW_GIVEAWAYS),
+            DexError::ZeroAmountOrder
+        );
+        Ok(())
+    }
+
+    /// Make sure the order's amounts are multiples of the configured lot and tick sizes.
+    pub fn ensure_on_grid(&self) -> Result<(), DexError> {
+        ensure!(
+            self.offer_amount.checked_rem(T::LOT_SIZE) == Some(0),
+            DexError::OfferAmountOffGrid
+        );
+        ensure!(
+            self.ask_amount.checked_rem(T::TICK_SIZE) == Some(0),
+            DexError::AskAmountOffGrid
+        );
+        Ok(())
+    }
+
+    /// Make sure the order neither offers nor asks for less than the dust threshold.
+    ///
+    /// Giveaways ask for nothing on purpose, so only their offer is held to the threshold.
+    pub fn ensure_not_dust(&self, dust_threshold: u128) -> Result<(), DexError> {
+        let giveaway = self.ask_amount == 0 && T::ALLOW_GIVEAWAYS;
+        ensure!(
+            self.offer_amount >= dust_threshold && (self.ask_amount >= dust_threshold || giveaway),
+            DexError::OrderBelowDustThreshold
+        );
+        Ok(())
+    }
+}
+
+/// The form in which an order is paid out.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, Default, TypeInfo)]
+pub enum PayoutKind {
+    /// Plain coins of the asked token
+    #[default]
+    Coin,
+    /// Coins of the asked token that are locked, and unlock over the given schedule. The
+    /// payout is a [`vesting::Vested`] UTXO of which nothing has been released yet.
+    Vested(VestingSchedule),
+}
+
+impl PayoutKind {
+    /// The amount of token `C` that a payout of this kind pays.
+    ///
+    /// Fails if the payout is not of this kind.
+    pub fn amount_paid<C: Cash + UtxoData>(
+        &self,
+        payout: &DynamicallyTypedData,
+    ) -> Result<u128, DexError> {
+        match self {
+            Self::Coin => Ok(payout.extract::<C>()?.value()),
+            Self::Vested(schedule) => {
+                let vested: Vested<C> = payout.extract()?;
+                ensure!(
+                    vested.schedule == *schedule && vested.released == 0,
+                    DexError::PayoutKindMismatch
+                );
+                Ok(vested.total)
+            }
+        }
+    }
+}
+
+/// Which side of the pair an order is on.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, TypeInfo)]
+pub enum Side {
+    /// The order offers token A in exchange for token B
+    OffersA,
+    /// The order offers token B in exchange for token A
+    OffersB,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+/// All the things that can go wrong while checking constraints on dex transactions
+pub enum DexError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// No outputs were supplied when making an order.
+    /// When making an order, exactly one output should be supplied, which is the order.
+    OrderMissing,
+    /// More than one output was supplied when making an order.
+    /// When making an order, exactly one output should be supplied, which is the order.
+    TooManyOutputsWhenMakingOrder,
+    /// The coins provided do not have enough combined value to back the order that you attempted to open.
+    NotEnoughCollateralToOpenOrder,
+    /// This transaction has a different number of input orders than output payouts.
+    /// When matching orders, every order must be paid, and only the outputs the match
+    /// allows may follow the payouts.
+    OrderAndPayoutCountDiffer,
+    /// This transaction tries to fill an order but provides an incorrect payout.
+    /// Only checkers that fill a single order, like buying an NFT or an auction, report
+    /// this. Matches and routes report [`DexError::PayoutMismatch`] instead.
+    PayoutDoesNotSatisfyOrder,
+    /// The payout for the order at the given input index, on the given side, is not for
+    /// the amount it asks. When orders share a payout, the index is the first of them.
+    PayoutMismatch { index: u32, side: Side },
+    /// The amount of token A supplied by the orders is not enough to match with the demand.
+    InsufficientTokenAForMatch,
+    /// The amount of token B supplied by the orders is not enough to match with the demand.
+    InsufficientTokenBForMatch,
+    /// The payout is not locked under the schedule that the order asks for, or has
+    /// released some of its coins already.
+    PayoutKindMismatch,
+    /// The verifier who is receiving the tokens is not correct one that was specified in the original order.
+    /// Only checkers that pay a single order or maker, like buying an NFT or an auction, or
+    /// cancelling an order, report this. Matches and routes report
+    /// [`DexError::PayoutVerifierMismatch`] instead.
+    VerifierMismatchForTrade,
+    /// The payout for the order at the given input index, on the given side, is not
+    /// protected by the order's payout verifier.
+    PayoutVerifierMismatch { index: u32, side: Side },
+    /// The coins taken from the surplus of a match, together with its payouts and fees,
+    /// are worth more than the orders offer.
+    SurplusOutputsExceedSurplus,
+    /// This transaction tries to match more orders than the dex configuration allows.
+    TooManyOrdersInMatch,
+    /// The order offers an amount that is not a multiple of the offered token's lot or tick size.
+    OfferAmountOffGrid,
+    /// The order asks for an amount that is not a multiple of the asked token's lot or tick size.
+    AskAmountOffGrid,
+    /// The order offers or asks for less than the current dust threshold.
+    OrderBelowDustThreshold,
+    /// The order offers nothing, or asks for nothing while giveaways are not allowed.
+    ZeroAmountOrder,
+    /// The order's expiry has passed, so it can only be cancelled.
+    OrderExpired,
+    /// A match with fees enabled must spend the open rebate pool as its last input, and a
+    /// rebate claim must spend a closed pool as its first input and recreate it as its first output.
+    RebatePoolMissing,
+    /// Fees can only be paid into the open rebate pool.
+    RebatePoolClosed,
+    /// Rebates can only be claimed once their epoch's pool is closed.
+    RebatePoolNotClosed,
+    /// The rebate pool or maker credit belongs to a different epoch than expected.
+    WrongRebateEpoch,
+    /// The rebate pool was not updated by exactly the fees and volume of the transaction.
+    RebatePoolMismatch,
+    /// A maker credit does not record the epoch and volume of its order, is not
+    /// protected by the order's payout verifier, or claims more volume than the pool holds.
+    MakerCreditMismatch,
+    /// The surplus of the match does not cover its fee.
+    InsufficientSurplusForFee,
+    /// The coins paid out by a rebate claim are worth more than the claimed share of the fees.
+    ClaimExceedsRebate,
+    /// Trading on this pair is paused, so orders can only be cancelled.
+    TradingPaused,
+    /// Orders can only be made for pairs that are listed in the pair registry.
+    PairNotListed,
+    /// Listing a pair must consume exactly one input, which is the current pair registry,
+    /// and create exactly one output, which is the new one.
+    PairRegistryMissing,
+    /// The new pair registry must stay under the control of the same verifier and keep the
+    /// existing pairs, adding exactly one after them.
+    PairRegistryChanged,
+    /// The pair is listed already, or trades a token against itself.
+    PairAlreadyListed,
+    /// Cancelling must consume exactly one order.
+    CancelRequiresOneOrder,
+    /// The coins returned by cancelling an order are worth more than the order offered.
+    RefundExceedsOrder,
+    /// Adding up the amounts in this transaction overflowed.
+    AmountOverflow,
+    /// A route match must consume exactly three orders: the routed order and one maker per hop.
+    WrongNumberOfOrdersInRoute,
+    /// The first hop of a route does not supply enough of the intermediate token for the second hop.
+    InsufficientIntermediateForRoute,
+    /// An iceberg tranche offers more than its collateral, or the last tranche leaves some collateral unoffered.
+    MalformedIcebergTranche,
+    /// The next iceberg tranche does not match the commitment in the tranche that was matched,
+    /// or does not carry on with the rest of its collateral.
+    TrancheDoesNotMatchCommitment,
+    /// The order commitment must be the only input when revealing or reclaiming it.
+    CommitmentMissing,
+    /// An order commitment must record the block in which it is made.
+    CommitmentForWrongBlock,
+    /// An order cannot be revealed in the same block it was committed to.
+    RevealTooEarly,
+    /// The order was not revealed before its commitment's deadline.
+    RevealWindowExpired,
+    /// The revealed order and salt do not hash to the commitment.
+    RevealDoesNotMatchCommitment,
+    /// The collateral of a commitment can only be reclaimed after its reveal deadline.
+    CommitmentNotExpired,
+    /// The coins reclaimed from an expired commitment are worth more than its collateral.
+    ReclaimExceedsCollateral,
+    /// No item was supplied when listing an nft.
+    /// When listing an nft, exactly one input should be supplied, which is the item.
+    NftMissing,
+    /// More than one input was supplied when listing an nft.
+    TooManyInputsWhenListingNft,
+    /// The listing does not refer to the exact item that was supplied as the input.
+    ListingDoesNotMatchNft,
+    /// A purchase did not deliver the listed item as its first output.
+    NftNotDelivered,
+    /// The coins provided do not have enough combined value to pay for the listed item.
+    NotEnoughPaymentForNft,
+    /// The change returned to the buyer is worth more than what they had left over.
+    ChangeExceedsLeftoverPayment,
+    /// The order has a cancel verifier, and cancelling it does not spend a second input
+    /// protected by that verifier.
+    CancelNotAuthorized,
+    /// The order has a matcher verifier, and the match does not spend an input protected
+    /// by that verifier, or is not one that can.
+    MatcherNotAuthorized,
+    /// The order does not lock exactly the bond that the dex requires.
+    WrongOrderBond,
+    /// A match does not refund the bond of some order that it fills to the order's payout
+    /// verifier, right after the payouts.
+    BondNotRefunded,
+    /// The order claims to be made in a later block than the one it is being made in,
+    /// which would let it skip rent.
+    OrderMadeInTheFuture,
+    /// The swept order has not expired, and its rent has not used up its bond.
+    OrderNotEvictable,
+    /// A sweep does not refund some order's offer to its payout verifier.
+    OfferNotRefunded,
+    /// The sweeper's reward is more than its share of the swept orders' bonds.
+    SweepRewardTooLarge,
+    /// Some coin spent to make the order is protected by a verifier that does not belong
+    /// to the order's payout verifier. See [`DexConfig::owns_collateral`].
+    CollateralNotOwned,
+    /// Both tokens of the pair are the same token. See [`DexConfig::same_token`].
+    SameToken,
+    /// The coins spent to buy an auction are worth less than its current ask.
+    AuctionUnderpaid,
+    /// The first output of buying an auction must be a single coin holding its whole lot.
+    LotNotDelivered,
+    /// A match that names a referrer does not pay it its share of the fee in each token,
+    /// right after the rebates.
+    ReferralNotPaid,
+}
+
+impl From<DynamicTypingError> for DexError {
+    fn from(_value: DynamicTypingError) -> Self {
+        DexError::TypeError
+    }
+}
+
+/// The messages describing each [`DexError`], indexed by [`DexError::code`].
+const DEX_ERROR_MESSAGES: [&str; 65] = [
+    "Some data is not of the type the dex expects",
+    "Making an order requires the order as an output",
+    "Making an order must create exactly one output, which is the order",
+    "The coins spent are worth less than the order offers",
+    "The match does not pay every order",
+    "A payout is not for the amount its order asks",
+    "A payout is not for the amount its orders ask",
+    "The orders do not offer enough of token A for the match",
+    "The orders do not offer enough of token B for the match",
+    "A payout is not in the form its order asks for",
+    "A payout is not protected by its order's payout verifier",
+    "A payout is not protected by its orders' payout verifier",
+    "The outputs taken from the surplus are worth more than the surplus",
+    "The match consumes more orders than the dex allows",
+    "The order offers an amount that is off the lot or tick size",
+    "The order asks for an amount that is off the lot or tick size",
+    "The order is below the dust threshold",
+    "The order offers or asks for nothing",
+    "The order has expired",
+    "The transaction does not spend the rebate pool where it should",
+    "Fees can only be paid into the open rebate pool",
+    "Rebates can only be claimed from a closed pool",
+    "The rebate pool or maker credit is for the wrong epoch",
+    "The rebate pool was not updated by exactly the match's fees and volume",
+    "A maker credit does not match its order",
+    "The surplus of the match does not cover its fee",
+    "The rebate claimed is more than the maker's share of the fees",
+    "Trading on this pair is paused",
+    "This pair is not listed for trading",
+    "Listing a pair must replace the pair registry",
+    "The new pair registry must keep its verifier and existing pairs, and add one",
+    "The pair is already listed, or trades a token against itself",
+    "Cancelling must consume exactly one order",
+    "The refund is worth more than the order offered",
+    "An amount in the transaction overflowed",
+    "A route match must consume exactly three orders",
+    "The first hop does not supply enough for the second",
+    "The iceberg tranche does not fit its collateral",
+    "The next iceberg tranche does not match its commitment",
+    "The order commitment must be the only input",
+    "The order commitment is not for the current block",
+    "The order cannot be revealed in the block it was committed in",
+    "The order commitment's reveal window has passed",
+    "The revealed order does not match its commitment",
+    "The commitment's collateral cannot be reclaimed before its deadline",
+    "The reclaimed coins are worth more than the commitment's collateral",
+    "Listing an item requires the item as an input",
+    "Listing an item must consume exactly one input, which is the item",
+    "The listing does not match the item",
+    "The purchase does not deliver the listed item",
+    "The payment is worth less than the listing's price",
+    "The change is worth more than what is left of the payment",
+    "Cancelling the order needs an input protected by its cancel verifier",
+    "Matching the order needs an input protected by its matcher verifier",
+    "The order's bond is not the one the dex requires",
+    "The match does not refund the bond of an order it fills",
+    "The order claims to be made in a later block than it is",
+    "The order can only be swept once it has expired or its bond has run out",
+    "The sweep does not refund an order's offer",
+    "The sweeper's reward is more than its share of the bonds",
+    "The coins spent do not belong to whoever the order pays out to",
+    "The dex is misconfigured to trade a token for itself",
+    "The coins spent are worth less than the auction currently asks",
+    "The first output must be a coin holding the auction's whole lot",
+    "The match does not pay its referrer's share of the fee",
+];
+
+impl DexError {
+    /// A number identifying the kind of error, which runtimes can report in a custom
+    /// `InvalidTransaction` so that clients can tell why a transaction was rejected.
+    ///
+    /// The codes are assigned explicitly rather than following the order of the variants,
+    /// so they stay the same as variants are added. An existing code must never change,
+    /// and new variants take the next free one.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::TypeError => 0,
+            Self::OrderMissing => 1,
+            Self::TooManyOutputsWhenMakingOrder => 2,
+            Self::NotEnoughCollateralToOpenOrder => 3,
+            Self::OrderAndPayoutCountDiffer => 4,
+            Self::PayoutDoesNotSatisfyOrder => 5,
+            Self::PayoutMismatch { .. } => 6,
+            Self::InsufficientTokenAForMatch => 7,
+            Self::InsufficientTokenBForMatch => 8,
+            Self::PayoutKindMismatch => 9,
+            Self::VerifierMismatchForTrade => 10,
+            Self::PayoutVerifierMismatch { .. } => 11,
+            Self::SurplusOutputsExceedSurplus => 12,
+            Self::TooManyOrdersInMatch => 13,
+            Self::OfferAmountOffGrid => 14,
+            Self::AskAmountOffGrid => 15,
+            Self::OrderBelowDustThreshold => 16,
+            Self::ZeroAmountOrder => 17,
+            Self::OrderExpired => 18,
+            Self::RebatePoolMissing => 19,
+            Self::RebatePoolClosed => 20,
+            Self::RebatePoolNotClosed => 21,
+            Self::WrongRebateEpoch => 22,
+            Self::RebatePoolMismatch => 23,
+            Self::MakerCreditMismatch => 24,
+            Self::InsufficientSurplusForFee => 25,
+            Self::ClaimExceedsRebate => 26,
+            Self::TradingPaused => 27,
+            Self::PairNotListed => 28,
+            Self::PairRegistryMissing => 29,
+            Self::PairRegistryChanged => 30,
+            Self::PairAlreadyListed => 31,
+            Self::CancelRequiresOneOrder => 32,
+            Self::RefundExceedsOrder => 33,
+            Self::AmountOverflow => 34,
+            Self::WrongNumberOfOrdersInRoute => 35,
+            Self::InsufficientIntermediateForRoute => 36,
+            Self::MalformedIcebergTranche => 37,
+            Self::TrancheDoesNotMatchCommitment => 38,
+            Self::CommitmentMissing => 39,
+            Self::CommitmentForWrongBlock => 40,
+            Self::RevealTooEarly => 41,
+            Self::RevealWindowExpired => 42,
+            Self::RevealDoesNotMatchCommitment => 43,
+            Self::CommitmentNotExpired => 44,
+            Self::ReclaimExceedsCollateral => 45,
+            Self::NftMissing => 46,
+            Self::TooManyInputsWhenListingNft => 47,
+            Self::ListingDoesNotMatchNft => 48,
+            Self::NftNotDelivered => 49,
+            Self::NotEnoughPaymentForNft => 50,
+            Self::ChangeExceedsLeftoverPayment => 51,
+            Self::CancelNotAuthorized => 52,
+            Self::MatcherNotAuthorized => 53,
+            Self::WrongOrderBond => 54,
+            Self::BondNotRefunded => 55,
+            Self::OrderMadeInTheFuture => 56,
+            Self::OrderNotEvictable => 57,
+            Self::OfferNotRefunded => 58,
+            Self::SweepRewardTooLarge => 59,
+            Self::CollateralNotOwned => 60,
+            Self::SameToken => 61,
+            Self::AuctionUnderpaid => 62,
+            Self::LotNotDelivered => 63,
+            Self::ReferralNotPaid => 64,
+        }
+    }
+
+    /// The message describing errors with the given code, if it is one.
+    pub fn message_for_code(code: u8) -> Option<&'static str> {
+        DEX_ERROR_MESSAGES.get(code as usize).copied()
+    }
+}
+
+impl sp_std::fmt::Display for DexError {
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        f.write_str(DEX_ERROR_MESSAGES[self.code() as usize])?;
+        match self {
+            Self::PayoutMismatch { index, side }
+            | Self::PayoutVerifierMismatch { index, side } => {
+                let token = match side {
+                    Side::OffersA => "A",
+                    Side::OffersB => "B",
+                };
+                write!(f, " (order {}, offering token {})", index, token)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DexError {}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// The Constraint checking logic for opening a new order.
+///
+/// It is generic over the dex configuration which determines the verifier
+/// type that can be used to protect matched outputs as well as the two
+/// coins that trade in this order book.
+///
+/// The inputs are coins of token A worth at least the order's offer and bond, and the only
+/// output is the order. Every coin must belong to the order's payout verifier, according
+/// to [`DexConfig::owns_collateral`], so that the collateral of an order always goes back
+/// to whoever spent it.
+pub struct MakeOrder<T: DexConfig>(pub PhantomData<T>);
+
+impl<T: DexConfig> ConstraintChecker<T::Verifier> for MakeOrder<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!T::trading_paused(), DexError::TradingPaused);
+        ensure!(T::pair_listed(), DexError::PairNotListed);
+        ensure_distinct_tokens::<T>()?;
+
+        // There should be a single order as the output.
+        ensure!(!outputs.is_empty(), DexError::OrderMissing);
+        ensure!(outputs.len() == 1, DexError::TooManyOutputsWhenMakingOrder);
+
+        // Now that we know there is a single output, we can
+        // try to extract it to the proper type. If the output
+        // is not an `Order` the extraction will fail.
+        let order: Order<T> = outputs[0].payload.extract()?;
+
+        // The amounts must line up with the configured lot and tick sizes, so that the
+        // book does not fragment into countless slightly different prices.
+        order.ensure_not_zero()?;
+        order.ensure_on_grid()?;
+        order.ensure_not_dust(T::parameters().dust_threshold)?;
+        let block_number = T::context().block_number;
+        order.ensure_not_expired(block_number)?;
+        ensure!(order.bond == T::ORDER_BOND, DexError::WrongOrderBond);
+        ensure!(
+            order.made_at <= block_number,
+            DexError::OrderMadeInTheFuture
+        );
+
+        // There may be many inputs and they should all be tokens whose combined value
+        // equals or exceeds the amount of token they need to provide for this order.
+        // Each of them must belong to whoever the order pays out to.
+        let mut total_collateral = 0u128;
+        for input in inputs {
+            let coin: T::A = input.payload.extract()?;
+            ensure!(
+                T::owns_collateral(&input.verifier, &order.payout_verifier),
+                DexError::CollateralNotOwned
+            );
+            total_collateral = total_collateral
+                .checked_add(coin.value())
+                .ok_or(DexError::AmountOverflow)?;
+        }
+
+        // Now that we know the total amount of input collateral, we
+        // need to make sure it is enough to cover the `offer_amount` and the bond
+        let locked = order
+            .offer_amount
+            .checked_add(order.bond)
+            .ok_or(DexError::AmountOverflow)?;
+        ensure!(
+            total_collateral >= locked,
+            DexError::NotEnoughCollateralToOpenOrder
+        );
+
+        // All constraints have passed their checks, so this transaction is valid.
+        Ok(0)
+    }
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for matching existing open orders against one another
+///
+/// The outputs start with the payouts, in the same order as the orders they pay. Each
+/// payout pays one order, or a run of consecutive orders on the same side with the same
+/// payout verifier and payout kind, exactly their combined ask. Combining payouts keeps
+/// matches small for makers who run many small orders.
+///
+/// The payouts are followed by a refund of what is left of the bond of each order that has
+/// some left, in the same order as the orders. See [`Order::bond`].
+///
+/// When fees are enabled, the open rebate pool follows the orders in the inputs, and the
+/// bond refunds are followed by outputs crediting the makers and updating the pool. See the
+/// [`rebate`] module. A match that names a referrer then pays it a share of the fee out of
+/// the surplus, in coins right after the rebates.
+///
+/// Whatever the orders offer beyond what they ask for is the match's surplus. Any outputs
+/// after the payouts, bond refunds, rebates and referral payouts are coins that take some of
+/// the surplus, typically as the matcher's reward. The payouts, fees, referral payouts and
+/// surplus outputs together never exceed what the orders offer, in either token. Any surplus that is left over is burned.
+///
+/// Orders with a matcher verifier can only be filled by a match that spends credentials:
+/// inputs after the orders, and before the rebate pool, that are protected by it. Every
+/// credential must be the matcher verifier of some order in the match. They may be returned
+/// unchanged among the surplus outputs.
+pub struct MatchOrders<T: DexConfig> {
+    /// Whoever referred the match, such as the front-end that built it for a trader.
+    ///
+    /// When fees are enabled, the referrer is paid [`DexConfig::REFERRAL_SHARE_PPM`] of the
+    /// fee in each token, in a coin protected by this verifier. The share comes out of the
+    /// surplus, so the makers get the whole fee either way.
+    pub referrer: Option<T::Verifier>,
+    pub _ph_data: PhantomData<T>,
+}
+
+/// An order being matched, from either side of the pair.
+enum MatchedOrder<T: DexConfig> {
+    /// An order offering token A for token B
+    OffersA(Order<T>),
+    /// An order offering token B for token A
+    OffersB(Order<OppositeSide<T>>),
+}
+
+impl<T: DexConfig> MatchedOrder<T> {
+    /// Decode an order from either side of the pair.
+    ///
+    /// The side is told apart by the type id, so the payload is only decoded once.
+    fn extract(payload: &DynamicallyTypedData) -> Result<Self, DexError> {
+        let data = &mut &payload.data[..];
+        let decoded = if payload.type_id == <Order<T> as UtxoData>::TYPE_ID {
+            Order::decode(data).map(Self::OffersA)
+        } else if payload.type_id == <Order<OppositeSide<T>> as UtxoData>::TYPE_ID {
+            Order::decode(data).map(Self::OffersB)
+        } else {
+            // If the order doesn't belong to either side of this pair, then it is not the
+            // right type and we return the general type error.
+            return Err(DexError::TypeError);
+        };
+        decoded.map_err(|_| DexError::TypeError)
+    }
+
+    fn ask_amount(&self) -> u128 {
+        match self {
+            Self::OffersA(order) => order.ask_amount,
+            Self::OffersB(order) => order.ask_amount,
+        }
+    }
+
+    fn payout_verifier(&self) -> &T::Verifier {
+        match self {
+            Self::OffersA(order) => &order.payout_verifier,
+            Self::OffersB(order) => &order.payout_verifier,
+        }
+    }
+
+    fn matcher_verifier(&self) -> Option<&T::Verifier> {
+        match self {
+            Self::OffersA(order) => order.matcher_verifier.as_ref(),
+            Self::OffersB(order) => order.matcher_verifier.as_ref(),
+        }
+    }
+
+    fn payout_kind(&self) -> PayoutKind {
+        match self {
+            Self::OffersA(order) => order.payout_kind,
+            Self::OffersB(order) => order.payout_kind,
+        }
+    }
+
+    fn side(&self) -> Side {
+        match self {
+            Self::OffersA(_) => Side::OffersA,
+            Self::OffersB(_) => Side::OffersB,
+        }
+    }
+
+    /// Whether this order can share a payout with the given one.
+    fn shares_payout_with(&self, other: &Self) -> bool {
+        self.side() == other.side()
+            && self.payout_verifier() == other.payout_verifier()
+            && self.payout_kind() == other.payout_kind()
+    }
+}
+
+impl<T: DexConfig> ConstraintChecker<T::Verifier> for MatchOrders<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!T::trading_paused(), DexError::TradingPaused);
+        ensure_distinct_tokens::<T>()?;
+        let parameters = T::parameters();
+        let block_number = T::context().block_number;
+
+        // When fees are enabled, the rebate pool comes after the orders, and the
+        // rebates after the payouts.
+        let (inputs, pool) = if parameters.taker_fee_ppm == 0 {
+            (inputs, None)
+        } else {
+            let (pool_input, orders) = inputs.split_last().ok_or(DexError::RebatePoolMissing)?;
+            let pool: RebatePool<T> = pool_input.payload.extract()?;
+            (orders, Some((pool, &pool_input.verifier)))
+        };
+
+        ensure!(
+            inputs.len() <= parameters.max_orders_per_match as usize,
+            DexError::TooManyOrdersInMatch
+        );
+
+        // The volume, in token A, and payout verifier of each order, for crediting makers.
+        let mut makers = Vec::new();
+
+        // Each order will add some tokens to the matching pot
+        // and demand some tokens from the matching pot.
+        // As we loop through the orders, we will keep track of these totals.
+        // After all orders have been paid, we will make sure the
+        // amounts add up.
+        let mut total_a_required = 0u128;
+        let mut total_b_required = 0u128;
+        let mut a_so_far = 0u128;
+        let mut b_so_far = 0u128;
+
+        let mut orders = Vec::with_capacity(inputs.len());
+        let mut credentials = &[][..];
+        for (position, input) in inputs.iter().enumerate() {
+            // It could be Order<V, A, B> or Order<V, B, A>. Any inputs after the orders
+            // are credentials of the matcher.
+            let order = match MatchedOrder::<T>::extract(&input.payload) {
+                Ok(order) => order,
+                Err(_) if position > 0 => {
+                    credentials = &inputs[position..];
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            match &order {
+                MatchedOrder::OffersA(order) => {
+                    order.ensure_not_expired(block_number)?;
+                    a_so_far = a_so_far
+                        .checked_add(order.offer_amount)
+                        .ok_or(DexError::AmountOverflow)?;
+                    total_b_required = total_b_required
+                        .checked_add(order.ask_amount)
+                        .ok_or(DexError::AmountOverflow)?;
+                    if pool.is_some() {
+                        makers.push((order.offer_amount, order.payout_verifier.clone()));
+                    }
+                }
+                MatchedOrder::OffersB(order) => {
+                    order.ensure_not_expired(block_number)?;
+                    b_so_far = b_so_far
+                        .checked_add(order.offer_amount)
+                        .ok_or(DexError::AmountOverflow)?;
+                    total_a_required = total_a_required
+                        .checked_add(order.ask_amount)
+                        .ok_or(DexError::AmountOverflow)?;
+                    if pool.is_some() {
+                        makers.push((order.ask_amount, order.payout_verifier.clone()));
+                    }
+                }
+            }
+            orders.push(order);
+        }
+
+        // Every credential must authorize the match to fill some order, so that stray
+        // inputs are still rejected, and none may be an order that would go unpaid.
+        for credential in credentials {
+            let authorizes = orders
+                .iter()
+                .any(|order| order.matcher_verifier() == Some(&credential.verifier));
+            ensure!(
+                authorizes && MatchedOrder::<T>::extract(&credential.payload).is_err(),
+                DexError::TypeError
+            );
+        }
+        for order in &orders {
+            match order {
+                MatchedOrder::OffersA(order) => order.ensure_matcher_authorized(credentials)?,
+                MatchedOrder::OffersB(order) => order.ensure_matcher_authorized(credentials)?,
+            }
+        }
+
+        // Each payout pays the next order, and as many of the orders after it as it takes
+        // to add up to the payout, as long as they can share it.
+        let mut outputs = outputs.iter();
+        let mut next_order = 0;
+        while let Some(first) = orders.get(next_order) {
+            let output = outputs.next().ok_or(DexError::OrderAndPayoutCountDiffer)?;
+            let (index, side) = (next_order as u32, first.side());
+
+            // Ensure the payout is in the asked token and form, and was given to the
+            // right owner
+            let kind = first.payout_kind();
+            let paid = match first {
+                MatchedOrder::OffersA(_) => kind.amount_paid::<T::B>(&output.payload)?,
+                MatchedOrder::OffersB(_) => kind.amount_paid::<T::A>(&output.payload)?,
+            };
+            ensure!(
+                output.verifier == *first.payout_verifier(),
+                DexError::PayoutVerifierMismatch { index, side }
+            );
+
+            // Ensure the payout is the right amount
+            let mut owed = first.ask_amount();
+            next_order += 1;
+            while owed < paid {
+                match orders.get(next_order) {
+                    Some(order) if order.shares_payout_with(first) => {
+                        owed = owed
+                            .checked_add(order.ask_amount())
+                            .ok_or(DexError::AmountOverflow)?;
+                        next_order += 1;
+                    }
+                    _ => break,
+                }
+            }
+            ensure!(owed == paid, DexError::PayoutMismatch { index, side });
+        }
+
+        // The bonds of the orders are refunded after the payouts, in the same order.
+        let mut rest = outputs.as_slice();
+        for order in &orders {
+            rest = match order {
+                MatchedOrder::OffersA(order) => order.take_bond_refund(rest, block_number)?,
+                MatchedOrder::OffersB(order) => order.take_bond_refund(rest, block_number)?,
+            };
+        }
+        let (rebate_outputs, surplus_outputs) = match &pool {
+            None => (&[][..], rest),
+            Some((pool, _)) => {
+                let rebate_count = rebate::rebate_output_count(pool, makers.len());
+                rest.split_at(rebate_count.min(rest.len()))
+            }
+        };
+
+        // Make sure the amounts in the orders actually match and satisfy each other.
+        ensure!(
+            a_so_far >= total_a_required,
+            DexError::InsufficientTokenAForMatch
+        );
+        ensure!(
+            b_so_far >= total_b_required,
+            DexError::InsufficientTokenBForMatch
+        );
+
+        let (fee_a, fee_b) = match pool {
+            None => (0, 0),
+            Some((pool, pool_verifier)) => rebate::check_match_fees::<T>(
+                &makers,
+                (total_a_required, total_b_required),
+                (a_so_far - total_a_required, b_so_far - total_b_required),
+                (pool, pool_verifier),
+                parameters.taker_fee_ppm,
+                rebate_outputs,
+            )?,
+        };
+
+        // The referrer's share of the fee comes out of the surplus, on top of the fee.
+        let (surplus_outputs, (referral_a, referral_b)) = match &self.referrer {
+            None => (surplus_outputs, (0, 0)),
+            Some(referrer) => {
+                rebate::take_referral_payouts::<T>(referrer, (fee_a, fee_b), surplus_outputs)?
+            }
+        };
+
+        // Account for every token that leaves the match. The payouts were checked against
+        // the asks above, so together with the fees, the referral payouts and the surplus
+        // outputs they must be covered by the offers.
+        let mut surplus_a = 0u128;
+        let mut surplus_b = 0u128;
+        let mut unreturned: Vec<_> = credentials.iter().collect();
+        for output in surplus_outputs {
+            // Credentials may be returned to the matcher unchanged, once each.
+            let returned = unreturned
+                .iter()
+                .position(|credential| *credential == output);
+            if let Some(position) = returned {
+                unreturned.swap_remove(position);
+                continue;
+            }
+            if let Ok(coin) = output.payload.extract::<T::A>() {
+                surplus_a = surplus_a
+                    .checked_add(coin.value())
+                    .ok_or(DexError::AmountOverflow)?;
+            } else {
+                let coin: T::B = output.payload.extract()?;
+                surplus_b = surplus_b
+                    .checked_add(coin.value())
+                    .ok_or(DexError::AmountOverflow)?;
+            }
+        }
+        let spent_a = [total_a_required, fee_a, referral_a, surplus_a]
+            .iter()
+            .try_fold(0u128, |total, amount| total.checked_add(*amount))
+            .ok_or(DexError::AmountOverflow)?;
+        let spent_b = [total_b_required, fee_b, referral_b, surplus_b]
+            .iter()
+            .try_fold(0u128, |total, amount| total.checked_add(*amount))
+            .ok_or(DexError::AmountOverflow)?;
+        ensure!(
+            spent_a <= a_so_far && spent_b <= b_so_far,
+            DexError::SurplusOutputsExceedSurplus
+        );
+
+        Ok(0)
+    }
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for cancelling an open order.
+///
+/// The first input is the order, and the outputs are coins of token A worth no more than
+/// it offered and what is left of its bond after rent, all protected by the order's payout
+/// verifier. The bond of an order that has expired is forfeited, so its refund is worth no
+/// more than it offered. Orders are usually left up
+/// for grabs so that anyone can match them, so anyone may be able to cancel one too, but
+/// the collateral always goes back to the maker. Cancelling is allowed even while trading
+/// is paused.
+///
+/// If the order has a cancel verifier, the second input must be protected by it, which
+/// proves that the canceller may cancel the order. That input may be passed through
+/// unchanged as the last output. Orders without one are the only input.
+pub struct CancelOrder<T: DexConfig>(pub PhantomData<T>);
+
+impl<T: DexConfig> ConstraintChecker<T::Verifier> for CancelOrder<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), DexError::CancelRequiresOneOrder);
+        let order: Order<T> = inputs[0].payload.extract()?;
+
+        let mut refunds = outputs;
+        match &order.cancel_verifier {
+            None => ensure!(inputs.len() == 1, DexError::CancelRequiresOneOrder),
+            Some(cancel_verifier) => {
+                ensure!(
+                    inputs.len() == 2 && inputs[1].verifier == *cancel_verifier,
+                    DexError::CancelNotAuthorized
+                );
+                // The canceller may keep the UTXO that authorized the cancellation.
+                if let Some((last, rest)) = outputs.split_last() {
+                    if *last == inputs[1] {
+                        refunds = rest;
+                    }
+                }
+            }
+        }
+
+        let mut total_refund = 0u128;
+        for output in refunds {
+            let coin: T::A = output.payload.extract()?;
+            ensure!(
+                output.verifier == order.payout_verifier,
+                DexError::VerifierMismatchForTrade
+            );
+            total_refund = total_refund
+                .checked_add(coin.value())
+                .ok_or(DexError::AmountOverflow)?;
+        }
+        // Orders that were left to expire forfeit their bond, which is burned, and so is
+        // the rent that the others have paid out of theirs.
+        let block_number = T::context().block_number;
+        let refundable = if order.ensure_not_expired(block_number).is_ok() {
+            order
+                .offer_amount
+                .checked_add(order.bond_left(block_number))
+                .ok_or(DexError::AmountOverflow)?
+        } else {
+            order.offer_amount
+        };
+        ensure!(total_refund <= refundable, DexError::RefundExceedsOrder);
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,106 @@
+//! Curated markets, whose pairs operators list while the chain is running.
+//!
+//! A runtime may compile in many dex instances but only want some of them open for
+//! trading. It keeps a single [`PairRegistry`] UTXO with the pairs that have been listed,
+//! protected by the operators' verifier, typically a threshold multi-signature like a
+//! governance council's. [`ListPair`] adds a pair to the registry, so listing takes the
+//! operators' approval, but no runtime upgrade.
+//!
+//! Constraint checkers cannot read UTXOs that their transaction does not consume, so each
+//! listing also records the registry in storage, in the same way that the governance piece
+//! records parameters. Dex configurations learn whether their pair is listed through
+//! [`DexConfig::pair_listed`], typically by calling [`is_listed`]. New orders, icebergs
+//! and commitments are rejected for pairs that are not listed, but existing ones can still
+//! be matched and cancelled. The runtime must record the genesis registry itself, under
+//! [`PAIR_REGISTRY_KEY`].
+
+use super::*;
+
+/// The storage key under which the current pair registry is recorded.
+pub const PAIR_REGISTRY_KEY: &[u8] = b"dex_pair_registry";
+
+/// A trading pair, identified by the `Cash` ids of its two tokens.
+pub type PairId = (u8, u8);
+
+/// The pair that the given dex configuration trades.
+pub fn pair_of<T: DexConfig>() -> PairId {
+    (T::A::ID, T::B::ID)
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default, TypeInfo)]
+/// The pairs that are listed for trading.
+pub struct PairRegistry {
+    /// The listed pairs, in the order they were listed
+    pub pairs: Vec<PairId>,
+}
+
+impl UtxoData for PairRegistry {
+    const TYPE_ID: [u8; 4] = *b"$pls";
+}
+
+impl PairRegistry {
+    /// Whether the given pair is listed, with its tokens in either order.
+    pub fn contains(&self, (a, b): PairId) -> bool {
+        self.pairs
+            .iter()
+            .any(|listed| *listed == (a, b) || *listed == (b, a))
+    }
+}
+
+/// The pair registry recorded by the most recent listing, if any.
+pub fn current_registry() -> Option<PairRegistry> {
+    sp_io::storage::get(PAIR_REGISTRY_KEY)
+        .and_then(|encoded| PairRegistry::decode(&mut &encoded[..]).ok())
+}
+
+/// Whether the pair of the given dex configuration is listed in the current registry.
+///
+/// Nothing is listed before a registry has been recorded.
+pub fn is_listed<T: DexConfig>() -> bool {
+    current_registry().map_or(false, |registry| registry.contains(pair_of::<T>()))
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default, TypeInfo)]
+/// List a new pair for trading.
+///
+/// The only input is the current registry and the only output is the new one, which must
+/// be protected by the same verifier and list exactly one more pair after the existing
+/// ones. The pair must have two different tokens and not be listed already.
+pub struct ListPair;
+
+impl<V: Verifier + PartialEq> ConstraintChecker<V> for ListPair {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        outputs: &[Output<V>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, DexError::PairRegistryMissing);
+        ensure!(outputs.len() == 1, DexError::PairRegistryMissing);
+
+        let current: PairRegistry = inputs[0].payload.extract()?;
+        let new: PairRegistry = outputs[0].payload.extract()?;
+        ensure!(
+            outputs[0].verifier == inputs[0].verifier,
+            DexError::PairRegistryChanged
+        );
+
+        let (pair, existing) = new
+            .pairs
+            .split_last()
+            .ok_or(DexError::PairRegistryChanged)?;
+        ensure!(existing == current.pairs, DexError::PairRegistryChanged);
+        ensure!(
+            pair.0 != pair.1 && !current.contains(*pair),
+            DexError::PairAlreadyListed
+        );
+
+        // SIDE EFFECT: Record the new registry so dex checkers can read it
+        sp_io::storage::set(PAIR_REGISTRY_KEY, &new.encode());
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,149 @@
+//! Fixed-price listings of non-fungible items.
+//!
+//! Unlike the fungible order book, where orders offer an amount of one token, an
+//! [`NftOrder`] offers one specific non-fungible item, such as a kitty, for a fixed
+//! price in a fungible token. There is no matching between listings; a buyer simply
+//! pays the asking price to take the item.
+
+use super::*;
+
+/// A Configuration for a fixed-price non-fungible item market.
+pub trait NftDexConfig {
+    /// The type of verifiers that can be used in payouts.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The non-fungible item that is listed for sale.
+    type Nft: UtxoData + PartialEq;
+    /// The token in which the items are priced.
+    type Token: Cash + UtxoData;
+
+    /// The chain context in which transactions are currently being checked.
+    ///
+    /// The default implementation always reports block zero at time zero.
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext::default()
+    }
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+/// A listing offering a single non-fungible item at a fixed price.
+///
+/// The user who opens this listing must put up the item itself as collateral.
+/// Anyone may take the item so long as the ask amount of the token is paid
+/// to the seller's payout verifier.
+pub struct NftOrder<T: NftDexConfig> {
+    /// The item that is offered
+    pub nft: T::Nft,
+    /// The amount of the token asked in exchange for the item
+    pub ask_amount: u128,
+    /// The verifier that will protect the payment coin
+    /// in the event of a successful purchase.
+    pub payout_verifier: T::Verifier,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: NftDexConfig> UtxoData for NftOrder<T> {
+    const TYPE_ID: [u8; 4] = [b'$', b'n', T::Nft::TYPE_ID[0], T::Token::ID];
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for listing a non-fungible item for sale.
+///
+/// The only input is the item, and the only output is a listing for that same item.
+pub struct ListNft<T: NftDexConfig>(pub PhantomData<T>);
+
+impl<T: NftDexConfig> SimpleConstraintChecker for ListNft<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!input_data.is_empty(), DexError::NftMissing);
+        ensure!(input_data.len() == 1, DexError::TooManyInputsWhenListingNft);
+        ensure!(!output_data.is_empty(), DexError::OrderMissing);
+        ensure!(
+            output_data.len() == 1,
+            DexError::TooManyOutputsWhenMakingOrder
+        );
+
+        let nft: T::Nft = input_data[0].extract()?;
+        let order: NftOrder<T> = output_data[0].extract()?;
+        ensure!(order.nft == nft, DexError::ListingDoesNotMatchNft);
+
+        Ok(0)
+    }
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for buying a listed non-fungible item.
+///
+/// The first input is the listing and all remaining inputs are payment coins.
+/// The first output is the item, protected by whatever verifier the buyer likes.
+/// The second output is the payment to the seller, and any further outputs are
+/// change coins returned to the buyer.
+pub struct BuyNft<T: NftDexConfig>(pub PhantomData<T>);
+
+impl<T: NftDexConfig> ConstraintChecker<T::Verifier> for BuyNft<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), DexError::OrderMissing);
+        let order: NftOrder<T> = inputs[0].payload.extract()?;
+
+        let mut total_payment = 0u128;
+        for input in &inputs[1..] {
+            let coin: T::Token = input.payload.extract()?;
+            total_payment = total_payment
+                .checked_add(coin.value())
+                .ok_or(DexError::AmountOverflow)?;
+        }
+        ensure!(
+            total_payment >= order.ask_amount,
+            DexError::NotEnoughPaymentForNft
+        );
+
+        // The buyer receives the item
+        ensure!(!outputs.is_empty(), DexError::NftNotDelivered);
+        let nft: T::Nft = outputs[0]
+            .payload
+            .extract()
+            .map_err(|_| DexError::NftNotDelivered)?;
+        ensure!(nft == order.nft, DexError::NftNotDelivered);
+
+        // The seller receives the payment
+        ensure!(outputs.len() >= 2, DexError::PayoutDoesNotSatisfyOrder);
+        let payout: T::Token = outputs[1].payload.extract()?;
+        ensure!(
+            payout.value() == order.ask_amount,
+            DexError::PayoutDoesNotSatisfyOrder
+        );
+        ensure!(
+            outputs[1].verifier == order.payout_verifier,
+            DexError::VerifierMismatchForTrade
+        );
+
+        // The buyer may take back some change
+        let mut total_change = 0u128;
+        for output in &outputs[2..] {
+            let coin: T::Token = output.payload.extract()?;
+            total_change = total_change
+                .checked_add(coin.value())
+                .ok_or(DexError::AmountOverflow)?;
+        }
+        ensure!(
+            total_change <= total_payment - order.ask_amount,
+            DexError::ChangeExceedsLeftoverPayment
+        );
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,174 @@
+//! Fixed-point prices, and the rounding rules for amounts derived from them.
+//!
+//! Orders only state whole amounts of each token, but fills of part of an order, fees,
+//! and market data all need the ratio between those amounts. Floating point is not
+//! deterministic enough for the runtime, and plain integer division silently rounds in
+//! whichever direction the operands happen to favour. A [`Price`] is a number of token B
+//! per unit of token A with [`Price::DECIMALS`] decimal places, and every calculation
+//! takes an explicit [`Rounding`] so that callers decide who the rounding favours.
+//!
+//! Intermediate products are computed in 256 bits, so no calculation overflows before
+//! its result is known not to fit in a `u128`.
+
+use super::*;
+use sp_core::U256;
+
+/// The direction in which to round a result that is not a whole number.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rounding {
+    /// Round towards zero
+    Down,
+    /// Round away from zero
+    Up,
+}
+
+/// Calculate `a * b / c` without overflowing in between.
+///
+/// Returns `None` if `c` is zero or the result does not fit in a `u128`.
+pub fn mul_div(a: u128, b: u128, c: u128, rounding: Rounding) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    let product = U256::from(a) * U256::from(b);
+    let c = U256::from(c);
+    let mut quotient = product / c;
+    if rounding == Rounding::Up && !(product % c).is_zero() {
+        quotient += U256::one();
+    }
+    u128::try_from(quotient).ok()
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default, TypeInfo)]
+/// An amount of token B per unit of token A, with [`Price::DECIMALS`] decimal places.
+pub struct Price(pub u128);
+
+impl Price {
+    /// The number of decimal places in a price.
+    pub const DECIMALS: u32 = 12;
+
+    /// The price of one unit of token B per unit of token A.
+    pub const ONE: Price = Price(10u128.pow(Self::DECIMALS));
+
+    /// The price at which `amount_a` of token A trades for `amount_b` of token B.
+    ///
+    /// Returns `None` if `amount_a` is zero or the price is too large to represent.
+    pub fn from_amounts(amount_b: u128, amount_a: u128, rounding: Rounding) -> Option<Self> {
+        mul_div(amount_b, Self::ONE.0, amount_a, rounding).map(Price)
+    }
+
+    /// The amount of token B that `amount_a` of token A is worth at this price.
+    ///
+    /// Returns `None` if the result does not fit in a `u128`.
+    pub fn amount_b_for(&self, amount_a: u128, rounding: Rounding) -> Option<u128> {
+        mul_div(amount_a, self.0, Self::ONE.0, rounding)
+    }
+
+    /// The amount of token A that `amount_b` of token B is worth at this price.
+    ///
+    /// Returns `None` if the price is zero or the result does not fit in a `u128`.
+    pub fn amount_a_for(&self, amount_b: u128, rounding: Rounding) -> Option<u128> {
+        mul_div(amount_b, Self::ONE.0, self.0, rounding)
+    }
+
+    /// The same price, expressed as token A per unit of token B.
+    ///
+    /// Returns `None` if the price is zero or its inverse is too large to represent.
+    pub fn inverse(&self, rounding: Rounding) -> Option<Self> {
+        mul_div(Self::ONE.0, Self::ONE.0, self.0, rounding).map(Price)
+    }
+}
+
+impl<T: DexConfig> Order<T> {
+    /// The lowest price, in token B per unit of token A, at which the maker is willing to
+    /// trade.
+    ///
+    /// It is rounded up, so that trading at this price never pays the maker less than
+    /// they asked for. Returns `None` if the order offers nothing.
+    pub fn limit_price(&self) -> Option<Price> {
+        Price::from_amounts(self.ask_amount, self.offer_amount, Rounding::Up)
+    }
+
+    /// The amount of token B that the maker asks for the given part of their offer.
+    ///
+    /// It is the maker's ask in proportion to the part of the offer that is filled,
+    /// rounded up in the maker's favour. Returns `None` if the order offers nothing.
+    pub fn ask_for_fill(&self, filled_amount: u128) -> Option<u128> {
+        mul_div(
+            self.ask_amount,
+            filled_amount,
+            self.offer_amount,
+            Rounding::Up,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use money::Coin;
+    use tuxedo_core::verifier::TestVerifier;
+
+    struct TestConfig;
+    impl DexConfig for TestConfig {
+        type Verifier = TestVerifier;
+        type A = Coin<0>;
+        type B = Coin<1>;
+    }
+
+    fn order(offer_amount: u128, ask_amount: u128) -> Order<TestConfig> {
+        Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
+    }
+
+    #[test]
+    fn mul_div_rounds_as_asked() {
+        assert_eq!(mul_div(10, 1, 3, Rounding::Down), Some(3));
+        assert_eq!(mul_div(10, 1, 3, Rounding::Up), Some(4));
+        assert_eq!(mul_div(9, 1, 3, Rounding::Up), Some(3));
+        assert_eq!(mul_div(1, 1, 0, Rounding::Down), None);
+    }
+
+    #[test]
+    fn mul_div_does_not_overflow_in_between() {
+        assert_eq!(
+            mul_div(u128::MAX, u128::MAX, u128::MAX, Rounding::Down),
+            Some(u128::MAX)
+        );
+        assert_eq!(mul_div(u128::MAX, 2, 1, Rounding::Down), None);
+    }
+
+    #[test]
+    fn prices_convert_between_amounts() {
+        let price = Price::from_amounts(3, 2, Rounding::Down).unwrap();
+        assert_eq!(price, Price(1_500_000_000_000));
+        assert_eq!(price.amount_b_for(4, Rounding::Down), Some(6));
+        assert_eq!(price.amount_a_for(6, Rounding::Down), Some(4));
+        assert_eq!(price.inverse(Rounding::Down), Some(Price(666_666_666_666)));
+        assert_eq!(Price::ONE.inverse(Rounding::Down), Some(Price::ONE));
+    }
+
+    #[test]
+    fn zero_amounts_have_no_price() {
+        assert_eq!(Price::from_amounts(3, 0, Rounding::Down), None);
+        assert_eq!(Price(0).inverse(Rounding::Down), None);
+        assert_eq!(Price(0).amount_a_for(1, Rounding::Down), None);
+        assert_eq!(order(0, 10).limit_price(), None);
+    }
+
+    #[test]
+    fn limit_price_rounds_in_the_makers_favour() {
+        // 1 for 3 is not exactly representable.
+        let price = order(3, 1).limit_price().unwrap();
+        assert_eq!(price, Price(333_333_333_334));
+        assert!(price.amount_b_for(3, Rounding::Down).unwrap() >= 1);
+    }
+
+    #[test]
+    fn partial_fills_round_the_ask_up() {
+        let order = order(3, 10);
+        assert_eq!(order.ask_for_fill(3), Some(10));
+        assert_eq!(order.ask_for_fill(1), Some(4));
+        assert_eq!(order.ask_for_fill(2), Some(7));
+        assert_eq!(order.ask_for_fill(0), Some(0));
+    }
+}
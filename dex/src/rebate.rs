@@ -0,0 +1,314 @@
+//! Taker fees that are paid back to makers as rebates.
+//!
+//! Resting orders are what give the book its liquidity, so a dex may want to reward the
+//! makers who open them. When the taker fee in [`DexConfig::parameters`] is not zero,
+//! every [`MatchOrders`] transaction pays a fee out of the match's surplus into a single
+//! [`RebatePool`]. The fee is that many millionths of each token paid out to the orders.
+//! Each matched order earns a [`MakerCredit`] for its volume, measured in token A, and
+//! protected by the order's payout verifier.
+//!
+//! Time is divided into epochs of [`DexConfig::REBATE_EPOCH_LENGTH`] blocks. The first
+//! match in a new epoch closes the pool of the previous epoch and starts a new one. Once a
+//! pool is closed, the makers who earned credits in its epoch can withdraw their share of
+//! its fees with [`ClaimRebate`], in proportion to their volume.
+//!
+//! A match may name a referrer, such as the front-end that built it, to reward. The matcher
+//! pays the referrer [`DexConfig::REFERRAL_SHARE_PPM`] of the fee in each token, rounded
+//! down, in coins that follow the rebate outputs. The share comes out of the match's surplus
+//! on top of the fee, and the whole fee still goes into the pool. This lets third-party
+//! front-ends earn from the trades they bring to the book. Since the makers' rebates are the
+//! same whoever refers a match, a matcher that names itself as the referrer only pays its
+//! own surplus back to itself.
+//!
+//! No checker creates open pools. The runtime must put exactly one open pool in its genesis,
+//! protected by a verifier that anyone can satisfy, so that every match can spend it.
+
+use super::*;
+use crate::price::{mul_div, Rounding};
+
+/// The denominator of the taker fee.
+pub const PPM: u128 = 1_000_000;
+
+/// The referrer's share of the given fee, at the given share in millionths of the fee.
+///
+/// It is rounded down, so that the matcher keeps any remainder, and never exceeds the fee.
+pub fn referral_share(fee: u128, share_ppm: u32) -> Option<u128> {
+    mul_div(fee, share_ppm.into(), PPM, Rounding::Down).map(|share| share.min(fee))
+}
+
+/// The rebate epoch that the given block falls in.
+pub fn epoch_of<T: DexConfig>(block_number: u32) -> u32 {
+    block_number
+        .checked_div(T::REBATE_EPOCH_LENGTH)
+        .unwrap_or_default()
+}
+
+/// The fee paid on the given amount of either token, at the given fee in millionths.
+///
+/// It is rounded up, so that splitting a match into smaller ones never avoids the fee.
+pub fn taker_fee(amount: u128, fee_ppm: u32) -> Option<u128> {
+    mul_div(amount, fee_ppm.into(), PPM, Rounding::Up)
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+/// The fees collected for makers during one epoch.
+pub struct RebatePool<T: DexConfig> {
+    /// The epoch the fees were collected in
+    pub epoch: u32,
+    /// Whether the epoch is over. Closed pools no longer collect fees but pay out rebates.
+    pub closed: bool,
+    /// The amount of token A collected and not yet claimed
+    pub fees_a: u128,
+    /// The amount of token B collected and not yet claimed
+    pub fees_b: u128,
+    /// The volume credited to makers and not yet claimed, measured in token A
+    pub volume: u128,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: DexConfig> UtxoData for RebatePool<T> {
+    const TYPE_ID: [u8; 4] = book_type_id::<T>(b'r');
+}
+
+impl<T: DexConfig> RebatePool<T> {
+    /// An empty open pool for the given epoch.
+    pub fn open(epoch: u32) -> Self {
+        Self {
+            epoch,
+            closed: false,
+            fees_a: 0,
+            fees_b: 0,
+            volume: 0,
+            _ph_data: PhantomData,
+        }
+    }
+
+    /// The epoch, whether it is closed, the two fee amounts, and the volume, in that order.
+    fn values(&self) -> (u32, bool, u128, u128, u128) {
+        (
+            self.epoch,
+            self.closed,
+            self.fees_a,
+            self.fees_b,
+            self.volume,
+        )
+    }
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+/// A maker's claim to part of the fees collected in one epoch.
+pub struct MakerCredit<T: DexConfig> {
+    /// The epoch the order was matched in
+    pub epoch: u32,
+    /// The volume of the matched order, measured in token A
+    pub volume: u128,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: DexConfig> UtxoData for MakerCredit<T> {
+    const TYPE_ID: [u8; 4] = book_type_id::<T>(b'm');
+}
+
+/// How many outputs follow the payouts of a match of `maker_count` orders that spends the
+/// given pool: a credit for each maker, then the closed pool if the match starts a new
+/// epoch, then the open pool.
+pub(crate) fn rebate_output_count<T: DexConfig>(pool: &RebatePool<T>, maker_count: usize) -> usize {
+    let rolls_over = pool.epoch < epoch_of::<T>(T::context().block_number);
+    maker_count + if rolls_over { 2 } else { 1 }
+}
+
+/// Check the fee part of a `MatchOrders` transaction, and return the fees paid in token A
+/// and token B.
+///
+/// `makers` are the volume and payout verifier of each matched order, `required` are the
+/// amounts of token A and token B paid out to the orders, and `surplus` are the amounts
+/// left over. `pool` is the spent pool and its verifier, and `fee_ppm` is the current taker
+/// fee. `outputs` are the outputs after the payouts, as counted by [`rebate_output_count`].
+pub(crate) fn check_match_fees<T: DexConfig>(
+    makers: &[(u128, T::Verifier)],
+    required: (u128, u128),
+    surplus: (u128, u128),
+    (pool, pool_verifier): (RebatePool<T>, &T::Verifier),
+    fee_ppm: u32,
+    outputs: &[Output<T::Verifier>],
+) -> Result<(u128, u128), DexError> {
+    ensure!(!pool.closed, DexError::RebatePoolClosed);
+    let epoch = epoch_of::<T>(T::context().block_number);
+    ensure!(pool.epoch <= epoch, DexError::WrongRebateEpoch);
+    let rolls_over = pool.epoch < epoch;
+
+    let expected_outputs = rebate_output_count(&pool, makers.len());
+    ensure!(
+        outputs.len() == expected_outputs,
+        DexError::OrderAndPayoutCountDiffer
+    );
+
+    // Every maker is credited with exactly its volume, under its payout verifier.
+    let mut total_volume = 0u128;
+    for ((volume, verifier), output) in makers.iter().zip(outputs) {
+        let credit: MakerCredit<T> = output.payload.extract()?;
+        ensure!(
+            credit.epoch == epoch && credit.volume == *volume,
+            DexError::MakerCreditMismatch
+        );
+        ensure!(output.verifier == *verifier, DexError::MakerCreditMismatch);
+        total_volume = total_volume
+            .checked_add(*volume)
+            .ok_or(DexError::AmountOverflow)?;
+    }
+
+    // The pool of a finished epoch is closed as it was, and the new epoch starts empty.
+    let (_, _, fees_a, fees_b, volume) = if rolls_over {
+        let closed_output = &outputs[makers.len()];
+        let closed: RebatePool<T> = closed_output.payload.extract()?;
+        ensure!(
+            closed.values() == (pool.epoch, true, pool.fees_a, pool.fees_b, pool.volume)
+                && closed_output.verifier == *pool_verifier,
+            DexError::RebatePoolMismatch
+        );
+        RebatePool::<T>::open(epoch).values()
+    } else {
+        pool.values()
+    };
+
+    // The fee comes out of the surplus.
+    let fee_a = taker_fee(required.0, fee_ppm).ok_or(DexError::AmountOverflow)?;
+    let fee_b = taker_fee(required.1, fee_ppm).ok_or(DexError::AmountOverflow)?;
+    ensure!(
+        fee_a <= surplus.0 && fee_b <= surplus.1,
+        DexError::InsufficientSurplusForFee
+    );
+
+    let pool_output = &outputs[expected_outputs - 1];
+    let new_pool: RebatePool<T> = pool_output.payload.extract()?;
+    let expected = (
+        epoch,
+        false,
+        fees_a.checked_add(fee_a).ok_or(DexError::AmountOverflow)?,
+        fees_b.checked_add(fee_b).ok_or(DexError::AmountOverflow)?,
+        volume
+            .checked_add(total_volume)
+            .ok_or(DexError::AmountOverflow)?,
+    );
+    ensure!(
+        new_pool.values() == expected && pool_output.verifier == *pool_verifier,
+        DexError::RebatePoolMismatch
+    );
+
+    Ok((fee_a, fee_b))
+}
+
+/// Check that a referred match pays its referrer's share of the fees in token A and token B,
+/// and return the outputs after the referral payouts and the shares paid.
+///
+/// The payouts are a coin of token A and then a coin of token B, each protected by the
+/// referrer, at the start of `outputs`. There is no payout in a token whose share is zero.
+/// The shares are paid out of the surplus, so the caller must count them as spent.
+pub(crate) fn take_referral_payouts<'a, T: DexConfig>(
+    referrer: &T::Verifier,
+    (fee_a, fee_b): (u128, u128),
+    outputs: &'a [Output<T::Verifier>],
+) -> Result<(&'a [Output<T::Verifier>], (u128, u128)), DexError> {
+    let share_a = referral_share(fee_a, T::REFERRAL_SHARE_PPM).ok_or(DexError::AmountOverflow)?;
+    let share_b = referral_share(fee_b, T::REFERRAL_SHARE_PPM).ok_or(DexError::AmountOverflow)?;
+
+    let mut rest = outputs;
+    if share_a > 0 {
+        let (payout, after) = rest.split_first().ok_or(DexError::ReferralNotPaid)?;
+        let coin: T::A = payout.payload.extract()?;
+        ensure!(
+            coin.value() == share_a && payout.verifier == *referrer,
+            DexError::ReferralNotPaid
+        );
+        rest = after;
+    }
+    if share_b > 0 {
+        let (payout, after) = rest.split_first().ok_or(DexError::ReferralNotPaid)?;
+        let coin: T::B = payout.payload.extract()?;
+        ensure!(
+            coin.value() == share_b && payout.verifier == *referrer,
+            DexError::ReferralNotPaid
+        );
+        rest = after;
+    }
+    Ok((rest, (share_a, share_b)))
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for withdrawing rebates from a closed pool.
+///
+/// The first input is the closed pool and the remaining inputs are credits earned in its
+/// epoch. The first output is the pool, less the claimed volume and fees. The remaining
+/// outputs are coins of token A and token B worth no more than the claimed share of the
+/// pool's fees, rounded down.
+pub struct ClaimRebate<T: DexConfig>(pub PhantomData<T>);
+
+impl<T: DexConfig> ConstraintChecker<T::Verifier> for ClaimRebate<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), DexError::RebatePoolMissing);
+        ensure!(!outputs.is_empty(), DexError::RebatePoolMissing);
+        let pool: RebatePool<T> = inputs[0].payload.extract()?;
+        ensure!(pool.closed, DexError::RebatePoolNotClosed);
+
+        let mut claimed_volume = 0u128;
+        for input in &inputs[1..] {
+            let credit: MakerCredit<T> = input.payload.extract()?;
+            ensure!(credit.epoch == pool.epoch, DexError::WrongRebateEpoch);
+            claimed_volume = claimed_volume
+                .checked_add(credit.volume)
+                .ok_or(DexError::AmountOverflow)?;
+        }
+
+        // Each credit is worth the same share of the fees, however many were claimed before.
+        let remaining_volume = pool
+            .volume
+            .checked_sub(claimed_volume)
+            .ok_or(DexError::MakerCreditMismatch)?;
+        let share_a = mul_div(pool.fees_a, claimed_volume, pool.volume, Rounding::Down)
+            .ok_or(DexError::MakerCreditMismatch)?;
+        let share_b = mul_div(pool.fees_b, claimed_volume, pool.volume, Rounding::Down)
+            .ok_or(DexError::MakerCreditMismatch)?;
+        let expected = (
+            pool.epoch,
+            true,
+            pool.fees_a - share_a,
+            pool.fees_b - share_b,
+            remaining_volume,
+        );
+        let new_pool: RebatePool<T> = outputs[0].payload.extract()?;
+        ensure!(
+            new_pool.values() == expected && outputs[0].verifier == inputs[0].verifier,
+            DexError::RebatePoolMismatch
+        );
+
+        let mut paid_a = 0u128;
+        let mut paid_b = 0u128;
+        for output in &outputs[1..] {
+            if let Ok(coin) = output.payload.extract::<T::A>() {
+                paid_a = paid_a
+                    .checked_add(coin.value())
+                    .ok_or(DexError::AmountOverflow)?;
+            } else {
+                let coin: T::B = output.payload.extract()?;
+                paid_b = paid_b
+                    .checked_add(coin.value())
+                    .ok_or(DexError::AmountOverflow)?;
+            }
+        }
+        ensure!(
+            paid_a <= share_a && paid_b <= share_b,
+            DexError::ClaimExceedsRebate
+        );
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,118 @@
+//! Rent for resting orders, and sweeping orders that nobody will fill off the book.
+//!
+//! Every open order is a UTXO that every node stores until it is spent. Makers who
+//! abandon their orders leave that cost to the chain forever. So dexes that require a bond
+//! with each order can also charge rent: for each block since it was made, an order pays
+//! [`DexConfig::RENT_PER_BLOCK`] out of its bond. Matching or cancelling the order refunds
+//! what is left of the bond, and the rent is burned.
+//!
+//! Once an order has expired, or rent has used up its bond, anyone may sweep it off the
+//! book with [`SweepExpired`]. Sweeping refunds the order's offer to its maker, so the
+//! collateral is never lost, and pays the sweeper a share of the bond, which makes it
+//! worth someone's while to clean up the book.
+
+use super::*;
+use crate::price::{mul_div, Rounding};
+use crate::rebate::PPM;
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for sweeping orders that are expired or out of bond.
+///
+/// The inputs are the swept orders, from either side of the pair. The outputs start with
+/// a refund of each order's offer, in the same order as the orders: a coin of the offered
+/// token worth exactly the offer, protected by the order's payout verifier. Any outputs
+/// after the refunds are coins that reward the sweeper. In each token, they are worth no
+/// more than [`DexConfig::SWEEPER_REWARD_PPM`] of the bonds of the orders offering it. The
+/// rest of the bonds are burned.
+///
+/// Anyone may sweep, even while trading is paused, since sweeping only returns collateral.
+pub struct SweepExpired<T: DexConfig>(pub PhantomData<T>);
+
+/// Make sure the given order may be swept in the given block, and that the given output
+/// refunds its offer. Returns the sweeper's share of its bond.
+fn evict<C: DexConfig>(
+    order: &Order<C>,
+    refund: Option<&Output<C::Verifier>>,
+    block_number: u32,
+) -> Result<u128, DexError> {
+    ensure!(
+        order.is_evictable(block_number),
+        DexError::OrderNotEvictable
+    );
+    let refund = refund.ok_or(DexError::OfferNotRefunded)?;
+    let refunded = refund
+        .payload
+        .extract::<C::A>()
+        .map_or(false, |coin| coin.value() == order.offer_amount);
+    ensure!(
+        refunded && refund.verifier == order.payout_verifier,
+        DexError::OfferNotRefunded
+    );
+    mul_div(
+        order.bond,
+        C::SWEEPER_REWARD_PPM.into(),
+        PPM,
+        Rounding::Down,
+    )
+    .ok_or(DexError::AmountOverflow)
+}
+
+impl<T: DexConfig> ConstraintChecker<T::Verifier> for SweepExpired<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), DexError::OrderMissing);
+        ensure!(
+            inputs.len() <= T::parameters().max_orders_per_match as usize,
+            DexError::TooManyOrdersInMatch
+        );
+        let block_number = T::context().block_number;
+
+        let mut refunds = outputs.iter();
+        let mut reward_a = 0u128;
+        let mut reward_b = 0u128;
+        for input in inputs {
+            match MatchedOrder::<T>::extract(&input.payload)? {
+                MatchedOrder::OffersA(order) => {
+                    let share = evict(&order, refunds.next(), block_number)?;
+                    reward_a = reward_a
+                        .checked_add(share)
+                        .ok_or(DexError::AmountOverflow)?;
+                }
+                MatchedOrder::OffersB(order) => {
+                    let share = evict(&order, refunds.next(), block_number)?;
+                    reward_b = reward_b
+                        .checked_add(share)
+                        .ok_or(DexError::AmountOverflow)?;
+                }
+            }
+        }
+
+        // Whatever follows the refunds is the sweeper's reward.
+        let mut taken_a = 0u128;
+        let mut taken_b = 0u128;
+        for output in refunds {
+            if let Ok(coin) = output.payload.extract::<T::A>() {
+                taken_a = taken_a
+                    .checked_add(coin.value())
+                    .ok_or(DexError::AmountOverflow)?;
+            } else {
+                let coin: T::B = output.payload.extract()?;
+                taken_b = taken_b
+                    .checked_add(coin.value())
+                    .ok_or(DexError::AmountOverflow)?;
+            }
+        }
+        ensure!(
+            taken_a <= reward_a && taken_b <= reward_b,
+            DexError::SweepRewardTooLarge
+        );
+
+        Ok(0)
+    }
+}
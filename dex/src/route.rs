@@ -0,0 +1,149 @@
+//! Matching an order through an intermediate token.
+//!
+//! Some pairs have no direct liquidity. An order offering token A and asking for token B
+//! can still be filled if there are orders trading A with some third token C, and C with B.
+//! A [`RouteMatch`] does both hops in a single transaction: the routed order's A pays a
+//! maker who offers C, and that C pays a maker who offers B, whose B fills the routed order.
+//!
+//! The orders in each hop are ordinary [`Order`]s of the corresponding pair, so the makers
+//! can be ones that were opened on the regular order books of those pairs.
+
+use super::*;
+
+/// A Configuration for routing orders between two tokens through a third.
+pub trait RouteConfig {
+    /// The type of verifiers that can be used in payouts.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The token offered by routed orders
+    type A: Cash + UtxoData;
+    /// The token asked for by routed orders
+    type B: Cash + UtxoData;
+    /// The intermediate token that the route passes through
+    type C: Cash + UtxoData;
+
+    /// The chain context in which transactions are currently being checked.
+    /// See [`DexConfig::context`].
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext::default()
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The dex configuration of a single pair in a route, offering `X` and asking for `Y`.
+///
+/// Orders of this configuration have the same type id and encoding as the orders of any
+/// other configuration offering the same token for the same token.
+pub struct Pair<T: RouteConfig, X, Y>(PhantomData<(T, X, Y)>);
+
+impl<T: RouteConfig, X: Cash + UtxoData, Y: Cash + UtxoData> DexConfig for Pair<T, X, Y> {
+    type Verifier = T::Verifier;
+    type A = X;
+    type B = Y;
+
+    fn context() -> ConstraintCheckingContext {
+        T::context()
+    }
+}
+
+/// An order offering token A and asking for token B, which is filled through a route.
+pub type RoutedOrder<T> = Order<Pair<T, <T as RouteConfig>::A, <T as RouteConfig>::B>>;
+
+/// A maker in the first hop, offering token C and asking for token A.
+pub type FirstHopOrder<T> = Order<Pair<T, <T as RouteConfig>::C, <T as RouteConfig>::A>>;
+
+/// A maker in the second hop, offering token B and asking for token C.
+pub type SecondHopOrder<T> = Order<Pair<T, <T as RouteConfig>::B, <T as RouteConfig>::C>>;
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+/// Constraint checking logic for filling an order through an intermediate token.
+///
+/// There are exactly three inputs: the routed order, the maker of the first hop, and the
+/// maker of the second hop. The first three outputs are their payouts, in the same order.
+/// Each payout must pay exactly its order's ask to its order's payout verifier, and each
+/// order's offer must cover the payout that it funds. Any surplus is burned.
+///
+/// The payouts are followed by a refund of what is left of the bond of each order that has
+/// some left, in the same order again. See [`Order::bond`].
+///
+/// A wrong payout is reported with the input index of its order. Each order offers token A
+/// of its own hop's pair, so they are all on that side.
+pub struct RouteMatch<T: RouteConfig>(pub PhantomData<T>);
+
+impl<T: RouteConfig> ConstraintChecker<T::Verifier> for RouteMatch<T> {
+    type Error = DexError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 3, DexError::WrongNumberOfOrdersInRoute);
+        ensure!(outputs.len() >= 3, DexError::OrderAndPayoutCountDiffer);
+
+        let routed: RoutedOrder<T> = inputs[0].payload.extract()?;
+        let first: FirstHopOrder<T> = inputs[1].payload.extract()?;
+        let second: SecondHopOrder<T> = inputs[2].payload.extract()?;
+
+        // None of the orders may have expired.
+        let block_number = T::context().block_number;
+        routed.ensure_not_expired(block_number)?;
+        first.ensure_not_expired(block_number)?;
+        second.ensure_not_expired(block_number)?;
+
+        // Routes take no credentials, so they only fill orders that anyone may match.
+        routed.ensure_matcher_authorized(&[])?;
+        first.ensure_matcher_authorized(&[])?;
+        second.ensure_matcher_authorized(&[])?;
+
+        // Each order is paid exactly what it asks, in the form it asks, to exactly whom it
+        // asks.
+        ensure_paid(&routed, &outputs[0], 0)?;
+        ensure_paid(&first, &outputs[1], 1)?;
+        ensure_paid(&second, &outputs[2], 2)?;
+
+        // The routed order's A pays the first maker, whose C pays the second maker,
+        // whose B pays the routed order.
+        ensure!(
+            routed.offer_amount >= first.ask_amount,
+            DexError::InsufficientTokenAForMatch
+        );
+        ensure!(
+            first.offer_amount >= second.ask_amount,
+            DexError::InsufficientIntermediateForRoute
+        );
+        ensure!(
+            second.offer_amount >= routed.ask_amount,
+            DexError::InsufficientTokenBForMatch
+        );
+
+        // Nothing but the bond refunds may follow the payouts.
+        let rest = routed.take_bond_refund(&outputs[3..], block_number)?;
+        let rest = first.take_bond_refund(rest, block_number)?;
+        let rest = second.take_bond_refund(rest, block_number)?;
+        ensure!(rest.is_empty(), DexError::OrderAndPayoutCountDiffer);
+
+        Ok(0)
+    }
+}
+
+/// Make sure the payout pays the order at the given input index exactly what it asks, in
+/// the form it asks, to its payout verifier.
+fn ensure_paid<P: DexConfig>(
+    order: &Order<P>,
+    payout: &Output<P::Verifier>,
+    index: u32,
+) -> Result<(), DexError> {
+    let side = Side::OffersA;
+    let paid = order.payout_kind.amount_paid::<P::B>(&payout.payload)?;
+    ensure!(
+        paid == order.ask_amount,
+        DexError::PayoutMismatch { index, side }
+    );
+    ensure!(
+        payout.verifier == order.payout_verifier,
+        DexError::PayoutVerifierMismatch { index, side }
+    );
+    Ok(())
+}
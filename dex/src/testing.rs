@@ -0,0 +1,198 @@
+//! Helpers for testing dex instantiations.
+//!
+//! Runtimes instantiate the dex with their own tokens and verifiers. These helpers let them
+//! test their instantiations without writing out every order and payout by hand.
+//! A [`MatchScenario`] builds the inputs and outputs of a match transaction that pays every
+//! order exactly what it asks for, and can then be tampered with to produce invalid matches.
+//! [`assert_conservation`] checks that a transaction does not pay out more of either token
+//! than it consumes.
+//!
+//! This module is available in the dex's own tests and to other crates with the `testing`
+//! feature.
+
+use super::*;
+
+/// The value of token A or token B held in the given outputs, either as coins or as the
+/// collateral of open orders.
+pub fn value_in<T: DexConfig>(outputs: &[Output<T::Verifier>]) -> (u128, u128) {
+    outputs.iter().fold((0, 0), |(a, b), output| {
+        let payload = &output.payload;
+        if let Ok(coin) = payload.extract::<T::A>() {
+            (a + coin.value(), b)
+        } else if let Ok(coin) = payload.extract::<T::B>() {
+            (a, b + coin.value())
+        } else if let Ok(order) = payload.extract::<Order<T>>() {
+            (a + order.offer_amount, b)
+        } else if let Ok(order) = payload.extract::<Order<OppositeSide<T>>>() {
+            (a, b + order.offer_amount)
+        } else {
+            (a, b)
+        }
+    })
+}
+
+/// Assert that the given outputs hold no more of either token than the given inputs.
+///
+/// Coins and the collateral of open orders both count towards the totals.
+#[track_caller]
+pub fn assert_conservation<T: DexConfig>(
+    inputs: &[Output<T::Verifier>],
+    outputs: &[Output<T::Verifier>],
+) {
+    let (a_in, b_in) = value_in::<T>(inputs);
+    let (a_out, b_out) = value_in::<T>(outputs);
+    assert!(
+        a_out <= a_in,
+        "Outputs hold {} of token A but inputs only {}",
+        a_out,
+        a_in
+    );
+    assert!(
+        b_out <= b_in,
+        "Outputs hold {} of token B but inputs only {}",
+        b_out,
+        b_in
+    );
+}
+
+/// A builder for the inputs and outputs of a `MatchOrders` transaction.
+///
+/// Each order added to the scenario is paid exactly its ask, to exactly its payout
+/// verifier, unless the scenario is tampered with afterwards. Payouts are built with the
+/// tokens' `From<u128>` implementations.
+pub struct MatchScenario<T: DexConfig> {
+    verifier: T::Verifier,
+    inputs: Vec<Output<T::Verifier>>,
+    outputs: Vec<Output<T::Verifier>>,
+}
+
+impl<T: DexConfig> MatchScenario<T>
+where
+    T::Verifier: Clone,
+    T::A: From<u128>,
+    T::B: From<u128>,
+{
+    /// Start an empty scenario whose orders, payouts and verifiers all use the given verifier.
+    pub fn new(verifier: T::Verifier) -> Self {
+        Self {
+            verifier,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    fn output(&self, payload: impl Into<DynamicallyTypedData>) -> Output<T::Verifier> {
+        Output {
+            payload: payload.into(),
+            verifier: self.verifier.clone(),
+        }
+    }
+
+    /// Add an order offering token A and asking for token B, along with its payout.
+    pub fn offer_a(mut self, offer_amount: u128, ask_amount: u128) -> Self {
+        let order = Order::<T>::new(offer_amount, ask_amount, self.verifier.clone());
+        self.inputs.push(self.output(order));
+        self.outputs.push(self.output(T::B::from(ask_amount)));
+        self
+    }
+
+    /// Add an order offering token B and asking for token A, along with its payout.
+    pub fn offer_b(mut self, offer_amount: u128, ask_amount: u128) -> Self {
+        let order = Order::<OppositeSide<T>>::new(offer_amount, ask_amount, self.verifier.clone());
+        self.inputs.push(self.output(order));
+        self.outputs.push(self.output(T::A::from(ask_amount)));
+        self
+    }
+
+    /// Replace the payout of the order at the given index with the given payload.
+    ///
+    /// Use this to underpay an order, or to pay it in the wrong token.
+    pub fn with_payout(mut self, index: usize, payload: impl Into<DynamicallyTypedData>) -> Self {
+        self.outputs[index].payload = payload.into();
+        self
+    }
+
+    /// Protect the payout of the order at the given index with the given verifier.
+    pub fn with_payout_verifier(mut self, index: usize, verifier: T::Verifier) -> Self {
+        self.outputs[index].verifier = verifier;
+        self
+    }
+
+    /// Remove the payout of the order at the given index.
+    pub fn without_payout(mut self, index: usize) -> Self {
+        self.outputs.remove(index);
+        self
+    }
+
+    /// The inputs and outputs of the match transaction.
+    pub fn build(self) -> (Vec<Output<T::Verifier>>, Vec<Output<T::Verifier>>) {
+        (self.inputs, self.outputs)
+    }
+
+    /// Check the match transaction with the `MatchOrders` constraint checker.
+    pub fn check(&self) -> Result<TransactionPriority, DexError> {
+        <MatchOrders<T> as ConstraintChecker<T::Verifier>>::check(
+            &Default::default(),
+            &self.inputs,
+            &self.outputs,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use money::Coin;
+    use tuxedo_core::verifier::TestVerifier;
+
+    struct TestConfig;
+    impl DexConfig for TestConfig {
+        type Verifier = TestVerifier;
+        type A = Coin<0>;
+        type B = Coin<1>;
+    }
+
+    fn scenario() -> MatchScenario<TestConfig> {
+        MatchScenario::new(TestVerifier { verifies: true })
+            .offer_a(100, 150)
+            .offer_b(150, 100)
+    }
+
+    #[test]
+    fn honest_scenario_matches_and_conserves_value() {
+        let scenario = scenario();
+        assert_eq!(scenario.check(), Ok(0));
+
+        let (inputs, outputs) = scenario.build();
+        assert_conservation::<TestConfig>(&inputs, &outputs);
+    }
+
+    #[test]
+    fn tampered_scenarios_fail() {
+        assert_eq!(
+            scenario().with_payout(0, Coin::<1>(149)).check(),
+            Err(DexError::PayoutMismatch { index: 0, side: Side::OffersA })
+        );
+        assert_eq!(
+            scenario().with_payout(0, Coin::<0>(150)).check(),
+            Err(DexError::TypeError)
+        );
+        assert_eq!(
+            scenario()
+                .with_payout_verifier(1, TestVerifier { verifies: false })
+                .check(),
+            Err(DexError::PayoutVerifierMismatch { index: 1, side: Side::OffersB })
+        );
+        assert_eq!(
+            scenario().without_payout(1).check(),
+            Err(DexError::OrderAndPayoutCountDiffer)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Outputs hold 151 of token B but inputs only 150")]
+    fn overpayment_breaks_conservation() {
+        let (inputs, outputs) = scenario().with_payout(0, Coin::<1>(151)).build();
+        assert_conservation::<TestConfig>(&inputs, &outputs);
+    }
+}
@@ -18,3 +18,68 @@ type TestOrder = Order<TestConfig>;
 /// A concrete `MakeOrder` constraint checker. It uses the test config above.
 type MakeTestOrder = MakeOrder<TestConfig>;
 
+/// An output protected by the verifier that the test orders pay out to.
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+
+#[test]
+fn summing_two_coins_for_collateral_works() {
+    let order = TestOrder::new(100, 150, TestVerifier { verifies: true });
+
+    let first_coin = Coin::<0>(40);
+    let second_coin = Coin::<0>(60);
+
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(first_coin), output_from(second_coin)],
+        &[output_from(order)],
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn making_order_with_inputs_and_outputs_reversed_fails() {
+    let order = TestOrder::new(100, 150, TestVerifier { verifies: true });
+
+    let coin = Coin::<0>(100);
+
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(order)],
+        &[output_from(coin)],
+    );
+
+    assert_eq!(result, Err(DexError::TypeError));
+}
+
+#[test]
+fn default_context_is_empty() {
+    assert_eq!(TestConfig::context(), ConstraintCheckingContext::default());
+}
+
+#[test]
+fn opposite_side_shares_context() {
+    struct LateConfig;
+    impl DexConfig for LateConfig {
+        type Verifier = TestVerifier;
+        type A = Coin<0>;
+        type B = Coin<1>;
+
+        fn context() -> ConstraintCheckingContext {
+            ConstraintCheckingContext {
+                block_number: 7,
+                timestamp: 21_000,
+            }
+        }
+    }
+
+    assert_eq!(
+        OppositeSide::<LateConfig>::context(),
+        LateConfig::context()
+    );
+}
@@ -0,0 +1,259 @@
+//! Versioned encodings, so that UTXO types can gain fields in a runtime upgrade.
+//!
+//! Once a UTXO is on chain, its encoding is fixed until it is spent. If a runtime upgrade
+//! adds a field to its type, the old encoding no longer decodes, and the UTXO is orphaned.
+//! Types that may change therefore follow a convention: they are encoded as a version
+//! byte followed by the fields of that version. The type id stays the same across
+//! versions.
+//!
+//! Such types implement [`Versioned`], and forward their `Encode` and `Decode` impls to
+//! [`encode_versioned`] and [`decode_versioned`]. Outputs are always encoded in the
+//! current version. Decoding accepts every earlier version too, and migrates it to the
+//! current layout, typically by filling in defaults for the new fields. So old UTXOs are
+//! upgraded lazily, when a transaction spends them, and no migration has to rewrite the
+//! whole UTXO set.
+//!
+//! [`Order`] is versioned this way. Its first version had no expiry, its second had no
+//! cancel verifier, its third had no matcher verifier, its fourth had no bond, and its fifth
+//! did not record the block it was made in.
+
+use super::*;
+use parity_scale_codec::{Error as CodecError, Input, Output as CodecOutput};
+
+/// A type that is encoded with a leading version byte. See the [module docs](self).
+pub trait Versioned: Sized {
+    /// The version that values are encoded in. It starts at one, and must go up by one
+    /// whenever the layout changes.
+    const VERSION: u8;
+
+    /// Encode the fields of the current version, without the version byte.
+    fn encode_fields_to<O: CodecOutput + ?Sized>(&self, dest: &mut O);
+
+    /// Decode the fields of the given version, which is at most the current one, and
+    /// migrate them to the current layout.
+    fn decode_fields<I: Input>(version: u8, input: &mut I) -> Result<Self, CodecError>;
+}
+
+/// Encode a value in its current version.
+pub fn encode_versioned<V: Versioned, O: CodecOutput + ?Sized>(value: &V, dest: &mut O) {
+    V::VERSION.encode_to(dest);
+    value.encode_fields_to(dest);
+}
+
+/// Decode a value in any of its versions, migrating it to the current one.
+pub fn decode_versioned<V: Versioned, I: Input>(input: &mut I) -> Result<V, CodecError> {
+    let version = u8::decode(input)?;
+    if version == 0 || version > V::VERSION {
+        return Err("unknown version".into());
+    }
+    V::decode_fields(version, input)
+}
+
+/// The first version of [`Order`], which had no expiry.
+#[derive(Decode)]
+struct OrderV1<T: DexConfig> {
+    offer_amount: u128,
+    ask_amount: u128,
+    payout_verifier: T::Verifier,
+    payout_kind: PayoutKind,
+    owner: H256,
+}
+
+impl<T: DexConfig> From<OrderV1<T>> for Order<T> {
+    fn from(v1: OrderV1<T>) -> Self {
+        Self {
+            offer_amount: v1.offer_amount,
+            ask_amount: v1.ask_amount,
+            payout_verifier: v1.payout_verifier,
+            payout_kind: v1.payout_kind,
+            owner: v1.owner,
+            // Orders from before expiries were introduced stay open until they are
+            // matched or cancelled, as their makers intended.
+            expiry: None,
+            cancel_verifier: None,
+            matcher_verifier: None,
+            bond: 0,
+            made_at: 0,
+            _ph_data: PhantomData,
+        }
+    }
+}
+
+/// The second version of [`Order`], which had no cancel verifier.
+#[derive(Decode)]
+struct OrderV2<T: DexConfig> {
+    offer_amount: u128,
+    ask_amount: u128,
+    payout_verifier: T::Verifier,
+    payout_kind: PayoutKind,
+    owner: H256,
+    expiry: Option<u32>,
+}
+
+impl<T: DexConfig> From<OrderV2<T>> for Order<T> {
+    fn from(v2: OrderV2<T>) -> Self {
+        Self {
+            offer_amount: v2.offer_amount,
+            ask_amount: v2.ask_amount,
+            payout_verifier: v2.payout_verifier,
+            payout_kind: v2.payout_kind,
+            owner: v2.owner,
+            expiry: v2.expiry,
+            // Orders from before cancel verifiers were introduced can still be cancelled
+            // by whoever can spend them.
+            cancel_verifier: None,
+            matcher_verifier: None,
+            bond: 0,
+            made_at: 0,
+            _ph_data: PhantomData,
+        }
+    }
+}
+
+/// The third version of [`Order`], which had no matcher verifier.
+#[derive(Decode)]
+struct OrderV3<T: DexConfig> {
+    offer_amount: u128,
+    ask_amount: u128,
+    payout_verifier: T::Verifier,
+    payout_kind: PayoutKind,
+    owner: H256,
+    expiry: Option<u32>,
+    cancel_verifier: Option<T::Verifier>,
+}
+
+impl<T: DexConfig> From<OrderV3<T>> for Order<T> {
+    fn from(v3: OrderV3<T>) -> Self {
+        Self {
+            offer_amount: v3.offer_amount,
+            ask_amount: v3.ask_amount,
+            payout_verifier: v3.payout_verifier,
+            payout_kind: v3.payout_kind,
+            owner: v3.owner,
+            expiry: v3.expiry,
+            cancel_verifier: v3.cancel_verifier,
+            // Orders from before matcher verifiers were introduced can be matched by anyone.
+            matcher_verifier: None,
+            bond: 0,
+            made_at: 0,
+            _ph_data: PhantomData,
+        }
+    }
+}
+
+/// The fourth version of [`Order`], which had no bond.
+#[derive(Decode)]
+struct OrderV4<T: DexConfig> {
+    offer_amount: u128,
+    ask_amount: u128,
+    payout_verifier: T::Verifier,
+    payout_kind: PayoutKind,
+    owner: H256,
+    expiry: Option<u32>,
+    cancel_verifier: Option<T::Verifier>,
+    matcher_verifier: Option<T::Verifier>,
+}
+
+impl<T: DexConfig> From<OrderV4<T>> for Order<T> {
+    fn from(v4: OrderV4<T>) -> Self {
+        Self {
+            offer_amount: v4.offer_amount,
+            ask_amount: v4.ask_amount,
+            payout_verifier: v4.payout_verifier,
+            payout_kind: v4.payout_kind,
+            owner: v4.owner,
+            expiry: v4.expiry,
+            cancel_verifier: v4.cancel_verifier,
+            matcher_verifier: v4.matcher_verifier,
+            // Orders from before bonds were introduced locked nothing beyond their offer.
+            bond: 0,
+            made_at: 0,
+            _ph_data: PhantomData,
+        }
+    }
+}
+
+/// The fifth version of [`Order`], which did not record the block it was made in.
+#[derive(Decode)]
+struct OrderV5<T: DexConfig> {
+    offer_amount: u128,
+    ask_amount: u128,
+    payout_verifier: T::Verifier,
+    payout_kind: PayoutKind,
+    owner: H256,
+    expiry: Option<u32>,
+    cancel_verifier: Option<T::Verifier>,
+    matcher_verifier: Option<T::Verifier>,
+    bond: u128,
+}
+
+impl<T: DexConfig> From<OrderV5<T>> for Order<T> {
+    fn from(v5: OrderV5<T>) -> Self {
+        Self {
+            offer_amount: v5.offer_amount,
+            ask_amount: v5.ask_amount,
+            payout_verifier: v5.payout_verifier,
+            payout_kind: v5.payout_kind,
+            owner: v5.owner,
+            expiry: v5.expiry,
+            cancel_verifier: v5.cancel_verifier,
+            matcher_verifier: v5.matcher_verifier,
+            bond: v5.bond,
+            // Orders from before rent was introduced count as made at genesis.
+            made_at: 0,
+            _ph_data: PhantomData,
+        }
+    }
+}
+
+impl<T: DexConfig> Versioned for Order<T> {
+    const VERSION: u8 = 6;
+
+    fn encode_fields_to<O: CodecOutput + ?Sized>(&self, dest: &mut O) {
+        self.offer_amount.encode_to(dest);
+        self.ask_amount.encode_to(dest);
+        self.payout_verifier.encode_to(dest);
+        self.payout_kind.encode_to(dest);
+        self.owner.encode_to(dest);
+        self.expiry.encode_to(dest);
+        self.cancel_verifier.encode_to(dest);
+        self.matcher_verifier.encode_to(dest);
+        self.bond.encode_to(dest);
+        self.made_at.encode_to(dest);
+    }
+
+    fn decode_fields<I: Input>(version: u8, input: &mut I) -> Result<Self, CodecError> {
+        match version {
+            1 => OrderV1::<T>::decode(input).map(Into::into),
+            2 => OrderV2::<T>::decode(input).map(Into::into),
+            3 => OrderV3::<T>::decode(input).map(Into::into),
+            4 => OrderV4::<T>::decode(input).map(Into::into),
+            5 => OrderV5::<T>::decode(input).map(Into::into),
+            _ => Ok(Self {
+                offer_amount: Decode::decode(input)?,
+                ask_amount: Decode::decode(input)?,
+                payout_verifier: Decode::decode(input)?,
+                payout_kind: Decode::decode(input)?,
+                owner: Decode::decode(input)?,
+                expiry: Decode::decode(input)?,
+                cancel_verifier: Decode::decode(input)?,
+                matcher_verifier: Decode::decode(input)?,
+                bond: Decode::decode(input)?,
+                made_at: Decode::decode(input)?,
+                _ph_data: PhantomData,
+            }),
+        }
+    }
+}
+
+impl<T: DexConfig> Encode for Order<T> {
+    fn encode_to<O: CodecOutput + ?Sized>(&self, dest: &mut O) {
+        encode_versioned(self, dest)
+    }
+}
+
+impl<T: DexConfig> Decode for Order<T> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        decode_versioned(input)
+    }
+}
@@ -0,0 +1,111 @@
+use dex::*;
+use money::Coin;
+use sp_runtime::transaction_validity::TransactionPriority;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+/// The maker, who receives the payout and any refund.
+const MAKER: TestVerifier = TestVerifier { verifies: true };
+
+/// The operator, who may cancel the maker's orders.
+const OPERATOR: TestVerifier = TestVerifier { verifies: false };
+
+fn order(cancel_verifier: Option<TestVerifier>) -> Order<TestConfig> {
+    Order {
+        cancel_verifier,
+        ..Order::new(10, 5, MAKER)
+    }
+}
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn cancel(
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, DexError> {
+    <CancelOrder<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    )
+}
+
+#[test]
+fn cancelling_needs_an_input_from_the_cancel_verifier() {
+    let order = output(order(Some(OPERATOR)), MAKER);
+    let refund = output(Coin::<0>(10), MAKER);
+
+    assert_eq!(
+        cancel(vec![order.clone()], vec![refund.clone()]),
+        Err(DexError::CancelNotAuthorized)
+    );
+    assert_eq!(
+        cancel(
+            vec![order.clone(), output(Coin::<1>(1), MAKER)],
+            vec![refund.clone()]
+        ),
+        Err(DexError::CancelNotAuthorized)
+    );
+    assert_eq!(
+        cancel(vec![order, output(Coin::<1>(1), OPERATOR)], vec![refund]),
+        Ok(0)
+    );
+}
+
+#[test]
+fn refunds_still_go_to_the_payout_verifier() {
+    let inputs = vec![
+        output(order(Some(OPERATOR)), MAKER),
+        output(Coin::<1>(1), OPERATOR),
+    ];
+    assert_eq!(
+        cancel(inputs, vec![output(Coin::<0>(10), OPERATOR)]),
+        Err(DexError::VerifierMismatchForTrade)
+    );
+}
+
+#[test]
+fn the_authorizing_input_may_be_passed_through() {
+    let authority = output(Coin::<0>(3), OPERATOR);
+    let inputs = vec![output(order(Some(OPERATOR)), MAKER), authority.clone()];
+
+    assert_eq!(
+        cancel(
+            inputs.clone(),
+            vec![output(Coin::<0>(10), MAKER), authority.clone()]
+        ),
+        Ok(0)
+    );
+
+    // Only an unchanged copy of it is passed through, not more of it.
+    let grown = output(Coin::<0>(4), OPERATOR);
+    assert_eq!(
+        cancel(inputs, vec![output(Coin::<0>(10), MAKER), grown]),
+        Err(DexError::VerifierMismatchForTrade)
+    );
+}
+
+#[test]
+fn orders_without_a_cancel_verifier_are_the_only_input() {
+    let inputs = vec![output(order(None), MAKER), output(Coin::<1>(1), OPERATOR)];
+    assert_eq!(
+        cancel(inputs, vec![output(Coin::<0>(10), MAKER)]),
+        Err(DexError::CancelRequiresOneOrder)
+    );
+}
@@ -0,0 +1,274 @@
+//! Differential tests between the ways constraint checkers see a transaction.
+//!
+//! `MakeOrder`, `MatchOrders` and `CancelOrder` all see the whole outputs, verifiers
+//! included, but `MakeOrder` only looks at the verifiers of the coins it spends, to check
+//! that they belong to whoever the order pays out to. A refactor of any of them can make
+//! them disagree about which orders are valid, which would leave orders that can be made
+//! but never filled or cancelled.
+//!
+//! Each generated case is a snapshot of a transaction's full inputs and outputs. These
+//! tests require `MakeOrder` to depend on the verifiers only through the ownership of the
+//! coins, and require the other checkers to accept every order that `MakeOrder` accepts,
+//! whenever the orders could be filled or cancelled.
+
+use dex::*;
+use money::Coin;
+use proptest::prelude::*;
+use sp_runtime::transaction_validity::TransactionPriority;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+/// The largest amount used in generated orders and coins. It is small enough that sums
+/// over any generated transaction cannot overflow.
+const MAX_AMOUNT: u128 = 1 << 64;
+
+/// A snapshot of the full inputs and outputs of a transaction.
+#[derive(Debug, Clone)]
+struct Snapshot {
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+}
+
+impl Snapshot {
+    /// The same snapshot with every coin it spends protected by the given verifier instead.
+    fn with_inputs_protected_by(&self, verifier: &TestVerifier) -> Self {
+        Self {
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| output_from(input.payload.clone(), verifier.clone()))
+                .collect(),
+            outputs: self.outputs.clone(),
+        }
+    }
+
+    /// The same snapshot with the verifier of every output flipped, which `MakeOrder` may
+    /// not notice.
+    fn with_outputs_flipped(&self) -> Self {
+        Self {
+            inputs: self.inputs.clone(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|output| {
+                    output_from(
+                        output.payload.clone(),
+                        TestVerifier {
+                            verifies: !output.verifier.verifies,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Check the snapshot with a full checker.
+fn check<C: ConstraintChecker<TestVerifier, Error = DexError> + Default>(
+    snapshot: &Snapshot,
+) -> Result<TransactionPriority, DexError> {
+    C::default().check(&snapshot.inputs, &snapshot.outputs)
+}
+
+/// Check that making an order with the snapshot depends on the verifiers only through
+/// whether the coins it spends belong to the order's payout verifier.
+fn check_ownership<C: ConstraintChecker<TestVerifier, Error = DexError> + Default>(
+    snapshot: &Snapshot,
+    payout_verifier: &TestVerifier,
+) -> Result<(), TestCaseError> {
+    let result = check::<C>(snapshot);
+    let owned = snapshot.with_inputs_protected_by(payout_verifier);
+    let owned_result = check::<C>(&owned);
+    prop_assert_eq!(&owned_result, &check::<C>(&owned.with_outputs_flipped()));
+
+    let all_owned = snapshot
+        .inputs
+        .iter()
+        .all(|input| input.verifier == *payout_verifier);
+    if all_owned {
+        prop_assert_eq!(&result, &owned_result);
+    } else {
+        prop_assert!(result.is_err());
+        if owned_result.is_ok() {
+            prop_assert_eq!(result, Err(DexError::CollateralNotOwned));
+        }
+    }
+    Ok(())
+}
+
+fn amount() -> impl Strategy<Value = u128> {
+    prop_oneof![1..=1_000u128, Just(0), Just(MAX_AMOUNT), 0..=MAX_AMOUNT]
+}
+
+fn verifier() -> impl Strategy<Value = TestVerifier> {
+    any::<bool>().prop_map(|verifies| TestVerifier { verifies })
+}
+
+fn output_from<T: Into<DynamicallyTypedData>>(
+    payload: T,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+/// The terms of a generated order, on either side of the pair.
+#[derive(Debug, Clone)]
+struct Terms {
+    offer_amount: u128,
+    ask_amount: u128,
+    payout_verifier: TestVerifier,
+}
+
+fn terms() -> impl Strategy<Value = Terms> {
+    (amount(), amount(), verifier()).prop_map(|(offer_amount, ask_amount, payout_verifier)| Terms {
+        offer_amount,
+        ask_amount,
+        payout_verifier,
+    })
+}
+
+fn order<T: DexConfig<Verifier = TestVerifier>>(terms: &Terms) -> Order<T> {
+    Order::new(
+        terms.offer_amount,
+        terms.ask_amount,
+        terms.payout_verifier.clone(),
+    )
+}
+
+/// A snapshot of a transaction making an order offering token A with the given terms,
+/// from coins of the given amounts, in token A or, where the flag is unset, in token B.
+fn make_a(
+    terms: &Terms,
+    coins: &[(u128, bool, TestVerifier)],
+    order_verifier: TestVerifier,
+) -> Snapshot {
+    Snapshot {
+        inputs: coins
+            .iter()
+            .map(|(value, in_a, verifier)| {
+                if *in_a {
+                    output_from(Coin::<0>(*value), verifier.clone())
+                } else {
+                    output_from(Coin::<1>(*value), verifier.clone())
+                }
+            })
+            .collect(),
+        outputs: vec![output_from(order::<TestConfig>(terms), order_verifier)],
+    }
+}
+
+/// Like [`make_a`], for an order offering token B, from coins in token B unless the flag
+/// is unset.
+fn make_b(
+    terms: &Terms,
+    coins: &[(u128, bool, TestVerifier)],
+    order_verifier: TestVerifier,
+) -> Snapshot {
+    Snapshot {
+        inputs: coins
+            .iter()
+            .map(|(value, in_b, verifier)| {
+                if *in_b {
+                    output_from(Coin::<1>(*value), verifier.clone())
+                } else {
+                    output_from(Coin::<0>(*value), verifier.clone())
+                }
+            })
+            .collect(),
+        outputs: vec![output_from(
+            order::<OppositeSide<TestConfig>>(terms),
+            order_verifier,
+        )],
+    }
+}
+
+/// Coins to make an order from. Most are in the offered token, so that plenty of orders
+/// are made.
+fn coins() -> impl Strategy<Value = Vec<(u128, bool, TestVerifier)>> {
+    prop::collection::vec((amount(), prop::bool::weighted(0.9), verifier()), 0..4)
+}
+
+/// The one coin that makes an order with the given terms, worth exactly its offer and
+/// owned by its payout verifier.
+fn exact_coin(terms: &Terms) -> Vec<(u128, bool, TestVerifier)> {
+    vec![(terms.offer_amount, true, terms.payout_verifier.clone())]
+}
+
+proptest! {
+    #[test]
+    fn making_orders_only_sees_who_owns_the_coins(
+        terms in terms(),
+        coins in coins(),
+        order_verifier in verifier(),
+        extra_output in prop::option::weighted(0.1, amount()),
+    ) {
+        let mut offers_a = make_a(&terms, &coins, order_verifier.clone());
+        let mut offers_b = make_b(&terms, &coins, order_verifier);
+        if let Some(value) = extra_output {
+            offers_a.outputs.push(output_from(Coin::<0>(value), TestVerifier { verifies: true }));
+            offers_b.outputs.push(output_from(Coin::<1>(value), TestVerifier { verifies: true }));
+        }
+
+        check_ownership::<MakeOrder<TestConfig>>(&offers_a, &terms.payout_verifier)?;
+        check_ownership::<MakeOrder<OppositeSide<TestConfig>>>(&offers_b, &terms.payout_verifier)?;
+    }
+
+    #[test]
+    fn made_orders_that_cross_can_be_matched(a in terms(), b in terms()) {
+        let made_a = make_a(&a, &exact_coin(&a), a.payout_verifier.clone());
+        let made_b = make_b(&b, &exact_coin(&b), b.payout_verifier.clone());
+        let accepted = check::<MakeOrder<TestConfig>>(&made_a).is_ok()
+            && check::<MakeOrder<OppositeSide<TestConfig>>>(&made_b).is_ok();
+        let crossing = a.offer_amount >= b.ask_amount && b.offer_amount >= a.ask_amount;
+        prop_assume!(accepted && crossing);
+
+        // Whichever order comes first, a match paying both orders exactly what they ask
+        // must be accepted.
+        let payout_a = output_from(Coin::<1>(a.ask_amount), a.payout_verifier.clone());
+        let payout_b = output_from(Coin::<0>(b.ask_amount), b.payout_verifier.clone());
+        let order_a = made_a.outputs[0].clone();
+        let order_b = made_b.outputs[0].clone();
+        for (inputs, outputs) in [
+            (vec![order_a.clone(), order_b.clone()], vec![payout_a.clone(), payout_b.clone()]),
+            (vec![order_b, order_a], vec![payout_b, payout_a]),
+        ] {
+            let result = <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+                &Default::default(),
+                &inputs,
+                &outputs,
+            );
+            prop_assert_eq!(result, Ok(0));
+        }
+    }
+
+    #[test]
+    fn made_orders_can_be_cancelled_in_full(terms in terms(), coins in coins()) {
+        let made = make_a(&terms, &coins, TestVerifier { verifies: true })
+            .with_inputs_protected_by(&terms.payout_verifier);
+        prop_assume!(check::<MakeOrder<TestConfig>>(&made).is_ok());
+
+        let refund = |value: u128| {
+            <CancelOrder<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+                &Default::default(),
+                &made.outputs,
+                &[output_from(Coin::<0>(value), terms.payout_verifier.clone())],
+            )
+        };
+        prop_assert_eq!(refund(terms.offer_amount), Ok(0));
+        prop_assert_eq!(
+            refund(terms.offer_amount + 1),
+            Err(DexError::RefundExceedsOrder)
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use dex::commit_reveal::order_commitment;
+use dex::*;
+use money::Coin;
+use sp_core::H256;
+use std::cell::Cell;
+use tuxedo_core::{verifier::TestVerifier, SimpleConstraintChecker};
+
+thread_local! {
+    static BLOCK_NUMBER: Cell<u32> = Cell::new(0);
+}
+
+fn set_block_number(block_number: u32) {
+    BLOCK_NUMBER.with(|b| b.set(block_number));
+}
+
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const REVEAL_WINDOW: u32 = 5;
+
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext {
+            block_number: BLOCK_NUMBER.with(|b| b.get()),
+            timestamp: 0,
+        }
+    }
+}
+
+fn order() -> Order<TestConfig> {
+    Order::new(100, 150, TestVerifier { verifies: true })
+}
+
+fn salt() -> H256 {
+    H256::repeat_byte(7)
+}
+
+/// A commitment to `order()` with `salt()`, made in block 10.
+fn commitment() -> OrderCommitment<TestConfig> {
+    OrderCommitment {
+        commitment: order_commitment(&order(), &salt()),
+        collateral: 100,
+        committed_at: 10,
+        _ph_data: Default::default(),
+    }
+}
+
+fn reveal(salt: H256) -> RevealOrder<TestConfig> {
+    RevealOrder {
+        salt,
+        _ph_data: Default::default(),
+    }
+}
+
+#[test]
+fn committing_to_an_order_works() {
+    set_block_number(10);
+    let result = <CommitOrder<TestConfig> as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![Coin::<0>(100).into()],
+        &vec![commitment().into()],
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn committing_for_another_block_fails() {
+    set_block_number(11);
+    let result = <CommitOrder<TestConfig> as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![Coin::<0>(100).into()],
+        &vec![commitment().into()],
+    );
+    assert_eq!(result, Err(DexError::CommitmentForWrongBlock));
+}
+
+#[test]
+fn committing_without_enough_collateral_fails() {
+    set_block_number(10);
+    let result = <CommitOrder<TestConfig> as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![Coin::<0>(99).into()],
+        &vec![commitment().into()],
+    );
+    assert_eq!(result, Err(DexError::NotEnoughCollateralToOpenOrder));
+}
+
+#[test]
+fn revealing_within_the_window_works() {
+    for block_number in 11..=15 {
+        set_block_number(block_number);
+        let result = <RevealOrder<TestConfig> as SimpleConstraintChecker>::check(
+            &reveal(salt()),
+            &vec![commitment().into()],
+            &vec![order().into()],
+        );
+        assert!(result.is_ok());
+    }
+}
+
+#[test]
+fn revealing_in_the_same_block_fails() {
+    set_block_number(10);
+    let result = <RevealOrder<TestConfig> as SimpleConstraintChecker>::check(
+        &reveal(salt()),
+        &vec![commitment().into()],
+        &vec![order().into()],
+    );
+    assert_eq!(result, Err(DexError::RevealTooEarly));
+}
+
+#[test]
+fn revealing_after_the_window_fails() {
+    set_block_number(16);
+    let result = <RevealOrder<TestConfig> as SimpleConstraintChecker>::check(
+        &reveal(salt()),
+        &vec![commitment().into()],
+        &vec![order().into()],
+    );
+    assert_eq!(result, Err(DexError::RevealWindowExpired));
+}
+
+#[test]
+fn revealing_with_wrong_salt_fails() {
+    set_block_number(11);
+    let result = <RevealOrder<TestConfig> as SimpleConstraintChecker>::check(
+        &reveal(H256::repeat_byte(8)),
+        &vec![commitment().into()],
+        &vec![order().into()],
+    );
+    assert_eq!(result, Err(DexError::RevealDoesNotMatchCommitment));
+}
+
+#[test]
+fn revealing_different_terms_fails() {
+    set_block_number(11);
+    let mut cheaper = order();
+    cheaper.ask_amount = 100;
+
+    let result = <RevealOrder<TestConfig> as SimpleConstraintChecker>::check(
+        &reveal(salt()),
+        &vec![commitment().into()],
+        &vec![cheaper.into()],
+    );
+    assert_eq!(result, Err(DexError::RevealDoesNotMatchCommitment));
+}
+
+#[test]
+fn revealing_more_than_the_collateral_fails() {
+    set_block_number(11);
+    let mut commitment = commitment();
+    commitment.collateral = 99;
+
+    let result = <RevealOrder<TestConfig> as SimpleConstraintChecker>::check(
+        &reveal(salt()),
+        &vec![commitment.into()],
+        &vec![order().into()],
+    );
+    assert_eq!(result, Err(DexError::NotEnoughCollateralToOpenOrder));
+}
+
+#[test]
+fn reclaiming_an_expired_commitment_works() {
+    set_block_number(16);
+    let result = <ReclaimCommitment<TestConfig> as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![commitment().into()],
+        &vec![Coin::<0>(60).into(), Coin::<0>(40).into()],
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn reclaiming_before_the_deadline_fails() {
+    set_block_number(15);
+    let result = <ReclaimCommitment<TestConfig> as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![commitment().into()],
+        &vec![Coin::<0>(100).into()],
+    );
+    assert_eq!(result, Err(DexError::CommitmentNotExpired));
+}
+
+#[test]
+fn reclaiming_more_than_the_collateral_fails() {
+    set_block_number(16);
+    let result = <ReclaimCommitment<TestConfig> as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![commitment().into()],
+        &vec![Coin::<0>(101).into()],
+    );
+    assert_eq!(result, Err(DexError::ReclaimExceedsCollateral));
+}
@@ -1,6 +1,8 @@
 use dex::*;
 use money::Coin;
-use tuxedo_core::{SimpleConstraintChecker, verifier::TestVerifier};
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
 
 struct TestConfig;
 impl DexConfig for TestConfig {
@@ -13,11 +15,13 @@ type TestOrder = Order<TestConfig>;
 type MakeTestOrder = MakeOrder<TestConfig>;
 
 fn a_for_b_order(offer_amount: u128, ask_amount: u128) -> TestOrder {
-    Order {
-        offer_amount,
-        ask_amount,
-        payout_verifier: TestVerifier { verifies: true },
-        _ph_data: Default::default(),
+    Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
+}
+
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
     }
 }
 
@@ -26,10 +30,10 @@ fn opening_an_order_seeking_a_works() {
     let order = a_for_b_order(100, 150);
     let input = Coin::<0>(100);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![order.into()],
+        &[output_from(input)],
+        &[output_from(order)],
     );
     assert!(result.is_ok());
 }
@@ -38,10 +42,10 @@ fn opening_an_order_seeking_a_works() {
 fn opening_order_with_no_inputs_fails() {
     let order = a_for_b_order(100, 150);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![],
-        &vec![order.into()],
+        &[],
+        &[output_from(order)],
     );
     assert_eq!(result, Err(DexError::NotEnoughCollateralToOpenOrder));
 }
@@ -50,10 +54,10 @@ fn opening_order_with_no_inputs_fails() {
 fn opening_order_with_no_outputs_fails() {
     let input = Coin::<0>(100);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![],
+        &[output_from(input)],
+        &[],
     );
     assert_eq!(result, Err(DexError::OrderMissing));
 }
@@ -64,10 +68,10 @@ fn opening_order_with_insufficient_collateral_fails() {
     let input = Coin::<0>(50);
     let order = a_for_b_order(100, 150);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![order.into()],
+        &[output_from(input)],
+        &[output_from(order)],
     );
 
     assert_eq!(result, Err(DexError::NotEnoughCollateralToOpenOrder));
@@ -79,10 +83,10 @@ fn opening_order_with_collateral_in_wrong_asset() {
     let input = Coin::<1>(100);
     let order = a_for_b_order(100, 150);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![order.into()],
+        &[output_from(input)],
+        &[output_from(order)],
     );
 
     assert_eq!(result, Err(DexError::TypeError));
@@ -0,0 +1,170 @@
+use dex::*;
+use money::Coin;
+use std::cell::Cell;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+thread_local! {
+    static BLOCK: Cell<u32> = Cell::new(0);
+}
+
+fn set_block(block: u32) {
+    BLOCK.with(|b| b.set(block));
+}
+
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext {
+            block_number: BLOCK.with(|b| b.get()),
+            timestamp: 0,
+        }
+    }
+}
+
+type BuyTestAuction = BuyAuction<TestConfig>;
+
+/// The verifier that the proceeds of the auction go to.
+const SELLER: TestVerifier = TestVerifier { verifies: false };
+/// The verifier of the buyer.
+const BUYER: TestVerifier = TestVerifier { verifies: true };
+
+/// An auction of 100 of token A that starts at a price of 2, and falls to 1 over 10
+/// blocks from block 10.
+fn auction() -> DutchAuction<TestConfig> {
+    DutchAuction {
+        lot: 100,
+        start_price: Price(2 * Price::ONE.0),
+        floor_price: Price::ONE,
+        started_at: 10,
+        duration: 10,
+        payout_verifier: SELLER,
+        _ph_data: Default::default(),
+    }
+}
+
+fn output_from<T: Into<DynamicallyTypedData>>(
+    payload: T,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn buy(payment: u128, outputs: &[Output<TestVerifier>]) -> Result<u64, DexError> {
+    <BuyTestAuction as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[
+            output_from(auction(), BUYER),
+            output_from(Coin::<1>(payment), BUYER),
+        ],
+        outputs,
+    )
+}
+
+#[test]
+fn auction_price_falls_to_the_floor() {
+    let auction = auction();
+    assert_eq!(auction.price_at(0), Price(2 * Price::ONE.0));
+    assert_eq!(auction.price_at(10), Price(2 * Price::ONE.0));
+    assert_eq!(auction.ask_at(13), Some(170));
+    assert_eq!(auction.ask_at(20), Some(100));
+    assert_eq!(auction.ask_at(1_000), Some(100));
+}
+
+#[test]
+fn auction_price_does_not_start_below_the_floor() {
+    let auction = DutchAuction::<TestConfig> {
+        start_price: Price(Price::ONE.0 / 2),
+        ..auction()
+    };
+    assert_eq!(auction.price_at(11), Price::ONE);
+}
+
+#[test]
+fn buying_an_auction_at_its_current_price_works() {
+    set_block(15);
+    let result = buy(
+        200,
+        &[
+            output_from(Coin::<0>(100), BUYER),
+            output_from(Coin::<1>(150), SELLER),
+            output_from(Coin::<1>(50), BUYER),
+        ],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn buying_an_auction_for_less_than_it_asks_fails() {
+    set_block(15);
+    let result = buy(
+        149,
+        &[
+            output_from(Coin::<0>(100), BUYER),
+            output_from(Coin::<1>(149), SELLER),
+        ],
+    );
+    assert_eq!(result, Err(DexError::AuctionUnderpaid));
+}
+
+#[test]
+fn paying_the_seller_less_than_the_ask_fails() {
+    set_block(15);
+    let result = buy(
+        200,
+        &[
+            output_from(Coin::<0>(100), BUYER),
+            output_from(Coin::<1>(149), SELLER),
+            output_from(Coin::<1>(51), BUYER),
+        ],
+    );
+    assert_eq!(result, Err(DexError::PayoutDoesNotSatisfyOrder));
+}
+
+#[test]
+fn paying_someone_other_than_the_seller_fails() {
+    set_block(15);
+    let result = buy(
+        150,
+        &[
+            output_from(Coin::<0>(100), BUYER),
+            output_from(Coin::<1>(150), BUYER),
+        ],
+    );
+    assert_eq!(result, Err(DexError::VerifierMismatchForTrade));
+}
+
+#[test]
+fn taking_more_than_the_lot_fails() {
+    set_block(15);
+    let result = buy(
+        150,
+        &[
+            output_from(Coin::<0>(101), BUYER),
+            output_from(Coin::<1>(150), SELLER),
+        ],
+    );
+    assert_eq!(result, Err(DexError::LotNotDelivered));
+}
+
+#[test]
+fn taking_more_change_than_is_left_fails() {
+    set_block(20);
+    let result = buy(
+        150,
+        &[
+            output_from(Coin::<0>(100), BUYER),
+            output_from(Coin::<1>(100), SELLER),
+            output_from(Coin::<1>(51), BUYER),
+        ],
+    );
+    assert_eq!(result, Err(DexError::ChangeExceedsLeftoverPayment));
+}
@@ -33,4 +33,42 @@ fn from_dynamic_typing_error_is_implemented_properly_for_wrong_type() {
     let dte = DynamicTypingError::WrongType;
     let de: DexError = dte.into();
     assert_eq!(de, DexError::TypeError);
-}
\ No newline at end of file
+}
+#[test]
+fn error_codes_are_stable() {
+    assert_eq!(TypeError.code(), 0);
+    assert_eq!(NotEnoughCollateralToOpenOrder.code(), 3);
+    assert_eq!(OrderExpired.code(), 18);
+    assert_eq!(TradingPaused.code(), 27);
+    assert_eq!(ChangeExceedsLeftoverPayment.code(), 51);
+    assert_eq!(CancelNotAuthorized.code(), 52);
+    assert_eq!(MatcherNotAuthorized.code(), 53);
+    assert_eq!(WrongOrderBond.code(), 54);
+    assert_eq!(BondNotRefunded.code(), 55);
+    assert_eq!(SweepRewardTooLarge.code(), 59);
+    assert_eq!(CollateralNotOwned.code(), 60);
+    assert_eq!(SameToken.code(), 61);
+    assert_eq!(LotNotDelivered.code(), 63);
+    assert_eq!(ReferralNotPaid.code(), 64);
+    assert_eq!(DexError::message_for_code(65), None);
+}
+
+#[test]
+fn errors_display_their_messages() {
+    for error in [TypeError, OrderExpired, PairNotListed] {
+        assert_eq!(
+            DexError::message_for_code(error.code()),
+            Some(error.to_string().as_str())
+        );
+    }
+
+    let error = PayoutMismatch {
+        index: 2,
+        side: dex::Side::OffersB,
+    };
+    let message = DexError::message_for_code(error.code()).unwrap();
+    assert_eq!(
+        error.to_string(),
+        format!("{message} (order 2, offering token B)")
+    );
+}
@@ -0,0 +1,176 @@
+use dex::iceberg::tranche_commitment;
+use dex::*;
+use money::Coin;
+use sp_core::H256;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+    SimpleConstraintChecker,
+};
+
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+type TestIceberg = IcebergOrder<TestConfig>;
+
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+/// The last tranche: 100 of token A for 120 of token B.
+fn last_tranche() -> TestIceberg {
+    IcebergOrder {
+        offer_amount: 100,
+        ask_amount: 120,
+        collateral: 100,
+        payout_verifier: TestVerifier { verifies: true },
+        next_tranche: None,
+        salt: H256::repeat_byte(2),
+        _ph_data: Default::default(),
+    }
+}
+
+/// The first tranche: 100 of token A for 110 of token B, followed by the last tranche.
+fn first_tranche() -> TestIceberg {
+    let last = last_tranche();
+    IcebergOrder {
+        offer_amount: 100,
+        ask_amount: 110,
+        collateral: 200,
+        payout_verifier: TestVerifier { verifies: true },
+        next_tranche: Some(tranche_commitment(
+            last.offer_amount,
+            last.ask_amount,
+            last.next_tranche,
+            last.salt,
+        )),
+        salt: H256::repeat_byte(1),
+        _ph_data: Default::default(),
+    }
+}
+
+fn taker(offer_amount: u128, ask_amount: u128) -> Output<TestVerifier> {
+    output_from(Order::<OppositeSide<TestConfig>>::new(
+        offer_amount,
+        ask_amount,
+        TestVerifier { verifies: true },
+    ))
+}
+
+#[test]
+fn opening_an_iceberg_works() {
+    let result = <MakeIceberg<TestConfig> as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![Coin::<0>(150).into(), Coin::<0>(50).into()],
+        &vec![first_tranche().into()],
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn opening_an_iceberg_with_insufficient_collateral_fails() {
+    let result = <MakeIceberg<TestConfig> as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![Coin::<0>(150).into()],
+        &vec![first_tranche().into()],
+    );
+    assert_eq!(result, Err(DexError::NotEnoughCollateralToOpenOrder));
+}
+
+#[test]
+fn last_tranche_must_offer_all_collateral() {
+    let mut order = last_tranche();
+    order.collateral = 200;
+
+    let result = <MakeIceberg<TestConfig> as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![Coin::<0>(200).into()],
+        &vec![order.into()],
+    );
+    assert_eq!(result, Err(DexError::MalformedIcebergTranche));
+}
+
+#[test]
+fn matching_a_tranche_reveals_the_next() {
+    let result = <MatchIceberg<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(first_tranche()), taker(110, 100)],
+        &vec![
+            output_from(Coin::<1>(110)),
+            output_from(Coin::<0>(100)),
+            output_from(last_tranche()),
+        ],
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn matching_the_last_tranche_needs_no_reveal() {
+    let result = <MatchIceberg<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(last_tranche()), taker(120, 100)],
+        &vec![output_from(Coin::<1>(120)), output_from(Coin::<0>(100))],
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn revealing_different_terms_fails() {
+    let mut cheaper = last_tranche();
+    cheaper.ask_amount = 50;
+
+    let result = <MatchIceberg<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(first_tranche()), taker(110, 100)],
+        &vec![
+            output_from(Coin::<1>(110)),
+            output_from(Coin::<0>(100)),
+            output_from(cheaper),
+        ],
+    );
+    assert_eq!(result, Err(DexError::TrancheDoesNotMatchCommitment));
+}
+
+#[test]
+fn skipping_the_reveal_fails() {
+    let result = <MatchIceberg<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(first_tranche()), taker(110, 100)],
+        &vec![output_from(Coin::<1>(110)), output_from(Coin::<0>(100))],
+    );
+    assert_eq!(result, Err(DexError::OrderAndPayoutCountDiffer));
+}
+
+#[test]
+fn taking_more_than_the_visible_tranche_fails() {
+    let result = <MatchIceberg<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(first_tranche()), taker(110, 101)],
+        &vec![
+            output_from(Coin::<1>(110)),
+            output_from(Coin::<0>(101)),
+            output_from(last_tranche()),
+        ],
+    );
+    assert_eq!(result, Err(DexError::InsufficientTokenAForMatch));
+}
+
+#[test]
+fn underpaying_the_maker_fails() {
+    let result = <MatchIceberg<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(first_tranche()), taker(109, 100)],
+        &vec![
+            output_from(Coin::<1>(109)),
+            output_from(Coin::<0>(100)),
+            output_from(last_tranche()),
+        ],
+    );
+    assert_eq!(result, Err(DexError::PayoutMismatch { index: 0, side: Side::OffersA }));
+}
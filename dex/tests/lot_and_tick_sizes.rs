@@ -0,0 +1,127 @@
+use dex::*;
+use money::Coin;
+use sp_runtime::transaction_validity::TransactionPriority;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+/// Token A trades in lots of 10 and token B in ticks of 5.
+struct GridConfig;
+impl DexConfig for GridConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const LOT_SIZE: u128 = 10;
+    const TICK_SIZE: u128 = 5;
+}
+
+fn order<T: DexConfig<Verifier = TestVerifier>>(offer_amount: u128, ask_amount: u128) -> Order<T> {
+    Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
+}
+
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+fn make_a_for_b(offer_amount: u128, ask_amount: u128) -> Result<TransactionPriority, DexError> {
+    <MakeOrder<GridConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(Coin::<0>(offer_amount))],
+        &[output_from(order::<GridConfig>(offer_amount, ask_amount))],
+    )
+}
+
+fn make_b_for_a(offer_amount: u128, ask_amount: u128) -> Result<TransactionPriority, DexError> {
+    <MakeOrder<OppositeSide<GridConfig>> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(Coin::<1>(offer_amount))],
+        &[output_from(order::<OppositeSide<GridConfig>>(
+            offer_amount,
+            ask_amount,
+        ))],
+    )
+}
+
+#[test]
+fn default_sizes_allow_any_amounts() {
+    struct DefaultConfig;
+    impl DexConfig for DefaultConfig {
+        type Verifier = TestVerifier;
+        type A = Coin<0>;
+        type B = Coin<1>;
+    }
+
+    let result = <MakeOrder<DefaultConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(Coin::<0>(7))],
+        &[output_from(order::<DefaultConfig>(7, 13))],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn amounts_on_the_grid_work() {
+    assert_eq!(make_a_for_b(10, 5), Ok(0));
+    assert_eq!(make_a_for_b(1_000, 1_005), Ok(0));
+}
+
+#[test]
+fn offer_just_off_the_lot_size_fails() {
+    assert_eq!(make_a_for_b(9, 5), Err(DexError::OfferAmountOffGrid));
+    assert_eq!(make_a_for_b(11, 5), Err(DexError::OfferAmountOffGrid));
+}
+
+#[test]
+fn ask_just_off_the_tick_size_fails() {
+    assert_eq!(make_a_for_b(10, 4), Err(DexError::AskAmountOffGrid));
+    assert_eq!(make_a_for_b(10, 6), Err(DexError::AskAmountOffGrid));
+}
+
+#[test]
+fn amounts_below_one_lot_or_tick_fail() {
+    assert_eq!(make_a_for_b(1, 5), Err(DexError::OfferAmountOffGrid));
+    assert_eq!(make_a_for_b(10, 1), Err(DexError::AskAmountOffGrid));
+}
+
+#[test]
+fn largest_amounts_on_the_grid_work() {
+    // The largest multiples of each size that fit in a u128.
+    let max_lots = u128::MAX - u128::MAX % 10;
+    let max_ticks = u128::MAX - u128::MAX % 5;
+    assert_eq!(make_a_for_b(max_lots, max_ticks), Ok(0));
+    assert_eq!(
+        make_a_for_b(u128::MAX, max_ticks),
+        Err(DexError::OfferAmountOffGrid)
+    );
+}
+
+#[test]
+fn opposite_side_swaps_lot_and_tick_sizes() {
+    // This order offers token B, so its offer is measured in ticks and its ask in lots.
+    assert_eq!(make_b_for_a(5, 10), Ok(0));
+    assert_eq!(make_b_for_a(10, 5), Err(DexError::AskAmountOffGrid));
+    assert_eq!(make_b_for_a(7, 10), Err(DexError::OfferAmountOffGrid));
+}
+
+#[test]
+fn zero_sizes_reject_every_order() {
+    struct ZeroLotConfig;
+    impl DexConfig for ZeroLotConfig {
+        type Verifier = TestVerifier;
+        type A = Coin<0>;
+        type B = Coin<1>;
+
+        const LOT_SIZE: u128 = 0;
+    }
+
+    let result = <MakeOrder<ZeroLotConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(Coin::<0>(10))],
+        &[output_from(order::<ZeroLotConfig>(10, 5))],
+    );
+    assert_eq!(result, Err(DexError::OfferAmountOffGrid));
+}
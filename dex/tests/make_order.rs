@@ -1,15 +1,27 @@
 use dex::*;
 use money::Coin;
-use tuxedo_core::{verifier::TestVerifier, SimpleConstraintChecker};
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
 
-type TestOrder = Order<TestVerifier>;
-type MakeTestOrder = MakeOrder<TestVerifier>;
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+type TestOrder = Order<TestConfig>;
+type MakeTestOrder = MakeOrder<TestConfig>;
 
 fn a_for_b_order(offer_amount: u128, ask_amount: u128) -> TestOrder {
-    Order {
-        offer_amount,
-        ask_amount,
-        payout_verifier: TestVerifier { verifies: true },
+    Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
+}
+
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
     }
 }
 
@@ -18,10 +30,10 @@ fn opening_an_order_seeking_a_works() {
     let order = a_for_b_order(100, 150);
     let input = Coin::<0>(100);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![order.into()],
+        &[output_from(input)],
+        &[output_from(order)],
     );
     assert!(result.is_ok());
 }
@@ -30,10 +42,10 @@ fn opening_an_order_seeking_a_works() {
 fn opening_order_with_no_inputs_fails() {
     let order = a_for_b_order(100, 150);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![],
-        &vec![order.into()],
+        &[],
+        &[output_from(order)],
     );
     assert_eq!(result, Err(DexError::NotEnoughCollateralToOpenOrder));
 }
@@ -42,10 +54,10 @@ fn opening_order_with_no_inputs_fails() {
 fn opening_order_with_no_outputs_fails() {
     let input = Coin::<0>(100);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![],
+        &[output_from(input)],
+        &[],
     );
     assert_eq!(result, Err(DexError::OrderMissing));
 }
@@ -56,10 +68,10 @@ fn opening_order_with_insufficient_collateral_fails() {
     let input = Coin::<0>(50);
     let order = a_for_b_order(100, 150);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![order.into()],
+        &[output_from(input)],
+        &[output_from(order)],
     );
 
     assert_eq!(result, Err(DexError::NotEnoughCollateralToOpenOrder));
@@ -71,11 +83,143 @@ fn opening_order_with_collateral_in_wrong_asset() {
     let input = Coin::<1>(100);
     let order = a_for_b_order(100, 150);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![order.into()],
+        &[output_from(input)],
+        &[output_from(order)],
     );
 
     assert_eq!(result, Err(DexError::TypeError));
-}
\ No newline at end of file
+}
+#[test]
+fn opening_order_offering_nothing_fails() {
+    let order = a_for_b_order(0, 150);
+
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[],
+        &[output_from(order)],
+    );
+
+    assert_eq!(result, Err(DexError::ZeroAmountOrder));
+}
+
+#[test]
+fn opening_order_asking_nothing_fails() {
+    let input = Coin::<0>(100);
+    let order = a_for_b_order(100, 0);
+
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(input)],
+        &[output_from(order)],
+    );
+
+    assert_eq!(result, Err(DexError::ZeroAmountOrder));
+}
+
+struct GiveawayConfig;
+impl DexConfig for GiveawayConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const ALLOW_GIVEAWAYS: bool = true;
+}
+
+#[test]
+fn giveaways_can_be_allowed() {
+    let input = Coin::<0>(100);
+    let order = Order::<GiveawayConfig>::new(100, 0, TestVerifier { verifies: true });
+
+    let result = <MakeOrder<GiveawayConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(input)],
+        &[output_from(order)],
+    );
+
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn giveaways_must_still_offer_something() {
+    let order = Order::<GiveawayConfig>::new(0, 0, TestVerifier { verifies: true });
+
+    let result = <MakeOrder<GiveawayConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[],
+        &[output_from(order)],
+    );
+
+    assert_eq!(result, Err(DexError::ZeroAmountOrder));
+}
+
+#[test]
+fn opening_order_with_someone_elses_coins_fails() {
+    let order = a_for_b_order(100, 150);
+    let theirs = Output {
+        payload: Coin::<0>(60).into(),
+        verifier: TestVerifier { verifies: false },
+    };
+
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(Coin::<0>(40)), theirs],
+        &[output_from(order)],
+    );
+
+    assert_eq!(result, Err(DexError::CollateralNotOwned));
+}
+
+struct UnboundConfig;
+impl DexConfig for UnboundConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    fn owns_collateral(_: &TestVerifier, _: &TestVerifier) -> bool {
+        true
+    }
+}
+
+#[test]
+fn collateral_ownership_can_be_opted_out_of() {
+    let order = Order::<UnboundConfig>::new(100, 150, TestVerifier { verifies: true });
+    let theirs = Output {
+        payload: Coin::<0>(100).into(),
+        verifier: TestVerifier { verifies: false },
+    };
+
+    let result = <MakeOrder<UnboundConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[theirs],
+        &[output_from(order)],
+    );
+
+    assert_eq!(result, Ok(0));
+}
+
+/// A dex whose tokens are only found out to be the same at run time.
+struct SelfTradingConfig;
+impl DexConfig for SelfTradingConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    fn same_token() -> bool {
+        true
+    }
+}
+
+#[test]
+fn opening_order_trading_a_token_for_itself_fails() {
+    let order = Order::<SelfTradingConfig>::new(100, 150, TestVerifier { verifies: true });
+
+    let result = <MakeOrder<SelfTradingConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output_from(Coin::<0>(100))],
+        &[output_from(order)],
+    );
+
+    assert_eq!(result, Err(DexError::SameToken));
+}
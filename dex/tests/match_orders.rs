@@ -1,6 +1,6 @@
 use dex::*;
 use money::Coin;
-use tuxedo_core::{verifier::TestVerifier, dynamic_typing::DynamicallyTypedData, types::Output, SimpleConstraintChecker, ConstraintChecker};
+use tuxedo_core::{verifier::TestVerifier, dynamic_typing::DynamicallyTypedData, types::Output, ConstraintChecker};
 
 #[test]
 fn error_enum_has_right_variants() {
@@ -13,9 +13,65 @@ fn error_enum_has_right_variants() {
             NotEnoughCollateralToOpenOrder => (),
             OrderAndPayoutCountDiffer => (),
             PayoutDoesNotSatisfyOrder => (),
+            PayoutMismatch { .. } => (),
             InsufficientTokenAForMatch => (),
             InsufficientTokenBForMatch => (),
+            PayoutKindMismatch => (),
             VerifierMismatchForTrade => (),
+            PayoutVerifierMismatch { .. } => (),
+            SurplusOutputsExceedSurplus => (),
+            TooManyOrdersInMatch => (),
+            OfferAmountOffGrid => (),
+            AskAmountOffGrid => (),
+            OrderBelowDustThreshold => (),
+            ZeroAmountOrder => (),
+            OrderExpired => (),
+            RebatePoolMissing => (),
+            RebatePoolClosed => (),
+            RebatePoolNotClosed => (),
+            WrongRebateEpoch => (),
+            RebatePoolMismatch => (),
+            MakerCreditMismatch => (),
+            InsufficientSurplusForFee => (),
+            ClaimExceedsRebate => (),
+            TradingPaused => (),
+            PairNotListed => (),
+            PairRegistryMissing => (),
+            PairRegistryChanged => (),
+            PairAlreadyListed => (),
+            CancelRequiresOneOrder => (),
+            RefundExceedsOrder => (),
+            AmountOverflow => (),
+            WrongNumberOfOrdersInRoute => (),
+            InsufficientIntermediateForRoute => (),
+            MalformedIcebergTranche => (),
+            TrancheDoesNotMatchCommitment => (),
+            CommitmentMissing => (),
+            CommitmentForWrongBlock => (),
+            RevealTooEarly => (),
+            RevealWindowExpired => (),
+            RevealDoesNotMatchCommitment => (),
+            CommitmentNotExpired => (),
+            ReclaimExceedsCollateral => (),
+            NftMissing => (),
+            TooManyInputsWhenListingNft => (),
+            ListingDoesNotMatchNft => (),
+            NftNotDelivered => (),
+            NotEnoughPaymentForNft => (),
+            ChangeExceedsLeftoverPayment => (),
+            CancelNotAuthorized => (),
+            MatcherNotAuthorized => (),
+            WrongOrderBond => (),
+            BondNotRefunded => (),
+            OrderMadeInTheFuture => (),
+            OrderNotEvictable => (),
+            OfferNotRefunded => (),
+            SweepRewardTooLarge => (),
+            CollateralNotOwned => (),
+            SameToken => (),
+            AuctionUnderpaid => (),
+            LotNotDelivered => (),
+            ReferralNotPaid => (),
         }
     }
 }
@@ -32,21 +88,11 @@ type MakeTestOrder = MakeOrder<TestConfig>;
 type MatchTestOrders = MatchOrders<TestConfig>;
 
 fn a_for_b_order(offer_amount: u128, ask_amount: u128) -> TestOrder {
-    Order {
-        offer_amount,
-        ask_amount,
-        payout_verifier: TestVerifier { verifies: true },
-        _ph_data: Default::default(),
-    }
+    Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
 }
 
 fn b_for_a_order(offer_amount: u128, ask_amount: u128) -> ReverseTestOrder {
-    Order {
-        offer_amount,
-        ask_amount,
-        payout_verifier: TestVerifier { verifies: true },
-        _ph_data: Default::default(),
-    }
+    Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
 }
 
 fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
@@ -61,10 +107,10 @@ fn opening_an_order_seeking_a_works() {
     let order = a_for_b_order(100, 150);
     let input = Coin::<0>(100);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![order.into()],
+        &[output_from(input)],
+        &[output_from(order)],
     );
     assert!(result.is_ok());
 }
@@ -73,10 +119,10 @@ fn opening_an_order_seeking_a_works() {
 fn opening_order_with_no_inputs_fails() {
     let order = a_for_b_order(100, 150);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![],
-        &vec![order.into()],
+        &[],
+        &[output_from(order)],
     );
     assert_eq!(result, Err(DexError::NotEnoughCollateralToOpenOrder));
 }
@@ -85,10 +131,10 @@ fn opening_order_with_no_inputs_fails() {
 fn opening_order_with_no_outputs_fails() {
     let input = Coin::<0>(100);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![],
+        &[output_from(input)],
+        &[],
     );
     assert_eq!(result, Err(DexError::OrderMissing));
 }
@@ -99,10 +145,10 @@ fn opening_order_with_insufficient_collateral_fails() {
     let input = Coin::<0>(50);
     let order = a_for_b_order(100, 150);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![order.into()],
+        &[output_from(input)],
+        &[output_from(order)],
     );
 
     assert_eq!(result, Err(DexError::NotEnoughCollateralToOpenOrder));
@@ -114,10 +160,10 @@ fn opening_order_with_collateral_in_wrong_asset() {
     let input = Coin::<1>(100);
     let order = a_for_b_order(100, 150);
 
-    let result = <MakeTestOrder as SimpleConstraintChecker>::check(
+    let result = <MakeTestOrder as ConstraintChecker<TestVerifier>>::check(
         &Default::default(),
-        &vec![input.into()],
-        &vec![order.into()],
+        &[output_from(input)],
+        &[output_from(order)],
     );
 
     assert_eq!(result, Err(DexError::TypeError));
@@ -153,7 +199,7 @@ fn bad_match_insufficient_payout() {
         &vec![output_from(order_a), output_from(order_b)],
         &vec![output_from(payout_a), output_from(payout_b)],
     );
-    assert_eq!(result, Err(DexError::PayoutDoesNotSatisfyOrder));
+    assert_eq!(result, Err(DexError::PayoutMismatch { index: 0, side: Side::OffersA }));
 }
 
 #[test]
@@ -240,5 +286,227 @@ fn wrong_verifier_on_match_payout() {
         &vec![output_from(order_a), output_from(order_b)],
         &vec![output_from(payout_a), payout_b_output],
     );
-    assert_eq!(result, Err(DexError::VerifierMismatchForTrade));
-}
\ No newline at end of file
+    assert_eq!(result, Err(DexError::PayoutVerifierMismatch { index: 1, side: Side::OffersB }));
+}
+
+struct SmallConfig;
+impl DexConfig for SmallConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const MAX_ORDERS_PER_MATCH: usize = 2;
+}
+
+#[test]
+fn match_with_too_many_orders_fails() {
+    let order = |offer_amount, ask_amount| {
+        output_from(Order::<SmallConfig>::new(
+            offer_amount,
+            ask_amount,
+            TestVerifier { verifies: true },
+        ))
+    };
+    let reverse_order = output_from(Order::<OppositeSide<SmallConfig>>::new(
+        200,
+        200,
+        TestVerifier { verifies: true },
+    ));
+
+    let result = <MatchOrders<SmallConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![order(100, 100), order(100, 100), reverse_order],
+        &vec![
+            output_from(Coin::<1>(100)),
+            output_from(Coin::<1>(100)),
+            output_from(Coin::<0>(200)),
+        ],
+    );
+    assert_eq!(result, Err(DexError::TooManyOrdersInMatch));
+}
+
+#[test]
+fn opposite_side_shares_the_match_bound() {
+    assert_eq!(
+        <OppositeSide<SmallConfig> as DexConfig>::MAX_ORDERS_PER_MATCH,
+        2
+    );
+}
+
+#[test]
+fn match_with_overflowing_amounts_fails() {
+    let order_a = a_for_b_order(u128::MAX, 1);
+    let order_a_again = a_for_b_order(1, 1);
+    let order_b = b_for_a_order(2, 2);
+
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(order_a),
+            output_from(order_a_again),
+            output_from(order_b),
+        ],
+        &vec![
+            output_from(Coin::<1>(1)),
+            output_from(Coin::<1>(1)),
+            output_from(Coin::<0>(2)),
+        ],
+    );
+    assert_eq!(result, Err(DexError::AmountOverflow));
+}
+
+#[test]
+fn orders_with_the_same_payout_verifier_can_share_a_payout() {
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(a_for_b_order(50, 75)),
+            output_from(a_for_b_order(50, 75)),
+            output_from(b_for_a_order(150, 100)),
+        ],
+        &vec![output_from(Coin::<1>(150)), output_from(Coin::<0>(100))],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn shared_payouts_may_be_mixed_with_single_ones() {
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(a_for_b_order(50, 75)),
+            output_from(a_for_b_order(25, 40)),
+            output_from(a_for_b_order(25, 35)),
+            output_from(b_for_a_order(150, 100)),
+        ],
+        &vec![
+            output_from(Coin::<1>(75)),
+            output_from(Coin::<1>(75)),
+            output_from(Coin::<0>(100)),
+        ],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn orders_on_opposite_sides_cannot_share_a_payout() {
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(a_for_b_order(100, 150)),
+            output_from(b_for_a_order(150, 100)),
+        ],
+        &vec![output_from(Coin::<1>(250))],
+    );
+    assert_eq!(result, Err(DexError::PayoutMismatch { index: 0, side: Side::OffersA }));
+}
+
+#[test]
+fn orders_with_different_payout_verifiers_cannot_share_a_payout() {
+    let other_makers_order = output_from(Order::<TestConfig> {
+        payout_verifier: TestVerifier { verifies: false },
+        ..a_for_b_order(50, 75)
+    });
+
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(a_for_b_order(50, 75)),
+            other_makers_order,
+            output_from(b_for_a_order(150, 100)),
+        ],
+        &vec![output_from(Coin::<1>(150)), output_from(Coin::<0>(100))],
+    );
+    assert_eq!(result, Err(DexError::PayoutMismatch { index: 0, side: Side::OffersA }));
+}
+
+#[test]
+fn shared_payouts_must_not_overpay() {
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(a_for_b_order(50, 75)),
+            output_from(a_for_b_order(50, 75)),
+            output_from(b_for_a_order(160, 100)),
+        ],
+        &vec![output_from(Coin::<1>(160)), output_from(Coin::<0>(100))],
+    );
+    assert_eq!(result, Err(DexError::PayoutMismatch { index: 0, side: Side::OffersA }));
+}
+
+#[test]
+fn payout_errors_name_the_failing_order() {
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(a_for_b_order(100, 150)),
+            output_from(b_for_a_order(75, 50)),
+            output_from(b_for_a_order(75, 50)),
+        ],
+        &vec![
+            output_from(Coin::<1>(150)),
+            output_from(Coin::<0>(50)),
+            output_from(Coin::<0>(49)),
+        ],
+    );
+    assert_eq!(
+        result,
+        Err(DexError::PayoutMismatch {
+            index: 2,
+            side: Side::OffersB
+        })
+    );
+}
+
+#[test]
+fn surplus_outputs_may_take_the_surplus() {
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(a_for_b_order(100, 150)),
+            output_from(b_for_a_order(160, 90)),
+        ],
+        &vec![
+            output_from(Coin::<1>(150)),
+            output_from(Coin::<0>(90)),
+            output_from(Coin::<0>(10)),
+            output_from(Coin::<1>(4)),
+            output_from(Coin::<1>(6)),
+        ],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn surplus_outputs_cannot_exceed_the_surplus() {
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(a_for_b_order(100, 150)),
+            output_from(b_for_a_order(150, 100)),
+        ],
+        &vec![
+            output_from(Coin::<1>(150)),
+            output_from(Coin::<0>(100)),
+            output_from(Coin::<0>(1)),
+        ],
+    );
+    assert_eq!(result, Err(DexError::SurplusOutputsExceedSurplus));
+}
+
+#[test]
+fn surplus_outputs_must_be_coins_of_the_pair() {
+    let result = <MatchTestOrders as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(a_for_b_order(100, 150)),
+            output_from(b_for_a_order(160, 100)),
+        ],
+        &vec![
+            output_from(Coin::<1>(150)),
+            output_from(Coin::<0>(100)),
+            output_from(Coin::<2>(10)),
+        ],
+    );
+    assert_eq!(result, Err(DexError::TypeError));
+}
@@ -0,0 +1,216 @@
+//! Property-based tests for `MatchOrders`.
+//!
+//! Rather than checking hand picked scenarios, these tests generate arbitrary sets of
+//! orders and payouts, including ones on both sides of the pair, with adversarial amounts
+//! and wrong verifiers, and assert that every match the checker accepts conserves value.
+
+use dex::*;
+use money::Coin;
+use proptest::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, traits::Cash, types::Output, verifier::TestVerifier,
+    ConstraintChecker,
+};
+
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+/// The largest amount used in generated orders and payouts. It is small enough that sums
+/// over any generated match cannot overflow.
+const MAX_AMOUNT: u128 = 1 << 64;
+
+/// A generated order along with the payout that is supposed to fill it.
+#[derive(Debug, Clone)]
+struct Scenario {
+    /// Whether the order offers token A, as opposed to token B
+    offers_a: bool,
+    offer_amount: u128,
+    ask_amount: u128,
+    /// The amount of the payout, which may or may not equal the ask.
+    payout_amount: u128,
+    /// Whether the payout is in the token the order asks for
+    payout_in_asked_token: bool,
+    /// Whether the payout goes to the verifier the order asked for
+    payout_verifier_matches: bool,
+}
+
+fn amount() -> impl Strategy<Value = u128> {
+    prop_oneof![0..=1_000u128, Just(0), Just(MAX_AMOUNT), 0..=MAX_AMOUNT]
+}
+
+fn scenario() -> impl Strategy<Value = Scenario> {
+    (
+        any::<bool>(),
+        amount(),
+        amount(),
+        prop_oneof![Just(None), amount().prop_map(Some)],
+        prop::bool::weighted(0.9),
+        prop::bool::weighted(0.9),
+    )
+        .prop_map(
+            |(offers_a, offer_amount, ask_amount, payout, in_asked_token, verifier_matches)| {
+                Scenario {
+                    offers_a,
+                    offer_amount,
+                    ask_amount,
+                    // Most payouts are honest so that plenty of matches are accepted.
+                    payout_amount: payout.unwrap_or(ask_amount),
+                    payout_in_asked_token: in_asked_token,
+                    payout_verifier_matches: verifier_matches,
+                }
+            },
+        )
+}
+
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T, verifies: bool) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies },
+    }
+}
+
+/// The inputs and outputs of a match transaction for the given scenarios.
+fn transaction(scenarios: &[Scenario]) -> (Vec<Output<TestVerifier>>, Vec<Output<TestVerifier>>) {
+    scenarios
+        .iter()
+        .map(|s| {
+            let order = if s.offers_a {
+                output_from(
+                    Order::<TestConfig>::new(
+                        s.offer_amount,
+                        s.ask_amount,
+                        TestVerifier { verifies: true },
+                    ),
+                    true,
+                )
+            } else {
+                output_from(
+                    Order::<OppositeSide<TestConfig>>::new(
+                        s.offer_amount,
+                        s.ask_amount,
+                        TestVerifier { verifies: true },
+                    ),
+                    true,
+                )
+            };
+
+            let payout_in_b = s.offers_a == s.payout_in_asked_token;
+            let verifies = s.payout_verifier_matches;
+            let payout = if payout_in_b {
+                output_from(Coin::<1>(s.payout_amount), verifies)
+            } else {
+                output_from(Coin::<0>(s.payout_amount), verifies)
+            };
+
+            (order, payout)
+        })
+        .unzip()
+}
+
+/// The total value of the given token in the given outputs, whether held as coins or
+/// offered by orders.
+fn total<const ID: u8>(outputs: &[Output<TestVerifier>]) -> u128 {
+    outputs
+        .iter()
+        .map(|output| {
+            if let Ok(coin) = output.payload.extract::<Coin<ID>>() {
+                coin.value()
+            } else if let Ok(order) = output.payload.extract::<Order<TestConfig>>() {
+                if ID == 0 {
+                    order.offer_amount
+                } else {
+                    0
+                }
+            } else if let Ok(order) = output.payload.extract::<Order<OppositeSide<TestConfig>>>() {
+                if ID == 1 {
+                    order.offer_amount
+                } else {
+                    0
+                }
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// The total amount of the given token paid to the makers' verifier in the given outputs.
+fn paid_to_makers<const ID: u8>(outputs: &[Output<TestVerifier>]) -> u128 {
+    outputs
+        .iter()
+        .filter(|output| output.verifier.verifies)
+        .filter_map(|output| output.payload.extract::<Coin<ID>>().ok())
+        .map(|coin| coin.value())
+        .sum()
+}
+
+/// The total amount asked by the orders on one side of the given scenarios.
+fn asked(scenarios: &[Scenario], offers_a: bool) -> u128 {
+    scenarios
+        .iter()
+        .filter(|s| s.offers_a == offers_a)
+        .map(|s| s.ask_amount)
+        .sum()
+}
+
+proptest! {
+    #[test]
+    fn accepted_matches_conserve_both_tokens(scenarios in prop::collection::vec(scenario(), 0..12)) {
+        let (inputs, outputs) = transaction(&scenarios);
+
+        let result = <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+            &Default::default(),
+            &inputs,
+            &outputs,
+        );
+
+        if result.is_ok() {
+            prop_assert!(total::<0>(&outputs) <= total::<0>(&inputs));
+            prop_assert!(total::<1>(&outputs) <= total::<1>(&inputs));
+        }
+    }
+
+    #[test]
+    fn accepted_matches_pay_the_makers_in_full(scenarios in prop::collection::vec(scenario(), 0..12)) {
+        let (inputs, outputs) = transaction(&scenarios);
+
+        let result = <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+            &Default::default(),
+            &inputs,
+            &outputs,
+        );
+
+        // Payouts may be shared and some outputs may take the surplus, so individual
+        // scenarios need not be honest. But the makers must receive all they asked for.
+        if result.is_ok() {
+            prop_assert!(paid_to_makers::<0>(&outputs) >= asked(&scenarios, false));
+            prop_assert!(paid_to_makers::<1>(&outputs) >= asked(&scenarios, true));
+        }
+    }
+
+    #[test]
+    fn dropping_a_payout_never_underpays(
+        scenarios in prop::collection::vec(scenario(), 1..12),
+        dropped in any::<prop::sample::Index>(),
+    ) {
+        let (inputs, mut outputs) = transaction(&scenarios);
+        outputs.remove(dropped.index(outputs.len()));
+
+        let result = <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+            &Default::default(),
+            &inputs,
+            &outputs,
+        );
+
+        // Orders may share a payout, so the match can still be accepted, but only if the
+        // remaining payouts cover every ask.
+        if result.is_ok() {
+            prop_assert!(paid_to_makers::<0>(&outputs) >= asked(&scenarios, false));
+            prop_assert!(paid_to_makers::<1>(&outputs) >= asked(&scenarios, true));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use dex::*;
+use money::Coin;
+use sp_runtime::transaction_validity::TransactionPriority;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+/// The makers, who receive the payouts.
+const MAKER: TestVerifier = TestVerifier { verifies: true };
+
+/// The relayer, who alone may match restricted orders.
+const RELAYER: TestVerifier = TestVerifier { verifies: false };
+
+fn order<T: DexConfig<Verifier = TestVerifier>>(
+    offer_amount: u128,
+    ask_amount: u128,
+    matcher_verifier: Option<TestVerifier>,
+) -> Order<T> {
+    Order {
+        matcher_verifier,
+        ..Order::new(offer_amount, ask_amount, MAKER)
+    }
+}
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+/// A credential of the relayer, which is not a coin of the pair.
+fn credential() -> Output<TestVerifier> {
+    output(Coin::<2>(1), RELAYER)
+}
+
+/// Match an order offering 10 A for 5 B, restricted to the relayer, against an order
+/// offering 5 B for 10 A, spending the given credentials and creating the given outputs
+/// after the payouts.
+fn match_with(
+    credentials: Vec<Output<TestVerifier>>,
+    extra_outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, DexError> {
+    let mut inputs = vec![
+        output(order::<TestConfig>(10, 5, Some(RELAYER)), MAKER),
+        output(order::<OppositeSide<TestConfig>>(5, 10, None), MAKER),
+    ];
+    inputs.extend(credentials);
+    let mut outputs = vec![output(Coin::<1>(5), MAKER), output(Coin::<0>(10), MAKER)];
+    outputs.extend(extra_outputs);
+
+    <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    )
+}
+
+#[test]
+fn restricted_orders_need_the_matchers_credential() {
+    assert_eq!(
+        match_with(vec![], vec![]),
+        Err(DexError::MatcherNotAuthorized)
+    );
+    assert_eq!(match_with(vec![credential()], vec![]), Ok(0));
+}
+
+#[test]
+fn credentials_may_be_returned_once() {
+    assert_eq!(match_with(vec![credential()], vec![credential()]), Ok(0));
+    assert_eq!(
+        match_with(vec![credential()], vec![credential(), credential()]),
+        Err(DexError::TypeError)
+    );
+}
+
+#[test]
+fn credentials_must_authorize_some_order() {
+    let stranger = output(Coin::<2>(1), MAKER);
+    assert_eq!(
+        match_with(vec![credential(), stranger], vec![]),
+        Err(DexError::TypeError)
+    );
+}
+
+#[test]
+fn orders_cannot_hide_among_the_credentials() {
+    let unpaid = output(order::<TestConfig>(10, 5, None), RELAYER);
+    assert_eq!(
+        match_with(vec![credential(), unpaid], vec![]),
+        Err(DexError::TypeError)
+    );
+}
+
+#[test]
+fn unrestricted_orders_match_without_credentials() {
+    let result = <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output(order::<TestConfig>(10, 5, None), MAKER),
+            output(order::<OppositeSide<TestConfig>>(5, 10, None), MAKER),
+        ],
+        &vec![output(Coin::<1>(5), MAKER), output(Coin::<0>(10), MAKER)],
+    );
+    assert_eq!(result, Ok(0));
+}
@@ -0,0 +1,175 @@
+use dex::*;
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    types::Output,
+    verifier::TestVerifier,
+    ConstraintChecker, SimpleConstraintChecker,
+};
+
+/// A stand-in for a real non-fungible item such as a kitty.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+struct TestNft(u32);
+
+impl UtxoData for TestNft {
+    const TYPE_ID: [u8; 4] = *b"tnft";
+}
+
+struct TestConfig;
+impl NftDexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type Nft = TestNft;
+    type Token = Coin<0>;
+}
+
+type TestNftOrder = NftOrder<TestConfig>;
+type ListTestNft = ListNft<TestConfig>;
+type BuyTestNft = BuyNft<TestConfig>;
+
+fn listing(id: u32, ask_amount: u128) -> TestNftOrder {
+    NftOrder {
+        nft: TestNft(id),
+        ask_amount,
+        payout_verifier: TestVerifier { verifies: true },
+        _ph_data: Default::default(),
+    }
+}
+
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+#[test]
+fn listing_an_nft_works() {
+    let result = <ListTestNft as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![TestNft(7).into()],
+        &vec![listing(7, 100).into()],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn listing_without_the_nft_fails() {
+    let result = <ListTestNft as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![],
+        &vec![listing(7, 100).into()],
+    );
+    assert_eq!(result, Err(DexError::NftMissing));
+}
+
+#[test]
+fn listing_a_different_nft_fails() {
+    let result = <ListTestNft as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![TestNft(7).into()],
+        &vec![listing(8, 100).into()],
+    );
+    assert_eq!(result, Err(DexError::ListingDoesNotMatchNft));
+}
+
+#[test]
+fn listing_a_coin_as_an_nft_fails() {
+    let result = <ListTestNft as SimpleConstraintChecker>::check(
+        &Default::default(),
+        &vec![Coin::<0>(7).into()],
+        &vec![listing(7, 100).into()],
+    );
+    assert_eq!(result, Err(DexError::TypeError));
+}
+
+#[test]
+fn buying_an_nft_works() {
+    let result = <BuyTestNft as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(listing(7, 100)), output_from(Coin::<0>(100))],
+        &vec![output_from(TestNft(7)), output_from(Coin::<0>(100))],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn buying_an_nft_with_change_works() {
+    let result = <BuyTestNft as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output_from(listing(7, 100)),
+            output_from(Coin::<0>(80)),
+            output_from(Coin::<0>(50)),
+        ],
+        &vec![
+            output_from(TestNft(7)),
+            output_from(Coin::<0>(100)),
+            output_from(Coin::<0>(30)),
+        ],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn buying_an_nft_with_too_much_change_fails() {
+    let result = <BuyTestNft as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(listing(7, 100)), output_from(Coin::<0>(120))],
+        &vec![
+            output_from(TestNft(7)),
+            output_from(Coin::<0>(100)),
+            output_from(Coin::<0>(30)),
+        ],
+    );
+    assert_eq!(result, Err(DexError::ChangeExceedsLeftoverPayment));
+}
+
+#[test]
+fn buying_an_nft_with_insufficient_payment_fails() {
+    let result = <BuyTestNft as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(listing(7, 100)), output_from(Coin::<0>(99))],
+        &vec![output_from(TestNft(7)), output_from(Coin::<0>(99))],
+    );
+    assert_eq!(result, Err(DexError::NotEnoughPaymentForNft));
+}
+
+#[test]
+fn buying_an_nft_without_taking_it_fails() {
+    let result = <BuyTestNft as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(listing(7, 100)), output_from(Coin::<0>(100))],
+        &vec![output_from(TestNft(8)), output_from(Coin::<0>(100))],
+    );
+    assert_eq!(result, Err(DexError::NftNotDelivered));
+}
+
+#[test]
+fn buying_an_nft_underpaying_the_seller_fails() {
+    let result = <BuyTestNft as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(listing(7, 100)), output_from(Coin::<0>(100))],
+        &vec![
+            output_from(TestNft(7)),
+            output_from(Coin::<0>(60)),
+            output_from(Coin::<0>(40)),
+        ],
+    );
+    assert_eq!(result, Err(DexError::PayoutDoesNotSatisfyOrder));
+}
+
+#[test]
+fn buying_an_nft_paying_the_wrong_seller_fails() {
+    let payout = Output {
+        payload: Coin::<0>(100).into(),
+        verifier: TestVerifier { verifies: false },
+    };
+
+    let result = <BuyTestNft as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output_from(listing(7, 100)), output_from(Coin::<0>(100))],
+        &vec![output_from(TestNft(7)), payout],
+    );
+    assert_eq!(result, Err(DexError::VerifierMismatchForTrade));
+}
@@ -0,0 +1,151 @@
+use dex::*;
+use money::Coin;
+use sp_runtime::transaction_validity::TransactionPriority;
+use std::cell::Cell;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+thread_local! {
+    static BLOCK: Cell<u32> = Cell::new(0);
+}
+
+fn set_block(block: u32) {
+    BLOCK.with(|b| b.set(block));
+}
+
+/// A dex that requires a bond of 2 with each order.
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const ORDER_BOND: u128 = 2;
+
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext {
+            block_number: BLOCK.with(|b| b.get()),
+            timestamp: 0,
+        }
+    }
+}
+
+/// The maker, who receives the payouts and refunds.
+const MAKER: TestVerifier = TestVerifier { verifies: true };
+
+/// Anyone else.
+const STRANGER: TestVerifier = TestVerifier { verifies: false };
+
+fn order<T: DexConfig<Verifier = TestVerifier>>(
+    offer_amount: u128,
+    ask_amount: u128,
+    bond: u128,
+) -> Order<T> {
+    Order {
+        expiry: Some(100),
+        bond,
+        ..Order::new(offer_amount, ask_amount, MAKER)
+    }
+}
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn make(collateral: u128, order: Order<TestConfig>) -> Result<TransactionPriority, DexError> {
+    <MakeOrder<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output(Coin::<0>(collateral), MAKER)],
+        &[output(order, MAKER)],
+    )
+}
+
+/// Match an order offering 10 A for 5 B against one offering 5 B for 10 A, both bonded.
+fn match_with(outputs: Vec<Output<TestVerifier>>) -> Result<TransactionPriority, DexError> {
+    <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output(order::<TestConfig>(10, 5, 2), MAKER),
+            output(order::<OppositeSide<TestConfig>>(5, 10, 2), MAKER),
+        ],
+        &outputs,
+    )
+}
+
+fn cancel(refund: u128) -> Result<TransactionPriority, DexError> {
+    <CancelOrder<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output(order::<TestConfig>(10, 5, 2), MAKER)],
+        &vec![output(Coin::<0>(refund), MAKER)],
+    )
+}
+
+#[test]
+fn making_an_order_locks_the_bond() {
+    set_block(0);
+    assert_eq!(make(12, order(10, 5, 2)), Ok(0));
+    assert_eq!(
+        make(11, order(10, 5, 2)),
+        Err(DexError::NotEnoughCollateralToOpenOrder)
+    );
+}
+
+#[test]
+fn orders_must_carry_the_configured_bond() {
+    set_block(0);
+    assert_eq!(make(12, order(10, 5, 0)), Err(DexError::WrongOrderBond));
+    assert_eq!(make(13, order(10, 5, 3)), Err(DexError::WrongOrderBond));
+}
+
+#[test]
+fn matches_refund_each_bond_after_the_payouts() {
+    set_block(0);
+    let payouts = vec![output(Coin::<1>(5), MAKER), output(Coin::<0>(10), MAKER)];
+
+    let mut refunded = payouts.clone();
+    refunded.extend([output(Coin::<0>(2), MAKER), output(Coin::<1>(2), MAKER)]);
+    assert_eq!(match_with(refunded), Ok(0));
+
+    assert_eq!(match_with(payouts.clone()), Err(DexError::BondNotRefunded));
+
+    let mut stolen = payouts;
+    stolen.extend([output(Coin::<0>(2), STRANGER), output(Coin::<1>(2), MAKER)]);
+    assert_eq!(match_with(stolen), Err(DexError::BondNotRefunded));
+}
+
+#[test]
+fn bonds_are_not_part_of_the_surplus() {
+    set_block(0);
+    let outputs = vec![
+        output(Coin::<1>(5), MAKER),
+        output(Coin::<0>(10), MAKER),
+        output(Coin::<0>(2), MAKER),
+        output(Coin::<1>(2), MAKER),
+        output(Coin::<0>(1), STRANGER),
+    ];
+    assert_eq!(
+        match_with(outputs),
+        Err(DexError::SurplusOutputsExceedSurplus)
+    );
+}
+
+#[test]
+fn cancelling_refunds_the_bond() {
+    set_block(100);
+    assert_eq!(cancel(12), Ok(0));
+    assert_eq!(cancel(13), Err(DexError::RefundExceedsOrder));
+}
+
+#[test]
+fn expired_orders_forfeit_their_bond() {
+    set_block(101);
+    assert_eq!(cancel(10), Ok(0));
+    assert_eq!(cancel(12), Err(DexError::RefundExceedsOrder));
+}
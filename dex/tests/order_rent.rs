@@ -0,0 +1,177 @@
+use dex::*;
+use money::Coin;
+use sp_runtime::transaction_validity::TransactionPriority;
+use std::cell::Cell;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+thread_local! {
+    static BLOCK: Cell<u32> = Cell::new(0);
+}
+
+fn set_block(block: u32) {
+    BLOCK.with(|b| b.set(block));
+}
+
+/// A dex whose orders lock a bond of 10, which pays 1 in rent per block, and whose
+/// sweepers take half of it.
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const ORDER_BOND: u128 = 10;
+    const RENT_PER_BLOCK: u128 = 1;
+    const SWEEPER_REWARD_PPM: u32 = 500_000;
+
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext {
+            block_number: BLOCK.with(|b| b.get()),
+            timestamp: 0,
+        }
+    }
+}
+
+/// The maker, who receives the payouts and refunds.
+const MAKER: TestVerifier = TestVerifier { verifies: true };
+
+/// The sweeper, who takes the reward.
+const SWEEPER: TestVerifier = TestVerifier { verifies: false };
+
+/// An order offering 100 for 50, made in block 10 and expiring in block 100.
+fn order<T: DexConfig<Verifier = TestVerifier>>() -> Order<T> {
+    Order {
+        expiry: Some(100),
+        bond: 10,
+        made_at: 10,
+        ..Order::new(100, 50, MAKER)
+    }
+}
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn sweep(
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, DexError> {
+    <SweepExpired<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    )
+}
+
+#[test]
+fn rent_comes_out_of_the_bond() {
+    let order = order::<TestConfig>();
+    assert_eq!(order.bond_left(10), 10);
+    assert_eq!(order.bond_left(13), 7);
+    assert_eq!(order.bond_left(20), 0);
+    assert_eq!(order.bond_left(1_000), 0);
+    assert!(!order.is_evictable(19));
+    assert!(order.is_evictable(20));
+}
+
+#[test]
+fn orders_cannot_be_made_in_the_future() {
+    set_block(9);
+    let made = <MakeOrder<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output(Coin::<0>(110), MAKER)],
+        &[output(order::<TestConfig>(), MAKER)],
+    );
+    assert_eq!(made, Err(DexError::OrderMadeInTheFuture));
+
+    set_block(11);
+    let made = <MakeOrder<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output(Coin::<0>(110), MAKER)],
+        &[output(order::<TestConfig>(), MAKER)],
+    );
+    assert_eq!(made, Ok(0));
+}
+
+#[test]
+fn cancelling_refunds_the_bond_after_rent() {
+    set_block(14);
+    let cancel = |refund| {
+        <CancelOrder<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+            &Default::default(),
+            &vec![output(order::<TestConfig>(), MAKER)],
+            &vec![output(Coin::<0>(refund), MAKER)],
+        )
+    };
+    assert_eq!(cancel(106), Ok(0));
+    assert_eq!(cancel(107), Err(DexError::RefundExceedsOrder));
+}
+
+#[test]
+fn orders_out_of_bond_can_be_swept() {
+    set_block(20);
+    let inputs = vec![
+        output(order::<TestConfig>(), MAKER),
+        output(order::<OppositeSide<TestConfig>>(), MAKER),
+    ];
+    let refunds = vec![output(Coin::<0>(100), MAKER), output(Coin::<1>(100), MAKER)];
+
+    assert_eq!(sweep(inputs.clone(), refunds.clone()), Ok(0));
+
+    let mut rewarded = refunds.clone();
+    rewarded.extend([output(Coin::<0>(5), SWEEPER), output(Coin::<1>(5), SWEEPER)]);
+    assert_eq!(sweep(inputs.clone(), rewarded), Ok(0));
+
+    let mut greedy = refunds;
+    greedy.push(output(Coin::<0>(6), SWEEPER));
+    assert_eq!(sweep(inputs, greedy), Err(DexError::SweepRewardTooLarge));
+}
+
+#[test]
+fn orders_with_bond_left_cannot_be_swept() {
+    set_block(19);
+    assert_eq!(
+        sweep(
+            vec![output(order::<TestConfig>(), MAKER)],
+            vec![output(Coin::<0>(100), MAKER)]
+        ),
+        Err(DexError::OrderNotEvictable)
+    );
+}
+
+#[test]
+fn expired_orders_can_be_swept_before_their_bond_runs_out() {
+    let mut expiring = order::<TestConfig>();
+    expiring.expiry = Some(15);
+    set_block(16);
+    assert_eq!(
+        sweep(
+            vec![output(expiring, MAKER)],
+            vec![output(Coin::<0>(100), MAKER)]
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn sweeping_refunds_the_offer_to_the_maker() {
+    set_block(20);
+    let inputs = vec![output(order::<TestConfig>(), MAKER)];
+    assert_eq!(
+        sweep(inputs.clone(), vec![output(Coin::<0>(100), SWEEPER)]),
+        Err(DexError::OfferNotRefunded)
+    );
+    assert_eq!(
+        sweep(inputs.clone(), vec![output(Coin::<0>(99), MAKER)]),
+        Err(DexError::OfferNotRefunded)
+    );
+    assert_eq!(sweep(inputs, vec![]), Err(DexError::OfferNotRefunded));
+}
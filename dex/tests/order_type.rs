@@ -1,17 +1,48 @@
 use dex::*;
+use money::Coin;
 use tuxedo_core::{dynamic_typing::UtxoData, verifier::TestVerifier};
 
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
 #[test]
 fn order_type_has_right_fields() {
-    Order::<TestVerifier> {
-        ask_amount: 1,
-        offer_amount: 1,
-        payout_verifier: TestVerifier{ verifies: true },
-    };
+    Order::<TestConfig>::new(1, 1, TestVerifier { verifies: true });
 }
 
 #[test]
 fn order_implements_utxo_data() {
-    let id = <Order::<TestVerifier> as UtxoData>::TYPE_ID;
-    assert_eq!(id, *b"ordr");
-}
\ No newline at end of file
+    let id = <Order::<TestConfig> as UtxoData>::TYPE_ID;
+    assert_eq!(id, [b'$', b'$', 0, 1]);
+}
+
+struct FeeConfig;
+impl DexConfig for FeeConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const TAKER_FEE_PPM: u32 = 3_000;
+    const INSTANCE: u8 = 1;
+}
+
+#[test]
+fn books_over_the_same_pair_have_distinct_order_types() {
+    let plain = <Order<TestConfig> as UtxoData>::TYPE_ID;
+    let with_fees = <Order<FeeConfig> as UtxoData>::TYPE_ID;
+    assert_ne!(plain, with_fees);
+    assert_eq!(with_fees, [b'$' + 1, b'$', 0, 1]);
+
+    assert_eq!(
+        <Order<OppositeSide<FeeConfig>> as UtxoData>::TYPE_ID,
+        [b'$' + 1, b'$', 1, 0]
+    );
+    assert_ne!(
+        <TradingStatus<TestConfig> as UtxoData>::TYPE_ID,
+        <TradingStatus<FeeConfig> as UtxoData>::TYPE_ID
+    );
+}
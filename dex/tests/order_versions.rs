@@ -0,0 +1,270 @@
+use dex::*;
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use sp_core::H256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use std::cell::Cell;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+thread_local! {
+    static BLOCK: Cell<u32> = Cell::new(0);
+}
+
+fn set_block(block: u32) {
+    BLOCK.with(|b| b.set(block));
+}
+
+/// A dex that knows which block it is checking transactions in.
+#[derive(PartialEq, Eq, Debug)]
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext {
+            block_number: BLOCK.with(|b| b.get()),
+            timestamp: 0,
+        }
+    }
+}
+
+fn order<T: DexConfig<Verifier = TestVerifier>>(
+    offer_amount: u128,
+    ask_amount: u128,
+    expiry: Option<u32>,
+) -> Order<T> {
+    Order {
+        owner: H256::repeat_byte(7),
+        expiry,
+        ..Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
+    }
+}
+
+fn output(payload: impl Into<DynamicallyTypedData>) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+fn make_order(order: Order<TestConfig>) -> Result<TransactionPriority, DexError> {
+    <MakeOrder<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output(Coin::<0>(10))],
+        &[output(order)],
+    )
+}
+
+fn match_orders(expiry: Option<u32>) -> Result<TransactionPriority, DexError> {
+    <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output(order::<TestConfig>(10, 5, expiry)),
+            output(order::<OppositeSide<TestConfig>>(5, 10, None)),
+        ],
+        &vec![output(Coin::<1>(5)), output(Coin::<0>(10))],
+    )
+}
+
+/// The encoding of an order offering 10 for 5 in the first version, which had the same
+/// fields except for the expiry.
+fn first_version_order() -> Vec<u8> {
+    let mut encoded = vec![1u8];
+    encoded.extend(
+        (
+            10u128,
+            5u128,
+            TestVerifier { verifies: true },
+            PayoutKind::Coin,
+            H256::repeat_byte(7),
+        )
+            .encode(),
+    );
+    encoded
+}
+
+/// The encoding of an order offering 10 for 5 in the second version, which had the same
+/// fields except for the cancel verifier.
+fn second_version_order(expiry: Option<u32>) -> Vec<u8> {
+    let mut encoded = vec![2u8];
+    encoded.extend(
+        (
+            10u128,
+            5u128,
+            TestVerifier { verifies: true },
+            PayoutKind::Coin,
+            H256::repeat_byte(7),
+            expiry,
+        )
+            .encode(),
+    );
+    encoded
+}
+
+/// The encoding of an order offering 10 for 5 in the third version, which had the same
+/// fields except for the matcher verifier.
+fn third_version_order() -> Vec<u8> {
+    let mut encoded = vec![3u8];
+    encoded.extend(
+        (
+            10u128,
+            5u128,
+            TestVerifier { verifies: true },
+            PayoutKind::Coin,
+            H256::repeat_byte(7),
+            None::<u32>,
+            None::<TestVerifier>,
+        )
+            .encode(),
+    );
+    encoded
+}
+
+/// The encoding of an order offering 10 for 5 in the fourth version, which had the same
+/// fields except for the bond.
+fn fourth_version_order() -> Vec<u8> {
+    let mut encoded = vec![4u8];
+    encoded.extend(
+        (
+            10u128,
+            5u128,
+            TestVerifier { verifies: true },
+            PayoutKind::Coin,
+            H256::repeat_byte(7),
+            None::<u32>,
+            None::<TestVerifier>,
+            None::<TestVerifier>,
+        )
+            .encode(),
+    );
+    encoded
+}
+
+/// The encoding of an order offering 10 for 5 in the fifth version, which had the same
+/// fields except for the block it was made in.
+fn fifth_version_order() -> Vec<u8> {
+    let mut encoded = vec![5u8];
+    encoded.extend(
+        (
+            10u128,
+            5u128,
+            TestVerifier { verifies: true },
+            PayoutKind::Coin,
+            H256::repeat_byte(7),
+            None::<u32>,
+            None::<TestVerifier>,
+            None::<TestVerifier>,
+            0u128,
+        )
+            .encode(),
+    );
+    encoded
+}
+
+#[test]
+fn orders_are_encoded_in_the_current_version() {
+    let order = order::<TestConfig>(10, 5, Some(100));
+    let encoded = order.encode();
+
+    assert_eq!(encoded[0], <Order<TestConfig> as Versioned>::VERSION);
+    assert_eq!(Order::<TestConfig>::decode(&mut &encoded[..]), Ok(order));
+}
+
+#[test]
+fn first_version_orders_are_migrated_when_decoded() {
+    let encoded = first_version_order();
+    assert_eq!(
+        Order::<TestConfig>::decode(&mut &encoded[..]),
+        Ok(order(10, 5, None))
+    );
+}
+
+#[test]
+fn second_version_orders_are_migrated_when_decoded() {
+    let encoded = second_version_order(Some(100));
+    assert_eq!(
+        Order::<TestConfig>::decode(&mut &encoded[..]),
+        Ok(order(10, 5, Some(100)))
+    );
+}
+
+#[test]
+fn third_version_orders_are_migrated_when_decoded() {
+    let encoded = third_version_order();
+    assert_eq!(
+        Order::<TestConfig>::decode(&mut &encoded[..]),
+        Ok(order(10, 5, None))
+    );
+}
+
+#[test]
+fn fourth_version_orders_are_migrated_when_decoded() {
+    let encoded = fourth_version_order();
+    assert_eq!(
+        Order::<TestConfig>::decode(&mut &encoded[..]),
+        Ok(order(10, 5, None))
+    );
+}
+
+#[test]
+fn fifth_version_orders_are_migrated_when_decoded() {
+    let encoded = fifth_version_order();
+    assert_eq!(
+        Order::<TestConfig>::decode(&mut &encoded[..]),
+        Ok(order(10, 5, None))
+    );
+}
+
+#[test]
+fn first_version_orders_can_still_be_spent() {
+    let stored = DynamicallyTypedData {
+        data: first_version_order(),
+        type_id: <Order<TestConfig> as tuxedo_core::dynamic_typing::UtxoData>::TYPE_ID,
+    };
+
+    let result = <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            Output {
+                payload: stored,
+                verifier: TestVerifier { verifies: true },
+            },
+            output(order::<OppositeSide<TestConfig>>(5, 10, None)),
+        ],
+        &vec![output(Coin::<1>(5)), output(Coin::<0>(10))],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn unknown_versions_are_rejected() {
+    let mut encoded = order::<TestConfig>(10, 5, None).encode();
+
+    encoded[0] = 0;
+    assert!(Order::<TestConfig>::decode(&mut &encoded[..]).is_err());
+
+    encoded[0] = <Order<TestConfig> as Versioned>::VERSION + 1;
+    assert!(Order::<TestConfig>::decode(&mut &encoded[..]).is_err());
+}
+
+#[test]
+fn expired_orders_cannot_be_made() {
+    set_block(100);
+    assert_eq!(make_order(order(10, 5, Some(100))), Ok(0));
+    assert_eq!(make_order(order(10, 5, None)), Ok(0));
+    assert_eq!(
+        make_order(order(10, 5, Some(99))),
+        Err(DexError::OrderExpired)
+    );
+}
+
+#[test]
+fn expired_orders_cannot_be_matched() {
+    set_block(100);
+    assert_eq!(match_orders(Some(100)), Ok(0));
+    assert_eq!(match_orders(Some(99)), Err(DexError::OrderExpired));
+}
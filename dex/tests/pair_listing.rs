@@ -0,0 +1,161 @@
+use dex::{listing::*, *};
+use money::Coin;
+use sp_io::TestExternalities;
+use sp_runtime::transaction_validity::TransactionPriority;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+/// A dex whose pair must be listed in the registry before orders can be made.
+struct CuratedConfig;
+impl DexConfig for CuratedConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    fn pair_listed() -> bool {
+        is_listed::<Self>()
+    }
+}
+
+fn output(payload: impl Into<DynamicallyTypedData>, verifies: bool) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies },
+    }
+}
+
+fn registry(pairs: &[PairId]) -> PairRegistry {
+    PairRegistry {
+        pairs: pairs.to_vec(),
+    }
+}
+
+fn list(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, DexError> {
+    <ListPair as ConstraintChecker<TestVerifier>>::check(&ListPair, inputs, outputs)
+}
+
+/// Make an order offering 10 of token A, backed by the given collateral.
+fn make_order<T: DexConfig<Verifier = TestVerifier>>(
+    collateral: impl Into<DynamicallyTypedData>,
+) -> Result<TransactionPriority, DexError> {
+    let order = Order::<T>::new(10, 5, TestVerifier { verifies: true });
+    <MakeOrder<T> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output(collateral, true)],
+        &[output(order, true)],
+    )
+}
+
+#[test]
+fn listing_a_pair_works_and_records_the_registry() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(current_registry(), None);
+
+        let result = list(
+            &[output(registry(&[(2, 3)]), true)],
+            &[output(registry(&[(2, 3), (0, 1)]), true)],
+        );
+        assert_eq!(result, Ok(0));
+        assert_eq!(current_registry(), Some(registry(&[(2, 3), (0, 1)])));
+        assert!(is_listed::<CuratedConfig>());
+        assert!(is_listed::<OppositeSide<CuratedConfig>>());
+    });
+}
+
+#[test]
+fn orders_for_unlisted_pairs_are_rejected() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            make_order::<CuratedConfig>(Coin::<0>(10)),
+            Err(DexError::PairNotListed)
+        );
+
+        list(
+            &[output(registry(&[]), true)],
+            &[output(registry(&[(1, 0)]), true)],
+        )
+        .unwrap();
+        assert_eq!(make_order::<CuratedConfig>(Coin::<0>(10)), Ok(0));
+        assert_eq!(
+            make_order::<OppositeSide<CuratedConfig>>(Coin::<1>(10)),
+            Ok(0)
+        );
+    });
+}
+
+#[test]
+fn listing_must_keep_the_registry_under_the_same_verifier() {
+    TestExternalities::default().execute_with(|| {
+        let result = list(
+            &[output(registry(&[]), true)],
+            &[output(registry(&[(0, 1)]), false)],
+        );
+        assert_eq!(result, Err(DexError::PairRegistryChanged));
+        assert_eq!(current_registry(), None);
+    });
+}
+
+#[test]
+fn listing_adds_exactly_one_pair() {
+    TestExternalities::default().execute_with(|| {
+        let current = [output(registry(&[(2, 3)]), true)];
+        assert_eq!(
+            list(&current, &[output(registry(&[(2, 3)]), true)]),
+            Err(DexError::PairRegistryChanged)
+        );
+        assert_eq!(
+            list(&current, &[output(registry(&[(0, 1)]), true)]),
+            Err(DexError::PairRegistryChanged)
+        );
+        assert_eq!(
+            list(
+                &current,
+                &[output(registry(&[(2, 3), (0, 1), (4, 5)]), true)]
+            ),
+            Err(DexError::PairRegistryChanged)
+        );
+    });
+}
+
+#[test]
+fn pairs_cannot_be_listed_twice() {
+    TestExternalities::default().execute_with(|| {
+        let current = [output(registry(&[(2, 3)]), true)];
+        assert_eq!(
+            list(&current, &[output(registry(&[(2, 3), (3, 2)]), true)]),
+            Err(DexError::PairAlreadyListed)
+        );
+        assert_eq!(
+            list(&current, &[output(registry(&[(2, 3), (4, 4)]), true)]),
+            Err(DexError::PairAlreadyListed)
+        );
+    });
+}
+
+#[test]
+fn listing_without_a_registry_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            list(&[], &[output(registry(&[(0, 1)]), true)]),
+            Err(DexError::PairRegistryMissing)
+        );
+    });
+}
+
+#[test]
+fn pairs_are_listed_by_default() {
+    struct UncuratedConfig;
+    impl DexConfig for UncuratedConfig {
+        type Verifier = TestVerifier;
+        type A = Coin<0>;
+        type B = Coin<1>;
+    }
+
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(make_order::<UncuratedConfig>(Coin::<0>(10)), Ok(0));
+    });
+}
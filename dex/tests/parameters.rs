@@ -0,0 +1,144 @@
+use dex::*;
+use money::Coin;
+use sp_runtime::transaction_validity::TransactionPriority;
+use std::cell::Cell;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+thread_local! {
+    static PARAMETERS: Cell<DexParameters> = Cell::new(DexParameters::defaults::<GovernedConfig>());
+}
+
+fn set_parameters(parameters: DexParameters) {
+    PARAMETERS.with(|p| p.set(parameters));
+}
+
+/// A dex whose parameters are set by the test, as governance would.
+struct GovernedConfig;
+impl DexConfig for GovernedConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    fn parameters() -> DexParameters {
+        PARAMETERS.with(|p| p.get())
+    }
+}
+
+/// A governed dex that allows giveaways.
+struct GovernedGiveawayConfig;
+impl DexConfig for GovernedGiveawayConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const ALLOW_GIVEAWAYS: bool = true;
+
+    fn parameters() -> DexParameters {
+        PARAMETERS.with(|p| p.get())
+    }
+}
+
+fn order<T: DexConfig<Verifier = TestVerifier>>(offer_amount: u128, ask_amount: u128) -> Order<T> {
+    Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
+}
+
+fn output(payload: impl Into<DynamicallyTypedData>) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+fn make_order(offer_amount: u128, ask_amount: u128) -> Result<TransactionPriority, DexError> {
+    <MakeOrder<GovernedConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output(Coin::<0>(offer_amount))],
+        &[output(order::<GovernedConfig>(offer_amount, ask_amount))],
+    )
+}
+
+#[test]
+fn defaults_come_from_the_constants() {
+    assert_eq!(
+        DexParameters::defaults::<GovernedConfig>(),
+        DexParameters {
+            taker_fee_ppm: 0,
+            dust_threshold: 0,
+            max_orders_per_match: 100,
+        }
+    );
+}
+
+#[test]
+fn dust_orders_are_rejected() {
+    set_parameters(DexParameters {
+        dust_threshold: 10,
+        ..DexParameters::defaults::<GovernedConfig>()
+    });
+
+    assert_eq!(make_order(10, 10), Ok(0));
+    assert_eq!(make_order(9, 10), Err(DexError::OrderBelowDustThreshold));
+    assert_eq!(make_order(10, 9), Err(DexError::OrderBelowDustThreshold));
+}
+
+#[test]
+fn giveaways_only_need_to_offer_more_than_dust() {
+    set_parameters(DexParameters {
+        dust_threshold: 10,
+        ..DexParameters::defaults::<GovernedGiveawayConfig>()
+    });
+    let make_giveaway = |offer_amount, ask_amount| {
+        <MakeOrder<GovernedGiveawayConfig> as ConstraintChecker<TestVerifier>>::check(
+            &Default::default(),
+            &[output(Coin::<0>(offer_amount))],
+            &[output(order::<GovernedGiveawayConfig>(
+                offer_amount,
+                ask_amount,
+            ))],
+        )
+    };
+
+    assert_eq!(make_giveaway(10, 0), Ok(0));
+    assert_eq!(make_giveaway(9, 0), Err(DexError::OrderBelowDustThreshold));
+    // Orders that ask for something still ask for more than dust.
+    assert_eq!(make_giveaway(10, 9), Err(DexError::OrderBelowDustThreshold));
+}
+
+#[test]
+fn opposite_side_shares_the_parameters() {
+    set_parameters(DexParameters {
+        dust_threshold: 10,
+        ..DexParameters::defaults::<GovernedConfig>()
+    });
+
+    let result =
+        <MakeOrder<OppositeSide<GovernedConfig>> as ConstraintChecker<TestVerifier>>::check(
+            &Default::default(),
+            &[output(Coin::<1>(5))],
+            &[output(order::<OppositeSide<GovernedConfig>>(5, 50))],
+        );
+    assert_eq!(result, Err(DexError::OrderBelowDustThreshold));
+}
+
+#[test]
+fn governed_match_size_limit_applies() {
+    set_parameters(DexParameters {
+        max_orders_per_match: 1,
+        ..DexParameters::defaults::<GovernedConfig>()
+    });
+
+    let inputs = vec![
+        output(order::<GovernedConfig>(100, 150)),
+        output(order::<OppositeSide<GovernedConfig>>(150, 100)),
+    ];
+    let outputs = vec![output(Coin::<1>(150)), output(Coin::<0>(100))];
+
+    let result = <MatchOrders<GovernedConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    );
+    assert_eq!(result, Err(DexError::TooManyOrdersInMatch));
+}
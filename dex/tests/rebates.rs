@@ -0,0 +1,398 @@
+use dex::*;
+use money::Coin;
+use sp_runtime::transaction_validity::TransactionPriority;
+use std::cell::Cell;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+thread_local! {
+    static BLOCK_NUMBER: Cell<u32> = Cell::new(0);
+}
+
+fn set_block_number(block_number: u32) {
+    BLOCK_NUMBER.with(|b| b.set(block_number));
+}
+
+/// A dex that charges a 1% fee, with epochs of 10 blocks.
+struct FeeConfig;
+impl DexConfig for FeeConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const TAKER_FEE_PPM: u32 = 10_000;
+    const REBATE_EPOCH_LENGTH: u32 = 10;
+
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext {
+            block_number: BLOCK_NUMBER.with(|b| b.get()),
+            timestamp: 0,
+        }
+    }
+}
+
+fn output(payload: impl Into<DynamicallyTypedData>, verifies: bool) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies },
+    }
+}
+
+fn pool(
+    epoch: u32,
+    closed: bool,
+    fees_a: u128,
+    fees_b: u128,
+    volume: u128,
+) -> RebatePool<FeeConfig> {
+    RebatePool {
+        epoch,
+        closed,
+        fees_a,
+        fees_b,
+        volume,
+        _ph_data: Default::default(),
+    }
+}
+
+fn credit(epoch: u32, volume: u128) -> MakerCredit<FeeConfig> {
+    MakerCredit {
+        epoch,
+        volume,
+        _ph_data: Default::default(),
+    }
+}
+
+/// An order offering 100 A for 150 B, paid out to `true`, and an order offering 160 B for
+/// 99 A, paid out to `false`. The match leaves a surplus of 1 A and 10 B, and its fee is
+/// 1 A and 2 B after rounding up.
+fn orders() -> Vec<Output<TestVerifier>> {
+    vec![
+        output(
+            Order::<FeeConfig>::new(100, 150, TestVerifier { verifies: true }),
+            true,
+        ),
+        output(
+            Order::<OppositeSide<FeeConfig>>::new(160, 99, TestVerifier { verifies: false }),
+            true,
+        ),
+    ]
+}
+
+/// The payouts and maker credits of matching `orders()` in the given epoch.
+fn payouts_and_credits(epoch: u32) -> Vec<Output<TestVerifier>> {
+    vec![
+        output(Coin::<1>(150), true),
+        output(Coin::<0>(99), false),
+        output(credit(epoch, 100), true),
+        output(credit(epoch, 99), false),
+    ]
+}
+
+fn check_match(
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, DexError> {
+    <MatchOrders<FeeConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    )
+}
+
+fn check_claim(
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, DexError> {
+    <ClaimRebate<FeeConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    )
+}
+
+#[test]
+fn taker_fee_rounds_up() {
+    assert_eq!(rebate::taker_fee(99, FeeConfig::TAKER_FEE_PPM), Some(1));
+    assert_eq!(rebate::taker_fee(150, FeeConfig::TAKER_FEE_PPM), Some(2));
+    assert_eq!(rebate::taker_fee(0, FeeConfig::TAKER_FEE_PPM), Some(0));
+}
+
+#[test]
+fn match_pays_fees_into_the_pool_and_credits_makers() {
+    set_block_number(5);
+    let mut inputs = orders();
+    inputs.push(output(pool(0, false, 3, 4, 50), true));
+    let mut outputs = payouts_and_credits(0);
+    outputs.push(output(pool(0, false, 4, 6, 249), true));
+
+    assert_eq!(check_match(inputs, outputs), Ok(0));
+}
+
+#[test]
+fn match_without_the_pool_fails() {
+    set_block_number(5);
+    let outputs = payouts_and_credits(0);
+
+    assert_eq!(check_match(orders(), outputs), Err(DexError::TypeError));
+    assert_eq!(
+        check_match(vec![], vec![]),
+        Err(DexError::RebatePoolMissing)
+    );
+}
+
+#[test]
+fn match_that_underpays_the_pool_fails() {
+    set_block_number(5);
+    let mut inputs = orders();
+    inputs.push(output(pool(0, false, 0, 0, 0), true));
+    let mut outputs = payouts_and_credits(0);
+    outputs.push(output(pool(0, false, 1, 1, 199), true));
+
+    assert_eq!(
+        check_match(inputs, outputs),
+        Err(DexError::RebatePoolMismatch)
+    );
+}
+
+#[test]
+fn match_whose_surplus_does_not_cover_the_fee_fails() {
+    set_block_number(5);
+    // Asking for all 100 A leaves no surplus of token A for the fee.
+    let mut inputs = orders();
+    inputs[1] = output(
+        Order::<OppositeSide<FeeConfig>>::new(160, 100, TestVerifier { verifies: false }),
+        true,
+    );
+    inputs.push(output(pool(0, false, 0, 0, 0), true));
+    let outputs = vec![
+        output(Coin::<1>(150), true),
+        output(Coin::<0>(100), false),
+        output(credit(0, 100), true),
+        output(credit(0, 100), false),
+        output(pool(0, false, 1, 2, 200), true),
+    ];
+
+    assert_eq!(
+        check_match(inputs, outputs),
+        Err(DexError::InsufficientSurplusForFee)
+    );
+}
+
+#[test]
+fn credit_to_the_wrong_maker_fails() {
+    set_block_number(5);
+    let mut inputs = orders();
+    inputs.push(output(pool(0, false, 0, 0, 0), true));
+    let mut outputs = payouts_and_credits(0);
+    outputs[3].verifier = TestVerifier { verifies: true };
+    outputs.push(output(pool(0, false, 1, 2, 199), true));
+
+    assert_eq!(
+        check_match(inputs, outputs),
+        Err(DexError::MakerCreditMismatch)
+    );
+}
+
+#[test]
+fn inflated_credit_fails() {
+    set_block_number(5);
+    let mut inputs = orders();
+    inputs.push(output(pool(0, false, 0, 0, 0), true));
+    let mut outputs = payouts_and_credits(0);
+    outputs[2] = output(credit(0, 1_000), true);
+    outputs.push(output(pool(0, false, 1, 2, 1_099), true));
+
+    assert_eq!(
+        check_match(inputs, outputs),
+        Err(DexError::MakerCreditMismatch)
+    );
+}
+
+#[test]
+fn first_match_of_an_epoch_closes_the_previous_pool() {
+    set_block_number(12);
+    let mut inputs = orders();
+    inputs.push(output(pool(0, false, 3, 4, 50), true));
+    let mut outputs = payouts_and_credits(1);
+    outputs.push(output(pool(0, true, 3, 4, 50), true));
+    outputs.push(output(pool(1, false, 1, 2, 199), true));
+
+    assert_eq!(check_match(inputs, outputs), Ok(0));
+}
+
+#[test]
+fn rolling_over_without_closing_the_pool_fails() {
+    set_block_number(12);
+    let mut inputs = orders();
+    inputs.push(output(pool(0, false, 3, 4, 50), true));
+    let mut outputs = payouts_and_credits(1);
+    outputs.push(output(pool(1, false, 4, 6, 249), true));
+
+    assert_eq!(
+        check_match(inputs, outputs),
+        Err(DexError::OrderAndPayoutCountDiffer)
+    );
+}
+
+#[test]
+fn closed_pool_collects_no_fees() {
+    set_block_number(12);
+    let mut inputs = orders();
+    inputs.push(output(pool(0, true, 3, 4, 50), true));
+    let mut outputs = payouts_and_credits(1);
+    outputs.push(output(pool(0, true, 3, 4, 50), true));
+    outputs.push(output(pool(1, false, 1, 2, 199), true));
+
+    assert_eq!(
+        check_match(inputs, outputs),
+        Err(DexError::RebatePoolClosed)
+    );
+}
+
+#[test]
+fn matches_without_fees_are_unchanged() {
+    struct FreeConfig;
+    impl DexConfig for FreeConfig {
+        type Verifier = TestVerifier;
+        type A = Coin<0>;
+        type B = Coin<1>;
+    }
+
+    let inputs = vec![
+        output(
+            Order::<FreeConfig>::new(100, 150, TestVerifier { verifies: true }),
+            true,
+        ),
+        output(
+            Order::<OppositeSide<FreeConfig>>::new(150, 100, TestVerifier { verifies: true }),
+            true,
+        ),
+    ];
+    let outputs = vec![output(Coin::<1>(150), true), output(Coin::<0>(100), true)];
+
+    let result = <MatchOrders<FreeConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn makers_claim_their_share_of_a_closed_pool() {
+    let inputs = vec![
+        output(pool(0, true, 10, 21, 200), true),
+        output(credit(0, 100), true),
+    ];
+    let outputs = vec![
+        output(pool(0, true, 5, 11, 100), true),
+        output(Coin::<0>(5), true),
+        output(Coin::<1>(10), true),
+    ];
+
+    assert_eq!(check_claim(inputs, outputs), Ok(0));
+}
+
+#[test]
+fn claiming_more_than_the_share_fails() {
+    let inputs = vec![
+        output(pool(0, true, 10, 21, 200), true),
+        output(credit(0, 100), true),
+    ];
+    let outputs = vec![
+        output(pool(0, true, 5, 11, 100), true),
+        output(Coin::<0>(6), true),
+    ];
+
+    assert_eq!(
+        check_claim(inputs, outputs),
+        Err(DexError::ClaimExceedsRebate)
+    );
+}
+
+#[test]
+fn claiming_from_an_open_pool_fails() {
+    let inputs = vec![
+        output(pool(0, false, 10, 20, 200), true),
+        output(credit(0, 100), true),
+    ];
+    let outputs = vec![output(pool(0, false, 5, 10, 100), true)];
+
+    assert_eq!(
+        check_claim(inputs, outputs),
+        Err(DexError::RebatePoolNotClosed)
+    );
+}
+
+#[test]
+fn claiming_with_a_credit_from_another_epoch_fails() {
+    let inputs = vec![
+        output(pool(0, true, 10, 20, 200), true),
+        output(credit(1, 100), true),
+    ];
+    let outputs = vec![output(pool(0, true, 5, 10, 100), true)];
+
+    assert_eq!(
+        check_claim(inputs, outputs),
+        Err(DexError::WrongRebateEpoch)
+    );
+}
+
+#[test]
+fn claim_must_keep_the_rest_of_the_pool() {
+    let inputs = vec![
+        output(pool(0, true, 10, 20, 200), true),
+        output(credit(0, 100), true),
+    ];
+    let outputs = vec![output(pool(0, true, 0, 0, 100), true)];
+
+    assert_eq!(
+        check_claim(inputs, outputs),
+        Err(DexError::RebatePoolMismatch)
+    );
+}
+
+#[test]
+fn claiming_more_volume_than_the_pool_holds_fails() {
+    let inputs = vec![
+        output(pool(0, true, 10, 20, 50), true),
+        output(credit(0, 100), true),
+    ];
+    let outputs = vec![output(pool(0, true, 0, 0, 0), true)];
+
+    assert_eq!(
+        check_claim(inputs, outputs),
+        Err(DexError::MakerCreditMismatch)
+    );
+}
+
+#[test]
+fn surplus_left_after_the_fee_can_be_taken() {
+    set_block_number(5);
+    let mut inputs = orders();
+    inputs.push(output(pool(0, false, 3, 4, 50), true));
+    let mut outputs = payouts_and_credits(0);
+    outputs.push(output(pool(0, false, 4, 6, 249), true));
+    outputs.push(output(Coin::<1>(8), true));
+
+    assert_eq!(check_match(inputs, outputs), Ok(0));
+}
+
+#[test]
+fn surplus_taken_by_the_fee_cannot_be_taken_again() {
+    set_block_number(5);
+    for taken in [output(Coin::<1>(9), true), output(Coin::<0>(1), true)] {
+        let mut inputs = orders();
+        inputs.push(output(pool(0, false, 3, 4, 50), true));
+        let mut outputs = payouts_and_credits(0);
+        outputs.push(output(pool(0, false, 4, 6, 249), true));
+        outputs.push(taken);
+
+        assert_eq!(
+            check_match(inputs, outputs),
+            Err(DexError::SurplusOutputsExceedSurplus)
+        );
+    }
+}
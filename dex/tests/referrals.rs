@@ -0,0 +1,261 @@
+use dex::*;
+use money::Coin;
+use sp_runtime::transaction_validity::TransactionPriority;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+/// A dex that charges a 1% fee, and has matchers pay referrers half as much again.
+struct ReferralConfig;
+impl DexConfig for ReferralConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const TAKER_FEE_PPM: u32 = 10_000;
+    const REFERRAL_SHARE_PPM: u32 = 500_000;
+}
+
+/// A dex without fees, which has nothing to share with referrers.
+struct FreeConfig;
+impl DexConfig for FreeConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    const REFERRAL_SHARE_PPM: u32 = 500_000;
+}
+
+/// The verifier of the front-end that referred the match.
+const REFERRER: TestVerifier = TestVerifier { verifies: false };
+
+fn output(payload: impl Into<DynamicallyTypedData>, verifies: bool) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies },
+    }
+}
+
+fn order<T: DexConfig<Verifier = TestVerifier>>(
+    offer_amount: u128,
+    ask_amount: u128,
+    payout_to: bool,
+) -> Order<T> {
+    Order::new(
+        offer_amount,
+        ask_amount,
+        TestVerifier {
+            verifies: payout_to,
+        },
+    )
+}
+
+fn pool(fees_a: u128, fees_b: u128, volume: u128) -> RebatePool<ReferralConfig> {
+    RebatePool {
+        epoch: 0,
+        closed: false,
+        fees_a,
+        fees_b,
+        volume,
+        _ph_data: Default::default(),
+    }
+}
+
+fn credit(volume: u128) -> MakerCredit<ReferralConfig> {
+    MakerCredit {
+        epoch: 0,
+        volume,
+        _ph_data: Default::default(),
+    }
+}
+
+/// An order offering 1020 A for 1500 B and an order offering 1600 B for 990 A, followed by
+/// an empty pool. The match leaves a surplus of 30 A and 100 B, and its fee is 10 A and
+/// 15 B. The referrer's share of the fee is 5 A and 7 B, which leaves the matcher 15 A and
+/// 78 B.
+fn inputs() -> Vec<Output<TestVerifier>> {
+    vec![
+        output(order::<ReferralConfig>(1020, 1500, true), true),
+        output(
+            order::<OppositeSide<ReferralConfig>>(1600, 990, false),
+            true,
+        ),
+        output(pool(0, 0, 0), true),
+    ]
+}
+
+/// The payouts and maker credits of matching the orders of `inputs()`, and the pool
+/// holding the given fees.
+fn rebate_outputs(fees_a: u128, fees_b: u128) -> Vec<Output<TestVerifier>> {
+    vec![
+        output(Coin::<1>(1500), true),
+        output(Coin::<0>(990), false),
+        output(credit(1020), true),
+        output(credit(990), false),
+        output(pool(fees_a, fees_b, 2010), true),
+    ]
+}
+
+fn check_match<T: DexConfig<Verifier = TestVerifier>>(
+    referrer: Option<TestVerifier>,
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, DexError> {
+    let checker = MatchOrders::<T> {
+        referrer,
+        _ph_data: Default::default(),
+    };
+    checker.check(&inputs, &outputs)
+}
+
+#[test]
+fn referral_share_rounds_down() {
+    assert_eq!(rebate::referral_share(15, 500_000), Some(7));
+    assert_eq!(rebate::referral_share(1, 500_000), Some(0));
+    assert_eq!(rebate::referral_share(10, 0), Some(0));
+    // A share above the whole fee is capped at the fee.
+    assert_eq!(rebate::referral_share(10, 2_000_000), Some(10));
+}
+
+#[test]
+fn referred_match_pays_the_referrer_its_share() {
+    let mut outputs = rebate_outputs(10, 15);
+    outputs.push(output(Coin::<0>(5), false));
+    outputs.push(output(Coin::<1>(7), false));
+    // The matcher takes the rest of the surplus.
+    outputs.push(output(Coin::<0>(15), true));
+    outputs.push(output(Coin::<1>(78), true));
+
+    assert_eq!(
+        check_match::<ReferralConfig>(Some(REFERRER), inputs(), outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn match_without_a_referrer_leaves_the_share_to_the_matcher() {
+    let mut outputs = rebate_outputs(10, 15);
+    outputs.push(output(Coin::<0>(20), true));
+    outputs.push(output(Coin::<1>(85), true));
+
+    assert_eq!(
+        check_match::<ReferralConfig>(None, inputs(), outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn referred_match_that_takes_the_share_out_of_the_pool_fails() {
+    let mut outputs = rebate_outputs(5, 8);
+    outputs.push(output(Coin::<0>(5), false));
+    outputs.push(output(Coin::<1>(7), false));
+
+    assert_eq!(
+        check_match::<ReferralConfig>(Some(REFERRER), inputs(), outputs),
+        Err(DexError::RebatePoolMismatch)
+    );
+}
+
+#[test]
+fn referred_match_that_underpays_the_referrer_fails() {
+    let mut outputs = rebate_outputs(10, 15);
+    outputs.push(output(Coin::<0>(4), false));
+    outputs.push(output(Coin::<1>(7), false));
+
+    assert_eq!(
+        check_match::<ReferralConfig>(Some(REFERRER), inputs(), outputs),
+        Err(DexError::ReferralNotPaid)
+    );
+}
+
+#[test]
+fn referred_match_that_pays_someone_else_fails() {
+    let mut outputs = rebate_outputs(10, 15);
+    outputs.push(output(Coin::<0>(5), false));
+    outputs.push(output(Coin::<1>(7), true));
+
+    assert_eq!(
+        check_match::<ReferralConfig>(Some(REFERRER), inputs(), outputs),
+        Err(DexError::ReferralNotPaid)
+    );
+}
+
+#[test]
+fn referred_match_without_the_referral_payouts_fails() {
+    let outputs = rebate_outputs(10, 15);
+
+    assert_eq!(
+        check_match::<ReferralConfig>(Some(REFERRER), inputs(), outputs),
+        Err(DexError::ReferralNotPaid)
+    );
+}
+
+#[test]
+fn referral_payouts_come_out_of_the_surplus_not_the_fee() {
+    let mut outputs = rebate_outputs(10, 15);
+    outputs.push(output(Coin::<0>(5), false));
+    outputs.push(output(Coin::<1>(7), false));
+    outputs.push(output(Coin::<1>(79), true));
+
+    assert_eq!(
+        check_match::<ReferralConfig>(Some(REFERRER), inputs(), outputs),
+        Err(DexError::SurplusOutputsExceedSurplus)
+    );
+}
+
+#[test]
+fn referred_match_without_surplus_for_the_share_fails() {
+    // Without the extra 20 A of the order, all the surplus of token A goes to the fee.
+    let mut inputs = inputs();
+    inputs[0] = output(order::<ReferralConfig>(1000, 1500, true), true);
+    let mut outputs = rebate_outputs(10, 15);
+    outputs[2] = output(credit(1000), true);
+    outputs[4] = output(pool(10, 15, 1990), true);
+    outputs.push(output(Coin::<0>(5), false));
+    outputs.push(output(Coin::<1>(7), false));
+
+    assert_eq!(
+        check_match::<ReferralConfig>(Some(REFERRER), inputs, outputs),
+        Err(DexError::SurplusOutputsExceedSurplus)
+    );
+}
+
+#[test]
+fn self_referral_only_pays_the_matcher_its_own_surplus() {
+    // The matcher names itself as the referrer. Together with the share it pays itself, it
+    // takes what it would without a referrer, and the makers get the whole fee either way.
+    let matcher = TestVerifier { verifies: true };
+    let mut outputs = rebate_outputs(10, 15);
+    outputs.push(output(Coin::<0>(5), true));
+    outputs.push(output(Coin::<1>(7), true));
+    outputs.push(output(Coin::<0>(15), true));
+    outputs.push(output(Coin::<1>(78), true));
+    assert_eq!(
+        check_match::<ReferralConfig>(Some(matcher.clone()), inputs(), outputs),
+        Ok(0)
+    );
+
+    let mut outputs = rebate_outputs(10, 15);
+    outputs.push(output(Coin::<0>(5), true));
+    outputs.push(output(Coin::<1>(7), true));
+    outputs.push(output(Coin::<0>(15), true));
+    outputs.push(output(Coin::<1>(79), true));
+    assert_eq!(
+        check_match::<ReferralConfig>(Some(matcher), inputs(), outputs),
+        Err(DexError::SurplusOutputsExceedSurplus)
+    );
+}
+
+#[test]
+fn referrers_of_matches_without_fees_get_nothing() {
+    let inputs = vec![
+        output(order::<FreeConfig>(1000, 1500, true), true),
+        output(order::<OppositeSide<FreeConfig>>(1600, 990, false), true),
+    ];
+    let outputs = vec![output(Coin::<1>(1500), true), output(Coin::<0>(990), false)];
+
+    assert_eq!(
+        check_match::<FreeConfig>(Some(REFERRER), inputs, outputs),
+        Ok(0)
+    );
+}
@@ -0,0 +1,177 @@
+use dex::route::{FirstHopOrder, RoutedOrder, SecondHopOrder};
+use dex::*;
+use money::Coin;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+struct TestRoute;
+impl RouteConfig for TestRoute {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+    type C = Coin<2>;
+}
+
+/// The regular dex between tokens 0 and 2, whose orders serve as the first hop.
+struct Pair02;
+impl DexConfig for Pair02 {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<2>;
+}
+
+type RouteTestMatch = RouteMatch<TestRoute>;
+
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+fn routed(offer_amount: u128, ask_amount: u128) -> Output<TestVerifier> {
+    output_from(RoutedOrder::<TestRoute>::new(
+        offer_amount,
+        ask_amount,
+        TestVerifier { verifies: true },
+    ))
+}
+
+fn first_hop(offer_amount: u128, ask_amount: u128) -> Output<TestVerifier> {
+    output_from(FirstHopOrder::<TestRoute>::new(
+        offer_amount,
+        ask_amount,
+        TestVerifier { verifies: true },
+    ))
+}
+
+fn second_hop(offer_amount: u128, ask_amount: u128) -> Output<TestVerifier> {
+    output_from(SecondHopOrder::<TestRoute>::new(
+        offer_amount,
+        ask_amount,
+        TestVerifier { verifies: true },
+    ))
+}
+
+fn payouts(b: u128, a: u128, c: u128) -> Vec<Output<TestVerifier>> {
+    vec![
+        output_from(Coin::<1>(b)),
+        output_from(Coin::<0>(a)),
+        output_from(Coin::<2>(c)),
+    ]
+}
+
+#[test]
+fn routing_through_intermediate_token_works() {
+    let result = <RouteTestMatch as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![routed(100, 50), first_hop(200, 100), second_hop(50, 200)],
+        &payouts(50, 100, 200),
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn first_hop_may_be_a_regular_order() {
+    let regular = output_from(Order::<OppositeSide<Pair02>>::new(
+        200,
+        100,
+        TestVerifier { verifies: true },
+    ));
+
+    let result = <RouteTestMatch as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![routed(100, 50), regular, second_hop(50, 200)],
+        &payouts(50, 100, 200),
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn route_with_insufficient_intermediate_fails() {
+    let result = <RouteTestMatch as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![routed(100, 50), first_hop(199, 100), second_hop(50, 200)],
+        &payouts(50, 100, 200),
+    );
+    assert_eq!(result, Err(DexError::InsufficientIntermediateForRoute));
+}
+
+#[test]
+fn route_with_insufficient_a_fails() {
+    let result = <RouteTestMatch as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![routed(99, 50), first_hop(200, 100), second_hop(50, 200)],
+        &payouts(50, 100, 200),
+    );
+    assert_eq!(result, Err(DexError::InsufficientTokenAForMatch));
+}
+
+#[test]
+fn route_with_insufficient_b_fails() {
+    let result = <RouteTestMatch as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![routed(100, 50), first_hop(200, 100), second_hop(49, 200)],
+        &payouts(50, 100, 200),
+    );
+    assert_eq!(result, Err(DexError::InsufficientTokenBForMatch));
+}
+
+#[test]
+fn route_with_underpaid_hop_fails() {
+    let result = <RouteTestMatch as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![routed(100, 50), first_hop(200, 100), second_hop(50, 200)],
+        &payouts(50, 100, 199),
+    );
+    assert_eq!(
+        result,
+        Err(DexError::PayoutMismatch {
+            index: 2,
+            side: Side::OffersA
+        })
+    );
+}
+
+#[test]
+fn route_with_wrong_verifier_fails() {
+    let mut outputs = payouts(50, 100, 200);
+    outputs[1].verifier = TestVerifier { verifies: false };
+
+    let result = <RouteTestMatch as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![routed(100, 50), first_hop(200, 100), second_hop(50, 200)],
+        &outputs,
+    );
+    assert_eq!(
+        result,
+        Err(DexError::PayoutVerifierMismatch {
+            index: 1,
+            side: Side::OffersA
+        })
+    );
+}
+
+#[test]
+fn route_with_missing_hop_fails() {
+    let mut outputs = payouts(50, 100, 200);
+    outputs.pop();
+
+    let result = <RouteTestMatch as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![routed(100, 50), first_hop(200, 100)],
+        &outputs,
+    );
+    assert_eq!(result, Err(DexError::WrongNumberOfOrdersInRoute));
+}
+
+#[test]
+fn route_with_hops_swapped_fails() {
+    let result = <RouteTestMatch as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![routed(100, 50), second_hop(50, 200), first_hop(200, 100)],
+        &payouts(50, 100, 200),
+    );
+    assert_eq!(result, Err(DexError::TypeError));
+}
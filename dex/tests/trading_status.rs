@@ -0,0 +1,164 @@
+use dex::*;
+use money::Coin;
+use sp_runtime::transaction_validity::TransactionPriority;
+use std::cell::Cell;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+
+thread_local! {
+    static PAUSED: Cell<bool> = Cell::new(false);
+}
+
+fn set_paused(paused: bool) {
+    PAUSED.with(|p| p.set(paused));
+}
+
+/// A dex whose trading status is set by the test, as an admin would.
+struct PausableConfig;
+impl DexConfig for PausableConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    fn trading_paused() -> bool {
+        PAUSED.with(|p| p.get())
+    }
+}
+
+fn order<T: DexConfig<Verifier = TestVerifier>>(offer_amount: u128, ask_amount: u128) -> Order<T> {
+    Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
+}
+
+fn output(payload: impl Into<DynamicallyTypedData>) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+fn make_order() -> Result<TransactionPriority, DexError> {
+    <MakeOrder<PausableConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &[output(Coin::<0>(10))],
+        &[output(order::<PausableConfig>(10, 5))],
+    )
+}
+
+fn match_orders() -> Result<TransactionPriority, DexError> {
+    <MatchOrders<PausableConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output(order::<PausableConfig>(10, 5)),
+            output(order::<OppositeSide<PausableConfig>>(5, 10)),
+        ],
+        &vec![output(Coin::<1>(5)), output(Coin::<0>(10))],
+    )
+}
+
+fn cancel_order(refunds: Vec<Output<TestVerifier>>) -> Result<TransactionPriority, DexError> {
+    <CancelOrder<PausableConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output(order::<PausableConfig>(10, 5))],
+        &refunds,
+    )
+}
+
+#[test]
+fn trading_is_allowed_by_default() {
+    set_paused(false);
+    assert!(make_order().is_ok());
+    assert!(match_orders().is_ok());
+}
+
+#[test]
+fn paused_trading_rejects_new_orders() {
+    set_paused(true);
+    assert_eq!(make_order(), Err(DexError::TradingPaused));
+}
+
+#[test]
+fn paused_trading_rejects_matches() {
+    set_paused(true);
+    assert_eq!(match_orders(), Err(DexError::TradingPaused));
+}
+
+#[test]
+fn pausing_applies_to_both_sides_of_the_pair() {
+    set_paused(true);
+    let result =
+        <MakeOrder<OppositeSide<PausableConfig>> as ConstraintChecker<TestVerifier>>::check(
+            &Default::default(),
+            &[output(Coin::<1>(5))],
+            &[output(order::<OppositeSide<PausableConfig>>(5, 10))],
+        );
+    assert_eq!(result, Err(DexError::TradingPaused));
+}
+
+#[test]
+fn cancelling_works_while_paused() {
+    set_paused(true);
+    assert!(cancel_order(vec![output(Coin::<0>(10))]).is_ok());
+}
+
+#[test]
+fn cancelling_may_split_the_refund() {
+    set_paused(false);
+    assert!(cancel_order(vec![output(Coin::<0>(4)), output(Coin::<0>(6))]).is_ok());
+}
+
+#[test]
+fn cancelling_cannot_refund_more_than_the_offer() {
+    set_paused(false);
+    assert_eq!(
+        cancel_order(vec![output(Coin::<0>(11))]),
+        Err(DexError::RefundExceedsOrder)
+    );
+}
+
+#[test]
+fn cancelling_refunds_only_the_offered_token() {
+    set_paused(false);
+    assert_eq!(
+        cancel_order(vec![output(Coin::<1>(5))]),
+        Err(DexError::TypeError)
+    );
+}
+
+#[test]
+fn cancelling_refunds_only_the_maker() {
+    set_paused(false);
+    let refund = Output {
+        payload: Coin::<0>(10).into(),
+        verifier: TestVerifier { verifies: false },
+    };
+    assert_eq!(
+        cancel_order(vec![refund]),
+        Err(DexError::VerifierMismatchForTrade)
+    );
+}
+
+#[test]
+fn cancelling_requires_exactly_one_order() {
+    set_paused(false);
+    let result = <CancelOrder<PausableConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![
+            output(order::<PausableConfig>(10, 5)),
+            output(order::<PausableConfig>(10, 5)),
+        ],
+        &vec![output(Coin::<0>(20))],
+    );
+    assert_eq!(result, Err(DexError::CancelRequiresOneOrder));
+}
+
+#[test]
+fn cancelling_requires_an_order() {
+    set_paused(false);
+    let result = <CancelOrder<PausableConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &vec![output(Coin::<0>(10))],
+        &vec![output(Coin::<0>(10))],
+    );
+    assert_eq!(result, Err(DexError::TypeError));
+}
@@ -0,0 +1,127 @@
+use dex::*;
+use money::Coin;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, types::Output, verifier::TestVerifier, ConstraintChecker,
+};
+use vesting::{Vested, VestingSchedule};
+
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+const LOCKUP: VestingSchedule = VestingSchedule {
+    start: 1_000,
+    duration: 500,
+};
+
+fn order(offer_amount: u128, ask_amount: u128, payout_kind: PayoutKind) -> Order<TestConfig> {
+    Order {
+        payout_kind,
+        ..Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
+    }
+}
+
+fn reverse_order(offer_amount: u128, ask_amount: u128) -> Order<OppositeSide<TestConfig>> {
+    Order::new(offer_amount, ask_amount, TestVerifier { verifies: true })
+}
+
+fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies: true },
+    }
+}
+
+fn check(
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<sp_runtime::transaction_validity::TransactionPriority, DexError> {
+    <MatchOrders<TestConfig> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        &inputs,
+        &outputs,
+    )
+}
+
+#[test]
+fn vested_orders_are_paid_in_vested_coins() {
+    // A buyer in a token sale gets token 1 that unlocks after the sale.
+    let result = check(
+        vec![
+            output_from(order(100, 200, PayoutKind::Vested(LOCKUP))),
+            output_from(reverse_order(200, 100)),
+        ],
+        vec![
+            output_from(Vested::<Coin<1>>::new(200, LOCKUP)),
+            output_from(Coin::<0>(100)),
+        ],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn vested_orders_cannot_be_paid_in_plain_coins() {
+    let result = check(
+        vec![
+            output_from(order(100, 200, PayoutKind::Vested(LOCKUP))),
+            output_from(reverse_order(200, 100)),
+        ],
+        vec![output_from(Coin::<1>(200)), output_from(Coin::<0>(100))],
+    );
+    assert_eq!(result, Err(DexError::TypeError));
+}
+
+#[test]
+fn vested_payouts_must_follow_the_order_schedule() {
+    let early = VestingSchedule {
+        start: 0,
+        duration: 0,
+    };
+    let result = check(
+        vec![
+            output_from(order(100, 200, PayoutKind::Vested(LOCKUP))),
+            output_from(reverse_order(200, 100)),
+        ],
+        vec![
+            output_from(Vested::<Coin<1>>::new(200, early)),
+            output_from(Coin::<0>(100)),
+        ],
+    );
+    assert_eq!(result, Err(DexError::PayoutKindMismatch));
+
+    let mut released = Vested::<Coin<1>>::new(200, LOCKUP);
+    released.released = 1;
+    let result = check(
+        vec![
+            output_from(order(100, 200, PayoutKind::Vested(LOCKUP))),
+            output_from(reverse_order(200, 100)),
+        ],
+        vec![output_from(released), output_from(Coin::<0>(100))],
+    );
+    assert_eq!(result, Err(DexError::PayoutKindMismatch));
+}
+
+#[test]
+fn vested_and_plain_orders_do_not_share_payouts() {
+    let result = check(
+        vec![
+            output_from(order(50, 100, PayoutKind::Vested(LOCKUP))),
+            output_from(order(50, 100, PayoutKind::Coin)),
+            output_from(reverse_order(200, 100)),
+        ],
+        vec![
+            output_from(Vested::<Coin<1>>::new(200, LOCKUP)),
+            output_from(Coin::<0>(100)),
+        ],
+    );
+    assert_eq!(
+        result,
+        Err(DexError::PayoutMismatch {
+            index: 0,
+            side: Side::OffersA
+        })
+    );
+}
@@ -0,0 +1,170 @@
+//! A faucet that hands out test coins, so that new users of a testnet can get funds.
+//!
+//! A [`Faucet`] UTXO, placed in the genesis state for each coin, is protected by a verifier
+//! that anyone can satisfy. Anyone may [`Drip`] from it: spend it to mint up to
+//! [`FaucetConfig::MAX_DRIP`] new coins for a single recipient, and recreate it. The faucet
+//! remembers who it dripped to in which block, and turns away recipients that it dripped to
+//! less than [`FaucetConfig::COOLDOWN`] blocks ago. Entries that have cooled down are
+//! forgotten on the next drip, so the faucet stays small.
+//!
+//! The cooldown is tracked for each recipient verifier, so it only slows down users who
+//! reuse their keys. That is enough for a testnet, where the coins are worth nothing, but
+//! this piece must never be part of a runtime whose coins are worth something. Each drip
+//! spends the faucet, so there is at most one drip from each faucet in each block.
+//!
+//! The current block is not known to constraint checkers, so the runtime hands it to this
+//! piece through [`FaucetConfig::block_number`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    traits::Cash,
+    types::Output,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The configuration of the faucet of one coin.
+pub trait FaucetConfig {
+    /// The type of verifiers that receive coins from the faucet.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The coin that the faucet hands out
+    type Coin: Cash + UtxoData;
+
+    /// The most coins that a single drip may mint.
+    const MAX_DRIP: u128;
+    /// The number of blocks a recipient must wait between drips.
+    const COOLDOWN: u32;
+
+    /// The verifier that protects the faucet, which anyone must be able to satisfy.
+    fn open_verifier() -> Self::Verifier;
+
+    /// The number of the block that transactions are being checked in.
+    fn block_number() -> u32;
+}
+
+/// The faucet of one coin, with the recipients it dripped to recently.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Faucet<T: FaucetConfig> {
+    /// The recipients that are still cooling down, with the block they got coins in,
+    /// in the order they got them
+    pub recent: Vec<(T::Verifier, u32)>,
+}
+
+impl<T: FaucetConfig> UtxoData for Faucet<T> {
+    const TYPE_ID: [u8; 4] = [b'f', b'c', b't', T::Coin::ID];
+}
+
+impl<T: FaucetConfig> Faucet<T> {
+    /// Whether the given recipient may get coins from the faucet in the given block.
+    pub fn may_drip_to(&self, recipient: &T::Verifier, now: u32) -> bool {
+        !self
+            .recent
+            .iter()
+            .any(|(past, at)| past == recipient && now < at.saturating_add(T::COOLDOWN))
+    }
+
+    /// The faucet after dripping to the given recipient in the given block.
+    ///
+    /// Clients build the faucet output of a drip with this.
+    pub fn after_drip(&self, recipient: T::Verifier, now: u32) -> Self {
+        let mut recent: Vec<_> = self
+            .recent
+            .iter()
+            .filter(|(_, at)| now < at.saturating_add(T::COOLDOWN))
+            .cloned()
+            .collect();
+        recent.push((recipient, now));
+        Self { recent }
+    }
+}
+
+/// All the things that can go wrong while checking faucet transactions.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum FaucetError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// The single input, and the first output, must be the faucet.
+    FaucetMissing,
+    /// The recreated faucet is not protected by the open verifier.
+    NotOpen,
+    /// The drip must mint some coins, all for the same recipient.
+    NoSingleRecipient,
+    /// Some amount does not fit in a `u128`.
+    AmountOverflow,
+    /// The drip mints more coins than the faucet hands out at once.
+    DripTooLarge,
+    /// The faucet dripped to the recipient too recently.
+    CoolingDown,
+    /// The recreated faucet must forget the recipients that have cooled down, and record
+    /// this drip, but keep everything else.
+    FaucetChanged,
+}
+
+impl From<DynamicTypingError> for FaucetError {
+    fn from(_value: DynamicTypingError) -> Self {
+        FaucetError::TypeError
+    }
+}
+
+/// Mint test coins for a recipient who has not had any recently.
+///
+/// The single input is the faucet. The first output is the faucet after this drip, as given
+/// by [`Faucet::after_drip`], and the rest are the new coins, all protected by the
+/// recipient's verifier and worth at most [`FaucetConfig::MAX_DRIP`] together.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Drip<T: FaucetConfig>(pub PhantomData<T>);
+
+impl<T: FaucetConfig> ConstraintChecker<T::Verifier> for Drip<T> {
+    type Error = FaucetError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, FaucetError::FaucetMissing);
+        let faucet: Faucet<T> = inputs[0].payload.extract()?;
+        let (next, coins) = outputs.split_first().ok_or(FaucetError::FaucetMissing)?;
+        ensure!(next.verifier == T::open_verifier(), FaucetError::NotOpen);
+        let next: Faucet<T> = next.payload.extract()?;
+
+        let recipient = &coins
+            .first()
+            .ok_or(FaucetError::NoSingleRecipient)?
+            .verifier;
+        let mut total = 0u128;
+        for coin in coins {
+            ensure!(coin.verifier == *recipient, FaucetError::NoSingleRecipient);
+            let value = coin.payload.extract::<T::Coin>()?.value();
+            total = total
+                .checked_add(value)
+                .ok_or(FaucetError::AmountOverflow)?;
+        }
+        ensure!(total <= T::MAX_DRIP, FaucetError::DripTooLarge);
+
+        let now = T::block_number();
+        ensure!(faucet.may_drip_to(recipient, now), FaucetError::CoolingDown);
+        ensure!(
+            next.recent == faucet.after_drip(recipient.clone(), now).recent,
+            FaucetError::FaucetChanged
+        );
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,163 @@
+//! Unit tests for the faucet piece
+
+use super::*;
+use money::Coin;
+use std::cell::Cell;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+thread_local! {
+    static BLOCK: Cell<u32> = Cell::new(0);
+}
+
+fn set_block(block: u32) {
+    BLOCK.with(|b| b.set(block));
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct TestConfig;
+impl FaucetConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type Coin = Coin<0>;
+
+    const MAX_DRIP: u128 = 100;
+    const COOLDOWN: u32 = 10;
+
+    fn open_verifier() -> TestVerifier {
+        ANYONE
+    }
+
+    fn block_number() -> u32 {
+        BLOCK.with(|b| b.get())
+    }
+}
+
+/// The verifier that protects the faucet, and that the first recipient uses.
+const ANYONE: TestVerifier = TestVerifier { verifies: true };
+/// The verifier of a second recipient.
+const OTHER: TestVerifier = TestVerifier { verifies: false };
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn faucet(recent: Vec<(TestVerifier, u32)>) -> Output<TestVerifier> {
+    output(Faucet::<TestConfig> { recent }, ANYONE)
+}
+
+fn drip(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, FaucetError> {
+    Drip::<TestConfig>::default().check(inputs, outputs)
+}
+
+#[test]
+fn anyone_gets_coins_up_to_the_cap() {
+    set_block(5);
+    assert_eq!(
+        drip(
+            &[faucet(vec![])],
+            &[
+                faucet(vec![(ANYONE, 5)]),
+                output(Coin::<0>(60), ANYONE),
+                output(Coin::<0>(40), ANYONE)
+            ],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        drip(
+            &[faucet(vec![])],
+            &[faucet(vec![(ANYONE, 5)]), output(Coin::<0>(101), ANYONE)],
+        ),
+        Err(FaucetError::DripTooLarge)
+    );
+    assert_eq!(
+        drip(
+            &[faucet(vec![])],
+            &[
+                faucet(vec![(ANYONE, 5)]),
+                output(Coin::<0>(50), ANYONE),
+                output(Coin::<0>(50), OTHER)
+            ],
+        ),
+        Err(FaucetError::NoSingleRecipient)
+    );
+}
+
+#[test]
+fn recipients_wait_out_the_cooldown() {
+    let dripped = faucet(vec![(ANYONE, 5)]);
+
+    set_block(14);
+    assert_eq!(
+        drip(
+            &[dripped.clone()],
+            &[
+                faucet(vec![(ANYONE, 5), (ANYONE, 14)]),
+                output(Coin::<0>(1), ANYONE)
+            ],
+        ),
+        Err(FaucetError::CoolingDown)
+    );
+    assert_eq!(
+        drip(
+            &[dripped.clone()],
+            &[
+                faucet(vec![(ANYONE, 5), (OTHER, 14)]),
+                output(Coin::<0>(1), OTHER)
+            ],
+        ),
+        Ok(0)
+    );
+
+    set_block(15);
+    assert_eq!(
+        drip(
+            &[dripped],
+            &[faucet(vec![(ANYONE, 15)]), output(Coin::<0>(1), ANYONE)],
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn the_faucet_must_record_the_drip() {
+    set_block(20);
+    let dripped = faucet(vec![(OTHER, 15)]);
+    assert_eq!(
+        drip(
+            &[dripped.clone()],
+            &[faucet(vec![]), output(Coin::<0>(1), ANYONE)],
+        ),
+        Err(FaucetError::FaucetChanged)
+    );
+    assert_eq!(
+        drip(
+            &[dripped.clone()],
+            &[faucet(vec![(ANYONE, 20)]), output(Coin::<0>(1), ANYONE)],
+        ),
+        Err(FaucetError::FaucetChanged)
+    );
+    assert_eq!(
+        drip(
+            &[dripped],
+            &[
+                output(
+                    Faucet::<TestConfig> {
+                        recent: vec![(OTHER, 15), (ANYONE, 20)]
+                    },
+                    OTHER
+                ),
+                output(Coin::<0>(1), ANYONE)
+            ],
+        ),
+        Err(FaucetError::NotOpen)
+    );
+}
@@ -0,0 +1,110 @@
+//! Runtime parameters that a council can update while the chain is running.
+//!
+//! Pieces are usually configured with compile-time constants, which can only change with a
+//! runtime upgrade. This piece instead keeps a set of parameters in a UTXO. The UTXO is
+//! protected by the council's verifier, typically a threshold multi-signature over the
+//! council's keys, so updating the parameters takes as many council signatures as the
+//! threshold. That is how the council votes.
+//!
+//! The piece is generic over the parameters type, so any piece can have governed parameters
+//! by defining a `UtxoData` type for them. Constraint checkers cannot read UTXOs that their
+//! transaction does not consume, so the current parameters are also recorded in storage as a
+//! side effect of each successful update, in the same way that the timestamp piece records
+//! the current time. Pieces read them from there with [`current`]. The runtime must record
+//! the genesis parameters itself.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The prefix of the storage keys under which the current parameters are recorded.
+pub const PARAMETERS_PREFIX: &[u8] = b"governed_parameters";
+
+/// The storage key at which the current parameters of the given type are recorded.
+pub fn parameters_key<P: UtxoData>() -> Vec<u8> {
+    (PARAMETERS_PREFIX, P::TYPE_ID).encode()
+}
+
+/// The current parameters of the given type, if any have been recorded.
+pub fn current<P: UtxoData + Decode>() -> Option<P> {
+    sp_io::storage::get(&parameters_key::<P>())
+        .and_then(|encoded| P::decode(&mut &encoded[..]).ok())
+}
+
+/// Record the given parameters as the current ones.
+fn set_current<P: UtxoData + Encode>(parameters: &P) {
+    sp_io::storage::set(&parameters_key::<P>(), &parameters.encode());
+}
+
+/// All the things that can go wrong while updating parameters.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum GovernanceError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// An update must consume exactly one input, which is the current parameters.
+    MissingCurrentParameters,
+    /// An update must create exactly one output, which is the new parameters.
+    MissingNewParameters,
+    /// The new parameters must stay under the control of the same council.
+    CouncilChanged,
+}
+
+impl From<DynamicTypingError> for GovernanceError {
+    fn from(_value: DynamicTypingError) -> Self {
+        GovernanceError::TypeError
+    }
+}
+
+/// Replace the current parameters with new ones.
+///
+/// The only input is the current parameters and the only output is the new ones, which
+/// must be protected by the same verifier. Checking the new values is up to the pieces
+/// that read them.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct UpdateParameters<P>(pub PhantomData<P>);
+
+impl<V, P> ConstraintChecker<V> for UpdateParameters<P>
+where
+    V: Verifier + PartialEq,
+    P: UtxoData + Encode,
+{
+    type Error = GovernanceError;
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        outputs: &[Output<V>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, GovernanceError::MissingCurrentParameters);
+        ensure!(outputs.len() == 1, GovernanceError::MissingNewParameters);
+
+        let _current: P = inputs[0].payload.extract()?;
+        let new: P = outputs[0].payload.extract()?;
+        ensure!(
+            outputs[0].verifier == inputs[0].verifier,
+            GovernanceError::CouncilChanged
+        );
+
+        // SIDE EFFECT: Record the new parameters so other pieces can read them
+        set_current(&new);
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,92 @@
+//! Unit tests for the governance piece
+
+use super::*;
+use sp_io::TestExternalities;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Debug, TypeInfo)]
+struct TestParameters {
+    fee: u32,
+}
+
+impl UtxoData for TestParameters {
+    const TYPE_ID: [u8; 4] = *b"tprm";
+}
+
+fn output(payload: impl Into<DynamicallyTypedData>, verifies: bool) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies },
+    }
+}
+
+fn update(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, GovernanceError> {
+    <UpdateParameters<TestParameters> as ConstraintChecker<TestVerifier>>::check(
+        &Default::default(),
+        inputs,
+        outputs,
+    )
+}
+
+#[test]
+fn updating_parameters_works_and_records_them() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(current::<TestParameters>(), None);
+
+        let result = update(
+            &[output(TestParameters { fee: 1 }, true)],
+            &[output(TestParameters { fee: 2 }, true)],
+        );
+        assert_eq!(result, Ok(0));
+        assert_eq!(current::<TestParameters>(), Some(TestParameters { fee: 2 }));
+    });
+}
+
+#[test]
+fn updating_without_current_parameters_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = update(&[], &[output(TestParameters { fee: 2 }, true)]);
+        assert_eq!(result, Err(GovernanceError::MissingCurrentParameters));
+    });
+}
+
+#[test]
+fn updating_without_new_parameters_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = update(&[output(TestParameters { fee: 1 }, true)], &[]);
+        assert_eq!(result, Err(GovernanceError::MissingNewParameters));
+    });
+}
+
+#[test]
+fn updating_other_data_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = update(
+            &[output(TestParameters { fee: 1 }, true)],
+            &[Output {
+                payload: DynamicallyTypedData {
+                    data: 5u32.encode(),
+                    type_id: *b"othr",
+                },
+                verifier: TestVerifier { verifies: true },
+            }],
+        );
+        assert_eq!(result, Err(GovernanceError::TypeError));
+        assert_eq!(current::<TestParameters>(), None);
+    });
+}
+
+#[test]
+fn handing_parameters_to_another_council_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = update(
+            &[output(TestParameters { fee: 1 }, true)],
+            &[output(TestParameters { fee: 2 }, false)],
+        );
+        assert_eq!(result, Err(GovernanceError::CouncilChanged));
+        assert_eq!(current::<TestParameters>(), None);
+    });
+}
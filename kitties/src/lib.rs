@@ -0,0 +1,177 @@
+//! Unique, breedable cryptokitties.
+//!
+//! Each kitty is a non-fungible UTXO identified by its DNA. New kitties can only come into
+//! existence by breeding two existing kitties, a mom and a dad. The child's DNA is derived
+//! from its parents' DNA and the number of times each parent has bred before, so every child
+//! is unique.
+//!
+//! Kitties can also be transferred. Ownership is expressed entirely by the verifiers on the
+//! kitty UTXOs, so a transfer just makes sure the same kitties come out as went in.
+//!
+//! Because kitties are also `UtxoData` with a meaningful `PartialEq`, they can be listed for
+//! sale in the dex piece's fixed-price nft market.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, DynamicallyTypedData, UtxoData},
+    ensure, SimpleConstraintChecker,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Whether a kitty can act as the mom or the dad when breeding.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum Gender {
+    Female,
+    Male,
+}
+
+/// A single kitty.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub struct KittyData {
+    /// Whether this kitty can be a mom or a dad when breeding
+    pub gender: Gender,
+    /// The kitty's unique identity
+    pub dna: H256,
+    /// How many times this kitty has bred. This keeps siblings unique.
+    pub num_breedings: u32,
+}
+
+impl UtxoData for KittyData {
+    const TYPE_ID: [u8; 4] = *b"Kitt";
+}
+
+impl KittyData {
+    /// The child that results from breeding the given mom and dad.
+    pub fn child_of(mom: &KittyData, dad: &KittyData) -> KittyData {
+        let dna = H256::from(sp_io::hashing::blake2_256(
+            &(mom.dna, dad.dna, mom.num_breedings, dad.num_breedings).encode(),
+        ));
+        let gender = if dna[0] % 2 == 0 {
+            Gender::Female
+        } else {
+            Gender::Male
+        };
+
+        KittyData {
+            gender,
+            dna,
+            num_breedings: 0,
+        }
+    }
+
+    /// This same kitty after it has bred one more time.
+    pub fn after_breeding(&self) -> KittyData {
+        KittyData {
+            num_breedings: self.num_breedings.saturating_add(1),
+            ..self.clone()
+        }
+    }
+}
+
+/// The constraint checker for the kitties piece.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum KittiesConstraintChecker {
+    /// Consume a mom and a dad and create the two parents (having bred once more)
+    /// along with their new child. The outputs must be mom, dad, child in that order.
+    Breed,
+    /// Consume some kitties and create the very same kitties, typically with new owners.
+    Transfer,
+}
+
+/// Errors that can occur when checking kitty transactions.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum KittyError {
+    /// Dynamic typing issue.
+    /// This error doesn't discriminate between badly typed inputs and outputs.
+    BadlyTyped,
+    /// Breeding requires exactly two parents as inputs.
+    WrongNumberOfParents,
+    /// The first parent must be female and the second must be male.
+    ParentsWrongGender,
+    /// Breeding must create exactly three outputs: mom, dad, and child.
+    WrongNumberOfBreedingOutputs,
+    /// A parent was not returned, or was returned with the wrong breeding count.
+    ParentNotReturned,
+    /// The child is not the one that results from breeding these parents.
+    WrongChild,
+    /// A transfer must move at least one kitty.
+    TransferringNothing,
+    /// A transfer must create exactly the same kitties that it consumes.
+    TransferMismatch,
+}
+
+impl From<DynamicTypingError> for KittyError {
+    fn from(_value: DynamicTypingError) -> Self {
+        Self::BadlyTyped
+    }
+}
+
+impl SimpleConstraintChecker for KittiesConstraintChecker {
+    type Error = KittyError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        match self {
+            Self::Breed => {
+                ensure!(input_data.len() == 2, KittyError::WrongNumberOfParents);
+                let mom: KittyData = input_data[0].extract()?;
+                let dad: KittyData = input_data[1].extract()?;
+                ensure!(
+                    mom.gender == Gender::Female && dad.gender == Gender::Male,
+                    KittyError::ParentsWrongGender
+                );
+
+                ensure!(
+                    output_data.len() == 3,
+                    KittyError::WrongNumberOfBreedingOutputs
+                );
+                let new_mom: KittyData = output_data[0].extract()?;
+                let new_dad: KittyData = output_data[1].extract()?;
+                let child: KittyData = output_data[2].extract()?;
+
+                ensure!(
+                    new_mom == mom.after_breeding() && new_dad == dad.after_breeding(),
+                    KittyError::ParentNotReturned
+                );
+                ensure!(
+                    child == KittyData::child_of(&mom, &dad),
+                    KittyError::WrongChild
+                );
+
+                Ok(0)
+            }
+            Self::Transfer => {
+                ensure!(!input_data.is_empty(), KittyError::TransferringNothing);
+                ensure!(
+                    input_data.len() == output_data.len(),
+                    KittyError::TransferMismatch
+                );
+
+                for (input, output) in input_data.iter().zip(output_data) {
+                    let kitty: KittyData = input.extract()?;
+                    let transferred: KittyData = output.extract()?;
+                    ensure!(kitty == transferred, KittyError::TransferMismatch);
+                }
+
+                Ok(0)
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Unit tests for the Kitties piece
+
+use super::*;
+
+fn mom() -> KittyData {
+    KittyData {
+        gender: Gender::Female,
+        dna: H256::repeat_byte(1),
+        num_breedings: 3,
+    }
+}
+
+fn dad() -> KittyData {
+    KittyData {
+        gender: Gender::Male,
+        dna: H256::repeat_byte(2),
+        num_breedings: 0,
+    }
+}
+
+fn breed_outputs(mom: &KittyData, dad: &KittyData) -> Vec<DynamicallyTypedData> {
+    vec![
+        mom.after_breeding().into(),
+        dad.after_breeding().into(),
+        KittyData::child_of(mom, dad).into(),
+    ]
+}
+
+#[test]
+fn breeding_works() {
+    let result = KittiesConstraintChecker::Breed.check(
+        &[mom().into(), dad().into()],
+        &breed_outputs(&mom(), &dad()),
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn siblings_are_unique() {
+    let first = KittyData::child_of(&mom(), &dad());
+    let second = KittyData::child_of(&mom().after_breeding(), &dad().after_breeding());
+    assert_ne!(first.dna, second.dna);
+}
+
+#[test]
+fn breeding_with_one_parent_fails() {
+    let result =
+        KittiesConstraintChecker::Breed.check(&[mom().into()], &breed_outputs(&mom(), &dad()));
+    assert_eq!(result, Err(KittyError::WrongNumberOfParents));
+}
+
+#[test]
+fn breeding_parents_in_wrong_order_fails() {
+    let result = KittiesConstraintChecker::Breed.check(
+        &[dad().into(), mom().into()],
+        &breed_outputs(&mom(), &dad()),
+    );
+    assert_eq!(result, Err(KittyError::ParentsWrongGender));
+}
+
+#[test]
+fn breeding_without_child_fails() {
+    let outputs = breed_outputs(&mom(), &dad())[..2].to_vec();
+    let result = KittiesConstraintChecker::Breed.check(&[mom().into(), dad().into()], &outputs);
+    assert_eq!(result, Err(KittyError::WrongNumberOfBreedingOutputs));
+}
+
+#[test]
+fn breeding_without_updating_parents_fails() {
+    let outputs = vec![
+        mom().into(),
+        dad().into(),
+        KittyData::child_of(&mom(), &dad()).into(),
+    ];
+    let result = KittiesConstraintChecker::Breed.check(&[mom().into(), dad().into()], &outputs);
+    assert_eq!(result, Err(KittyError::ParentNotReturned));
+}
+
+#[test]
+fn breeding_a_designer_child_fails() {
+    let mut outputs = breed_outputs(&mom(), &dad());
+    outputs[2] = KittyData {
+        gender: Gender::Female,
+        dna: H256::repeat_byte(42),
+        num_breedings: 0,
+    }
+    .into();
+
+    let result = KittiesConstraintChecker::Breed.check(&[mom().into(), dad().into()], &outputs);
+    assert_eq!(result, Err(KittyError::WrongChild));
+}
+
+#[test]
+fn transfer_works() {
+    let result = KittiesConstraintChecker::Transfer
+        .check(&[mom().into(), dad().into()], &[mom().into(), dad().into()]);
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn transfer_nothing_fails() {
+    let result = KittiesConstraintChecker::Transfer.check(&[], &[]);
+    assert_eq!(result, Err(KittyError::TransferringNothing));
+}
+
+#[test]
+fn transfer_that_mutates_kitty_fails() {
+    let result =
+        KittiesConstraintChecker::Transfer.check(&[mom().into()], &[mom().after_breeding().into()]);
+    assert_eq!(result, Err(KittyError::TransferMismatch));
+}
+
+#[test]
+fn transfer_that_duplicates_kitty_fails() {
+    let result =
+        KittiesConstraintChecker::Transfer.check(&[mom().into()], &[mom().into(), mom().into()]);
+    assert_eq!(result, Err(KittyError::TransferMismatch));
+}
@@ -0,0 +1,605 @@
+//! A lending market, where depositors earn interest from borrowers who lock collateral.
+//!
+//! All the lent token that is not borrowed sits in a single [`Pool`] UTXO, which every
+//! transaction of the market consumes and recreates. Depositors add to the pool and get a
+//! [`Deposit`] in return. Borrowers lock collateral in a [`Loan`] and take tokens from the
+//! pool against it. Interest accrues per block, at [`LendingConfig::BORROW_RATE_PPB`] on
+//! loans and at the lower [`LendingConfig::SUPPLY_RATE_PPB`] on deposits. Repaid interest
+//! stays in the pool, and deposits are withdrawn with their interest from it.
+//!
+//! Every loan must keep collateral worth at least [`LendingConfig::MIN_COLLATERAL_PERCENT`]
+//! of its debt, at a price that the runtime hands to the piece like the cdp piece's. When a
+//! loan no longer does, anyone may liquidate it by repaying its debt. The collateral is not
+//! paid out directly. Instead the liquidation opens dex orders that sell it in the market
+//! between the collateral and the lent token: an order for the liquidator, worth the debt
+//! plus a [`LendingConfig::LIQUIDATION_PENALTY_PERCENT`] penalty, and a Dutch auction of the
+//! rest for the borrower. The auction starts at the current price and falls to a floor over
+//! [`LendingConfig::AUCTION_BLOCKS`], so the rest sells even if the market has moved away
+//! from the price, for the best price that a buyer pays on the way down. Liquidation
+//! orders and auctions skip the dex's lot and tick sizes, since their amounts follow from
+//! the loan.
+//!
+//! The pool, loans, liquidation orders and auctions have to be spendable by anyone.
+//! Borrowers are therefore recorded in their loans, and authorize borrowing by also
+//! spending a UTXO that is protected by their verifier. Deposits are protected by their
+//! depositor's verifier like any coin.
+//!
+//! Because there is a single pool, each transaction of the market spends the pool that the
+//! previous one created, so they are processed one after another.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use dex::{price::mul_div, DexConfig, DutchAuction, Order, Price, Rounding};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    traits::Cash,
+    types::Output,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The configuration of a lending market.
+pub trait LendingConfig {
+    /// The type of verifiers that can own deposits and loans.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The dex market that liquidated collateral is sold in. Its token A is the collateral
+    /// and its token B is the lent token.
+    type Market: DexConfig<Verifier = Self::Verifier>;
+
+    /// The interest that loans accrue, in billionths of the principal per block.
+    const BORROW_RATE_PPB: u128 = 10;
+
+    /// The interest that deposits accrue, in billionths of the principal per block.
+    ///
+    /// It should be lower than the borrow rate, or the pool pays out more interest than
+    /// it takes in.
+    const SUPPLY_RATE_PPB: u128 = 8;
+
+    /// The least value of collateral that a loan must hold, in percent of its debt.
+    const MIN_COLLATERAL_PERCENT: u32 = 150;
+
+    /// The collateral that a liquidator may sell beyond the value of the repaid debt, in
+    /// percent of that value.
+    const LIQUIDATION_PENALTY_PERCENT: u32 = 10;
+
+    /// The number of blocks over which the auction of a liquidated borrower's collateral
+    /// falls from the current price to its floor.
+    const AUCTION_BLOCKS: u32 = 100;
+
+    /// The floor of the auction of a liquidated borrower's collateral, in percent of the
+    /// current price.
+    const AUCTION_FLOOR_PERCENT: u32 = 80;
+
+    /// The verifier that protects the pool, loans, liquidation orders and auctions, which
+    /// anyone must be able to satisfy.
+    fn open_verifier() -> Self::Verifier;
+
+    /// The current price of the collateral, in lent token per unit of collateral, if
+    /// there is one.
+    fn collateral_price() -> Option<Price>;
+}
+
+/// The collateral token of a lending market.
+type Collateral<T> = <<T as LendingConfig>::Market as DexConfig>::A;
+
+/// The lent token of a lending market.
+type Lent<T> = <<T as LendingConfig>::Market as DexConfig>::B;
+
+/// The number of the block that transactions are being checked in.
+fn now<T: LendingConfig>() -> u32 {
+    T::Market::context().block_number
+}
+
+/// The lent token that is not currently borrowed.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct Pool<T: LendingConfig> {
+    /// The amount of lent token in the pool
+    pub cash: u128,
+    /// The total principal of all open loans
+    pub borrowed: u128,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: LendingConfig> Pool<T> {
+    /// Whether the pool holds exactly the given cash and borrowed amounts.
+    fn holds(&self, cash: u128, borrowed: u128) -> bool {
+        self.cash == cash && self.borrowed == borrowed
+    }
+}
+
+impl<T: LendingConfig> UtxoData for Pool<T> {
+    const TYPE_ID: [u8; 4] = [b'l', b'p', Collateral::<T>::ID, Lent::<T>::ID];
+}
+
+/// Lent token deposited in the pool, which accrues interest until it is withdrawn.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct Deposit<T: LendingConfig> {
+    /// The amount that was deposited
+    pub principal: u128,
+    /// The block in which it was deposited
+    pub since: u32,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: LendingConfig> UtxoData for Deposit<T> {
+    const TYPE_ID: [u8; 4] = [b'l', b'd', Collateral::<T>::ID, Lent::<T>::ID];
+}
+
+/// Lent token borrowed against collateral, which accrues interest until it is repaid.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct Loan<T: LendingConfig> {
+    /// The amount of collateral locked in the loan
+    pub collateral: u128,
+    /// The amount that was borrowed
+    pub principal: u128,
+    /// The block in which it was borrowed
+    pub since: u32,
+    /// The verifier that authorized the loan, and that receives the collateral back
+    pub owner: T::Verifier,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: LendingConfig> UtxoData for Loan<T> {
+    const TYPE_ID: [u8; 4] = [b'l', b'n', Collateral::<T>::ID, Lent::<T>::ID];
+}
+
+/// The price of the collateral in the lent token, for runtimes that publish it with the
+/// governance piece.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct PriceFeed<T: LendingConfig> {
+    pub price: Price,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: LendingConfig> UtxoData for PriceFeed<T> {
+    const TYPE_ID: [u8; 4] = [b'l', b'f', Collateral::<T>::ID, Lent::<T>::ID];
+}
+
+/// All the things that can go wrong in the lending market.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum LendingError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// Some amount does not fit in a `u128`.
+    AmountOverflow,
+    /// The first input and the first output must be the pool.
+    PoolMissing,
+    /// A new pool, loan, liquidation order or auction is not protected by the open verifier.
+    NotOpen,
+    /// The new pool does not hold what the transaction leaves in it.
+    PoolMismatch,
+    /// The second input must be the loan.
+    LoanMissing,
+    /// The transaction creates more or fewer outputs than it should.
+    WrongOutputCount,
+    /// A new deposit or loan does not start in the current block.
+    WrongStart,
+    /// A new deposit is not for the deposited amount.
+    DepositMismatch,
+    /// The withdrawal pays out more than the deposits are worth.
+    WithdrawalExceedsDeposits,
+    /// The pool does not hold enough lent token for the transaction.
+    InsufficientLiquidity,
+    /// The transaction does not spend any UTXO protected by the borrower.
+    NotAuthorizedByOwner,
+    /// A new loan does not hold the locked collateral or is not for the borrowed amount.
+    LoanMismatch,
+    /// There is no price for the collateral.
+    PriceUnavailable,
+    /// The loan's collateral would be worth less than the minimum share of its debt.
+    Undercollateralized,
+    /// The debt of the loan, with interest, must be repaid.
+    DebtNotRepaid,
+    /// A repaid loan's collateral must all go back to its borrower.
+    CollateralNotReturned,
+    /// Only loans whose collateral is worth less than the minimum share of their debt may
+    /// be liquidated.
+    LoanHealthy,
+    /// The liquidator's order sells collateral worth more than the debt plus the penalty.
+    LiquidatorTakesTooMuch,
+    /// The borrower's auction is not the one that [`liquidation_auction`] gives for the
+    /// rest of the collateral.
+    OwnerOrderMismatch,
+}
+
+impl From<DynamicTypingError> for LendingError {
+    fn from(_value: DynamicTypingError) -> Self {
+        LendingError::TypeError
+    }
+}
+
+/// The total value of the coins of type `C` among the given UTXOs, which must all be such
+/// coins.
+fn coin_total<C: Cash + UtxoData, V>(utxos: &[Output<V>]) -> Result<u128, LendingError> {
+    let mut total = 0u128;
+    for utxo in utxos {
+        let coin: C = utxo.payload.extract()?;
+        total = total
+            .checked_add(coin.value())
+            .ok_or(LendingError::AmountOverflow)?;
+    }
+    Ok(total)
+}
+
+/// The principal together with simple interest at the given rate since the given block.
+fn with_interest(
+    principal: u128,
+    rate_ppb: u128,
+    since: u32,
+    now: u32,
+    rounding: Rounding,
+) -> Option<u128> {
+    let blocks = now.saturating_sub(since) as u128;
+    let interest = mul_div(
+        principal,
+        rate_ppb.checked_mul(blocks)?,
+        1_000_000_000,
+        rounding,
+    )?;
+    principal.checked_add(interest)
+}
+
+/// What it takes to repay a loan in the given block.
+pub fn debt<T: LendingConfig>(loan: &Loan<T>, now: u32) -> Option<u128> {
+    with_interest(
+        loan.principal,
+        T::BORROW_RATE_PPB,
+        loan.since,
+        now,
+        Rounding::Up,
+    )
+}
+
+/// What a deposit may be withdrawn for in the given block.
+pub fn deposit_value<T: LendingConfig>(deposit: &Deposit<T>, now: u32) -> Option<u128> {
+    with_interest(
+        deposit.principal,
+        T::SUPPLY_RATE_PPB,
+        deposit.since,
+        now,
+        Rounding::Down,
+    )
+}
+
+/// Whether the loan holds enough collateral for its debt in the given block.
+pub fn is_healthy<T: LendingConfig>(loan: &Loan<T>, now: u32) -> Result<bool, LendingError> {
+    let debt = debt(loan, now).ok_or(LendingError::AmountOverflow)?;
+    if debt == 0 {
+        return Ok(true);
+    }
+    let price = T::collateral_price().ok_or(LendingError::PriceUnavailable)?;
+    let required = mul_div(debt, T::MIN_COLLATERAL_PERCENT.into(), 100, Rounding::Up)
+        .ok_or(LendingError::AmountOverflow)?;
+    // Collateral worth more than a `u128` of lent token covers any debt.
+    Ok(price
+        .amount_b_for(loan.collateral, Rounding::Down)
+        .map_or(true, |value| value >= required))
+}
+
+/// The Dutch auction that sells the rest of a liquidated loan's collateral for its
+/// borrower, starting at the given price in the given block, or `None` if its floor does
+/// not fit in a `u128`.
+///
+/// Clients build the borrower's output of a liquidation with this.
+pub fn liquidation_auction<T: LendingConfig>(
+    loan: &Loan<T>,
+    rest: u128,
+    price: Price,
+    now: u32,
+) -> Option<DutchAuction<T::Market>> {
+    let floor = mul_div(
+        price.0,
+        T::AUCTION_FLOOR_PERCENT.into(),
+        100,
+        Rounding::Down,
+    )?;
+    Some(DutchAuction {
+        lot: rest,
+        start_price: price,
+        floor_price: Price(floor),
+        started_at: now,
+        duration: T::AUCTION_BLOCKS,
+        payout_verifier: loan.owner.clone(),
+        _ph_data: PhantomData,
+    })
+}
+
+/// The old pool from the first input and the new pool from the first output.
+fn pools<T: LendingConfig>(
+    inputs: &[Output<T::Verifier>],
+    outputs: &[Output<T::Verifier>],
+) -> Result<(Pool<T>, Pool<T>), LendingError> {
+    ensure!(
+        !inputs.is_empty() && !outputs.is_empty(),
+        LendingError::PoolMissing
+    );
+    let old: Pool<T> = inputs[0].payload.extract()?;
+    let new: Pool<T> = outputs[0].payload.extract()?;
+    ensure!(
+        outputs[0].verifier == T::open_verifier(),
+        LendingError::NotOpen
+    );
+    Ok((old, new))
+}
+
+/// Deposit lent token in the pool.
+///
+/// The first input is the pool and all further inputs are the deposited coins. The
+/// outputs are the pool, which gains the coins, and the new deposit.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Supply<T: LendingConfig>(pub PhantomData<T>);
+
+impl<T: LendingConfig> ConstraintChecker<T::Verifier> for Supply<T> {
+    type Error = LendingError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (old, new) = pools::<T>(inputs, outputs)?;
+        ensure!(outputs.len() == 2, LendingError::WrongOutputCount);
+        let deposit: Deposit<T> = outputs[1].payload.extract()?;
+
+        let supplied = coin_total::<Lent<T>, _>(&inputs[1..])?;
+        ensure!(deposit.principal == supplied, LendingError::DepositMismatch);
+        ensure!(deposit.since == now::<T>(), LendingError::WrongStart);
+
+        let cash = old
+            .cash
+            .checked_add(supplied)
+            .ok_or(LendingError::AmountOverflow)?;
+        ensure!(new.holds(cash, old.borrowed), LendingError::PoolMismatch);
+
+        Ok(0)
+    }
+}
+
+/// Withdraw deposits with their interest from the pool.
+///
+/// The first input is the pool and all further inputs are deposits. The first output is
+/// the pool and all further outputs are the withdrawn coins, worth at most the deposits.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Withdraw<T: LendingConfig>(pub PhantomData<T>);
+
+impl<T: LendingConfig> ConstraintChecker<T::Verifier> for Withdraw<T> {
+    type Error = LendingError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (old, new) = pools::<T>(inputs, outputs)?;
+
+        let mut value = 0u128;
+        for input in &inputs[1..] {
+            let deposit: Deposit<T> = input.payload.extract()?;
+            value = deposit_value(&deposit, now::<T>())
+                .and_then(|worth| value.checked_add(worth))
+                .ok_or(LendingError::AmountOverflow)?;
+        }
+        let withdrawn = coin_total::<Lent<T>, _>(&outputs[1..])?;
+        ensure!(withdrawn <= value, LendingError::WithdrawalExceedsDeposits);
+
+        let cash = old
+            .cash
+            .checked_sub(withdrawn)
+            .ok_or(LendingError::InsufficientLiquidity)?;
+        ensure!(new.holds(cash, old.borrowed), LendingError::PoolMismatch);
+
+        Ok(0)
+    }
+}
+
+/// Borrow lent token from the pool against collateral.
+///
+/// The first input is the pool and all further inputs are the collateral coins, at least
+/// one of which is protected by the borrower. The outputs are the pool, the new loan and
+/// then the borrowed coins.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Borrow<T: LendingConfig>(pub PhantomData<T>);
+
+impl<T: LendingConfig> ConstraintChecker<T::Verifier> for Borrow<T> {
+    type Error = LendingError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (old, new) = pools::<T>(inputs, outputs)?;
+        ensure!(outputs.len() >= 2, LendingError::WrongOutputCount);
+        let loan: Loan<T> = outputs[1].payload.extract()?;
+        ensure!(
+            outputs[1].verifier == T::open_verifier(),
+            LendingError::NotOpen
+        );
+        ensure!(
+            inputs[1..].iter().any(|input| input.verifier == loan.owner),
+            LendingError::NotAuthorizedByOwner
+        );
+
+        let collateral = coin_total::<Collateral<T>, _>(&inputs[1..])?;
+        let borrowed = coin_total::<Lent<T>, _>(&outputs[2..])?;
+        ensure!(
+            loan.collateral == collateral && loan.principal == borrowed,
+            LendingError::LoanMismatch
+        );
+        ensure!(loan.since == now::<T>(), LendingError::WrongStart);
+        ensure!(
+            is_healthy(&loan, now::<T>())?,
+            LendingError::Undercollateralized
+        );
+
+        let cash = old
+            .cash
+            .checked_sub(borrowed)
+            .ok_or(LendingError::InsufficientLiquidity)?;
+        let total_borrowed = old
+            .borrowed
+            .checked_add(borrowed)
+            .ok_or(LendingError::AmountOverflow)?;
+        ensure!(new.holds(cash, total_borrowed), LendingError::PoolMismatch);
+
+        Ok(0)
+    }
+}
+
+/// Check that the coins repay the loan, and that the new pool gains them.
+fn repay<T: LendingConfig>(
+    old: &Pool<T>,
+    new: &Pool<T>,
+    loan: &Loan<T>,
+    repayment: &[Output<T::Verifier>],
+) -> Result<(), LendingError> {
+    let repaid = coin_total::<Lent<T>, _>(repayment)?;
+    let debt = debt(loan, now::<T>()).ok_or(LendingError::AmountOverflow)?;
+    ensure!(repaid >= debt, LendingError::DebtNotRepaid);
+
+    let cash = old
+        .cash
+        .checked_add(repaid)
+        .ok_or(LendingError::AmountOverflow)?;
+    ensure!(
+        new.holds(cash, old.borrowed.saturating_sub(loan.principal)),
+        LendingError::PoolMismatch
+    );
+    Ok(())
+}
+
+/// Repay a loan with interest and take back its collateral.
+///
+/// The inputs are the pool, the loan and then the repaying coins, which must be worth at
+/// least the debt. The first output is the pool and all further outputs are the
+/// collateral, which goes back to the borrower.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Repay<T: LendingConfig>(pub PhantomData<T>);
+
+impl<T: LendingConfig> ConstraintChecker<T::Verifier> for Repay<T> {
+    type Error = LendingError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (old, new) = pools::<T>(inputs, outputs)?;
+        ensure!(inputs.len() >= 2, LendingError::LoanMissing);
+        let loan: Loan<T> = inputs[1].payload.extract()?;
+        repay(&old, &new, &loan, &inputs[2..])?;
+
+        let returned = coin_total::<Collateral<T>, _>(&outputs[1..])?;
+        ensure!(
+            returned == loan.collateral
+                && outputs[1..]
+                    .iter()
+                    .all(|output| output.verifier == loan.owner),
+            LendingError::CollateralNotReturned
+        );
+
+        Ok(0)
+    }
+}
+
+/// Repay an undercollateralized loan, and sell its collateral in the dex.
+///
+/// The inputs are the pool, the loan and then the repaying coins, which must be worth at
+/// least the debt. The outputs are the pool, the liquidator's order and, unless the
+/// liquidator sells all of the collateral, the borrower's auction of the rest, as given by
+/// [`liquidation_auction`] at the current price. The liquidator chooses their order's
+/// owner, payout and price, and pays any bond on it out of what they take.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Liquidate<T: LendingConfig>(pub PhantomData<T>);
+
+impl<T: LendingConfig> ConstraintChecker<T::Verifier> for Liquidate<T> {
+    type Error = LendingError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (old, new) = pools::<T>(inputs, outputs)?;
+        ensure!(inputs.len() >= 2, LendingError::LoanMissing);
+        let loan: Loan<T> = inputs[1].payload.extract()?;
+        let now = now::<T>();
+        ensure!(!is_healthy(&loan, now)?, LendingError::LoanHealthy);
+        repay(&old, &new, &loan, &inputs[2..])?;
+
+        ensure!(
+            outputs[1..]
+                .iter()
+                .all(|output| output.verifier == T::open_verifier()),
+            LendingError::NotOpen
+        );
+        ensure!(outputs.len() >= 2, LendingError::WrongOutputCount);
+        let liquidator: Order<T::Market> = outputs[1].payload.extract()?;
+
+        // An unhealthy loan has a price, or `is_healthy` would have failed.
+        let price = T::collateral_price().ok_or(LendingError::PriceUnavailable)?;
+        let max_sold = debt(&loan, now)
+            .and_then(|debt| price.amount_a_for(debt, Rounding::Up))
+            .and_then(|value| {
+                mul_div(
+                    value,
+                    (100 + T::LIQUIDATION_PENALTY_PERCENT).into(),
+                    100,
+                    Rounding::Up,
+                )
+            })
+            .map_or(loan.collateral, |max| max.min(loan.collateral));
+        // The liquidator's order may lock a bond, which also comes out of the collateral.
+        // It must not claim to be made later than now, or it would skip rent on its bond.
+        let sold = liquidator
+            .offer_amount
+            .checked_add(liquidator.bond)
+            .ok_or(LendingError::AmountOverflow)?;
+        ensure!(
+            sold <= max_sold && liquidator.made_at <= now,
+            LendingError::LiquidatorTakesTooMuch
+        );
+
+        let rest = loan.collateral - sold;
+        if rest == 0 {
+            ensure!(outputs.len() == 2, LendingError::WrongOutputCount);
+            return Ok(0);
+        }
+        ensure!(outputs.len() == 3, LendingError::WrongOutputCount);
+        let borrower: DutchAuction<T::Market> = outputs[2].payload.extract()?;
+        let auction =
+            liquidation_auction(&loan, rest, price, now).ok_or(LendingError::AmountOverflow)?;
+        ensure!(
+            borrower.lot == auction.lot
+                && borrower.start_price == auction.start_price
+                && borrower.floor_price == auction.floor_price
+                && borrower.started_at == auction.started_at
+                && borrower.duration == auction.duration
+                && borrower.payout_verifier == auction.payout_verifier,
+            LendingError::OwnerOrderMismatch
+        );
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,346 @@
+//! Unit tests for the lending piece
+
+use super::*;
+use dex::ConstraintCheckingContext;
+use money::Coin;
+use sp_core::H256;
+use std::cell::Cell;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+thread_local! {
+    static BLOCK: Cell<u32> = Cell::new(0);
+    static PRICE: Cell<Option<Price>> = Cell::new(Some(Price::ONE));
+}
+
+fn set_block(block: u32) {
+    BLOCK.with(|b| b.set(block));
+}
+
+fn set_price(price: Option<Price>) {
+    PRICE.with(|p| p.set(price));
+}
+
+struct TestMarket;
+impl DexConfig for TestMarket {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+
+    fn context() -> ConstraintCheckingContext {
+        ConstraintCheckingContext {
+            block_number: BLOCK.with(|b| b.get()),
+            timestamp: 0,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct TestConfig;
+impl LendingConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type Market = TestMarket;
+
+    fn open_verifier() -> TestVerifier {
+        ANYONE
+    }
+
+    fn collateral_price() -> Option<Price> {
+        PRICE.with(|p| p.get())
+    }
+}
+
+/// The verifier that protects the pool, loans and orders.
+const ANYONE: TestVerifier = TestVerifier { verifies: true };
+/// The verifier of depositors and borrowers.
+const OWNER: TestVerifier = TestVerifier { verifies: false };
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn pool(cash: u128, borrowed: u128) -> Output<TestVerifier> {
+    output(
+        Pool::<TestConfig> {
+            cash,
+            borrowed,
+            _ph_data: Default::default(),
+        },
+        ANYONE,
+    )
+}
+
+fn deposit(principal: u128, since: u32) -> Output<TestVerifier> {
+    output(
+        Deposit::<TestConfig> {
+            principal,
+            since,
+            _ph_data: Default::default(),
+        },
+        OWNER,
+    )
+}
+
+fn loan(collateral: u128, principal: u128, since: u32) -> Output<TestVerifier> {
+    output(
+        Loan::<TestConfig> {
+            collateral,
+            principal,
+            since,
+            owner: OWNER,
+            _ph_data: Default::default(),
+        },
+        ANYONE,
+    )
+}
+
+fn order(offer_amount: u128, ask_amount: u128, payout: TestVerifier) -> Output<TestVerifier> {
+    output(
+        Order::<TestMarket> {
+            owner: H256::repeat_byte(9),
+            ..Order::new(offer_amount, ask_amount, payout)
+        },
+        ANYONE,
+    )
+}
+
+/// An auction of the given lot for the given payout verifier, starting at the given price
+/// in block 0, as liquidations open them.
+fn auction(lot: u128, start_price: Price, payout: TestVerifier) -> Output<TestVerifier> {
+    output(
+        DutchAuction::<TestMarket> {
+            lot,
+            start_price,
+            floor_price: Price(start_price.0 * 4 / 5),
+            started_at: 0,
+            duration: 100,
+            payout_verifier: payout,
+            _ph_data: Default::default(),
+        },
+        ANYONE,
+    )
+}
+
+fn collateral(amount: u128, verifier: TestVerifier) -> Output<TestVerifier> {
+    output(Coin::<0>(amount), verifier)
+}
+
+fn lent(amount: u128, verifier: TestVerifier) -> Output<TestVerifier> {
+    output(Coin::<1>(amount), verifier)
+}
+
+fn check<C: ConstraintChecker<TestVerifier, Error = LendingError> + Default>(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, LendingError> {
+    C::default().check(inputs, outputs)
+}
+
+#[test]
+fn supplying_adds_to_the_pool() {
+    set_block(7);
+    assert_eq!(
+        check::<Supply<TestConfig>>(
+            &[pool(100, 20), lent(30, OWNER), lent(20, OWNER)],
+            &[pool(150, 20), deposit(50, 7)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<Supply<TestConfig>>(
+            &[pool(100, 20), lent(50, OWNER)],
+            &[pool(150, 20), deposit(50, 6)],
+        ),
+        Err(LendingError::WrongStart)
+    );
+}
+
+#[test]
+fn deposits_are_withdrawn_with_interest() {
+    // A million blocks at 8 billionths per block is 0.8%.
+    set_block(1_000_000);
+    assert_eq!(
+        check::<Withdraw<TestConfig>>(
+            &[pool(2_000, 0), deposit(1_000, 0)],
+            &[pool(992, 0), lent(1_008, OWNER)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<Withdraw<TestConfig>>(
+            &[pool(2_000, 0), deposit(1_000, 0)],
+            &[pool(991, 0), lent(1_009, OWNER)],
+        ),
+        Err(LendingError::WithdrawalExceedsDeposits)
+    );
+}
+
+#[test]
+fn withdrawing_more_than_the_pool_holds_fails() {
+    set_block(0);
+    assert_eq!(
+        check::<Withdraw<TestConfig>>(
+            &[pool(500, 600), deposit(1_000, 0)],
+            &[pool(0, 600), lent(1_000, OWNER)],
+        ),
+        Err(LendingError::InsufficientLiquidity)
+    );
+}
+
+#[test]
+fn borrowing_within_the_ratio_works() {
+    set_block(3);
+    set_price(Some(Price::ONE));
+    assert_eq!(
+        check::<Borrow<TestConfig>>(
+            &[pool(200, 0), collateral(150, OWNER)],
+            &[pool(100, 100), loan(150, 100, 3), lent(100, OWNER)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<Borrow<TestConfig>>(
+            &[pool(200, 0), collateral(149, OWNER)],
+            &[pool(100, 100), loan(149, 100, 3), lent(100, OWNER)],
+        ),
+        Err(LendingError::Undercollateralized)
+    );
+}
+
+#[test]
+fn borrowing_needs_the_borrower_and_liquidity() {
+    set_block(0);
+    set_price(Some(Price::ONE));
+    assert_eq!(
+        check::<Borrow<TestConfig>>(
+            &[pool(200, 0), collateral(150, ANYONE)],
+            &[pool(100, 100), loan(150, 100, 0), lent(100, OWNER)],
+        ),
+        Err(LendingError::NotAuthorizedByOwner)
+    );
+    assert_eq!(
+        check::<Borrow<TestConfig>>(
+            &[pool(50, 0), collateral(150, OWNER)],
+            &[pool(0, 100), loan(150, 100, 0), lent(100, OWNER)],
+        ),
+        Err(LendingError::InsufficientLiquidity)
+    );
+}
+
+#[test]
+fn loans_are_repaid_with_interest() {
+    // A million blocks at 10 billionths per block is 1%.
+    set_block(1_000_000);
+    assert_eq!(
+        check::<Repay<TestConfig>>(
+            &[pool(100, 100), loan(150, 100, 0), lent(101, OWNER)],
+            &[pool(201, 0), collateral(150, OWNER)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<Repay<TestConfig>>(
+            &[pool(100, 100), loan(150, 100, 0), lent(100, OWNER)],
+            &[pool(200, 0), collateral(150, OWNER)],
+        ),
+        Err(LendingError::DebtNotRepaid)
+    );
+}
+
+#[test]
+fn repaid_collateral_goes_to_the_borrower() {
+    set_block(0);
+    assert_eq!(
+        check::<Repay<TestConfig>>(
+            &[pool(100, 100), loan(150, 100, 0), lent(100, ANYONE)],
+            &[pool(200, 0), collateral(150, ANYONE)],
+        ),
+        Err(LendingError::CollateralNotReturned)
+    );
+}
+
+#[test]
+fn healthy_loans_cannot_be_liquidated() {
+    set_block(0);
+    set_price(Some(Price::ONE));
+    assert_eq!(
+        check::<Liquidate<TestConfig>>(
+            &[pool(100, 100), loan(150, 100, 0), lent(100, ANYONE)],
+            &[pool(200, 0), order(150, 150, ANYONE)],
+        ),
+        Err(LendingError::LoanHealthy)
+    );
+}
+
+#[test]
+fn liquidation_sells_the_collateral_in_the_dex() {
+    // The collateral is worth 120 of lent token, when 150 would be needed.
+    set_block(0);
+    let price = Price(Price::ONE.0 * 4 / 5);
+    set_price(Some(price));
+    let inputs = [pool(100, 100), loan(150, 100, 0), lent(100, ANYONE)];
+
+    // Repaying 100 buys 125 of collateral, and with the 10% penalty 137.5, which rounds
+    // up to 138. The borrower's 12 are auctioned from the price down to 80% of it.
+    assert_eq!(
+        check::<Liquidate<TestConfig>>(
+            &inputs,
+            &[
+                pool(200, 0),
+                order(138, 100, ANYONE),
+                auction(12, price, OWNER)
+            ],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<Liquidate<TestConfig>>(
+            &inputs,
+            &[
+                pool(200, 0),
+                order(139, 100, ANYONE),
+                auction(11, price, OWNER)
+            ],
+        ),
+        Err(LendingError::LiquidatorTakesTooMuch)
+    );
+    assert_eq!(
+        check::<Liquidate<TestConfig>>(
+            &inputs,
+            &[
+                pool(200, 0),
+                order(138, 100, ANYONE),
+                auction(12, Price::ONE, OWNER)
+            ],
+        ),
+        Err(LendingError::OwnerOrderMismatch)
+    );
+    assert_eq!(
+        check::<Liquidate<TestConfig>>(
+            &inputs,
+            &[
+                pool(200, 0),
+                order(138, 100, ANYONE),
+                auction(12, price, ANYONE)
+            ],
+        ),
+        Err(LendingError::OwnerOrderMismatch)
+    );
+    // The rest may no longer sit in an order at a fixed price.
+    assert_eq!(
+        check::<Liquidate<TestConfig>>(
+            &inputs,
+            &[pool(200, 0), order(138, 100, ANYONE), order(12, 9, OWNER)],
+        ),
+        Err(LendingError::TypeError)
+    );
+    assert_eq!(
+        check::<Liquidate<TestConfig>>(&inputs, &[pool(200, 0), order(138, 100, ANYONE)]),
+        Err(LendingError::WrongOutputCount)
+    );
+}
@@ -0,0 +1,243 @@
+//! A simple fungible token.
+//!
+//! This piece is instantiable and parameterized in a token id. Each instance represents
+//! a separate denomination of coin such as `Coin<0>` and `Coin<1>`. If you want multiple
+//! tokens in your runtime, then you will need multiple instances of this piece.
+//!
+//! There are four ways of using coins.
+//! * `Mint` - Create new coins. Minting requires consuming (and recreating) the token's
+//!   `MintAuthority` UTXO, so only whoever can satisfy that UTXO's verifier may mint.
+//! * `Spend` - Consume some coins and create others worth no more than the inputs.
+//!   Any difference is burned as a fee and reflected in the transaction's priority.
+//! * `Split` - Break a single coin into several smaller coins of exactly the same total value.
+//! * `Merge` - Combine several coins into a single coin of exactly the same total value.
+//!
+//! Tokens can also be registered in the genesis state instead of being chosen at compile
+//! time. See the [`multi_asset`] module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, DynamicallyTypedData, UtxoData},
+    ensure,
+    traits::Cash,
+    SimpleConstraintChecker,
+};
+
+pub mod multi_asset;
+pub use multi_asset::{
+    AssetId, AssetInfo, AssetSlot, MultiAsset, MultiAssetConstraintChecker, Registered,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A single coin in the fungible money system.
+/// A new-type wrapper around a `u128` value.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub struct Coin<const ID: u8>(pub u128);
+
+impl<const ID: u8> Coin<ID> {
+    pub fn new(amount: u128) -> Self {
+        Coin(amount)
+    }
+}
+
+impl<const ID: u8> From<u128> for Coin<ID> {
+    fn from(amount: u128) -> Self {
+        Coin(amount)
+    }
+}
+
+impl<const ID: u8> Cash for Coin<ID> {
+    fn value(&self) -> u128 {
+        self.0
+    }
+
+    const ID: u8 = ID;
+}
+
+impl<const ID: u8> UtxoData for Coin<ID> {
+    const TYPE_ID: [u8; 4] = [b'c', b'o', b'i', ID];
+}
+
+/// The privilege to mint new coins of a single token.
+///
+/// There should be exactly one of these per token, typically placed in the genesis state.
+/// The verifier protecting it is the token's configured minter. A `Mint` transaction consumes
+/// it and must recreate it, possibly under a new verifier to hand the privilege to someone else.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Hash, Debug, Default, TypeInfo)]
+pub struct MintAuthority<const ID: u8>;
+
+impl<const ID: u8> UtxoData for MintAuthority<ID> {
+    const TYPE_ID: [u8; 4] = [b'm', b'n', b't', ID];
+}
+
+/// The main constraint checker for the money piece.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum MoneyConstraintChecker<const ID: u8> {
+    /// A typical spend transaction where some coins are consumed and others are created.
+    /// Input value must meet or exceed output value. The difference is burned as a fee
+    /// and reflected in the transaction's priority.
+    Spend,
+    /// A mint transaction that creates new coins out of the void. The token's `MintAuthority`
+    /// must be the only input and must be recreated as the first output. All the remaining
+    /// outputs are the newly minted coins.
+    Mint,
+    /// Break a single coin into several coins whose values sum to exactly the original value.
+    Split,
+    /// Combine several coins into a single coin whose value is exactly the sum of the originals.
+    Merge,
+}
+
+/// Errors that can occur when checking money transactions.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum ConstraintCheckerError {
+    /// Dynamic typing issue.
+    /// This error doesn't discriminate between badly typed inputs and outputs.
+    BadlyTyped,
+    /// The mint transaction did not consume the token's `MintAuthority`, or the asset's
+    /// `AssetInfo`, as its only input.
+    MintAuthorityMissing,
+    /// The mint transaction did not recreate the token's `MintAuthority`, or the asset's
+    /// `AssetInfo`, as its first output.
+    MintAuthorityNotReturned,
+    /// The transaction attempted to mint zero coins. This is not allowed.
+    MintingNothing,
+    /// This transaction spends more value than it consumes.
+    /// aka. "Your outputs exceed your inputs"
+    OutputsExceedInputs,
+    /// The value consumed or created by this transaction overflows the value type.
+    /// This could lead to problems like https://bitcointalk.org/index.php?topic=823.0
+    ValueOverflow,
+    /// The transaction is attempting to create a zero value output. This is not allowed.
+    ZeroValueCoin,
+    /// The transaction is attempting to spend nothing.
+    SpendingNothing,
+    /// A split transaction must consume exactly one coin.
+    SplitRequiresSingleInput,
+    /// A merge transaction must create exactly one coin.
+    MergeRequiresSingleOutput,
+    /// A split or merge transaction must create exactly as much value as it consumes.
+    ValueNotConserved,
+    /// The mint transaction creates coins of an asset other than the registered one.
+    WrongAsset,
+}
+
+impl From<DynamicTypingError> for ConstraintCheckerError {
+    fn from(_value: DynamicTypingError) -> Self {
+        Self::BadlyTyped
+    }
+}
+
+/// Sum the values of some coins, making sure none of them are worth zero.
+fn total_value<const ID: u8>(
+    data: &[DynamicallyTypedData],
+) -> Result<u128, ConstraintCheckerError> {
+    let mut total: u128 = 0;
+    for utxo in data {
+        let coin = utxo.extract::<Coin<ID>>()?;
+        ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValueCoin);
+        total = total
+            .checked_add(coin.0)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+    }
+    Ok(total)
+}
+
+impl<const ID: u8> SimpleConstraintChecker for MoneyConstraintChecker<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        match &self {
+            Self::Spend => {
+                // Check that we are consuming at least one input
+                ensure!(
+                    !input_data.is_empty(),
+                    ConstraintCheckerError::SpendingNothing
+                );
+
+                let total_input_value = total_value::<ID>(input_data)?;
+                let total_output_value = total_value::<ID>(output_data)?;
+
+                ensure!(
+                    total_output_value <= total_input_value,
+                    ConstraintCheckerError::OutputsExceedInputs
+                );
+
+                // Priority is based on how many tokens are burned as a fee
+                let burned = total_input_value - total_output_value;
+                Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+            }
+            Self::Mint => {
+                // The only input must be the mint authority, and it must be returned
+                // as the first output.
+                ensure!(
+                    input_data.len() == 1,
+                    ConstraintCheckerError::MintAuthorityMissing
+                );
+                input_data[0]
+                    .extract::<MintAuthority<ID>>()
+                    .map_err(|_| ConstraintCheckerError::MintAuthorityMissing)?;
+                ensure!(
+                    !output_data.is_empty(),
+                    ConstraintCheckerError::MintAuthorityNotReturned
+                );
+                output_data[0]
+                    .extract::<MintAuthority<ID>>()
+                    .map_err(|_| ConstraintCheckerError::MintAuthorityNotReturned)?;
+
+                // Make sure there is at least one coin being minted,
+                // and that all the new coins are the right type
+                let minted = &output_data[1..];
+                ensure!(!minted.is_empty(), ConstraintCheckerError::MintingNothing);
+                total_value::<ID>(minted)?;
+
+                // No priority for minting
+                Ok(0)
+            }
+            Self::Split => {
+                ensure!(
+                    input_data.len() == 1,
+                    ConstraintCheckerError::SplitRequiresSingleInput
+                );
+                ensure!(
+                    total_value::<ID>(input_data)? == total_value::<ID>(output_data)?,
+                    ConstraintCheckerError::ValueNotConserved
+                );
+
+                Ok(0)
+            }
+            Self::Merge => {
+                ensure!(
+                    !input_data.is_empty(),
+                    ConstraintCheckerError::SpendingNothing
+                );
+                ensure!(
+                    output_data.len() == 1,
+                    ConstraintCheckerError::MergeRequiresSingleOutput
+                );
+                ensure!(
+                    total_value::<ID>(input_data)? == total_value::<ID>(output_data)?,
+                    ConstraintCheckerError::ValueNotConserved
+                );
+
+                Ok(0)
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Coins whose asset is part of their data rather than their type.
+//!
+//! Every [`Coin`] denomination is a separate type, so its token has to be chosen when
+//! the runtime is compiled. A [`MultiAsset`] coin instead records which asset it is, so
+//! new assets can be registered in the genesis state. Each asset has an [`AssetInfo`]
+//! UTXO with its id, creator, decimals and symbol, which doubles as its mint authority.
+//!
+//! Pieces that are generic over a coin type, like the dex, can still trade registered
+//! assets through [`Registered`], a typed view of the coins of a single asset. Which
+//! asset that is, the runtime decides through an [`AssetSlot`], for example by reading
+//! an asset id that was chosen at genesis from storage.
+
+use super::*;
+use sp_core::H256;
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData};
+
+/// The identifier of a registered asset.
+pub type AssetId = u16;
+
+/// The key that the value of an asset is accounted under, like the `TYPE_ID` of a coin.
+///
+/// Coins of every asset share a single type id, so runtimes that check value conservation
+/// per type id should use these keys for multi-asset coins instead.
+pub fn asset_type_id(asset: AssetId) -> [u8; 4] {
+    let [high, low] = asset.to_be_bytes();
+    [b'a', b'c', high, low]
+}
+
+/// A coin of any registered asset.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub struct MultiAsset {
+    /// The asset that this coin is of
+    pub asset: AssetId,
+    /// The amount of the asset
+    pub value: u128,
+}
+
+impl UtxoData for MultiAsset {
+    const TYPE_ID: [u8; 4] = *b"mast";
+}
+
+/// The registration of an asset.
+///
+/// There should be exactly one of these per asset, typically placed in the genesis state.
+/// Like a [`MintAuthority`], the verifier protecting it may mint the asset.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub struct AssetInfo {
+    /// The asset's identifier
+    pub id: AssetId,
+    /// An identifier of the account that registered the asset, such as its public key
+    pub creator: H256,
+    /// The number of decimals that wallets display amounts of the asset with
+    pub decimals: u8,
+    /// The asset's ticker symbol
+    pub symbol: Vec<u8>,
+}
+
+impl UtxoData for AssetInfo {
+    const TYPE_ID: [u8; 4] = *b"asst";
+}
+
+/// A place in a runtime that holds a single registered asset, such as one side of a dex
+/// pair.
+pub trait AssetSlot {
+    /// The `Cash` id that the asset goes by in this slot. It must differ from the ids of
+    /// the runtime's other coins, since pieces such as the dex use it in their type ids.
+    const ID: u8;
+
+    /// The asset in this slot.
+    fn asset_id() -> AssetId;
+}
+
+/// A [`MultiAsset`] coin of the asset in the slot `S`.
+///
+/// It has the same type id and encoding as a `MultiAsset`, but extracting a coin of any
+/// other asset as this type fails.
+pub struct Registered<S> {
+    /// The amount of the asset
+    pub value: u128,
+    pub _ph_data: PhantomData<S>,
+}
+
+impl<S> Registered<S> {
+    pub fn new(value: u128) -> Self {
+        Self {
+            value,
+            _ph_data: PhantomData,
+        }
+    }
+}
+
+impl<S> From<u128> for Registered<S> {
+    fn from(value: u128) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<S> Clone for Registered<S> {
+    fn clone(&self) -> Self {
+        Self::new(self.value)
+    }
+}
+
+impl<S> PartialEq for Registered<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<S> Eq for Registered<S> {}
+
+impl<S> sp_std::fmt::Debug for Registered<S> {
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        f.debug_tuple("Registered").field(&self.value).finish()
+    }
+}
+
+impl<S: AssetSlot> Encode for Registered<S> {
+    fn size_hint(&self) -> usize {
+        self.to_multi_asset().size_hint()
+    }
+
+    fn encode_to<O: parity_scale_codec::Output + ?Sized>(&self, dest: &mut O) {
+        self.to_multi_asset().encode_to(dest)
+    }
+}
+
+impl<S: AssetSlot> Decode for Registered<S> {
+    fn decode<I: parity_scale_codec::Input>(
+        input: &mut I,
+    ) -> Result<Self, parity_scale_codec::Error> {
+        let coin = MultiAsset::decode(input)?;
+        if coin.asset != S::asset_id() {
+            return Err("coin is of another asset".into());
+        }
+        Ok(Self::new(coin.value))
+    }
+}
+
+impl<S: 'static> TypeInfo for Registered<S> {
+    type Identity = MultiAsset;
+
+    fn type_info() -> scale_info::Type {
+        MultiAsset::type_info()
+    }
+}
+
+impl<S: AssetSlot> Registered<S> {
+    fn to_multi_asset(&self) -> MultiAsset {
+        MultiAsset {
+            asset: S::asset_id(),
+            value: self.value,
+        }
+    }
+}
+
+impl<S: AssetSlot> Cash for Registered<S> {
+    fn value(&self) -> u128 {
+        self.value
+    }
+
+    const ID: u8 = S::ID;
+}
+
+impl<S: AssetSlot> UtxoData for Registered<S> {
+    const TYPE_ID: [u8; 4] = MultiAsset::TYPE_ID;
+}
+
+/// The constraint checker for multi-asset coins.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum MultiAssetConstraintChecker {
+    /// Consume some coins and create others, worth no more than the inputs in each asset.
+    /// Any difference is burned. Burned amounts of different assets cannot be compared, so
+    /// they do not count towards the transaction's priority.
+    Spend,
+    /// Create new coins of a single asset. The asset's `AssetInfo` must be the only input
+    /// and must be recreated unchanged as the first output. All the remaining outputs are
+    /// the newly minted coins.
+    Mint,
+}
+
+/// Add up the values of some multi-asset coins per asset, making sure none of them are
+/// worth zero.
+fn totals(
+    data: &[DynamicallyTypedData],
+) -> Result<BTreeMap<AssetId, u128>, ConstraintCheckerError> {
+    let mut totals = BTreeMap::<AssetId, u128>::new();
+    for utxo in data {
+        let coin = utxo.extract::<MultiAsset>()?;
+        ensure!(coin.value > 0, ConstraintCheckerError::ZeroValueCoin);
+        let total = totals.entry(coin.asset).or_default();
+        *total = total
+            .checked_add(coin.value)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+    }
+    Ok(totals)
+}
+
+impl SimpleConstraintChecker for MultiAssetConstraintChecker {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        match &self {
+            Self::Spend => {
+                ensure!(
+                    !input_data.is_empty(),
+                    ConstraintCheckerError::SpendingNothing
+                );
+
+                let inputs = totals(input_data)?;
+                for (asset, output) in totals(output_data)? {
+                    ensure!(
+                        output <= inputs.get(&asset).copied().unwrap_or_default(),
+                        ConstraintCheckerError::OutputsExceedInputs
+                    );
+                }
+
+                Ok(0)
+            }
+            Self::Mint => {
+                ensure!(
+                    input_data.len() == 1,
+                    ConstraintCheckerError::MintAuthorityMissing
+                );
+                let info = input_data[0]
+                    .extract::<AssetInfo>()
+                    .map_err(|_| ConstraintCheckerError::MintAuthorityMissing)?;
+                let returned = output_data
+                    .first()
+                    .and_then(|output| output.extract::<AssetInfo>().ok());
+                ensure!(
+                    returned.as_ref() == Some(&info),
+                    ConstraintCheckerError::MintAuthorityNotReturned
+                );
+
+                let minted = &output_data[1..];
+                ensure!(!minted.is_empty(), ConstraintCheckerError::MintingNothing);
+                ensure!(
+                    totals(minted)?.keys().all(|asset| *asset == info.id),
+                    ConstraintCheckerError::WrongAsset
+                );
+
+                Ok(0)
+            }
+        }
+    }
+}
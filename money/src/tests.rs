@@ -0,0 +1,251 @@
+//! Unit tests for the Money piece
+
+use super::*;
+
+type Checker = MoneyConstraintChecker<0>;
+
+fn coins(values: &[u128]) -> Vec<DynamicallyTypedData> {
+    values.iter().map(|v| Coin::<0>(*v).into()).collect()
+}
+
+fn authority() -> DynamicallyTypedData {
+    MintAuthority::<0>.into()
+}
+
+#[test]
+fn spend_valid_transaction_works() {
+    let result = Checker::Spend.check(&coins(&[5, 7]), &coins(&[8, 4]));
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn spend_with_fee_burns_difference_as_priority() {
+    let result = Checker::Spend.check(&coins(&[10]), &coins(&[7]));
+    assert_eq!(result, Ok(3));
+}
+
+#[test]
+fn spend_with_no_inputs_fails() {
+    let result = Checker::Spend.check(&[], &coins(&[1]));
+    assert_eq!(result, Err(ConstraintCheckerError::SpendingNothing));
+}
+
+#[test]
+fn spend_creating_value_fails() {
+    let result = Checker::Spend.check(&coins(&[10]), &coins(&[6, 6]));
+    assert_eq!(result, Err(ConstraintCheckerError::OutputsExceedInputs));
+}
+
+#[test]
+fn spend_zero_value_output_fails() {
+    let result = Checker::Spend.check(&coins(&[10]), &coins(&[10, 0]));
+    assert_eq!(result, Err(ConstraintCheckerError::ZeroValueCoin));
+}
+
+#[test]
+fn spend_overflowing_inputs_fails() {
+    let result = Checker::Spend.check(&coins(&[u128::MAX, 1]), &coins(&[1]));
+    assert_eq!(result, Err(ConstraintCheckerError::ValueOverflow));
+}
+
+#[test]
+fn spend_wrong_denomination_fails() {
+    let result = Checker::Spend.check(&[Coin::<1>(10).into()], &coins(&[10]));
+    assert_eq!(result, Err(ConstraintCheckerError::BadlyTyped));
+}
+
+#[test]
+fn mint_with_authority_works() {
+    let mut outputs = vec![authority()];
+    outputs.extend(coins(&[100, 50]));
+
+    let result = Checker::Mint.check(&[authority()], &outputs);
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn mint_without_authority_fails() {
+    let result = Checker::Mint.check(&[], &coins(&[100]));
+    assert_eq!(result, Err(ConstraintCheckerError::MintAuthorityMissing));
+}
+
+#[test]
+fn mint_with_other_tokens_authority_fails() {
+    let other_authority: DynamicallyTypedData = MintAuthority::<1>.into();
+    let mut outputs = vec![other_authority.clone()];
+    outputs.extend(coins(&[100]));
+
+    let result = Checker::Mint.check(&[other_authority], &outputs);
+    assert_eq!(result, Err(ConstraintCheckerError::MintAuthorityMissing));
+}
+
+#[test]
+fn mint_consuming_coins_fails() {
+    let result = Checker::Mint.check(&[authority(), Coin::<0>(5).into()], &coins(&[100]));
+    assert_eq!(result, Err(ConstraintCheckerError::MintAuthorityMissing));
+}
+
+#[test]
+fn mint_without_returning_authority_fails() {
+    let result = Checker::Mint.check(&[authority()], &coins(&[100]));
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::MintAuthorityNotReturned)
+    );
+}
+
+#[test]
+fn mint_nothing_fails() {
+    let result = Checker::Mint.check(&[authority()], &[authority()]);
+    assert_eq!(result, Err(ConstraintCheckerError::MintingNothing));
+}
+
+#[test]
+fn mint_zero_value_coin_fails() {
+    let result = Checker::Mint.check(&[authority()], &[authority(), Coin::<0>(0).into()]);
+    assert_eq!(result, Err(ConstraintCheckerError::ZeroValueCoin));
+}
+
+#[test]
+fn split_works() {
+    let result = Checker::Split.check(&coins(&[10]), &coins(&[3, 3, 4]));
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn split_multiple_inputs_fails() {
+    let result = Checker::Split.check(&coins(&[5, 5]), &coins(&[3, 3, 4]));
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::SplitRequiresSingleInput)
+    );
+}
+
+#[test]
+fn split_with_fee_fails() {
+    let result = Checker::Split.check(&coins(&[10]), &coins(&[3, 3]));
+    assert_eq!(result, Err(ConstraintCheckerError::ValueNotConserved));
+}
+
+#[test]
+fn merge_works() {
+    let result = Checker::Merge.check(&coins(&[3, 3, 4]), &coins(&[10]));
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn merge_multiple_outputs_fails() {
+    let result = Checker::Merge.check(&coins(&[3, 3, 4]), &coins(&[5, 5]));
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::MergeRequiresSingleOutput)
+    );
+}
+
+#[test]
+fn merge_creating_value_fails() {
+    let result = Checker::Merge.check(&coins(&[3, 3]), &coins(&[10]));
+    assert_eq!(result, Err(ConstraintCheckerError::ValueNotConserved));
+}
+
+#[test]
+fn merge_nothing_fails() {
+    let result = Checker::Merge.check(&[], &coins(&[10]));
+    assert_eq!(result, Err(ConstraintCheckerError::SpendingNothing));
+}
+
+type AssetChecker = MultiAssetConstraintChecker;
+
+fn assets(coins: &[(AssetId, u128)]) -> Vec<DynamicallyTypedData> {
+    coins
+        .iter()
+        .map(|(asset, value)| {
+            MultiAsset {
+                asset: *asset,
+                value: *value,
+            }
+            .into()
+        })
+        .collect()
+}
+
+fn gold() -> AssetInfo {
+    AssetInfo {
+        id: 7,
+        creator: Default::default(),
+        decimals: 12,
+        symbol: b"GOLD".to_vec(),
+    }
+}
+
+#[test]
+fn multi_asset_spend_conserves_each_asset() {
+    let inputs = assets(&[(7, 10), (8, 5)]);
+    assert_eq!(
+        AssetChecker::Spend.check(&inputs, &assets(&[(7, 4), (7, 6), (8, 5)])),
+        Ok(0)
+    );
+    assert_eq!(
+        AssetChecker::Spend.check(&inputs, &assets(&[(7, 11), (8, 4)])),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+    assert_eq!(
+        AssetChecker::Spend.check(&inputs, &assets(&[(9, 1)])),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn multi_asset_mint_needs_the_asset_info() {
+    let mut outputs = vec![gold().into()];
+    outputs.extend(assets(&[(7, 100)]));
+    assert_eq!(AssetChecker::Mint.check(&[gold().into()], &outputs), Ok(0));
+
+    assert_eq!(
+        AssetChecker::Mint.check(&assets(&[(7, 1)]), &outputs),
+        Err(ConstraintCheckerError::MintAuthorityMissing)
+    );
+
+    let mut renamed = gold();
+    renamed.symbol = b"FOOL".to_vec();
+    outputs[0] = renamed.into();
+    assert_eq!(
+        AssetChecker::Mint.check(&[gold().into()], &outputs),
+        Err(ConstraintCheckerError::MintAuthorityNotReturned)
+    );
+}
+
+#[test]
+fn multi_asset_mint_of_another_asset_fails() {
+    let mut outputs = vec![gold().into()];
+    outputs.extend(assets(&[(8, 100)]));
+    assert_eq!(
+        AssetChecker::Mint.check(&[gold().into()], &outputs),
+        Err(ConstraintCheckerError::WrongAsset)
+    );
+}
+
+struct GoldSlot;
+impl AssetSlot for GoldSlot {
+    const ID: u8 = 10;
+
+    fn asset_id() -> AssetId {
+        7
+    }
+}
+
+#[test]
+fn registered_coins_are_only_of_their_asset() {
+    let coins = assets(&[(7, 5), (8, 5)]);
+    assert_eq!(
+        coins[0].extract::<Registered<GoldSlot>>().ok(),
+        Some(Registered::new(5))
+    );
+    assert!(coins[1].extract::<Registered<GoldSlot>>().is_err());
+
+    let coin: DynamicallyTypedData = Registered::<GoldSlot>::new(5).into();
+    assert_eq!(
+        coin.extract::<MultiAsset>().ok(),
+        Some(MultiAsset { asset: 7, value: 5 })
+    );
+}
@@ -0,0 +1,243 @@
+//! Multisig proposals, which collect the approvals of several signatories across transactions.
+//!
+//! A threshold multisignature needs every signature in the one transaction that spends the
+//! UTXO it protects, so the signatories have to coordinate off chain to sign it. A trading
+//! desk whose large orders need two signers would rather have each of them approve on their
+//! own time. So one of them proposes the transaction, by its hash, in a [`Proposal`], and the
+//! others approve it in transactions of their own. The partially approved proposal stays on
+//! chain in between, accumulating the approvals.
+//!
+//! Checkers cannot see signatures, so signatories approve by spending UTXOs that are
+//! protected by their own verifiers, which they get back unchanged in the same transaction.
+//! Proposals themselves are protected by a verifier that anyone can satisfy.
+//!
+//! This piece only collects the approvals. A verifier in the runtime lets UTXOs be spent by a
+//! transaction that an approved proposal names, which is how orders are opened and cancelled
+//! with the desk's approval. Executing the transaction does not spend the proposal, since
+//! the proposal names its hash. The signatories discard it once it has been executed.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The configuration of multisig proposals.
+pub trait MultisigConfig {
+    /// The type of verifiers that signatories use.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+
+    /// The verifier that protects proposals, which anyone must be able to satisfy.
+    fn open_verifier() -> Self::Verifier;
+}
+
+/// A transaction that some of the signatories have approved.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct Proposal<T: MultisigConfig> {
+    /// The verifiers of the signatories who may approve the transaction
+    pub signatories: Vec<T::Verifier>,
+    /// How many of the signatories must approve the transaction
+    pub threshold: u32,
+    /// The blake2-256 hash of the transaction, with all redeemers stripped
+    pub transaction: H256,
+    /// The positions among the signatories of those who have approved, in increasing order
+    pub approvals: Vec<u32>,
+}
+
+impl<T: MultisigConfig> UtxoData for Proposal<T> {
+    const TYPE_ID: [u8; 4] = *b"prop";
+}
+
+impl<T: MultisigConfig> Proposal<T> {
+    /// Whether enough signatories have approved the transaction.
+    pub fn is_approved(&self) -> bool {
+        self.approvals.len() >= self.threshold as usize
+    }
+
+    /// The positions of the signatories who protect the given UTXOs, in increasing order.
+    fn approvals_by(&self, credentials: &[Output<T::Verifier>]) -> Result<Vec<u32>, MultisigError> {
+        ensure!(!credentials.is_empty(), MultisigError::NoCredentials);
+        let mut approvals = Vec::with_capacity(credentials.len());
+        for credential in credentials {
+            let index = self
+                .signatories
+                .iter()
+                .position(|signatory| *signatory == credential.verifier)
+                .ok_or(MultisigError::NotASignatory)? as u32;
+            ensure!(!approvals.contains(&index), MultisigError::AlreadyApproved);
+            approvals.push(index);
+        }
+        approvals.sort();
+        Ok(approvals)
+    }
+
+    /// Whether the given proposal is this one with the given approvals added.
+    fn is_continued_by(&self, next: &Self, added: &[u32]) -> bool {
+        let mut approvals = self.approvals.clone();
+        approvals.extend_from_slice(added);
+        approvals.sort();
+
+        next.signatories == self.signatories
+            && next.threshold == self.threshold
+            && next.transaction == self.transaction
+            && next.approvals == approvals
+    }
+}
+
+/// All the things that can go wrong while checking multisig transactions.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum MultisigError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// The first input must be the proposal, and so must the first output when approving.
+    ProposalMissing,
+    /// A new or approved proposal is not protected by the open verifier.
+    NotOpen,
+    /// A new proposal must require between one and all of its signatories.
+    MalformedProposal,
+    /// The transaction does not spend any UTXOs protected by a signatory.
+    NoCredentials,
+    /// Some UTXO that is spent to approve is not protected by a signatory.
+    NotASignatory,
+    /// A signatory approves the same proposal twice.
+    AlreadyApproved,
+    /// The proposal must carry on unchanged, except for the approvals of the signatories
+    /// who spent UTXOs in this transaction.
+    ProposalChanged,
+    /// The UTXOs spent to approve must be returned unchanged, in the same order, after the
+    /// proposal.
+    CredentialsNotReturned,
+}
+
+impl From<DynamicTypingError> for MultisigError {
+    fn from(_value: DynamicTypingError) -> Self {
+        MultisigError::TypeError
+    }
+}
+
+/// Propose a transaction, with the approvals of the signatories who propose it.
+///
+/// The inputs are UTXOs protected by the proposing signatories. The first output is the new
+/// proposal, approved by exactly those signatories, and the rest return the inputs unchanged.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct ProposeTransaction<T: MultisigConfig>(pub PhantomData<T>);
+
+impl<T: MultisigConfig> ConstraintChecker<T::Verifier> for ProposeTransaction<T> {
+    type Error = MultisigError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (first, returned) = outputs
+            .split_first()
+            .ok_or(MultisigError::ProposalMissing)?;
+        let proposal: Proposal<T> = first.payload.extract()?;
+        ensure!(first.verifier == T::open_verifier(), MultisigError::NotOpen);
+        ensure!(
+            proposal.threshold > 0 && proposal.threshold as usize <= proposal.signatories.len(),
+            MultisigError::MalformedProposal
+        );
+
+        ensure!(
+            proposal.approvals == proposal.approvals_by(inputs)?,
+            MultisigError::ProposalChanged
+        );
+        ensure!(returned == inputs, MultisigError::CredentialsNotReturned);
+
+        Ok(0)
+    }
+}
+
+/// Add the approvals of more signatories to a proposal.
+///
+/// The first input is the proposal, and the rest are UTXOs protected by signatories who have
+/// not approved it yet. The first output is the proposal with their approvals added, and the
+/// rest return the other inputs unchanged.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct ApproveProposal<T: MultisigConfig>(pub PhantomData<T>);
+
+impl<T: MultisigConfig> ConstraintChecker<T::Verifier> for ApproveProposal<T> {
+    type Error = MultisigError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (proposal, credentials) = inputs.split_first().ok_or(MultisigError::ProposalMissing)?;
+        let proposal: Proposal<T> = proposal.payload.extract()?;
+        let (next, returned) = outputs
+            .split_first()
+            .ok_or(MultisigError::ProposalMissing)?;
+        ensure!(next.verifier == T::open_verifier(), MultisigError::NotOpen);
+        let next: Proposal<T> = next.payload.extract()?;
+
+        let added = proposal.approvals_by(credentials)?;
+        ensure!(
+            added
+                .iter()
+                .all(|index| !proposal.approvals.contains(index)),
+            MultisigError::AlreadyApproved
+        );
+        ensure!(
+            proposal.is_continued_by(&next, &added),
+            MultisigError::ProposalChanged
+        );
+        ensure!(
+            returned == credentials,
+            MultisigError::CredentialsNotReturned
+        );
+
+        Ok(0)
+    }
+}
+
+/// Discard a proposal that has been executed, or that should never be.
+///
+/// The first input is the proposal, and the rest are UTXOs protected by its signatories. Any
+/// one signatory may discard it. The outputs return the other inputs unchanged.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct DiscardProposal<T: MultisigConfig>(pub PhantomData<T>);
+
+impl<T: MultisigConfig> ConstraintChecker<T::Verifier> for DiscardProposal<T> {
+    type Error = MultisigError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (proposal, credentials) = inputs.split_first().ok_or(MultisigError::ProposalMissing)?;
+        let proposal: Proposal<T> = proposal.payload.extract()?;
+
+        proposal.approvals_by(credentials)?;
+        ensure!(
+            outputs == credentials,
+            MultisigError::CredentialsNotReturned
+        );
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,176 @@
+//! Unit tests for the multisig piece
+
+use super::*;
+use money::Coin;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct TestConfig;
+impl MultisigConfig for TestConfig {
+    type Verifier = TestVerifier;
+
+    fn open_verifier() -> TestVerifier {
+        ANYONE
+    }
+}
+
+/// The verifier that protects proposals, and that the first signatory uses.
+const ANYONE: TestVerifier = TestVerifier { verifies: true };
+const ALICE: TestVerifier = ANYONE;
+/// The verifier of the second signatory.
+const BOB: TestVerifier = TestVerifier { verifies: false };
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn credential(verifier: TestVerifier) -> Output<TestVerifier> {
+    output(Coin::<0>(1), verifier)
+}
+
+/// A 2-of-2 proposal between Alice and Bob, approved by the signatories at the given positions.
+fn proposal(approvals: Vec<u32>) -> Output<TestVerifier> {
+    output(
+        Proposal::<TestConfig> {
+            signatories: vec![ALICE, BOB],
+            threshold: 2,
+            transaction: H256::repeat_byte(1),
+            approvals,
+        },
+        ANYONE,
+    )
+}
+
+fn check<C: ConstraintChecker<TestVerifier, Error = MultisigError> + Default>(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, MultisigError> {
+    C::default().check(inputs, outputs)
+}
+
+#[test]
+fn proposing_approves_for_the_proposer() {
+    assert_eq!(
+        check::<ProposeTransaction<TestConfig>>(
+            &[credential(BOB)],
+            &[proposal(vec![1]), credential(BOB)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<ProposeTransaction<TestConfig>>(
+            &[credential(BOB)],
+            &[proposal(vec![0, 1]), credential(BOB)],
+        ),
+        Err(MultisigError::ProposalChanged)
+    );
+    assert_eq!(
+        check::<ProposeTransaction<TestConfig>>(&[credential(BOB)], &[proposal(vec![1])]),
+        Err(MultisigError::CredentialsNotReturned)
+    );
+}
+
+#[test]
+fn proposals_need_a_reachable_threshold() {
+    let impossible = output(
+        Proposal::<TestConfig> {
+            signatories: vec![BOB],
+            threshold: 2,
+            transaction: H256::zero(),
+            approvals: vec![0],
+        },
+        ANYONE,
+    );
+    assert_eq!(
+        check::<ProposeTransaction<TestConfig>>(&[credential(BOB)], &[impossible, credential(BOB)],),
+        Err(MultisigError::MalformedProposal)
+    );
+}
+
+#[test]
+fn approvals_accumulate_across_transactions() {
+    let approved_by_bob = proposal(vec![1]);
+    assert!(!approved_by_bob
+        .payload
+        .extract::<Proposal<TestConfig>>()
+        .unwrap()
+        .is_approved());
+
+    assert_eq!(
+        check::<ApproveProposal<TestConfig>>(
+            &[approved_by_bob, credential(ALICE)],
+            &[proposal(vec![0, 1]), credential(ALICE)],
+        ),
+        Ok(0)
+    );
+    assert!(proposal(vec![0, 1])
+        .payload
+        .extract::<Proposal<TestConfig>>()
+        .unwrap()
+        .is_approved());
+}
+
+#[test]
+fn signatories_approve_only_once() {
+    assert_eq!(
+        check::<ApproveProposal<TestConfig>>(
+            &[proposal(vec![1]), credential(BOB)],
+            &[proposal(vec![1, 1]), credential(BOB)],
+        ),
+        Err(MultisigError::AlreadyApproved)
+    );
+    assert_eq!(
+        check::<ProposeTransaction<TestConfig>>(
+            &[credential(BOB), credential(BOB)],
+            &[proposal(vec![1]), credential(BOB), credential(BOB)],
+        ),
+        Err(MultisigError::AlreadyApproved)
+    );
+}
+
+#[test]
+fn approving_cannot_change_the_proposal() {
+    let mut changed: Proposal<TestConfig> = proposal(vec![0, 1]).payload.extract().unwrap();
+    changed.transaction = H256::repeat_byte(2);
+    assert_eq!(
+        check::<ApproveProposal<TestConfig>>(
+            &[proposal(vec![1]), credential(ALICE)],
+            &[output(changed, ANYONE), credential(ALICE)],
+        ),
+        Err(MultisigError::ProposalChanged)
+    );
+    assert_eq!(
+        check::<ApproveProposal<TestConfig>>(&[proposal(vec![1])], &[proposal(vec![1])]),
+        Err(MultisigError::NoCredentials)
+    );
+}
+
+#[test]
+fn only_signatories_approve_or_discard() {
+    let bobs_alone = output(
+        Proposal::<TestConfig> {
+            signatories: vec![BOB],
+            threshold: 1,
+            transaction: H256::zero(),
+            approvals: vec![],
+        },
+        ANYONE,
+    );
+    assert_eq!(
+        check::<DiscardProposal<TestConfig>>(
+            &[bobs_alone.clone(), credential(ALICE)],
+            &[credential(ALICE)],
+        ),
+        Err(MultisigError::NotASignatory)
+    );
+    assert_eq!(
+        check::<DiscardProposal<TestConfig>>(&[bobs_alone, credential(BOB)], &[credential(BOB)]),
+        Ok(0)
+    );
+}
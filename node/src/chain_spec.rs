@@ -1,5 +1,10 @@
-use node_template_runtime::GenesisConfig;
-use sc_service::ChainType;
+use node_template_runtime::{
+    money, verifiers::Sr25519SigCheck, GenesisConfig, OuterVerifier, Output,
+};
+use sc_service::{ChainType, Properties};
+use sp_core::{sr25519, Pair, H256};
+use sp_keyring::Sr25519Keyring;
+use tuxedo_core::dynamic_typing::DynamicallyTypedData;
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
@@ -72,3 +77,131 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
         None,
     ))
 }
+
+/// The dev accounts that the dex presets fund.
+const DEX_ACCOUNTS: [Sr25519Keyring; 3] = [
+    Sr25519Keyring::Alice,
+    Sr25519Keyring::Bob,
+    Sr25519Keyring::Charlie,
+];
+
+/// How much of each token the dex presets give each of the dev accounts.
+const DEX_ACCOUNT_BALANCE: u128 = 1_000_000;
+
+/// The secret URI of the faucet account, which the dex presets fund generously so that a
+/// faucet service can hand out tokens to new traders.
+pub const FAUCET_URI: &str = "//Faucet";
+
+/// How much of each token the dex presets give the faucet.
+const FAUCET_BALANCE: u128 = 1_000_000_000;
+
+/// The public key of the faucet account.
+fn faucet_key() -> H256 {
+    sr25519::Pair::from_string(FAUCET_URI, None)
+        .expect("static values are valid; qed")
+        .public()
+        .0
+        .into()
+}
+
+/// A coin of every token that the dex presets trade, worth the given amount, for the given key.
+fn funded_coins(owner: H256, amount: u128) -> Vec<Output> {
+    let verifier = OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+        owner_pubkey: owner,
+    });
+    let (gold, silver) = GenesisConfig::default().asset_pair;
+    let payloads: [DynamicallyTypedData; 4] = [
+        money::Coin::<0>(amount).into(),
+        money::Coin::<1>(amount).into(),
+        money::MultiAsset {
+            asset: gold,
+            value: amount,
+        }
+        .into(),
+        money::MultiAsset {
+            asset: silver,
+            value: amount,
+        }
+        .into(),
+    ];
+    payloads
+        .into_iter()
+        .map(|payload| Output {
+            payload,
+            verifier: verifier.clone(),
+        })
+        .collect()
+}
+
+/// The genesis of the dex presets: the usual development genesis, plus coins of every token
+/// for Alice, Bob, Charlie and the faucet.
+///
+/// The usual genesis already opens the three markets: tokens 0 and 1, the two registered
+/// assets, and kitties for token 0.
+fn dex_genesis() -> GenesisConfig {
+    let mut genesis = GenesisConfig::default();
+    for account in DEX_ACCOUNTS {
+        genesis
+            .genesis_utxos
+            .extend(funded_coins(account.public().0.into(), DEX_ACCOUNT_BALANCE));
+    }
+    genesis
+        .genesis_utxos
+        .extend(funded_coins(faucet_key(), FAUCET_BALANCE));
+    genesis
+}
+
+/// The chain properties of the dex presets, which tell clients where the faucet is.
+fn dex_properties() -> Properties {
+    let mut properties = Properties::new();
+    properties.insert("faucet".into(), format!("{:?}", faucet_key()).into());
+    properties
+}
+
+/// A chain with the dex genesis, for trying out the whole tutorial out of the box.
+fn dex_config(name: &str, id: &str, chain_type: ChainType) -> ChainSpec {
+    ChainSpec::from_genesis(
+        // Name
+        name,
+        // ID
+        id,
+        chain_type,
+        dex_genesis,
+        // Bootnodes
+        vec![],
+        // Telemetry
+        None,
+        // Protocol ID
+        None,
+        None,
+        // Properties
+        Some(dex_properties()),
+        // Extensions
+        None,
+    )
+}
+
+/// A development chain with funded dex accounts. Use it with `--chain dex-dev`.
+pub fn dex_development_config() -> Result<ChainSpec, String> {
+    Ok(dex_config(
+        "Dex Development",
+        "dex_dev",
+        ChainType::Development,
+    ))
+}
+
+/// A local testnet with funded dex accounts. Use it with `--chain dex-local`.
+pub fn dex_local_testnet_config() -> Result<ChainSpec, String> {
+    Ok(dex_config(
+        "Dex Local Testnet",
+        "dex_local",
+        ChainType::Local,
+    ))
+}
+
+/// A public testnet with funded dex accounts. Use it with `--chain dex-testnet`.
+///
+/// The dev accounts and the faucet have well known keys, so it is only fit for testing.
+pub fn dex_testnet_config() -> Result<ChainSpec, String> {
+    Ok(dex_config("Dex Testnet", "dex_testnet", ChainType::Live))
+}
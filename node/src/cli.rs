@@ -39,6 +39,15 @@ pub enum Subcommand {
     /// Db meta columns information.
     ChainInfo(sc_cli::ChainInfoCmd),
 
+    /// Export the open orders and coins of a dex market at some block to JSON.
+    ExportDex(crate::dex_snapshot::ExportDexCmd),
+
+    /// Build a development chain spec whose genesis holds an exported dex market.
+    ImportDex(crate::dex_snapshot::ImportDexCmd),
+
+    /// Check the invariants of the dex against the state at some block.
+    TryState(crate::try_state::TryStateCmd),
+
     /// Custom -- extend it as you wish.
     Custom(CustomCommand),
 }
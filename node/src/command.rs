@@ -35,6 +35,9 @@ impl SubstrateCli for Cli {
     fn load_spec(&self, id: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
         Ok(match id {
             "dev" => Box::new(chain_spec::development_config()?),
+            "dex-dev" => Box::new(chain_spec::dex_development_config()?),
+            "dex-local" => Box::new(chain_spec::dex_local_testnet_config()?),
+            "dex-testnet" => Box::new(chain_spec::dex_testnet_config()?),
             "" | "local" => Box::new(chain_spec::local_testnet_config()?),
             path => Box::new(chain_spec::ChainSpec::from_json_file(
                 std::path::PathBuf::from(path),
@@ -131,6 +134,21 @@ pub fn run() -> sc_cli::Result<()> {
                 )
             })
         }
+        Some(Subcommand::ExportDex(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.sync_run(|config| {
+                let PartialComponents { client, .. } = service::new_partial(&config)?;
+                cmd.run(client)
+            })
+        }
+        Some(Subcommand::ImportDex(cmd)) => cmd.run(),
+        Some(Subcommand::TryState(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.sync_run(|config| {
+                let PartialComponents { client, .. } = service::new_partial(&config)?;
+                cmd.run(client)
+            })
+        }
         Some(Subcommand::Custom(_)) => {
             todo!()
         }
@@ -0,0 +1,194 @@
+//! Prometheus metrics of the dex markets, for node operators.
+//!
+//! The node follows its best chain and keeps the terms of every open order in memory. It
+//! reads them from the UTXO set when it starts, and then applies the order book deltas of
+//! each new best block. When the best chain reorganizes it reads them again, rather than
+//! undoing the retracted blocks. From those orders, and the transactions of each block, it
+//! exports through the usual Substrate Prometheus endpoint:
+//! - `dex_open_orders`: the number of open orders in each market, labelled with its `pair`.
+//! - `dex_matches_per_block`: a histogram of the number of matches in each best block.
+//! - `dex_spread`: a histogram of each market's spread after each best block, as a fraction
+//!   of the mid price. Its sum over its count is the average spread. Markets without orders
+//!   on both sides have no spread, so those blocks are left out.
+//!
+//! How long matches take to validate is measured by the pool. See the
+//! [`pool_metrics`](crate::pool_metrics) module.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use futures::StreamExt;
+use node_template_runtime::{
+    journal::SpentOutputsApi,
+    order_book::{self, OrderBookDelta, OrderTerms},
+    Output, Transaction,
+};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use prometheus_endpoint::{
+    register, GaugeVec, Histogram, HistogramOpts, HistogramVec, Opts, PrometheusError, Registry,
+    U64,
+};
+use sc_client_api::{BlockBackend, BlockchainEvents, StorageProvider};
+use sc_service::SpawnTaskHandle;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::traits::Header as _;
+use tuxedo_core::types::OutputRef;
+
+use crate::service::FullClient;
+
+/// The upper bounds of the buckets of the spread histogram, as fractions of the mid price.
+const SPREAD_BUCKETS: &[f64] = &[0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0];
+
+/// The upper bounds of the buckets of the matches per block histogram.
+const MATCH_COUNT_BUCKETS: &[f64] = &[0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0];
+
+/// The metrics of the dex markets.
+#[derive(Clone)]
+pub struct DexMetrics {
+    open_orders: GaugeVec<U64>,
+    matches_per_block: Histogram,
+    spread: HistogramVec,
+}
+
+impl DexMetrics {
+    /// Register the metrics with the given registry.
+    pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            open_orders: register(
+                GaugeVec::new(
+                    Opts::new("dex_open_orders", "Open orders in each dex market"),
+                    &["pair"],
+                )?,
+                registry,
+            )?,
+            matches_per_block: register(
+                Histogram::with_opts(
+                    HistogramOpts::new(
+                        "dex_matches_per_block",
+                        "Transactions that match dex orders in each best block",
+                    )
+                    .buckets(MATCH_COUNT_BUCKETS.to_vec()),
+                )?,
+                registry,
+            )?,
+            spread: register(
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "dex_spread",
+                        "The spread of each dex market after each best block, relative to the mid price",
+                    )
+                    .buckets(SPREAD_BUCKETS.to_vec()),
+                    &["pair"],
+                )?,
+                registry,
+            )?,
+        })
+    }
+}
+
+/// The label of a market.
+fn pair_label(pair: (u8, u8)) -> String {
+    format!("{}/{}", pair.0, pair.1)
+}
+
+/// The spread between the best bid and the best ask of the given market, as a fraction of
+/// the mid price, if it has orders on both sides. The first token of `pair` is the base.
+fn spread<'a>(orders: impl Iterator<Item = &'a OrderTerms>, pair: (u8, u8)) -> Option<f64> {
+    let mut best_bid: Option<f64> = None;
+    let mut best_ask: Option<f64> = None;
+    for terms in orders.filter(|terms| terms.offer_amount > 0 && terms.ask_amount > 0) {
+        if (terms.offer_token, terms.ask_token) == pair {
+            let price = terms.ask_amount as f64 / terms.offer_amount as f64;
+            best_ask = Some(best_ask.map_or(price, |best| best.min(price)));
+        } else if (terms.ask_token, terms.offer_token) == pair {
+            let price = terms.offer_amount as f64 / terms.ask_amount as f64;
+            best_bid = Some(best_bid.map_or(price, |best| best.max(price)));
+        }
+    }
+    let (bid, ask) = (best_bid?, best_ask?);
+    Some((ask - bid) / ((ask + bid) / 2.0))
+}
+
+/// The terms of every open order at the given block, by where it is stored.
+///
+/// Every UTXO is stored directly under its encoded output ref. Other runtime storage items
+/// have keys of different shapes, so they fail to decode as a whole.
+fn read_book(client: &FullClient, hash: H256) -> BTreeMap<OutputRef, OrderTerms> {
+    let Ok(pairs) = client.storage_pairs(hash, None, None) else {
+        return BTreeMap::new();
+    };
+    pairs
+        .filter_map(|(key, value)| {
+            let output_ref = OutputRef::decode_all(&mut &key.0[..]).ok()?;
+            let output = Output::decode_all(&mut &value.0[..]).ok()?;
+            Some((output_ref, order_book::order_terms(&output)?))
+        })
+        .collect()
+}
+
+/// Follow the best chain and keep the metrics of the dex markets up to date.
+pub fn start(client: Arc<FullClient>, metrics: DexMetrics, spawn_handle: &SpawnTaskHandle) {
+    let mut book = read_book(&client, client.info().best_hash);
+    let mut imports = client.import_notification_stream();
+
+    let follow = async move {
+        while let Some(notification) = imports.next().await {
+            if !notification.is_new_best {
+                continue;
+            }
+            let hash = notification.hash;
+            let reorged = notification
+                .tree_route
+                .as_ref()
+                .map_or(false, |route| !route.retracted().is_empty());
+
+            let transactions: Vec<Transaction> = client
+                .block_body(hash)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|extrinsic| Transaction::decode(&mut &extrinsic.encode()[..]).ok())
+                .collect();
+            let matches = transactions
+                .iter()
+                .filter(|transaction| transaction.checker.is_match())
+                .count();
+            metrics.matches_per_block.observe(matches as f64);
+
+            if reorged {
+                book = read_book(&client, hash);
+            } else {
+                let spent = client
+                    .runtime_api()
+                    .spent_in(hash, *notification.header.number())
+                    .unwrap_or_default();
+                for delta in order_book::deltas(&transactions, &spent) {
+                    match delta {
+                        OrderBookDelta::Opened { order_ref, terms } => {
+                            book.insert(order_ref, terms);
+                        }
+                        OrderBookDelta::Filled { order_ref, .. }
+                        | OrderBookDelta::Cancelled { order_ref, .. } => {
+                            book.remove(&order_ref);
+                        }
+                    }
+                }
+            }
+
+            for &pair in order_book::TRADED_PAIRS {
+                let label = pair_label(pair);
+                let open = book.values().filter(|terms| terms.is_in_pair(pair)).count();
+                metrics
+                    .open_orders
+                    .with_label_values(&[&label])
+                    .set(open as u64);
+                if let Some(spread) = spread(book.values(), pair) {
+                    metrics.spread.with_label_values(&[&label]).observe(spread);
+                }
+            }
+        }
+    };
+    spawn_handle.spawn("dex-metrics", Some("dex"), follow);
+}
@@ -0,0 +1,183 @@
+//! Exporting the state of a dex market, and importing it into a development chain.
+//!
+//! Testing a matching engine against a handful of hand made orders says little about how
+//! it behaves against a real book. These commands take a snapshot of one market on a
+//! running chain, meaning its open orders and the coins of both its tokens, and write it to
+//! JSON. The snapshot can then be turned into the chain spec of a fresh development chain
+//! whose genesis holds all of those UTXOs, with their original verifiers, on top of the
+//! usual development genesis.
+//!
+//! Genesis UTXOs are stored under genesis output refs, so the imported orders live at
+//! different refs than they did on the original chain. The snapshot records the original
+//! refs for reference only.
+
+use std::{fs, path::PathBuf, sync::Arc};
+
+use node_template_runtime::{order_book, BlockNumber, GenesisConfig, Output};
+use parity_scale_codec::DecodeAll;
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::StorageProvider;
+use sc_service::ChainType;
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use tuxedo_core::types::OutputRef;
+
+use crate::{chain_spec::ChainSpec, service::FullClient};
+
+/// One market of the dex, as it was at some block.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DexSnapshot {
+    /// The pair of token ids the snapshot is for
+    pub pair: (u8, u8),
+    /// The hash of the block the snapshot was taken at
+    pub block_hash: H256,
+    /// The number of that block
+    pub block_number: BlockNumber,
+    /// The orders and coins of the market, sorted by output ref
+    pub utxos: Vec<(OutputRef, Output)>,
+}
+
+/// Export the open orders and coins of a dex market at some block to JSON.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportDexCmd {
+    /// The id of the first token in the pair
+    pub base: u8,
+
+    /// The id of the second token in the pair
+    pub quote: u8,
+
+    /// The hash of the block to export the market at. Defaults to the best block.
+    #[arg(long)]
+    pub at: Option<H256>,
+
+    /// The file to write the snapshot to. Defaults to stdout.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub pruning_params: PruningParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub database_params: DatabaseParams,
+}
+
+impl ExportDexCmd {
+    /// Take the snapshot from the given client and write it out.
+    pub fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+        let pair = (self.base, self.quote);
+        let block_hash = self.at.unwrap_or_else(|| client.info().best_hash);
+        let block_number = client
+            .number(block_hash)?
+            .ok_or_else(|| format!("Block {:?} is not known", block_hash))?;
+
+        // Every UTXO is stored directly under its encoded output ref. Other runtime storage
+        // items have keys of different shapes, so they fail to decode as a whole.
+        let utxos = client
+            .storage_pairs(block_hash, None, None)?
+            .filter_map(|(key, value)| {
+                let output_ref = OutputRef::decode_all(&mut &key.0[..]).ok()?;
+                let output = Output::decode_all(&mut &value.0[..]).ok()?;
+                Some((output_ref, output))
+            })
+            .filter(|(_, output)| order_book::is_in_market(output, pair))
+            .collect();
+
+        let snapshot = DexSnapshot {
+            pair,
+            block_hash,
+            block_number,
+            utxos,
+        };
+        let json = serde_json::to_string_pretty(&snapshot)
+            .map_err(|e| format!("Failed to serialize the snapshot: {}", e))?;
+        match &self.output {
+            Some(path) => fs::write(path, json)?,
+            None => println!("{}", json),
+        }
+
+        Ok(())
+    }
+}
+
+impl CliConfiguration for ExportDexCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn pruning_params(&self) -> Option<&PruningParams> {
+        Some(&self.pruning_params)
+    }
+
+    fn database_params(&self) -> Option<&DatabaseParams> {
+        Some(&self.database_params)
+    }
+}
+
+/// Build the chain spec of a development chain whose genesis holds a dex snapshot.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ImportDexCmd {
+    /// The snapshot file written by `export-dex`
+    pub snapshot: PathBuf,
+
+    /// The file to write the chain spec to. Defaults to stdout.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Write the chain spec in raw storage form
+    #[arg(long)]
+    pub raw: bool,
+}
+
+impl ImportDexCmd {
+    /// Read the snapshot and write out the chain spec.
+    pub fn run(&self) -> sc_cli::Result<()> {
+        let json = fs::read_to_string(&self.snapshot)?;
+        let snapshot: DexSnapshot = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse the snapshot: {}", e))?;
+        let imported: Vec<Output> = snapshot
+            .utxos
+            .into_iter()
+            .map(|(_, output)| output)
+            .collect();
+
+        let chain_spec = ChainSpec::from_genesis(
+            // Name
+            "Development with dex snapshot",
+            // ID
+            "dev_dex_snapshot",
+            ChainType::Development,
+            move || {
+                let mut genesis = GenesisConfig::default();
+                genesis.genesis_utxos.extend(imported.iter().cloned());
+                genesis
+            },
+            // Bootnodes
+            vec![],
+            // Telemetry
+            None,
+            // Protocol ID
+            None,
+            None,
+            // Properties
+            None,
+            // Extensions
+            None,
+        );
+
+        let json = sc_service::chain_ops::build_spec(&chain_spec, self.raw)?;
+        match &self.output {
+            Some(path) => fs::write(path, json)?,
+            None => println!("{}", json),
+        }
+
+        Ok(())
+    }
+}
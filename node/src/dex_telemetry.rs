@@ -0,0 +1,112 @@
+//! Telemetry messages about trading on the dex.
+//!
+//! Each time a new best block is imported, the node reports the orders it opened and the
+//! orders it matched through its telemetry handle, so that dashboards collecting the
+//! telemetry of the tutorial network can aggregate trading across all of its nodes. Every
+//! node reports the same blocks, so dashboards should count each block once, by its number.
+//!
+//! The messages are `dex.order_opened` and `dex.order_matched`. Both carry:
+//! - `block`: the number of the block.
+//! - `pair`: the market, as `base/quote` token ids from the runtime's `TRADED_PAIRS`.
+//! - `side`: `sell` if the order offered the base token, and `buy` otherwise.
+//! - `size`: the order of magnitude of the amount of the base token, like `1e3` for anything
+//!   from 1000 to 9999, or `0` for nothing at all.
+//!
+//! The exact amounts and refs of orders are left out, as dashboards do not need them to
+//! aggregate, and telemetry is public.
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use node_template_runtime::{
+    journal::SpentOutputsApi,
+    order_book::{self, OrderBookDelta, OrderTerms},
+    Transaction,
+};
+use parity_scale_codec::{Decode, Encode};
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sc_service::SpawnTaskHandle;
+use sc_telemetry::{telemetry, TelemetryHandle, SUBSTRATE_INFO};
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::traits::Header as _;
+
+use crate::service::FullClient;
+
+/// The size bucket of an amount of the base token.
+fn size_bucket(amount: u128) -> String {
+    match amount.checked_ilog10() {
+        Some(magnitude) => format!("1e{}", magnitude),
+        None => "0".into(),
+    }
+}
+
+/// The market, side and size bucket that an order is reported with, if it is in a market.
+fn describe(terms: &OrderTerms) -> Option<(String, &'static str, String)> {
+    let &pair = order_book::TRADED_PAIRS
+        .iter()
+        .find(|&&pair| terms.is_in_pair(pair))?;
+    let (side, size) = if terms.offer_token == pair.0 {
+        ("sell", terms.offer_amount)
+    } else {
+        ("buy", terms.ask_amount)
+    };
+    Some((format!("{}/{}", pair.0, pair.1), side, size_bucket(size)))
+}
+
+/// Report the trading in every new best block through the given telemetry handle.
+pub fn start(client: Arc<FullClient>, telemetry: TelemetryHandle, spawn_handle: &SpawnTaskHandle) {
+    let telemetry = Some(telemetry);
+    let mut imports = client.import_notification_stream();
+
+    let report = async move {
+        while let Some(notification) = imports.next().await {
+            if !notification.is_new_best {
+                continue;
+            }
+            let number = *notification.header.number();
+            let transactions: Vec<Transaction> = client
+                .block_body(notification.hash)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|extrinsic| Transaction::decode(&mut &extrinsic.encode()[..]).ok())
+                .collect();
+            let spent = client
+                .runtime_api()
+                .spent_in(notification.hash, number)
+                .unwrap_or_default();
+
+            for delta in order_book::deltas(&transactions, &spent) {
+                let OrderBookDelta::Opened { terms, .. } = delta else {
+                    continue;
+                };
+                if let Some((pair, side, size)) = describe(&terms) {
+                    telemetry!(
+                        telemetry;
+                        SUBSTRATE_INFO;
+                        "dex.order_opened";
+                        "block" => number,
+                        "pair" => pair,
+                        "side" => side,
+                        "size" => size,
+                    );
+                }
+            }
+            for fill in order_book::fills(&transactions, &spent) {
+                if let Some((pair, side, size)) = describe(&fill.terms) {
+                    telemetry!(
+                        telemetry;
+                        SUBSTRATE_INFO;
+                        "dex.order_matched";
+                        "block" => number,
+                        "pair" => pair,
+                        "side" => side,
+                        "size" => size,
+                    );
+                }
+            }
+        }
+    };
+    spawn_handle.spawn("dex-telemetry", Some("telemetry"), report);
+}
@@ -0,0 +1,251 @@
+//! Gossiping order intents between nodes, off chain.
+//!
+//! Making an order locks up its collateral until the order is matched or cancelled. A
+//! trader who would first like to know whether anyone wants the other side can instead
+//! announce an [`OrderIntent`]: the terms they would offer, unsigned and without any
+//! collateral. Nodes gossip intents to each other over a dedicated notifications protocol,
+//! and serve them to matchers through the `dex_subscribeIntents` RPC subscription.
+//!
+//! Intents cost nothing on chain, so the network has to be protected from floods of them.
+//! Every intent carries a nonce that makes its hash start with [`POW_DIFFICULTY`] zero
+//! bits, which takes the sender some work to find and takes every node a single hash to
+//! check. Intents also expire, after at most [`MAX_INTENT_LIFETIME`] blocks, at which point
+//! nodes stop gossiping them.
+
+use std::{
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+
+use futures::{channel::mpsc, future, FutureExt, StreamExt};
+use node_template_runtime::{opaque::Block, order_book, BlockNumber, OuterVerifier};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use sc_network::{config::NonDefaultSetConfig, PeerId};
+use sc_network_gossip::{
+    GossipEngine, Network, Syncing, ValidationResult, Validator, ValidatorContext,
+};
+use sc_service::SpawnTaskHandle;
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+
+/// The name of the notifications protocol intents are gossiped over.
+pub const PROTOCOL_NAME: &str = "/dex/order-intents/1";
+
+/// The largest encoded intent that peers accept.
+pub const MAX_INTENT_SIZE: u64 = 1024;
+
+/// How many zero bits the hash of an intent must start with.
+pub const POW_DIFFICULTY: u32 = 16;
+
+/// How many blocks ahead of the best block an intent may expire.
+pub const MAX_INTENT_LIFETIME: BlockNumber = 100;
+
+/// The gossip topic that all intents are sent under.
+fn topic() -> H256 {
+    BlakeTwo256::hash(b"dex-order-intents")
+}
+
+/// An unsigned announcement of an order that someone would like to make.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderIntent {
+    /// The id of the token that would be offered
+    pub offer_token: u8,
+    /// The amount of the offered token
+    pub offer_amount: u128,
+    /// The id of the token that would be asked in exchange
+    pub ask_token: u8,
+    /// The amount of the asked token
+    pub ask_amount: u128,
+    /// The verifier the order would be paid out to, which also tells matchers who to talk to
+    pub payout_verifier: OuterVerifier,
+    /// The last block in which the intent is gossiped
+    pub expires_at: BlockNumber,
+    /// Chosen by the sender so that the intent has enough work. See [`OrderIntent::mine`].
+    pub nonce: u64,
+}
+
+impl OrderIntent {
+    /// The hash that the proof of work is checked on.
+    pub fn hash(&self) -> H256 {
+        BlakeTwo256::hash_of(self)
+    }
+
+    /// Whether the hash of this intent starts with at least [`POW_DIFFICULTY`] zero bits.
+    pub fn has_enough_work(&self) -> bool {
+        let mut zero_bits = 0;
+        for byte in self.hash().as_bytes() {
+            zero_bits += byte.leading_zeros();
+            if *byte != 0 {
+                break;
+            }
+        }
+        zero_bits >= POW_DIFFICULTY
+    }
+
+    /// Find a nonce that gives this intent enough work.
+    pub fn mine(mut self) -> Self {
+        while !self.has_enough_work() {
+            self.nonce = self.nonce.wrapping_add(1);
+        }
+        self
+    }
+}
+
+/// All the reasons an intent may be refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntentError {
+    /// The intent does not decode, or is too large.
+    Malformed,
+    /// The runtime has no order book for the intent's tokens.
+    UnknownPair,
+    /// The intent's hash does not start with enough zero bits.
+    NotEnoughWork,
+    /// The intent has already expired.
+    Expired,
+    /// The intent expires further ahead than [`MAX_INTENT_LIFETIME`] allows.
+    LivesTooLong,
+}
+
+/// Check that an intent may be gossiped while the best block has the given number.
+pub fn check_intent(intent: &OrderIntent, best_number: BlockNumber) -> Result<(), IntentError> {
+    if intent.encoded_size() as u64 > MAX_INTENT_SIZE {
+        return Err(IntentError::Malformed);
+    }
+    if !order_book::is_traded((intent.offer_token, intent.ask_token)) {
+        return Err(IntentError::UnknownPair);
+    }
+    if intent.expires_at <= best_number {
+        return Err(IntentError::Expired);
+    }
+    if intent.expires_at > best_number.saturating_add(MAX_INTENT_LIFETIME) {
+        return Err(IntentError::LivesTooLong);
+    }
+    if !intent.has_enough_work() {
+        return Err(IntentError::NotEnoughWork);
+    }
+    Ok(())
+}
+
+/// The configuration of the peer set that intents are gossiped to.
+pub fn peers_set_config() -> NonDefaultSetConfig {
+    let mut config = NonDefaultSetConfig::new(PROTOCOL_NAME.into(), MAX_INTENT_SIZE);
+    config.allow_non_reserved(25, 25);
+    config
+}
+
+/// Decides which gossiped intents are kept and passed on.
+struct IntentValidator {
+    best_number: Arc<dyn Fn() -> BlockNumber + Send + Sync>,
+}
+
+impl IntentValidator {
+    fn check(&self, data: &[u8]) -> Result<OrderIntent, IntentError> {
+        let intent = OrderIntent::decode_all(&mut &data[..]).map_err(|_| IntentError::Malformed)?;
+        check_intent(&intent, (self.best_number)())?;
+        Ok(intent)
+    }
+}
+
+impl Validator<Block> for IntentValidator {
+    fn validate(
+        &self,
+        _context: &mut dyn ValidatorContext<Block>,
+        _sender: &PeerId,
+        data: &[u8],
+    ) -> ValidationResult<H256> {
+        match self.check(data) {
+            Ok(_) => ValidationResult::ProcessAndKeep(topic()),
+            Err(_) => ValidationResult::Discard,
+        }
+    }
+
+    fn message_expired<'a>(&'a self) -> Box<dyn FnMut(H256, &[u8]) -> bool + 'a> {
+        Box::new(move |_topic, data| self.check(data).is_err())
+    }
+}
+
+/// A handle to the intent gossip, through which intents are sent and received.
+#[derive(Clone)]
+pub struct IntentPool {
+    outgoing: mpsc::UnboundedSender<OrderIntent>,
+    subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<OrderIntent>>>>,
+    best_number: Arc<dyn Fn() -> BlockNumber + Send + Sync>,
+}
+
+impl IntentPool {
+    /// Gossip an intent to the network, and pass it to local subscribers.
+    pub fn submit(&self, intent: OrderIntent) -> Result<H256, IntentError> {
+        check_intent(&intent, (self.best_number)())?;
+        let hash = intent.hash();
+        self.publish(&intent);
+        // The gossip task only stops when the node shuts down.
+        let _ = self.outgoing.unbounded_send(intent);
+        Ok(hash)
+    }
+
+    /// Receive every valid intent that arrives from now on.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<OrderIntent> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.subscribers
+            .lock()
+            .expect("Subscribers do not panic while holding the lock")
+            .push(sender);
+        receiver
+    }
+
+    /// Pass an intent to every subscriber, forgetting those that went away.
+    fn publish(&self, intent: &OrderIntent) {
+        self.subscribers
+            .lock()
+            .expect("Subscribers do not panic while holding the lock")
+            .retain(|subscriber| subscriber.unbounded_send(intent.clone()).is_ok());
+    }
+}
+
+/// Start gossiping intents, and return the handle to send and receive them.
+pub fn start<C, N, S>(
+    client: Arc<C>,
+    network: N,
+    sync: S,
+    spawn_handle: &SpawnTaskHandle,
+) -> IntentPool
+where
+    C: HeaderBackend<Block> + 'static,
+    N: Network<Block> + Send + Clone + 'static,
+    S: Syncing<Block> + Send + Clone + 'static,
+{
+    let best_number: Arc<dyn Fn() -> BlockNumber + Send + Sync> =
+        Arc::new(move || client.info().best_number);
+    let validator = Arc::new(IntentValidator {
+        best_number: best_number.clone(),
+    });
+    let (outgoing, mut to_gossip) = mpsc::unbounded();
+    let pool = IntentPool {
+        outgoing,
+        subscribers: Default::default(),
+        best_number,
+    };
+
+    let mut engine = GossipEngine::new(network, sync, PROTOCOL_NAME, validator, None);
+    let mut incoming = engine.messages_for(topic());
+    let local = pool.clone();
+    let gossip = future::poll_fn(move |cx| {
+        while let Poll::Ready(Some(intent)) = to_gossip.poll_next_unpin(cx) {
+            engine.gossip_message(topic(), intent.encode(), false);
+        }
+        // The engine only passes on intents that its validator kept.
+        while let Poll::Ready(Some(notification)) = incoming.poll_next_unpin(cx) {
+            if let Ok(intent) = OrderIntent::decode_all(&mut &notification.message[..]) {
+                local.publish(&intent);
+            }
+        }
+        // The engine is done once the network is.
+        engine.poll_unpin(cx)
+    });
+    spawn_handle.spawn("dex-intent-gossip", Some("networking"), gossip);
+
+    pool
+}
@@ -1,3 +1,7 @@
 pub mod chain_spec;
+pub mod dex_metrics;
+pub mod dex_telemetry;
+pub mod intent_gossip;
+pub mod pool_metrics;
 pub mod rpc;
 pub mod service;
@@ -6,7 +6,13 @@ mod chain_spec;
 mod service;
 mod cli;
 mod command;
+mod dex_metrics;
+mod dex_snapshot;
+mod dex_telemetry;
+mod intent_gossip;
+mod pool_metrics;
 mod rpc;
+mod try_state;
 
 fn main() -> sc_cli::Result<()> {
     command::run()
@@ -0,0 +1,198 @@
+//! Prometheus metrics of the transaction pool: rejections by cause, and how long matches take.
+//!
+//! The runtime reports why it rejects a transaction in the `TransactionValidityError` it
+//! returns, with the custom codes described in the runtime's `error_codes` module. The
+//! pool only keeps a total of the transactions it found invalid, so this wraps the pool's
+//! chain API and counts each rejection under the cause and the code, which lets operators
+//! spot attacks and bugs, like a peer replaying stale matches or a wallet that signs badly.
+//!
+//! The counter is `dex_pool_rejected_transactions_total`, labelled with:
+//! - `cause`: the piece or runtime-level check named by the runtime's `source_of_code` for
+//!   custom codes, `bad_witness` for redeemers that some verifier rejects, `spent_input` for
+//!   inputs that were spent recently, and the name of the error otherwise.
+//! - `code`: the custom code, or nothing. For the dex, the code minus `DEX_ERROR_OFFSET` is
+//!   the `DexError::code` of the error.
+//!
+//! The pool validates transactions again as blocks come in, so transactions that were valid
+//! once and no longer are count too. Transactions whose inputs do not exist yet are not
+//! rejected, since the pool keeps them until the inputs show up.
+//!
+//! Matches are the most expensive transactions to check, so the chain API also times their
+//! validation, rejected or not, in the `dex_match_validation_seconds` histogram.
+
+use futures::FutureExt;
+use node_template_runtime::{error_codes::source_of_code, Transaction};
+use parity_scale_codec::{Decode, Encode};
+use prometheus_endpoint::{
+    register, CounterVec, Histogram, HistogramOpts, Opts, PrometheusError, Registry, U64,
+};
+use sc_transaction_pool::ChainApi;
+use sp_blockchain::TreeRoute;
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, NumberFor},
+    transaction_validity::{
+        InvalidTransaction, TransactionSource, TransactionValidity, TransactionValidityError,
+        UnknownTransaction,
+    },
+};
+use std::{future::Future, pin::Pin, time::Instant};
+
+/// The metrics of the transaction pool.
+#[derive(Clone)]
+pub struct PoolMetrics {
+    rejected: CounterVec<U64>,
+    match_validation: Histogram,
+}
+
+impl PoolMetrics {
+    /// Register the metrics with the given registry.
+    pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            rejected: register(
+                CounterVec::new(
+                    Opts::new(
+                        "dex_pool_rejected_transactions_total",
+                        "Transactions rejected by the runtime while validating them for the pool",
+                    ),
+                    &["cause", "code"],
+                )?,
+                registry,
+            )?,
+            match_validation: register(
+                Histogram::with_opts(HistogramOpts::new(
+                    "dex_match_validation_seconds",
+                    "How long the pool takes to validate transactions that match dex orders",
+                ))?,
+                registry,
+            )?,
+        })
+    }
+
+    /// Count a rejection.
+    fn report(&self, error: &TransactionValidityError) {
+        let (cause, code) = labels(error);
+        self.rejected.with_label_values(&[cause, &code]).inc();
+    }
+}
+
+/// Whether an extrinsic is a transaction that matches dex orders.
+fn is_match(extrinsic: &impl Encode) -> bool {
+    Transaction::decode(&mut &extrinsic.encode()[..])
+        .map_or(false, |transaction| transaction.checker.is_match())
+}
+
+/// The cause and the code that a rejection is counted under.
+fn labels(error: &TransactionValidityError) -> (&'static str, String) {
+    let cause = match error {
+        TransactionValidityError::Invalid(InvalidTransaction::Custom(code)) => {
+            return (source_of_code(*code).unwrap_or("custom"), code.to_string());
+        }
+        TransactionValidityError::Invalid(invalid) => match invalid {
+            InvalidTransaction::BadProof => "bad_witness",
+            InvalidTransaction::Stale => "spent_input",
+            InvalidTransaction::ExhaustsResources => "exhausts_resources",
+            InvalidTransaction::MandatoryValidation | InvalidTransaction::BadMandatory => {
+                "inherent"
+            }
+            _ => "invalid",
+        },
+        TransactionValidityError::Unknown(unknown) => match unknown {
+            UnknownTransaction::CannotLookup => "cannot_lookup",
+            _ => "unknown",
+        },
+    };
+    (cause, String::new())
+}
+
+/// A chain API for the pool that counts the transactions that the wrapped one rejects, and
+/// times how long it takes to validate matches.
+pub struct MeteredChainApi<A> {
+    inner: A,
+    metrics: Option<PoolMetrics>,
+}
+
+impl<A> MeteredChainApi<A> {
+    /// Wrap a chain API. Nothing is measured without metrics, like when Prometheus is disabled.
+    pub fn new(inner: A, metrics: Option<PoolMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+type Extrinsic<A> = <<A as ChainApi>::Block as BlockT>::Extrinsic;
+type Hash<A> = <<A as ChainApi>::Block as BlockT>::Hash;
+
+impl<A: ChainApi> ChainApi for MeteredChainApi<A>
+where
+    A::ValidationFuture: 'static,
+{
+    type Block = A::Block;
+    type Error = A::Error;
+    type ValidationFuture =
+        Pin<Box<dyn Future<Output = Result<TransactionValidity, Self::Error>> + Send>>;
+    type BodyFuture = A::BodyFuture;
+
+    fn validate_transaction(
+        &self,
+        at: &BlockId<Self::Block>,
+        source: TransactionSource,
+        uxt: Extrinsic<Self>,
+    ) -> Self::ValidationFuture {
+        let metrics = self.metrics.clone();
+        let timed = metrics.is_some() && is_match(&uxt);
+        let started = Instant::now();
+        self.inner
+            .validate_transaction(at, source, uxt)
+            .map(move |result| {
+                if let Some(metrics) = &metrics {
+                    if timed {
+                        metrics
+                            .match_validation
+                            .observe(started.elapsed().as_secs_f64());
+                    }
+                    if let Ok(Err(error)) = &result {
+                        metrics.report(error);
+                    }
+                }
+                result
+            })
+            .boxed()
+    }
+
+    fn block_id_to_number(
+        &self,
+        at: &BlockId<Self::Block>,
+    ) -> Result<Option<NumberFor<Self::Block>>, Self::Error> {
+        self.inner.block_id_to_number(at)
+    }
+
+    fn block_id_to_hash(
+        &self,
+        at: &BlockId<Self::Block>,
+    ) -> Result<Option<Hash<Self>>, Self::Error> {
+        self.inner.block_id_to_hash(at)
+    }
+
+    fn hash_and_length(&self, uxt: &Extrinsic<Self>) -> (Hash<Self>, usize) {
+        self.inner.hash_and_length(uxt)
+    }
+
+    fn block_body(&self, at: Hash<Self>) -> Self::BodyFuture {
+        self.inner.block_body(at)
+    }
+
+    fn block_header(
+        &self,
+        at: Hash<Self>,
+    ) -> Result<Option<<Self::Block as BlockT>::Header>, Self::Error> {
+        self.inner.block_header(at)
+    }
+
+    fn tree_route(
+        &self,
+        from: Hash<Self>,
+        to: Hash<Self>,
+    ) -> Result<TreeRoute<Self::Block>, Self::Error> {
+        self.inner.tree_route(from, to)
+    }
+}
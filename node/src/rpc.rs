@@ -7,8 +7,11 @@
 
 use std::sync::Arc;
 
+use crate::intent_gossip::IntentPool;
 use jsonrpsee::RpcModule;
-use node_template_runtime::opaque::Block;
+use node_template_runtime::{dry_run::DryRunApi, journal::SpentOutputsApi, opaque::Block};
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sc_rpc::SubscriptionTaskExecutor;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
@@ -16,6 +19,9 @@ use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 
 pub use sc_rpc_api::DenyUnsafe;
 
+pub mod dex;
+pub mod intents;
+
 /// Full client dependencies.
 pub struct FullDeps<C, P> {
     /// The client instance to use.
@@ -24,20 +30,40 @@ pub struct FullDeps<C, P> {
     pub pool: Arc<P>,
     /// Whether to deny unsafe calls
     pub deny_unsafe: DenyUnsafe,
+    /// Executor on which subscriptions are driven
+    pub subscription_executor: SubscriptionTaskExecutor,
+    /// The gossiped order intents
+    pub intents: IntentPool,
 }
 
 /// Instantiate all full RPC extensions.
 pub fn create_full<C, P>(
-    _deps: FullDeps<C, P>,
+    deps: FullDeps<C, P>,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
     C: ProvideRuntimeApi<Block>,
     C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+    C: BlockBackend<Block> + BlockchainEvents<Block>,
     C: Send + Sync + 'static,
     C::Api: BlockBuilder<Block>,
+    C::Api: SpentOutputsApi<Block>,
+    C::Api: DryRunApi<Block>,
     P: TransactionPool + 'static,
 {
-    let module = RpcModule::new(());
+    use dex::{Dex, DexApiServer};
+    use intents::{Intents, IntentsApiServer};
+
+    let mut module = RpcModule::new(());
+    let FullDeps {
+        client,
+        subscription_executor,
+        intents,
+        ..
+    } = deps;
+
+    module.merge(Dex::new(client, subscription_executor.clone()).into_rpc())?;
+    module.merge(Intents::new(intents, subscription_executor).into_rpc())?;
+
     // Extend this RPC with a custom API by using the following syntax.
     // `YourRpcStruct` should have a reference to a client, which is needed
     // to call into the runtime.
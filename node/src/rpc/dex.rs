@@ -0,0 +1,308 @@
+//! RPC subscriptions that follow the dex as blocks are imported.
+//!
+//! Each time a new best block is imported, the node reads its transactions and the outputs
+//! it spent, and pushes whatever changed in the subscribed market to the subscriber. That
+//! way user interfaces and market-data consumers do not need to poll the runtime every block.
+//!
+//! Only new best blocks are reported. When the best chain reorganizes, the blocks that were
+//! retracted are not reported again, so subscribers that need to survive reorgs should track
+//! block hashes and resubscribe.
+//!
+//! Matchers can also dry-run a match against the best block before submitting it, to find
+//! out exactly why the dex would reject it.
+
+use std::sync::Arc;
+
+use dex::{Price, Rounding};
+use futures::{future, FutureExt, Stream, StreamExt};
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    types::{error::ErrorObject, SubscriptionResult},
+    SubscriptionSink,
+};
+use node_template_runtime::{
+    dry_run::{DryRunApi, DryRunError},
+    journal::SpentOutputsApi,
+    opaque::Block,
+    order_book::{self, Fill, OrderBookDelta},
+    BlockNumber, Output, Transaction,
+};
+use parity_scale_codec::{Decode, Encode};
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{traits::SpawnNamed, Bytes, H256};
+use sp_runtime::{traits::Header as _, transaction_validity::TransactionPriority};
+use tuxedo_core::types::OutputRef;
+
+/// The error code returned when subscribing to a pair of tokens the runtime does not trade.
+const UNKNOWN_PAIR: i32 = 1001;
+
+/// The error code returned when a dry-run match fails.
+const DRY_RUN_FAILED: i32 = 1003;
+
+/// Everything that changed in one market's order book in a single block.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderBookUpdate {
+    /// The hash of the block that made the changes
+    pub block_hash: H256,
+    /// The number of the block that made the changes
+    pub block_number: BlockNumber,
+    /// The changes, with opened orders before filled ones
+    pub deltas: Vec<OrderBookDelta>,
+}
+
+/// A single executed order, as seen from one market.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Trade {
+    /// The hash of the block that executed the order
+    pub block_hash: H256,
+    /// The number of the block that executed the order
+    pub block_number: BlockNumber,
+    /// Where the executed order was stored
+    pub order_ref: OutputRef,
+    /// Whether the executed order offered the base token
+    pub sold_base: bool,
+    /// The amount of the base token that changed hands
+    pub size: u128,
+    /// The amount of the quote token that changed hands
+    pub quote_amount: u128,
+    /// The amount of the quote token paid for each unit of the base token, with
+    /// `Price::DECIMALS` decimal places, or `None` if no base token changed hands
+    pub price: Option<Price>,
+}
+
+impl Trade {
+    /// The trade made by a fill in the given market, where the first token of `pair` is
+    /// the base, if the fill was in that market.
+    fn new(
+        block_hash: H256,
+        block_number: BlockNumber,
+        fill: Fill,
+        pair: (u8, u8),
+    ) -> Option<Self> {
+        let (size, quote_amount) = fill.amounts_in(pair)?;
+        Some(Self {
+            block_hash,
+            block_number,
+            sold_base: fill.terms.offer_token == pair.0,
+            size,
+            quote_amount,
+            price: Price::from_amounts(quote_amount, size, Rounding::Down),
+            order_ref: fill.order_ref,
+        })
+    }
+}
+
+/// Subscriptions to the dex markets.
+#[rpc(server)]
+pub trait DexApi {
+    /// Subscribe to the changes in the order book of the given pair of token ids.
+    ///
+    /// Blocks that do not change this market are skipped.
+    #[subscription(
+        name = "dex_subscribeOrderBook" => "dex_orderBook",
+        unsubscribe = "dex_unsubscribeOrderBook",
+        item = OrderBookUpdate
+    )]
+    fn subscribe_order_book(&self, pair: (u8, u8));
+
+    /// Subscribe to the orders executed in the market of the given pair of token ids.
+    ///
+    /// The first token of the pair is the base in which trade sizes are measured, and the
+    /// second is the quote in which prices are given. Every order executed by a
+    /// `MatchOrders` transaction is reported as its own trade.
+    #[subscription(
+        name = "dex_subscribeTrades" => "dex_trades",
+        unsubscribe = "dex_unsubscribeTrades",
+        item = Trade
+    )]
+    fn subscribe_trades(&self, pair: (u8, u8));
+
+    /// Check the given SCALE encoded match transaction against the best block, without
+    /// submitting it, and return the priority it would get.
+    ///
+    /// If the dex rejects the match, the error says why, and its data is the `DexError`.
+    /// Signatures are not checked.
+    #[method(name = "dex_dryRunMatch")]
+    fn dry_run_match(&self, transaction: Bytes) -> RpcResult<TransactionPriority>;
+}
+
+/// The implementation of the [`DexApiServer`].
+pub struct Dex<C> {
+    client: Arc<C>,
+    executor: SubscriptionTaskExecutor,
+}
+
+impl<C> Dex<C> {
+    /// Create a new instance of the dex subscriptions.
+    pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+        Self { client, executor }
+    }
+}
+
+/// The parts of an imported block that the dex subscriptions are interested in.
+struct BlockContents {
+    hash: H256,
+    number: BlockNumber,
+    transactions: Vec<Transaction>,
+    /// The outputs the block spent, as journaled by the runtime
+    spent: Vec<(OutputRef, Output)>,
+}
+
+/// Read the contents of the given block.
+///
+/// Returns `None` if the block's body or its journal entry cannot be read.
+fn block_contents<C>(client: &C, hash: H256, number: BlockNumber) -> Option<BlockContents>
+where
+    C: BlockBackend<Block> + ProvideRuntimeApi<Block>,
+    C::Api: SpentOutputsApi<Block>,
+{
+    let transactions = client
+        .block_body(hash)
+        .ok()??
+        .iter()
+        .filter_map(|extrinsic| Transaction::decode(&mut &extrinsic.encode()[..]).ok())
+        .collect();
+    let spent = client.runtime_api().spent_in(hash, number).ok()?;
+
+    Some(BlockContents {
+        hash,
+        number,
+        transactions,
+        spent,
+    })
+}
+
+/// The contents of every new best block, as it is imported.
+///
+/// Blocks whose contents cannot be read are skipped.
+fn best_blocks<C>(client: Arc<C>) -> impl Stream<Item = BlockContents>
+where
+    C: BlockBackend<Block> + BlockchainEvents<Block> + ProvideRuntimeApi<Block>,
+    C::Api: SpentOutputsApi<Block>,
+{
+    client
+        .import_notification_stream()
+        .filter(|notification| future::ready(notification.is_new_best))
+        .filter_map(move |notification| {
+            future::ready(block_contents(
+                &*client,
+                notification.hash,
+                *notification.header.number(),
+            ))
+        })
+}
+
+/// The RPC error for a failed dry run.
+fn dry_run_failed(reason: String, data: Option<String>) -> jsonrpsee::core::Error {
+    ErrorObject::owned(DRY_RUN_FAILED, reason, data).into()
+}
+
+/// Reject a subscription to a pair of tokens the runtime does not trade.
+fn reject_unknown_pair(mut sink: SubscriptionSink, pair: (u8, u8)) -> SubscriptionResult {
+    let _ = sink.reject(ErrorObject::owned(
+        UNKNOWN_PAIR,
+        format!(
+            "There is no market between tokens {} and {}",
+            pair.0, pair.1
+        ),
+        None::<()>,
+    ));
+    Ok(())
+}
+
+impl<C> DexApiServer for Dex<C>
+where
+    C: BlockBackend<Block> + BlockchainEvents<Block> + ProvideRuntimeApi<Block>,
+    C: HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: SpentOutputsApi<Block> + DryRunApi<Block>,
+{
+    fn subscribe_order_book(
+        &self,
+        mut sink: SubscriptionSink,
+        pair: (u8, u8),
+    ) -> SubscriptionResult {
+        if !order_book::is_traded(pair) {
+            return reject_unknown_pair(sink, pair);
+        }
+
+        let updates = best_blocks(self.client.clone())
+            .filter_map(move |block| {
+                let deltas: Vec<OrderBookDelta> =
+                    order_book::deltas(&block.transactions, &block.spent)
+                        .into_iter()
+                        .filter(|delta| delta.terms().is_in_pair(pair))
+                        .collect();
+                future::ready((!deltas.is_empty()).then_some(OrderBookUpdate {
+                    block_hash: block.hash,
+                    block_number: block.number,
+                    deltas,
+                }))
+            })
+            .boxed();
+
+        let fut = async move {
+            sink.pipe_from_stream(updates).await;
+        };
+        self.executor
+            .spawn("dex-order-book-subscription", Some("rpc"), fut.boxed());
+
+        Ok(())
+    }
+
+    fn subscribe_trades(&self, mut sink: SubscriptionSink, pair: (u8, u8)) -> SubscriptionResult {
+        if !order_book::is_traded(pair) {
+            return reject_unknown_pair(sink, pair);
+        }
+
+        let trades = best_blocks(self.client.clone())
+            .flat_map(move |block| {
+                let trades: Vec<Trade> = order_book::fills(&block.transactions, &block.spent)
+                    .into_iter()
+                    .filter_map(|fill| Trade::new(block.hash, block.number, fill, pair))
+                    .collect();
+                futures::stream::iter(trades)
+            })
+            .boxed();
+
+        let fut = async move {
+            sink.pipe_from_stream(trades).await;
+        };
+        self.executor
+            .spawn("dex-trades-subscription", Some("rpc"), fut.boxed());
+
+        Ok(())
+    }
+
+    fn dry_run_match(&self, transaction: Bytes) -> RpcResult<TransactionPriority> {
+        let transaction = Transaction::decode(&mut &transaction[..])
+            .map_err(|_| dry_run_failed("The transaction does not decode".into(), None))?;
+        let best = self.client.info().best_hash;
+        let result = self
+            .client
+            .runtime_api()
+            .dry_run_match(best, transaction)
+            .map_err(|e| dry_run_failed(format!("The runtime could not be called: {}", e), None))?;
+
+        result.map_err(|error| match error {
+            DryRunError::NotAMatch => {
+                dry_run_failed("The transaction does not match orders".into(), None)
+            }
+            DryRunError::MissingInputs(missing) => dry_run_failed(
+                format!("{} of the inputs do not exist", missing.len()),
+                Some(format!("{:?}", missing)),
+            ),
+            DryRunError::RuntimeCheck(e) => dry_run_failed(
+                "The runtime-level checks reject the transaction".into(),
+                Some(format!("{:?}", e)),
+            ),
+            DryRunError::Dex(e) => dry_run_failed(e.to_string(), Some(format!("{:?}", e))),
+        })
+    }
+}
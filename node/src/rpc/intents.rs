@@ -0,0 +1,73 @@
+//! RPC access to the gossiped order intents. See the `intent_gossip` module.
+
+use futures::{FutureExt, StreamExt};
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    types::{error::ErrorObject, SubscriptionResult},
+    SubscriptionSink,
+};
+use sc_rpc::SubscriptionTaskExecutor;
+use sp_core::{traits::SpawnNamed, H256};
+
+use crate::intent_gossip::{IntentError, IntentPool, OrderIntent};
+
+/// The error code returned when an intent is refused.
+const INTENT_REFUSED: i32 = 1002;
+
+/// Announcing and discovering order intents.
+#[rpc(server)]
+pub trait IntentsApi {
+    /// Gossip an order intent to the network, and return its hash.
+    ///
+    /// The intent must already carry enough proof of work.
+    #[method(name = "dex_submitIntent")]
+    fn submit_intent(&self, intent: OrderIntent) -> RpcResult<H256>;
+
+    /// Subscribe to the order intents that arrive from now on, including local ones.
+    #[subscription(
+        name = "dex_subscribeIntents" => "dex_intent",
+        unsubscribe = "dex_unsubscribeIntents",
+        item = OrderIntent
+    )]
+    fn subscribe_intents(&self);
+}
+
+/// The implementation of the [`IntentsApiServer`].
+pub struct Intents {
+    pool: IntentPool,
+    executor: SubscriptionTaskExecutor,
+}
+
+impl Intents {
+    /// Create a new instance of the intents API.
+    pub fn new(pool: IntentPool, executor: SubscriptionTaskExecutor) -> Self {
+        Self { pool, executor }
+    }
+}
+
+impl IntentsApiServer for Intents {
+    fn submit_intent(&self, intent: OrderIntent) -> RpcResult<H256> {
+        self.pool.submit(intent).map_err(|error| {
+            let reason = match error {
+                IntentError::Malformed => "The intent is too large",
+                IntentError::UnknownPair => "There is no market between the intent's tokens",
+                IntentError::NotEnoughWork => "The intent does not carry enough proof of work",
+                IntentError::Expired => "The intent has already expired",
+                IntentError::LivesTooLong => "The intent expires too far in the future",
+            };
+            ErrorObject::owned(INTENT_REFUSED, reason, None::<()>).into()
+        })
+    }
+
+    fn subscribe_intents(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
+        let intents = self.pool.subscribe().boxed();
+        let fut = async move {
+            sink.pipe_from_stream(intents).await;
+        };
+        self.executor
+            .spawn("dex-intents-subscription", Some("rpc"), fut.boxed());
+
+        Ok(())
+    }
+}
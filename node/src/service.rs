@@ -1,6 +1,6 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use crate::rpc;
+use crate::{dex_metrics, dex_telemetry, intent_gossip, pool_metrics, rpc};
 use node_template_runtime::{self, opaque::Block, RuntimeApi};
 use sc_client_api::BlockBackend;
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
@@ -8,6 +8,7 @@ use sc_consensus_grandpa::SharedVoterState;
 pub use sc_executor::NativeElseWasmExecutor;
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager, WarpSyncParams};
 use sc_telemetry::{Telemetry, TelemetryWorker};
+use sp_blockchain::HeaderBackend;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
 use std::{sync::Arc, time::Duration};
 
@@ -30,6 +31,12 @@ pub(crate) type FullClient =
     sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
+/// The transaction pool, which measures how it validates transactions.
+/// See the [`pool_metrics`] module.
+pub(crate) type FullPool = sc_transaction_pool::BasicPool<
+    pool_metrics::MeteredChainApi<sc_transaction_pool::FullChainApi<FullClient, Block>>,
+    Block,
+>;
 
 #[allow(clippy::type_complexity)]
 pub fn new_partial(
@@ -40,7 +47,7 @@ pub fn new_partial(
         FullBackend,
         FullSelectChain,
         sc_consensus::DefaultImportQueue<Block, FullClient>,
-        sc_transaction_pool::FullPool<Block, FullClient>,
+        FullPool,
         (
             sc_consensus_grandpa::GrandpaBlockImport<
                 FullBackend,
@@ -84,13 +91,32 @@ pub fn new_partial(
 
     let select_chain = sc_consensus::LongestChain::new(backend.clone());
 
-    let transaction_pool = sc_transaction_pool::BasicPool::new_full(
+    // This builds the pool like `BasicPool::new_full` does, but with a chain API that takes
+    // measurements. It is not registered for offchain workers, which Tuxedo does not support.
+    let metrics = config
+        .prometheus_registry()
+        .map(pool_metrics::PoolMetrics::register)
+        .transpose()?;
+    let pool_api = pool_metrics::MeteredChainApi::new(
+        sc_transaction_pool::FullChainApi::new(
+            client.clone(),
+            config.prometheus_registry(),
+            &task_manager.spawn_essential_handle(),
+        ),
+        metrics,
+    );
+    let chain = client.info();
+    let transaction_pool = Arc::new(sc_transaction_pool::BasicPool::with_revalidation_type(
         config.transaction_pool.clone(),
         config.role.is_authority().into(),
+        Arc::new(pool_api),
         config.prometheus_registry(),
+        sc_transaction_pool::RevalidationType::Full,
         task_manager.spawn_essential_handle(),
-        client.clone(),
-    );
+        chain.best_number,
+        chain.best_hash,
+        chain.finalized_hash,
+    ));
 
     let (grandpa_block_import, grandpa_link) = sc_consensus_grandpa::block_import(
         client.clone(),
@@ -164,6 +190,10 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
         .push(sc_consensus_grandpa::grandpa_peers_set_config(
             grandpa_protocol_name.clone(),
         ));
+    config
+        .network
+        .extra_sets
+        .push(intent_gossip::peers_set_config());
     let warp_sync = Arc::new(sc_consensus_grandpa::warp_proof::NetworkProvider::new(
         backend.clone(),
         grandpa_link.shared_authority_set().clone(),
@@ -181,6 +211,25 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
             warp_sync_params: Some(WarpSyncParams::WithProvider(warp_sync)),
         })?;
 
+    let intents = intent_gossip::start(
+        client.clone(),
+        network.clone(),
+        sync_service.clone(),
+        &task_manager.spawn_handle(),
+    );
+
+    if let Some(registry) = config.prometheus_registry() {
+        dex_metrics::start(
+            client.clone(),
+            dex_metrics::DexMetrics::register(registry)?,
+            &task_manager.spawn_handle(),
+        );
+    }
+
+    if let Some(handle) = telemetry.as_ref().map(|x| x.handle()) {
+        dex_telemetry::start(client.clone(), handle, &task_manager.spawn_handle());
+    }
+
     if config.offchain_worker.enabled {
         sc_service::build_offchain_workers(
             &config,
@@ -201,11 +250,13 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
         let client = client.clone();
         let pool = transaction_pool.clone();
 
-        Box::new(move |deny_unsafe, _| {
+        Box::new(move |deny_unsafe, subscription_executor| {
             let deps = rpc::FullDeps {
                 client: client.clone(),
                 pool: pool.clone(),
                 deny_unsafe,
+                subscription_executor,
+                intents: intents.clone(),
             };
             rpc::create_full(deps).map_err(Into::into)
         })
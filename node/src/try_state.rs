@@ -0,0 +1,75 @@
+//! Checking the invariants of the dex against the state of a running chain.
+//!
+//! The checks themselves live in the runtime, so that they always match the rules that
+//! built the state. See the runtime's `try_state` module. This command runs them at some
+//! block through the `TryStateApi`, reports every broken invariant, and fails if there are
+//! any, so that operators can run it from scripts after upgrades.
+
+use std::sync::Arc;
+
+use node_template_runtime::try_state::TryStateApi;
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+
+use crate::service::FullClient;
+
+/// Check the invariants of the dex against the state at some block.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct TryStateCmd {
+    /// The hash of the block to check the state of. Defaults to the best block.
+    #[arg(long)]
+    pub at: Option<H256>,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub pruning_params: PruningParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub database_params: DatabaseParams,
+}
+
+impl TryStateCmd {
+    /// Run the checks with the given client and report what they find.
+    pub fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+        let block_hash = self.at.unwrap_or_else(|| client.info().best_hash);
+        let errors = client
+            .runtime_api()
+            .try_state(block_hash)
+            .map_err(|e| format!("Failed to run the checks at {:?}: {}", block_hash, e))?;
+
+        if errors.is_empty() {
+            println!("The dex state at {:?} is sane", block_hash);
+            return Ok(());
+        }
+        for error in &errors {
+            eprintln!("{:?}", error);
+        }
+        Err(format!(
+            "The dex state at {:?} breaks {} invariants",
+            block_hash,
+            errors.len()
+        )
+        .into())
+    }
+}
+
+impl CliConfiguration for TryStateCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn pruning_params(&self) -> Option<&PruningParams> {
+        Some(&self.pruning_params)
+    }
+
+    fn database_params(&self) -> Option<&DatabaseParams> {
+        Some(&self.database_params)
+    }
+}
@@ -0,0 +1,146 @@
+//! Proof of existence.
+//!
+//! Users may claim the hash of a document at a certain point in time. A claim proves that
+//! the claimer knew the document no later than the block in which the claim was made.
+//!
+//! Each claim is a UTXO protected by the claimer's verifier. Because the piece offers no way
+//! to transfer a claim, only the original claimer can ever consume it, which is how a claim
+//! is revoked.
+//!
+//! A document hash may only be claimed once at a time. To enforce this, the piece keeps an
+//! index of all active claims in storage, keyed by the document hash. The index is updated
+//! as a side effect of successful constraint checking, in the same way that the runtime
+//! upgrade piece writes the new code.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{collections::btree_set::BTreeSet, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, DynamicallyTypedData, UtxoData},
+    ensure, SimpleConstraintChecker,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The prefix of the storage keys under which active claims are indexed.
+pub const CLAIM_INDEX_PREFIX: &[u8] = b"poe_claim";
+
+/// A single claim to the existence of some document.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub struct ClaimData {
+    /// The hash of the document being claimed
+    pub claim: H256,
+}
+
+impl UtxoData for ClaimData {
+    const TYPE_ID: [u8; 4] = *b"poe_";
+}
+
+/// The storage key at which a claim to the given document hash is indexed.
+pub fn claim_index_key(claim: &H256) -> Vec<u8> {
+    (CLAIM_INDEX_PREFIX, claim).encode()
+}
+
+/// Whether there is currently an active claim to the given document hash.
+pub fn is_claimed(claim: &H256) -> bool {
+    sp_io::storage::exists(&claim_index_key(claim))
+}
+
+/// The constraint checker for the proof of existence piece.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum PoeConstraintChecker {
+    /// Create new claims. There are no inputs, and every output is a claim
+    /// to a document hash that is not already claimed.
+    Claim,
+    /// Revoke existing claims. Every input is a claim, and there are no outputs.
+    /// The claims' verifiers make sure that only the original claimer can do this.
+    Revoke,
+}
+
+/// Errors that can occur when checking proof of existence transactions.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum PoeError {
+    /// Dynamic typing issue.
+    /// This error doesn't discriminate between badly typed inputs and outputs.
+    BadlyTyped,
+    /// Claims are created from nothing, so no inputs may be supplied when claiming.
+    UnexpectedInputsWhenClaiming,
+    /// A claim transaction must create at least one claim.
+    ClaimingNothing,
+    /// The document hash is already claimed, either by an earlier transaction
+    /// or by another output of this same transaction.
+    DuplicateClaim,
+    /// A revoke transaction must consume at least one claim.
+    RevokingNothing,
+    /// Revoked claims are simply destroyed, so no outputs may be supplied when revoking.
+    UnexpectedOutputsWhenRevoking,
+}
+
+impl From<DynamicTypingError> for PoeError {
+    fn from(_value: DynamicTypingError) -> Self {
+        Self::BadlyTyped
+    }
+}
+
+impl SimpleConstraintChecker for PoeConstraintChecker {
+    type Error = PoeError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        match self {
+            Self::Claim => {
+                ensure!(
+                    input_data.is_empty(),
+                    PoeError::UnexpectedInputsWhenClaiming
+                );
+                ensure!(!output_data.is_empty(), PoeError::ClaimingNothing);
+
+                let mut claims = BTreeSet::new();
+                for output in output_data {
+                    let ClaimData { claim } = output.extract()?;
+                    ensure!(!is_claimed(&claim), PoeError::DuplicateClaim);
+                    ensure!(claims.insert(claim), PoeError::DuplicateClaim);
+                }
+
+                // SIDE EFFECT: Index the new claims so they cannot be claimed again
+                for claim in claims {
+                    sp_io::storage::set(&claim_index_key(&claim), &[]);
+                }
+
+                Ok(0)
+            }
+            Self::Revoke => {
+                ensure!(!input_data.is_empty(), PoeError::RevokingNothing);
+                ensure!(
+                    output_data.is_empty(),
+                    PoeError::UnexpectedOutputsWhenRevoking
+                );
+
+                let claims = input_data
+                    .iter()
+                    .map(|input| input.extract::<ClaimData>().map(|data| data.claim))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                // SIDE EFFECT: Free up the revoked document hashes so they may be claimed again
+                for claim in claims {
+                    sp_io::storage::clear(&claim_index_key(&claim));
+                }
+
+                Ok(0)
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Unit tests for the Proof of Existence piece
+
+use super::*;
+use sp_io::TestExternalities;
+
+fn claim(byte: u8) -> ClaimData {
+    ClaimData {
+        claim: H256::repeat_byte(byte),
+    }
+}
+
+#[test]
+fn claiming_works() {
+    TestExternalities::default().execute_with(|| {
+        let result = PoeConstraintChecker::Claim.check(&[], &[claim(1).into(), claim(2).into()]);
+        assert_eq!(result, Ok(0));
+        assert!(is_claimed(&claim(1).claim));
+        assert!(is_claimed(&claim(2).claim));
+    });
+}
+
+#[test]
+fn claiming_nothing_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = PoeConstraintChecker::Claim.check(&[], &[]);
+        assert_eq!(result, Err(PoeError::ClaimingNothing));
+    });
+}
+
+#[test]
+fn claiming_with_inputs_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = PoeConstraintChecker::Claim.check(&[claim(1).into()], &[claim(2).into()]);
+        assert_eq!(result, Err(PoeError::UnexpectedInputsWhenClaiming));
+    });
+}
+
+#[test]
+fn claiming_already_claimed_hash_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            PoeConstraintChecker::Claim.check(&[], &[claim(1).into()]),
+            Ok(0)
+        );
+
+        let result = PoeConstraintChecker::Claim.check(&[], &[claim(1).into()]);
+        assert_eq!(result, Err(PoeError::DuplicateClaim));
+    });
+}
+
+#[test]
+fn claiming_same_hash_twice_in_one_transaction_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = PoeConstraintChecker::Claim.check(&[], &[claim(1).into(), claim(1).into()]);
+        assert_eq!(result, Err(PoeError::DuplicateClaim));
+        assert!(!is_claimed(&claim(1).claim));
+    });
+}
+
+#[test]
+fn claiming_badly_typed_output_fails() {
+    TestExternalities::default().execute_with(|| {
+        let bogus = DynamicallyTypedData {
+            data: vec![],
+            type_id: *b"bogs",
+        };
+        let result = PoeConstraintChecker::Claim.check(&[], &[bogus]);
+        assert_eq!(result, Err(PoeError::BadlyTyped));
+    });
+}
+
+#[test]
+fn revoking_works_and_frees_the_hash() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            PoeConstraintChecker::Claim.check(&[], &[claim(1).into()]),
+            Ok(0)
+        );
+
+        let result = PoeConstraintChecker::Revoke.check(&[claim(1).into()], &[]);
+        assert_eq!(result, Ok(0));
+        assert!(!is_claimed(&claim(1).claim));
+
+        // Now that it is revoked, the same hash may be claimed again
+        assert_eq!(
+            PoeConstraintChecker::Claim.check(&[], &[claim(1).into()]),
+            Ok(0)
+        );
+    });
+}
+
+#[test]
+fn revoking_nothing_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = PoeConstraintChecker::Revoke.check(&[], &[]);
+        assert_eq!(result, Err(PoeError::RevokingNothing));
+    });
+}
+
+#[test]
+fn revoking_with_outputs_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = PoeConstraintChecker::Revoke.check(&[claim(1).into()], &[claim(1).into()]);
+        assert_eq!(result, Err(PoeError::UnexpectedOutputsWhenRevoking));
+    });
+}
@@ -0,0 +1,284 @@
+//! Social recovery, where a quorum of guardians hands an owner's coins to a new owner.
+//!
+//! An owner who fears losing their key protects their coins, and the payouts and refunds of
+//! their orders, with a recoverable verifier in the runtime. It names the owner, their
+//! guardians, how many guardians must agree, and a delay. Should the owner lose their key,
+//! the guardians open a [`Recovery`] that names a new owner, and approve it in as many
+//! transactions as they like. Once enough of them have approved and the delay has passed,
+//! the recoverable verifier lets the new owner spend in place of the old one.
+//!
+//! The delay is the owner's chance to challenge guardians who try to steal the coins. Until
+//! it has passed, the owner may cancel the recovery with [`CancelRecovery`].
+//!
+//! Checkers cannot see signatures, so guardians approve, and owners cancel, by spending UTXOs
+//! that are protected by their own verifiers, which they get back unchanged in the same
+//! transaction. Recoveries themselves are protected by a verifier that anyone can satisfy.
+//! The current block is not known to constraint checkers, so the runtime hands it to this
+//! piece through [`RecoveryConfig::block_number`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The configuration of social recovery.
+pub trait RecoveryConfig {
+    /// The type of verifiers that owners and guardians use.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+
+    /// The verifier that protects recoveries, which anyone must be able to satisfy.
+    fn open_verifier() -> Self::Verifier;
+
+    /// The number of the block that transactions are being checked in.
+    fn block_number() -> u32;
+}
+
+/// A request of some guardians to hand an owner's coins to a new owner.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct Recovery<T: RecoveryConfig> {
+    /// The verifier whose coins are recovered, which may cancel the recovery
+    pub owner: T::Verifier,
+    /// The verifier that may spend the coins once the recovery is ready
+    pub new_owner: T::Verifier,
+    /// The verifiers of the guardians who may approve the recovery
+    pub guardians: Vec<T::Verifier>,
+    /// How many of the guardians must approve the recovery
+    pub threshold: u32,
+    /// How many blocks the owner has to cancel the recovery once it is approved
+    pub delay: u32,
+    /// The positions among the guardians of those who have approved, in increasing order
+    pub approvals: Vec<u32>,
+    /// The block in which enough guardians had approved, if they have yet
+    pub approved_at: Option<u32>,
+}
+
+impl<T: RecoveryConfig> UtxoData for Recovery<T> {
+    const TYPE_ID: [u8; 4] = *b"rcvr";
+}
+
+impl<T: RecoveryConfig> Recovery<T> {
+    /// Whether the new owner may spend the owner's coins in the given block.
+    pub fn is_ready(&self, now: u32) -> bool {
+        self.approved_at.map_or(false, |approved_at| {
+            now >= approved_at.saturating_add(self.delay)
+        })
+    }
+
+    /// The block in which a recovery with the given approvals is approved, if it is by now.
+    fn approved_at_with(&self, approvals: &[u32], now: u32) -> Option<u32> {
+        (approvals.len() >= self.threshold as usize).then_some(now)
+    }
+
+    /// The positions of the guardians who protect the given UTXOs, in increasing order.
+    fn approvals_by(&self, credentials: &[Output<T::Verifier>]) -> Result<Vec<u32>, RecoveryError> {
+        ensure!(!credentials.is_empty(), RecoveryError::NoCredentials);
+        let mut approvals = Vec::with_capacity(credentials.len());
+        for credential in credentials {
+            let index = self
+                .guardians
+                .iter()
+                .position(|guardian| *guardian == credential.verifier)
+                .ok_or(RecoveryError::NotAGuardian)? as u32;
+            ensure!(
+                !approvals.contains(&index) && !self.approvals.contains(&index),
+                RecoveryError::AlreadyApproved
+            );
+            approvals.push(index);
+        }
+        approvals.sort();
+        Ok(approvals)
+    }
+
+    /// Whether the given recovery is this one with the given approvals added in the given block.
+    fn is_continued_by(&self, next: &Self, added: &[u32], now: u32) -> bool {
+        let mut approvals = self.approvals.clone();
+        approvals.extend_from_slice(added);
+        approvals.sort();
+
+        next.owner == self.owner
+            && next.new_owner == self.new_owner
+            && next.guardians == self.guardians
+            && next.threshold == self.threshold
+            && next.delay == self.delay
+            && next.approved_at == self.approved_at_with(&approvals, now)
+            && next.approvals == approvals
+    }
+}
+
+/// All the things that can go wrong while checking social recovery transactions.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum RecoveryError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// The first input must be the recovery, and so must the first output when approving.
+    RecoveryMissing,
+    /// A new or approved recovery is not protected by the open verifier.
+    NotOpen,
+    /// A new recovery must require between one and all of its guardians.
+    MalformedRecovery,
+    /// The transaction does not spend any UTXOs protected by a guardian, or by the owner
+    /// when cancelling.
+    NoCredentials,
+    /// Some UTXO that is spent to approve is not protected by a guardian.
+    NotAGuardian,
+    /// A guardian approves the same recovery twice, or approves one that is approved already.
+    AlreadyApproved,
+    /// The recovery must carry on unchanged, except for the approvals of the guardians who
+    /// spent UTXOs in this transaction, and the block in which it was approved.
+    RecoveryChanged,
+    /// The UTXOs spent to approve or cancel must be returned unchanged, in the same order,
+    /// after the recovery.
+    CredentialsNotReturned,
+    /// Some UTXO that is spent to cancel is not protected by the owner.
+    NotAuthorizedByOwner,
+    /// The delay has passed, so the recovery can no longer be cancelled.
+    RecoveryReady,
+}
+
+impl From<DynamicTypingError> for RecoveryError {
+    fn from(_value: DynamicTypingError) -> Self {
+        RecoveryError::TypeError
+    }
+}
+
+/// Open a recovery, with the approvals of the guardians who open it.
+///
+/// The inputs are UTXOs protected by the opening guardians. The first output is the new
+/// recovery, approved by exactly those guardians, and the rest return the inputs unchanged.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct StartRecovery<T: RecoveryConfig>(pub PhantomData<T>);
+
+impl<T: RecoveryConfig> ConstraintChecker<T::Verifier> for StartRecovery<T> {
+    type Error = RecoveryError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (first, returned) = outputs
+            .split_first()
+            .ok_or(RecoveryError::RecoveryMissing)?;
+        let recovery: Recovery<T> = first.payload.extract()?;
+        ensure!(first.verifier == T::open_verifier(), RecoveryError::NotOpen);
+        ensure!(
+            recovery.threshold > 0 && recovery.threshold as usize <= recovery.guardians.len(),
+            RecoveryError::MalformedRecovery
+        );
+
+        let unapproved = Recovery::<T> {
+            approvals: Vec::new(),
+            approved_at: None,
+            ..recovery.clone()
+        };
+        let added = unapproved.approvals_by(inputs)?;
+        ensure!(
+            unapproved.is_continued_by(&recovery, &added, T::block_number()),
+            RecoveryError::RecoveryChanged
+        );
+        ensure!(returned == inputs, RecoveryError::CredentialsNotReturned);
+
+        Ok(0)
+    }
+}
+
+/// Add the approvals of more guardians to a recovery.
+///
+/// The first input is the recovery, and the rest are UTXOs protected by guardians who have not
+/// approved it yet. The first output is the recovery with their approvals added, and approved
+/// in this block if there are enough of them now. The rest return the other inputs unchanged.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct ApproveRecovery<T: RecoveryConfig>(pub PhantomData<T>);
+
+impl<T: RecoveryConfig> ConstraintChecker<T::Verifier> for ApproveRecovery<T> {
+    type Error = RecoveryError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (recovery, credentials) = inputs.split_first().ok_or(RecoveryError::RecoveryMissing)?;
+        let recovery: Recovery<T> = recovery.payload.extract()?;
+        let (next, returned) = outputs
+            .split_first()
+            .ok_or(RecoveryError::RecoveryMissing)?;
+        ensure!(next.verifier == T::open_verifier(), RecoveryError::NotOpen);
+        let next: Recovery<T> = next.payload.extract()?;
+
+        // Late approvals must not restart the delay.
+        ensure!(
+            recovery.approved_at.is_none(),
+            RecoveryError::AlreadyApproved
+        );
+        let added = recovery.approvals_by(credentials)?;
+        ensure!(
+            recovery.is_continued_by(&next, &added, T::block_number()),
+            RecoveryError::RecoveryChanged
+        );
+        ensure!(
+            returned == credentials,
+            RecoveryError::CredentialsNotReturned
+        );
+
+        Ok(0)
+    }
+}
+
+/// Cancel a recovery before its delay has passed, with the approval of the owner.
+///
+/// The first input is the recovery, and the rest are UTXOs protected by its owner. The outputs
+/// return the other inputs unchanged.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct CancelRecovery<T: RecoveryConfig>(pub PhantomData<T>);
+
+impl<T: RecoveryConfig> ConstraintChecker<T::Verifier> for CancelRecovery<T> {
+    type Error = RecoveryError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (recovery, credentials) = inputs.split_first().ok_or(RecoveryError::RecoveryMissing)?;
+        let recovery: Recovery<T> = recovery.payload.extract()?;
+
+        ensure!(!credentials.is_empty(), RecoveryError::NoCredentials);
+        ensure!(
+            credentials
+                .iter()
+                .all(|credential| credential.verifier == recovery.owner),
+            RecoveryError::NotAuthorizedByOwner
+        );
+        ensure!(
+            !recovery.is_ready(T::block_number()),
+            RecoveryError::RecoveryReady
+        );
+        ensure!(
+            outputs == credentials,
+            RecoveryError::CredentialsNotReturned
+        );
+
+        Ok(0)
+    }
+}
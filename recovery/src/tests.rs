@@ -0,0 +1,163 @@
+//! Unit tests for the social recovery piece
+
+use super::*;
+use money::Coin;
+use std::cell::Cell;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+thread_local! {
+    static BLOCK: Cell<u32> = Cell::new(0);
+}
+
+fn set_block(block: u32) {
+    BLOCK.with(|b| b.set(block));
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct TestConfig;
+impl RecoveryConfig for TestConfig {
+    type Verifier = TestVerifier;
+
+    fn open_verifier() -> TestVerifier {
+        ANYONE
+    }
+
+    fn block_number() -> u32 {
+        BLOCK.with(|b| b.get())
+    }
+}
+
+/// The verifier that protects recoveries, and that the guardian and the new owner use.
+const ANYONE: TestVerifier = TestVerifier { verifies: true };
+const GUARDIAN: TestVerifier = ANYONE;
+/// The verifier of the owner who lost their key.
+const OWNER: TestVerifier = TestVerifier { verifies: false };
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn credential(verifier: TestVerifier) -> Output<TestVerifier> {
+    output(Coin::<0>(1), verifier)
+}
+
+/// A recovery with a delay of 10 blocks by the given guardians, of whom all must approve.
+fn recovery(
+    guardians: Vec<TestVerifier>,
+    approvals: Vec<u32>,
+    approved_at: Option<u32>,
+) -> Recovery<TestConfig> {
+    Recovery {
+        owner: OWNER,
+        new_owner: ANYONE,
+        threshold: guardians.len() as u32,
+        guardians,
+        delay: 10,
+        approvals,
+        approved_at,
+    }
+}
+
+fn check<C: ConstraintChecker<TestVerifier, Error = RecoveryError> + Default>(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, RecoveryError> {
+    C::default().check(inputs, outputs)
+}
+
+#[test]
+fn guardians_start_recoveries_with_their_approval() {
+    set_block(5);
+    let approved = recovery(vec![GUARDIAN], vec![0], Some(5));
+    assert_eq!(
+        check::<StartRecovery<TestConfig>>(
+            &[credential(GUARDIAN)],
+            &[output(approved, ANYONE), credential(GUARDIAN)],
+        ),
+        Ok(0)
+    );
+
+    let backdated = recovery(vec![GUARDIAN], vec![0], Some(0));
+    assert_eq!(
+        check::<StartRecovery<TestConfig>>(
+            &[credential(GUARDIAN)],
+            &[output(backdated, ANYONE), credential(GUARDIAN)],
+        ),
+        Err(RecoveryError::RecoveryChanged)
+    );
+}
+
+#[test]
+fn only_guardians_start_recoveries() {
+    assert_eq!(
+        check::<StartRecovery<TestConfig>>(
+            &[credential(OWNER)],
+            &[
+                output(recovery(vec![GUARDIAN], vec![0], Some(0)), ANYONE),
+                credential(OWNER)
+            ],
+        ),
+        Err(RecoveryError::NotAGuardian)
+    );
+}
+
+#[test]
+fn the_delay_starts_once_enough_guardians_approve() {
+    // The owner is the second of two guardians here, to tell the two verifiers apart.
+    let guardians = vec![GUARDIAN, OWNER];
+    let started = recovery(guardians.clone(), vec![0], None);
+    assert!(!started.is_ready(1_000));
+
+    set_block(20);
+    let approved = recovery(guardians.clone(), vec![0, 1], Some(20));
+    assert_eq!(
+        check::<ApproveRecovery<TestConfig>>(
+            &[output(started, ANYONE), credential(OWNER)],
+            &[output(approved.clone(), ANYONE), credential(OWNER)],
+        ),
+        Ok(0)
+    );
+    assert!(!approved.is_ready(29));
+    assert!(approved.is_ready(30));
+
+    assert_eq!(
+        check::<ApproveRecovery<TestConfig>>(
+            &[output(approved.clone(), ANYONE), credential(OWNER)],
+            &[output(approved, ANYONE), credential(OWNER)],
+        ),
+        Err(RecoveryError::AlreadyApproved)
+    );
+}
+
+#[test]
+fn owners_cancel_until_the_delay_has_passed() {
+    let approved = output(recovery(vec![GUARDIAN], vec![0], Some(20)), ANYONE);
+
+    set_block(29);
+    assert_eq!(
+        check::<CancelRecovery<TestConfig>>(
+            &[approved.clone(), credential(OWNER)],
+            &[credential(OWNER)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<CancelRecovery<TestConfig>>(
+            &[approved.clone(), credential(GUARDIAN)],
+            &[credential(GUARDIAN)],
+        ),
+        Err(RecoveryError::NotAuthorizedByOwner)
+    );
+
+    set_block(30);
+    assert_eq!(
+        check::<CancelRecovery<TestConfig>>(&[approved, credential(OWNER)], &[credential(OWNER)]),
+        Err(RecoveryError::RecoveryReady)
+    );
+}
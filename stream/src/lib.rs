@@ -0,0 +1,307 @@
+//! Payment streams, which pay coins out to a payee a little every block.
+//!
+//! A payer opens a [`Stream`] by locking coins in it, and from its start block on the
+//! stream accrues a fixed rate per block to the payee, until all of the locked coins have
+//! accrued. The payee may withdraw what has accrued at any time, and what has not accrued
+//! stays in the stream. The payer may cancel the stream, which pays the payee what has
+//! accrued and refunds the rest.
+//!
+//! Both parties need to spend the stream, so it is protected by a verifier that anyone can
+//! satisfy, and the parties are recorded in the stream itself. Withdrawals always pay the
+//! payee, so anyone may make them. Cancelling must be authorized by the payer, by also
+//! spending coins that are protected by the payer's verifier.
+//!
+//! The current block is not known to constraint checkers, so the runtime hands it to this
+//! piece through [`StreamConfig::block_number`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    traits::Cash,
+    types::Output,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The configuration of payment streams in one coin.
+pub trait StreamConfig {
+    /// The type of verifiers that can pay and receive streams.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The coin that is streamed
+    type Token: Cash + UtxoData;
+
+    /// The verifier that protects streams, which anyone must be able to satisfy.
+    fn open_verifier() -> Self::Verifier;
+
+    /// The number of the block that transactions are being checked in.
+    fn block_number() -> u32;
+}
+
+/// Coins that accrue to a payee, block by block.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct Stream<T: StreamConfig> {
+    /// The verifier that opened the stream, and gets back what has not accrued when it is
+    /// cancelled
+    pub payer: T::Verifier,
+    /// The verifier that the accrued coins are paid to
+    pub payee: T::Verifier,
+    /// The amount that was locked in the stream
+    pub total: u128,
+    /// The amount that has been withdrawn already
+    pub withdrawn: u128,
+    /// The amount that accrues every block
+    pub rate: u128,
+    /// The first block whose amount accrues
+    pub start: u32,
+}
+
+impl<T: StreamConfig> UtxoData for Stream<T> {
+    const TYPE_ID: [u8; 4] = [b's', b't', b'r', T::Token::ID];
+}
+
+impl<T: StreamConfig> Stream<T> {
+    /// How much of the total has accrued by the end of the given block.
+    pub fn accrued(&self, now: u32) -> u128 {
+        // The start block itself accrues, so a stream that starts now has one block's worth.
+        let blocks = match now.checked_sub(self.start) {
+            Some(blocks) => blocks as u128 + 1,
+            None => return 0,
+        };
+        self.rate.saturating_mul(blocks).min(self.total)
+    }
+
+    /// The amount that the payee may withdraw in the given block.
+    pub fn withdrawable(&self, now: u32) -> u128 {
+        self.accrued(now).saturating_sub(self.withdrawn)
+    }
+
+    /// Whether the given stream carries on with this one once the given amount is withdrawn.
+    fn is_continued_by(&self, next: &Self, withdrawn: u128) -> bool {
+        next.payer == self.payer
+            && next.payee == self.payee
+            && next.total == self.total
+            && next.rate == self.rate
+            && next.start == self.start
+            && Some(next.withdrawn) == self.withdrawn.checked_add(withdrawn)
+    }
+}
+
+/// All the things that can go wrong while checking payment stream transactions.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum StreamError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// Some amount does not fit in a `u128`.
+    AmountOverflow,
+    /// Opening a stream must create exactly one output, which is the stream.
+    WrongOutputCount,
+    /// A new stream is not protected by the open verifier.
+    NotOpen,
+    /// A new stream must accrue something every block, and must not have been withdrawn from.
+    MalformedStream,
+    /// The stream locks more coins than the transaction spends.
+    StreamExceedsCoins,
+    /// The first input must be the stream.
+    StreamMissing,
+    /// The coins paid to the payee are worth more than has accrued.
+    WithdrawalExceedsAccrued,
+    /// Some coins are paid to a verifier other than the payee, or the payer when cancelling.
+    WrongRecipient,
+    /// The stream must carry on, unchanged except for what has been withdrawn, in the last
+    /// output, unless everything in it has been withdrawn.
+    StreamNotContinued,
+    /// The transaction does not spend any coins protected by the payer.
+    NotAuthorizedByPayer,
+    /// The coins refunded to the payer are worth more than has not accrued.
+    RefundExceedsUnaccrued,
+}
+
+impl From<DynamicTypingError> for StreamError {
+    fn from(_value: DynamicTypingError) -> Self {
+        StreamError::TypeError
+    }
+}
+
+/// The total value of the coins of type `C` among the given UTXOs, which must all be such
+/// coins.
+fn coin_total<C: Cash + UtxoData, V>(utxos: &[Output<V>]) -> Result<u128, StreamError> {
+    let mut total = 0u128;
+    for utxo in utxos {
+        let coin: C = utxo.payload.extract()?;
+        total = total
+            .checked_add(coin.value())
+            .ok_or(StreamError::AmountOverflow)?;
+    }
+    Ok(total)
+}
+
+/// Lock coins in a new stream.
+///
+/// All the inputs are coins, and the single output is the stream, which locks at most
+/// their value.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct OpenStream<T: StreamConfig>(pub PhantomData<T>);
+
+impl<T: StreamConfig> ConstraintChecker<T::Verifier> for OpenStream<T> {
+    type Error = StreamError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(outputs.len() == 1, StreamError::WrongOutputCount);
+        let stream: Stream<T> = outputs[0].payload.extract()?;
+        ensure!(
+            outputs[0].verifier == T::open_verifier(),
+            StreamError::NotOpen
+        );
+        ensure!(
+            stream.rate > 0 && stream.withdrawn == 0,
+            StreamError::MalformedStream
+        );
+
+        let locked = coin_total::<T::Token, _>(inputs)?;
+        ensure!(stream.total <= locked, StreamError::StreamExceedsCoins);
+
+        Ok(0)
+    }
+}
+
+/// Withdraw what has accrued in a stream to its payee.
+///
+/// The single input is the stream. The outputs are coins for the payee, worth at most
+/// what has accrued and not been withdrawn yet, followed by the continued stream unless
+/// everything in it has now been withdrawn.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct WithdrawFromStream<T: StreamConfig>(pub PhantomData<T>);
+
+impl<T: StreamConfig> ConstraintChecker<T::Verifier> for WithdrawFromStream<T> {
+    type Error = StreamError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, StreamError::StreamMissing);
+        let stream: Stream<T> = inputs[0].payload.extract()?;
+
+        // The last output may carry on with the stream.
+        let (coins, next) = match outputs.split_last() {
+            Some((last, coins)) => match last.payload.extract::<Stream<T>>() {
+                Ok(next) => (coins, Some((next, &last.verifier))),
+                Err(_) => (outputs, None),
+            },
+            None => (outputs, None),
+        };
+
+        ensure!(
+            coins.iter().all(|coin| coin.verifier == stream.payee),
+            StreamError::WrongRecipient
+        );
+        let withdrawn = coin_total::<T::Token, _>(coins)?;
+        ensure!(
+            withdrawn <= stream.withdrawable(T::block_number()),
+            StreamError::WithdrawalExceedsAccrued
+        );
+
+        match next {
+            Some((next, verifier)) => ensure!(
+                stream.is_continued_by(&next, withdrawn) && *verifier == T::open_verifier(),
+                StreamError::StreamNotContinued
+            ),
+            None => ensure!(
+                stream.withdrawn.checked_add(withdrawn) == Some(stream.total),
+                StreamError::StreamNotContinued
+            ),
+        }
+
+        Ok(0)
+    }
+}
+
+/// Cancel a stream, paying the payee what has accrued and refunding the rest to the payer.
+///
+/// The first input is the stream, and all further inputs are coins protected by the
+/// payer, which authorize the cancellation and are refunded too. Each output is a coin
+/// for either the payee or the payer. The payee's coins are worth at most what has
+/// accrued and not been withdrawn, and the payer's at most the rest.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct CancelStream<T: StreamConfig>(pub PhantomData<T>);
+
+impl<T: StreamConfig> ConstraintChecker<T::Verifier> for CancelStream<T> {
+    type Error = StreamError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), StreamError::StreamMissing);
+        let stream: Stream<T> = inputs[0].payload.extract()?;
+        let authorization = &inputs[1..];
+        ensure!(
+            !authorization.is_empty()
+                && authorization
+                    .iter()
+                    .all(|input| input.verifier == stream.payer),
+            StreamError::NotAuthorizedByPayer
+        );
+
+        let now = T::block_number();
+        let withdrawable = stream.withdrawable(now);
+        let refundable = coin_total::<T::Token, _>(authorization)?
+            .checked_add(stream.total - stream.accrued(now))
+            .ok_or(StreamError::AmountOverflow)?;
+
+        let mut to_payee = 0u128;
+        let mut to_payer = 0u128;
+        for output in outputs {
+            let coin: T::Token = output.payload.extract()?;
+            let received = if output.verifier == stream.payee {
+                &mut to_payee
+            } else if output.verifier == stream.payer {
+                &mut to_payer
+            } else {
+                return Err(StreamError::WrongRecipient);
+            };
+            *received = received
+                .checked_add(coin.value())
+                .ok_or(StreamError::AmountOverflow)?;
+        }
+
+        if stream.payee == stream.payer {
+            // A payer who streams to themselves gets everything back.
+            let everything = withdrawable
+                .checked_add(refundable)
+                .ok_or(StreamError::AmountOverflow)?;
+            ensure!(to_payee <= everything, StreamError::RefundExceedsUnaccrued);
+        } else {
+            ensure!(
+                to_payee <= withdrawable,
+                StreamError::WithdrawalExceedsAccrued
+            );
+            ensure!(to_payer <= refundable, StreamError::RefundExceedsUnaccrued);
+        }
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,186 @@
+//! Unit tests for the payment stream piece
+
+use super::*;
+use money::Coin;
+use std::cell::Cell;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+thread_local! {
+    static BLOCK: Cell<u32> = Cell::new(0);
+}
+
+fn set_block(block: u32) {
+    BLOCK.with(|b| b.set(block));
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct TestConfig;
+impl StreamConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type Token = Coin<0>;
+
+    fn open_verifier() -> TestVerifier {
+        ANYONE
+    }
+
+    fn block_number() -> u32 {
+        BLOCK.with(|b| b.get())
+    }
+}
+
+/// The verifier that protects streams, and that the payee uses.
+const ANYONE: TestVerifier = TestVerifier { verifies: true };
+/// The verifier of the payer.
+const PAYER: TestVerifier = TestVerifier { verifies: false };
+const PAYEE: TestVerifier = ANYONE;
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn coin(amount: u128, verifier: TestVerifier) -> Output<TestVerifier> {
+    output(Coin::<0>(amount), verifier)
+}
+
+/// A stream of 10 per block from block 100, of which `withdrawn` has been withdrawn.
+fn stream(total: u128, withdrawn: u128) -> Output<TestVerifier> {
+    output(
+        Stream::<TestConfig> {
+            payer: PAYER,
+            payee: PAYEE,
+            total,
+            withdrawn,
+            rate: 10,
+            start: 100,
+        },
+        ANYONE,
+    )
+}
+
+fn check<C: ConstraintChecker<TestVerifier, Error = StreamError> + Default>(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, StreamError> {
+    C::default().check(inputs, outputs)
+}
+
+#[test]
+fn opening_a_stream_works() {
+    assert_eq!(
+        check::<OpenStream<TestConfig>>(&[coin(600, PAYER), coin(400, PAYER)], &[stream(1_000, 0)]),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<OpenStream<TestConfig>>(&[coin(999, PAYER)], &[stream(1_000, 0)]),
+        Err(StreamError::StreamExceedsCoins)
+    );
+    assert_eq!(
+        check::<OpenStream<TestConfig>>(&[coin(1_000, PAYER)], &[stream(1_000, 1)]),
+        Err(StreamError::MalformedStream)
+    );
+}
+
+#[test]
+fn streams_accrue_per_block() {
+    let stream: Stream<TestConfig> = stream(1_000, 0).payload.extract().unwrap();
+    assert_eq!(stream.accrued(99), 0);
+    assert_eq!(stream.accrued(100), 10);
+    assert_eq!(stream.accrued(149), 500);
+    assert_eq!(stream.accrued(10_000), 1_000);
+}
+
+#[test]
+fn payees_withdraw_what_has_accrued() {
+    set_block(149);
+    assert_eq!(
+        check::<WithdrawFromStream<TestConfig>>(
+            &[stream(1_000, 200)],
+            &[coin(300, PAYEE), stream(1_000, 500)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<WithdrawFromStream<TestConfig>>(
+            &[stream(1_000, 200)],
+            &[coin(301, PAYEE), stream(1_000, 501)],
+        ),
+        Err(StreamError::WithdrawalExceedsAccrued)
+    );
+    assert_eq!(
+        check::<WithdrawFromStream<TestConfig>>(
+            &[stream(1_000, 200)],
+            &[coin(300, PAYER), stream(1_000, 500)],
+        ),
+        Err(StreamError::WrongRecipient)
+    );
+}
+
+#[test]
+fn streams_carry_on_until_they_are_drained() {
+    set_block(149);
+    assert_eq!(
+        check::<WithdrawFromStream<TestConfig>>(&[stream(1_000, 200)], &[coin(300, PAYEE)]),
+        Err(StreamError::StreamNotContinued)
+    );
+    assert_eq!(
+        check::<WithdrawFromStream<TestConfig>>(
+            &[stream(1_000, 200)],
+            &[coin(300, PAYEE), stream(1_000, 400)],
+        ),
+        Err(StreamError::StreamNotContinued)
+    );
+
+    set_block(1_000);
+    assert_eq!(
+        check::<WithdrawFromStream<TestConfig>>(&[stream(1_000, 200)], &[coin(800, PAYEE)]),
+        Ok(0)
+    );
+}
+
+#[test]
+fn payers_cancel_and_take_back_the_rest() {
+    set_block(149);
+    assert_eq!(
+        check::<CancelStream<TestConfig>>(
+            &[stream(1_000, 200), coin(5, PAYER)],
+            &[coin(300, PAYEE), coin(505, PAYER)],
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check::<CancelStream<TestConfig>>(
+            &[stream(1_000, 200), coin(5, PAYER)],
+            &[coin(300, PAYEE), coin(506, PAYER)],
+        ),
+        Err(StreamError::RefundExceedsUnaccrued)
+    );
+    assert_eq!(
+        check::<CancelStream<TestConfig>>(
+            &[stream(1_000, 200), coin(5, PAYER)],
+            &[coin(301, PAYEE), coin(504, PAYER)],
+        ),
+        Err(StreamError::WithdrawalExceedsAccrued)
+    );
+}
+
+#[test]
+fn only_the_payer_can_cancel() {
+    set_block(149);
+    assert_eq!(
+        check::<CancelStream<TestConfig>>(&[stream(1_000, 200)], &[coin(300, PAYEE)]),
+        Err(StreamError::NotAuthorizedByPayer)
+    );
+    assert_eq!(
+        check::<CancelStream<TestConfig>>(
+            &[stream(1_000, 200), coin(5, PAYEE)],
+            &[coin(300, PAYEE)],
+        ),
+        Err(StreamError::NotAuthorizedByPayer)
+    );
+}
@@ -0,0 +1,115 @@
+//! The time at which each block was authored.
+//!
+//! The current time lives in a single [`Timestamp`] UTXO. Every block, the block author
+//! includes an inherent transaction that consumes the previous block's timestamp and
+//! creates a new one. The runtime is responsible for building that inherent and making
+//! sure it appears in every block. This piece only checks that time moves forward.
+//!
+//! Other pieces, such as those with expiring orders or auctions, can read the latest
+//! timestamp with [`current`]. It is recorded in storage as a side effect of constraint
+//! checking, in the same way that the runtime upgrade piece writes the new code.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, DynamicallyTypedData, UtxoData},
+    ensure, SimpleConstraintChecker,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The storage key under which the latest timestamp is recorded.
+pub const CURRENT_TIMESTAMP_KEY: &[u8] = b"current_timestamp";
+
+/// The time at which a block was authored.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, Hash, Debug, Default, TypeInfo)]
+pub struct Timestamp {
+    /// Milliseconds since the unix epoch
+    pub time: u64,
+    /// The number of the block this timestamp was set in
+    pub block: u32,
+}
+
+impl UtxoData for Timestamp {
+    const TYPE_ID: [u8; 4] = *b"time";
+}
+
+/// The most recently set timestamp, if any has been set yet.
+pub fn current() -> Option<Timestamp> {
+    sp_io::storage::get(CURRENT_TIMESTAMP_KEY)
+        .and_then(|encoded| Timestamp::decode(&mut &encoded[..]).ok())
+}
+
+/// Record the given timestamp as the most recent one.
+fn set_current(timestamp: &Timestamp) {
+    sp_io::storage::set(CURRENT_TIMESTAMP_KEY, &timestamp.encode());
+}
+
+/// Replace the previous block's timestamp with a new one.
+///
+/// The only input is the previous timestamp and the only output is the new one.
+/// Both the time and the block number must increase.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub struct SetTimestamp;
+
+/// Errors that can occur when setting the timestamp.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum TimestampError {
+    /// Dynamic typing issue.
+    /// This error doesn't discriminate between badly typed inputs and outputs.
+    BadlyTyped,
+    /// Exactly one input, the previous timestamp, must be supplied.
+    MissingPreviousTimestamp,
+    /// Exactly one output, the new timestamp, must be supplied.
+    MissingNewTimestamp,
+    /// The new timestamp is not later than the previous one.
+    TimeNotIncreasing,
+    /// The new timestamp is not for a later block than the previous one.
+    BlockNotIncreasing,
+}
+
+impl From<DynamicTypingError> for TimestampError {
+    fn from(_value: DynamicTypingError) -> Self {
+        Self::BadlyTyped
+    }
+}
+
+impl SimpleConstraintChecker for SetTimestamp {
+    type Error = TimestampError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.len() == 1,
+            TimestampError::MissingPreviousTimestamp
+        );
+        ensure!(output_data.len() == 1, TimestampError::MissingNewTimestamp);
+
+        let previous: Timestamp = input_data[0].extract()?;
+        let new: Timestamp = output_data[0].extract()?;
+
+        ensure!(new.time > previous.time, TimestampError::TimeNotIncreasing);
+        ensure!(
+            new.block > previous.block,
+            TimestampError::BlockNotIncreasing
+        );
+
+        // SIDE EFFECT: Record the new time so other pieces can read it
+        set_current(&new);
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,75 @@
+//! Unit tests for the Timestamp piece
+
+use super::*;
+use sp_io::TestExternalities;
+
+fn at(time: u64, block: u32) -> DynamicallyTypedData {
+    Timestamp { time, block }.into()
+}
+
+#[test]
+fn setting_timestamp_works() {
+    TestExternalities::default().execute_with(|| {
+        let result = SetTimestamp.check(&[at(1000, 1)], &[at(4000, 2)]);
+        assert_eq!(result, Ok(0));
+        assert_eq!(
+            current(),
+            Some(Timestamp {
+                time: 4000,
+                block: 2
+            })
+        );
+    });
+}
+
+#[test]
+fn nothing_is_current_before_first_set() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(current(), None);
+    });
+}
+
+#[test]
+fn setting_timestamp_without_previous_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = SetTimestamp.check(&[], &[at(4000, 2)]);
+        assert_eq!(result, Err(TimestampError::MissingPreviousTimestamp));
+    });
+}
+
+#[test]
+fn setting_two_timestamps_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = SetTimestamp.check(&[at(1000, 1)], &[at(4000, 2), at(5000, 3)]);
+        assert_eq!(result, Err(TimestampError::MissingNewTimestamp));
+    });
+}
+
+#[test]
+fn going_back_in_time_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = SetTimestamp.check(&[at(4000, 1)], &[at(4000, 2)]);
+        assert_eq!(result, Err(TimestampError::TimeNotIncreasing));
+        assert_eq!(current(), None);
+    });
+}
+
+#[test]
+fn setting_timestamp_twice_in_one_block_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = SetTimestamp.check(&[at(1000, 2)], &[at(4000, 2)]);
+        assert_eq!(result, Err(TimestampError::BlockNotIncreasing));
+    });
+}
+
+#[test]
+fn setting_badly_typed_timestamp_fails() {
+    TestExternalities::default().execute_with(|| {
+        let bogus = DynamicallyTypedData {
+            data: vec![],
+            type_id: *b"bogs",
+        };
+        let result = SetTimestamp.check(&[at(1000, 1)], &[bogus]);
+        assert_eq!(result, Err(TimestampError::BadlyTyped));
+    });
+}
@@ -0,0 +1,227 @@
+//! Derive macros that generate the boilerplate for aggregating several Tuxedo pieces
+//! into the outer enums of a runtime.
+//!
+//! A runtime that combines the dex with the money, proof of existence, and kitties pieces
+//! needs outer enums that wrap each piece's types and forward to them. Writing the `From`
+//! impls and the dispatching `match` statements by hand is tedious and error prone, so
+//! these derives write them for you.
+//!
+//! * [`TuxedoVerifier`] implements `tuxedo_core::Verifier` for an enum of verifiers.
+//! * [`TuxedoData`] turns an enum of `UtxoData` types into a typed view of
+//!   `DynamicallyTypedData`, routing by each type's `TYPE_ID`.
+//!
+//! Both derives expect every variant to have exactly one unnamed field.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, Ident, LitStr, Type};
+
+/// The name and inner type of each variant of the enum being derived on.
+fn single_field_variants(input: &DeriveInput) -> syn::Result<Vec<(Ident, Type)>> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "Tuxedo aggregation can only be derived for enums",
+        ));
+    };
+
+    data.variants
+        .iter()
+        .map(|variant| match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                Ok((variant.ident.clone(), fields.unnamed[0].ty.clone()))
+            }
+            _ => Err(syn::Error::new(
+                variant.span(),
+                "each variant must have exactly one unnamed field",
+            )),
+        })
+        .collect()
+}
+
+/// `From` impls for wrapping each inner type in its variant of the outer enum.
+fn from_inner_impls(input: &DeriveInput, variants: &[(Ident, Type)]) -> TokenStream2 {
+    let outer = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    variants
+        .iter()
+        .map(|(variant, inner)| {
+            quote! {
+                impl #impl_generics From<#inner> for #outer #ty_generics #where_clause {
+                    fn from(inner: #inner) -> Self {
+                        Self::#variant(inner)
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// Implement `tuxedo_core::Verifier` for an enum whose variants each wrap a verifier.
+///
+/// Verification is forwarded to whichever verifier the enum holds. A `From` impl is also
+/// generated for each inner verifier.
+#[proc_macro_derive(TuxedoVerifier)]
+pub fn derive_tuxedo_verifier(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let variants = match single_field_variants(&input) {
+        Ok(variants) => variants,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let outer = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let from_impls = from_inner_impls(&input, &variants);
+    let variant_names = variants.iter().map(|(variant, _)| variant);
+
+    quote! {
+        #from_impls
+
+        impl #impl_generics tuxedo_core::Verifier for #outer #ty_generics #where_clause {
+            fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+                match self {
+                    #( Self::#variant_names(inner) => tuxedo_core::Verifier::verify(inner, simplified_tx, redeemer), )*
+                }
+            }
+        }
+    }
+    .into()
+}
+
+/// Turn an enum whose variants each wrap a `UtxoData` type into a typed view of
+/// `tuxedo_core::dynamic_typing::DynamicallyTypedData`.
+///
+/// The generated code provides
+/// * a `type_id` method returning the `TYPE_ID` of the held type,
+/// * a `TryFrom<&DynamicallyTypedData>` impl that picks the variant by `TYPE_ID`,
+/// * a `From` impl back into `DynamicallyTypedData`, and
+/// * a `From` impl for each inner type.
+///
+/// Each inner type must have a distinct `TYPE_ID`, or data of the later type could never
+/// be decoded. Type ids are often derived from the ids of a piece's tokens, so two
+/// instances of a piece, or two pieces, can collide without anyone noticing. The derive
+/// checks this at compile time, and fails with an error naming both variants if two of
+/// them share a `TYPE_ID`. For generic enums the check happens when the enum is used
+/// with concrete types.
+///
+/// ```compile_fail
+/// use parity_scale_codec::{Decode, Encode};
+/// use tuxedo_core::dynamic_typing::UtxoData;
+/// use tuxedo_derive::TuxedoData;
+///
+/// #[derive(Encode, Decode)]
+/// struct Bill(u128);
+/// impl UtxoData for Bill {
+///     const TYPE_ID: [u8; 4] = *b"bill";
+/// }
+///
+/// #[derive(Encode, Decode)]
+/// struct Invoice(u128);
+/// impl UtxoData for Invoice {
+///     const TYPE_ID: [u8; 4] = *b"bill";
+/// }
+///
+/// #[derive(TuxedoData)]
+/// enum OuterData {
+///     Bill(Bill),
+///     Invoice(Invoice),
+/// }
+/// ```
+#[proc_macro_derive(TuxedoData)]
+pub fn derive_tuxedo_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let variants = match single_field_variants(&input) {
+        Ok(variants) => variants,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let outer = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let from_impls = from_inner_impls(&input, &variants);
+    let variant_names: Vec<_> = variants.iter().map(|(variant, _)| variant).collect();
+    let inner_types: Vec<_> = variants.iter().map(|(_, inner)| inner).collect();
+    let distinct_type_ids = distinct_type_ids_assertions(outer, &variants);
+
+    // Generic enums can only be checked once their parameters are known, which is
+    // when their methods are used. Others are checked right away.
+    let eager_check = input.generics.params.is_empty().then(|| {
+        quote! {
+            const _: () = #outer::DISTINCT_TYPE_IDS;
+        }
+    });
+
+    quote! {
+        #from_impls
+
+        impl #impl_generics #outer #ty_generics #where_clause {
+            /// Fails to evaluate if two variants hold types with the same `TYPE_ID`.
+            const DISTINCT_TYPE_IDS: () = {
+                #distinct_type_ids
+            };
+
+            /// The `TYPE_ID` of the data held in this enum.
+            pub fn type_id(&self) -> [u8; 4] {
+                let () = Self::DISTINCT_TYPE_IDS;
+                match self {
+                    #( Self::#variant_names(_) => <#inner_types as tuxedo_core::dynamic_typing::UtxoData>::TYPE_ID, )*
+                }
+            }
+        }
+
+        impl #impl_generics TryFrom<&tuxedo_core::dynamic_typing::DynamicallyTypedData> for #outer #ty_generics #where_clause {
+            type Error = tuxedo_core::dynamic_typing::DynamicTypingError;
+
+            fn try_from(data: &tuxedo_core::dynamic_typing::DynamicallyTypedData) -> Result<Self, Self::Error> {
+                let () = Self::DISTINCT_TYPE_IDS;
+                #(
+                    if data.type_id == <#inner_types as tuxedo_core::dynamic_typing::UtxoData>::TYPE_ID {
+                        return data.extract::<#inner_types>().map(Self::#variant_names);
+                    }
+                )*
+                Err(tuxedo_core::dynamic_typing::DynamicTypingError::WrongType)
+            }
+        }
+
+        impl #impl_generics From<#outer #ty_generics> for tuxedo_core::dynamic_typing::DynamicallyTypedData #where_clause {
+            fn from(outer: #outer #ty_generics) -> Self {
+                match outer {
+                    #( #outer::#variant_names(inner) => inner.into(), )*
+                }
+            }
+        }
+
+        #eager_check
+    }
+    .into()
+}
+
+/// Const assertions that no two of the given variants hold types with the same `TYPE_ID`.
+fn distinct_type_ids_assertions(outer: &Ident, variants: &[(Ident, Type)]) -> TokenStream2 {
+    let mut assertions = quote! {
+        const fn same(a: [u8; 4], b: [u8; 4]) -> bool {
+            a[0] == b[0] && a[1] == b[1] && a[2] == b[2] && a[3] == b[3]
+        }
+    };
+
+    for (index, (first, first_inner)) in variants.iter().enumerate() {
+        for (second, second_inner) in &variants[index + 1..] {
+            let message = LitStr::new(
+                &format!("the `{first}` and `{second}` variants of `{outer}` hold data with the same TYPE_ID"),
+                second.span(),
+            );
+            assertions.extend(quote! {
+                assert!(
+                    !same(
+                        <#first_inner as tuxedo_core::dynamic_typing::UtxoData>::TYPE_ID,
+                        <#second_inner as tuxedo_core::dynamic_typing::UtxoData>::TYPE_ID,
+                    ),
+                    #message
+                );
+            });
+        }
+    }
+
+    assertions
+}
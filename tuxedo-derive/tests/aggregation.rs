@@ -0,0 +1,79 @@
+use parity_scale_codec::{Decode, Encode};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, DynamicallyTypedData, UtxoData},
+    verifier::{TestVerifier, UpForGrabs},
+    Verifier,
+};
+use tuxedo_derive::{TuxedoData, TuxedoVerifier};
+
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TuxedoVerifier)]
+enum OuterVerifier {
+    Test(TestVerifier),
+    UpForGrabs(UpForGrabs),
+}
+
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+struct Bill(u128);
+
+impl UtxoData for Bill {
+    const TYPE_ID: [u8; 4] = *b"bill";
+}
+
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+struct Deed {
+    lot: u32,
+}
+
+impl UtxoData for Deed {
+    const TYPE_ID: [u8; 4] = *b"deed";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, TuxedoData)]
+enum OuterData {
+    Bill(Bill),
+    Deed(Deed),
+}
+
+#[test]
+fn verifier_dispatches_to_inner_verifier() {
+    let failing: OuterVerifier = TestVerifier { verifies: false }.into();
+    let passing: OuterVerifier = UpForGrabs.into();
+
+    assert!(!failing.verify(&[], &[]));
+    assert!(passing.verify(&[], &[]));
+}
+
+#[test]
+fn data_routes_by_type_id() {
+    let bill: DynamicallyTypedData = Bill(7).into();
+    let deed: DynamicallyTypedData = Deed { lot: 3 }.into();
+
+    assert_eq!(OuterData::try_from(&bill), Ok(OuterData::Bill(Bill(7))));
+    assert_eq!(
+        OuterData::try_from(&deed),
+        Ok(OuterData::Deed(Deed { lot: 3 }))
+    );
+}
+
+#[test]
+fn unknown_type_id_is_rejected() {
+    let unknown = DynamicallyTypedData {
+        data: vec![],
+        type_id: *b"what",
+    };
+
+    assert_eq!(
+        OuterData::try_from(&unknown),
+        Err(DynamicTypingError::WrongType)
+    );
+}
+
+#[test]
+fn data_round_trips() {
+    let original: OuterData = Deed { lot: 9 }.into();
+    assert_eq!(original.type_id(), Deed::TYPE_ID);
+
+    let dynamic: DynamicallyTypedData = original.clone().into();
+    assert_eq!(dynamic.type_id, Deed::TYPE_ID);
+    assert_eq!(OuterData::try_from(&dynamic), Ok(original));
+}
@@ -0,0 +1,152 @@
+//! A canonical order for the dex orders that a transaction spends.
+//!
+//! A set of orders can be matched in any order. Each order gives a different transaction
+//! with a different hash, even though they all do the same thing. Matchers racing for the
+//! same orders fill the pool with such permutations. Worse, anyone who sees a match can
+//! reorder orders that are up for grabs to get a copy of it under another hash.
+//!
+//! So the runtime only accepts transactions that spend their orders in ascending order of
+//! their output refs: by transaction hash, then by index. Then matches of the same orders
+//! with the same outputs have the same hash, and the pool keeps just one of them.
+//!
+//! The rule only applies to the orders among the inputs, wherever they are. The checkers
+//! already fix where the other inputs go, like the credentials and the rebate pool of a
+//! match. Orders only share a payout in `MatchOrders` if they are next to each other in
+//! the canonical order.
+
+use crate::{OuterData, Output};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_std::prelude::*;
+use tuxedo_core::types::{Input, OutputRef};
+
+/// All the ways a transaction can spend its orders out of the canonical order.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum CanonicalOrderError {
+    /// The order spent by the input at this position does not come after the one before it.
+    OrderOutOfPlace { position: u32 },
+}
+
+/// The key that orders are sorted by.
+pub fn sort_key(output_ref: &OutputRef) -> (H256, u32) {
+    (output_ref.tx_hash, output_ref.index)
+}
+
+/// Sort order refs into the canonical order.
+///
+/// Clients that build matches sort their orders with this before lining up the payouts.
+pub fn sort(order_refs: &mut [OutputRef]) {
+    order_refs.sort_by_key(sort_key);
+}
+
+/// Whether a UTXO holds a plain dex order of any pair.
+pub fn is_order(output: &Output) -> bool {
+    matches!(
+        OuterData::try_from(&output.payload),
+        Ok(OuterData::Order01(_)
+            | OuterData::Order10(_)
+            | OuterData::AssetOrderAB(_)
+            | OuterData::AssetOrderBA(_))
+    )
+}
+
+/// Check that the inputs that spend orders come in strictly ascending order of their refs.
+/// `spent` must be the UTXOs that the inputs spend.
+pub fn check(inputs: &[Input], spent: &[Output]) -> Result<(), CanonicalOrderError> {
+    let mut previous = None;
+    for (position, (input, utxo)) in inputs.iter().zip(spent).enumerate() {
+        if !is_order(utxo) {
+            continue;
+        }
+        let key = sort_key(&input.output_ref);
+        if previous.map_or(false, |previous| key <= previous) {
+            return Err(CanonicalOrderError::OrderOutOfPlace {
+                position: position as u32,
+            });
+        }
+        previous = Some(key);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DexConfig01, OuterVerifier};
+    use tuxedo_core::verifier::UpForGrabs;
+
+    fn order_ref(tx_byte: u8, index: u32) -> OutputRef {
+        OutputRef {
+            tx_hash: H256::repeat_byte(tx_byte),
+            index,
+        }
+    }
+
+    fn inputs(refs: &[&OutputRef]) -> Vec<Input> {
+        refs.iter()
+            .map(|output_ref| Input {
+                output_ref: (*output_ref).clone(),
+                redeemer: Vec::new(),
+            })
+            .collect()
+    }
+
+    fn order() -> Output {
+        Output {
+            payload: dex::Order::<DexConfig01>::new(10, 5, OuterVerifier::UpForGrabs(UpForGrabs))
+                .into(),
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+        }
+    }
+
+    fn coin() -> Output {
+        Output {
+            payload: money::Coin::<0>(1).into(),
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+        }
+    }
+
+    #[test]
+    fn orders_must_be_spent_in_ascending_order() {
+        let (a, b, c) = (order_ref(1, 0), order_ref(1, 1), order_ref(2, 0));
+        let spent = vec![order(), order(), order()];
+
+        assert_eq!(check(&inputs(&[&a, &b, &c]), &spent), Ok(()));
+        assert_eq!(
+            check(&inputs(&[&a, &c, &b]), &spent),
+            Err(CanonicalOrderError::OrderOutOfPlace { position: 2 })
+        );
+        assert_eq!(
+            check(&inputs(&[&a, &a, &c]), &spent),
+            Err(CanonicalOrderError::OrderOutOfPlace { position: 1 })
+        );
+    }
+
+    #[test]
+    fn other_inputs_may_go_anywhere() {
+        let (a, b, c) = (order_ref(1, 0), order_ref(2, 0), order_ref(0, 0));
+        assert_eq!(
+            check(&inputs(&[&a, &b, &c]), &[order(), order(), coin()]),
+            Ok(())
+        );
+        assert_eq!(
+            check(&inputs(&[&c, &b, &a]), &[coin(), coin(), order()]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn sorting_makes_any_order_canonical() {
+        let mut refs = vec![order_ref(2, 0), order_ref(1, 1), order_ref(1, 0)];
+        sort(&mut refs);
+        let input_refs: Vec<&OutputRef> = refs.iter().collect();
+        assert_eq!(
+            check(&inputs(&input_refs), &[order(), order(), order()]),
+            Ok(())
+        );
+    }
+}
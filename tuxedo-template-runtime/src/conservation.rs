@@ -0,0 +1,210 @@
+//! A runtime-level safety net ensuring that transactions do not create value out of thin air.
+//!
+//! Each piece is responsible for checking its own transactions. But a single buggy piece could
+//! otherwise mint coins of any `Cash` type in the runtime. This check is independent of all
+//! pieces and runs on every transaction. It makes sure that, for each `Cash` type, the total
+//! value consumed by the transaction is at least the total value it creates.
+//!
+//! Some UTXOs are not coins themselves but hold coins, like the collateral in an open dex order.
+//! These count towards the total of the `Cash` type they hold. A few, like the dex's rebate
+//! pool, hold coins of several `Cash` types, and count towards each of them.
+//!
+//! Pieces that intentionally create value, such as the money piece's `Mint`, opt out of this
+//! check for the `Cash` types they create, and only those. See
+//! `OuterConstraintChecker::may_mint` in the runtime.
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::collections::btree_map::BTreeMap;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, types::Output};
+
+/// All the things that can go wrong while checking value conservation.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum ConservationError {
+    /// The total value of some `Cash` type in the transaction does not fit in a `u128`.
+    ValueOverflow,
+    /// The transaction creates more value of the given `Cash` type than it consumes.
+    ValueCreated { type_id: [u8; 4] },
+}
+
+/// Sum the values of all the `Cash` payloads, grouped by `Cash` type id.
+///
+/// `value_of` should return the type id and value of each `Cash` that the payload is or holds,
+/// and nothing if the payload has nothing to do with `Cash`. An `Option` will do for payloads
+/// that hold at most one `Cash` type.
+fn totals_by_type<'a, V: 'a, I: IntoIterator<Item = ([u8; 4], u128)>>(
+    utxos: impl IntoIterator<Item = &'a Output<V>>,
+    value_of: &impl Fn(&DynamicallyTypedData) -> I,
+) -> Result<BTreeMap<[u8; 4], u128>, ConservationError> {
+    let mut totals = BTreeMap::new();
+    for utxo in utxos {
+        for (type_id, value) in value_of(&utxo.payload) {
+            let total: &mut u128 = totals.entry(type_id).or_default();
+            *total = total
+                .checked_add(value)
+                .ok_or(ConservationError::ValueOverflow)?;
+        }
+    }
+    Ok(totals)
+}
+
+/// Ensure that, for every `Cash` type except the `mintable` ones, the outputs are worth no
+/// more than the inputs.
+pub fn check<V, I: IntoIterator<Item = ([u8; 4], u128)>>(
+    inputs: &[Output<V>],
+    outputs: &[Output<V>],
+    mintable: &[[u8; 4]],
+    value_of: impl Fn(&DynamicallyTypedData) -> I,
+) -> Result<(), ConservationError> {
+    let input_totals = totals_by_type(inputs, &value_of)?;
+    let output_totals = totals_by_type(outputs, &value_of)?;
+
+    for (type_id, output_total) in output_totals {
+        if mintable.contains(&type_id) {
+            continue;
+        }
+        let input_total = input_totals.get(&type_id).copied().unwrap_or_default();
+        if output_total > input_total {
+            return Err(ConservationError::ValueCreated { type_id });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use money::Coin;
+    use tuxedo_core::{dynamic_typing::UtxoData, traits::Cash, verifier::TestVerifier};
+
+    fn value_of(payload: &DynamicallyTypedData) -> Option<([u8; 4], u128)> {
+        payload
+            .extract::<Coin<0>>()
+            .map(|c| (Coin::<0>::TYPE_ID, c.value()))
+            .or_else(|_| {
+                payload
+                    .extract::<Coin<1>>()
+                    .map(|c| (Coin::<1>::TYPE_ID, c.value()))
+            })
+            .ok()
+    }
+
+    fn output_from<T: Into<DynamicallyTypedData>>(payload: T) -> Output<TestVerifier> {
+        Output {
+            payload: payload.into(),
+            verifier: TestVerifier { verifies: true },
+        }
+    }
+
+    #[test]
+    fn spending_less_than_inputs_works() {
+        let inputs = vec![output_from(Coin::<0>(10)), output_from(Coin::<0>(5))];
+        let outputs = vec![output_from(Coin::<0>(12))];
+
+        assert_eq!(check(&inputs, &outputs, &[], value_of), Ok(()));
+    }
+
+    #[test]
+    fn spending_exactly_inputs_works() {
+        let inputs = vec![output_from(Coin::<0>(10)), output_from(Coin::<1>(5))];
+        let outputs = vec![output_from(Coin::<1>(5)), output_from(Coin::<0>(10))];
+
+        assert_eq!(check(&inputs, &outputs, &[], value_of), Ok(()));
+    }
+
+    #[test]
+    fn creating_value_fails() {
+        let inputs = vec![output_from(Coin::<0>(10))];
+        let outputs = vec![output_from(Coin::<0>(11))];
+
+        assert_eq!(
+            check(&inputs, &outputs, &[], value_of),
+            Err(ConservationError::ValueCreated {
+                type_id: <Coin<0> as UtxoData>::TYPE_ID
+            })
+        );
+    }
+
+    #[test]
+    fn value_of_one_token_cannot_pay_for_another() {
+        let inputs = vec![output_from(Coin::<0>(10))];
+        let outputs = vec![output_from(Coin::<1>(10))];
+
+        assert_eq!(
+            check(&inputs, &outputs, &[], value_of),
+            Err(ConservationError::ValueCreated {
+                type_id: <Coin<1> as UtxoData>::TYPE_ID
+            })
+        );
+    }
+
+    #[test]
+    fn overflowing_values_fail() {
+        let inputs = vec![output_from(Coin::<0>(u128::MAX))];
+        let outputs = vec![output_from(Coin::<0>(u128::MAX)), output_from(Coin::<0>(1))];
+
+        assert_eq!(
+            check(&inputs, &outputs, &[], value_of),
+            Err(ConservationError::ValueOverflow)
+        );
+    }
+
+    #[test]
+    fn non_cash_outputs_are_ignored() {
+        let inputs = vec![];
+        let outputs = vec![Output {
+            payload: DynamicallyTypedData {
+                data: vec![1, 2, 3],
+                type_id: *b"nope",
+            },
+            verifier: TestVerifier { verifies: true },
+        }];
+
+        assert_eq!(check(&inputs, &outputs, &[], value_of), Ok(()));
+    }
+
+    #[test]
+    fn payloads_holding_several_types_count_towards_each() {
+        // Pretend that a coin of token 0 also holds as much of token 1.
+        let values_of = |payload: &DynamicallyTypedData| match payload.extract::<Coin<0>>() {
+            Ok(coin) => vec![
+                (Coin::<0>::TYPE_ID, coin.value()),
+                (Coin::<1>::TYPE_ID, coin.value()),
+            ],
+            Err(_) => value_of(payload).into_iter().collect(),
+        };
+        let inputs = vec![output_from(Coin::<0>(10))];
+
+        let outputs = vec![output_from(Coin::<0>(4)), output_from(Coin::<1>(6))];
+        assert_eq!(check(&inputs, &outputs, &[], values_of), Ok(()));
+
+        let outputs = vec![output_from(Coin::<0>(4)), output_from(Coin::<1>(7))];
+        assert_eq!(
+            check(&inputs, &outputs, &[], values_of),
+            Err(ConservationError::ValueCreated {
+                type_id: <Coin<1> as UtxoData>::TYPE_ID
+            })
+        );
+    }
+
+    #[test]
+    fn only_the_mintable_types_may_be_created() {
+        let inputs = vec![output_from(Coin::<0>(10))];
+        let outputs = vec![output_from(Coin::<0>(10)), output_from(Coin::<1>(5))];
+        let mintable = [<Coin<1> as UtxoData>::TYPE_ID];
+
+        assert_eq!(check(&inputs, &outputs, &mintable, value_of), Ok(()));
+
+        let outputs = vec![output_from(Coin::<0>(11)), output_from(Coin::<1>(5))];
+        assert_eq!(
+            check(&inputs, &outputs, &mintable, value_of),
+            Err(ConservationError::ValueCreated {
+                type_id: <Coin<0> as UtxoData>::TYPE_ID
+            })
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! A description of the dex types, for tools that build transactions generically.
+//!
+//! Tuxedo does not yet produce FRAME metadata, so tools like polkadot-js and subxt cannot
+//! learn the shape of a `MakeOrder` transaction from the chain, and every wallet has to
+//! hand-write the codecs. Instead, the runtime describes its transaction type and the
+//! payloads of the dex UTXOs in a `scale-info` type registry, served by [`DexMetadataApi`].
+//!
+//! UTXO payloads are stored as opaque bytes tagged with a `TYPE_ID`, so the registry alone
+//! does not say which type a payload holds. [`DexMetadata::payloads`] maps each `TYPE_ID`
+//! to its type in the registry.
+
+use crate::{DexConfig01, KittyDexConfig, Transaction};
+use parity_scale_codec::Encode;
+use scale_info::{meta_type, PortableRegistry, Registry, TypeInfo};
+use sp_std::prelude::*;
+use tuxedo_core::dynamic_typing::UtxoData;
+
+/// The types a client needs to build dex transactions in this runtime.
+#[derive(Encode)]
+pub struct DexMetadata {
+    /// The registry every other field refers into
+    pub types: PortableRegistry,
+    /// The type of the runtime's transactions, whose checker and verifier types cover
+    /// every dex checker and every verifier a payout may be protected by
+    pub transaction: u32,
+    /// The `TYPE_ID` and type of each payload that dex transactions spend or create
+    pub payloads: Vec<([u8; 4], u32)>,
+}
+
+/// Register a UTXO payload type, and return its `TYPE_ID` together with its type.
+fn payload<T: UtxoData + TypeInfo + 'static>(registry: &mut Registry) -> ([u8; 4], u32) {
+    (T::TYPE_ID, registry.register_type(&meta_type::<T>()).id)
+}
+
+/// The description of the dex types in this runtime.
+pub fn dex_metadata() -> DexMetadata {
+    type Opposite = dex::OppositeSide<DexConfig01>;
+
+    let mut registry = Registry::new();
+    let transaction = registry.register_type(&meta_type::<Transaction>()).id;
+    let payloads = vec![
+        payload::<money::Coin<0>>(&mut registry),
+        payload::<money::Coin<1>>(&mut registry),
+        payload::<dex::Order<DexConfig01>>(&mut registry),
+        payload::<dex::Order<Opposite>>(&mut registry),
+        payload::<dex::IcebergOrder<DexConfig01>>(&mut registry),
+        payload::<dex::IcebergOrder<Opposite>>(&mut registry),
+        payload::<dex::OrderCommitment<DexConfig01>>(&mut registry),
+        payload::<dex::OrderCommitment<Opposite>>(&mut registry),
+        payload::<dex::NftOrder<KittyDexConfig>>(&mut registry),
+        payload::<dex::DexParameters>(&mut registry),
+        payload::<dex::TradingStatus<DexConfig01>>(&mut registry),
+    ];
+
+    DexMetadata {
+        types: registry.into(),
+        transaction,
+        payloads,
+    }
+}
+
+sp_api::decl_runtime_apis! {
+    /// Access to the description of the dex types.
+    pub trait DexMetadataApi {
+        /// The SCALE encoded [`DexMetadata`].
+        fn dex_metadata() -> Vec<u8>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The last segment of the path of the type with the given id.
+    fn type_name(metadata: &DexMetadata, id: u32) -> &str {
+        let ty = metadata
+            .types
+            .resolve(id)
+            .expect("registered types resolve");
+        ty.path.segments.last().expect("named types have a path")
+    }
+
+    #[test]
+    fn transactions_are_described() {
+        let metadata = dex_metadata();
+        assert_eq!(type_name(&metadata, metadata.transaction), "Transaction");
+    }
+
+    #[test]
+    fn order_payloads_are_found_by_their_type_id() {
+        let metadata = dex_metadata();
+        let order_type_id = <dex::Order<DexConfig01> as UtxoData>::TYPE_ID;
+        let (_, id) = metadata
+            .payloads
+            .iter()
+            .find(|(type_id, _)| *type_id == order_type_id)
+            .expect("orders are described");
+        assert_eq!(type_name(&metadata, *id), "Order");
+    }
+
+    #[test]
+    fn every_payload_has_its_own_type_id() {
+        let metadata = dex_metadata();
+        let mut type_ids: Vec<_> = metadata
+            .payloads
+            .iter()
+            .map(|(type_id, _)| type_id)
+            .collect();
+        type_ids.sort();
+        type_ids.dedup();
+        assert_eq!(type_ids.len(), metadata.payloads.len());
+    }
+}
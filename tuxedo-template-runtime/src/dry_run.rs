@@ -0,0 +1,131 @@
+//! Dry runs of dex matches, so matchers can debug a match without submitting it.
+//!
+//! A match that the pool rejects only comes back with a custom error code. Before
+//! submitting, a matcher can instead dry-run it against the latest state with
+//! [`DryRunApi`]. That runs the same runtime-level checks and constraint checker as the
+//! pool would, and returns the [`dex::DexError`] itself if the checker rejects the match.
+//! Nothing is written to storage. The inputs' verifiers are not checked, so a match can
+//! be dry-run before it is signed.
+
+use crate::{OuterConstraintCheckerError, Output, Runtime, Transaction};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_runtime::transaction_validity::{TransactionPriority, TransactionValidityError};
+use sp_std::prelude::*;
+use tuxedo_core::{types::OutputRef, ConstraintChecker};
+
+/// All the reasons a dry-run match can fail.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum DryRunError {
+    /// The transaction does not match orders.
+    NotAMatch,
+    /// The UTXOs spent by these inputs do not exist.
+    MissingInputs(Vec<OutputRef>),
+    /// The runtime-level checks reject the transaction.
+    RuntimeCheck(TransactionValidityError),
+    /// The dex rejects the match.
+    Dex(dex::DexError),
+}
+
+/// The dex error inside a match checker's error.
+fn match_error(error: OuterConstraintCheckerError) -> Option<dex::DexError> {
+    match error {
+        OuterConstraintCheckerError::MatchOrders(e)
+        | OuterConstraintCheckerError::MatchIceberg01(e)
+        | OuterConstraintCheckerError::MatchIceberg10(e)
+        | OuterConstraintCheckerError::MatchAssetOrders(e) => Some(e),
+        _ => None,
+    }
+}
+
+/// Check a match against the current state, as if it were included in the next block.
+///
+/// Returns the priority the match checker would give it.
+pub fn dry_run_match(transaction: &Transaction) -> Result<TransactionPriority, DryRunError> {
+    if !transaction.checker.is_match() {
+        return Err(DryRunError::NotAMatch);
+    }
+
+    let peeked = Runtime::peek_inputs(transaction);
+    let missing: Vec<OutputRef> = transaction
+        .inputs
+        .iter()
+        .zip(&peeked)
+        .filter(|(_, utxo)| utxo.is_none())
+        .map(|(input, _)| input.output_ref.clone())
+        .collect();
+    if !missing.is_empty() {
+        return Err(DryRunError::MissingInputs(missing));
+    }
+    let inputs: Vec<Output> = peeked.iter().flatten().cloned().collect();
+
+    // Like the pool, check the match as if it were in the next block.
+    sp_io::storage::start_transaction();
+    Runtime::set_block_number(Runtime::block_number() + 1);
+    let result = Runtime::runtime_checks(transaction, &peeked)
+        .map_err(DryRunError::RuntimeCheck)
+        .and_then(|()| {
+            transaction
+                .checker
+                .check(&inputs, &transaction.outputs)
+                .map_err(|e| match_error(e).map_or(DryRunError::NotAMatch, DryRunError::Dex))
+        });
+    sp_io::storage::rollback_transaction();
+
+    result
+}
+
+sp_api::decl_runtime_apis! {
+    /// Dry runs of dex matches.
+    pub trait DryRunApi {
+        /// Check a match against the current state without submitting it.
+        fn dry_run_match(transaction: Transaction) -> Result<TransactionPriority, DryRunError>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OuterConstraintChecker, OuterVerifier};
+    use tuxedo_core::{types::Input, verifier::UpForGrabs};
+
+    fn transaction(checker: OuterConstraintChecker) -> Transaction {
+        Transaction {
+            inputs: vec![Input {
+                output_ref: OutputRef {
+                    tx_hash: Default::default(),
+                    index: 7,
+                },
+                redeemer: Vec::new(),
+            }],
+            outputs: vec![Output {
+                payload: money::Coin::<0>(1).into(),
+                verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            }],
+            checker,
+        }
+    }
+
+    #[test]
+    fn only_matches_can_be_dry_run() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let spend = transaction(OuterConstraintChecker::Money(
+                money::MoneyConstraintChecker::Spend,
+            ));
+            assert_eq!(dry_run_match(&spend), Err(DryRunError::NotAMatch));
+        });
+    }
+
+    #[test]
+    fn missing_inputs_are_reported() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let tx = transaction(OuterConstraintChecker::MatchOrders(Default::default()));
+            assert_eq!(
+                dry_run_match(&tx),
+                Err(DryRunError::MissingInputs(vec![tx.inputs[0]
+                    .output_ref
+                    .clone()]))
+            );
+        });
+    }
+}
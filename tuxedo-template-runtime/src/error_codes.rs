@@ -0,0 +1,287 @@
+//! Custom `InvalidTransaction` codes for the errors of the constraint checkers.
+//!
+//! The Tuxedo executive rejects every transaction whose constraint checker fails with the
+//! same code, so wallets cannot tell why. Instead, the runtime checks a rejected
+//! transaction again and reports the checker's error with [`IntoInvalidTransaction`].
+//!
+//! Each piece's errors get a range of codes, starting at the piece's offset. The code of an
+//! error is the offset plus the error's index in that range: [`dex::DexError::code`] for the
+//! dex, and the variant's position in its enum for the other pieces. So the other pieces
+//! must only ever add variants at the end of their error enums.
+//!
+//! | Piece                  | Codes      |
+//! |------------------------|------------|
+//! | money                  | 1..=12     |
+//! | kitties                | 13..=20    |
+//! | poe                    | 21..=26    |
+//! | timestamp              | 27..=31    |
+//! | governance             | 32..=35    |
+//! | vesting                | 36..=43    |
+//! | stream                 | 44..=55    |
+//! | bridge                 | 56..=73    |
+//! | cdp                    | 74..=89    |
+//! | multisig               | 90..=98    |
+//! | dex                    | 100..=199  |
+//! | runtime-level checks   | 200..=203  |
+//! | lending                | 204..=223  |
+//! | runtime upgrades       | 224        |
+//! | recovery               | 225..=235  |
+//! | canonical order check  | 236        |
+//! | duplicate input check  | 237        |
+//! | faucet                 | 238..=245  |
+//! | twap                   | 246..=253  |
+//!
+//! [`source_of_code`] names the piece or check that a code comes from, which the node uses to
+//! label its metrics of rejected transactions.
+
+use crate::OuterConstraintCheckerError;
+use sp_runtime::transaction_validity::InvalidTransaction;
+
+/// The first code for errors of the money piece.
+pub const MONEY_ERROR_OFFSET: u8 = 1;
+/// The first code for errors of the kitties piece.
+pub const KITTY_ERROR_OFFSET: u8 = 13;
+/// The first code for errors of the proof of existence piece.
+pub const POE_ERROR_OFFSET: u8 = 21;
+/// The first code for errors of the timestamp piece.
+pub const TIMESTAMP_ERROR_OFFSET: u8 = 27;
+/// The first code for errors of the governance piece.
+pub const GOVERNANCE_ERROR_OFFSET: u8 = 32;
+/// The first code for errors of the vesting piece.
+pub const VESTING_ERROR_OFFSET: u8 = 36;
+/// The first code for errors of the stream piece.
+pub const STREAM_ERROR_OFFSET: u8 = 44;
+/// The first code for errors of the bridge piece.
+pub const BRIDGE_ERROR_OFFSET: u8 = 56;
+/// The first code for errors of the cdp piece.
+pub const CDP_ERROR_OFFSET: u8 = 74;
+/// The first code for errors of the multisig piece.
+pub const MULTISIG_ERROR_OFFSET: u8 = 90;
+/// The first code for errors of the dex piece.
+///
+/// A transaction rejected with [`dex::DexError`] `e` gets code `DEX_ERROR_OFFSET + e.code()`,
+/// so clients can look up the reason with [`dex::DexError::message_for_code`].
+pub const DEX_ERROR_OFFSET: u8 = 100;
+/// The first code for errors of the lending piece.
+pub const LENDING_ERROR_OFFSET: u8 = 204;
+/// The code for transactions that the runtime upgrade checker rejects.
+pub const RUNTIME_UPGRADE_ERROR: u8 = 224;
+/// The first code for errors of the recovery piece.
+pub const RECOVERY_ERROR_OFFSET: u8 = 225;
+/// The first code for errors of the faucet piece.
+pub const FAUCET_ERROR_OFFSET: u8 = 238;
+/// The first code for errors of the TWAP piece.
+pub const TWAP_ERROR_OFFSET: u8 = 246;
+
+/// The piece, or runtime-level check, that rejects transactions with the given custom code.
+pub fn source_of_code(code: u8) -> Option<&'static str> {
+    Some(match code {
+        1..=12 => "money",
+        13..=20 => "kitties",
+        21..=26 => "poe",
+        27..=31 => "timestamp",
+        32..=35 => "governance",
+        36..=43 => "vesting",
+        44..=55 => "stream",
+        56..=73 => "bridge",
+        74..=89 => "cdp",
+        90..=98 => "multisig",
+        100..=199 => "dex",
+        crate::VALUE_NOT_CONSERVED => "value_conservation",
+        crate::TIMESTAMP_WRONG_BLOCK => "timestamp_block",
+        crate::INVALID_TIP => "tip",
+        crate::ORDER_LIMIT_VIOLATED => "order_limit",
+        204..=223 => "lending",
+        RUNTIME_UPGRADE_ERROR => "runtime_upgrade",
+        225..=235 => "recovery",
+        crate::ORDERS_NOT_CANONICAL => "canonical_order",
+        crate::DUPLICATE_INPUT => "duplicate_input",
+        238..=245 => "faucet",
+        246..=253 => "twap",
+        _ => return None,
+    })
+}
+
+/// An error that the runtime reports to clients as a custom `InvalidTransaction`.
+pub trait IntoInvalidTransaction {
+    /// The `InvalidTransaction` that a transaction rejected with this error gets.
+    fn into_invalid_transaction(&self) -> InvalidTransaction;
+}
+
+impl IntoInvalidTransaction for dex::DexError {
+    fn into_invalid_transaction(&self) -> InvalidTransaction {
+        InvalidTransaction::Custom(DEX_ERROR_OFFSET + self.code())
+    }
+}
+
+/// Implement [`IntoInvalidTransaction`] for error enums without fields, numbering their
+/// variants in order from the given offset.
+macro_rules! codes_by_position {
+    ($($error:ty => $offset:ident,)*) => {
+        $(
+            impl IntoInvalidTransaction for $error {
+                fn into_invalid_transaction(&self) -> InvalidTransaction {
+                    InvalidTransaction::Custom($offset + self.clone() as u8)
+                }
+            }
+        )*
+    };
+}
+
+codes_by_position! {
+    money::ConstraintCheckerError => MONEY_ERROR_OFFSET,
+    kitties::KittyError => KITTY_ERROR_OFFSET,
+    poe::PoeError => POE_ERROR_OFFSET,
+    timestamp::TimestampError => TIMESTAMP_ERROR_OFFSET,
+    governance::GovernanceError => GOVERNANCE_ERROR_OFFSET,
+    vesting::VestingError => VESTING_ERROR_OFFSET,
+    stream::StreamError => STREAM_ERROR_OFFSET,
+    bridge::BridgeError => BRIDGE_ERROR_OFFSET,
+    cdp::CdpError => CDP_ERROR_OFFSET,
+    multisig::MultisigError => MULTISIG_ERROR_OFFSET,
+    lending::LendingError => LENDING_ERROR_OFFSET,
+    recovery::RecoveryError => RECOVERY_ERROR_OFFSET,
+    faucet::FaucetError => FAUCET_ERROR_OFFSET,
+    twap::TwapError => TWAP_ERROR_OFFSET,
+}
+
+impl IntoInvalidTransaction for OuterConstraintCheckerError {
+    fn into_invalid_transaction(&self) -> InvalidTransaction {
+        use OuterConstraintCheckerError::*;
+        match self {
+            Money(e) | SecondToken(e) | WrappedToken(e) | DebtToken(e) | MultiAsset(e) => {
+                e.into_invalid_transaction()
+            }
+            RuntimeUpgrade(_) => InvalidTransaction::Custom(RUNTIME_UPGRADE_ERROR),
+            MakeOrder01(e)
+            | MakeOrder10(e)
+            | MatchOrders(e)
+            | CancelOrder01(e)
+            | CancelOrder10(e)
+            | MakeIceberg01(e)
+            | MakeIceberg10(e)
+            | MatchIceberg01(e)
+            | MatchIceberg10(e)
+            | CommitOrder01(e)
+            | CommitOrder10(e)
+            | RevealOrder01(e)
+            | RevealOrder10(e)
+            | ReclaimCommitment01(e)
+            | ReclaimCommitment10(e)
+            | ListKitty(e)
+            | BuyKitty(e)
+            | ListPair(e)
+            | MakeAssetOrderAB(e)
+            | MakeAssetOrderBA(e)
+            | MatchAssetOrders(e)
+            | CancelAssetOrderAB(e)
+            | CancelAssetOrderBA(e)
+            | SweepExpired(e)
+            | SweepExpiredAssetOrders(e)
+            | BuyAuction01(e)
+            | ClaimRebate01(e)
+            | ClaimAssetRebate(e) => e.into_invalid_transaction(),
+            Kitties(e) => e.into_invalid_transaction(),
+            Poe(e) => e.into_invalid_transaction(),
+            Timestamp(e) => e.into_invalid_transaction(),
+            UpdateDexParameters(e)
+            | UpdateTradingStatus01(e)
+            | UpdateCollateralPrice03(e)
+            | UpdateLendingPrice01(e) => e.into_invalid_transaction(),
+            SendToSibling0(e)
+            | ReceiveFromSibling0(e)
+            | SendToSibling2(e)
+            | ReceiveFromSibling2(e)
+            | RelaySiblingHeader(e) => e.into_invalid_transaction(),
+            OpenVault03(e) | AdjustVault03(e) | Liquidate03(e) => e.into_invalid_transaction(),
+            Supply01(e) | Withdraw01(e) | Borrow01(e) | Repay01(e) | Liquidate01(e) => {
+                e.into_invalid_transaction()
+            }
+            LockCoins0(e) | Release0(e) | LockCoins1(e) | Release1(e) => {
+                e.into_invalid_transaction()
+            }
+            OpenStream0(e) | WithdrawFromStream0(e) | CancelStream0(e) => {
+                e.into_invalid_transaction()
+            }
+            ProposeTransaction(e) | ApproveProposal(e) | DiscardProposal(e) => {
+                e.into_invalid_transaction()
+            }
+            StartRecovery(e) | ApproveRecovery(e) | CancelRecovery(e) => {
+                e.into_invalid_transaction()
+            }
+            Drip0(e) | Drip1(e) => e.into_invalid_transaction(),
+            ObserveTwap01(e) => e.into_invalid_transaction(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(error: impl IntoInvalidTransaction) -> u8 {
+        match error.into_invalid_transaction() {
+            InvalidTransaction::Custom(code) => code,
+            other => panic!("not a custom code: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn each_piece_starts_at_its_offset() {
+        assert_eq!(code(money::ConstraintCheckerError::BadlyTyped), 1);
+        assert_eq!(code(kitties::KittyError::BadlyTyped), 13);
+        assert_eq!(code(dex::DexError::TypeError), 100);
+        assert_eq!(code(lending::LendingError::TypeError), 204);
+    }
+
+    #[test]
+    fn no_piece_runs_into_the_next_range() {
+        assert_eq!(code(money::ConstraintCheckerError::WrongAsset), 12);
+        assert_eq!(code(kitties::KittyError::TransferMismatch), 20);
+        assert_eq!(code(poe::PoeError::UnexpectedOutputsWhenRevoking), 26);
+        assert_eq!(code(timestamp::TimestampError::BlockNotIncreasing), 31);
+        assert_eq!(code(governance::GovernanceError::CouncilChanged), 35);
+        assert_eq!(code(vesting::VestingError::RemainderNotLocked), 43);
+        assert_eq!(code(stream::StreamError::RefundExceedsUnaccrued), 55);
+        assert_eq!(code(bridge::BridgeError::RelayersChanged), 73);
+        assert_eq!(code(cdp::CdpError::OwnerNotRefunded), 89);
+        assert_eq!(code(multisig::MultisigError::CredentialsNotReturned), 98);
+        assert!(code(dex::DexError::ReferralNotPaid) < crate::VALUE_NOT_CONSERVED);
+        assert_eq!(code(lending::LendingError::OwnerOrderMismatch), 223);
+        assert_eq!(code(recovery::RecoveryError::RecoveryReady), 235);
+        assert!(code(recovery::RecoveryError::RecoveryReady) < crate::ORDERS_NOT_CANONICAL);
+        assert_eq!(code(faucet::FaucetError::TypeError), 238);
+        assert_eq!(code(faucet::FaucetError::FaucetChanged), 245);
+        assert_eq!(code(twap::TwapError::TypeError), 246);
+        assert_eq!(code(twap::TwapError::OracleChanged), 253);
+    }
+
+    #[test]
+    fn codes_are_traced_back_to_their_piece() {
+        assert_eq!(source_of_code(MONEY_ERROR_OFFSET), Some("money"));
+        assert_eq!(source_of_code(code(dex::DexError::TypeError)), Some("dex"));
+        assert_eq!(
+            source_of_code(code(dex::DexError::ReferralNotPaid)),
+            Some("dex")
+        );
+        assert_eq!(
+            source_of_code(code(lending::LendingError::OwnerOrderMismatch)),
+            Some("lending")
+        );
+        assert_eq!(
+            source_of_code(crate::DUPLICATE_INPUT),
+            Some("duplicate_input")
+        );
+        assert_eq!(source_of_code(0), None);
+        assert_eq!(source_of_code(99), None);
+        assert_eq!(
+            source_of_code(code(faucet::FaucetError::CoolingDown)),
+            Some("faucet")
+        );
+        assert_eq!(
+            source_of_code(code(twap::TwapError::ObservationStale)),
+            Some("twap")
+        );
+        assert_eq!(source_of_code(254), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! A journal of the outputs spent in recent blocks.
+//!
+//! Once a UTXO is spent it is removed from storage, so state alone cannot tell a wallet
+//! what it lost when a block is reorged away, or what happened to an order it was watching.
+//! The runtime therefore journals every spent output under the number of the block that
+//! spent it.
+//!
+//! The journal only covers the most recent [`JOURNAL_RETENTION`] blocks. At the start of
+//! each block the entry that has fallen out of the window is pruned, so the journal's
+//! size is bounded by the activity in that window rather than growing with the chain.
+
+use crate::{BlockNumber, Output};
+use parity_scale_codec::{Decode, Encode};
+use sp_std::prelude::*;
+use tuxedo_core::types::OutputRef;
+
+/// The prefix of the storage keys under which each block's spent outputs are journaled.
+pub const JOURNAL_PREFIX: &[u8] = b"spent_journal";
+
+/// How many blocks' worth of spent outputs are kept, including the current block.
+pub const JOURNAL_RETENTION: BlockNumber = 256;
+
+/// The storage key of the journal entry for the given block.
+fn entry_key(block: BlockNumber) -> Vec<u8> {
+    (JOURNAL_PREFIX, block).encode()
+}
+
+/// The outputs spent in the given block, in the order they were spent.
+///
+/// This is empty for blocks that spent nothing as well as for blocks that have already
+/// fallen out of the retention window.
+pub fn spent_in(block: BlockNumber) -> Vec<(OutputRef, Output)> {
+    sp_io::storage::get(&entry_key(block))
+        .and_then(|encoded| Vec::<(OutputRef, Output)>::decode(&mut &encoded[..]).ok())
+        .unwrap_or_default()
+}
+
+/// How many recent blocks, before the given one, [`spent_recently`] looks back over.
+pub const STALE_LOOKBACK: BlockNumber = 16;
+
+/// Whether the given output was spent in one of the [`STALE_LOOKBACK`] blocks before the
+/// given block.
+///
+/// Transaction hashes never repeat, so an output that has been spent never exists again.
+/// The transaction pool uses this to reject transactions that spend it as stale right
+/// away, instead of keeping them around in case the output still appears.
+pub fn spent_recently(output_ref: &OutputRef, block: BlockNumber) -> bool {
+    (block.saturating_sub(STALE_LOOKBACK)..block).any(|earlier| {
+        spent_in(earlier)
+            .iter()
+            .any(|(spent, _)| spent == output_ref)
+    })
+}
+
+/// Journal some outputs as spent in the given block.
+pub(crate) fn record(block: BlockNumber, spent: Vec<(OutputRef, Output)>) {
+    if spent.is_empty() {
+        return;
+    }
+
+    let mut entry = spent_in(block);
+    entry.extend(spent);
+    sp_io::storage::set(&entry_key(block), &entry.encode());
+}
+
+/// Remove the entry that falls out of the retention window when the given block begins.
+pub(crate) fn prune(block: BlockNumber) {
+    if let Some(expired) = block.checked_sub(JOURNAL_RETENTION) {
+        sp_io::storage::clear(&entry_key(expired));
+    }
+}
+
+sp_api::decl_runtime_apis! {
+    /// Access to the outputs spent in recent blocks.
+    pub trait SpentOutputsApi {
+        /// The outputs spent in the given block, if it is within the retention window.
+        fn spent_in(block: BlockNumber) -> Vec<(OutputRef, Output)>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OuterVerifier;
+    use sp_core::H256;
+    use tuxedo_core::verifier::UpForGrabs;
+
+    fn spent(index: u32) -> (OutputRef, Output) {
+        (
+            OutputRef {
+                tx_hash: H256::repeat_byte(1),
+                index,
+            },
+            Output {
+                verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                payload: money::Coin::<0>(index as u128).into(),
+            },
+        )
+    }
+
+    #[test]
+    fn spends_accumulate_within_a_block() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            record(5, vec![spent(0)]);
+            record(5, vec![spent(1), spent(2)]);
+
+            assert_eq!(spent_in(5), vec![spent(0), spent(1), spent(2)]);
+            assert_eq!(spent_in(6), vec![]);
+        });
+    }
+
+    #[test]
+    fn old_entries_are_pruned() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            record(5, vec![spent(0)]);
+
+            prune(5 + JOURNAL_RETENTION - 1);
+            assert_eq!(spent_in(5), vec![spent(0)]);
+
+            prune(5 + JOURNAL_RETENTION);
+            assert_eq!(spent_in(5), vec![]);
+        });
+    }
+
+    #[test]
+    fn recent_spends_are_found() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            record(5, vec![spent(0)]);
+
+            assert!(!spent_recently(&spent(0).0, 5));
+            assert!(spent_recently(&spent(0).0, 6));
+            assert!(spent_recently(&spent(0).0, 5 + STALE_LOOKBACK));
+            assert!(!spent_recently(&spent(0).0, 6 + STALE_LOOKBACK));
+            assert!(!spent_recently(&spent(1).0, 6));
+        });
+    }
+
+    #[test]
+    fn pruning_early_blocks_does_nothing() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            record(0, vec![spent(0)]);
+            prune(JOURNAL_RETENTION - 1);
+            assert_eq!(spent_in(0), vec![spent(0)]);
+        });
+    }
+}
@@ -18,12 +18,15 @@ use sp_api::impl_runtime_apis;
 use sp_runtime::{
     create_runtime_str, impl_opaque_keys,
     traits::{BlakeTwo256, Block as BlockT},
-    transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
+    transaction_validity::{
+        InvalidTransaction, TransactionLongevity, TransactionPriority, TransactionSource,
+        TransactionValidity, TransactionValidityError,
+    },
     ApplyExtrinsicResult, BoundToRuntimeAppPublic,
 };
 use sp_std::prelude::*;
 
-use sp_core::OpaqueMetadata;
+use sp_core::{OpaqueMetadata, H256};
 #[cfg(any(feature = "std", test))]
 use sp_runtime::{BuildStorage, Storage};
 
@@ -34,17 +37,49 @@ use sp_version::RuntimeVersion;
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
+use error_codes::IntoInvalidTransaction;
+
 use tuxedo_core::{
     dynamic_typing::{DynamicallyTypedData, UtxoData},
+    traits::Cash,
     tuxedo_constraint_checker, tuxedo_verifier,
-    types::Transaction as TuxedoTransaction,
+    types::{Input, Transaction as TuxedoTransaction},
     verifier::{SigCheck, ThresholdMultiSignature, UpForGrabs},
+    ConstraintChecker,
 };
 
+pub use dex;
+pub use governance;
+pub use kitties;
 pub use money;
+pub use poe;
 pub use runtime_upgrade;
+pub use timestamp;
+
+pub mod canonical;
+pub mod conservation;
+pub mod dex_metadata;
+pub mod dry_run;
+pub mod error_codes;
+pub mod journal;
+pub mod migrations;
+pub mod order_book;
+pub mod order_limits;
+pub mod quote;
+pub mod sealed_matches;
+pub mod stats;
+pub mod tips;
+pub mod try_state;
+pub mod utxo_set;
+pub mod verifiers;
+pub mod weights;
+pub mod witness;
+use tuxedo_derive::TuxedoData;
+use verifiers::{
+    BlockAuthorTip, CompoundVerifier, Delegated, EcdsaSigCheck, Ed25519SigCheck, MultisigProposal,
+    Recoverable, Sr25519SigCheck, TimeLock,
+};
 
-#[cfg(feature = "std")]
 use tuxedo_core::types::OutputRef;
 
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
@@ -84,7 +119,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("tuxedo-template-runtime"),
     impl_name: create_runtime_str!("tuxedo-template-runtime"),
     authoring_version: 1,
-    spec_version: 1,
+    spec_version: 2,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
     transaction_version: 1,
@@ -104,6 +139,9 @@ pub fn native_version() -> NativeVersion {
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct GenesisConfig {
     pub genesis_utxos: Vec<Output>,
+    /// The registered assets that the asset dex trades, as its tokens A and B.
+    /// Each should have an `AssetInfo` among the genesis UTXOs.
+    pub asset_pair: (money::AssetId, money::AssetId),
 }
 
 impl Default for GenesisConfig {
@@ -115,34 +153,200 @@ impl Default for GenesisConfig {
         const ANDREW_PUB_KEY_BYTES: [u8; 32] =
             hex!("baa81e58b1b4d053c2e86d93045765036f9d265c7dfe8b9693bbc2c0f048d93a");
 
+        // Genesis outputs are owned through signatures that are bound to this chain's
+        // genesis hash, so that they cannot be spent by replaying transactions from other
+        // chains built from the same pieces. See `verifiers::signing_payload`.
+        let shawn = OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+            owner_pubkey: SHAWN_PUB_KEY_BYTES.into(),
+        });
+        let andrew = OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+            owner_pubkey: ANDREW_PUB_KEY_BYTES.into(),
+        });
+
         // Initial Config just for a Money UTXO
         GenesisConfig {
             genesis_utxos: vec![
                 Output {
-                    verifier: OuterVerifier::SigCheck(SigCheck {
-                        owner_pubkey: SHAWN_PUB_KEY_BYTES.into(),
-                    }),
+                    verifier: shawn.clone(),
                     payload: DynamicallyTypedData {
                         data: 100u128.encode(),
                         type_id: <money::Coin<0> as UtxoData>::TYPE_ID,
                     },
                 },
                 Output {
-                    verifier: OuterVerifier::ThresholdMultiSignature(ThresholdMultiSignature {
+                    verifier: OuterVerifier::Compound(CompoundVerifier::Threshold {
                         threshold: 1,
-                        signatories: vec![SHAWN_PUB_KEY_BYTES.into(), ANDREW_PUB_KEY_BYTES.into()],
+                        verifiers: vec![shawn.clone(), andrew.clone()],
                     }),
                     payload: DynamicallyTypedData {
                         data: 100u128.encode(),
                         type_id: <money::Coin<0> as UtxoData>::TYPE_ID,
                     },
                 },
+                // The privileges to mint each token. They are up for grabs so that
+                // anyone can mint tokens while playing with the chain.
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: money::MintAuthority::<0>.into(),
+                },
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: money::MintAuthority::<1>.into(),
+                },
+                // A founding pair of kitties. All other kitties descend from them.
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: kitties::KittyData {
+                        gender: kitties::Gender::Female,
+                        dna: H256::repeat_byte(1),
+                        num_breedings: 0,
+                    }
+                    .into(),
+                },
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: kitties::KittyData {
+                        gender: kitties::Gender::Male,
+                        dna: H256::repeat_byte(2),
+                        num_breedings: 0,
+                    }
+                    .into(),
+                },
+                // The time before the first block. Each block's timestamp inherent replaces it.
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: timestamp::Timestamp::default().into(),
+                },
+                // The tunable dex parameters. Shawn and Andrew form the council, and
+                // both of them must sign to update the parameters.
+                Output {
+                    verifier: OuterVerifier::Compound(CompoundVerifier::Threshold {
+                        threshold: 2,
+                        verifiers: vec![shawn.clone(), andrew.clone()],
+                    }),
+                    payload: dex::DexParameters::defaults::<DexConfig01>().into(),
+                },
+                // Whether trading between tokens 0 and 1 is paused. Shawn is the admin
+                // who may pause it during an incident.
+                Output {
+                    verifier: shawn.clone(),
+                    payload: dex::TradingStatus::<DexConfig01> {
+                        paused: false,
+                        _ph_data: Default::default(),
+                    }
+                    .into(),
+                },
+                // An example claim to the existence of a document, owned by Shawn.
+                Output {
+                    verifier: shawn.clone(),
+                    payload: poe::ClaimData {
+                        claim: H256::repeat_byte(0xaa),
+                    }
+                    .into(),
+                },
+                // The latest sibling block that the bridge knows of. Shawn and Andrew are
+                // the relayers, and both of them must sign to relay a newer one.
+                Output {
+                    verifier: OuterVerifier::Compound(CompoundVerifier::Threshold {
+                        threshold: 2,
+                        verifiers: vec![shawn.clone(), andrew.clone()],
+                    }),
+                    payload: bridge::SiblingHeader::default().into(),
+                },
+                // The price of token 0 in token 3, which vaults are valued at. Shawn is
+                // the oracle who publishes it.
+                Output {
+                    verifier: shawn.clone(),
+                    payload: cdp::PriceFeed::<CdpConfig03> {
+                        price: dex::Price::ONE,
+                        _ph_data: Default::default(),
+                    }
+                    .into(),
+                },
+                // The empty pool of the market that lends token 1 against token 0.
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: lending::Pool::<LendingConfig01> {
+                        cash: 0,
+                        borrowed: 0,
+                        _ph_data: Default::default(),
+                    }
+                    .into(),
+                },
+                // The price of token 0 in token 1, which loans are valued at. Shawn is
+                // the oracle who publishes it.
+                Output {
+                    verifier: shawn.clone(),
+                    payload: lending::PriceFeed::<LendingConfig01> {
+                        price: dex::Price::ONE,
+                        _ph_data: Default::default(),
+                    }
+                    .into(),
+                },
+                // Two registered assets, which the asset dex trades. Shawn created them,
+                // and may mint them.
+                Output {
+                    verifier: shawn.clone(),
+                    payload: money::AssetInfo {
+                        id: 1,
+                        creator: SHAWN_PUB_KEY_BYTES.into(),
+                        decimals: 12,
+                        symbol: b"GOLD".to_vec(),
+                    }
+                    .into(),
+                },
+                Output {
+                    verifier: shawn.clone(),
+                    payload: money::AssetInfo {
+                        id: 2,
+                        creator: SHAWN_PUB_KEY_BYTES.into(),
+                        decimals: 12,
+                        symbol: b"SLVR".to_vec(),
+                    }
+                    .into(),
+                },
+                // The pairs that are open for trading. The council lists new ones.
+                Output {
+                    verifier: OuterVerifier::Compound(CompoundVerifier::Threshold {
+                        threshold: 2,
+                        verifiers: vec![shawn.clone(), andrew.clone()],
+                    }),
+                    payload: dex::PairRegistry {
+                        pairs: vec![
+                            dex::listing::pair_of::<DexConfig01>(),
+                            dex::listing::pair_of::<AssetDexConfig>(),
+                        ],
+                    }
+                    .into(),
+                },
+                // The faucets of tokens 0 and 1, which anyone may drip test coins from.
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: faucet::Faucet::<FaucetConfig0>::default().into(),
+                },
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: faucet::Faucet::<FaucetConfig1>::default().into(),
+                },
+                // The oracle of the average price of token 0 in token 1, which anyone may
+                // observe the market in.
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: twap::TwapOracle::<TwapConfig01>::default().into(),
+                },
+                // The open rebate pools of both dexes, which every match that pays a fee
+                // spends and recreates, so anyone must be able to spend them.
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: dex::RebatePool::<DexConfig01>::open(0).into(),
+                },
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: dex::RebatePool::<AssetDexConfig>::open(0).into(),
+                },
             ],
+            asset_pair: (1, 2),
         }
-
-        // TODO: Initial UTXO for Kitties
-
-        // TODO: Initial UTXO for Existence
     }
 }
 
@@ -155,13 +359,117 @@ impl BuildStorage for GenesisConfig {
             WASM_BINARY.unwrap().to_vec(),
         );
 
+        // A new chain starts out with storage in the current encodings.
+        storage.top.insert(
+            migrations::MIGRATED_SPEC_VERSION_KEY.to_vec(),
+            VERSION.spec_version.encode(),
+        );
+
+        // The asset dex learns which assets it trades from storage.
+        if self.asset_pair.0 == self.asset_pair.1 {
+            return Err("The asset dex cannot trade an asset for itself".into());
+        }
+        storage
+            .top
+            .insert(ASSET_PAIR_KEY.to_vec(), self.asset_pair.encode());
+
+        // Genesis orders count towards their owners' limits like any others.
+        let mut open_orders = sp_std::collections::btree_map::BTreeMap::<H256, u32>::new();
+
         for (index, utxo) in self.genesis_utxos.iter().enumerate() {
-            let output_ref = OutputRef {
-                // Genesis UTXOs don't come from any real transaction, so just use the zero hash
-                tx_hash: <Header as sp_api::HeaderT>::Hash::zero(),
-                index: index as u32,
-            };
+            let output_ref = genesis_output_ref(index as u32);
             storage.top.insert(output_ref.encode(), utxo.encode());
+            storage.top.insert(
+                utxo_set::leaf_key(&output_ref),
+                utxo_set::leaf_hash(&output_ref, utxo).encode(),
+            );
+
+            // The first block's timestamp inherent needs to find the genesis timestamp.
+            if let Ok(timestamp) = utxo.payload.extract::<timestamp::Timestamp>() {
+                storage
+                    .top
+                    .insert(TIMESTAMP_UTXO_KEY.to_vec(), output_ref.encode());
+                storage.top.insert(
+                    timestamp::CURRENT_TIMESTAMP_KEY.to_vec(),
+                    timestamp.encode(),
+                );
+            }
+
+            // Dex checkers read the current parameters and trading status from storage.
+            if let Ok(parameters) = utxo.payload.extract::<dex::DexParameters>() {
+                storage.top.insert(
+                    governance::parameters_key::<dex::DexParameters>(),
+                    parameters.encode(),
+                );
+            }
+            if let Ok(status) = utxo.payload.extract::<dex::TradingStatus<DexConfig01>>() {
+                storage.top.insert(
+                    governance::parameters_key::<dex::TradingStatus<DexConfig01>>(),
+                    status.encode(),
+                );
+            }
+            if let Ok(registry) = utxo.payload.extract::<dex::PairRegistry>() {
+                storage
+                    .top
+                    .insert(dex::listing::PAIR_REGISTRY_KEY.to_vec(), registry.encode());
+            }
+
+            // Clients find the faucets through storage.
+            if let Some(coin_id) = faucet_coin(utxo) {
+                storage
+                    .top
+                    .insert(faucet_utxo_key(coin_id), output_ref.encode());
+            }
+            // And the oracle of the average price.
+            if utxo
+                .payload
+                .extract::<twap::TwapOracle<TwapConfig01>>()
+                .is_ok()
+            {
+                storage
+                    .top
+                    .insert(TWAP_ORACLE_UTXO_KEY.to_vec(), output_ref.encode());
+            }
+            // And the open rebate pools.
+            if let Some(key) = open_rebate_pool_key(utxo) {
+                storage.top.insert(key, output_ref.encode());
+            }
+
+            // Vaults are valued at the price recorded in storage.
+            if let Ok(feed) = utxo.payload.extract::<cdp::PriceFeed<CdpConfig03>>() {
+                storage.top.insert(
+                    governance::parameters_key::<cdp::PriceFeed<CdpConfig03>>(),
+                    feed.encode(),
+                );
+            }
+
+            // So are loans.
+            if let Ok(feed) = utxo
+                .payload
+                .extract::<lending::PriceFeed<LendingConfig01>>()
+            {
+                storage.top.insert(
+                    governance::parameters_key::<lending::PriceFeed<LendingConfig01>>(),
+                    feed.encode(),
+                );
+            }
+
+            if let Some(owner) = order_limits::order_owner(utxo) {
+                *open_orders.entry(owner).or_default() += 1;
+            }
+
+            // Genesis claims must be indexed just like claims made in transactions.
+            if let Ok(claim) = utxo.payload.extract::<poe::ClaimData>() {
+                storage
+                    .top
+                    .insert(poe::claim_index_key(&claim.claim), Vec::new());
+            }
+        }
+
+        for (owner, count) in open_orders {
+            storage
+                .top
+                .insert(order_limits::open_orders_key(&owner), count.encode());
         }
 
         Ok(())
@@ -175,6 +483,48 @@ pub type Block = sp_runtime::generic::Block<Header, Transaction>;
 pub type Executive = tuxedo_core::Executive<Block, OuterVerifier, OuterConstraintChecker>;
 pub type Output = tuxedo_core::types::Output<OuterVerifier>;
 
+/// The canonical way to refer to the outputs of a transaction.
+///
+/// Every output is stored under the `OutputRef` made of the transaction's id and the
+/// output's index in the transaction's list of outputs, and so that is how inputs must
+/// later refer to it. The id is the hash of the transaction with its redeemers left empty,
+/// so relaying a transaction with other redeemers can't change the refs of its outputs.
+/// The executive stores new UTXOs under the hash of the entire transaction, and the runtime
+/// moves them, as described in the [`witness`] module.
+pub trait OutputRefs {
+    /// The reference to the output at the given index.
+    fn output_ref(&self, index: u32) -> OutputRef;
+
+    /// The references to all of the outputs, in order.
+    fn output_refs(&self) -> Vec<OutputRef>;
+}
+
+impl<V: Encode + Clone, C: Encode + Clone> OutputRefs for TuxedoTransaction<V, C> {
+    fn output_ref(&self, index: u32) -> OutputRef {
+        OutputRef {
+            tx_hash: witness::txid(self),
+            index,
+        }
+    }
+
+    fn output_refs(&self) -> Vec<OutputRef> {
+        let tx_hash = witness::txid(self);
+        (0..self.outputs.len() as u32)
+            .map(|index| OutputRef { tx_hash, index })
+            .collect()
+    }
+}
+
+/// The reference to the genesis UTXO at the given index.
+///
+/// Genesis UTXOs don't come from any real transaction, so they use the zero hash.
+pub fn genesis_output_ref(index: u32) -> OutputRef {
+    OutputRef {
+        tx_hash: <Header as sp_api::HeaderT>::Hash::zero(),
+        index,
+    }
+}
+
 impl sp_runtime::traits::GetNodeBlockType for Runtime {
     type NodeBlock = opaque::Block;
 }
@@ -186,8 +536,142 @@ impl sp_runtime::traits::GetRuntimeBlockType for Runtime {
 /// The Aura slot duration. When things are working well, this will also be the block time.
 const BLOCK_TIME: u64 = 3000;
 
+/// The log target used by the runtime-level checks.
+const LOG_TARGET: &str = "tuxedo-template-runtime";
+
+/// Custom `InvalidTransaction` code for transactions that create value out of thin air.
+pub const VALUE_NOT_CONSERVED: u8 = 200;
+
+/// The storage key under which the runtime records the number of the block
+/// that is currently being built, executed, or validated against.
+pub const BLOCK_NUMBER_KEY: &[u8] = b"block_number";
+
+/// Custom `InvalidTransaction` code for transactions that misuse the tip convention.
+/// See the [`tips`] module.
+pub const INVALID_TIP: u8 = 202;
+
+/// The storage key under which the runtime records the hash of the genesis block.
+///
+/// The genesis hash depends on the genesis state, so it cannot be stored at genesis.
+/// Instead it is recorded when block 1 is built or imported, from block 1's parent hash.
+pub const GENESIS_HASH_KEY: &[u8] = b"genesis_hash";
+
+/// The storage key under which the runtime records where the latest timestamp UTXO is,
+/// so that the next block's timestamp inherent can consume it.
+pub const TIMESTAMP_UTXO_KEY: &[u8] = b"timestamp_utxo";
+
+/// The storage key under which the runtime records where the faucet of the given token is,
+/// so that clients can drip from it. It is updated after every drip.
+pub fn faucet_utxo_key(coin_id: u8) -> Vec<u8> {
+    [b"faucet_utxo".as_slice(), &[coin_id]].concat()
+}
+
+/// The token whose faucet a UTXO is, if it is one.
+fn faucet_coin(utxo: &Output) -> Option<u8> {
+    match OuterData::try_from(&utxo.payload).ok()? {
+        OuterData::Faucet0(_) => Some(0),
+        OuterData::Faucet1(_) => Some(1),
+        _ => None,
+    }
+}
+
+/// The storage key under which the runtime records where the oracle of the average price of
+/// token 0 in token 1 is, so that anyone can observe the market in it, and pieces can read
+/// the average from it. It is updated after every observation.
+pub const TWAP_ORACLE_UTXO_KEY: &[u8] = b"twap_oracle_utxo";
+
+/// The average price of token 0 in token 1 over the last window, as the oracle last
+/// observed it, or `None` until the oracle's observations cover a whole window.
+pub fn market_twap() -> Option<dex::Price> {
+    let oracle_ref = sp_io::storage::get(TWAP_ORACLE_UTXO_KEY)
+        .and_then(|encoded| OutputRef::decode(&mut &encoded[..]).ok())?;
+    Runtime::peek_utxo(&oracle_ref)?
+        .payload
+        .extract::<twap::TwapOracle<TwapConfig01>>()
+        .ok()?
+        .twap()
+}
+
+/// The storage key under which the runtime records where the open rebate pool of the given
+/// dex is, so that matchers can spend it while fees are enabled. It is updated after every
+/// transaction that recreates the pool.
+pub fn rebate_pool_utxo_key<T: dex::DexConfig>() -> Vec<u8> {
+    [
+        b"rebate_pool_utxo".as_slice(),
+        &<dex::RebatePool<T> as UtxoData>::TYPE_ID,
+    ]
+    .concat()
+}
+
+/// The storage key of the rebate pool that a UTXO is, if it is an open one.
+fn open_rebate_pool_key(utxo: &Output) -> Option<Vec<u8>> {
+    match OuterData::try_from(&utxo.payload).ok()? {
+        OuterData::RebatePool01(pool) if !pool.closed => {
+            Some(rebate_pool_utxo_key::<DexConfig01>())
+        }
+        OuterData::AssetRebatePool(pool) if !pool.closed => {
+            Some(rebate_pool_utxo_key::<AssetDexConfig>())
+        }
+        _ => None,
+    }
+}
+
+/// Feed the trades made by a match that was just applied at the given time to the average
+/// price of token 0 in token 1.
+///
+/// `spent` must be the outputs that the transaction spent.
+fn record_twap_trades(transaction: &Transaction, spent: &[(OutputRef, Output)], now: u64) {
+    for fill in order_book::fills(sp_std::slice::from_ref(transaction), spent) {
+        let Some((base, quote)) = fill.amounts_in((0, 1)) else {
+            continue;
+        };
+        if let Some(price) = dex::Price::from_amounts(quote, base, dex::Rounding::Down) {
+            twap::record_trade::<TwapConfig01>(price, now);
+        }
+    }
+}
+
+/// The storage key under which the genesis state records the assets that the asset dex
+/// trades. See [`AssetSlotA`].
+pub const ASSET_PAIR_KEY: &[u8] = b"asset_pair";
+
+/// Custom `InvalidTransaction` code for timestamps that are not for the current block.
+pub const TIMESTAMP_WRONG_BLOCK: u8 = 201;
+
+/// Custom `InvalidTransaction` code for transactions that break the open order limit.
+/// See the [`order_limits`] module.
+pub const ORDER_LIMIT_VIOLATED: u8 = 203;
+
+/// Custom `InvalidTransaction` code for transactions that spend their orders out of the
+/// canonical order. See the [`canonical`] module.
+pub const ORDERS_NOT_CANONICAL: u8 = 236;
+
+/// Custom `InvalidTransaction` code for transactions that spend the same output twice.
+pub const DUPLICATE_INPUT: u8 = 237;
+
+/// How many blocks a transaction that matches orders stays valid in the pool for.
+///
+/// Matches race each other for the same orders. Once one of them is included, the others
+/// can never be, so they should not linger in the pool for long waiting for their orders.
+pub const MATCH_LONGEVITY: TransactionLongevity = 16;
+
+/// How far into the future, in milliseconds, a block's timestamp may be
+/// compared to the importing node's own clock.
+pub const MAX_TIMESTAMP_DRIFT: u64 = 30_000;
+
+/// The largest encoded transaction, in bytes, that the runtime accepts.
+///
+/// Constraint checkers like `MatchOrders` loop over every input and output, so the time it
+/// takes to check a transaction grows with its size. The dex benchmarks (`cargo bench -p dex`)
+/// measure that growth. This limit admits a match of a little over a hundred signed orders,
+/// which keeps even the largest transaction a small fraction of the block time. A match of
+/// 500 orders must be split up. Recalibrate it from the benchmarks when changing the block
+/// time or the checkers.
+pub const MAX_TRANSACTION_LENGTH: usize = 16 * 1024;
+
 /// A verifier checks that an individual input can be consumed. For example that it is signed properly
 /// To begin playing, we will have two kinds. A simple signature check, and an anyone-can-consume check.
+/// Signature checks are available for each of the sr25519, ed25519, and ecdsa schemes.
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
 #[tuxedo_verifier]
@@ -195,9 +679,334 @@ pub enum OuterVerifier {
     SigCheck(SigCheck),
     UpForGrabs(UpForGrabs),
     ThresholdMultiSignature(ThresholdMultiSignature),
+    Ed25519SigCheck(Ed25519SigCheck),
+    EcdsaSigCheck(EcdsaSigCheck),
+    TimeLock(TimeLock<OuterVerifier>),
+    Compound(CompoundVerifier<OuterVerifier>),
+    BlockAuthorTip(BlockAuthorTip),
+    Sr25519SigCheck(Sr25519SigCheck),
+    MultisigProposal(MultisigProposal),
+    Recoverable(Recoverable),
+    Delegated(Delegated),
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// A Dex Configuration for the Dex that trades tokens 0 and 1
+pub struct DexConfig01;
+impl dex::DexConfig for DexConfig01 {
+    type Verifier = OuterVerifier;
+    type A = money::Coin<0>;
+    type B = money::Coin<1>;
+
+    // Stays comfortably within `MAX_TRANSACTION_LENGTH` for signed orders.
+    const MAX_ORDERS_PER_MATCH: usize = 100;
+    // Matchers pay the front-ends that refer them a fifth of the taker fee, out of the surplus.
+    const REFERRAL_SHARE_PPM: u32 = 200_000;
+
+    fn context() -> dex::ConstraintCheckingContext {
+        Runtime::context()
+    }
+
+    // The council may tune these through the governance piece.
+    fn parameters() -> dex::DexParameters {
+        governance::current().unwrap_or_else(dex::DexParameters::defaults::<Self>)
+    }
+
+    // The admin may pause trading with the governance piece too.
+    fn trading_paused() -> bool {
+        governance::current::<dex::TradingStatus<Self>>().map_or(false, |status| status.paused)
+    }
+
+    // The council lists the pairs that are open for trading.
+    fn pair_listed() -> bool {
+        dex::listing::is_listed::<Self>()
+    }
+
+    // Owners protect their coins with any of the verifiers their key signs for.
+    fn owns_collateral(coin_verifier: &OuterVerifier, payout_verifier: &OuterVerifier) -> bool {
+        order_limits::same_owner(coin_verifier, payout_verifier)
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// A configuration for the market where kitties are sold for token 0
+pub struct KittyDexConfig;
+impl dex::NftDexConfig for KittyDexConfig {
+    type Verifier = OuterVerifier;
+    type Nft = kitties::KittyData;
+    type Token = money::Coin<0>;
+
+    fn context() -> dex::ConstraintCheckingContext {
+        Runtime::context()
+    }
+}
+
+/// The assets that the asset dex trades, as chosen at genesis.
+fn asset_pair() -> (money::AssetId, money::AssetId) {
+    sp_io::storage::get(ASSET_PAIR_KEY)
+        .and_then(|encoded| Decode::decode(&mut &encoded[..]).ok())
+        .unwrap_or_default()
+}
+
+/// The asset that the asset dex trades as its token A
+pub struct AssetSlotA;
+impl money::AssetSlot for AssetSlotA {
+    const ID: u8 = 10;
+
+    fn asset_id() -> money::AssetId {
+        asset_pair().0
+    }
+}
+
+/// The asset that the asset dex trades as its token B
+pub struct AssetSlotB;
+impl money::AssetSlot for AssetSlotB {
+    const ID: u8 = 11;
+
+    fn asset_id() -> money::AssetId {
+        asset_pair().1
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// A Dex Configuration for the Dex that trades the two registered assets chosen at genesis
+pub struct AssetDexConfig;
+impl dex::DexConfig for AssetDexConfig {
+    type Verifier = OuterVerifier;
+    type A = money::Registered<AssetSlotA>;
+    type B = money::Registered<AssetSlotB>;
+
+    fn context() -> dex::ConstraintCheckingContext {
+        Runtime::context()
+    }
+
+    // The council lists the pairs that are open for trading.
+    fn pair_listed() -> bool {
+        dex::listing::is_listed::<Self>()
+    }
+
+    // Owners protect their coins with any of the verifiers their key signs for.
+    fn owns_collateral(coin_verifier: &OuterVerifier, payout_verifier: &OuterVerifier) -> bool {
+        order_limits::same_owner(coin_verifier, payout_verifier)
+    }
+
+    // Both slots read their asset from storage, so nothing stops them holding the same one.
+    fn same_token() -> bool {
+        let (asset_a, asset_b) = asset_pair();
+        asset_a == asset_b
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The bridge configuration for token 0, which is native to this chain and is wrapped
+/// as token 2 on the sibling chain
+pub struct BridgeConfig0;
+impl bridge::BridgeConfig for BridgeConfig0 {
+    type Verifier = OuterVerifier;
+    type Token = money::Coin<0>;
+    const SIBLING_TOKEN: u8 = 2;
+    const NATIVE: bool = true;
+
+    fn is_included(
+        commitment: H256,
+        output_ref: &OutputRef,
+        output: &Output,
+        proof: &bridge::InclusionProof,
+    ) -> bool {
+        // The sibling runs this runtime too, so its UTXO set is committed to in the same way.
+        let proof = utxo_set::UtxoProof {
+            output: output.clone(),
+            leaf_index: proof.leaf_index,
+            leaf_count: proof.leaf_count,
+            siblings: proof.siblings.clone(),
+        };
+        utxo_set::verify_proof(commitment, output_ref, &proof)
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The bridge configuration for token 2, which wraps the sibling chain's token 0
+pub struct BridgeConfig2;
+impl bridge::BridgeConfig for BridgeConfig2 {
+    type Verifier = OuterVerifier;
+    type Token = money::Coin<2>;
+    const SIBLING_TOKEN: u8 = 0;
+    const NATIVE: bool = false;
+
+    fn is_included(
+        commitment: H256,
+        output_ref: &OutputRef,
+        output: &Output,
+        proof: &bridge::InclusionProof,
+    ) -> bool {
+        BridgeConfig0::is_included(commitment, output_ref, output, proof)
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The configuration of token 3, a debt token minted against token 0
+pub struct CdpConfig03;
+impl cdp::CdpConfig for CdpConfig03 {
+    type Verifier = OuterVerifier;
+    type Collateral = money::Coin<0>;
+    type Debt = money::Coin<3>;
+
+    fn vault_verifier() -> OuterVerifier {
+        OuterVerifier::UpForGrabs(UpForGrabs)
+    }
+
+    // The oracle publishes the price through the governance piece.
+    fn collateral_price() -> Option<dex::Price> {
+        governance::current::<cdp::PriceFeed<Self>>().map(|feed| feed.price)
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The configuration of the market that lends token 1 against token 0
+pub struct LendingConfig01;
+impl lending::LendingConfig for LendingConfig01 {
+    type Verifier = OuterVerifier;
+    type Market = DexConfig01;
+
+    fn open_verifier() -> OuterVerifier {
+        OuterVerifier::UpForGrabs(UpForGrabs)
+    }
+
+    // The market's own average price, once it covers a whole window. Until then, the
+    // oracle publishes the price through the governance piece.
+    fn collateral_price() -> Option<dex::Price> {
+        market_twap()
+            .or_else(|| governance::current::<lending::PriceFeed<Self>>().map(|feed| feed.price))
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The vesting configuration for token 0
+pub struct VestingConfig0;
+impl vesting::VestingConfig for VestingConfig0 {
+    type Verifier = OuterVerifier;
+    type Token = money::Coin<0>;
+
+    fn block_number() -> u32 {
+        Runtime::block_number()
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The vesting configuration for token 1
+pub struct VestingConfig1;
+impl vesting::VestingConfig for VestingConfig1 {
+    type Verifier = OuterVerifier;
+    type Token = money::Coin<1>;
+
+    fn block_number() -> u32 {
+        Runtime::block_number()
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The configuration of payment streams in token 0
+pub struct StreamConfig0;
+impl stream::StreamConfig for StreamConfig0 {
+    type Verifier = OuterVerifier;
+    type Token = money::Coin<0>;
+
+    fn open_verifier() -> OuterVerifier {
+        OuterVerifier::UpForGrabs(UpForGrabs)
+    }
+
+    fn block_number() -> u32 {
+        Runtime::block_number()
+    }
 }
 
-// TODO Declare a configuration for our dex here.
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The configuration of multisig proposals, which trading desks use to approve large orders
+pub struct DeskConfig;
+impl multisig::MultisigConfig for DeskConfig {
+    type Verifier = OuterVerifier;
+
+    fn open_verifier() -> OuterVerifier {
+        OuterVerifier::UpForGrabs(UpForGrabs)
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The configuration of social recovery, which hands the coins of recoverable verifiers
+/// to new owners
+pub struct SocialRecoveryConfig;
+impl recovery::RecoveryConfig for SocialRecoveryConfig {
+    type Verifier = OuterVerifier;
+
+    fn open_verifier() -> OuterVerifier {
+        OuterVerifier::UpForGrabs(UpForGrabs)
+    }
+
+    fn block_number() -> u32 {
+        Runtime::block_number()
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The configuration of the faucet of token 0, which hands out test coins
+pub struct FaucetConfig0;
+impl faucet::FaucetConfig for FaucetConfig0 {
+    type Verifier = OuterVerifier;
+    type Coin = money::Coin<0>;
+
+    const MAX_DRIP: u128 = 10_000;
+    const COOLDOWN: u32 = 100;
+
+    fn open_verifier() -> OuterVerifier {
+        OuterVerifier::UpForGrabs(UpForGrabs)
+    }
+
+    fn block_number() -> u32 {
+        Runtime::block_number()
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The configuration of the faucet of token 1, which hands out test coins
+pub struct FaucetConfig1;
+impl faucet::FaucetConfig for FaucetConfig1 {
+    type Verifier = OuterVerifier;
+    type Coin = money::Coin<1>;
+
+    const MAX_DRIP: u128 = 10_000;
+    const COOLDOWN: u32 = 100;
+
+    fn open_verifier() -> OuterVerifier {
+        OuterVerifier::UpForGrabs(UpForGrabs)
+    }
+
+    fn block_number() -> u32 {
+        Runtime::block_number()
+    }
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+/// The configuration of the average price of token 0 in token 1, observed in the market
+/// between them
+///
+/// Pieces that value token 0 in token 1 may read it with [`market_twap`].
+pub struct TwapConfig01;
+impl twap::TwapConfig for TwapConfig01 {
+    type Verifier = OuterVerifier;
+    type Market = DexConfig01;
+
+    fn open_verifier() -> OuterVerifier {
+        OuterVerifier::UpForGrabs(UpForGrabs)
+    }
+
+    fn now() -> u64 {
+        Runtime::context().timestamp
+    }
+
+    fn accumulator() -> Option<twap::Accumulator> {
+        twap::recorded_accumulator::<Self>()
+    }
+}
 
 /// A constraint checker is a piece of logic that can be used to check a transaction.
 /// For any given Tuxedo runtime there is a finite set of such constraint checkers.
@@ -210,14 +1019,350 @@ pub enum OuterConstraintChecker {
     Money(money::MoneyConstraintChecker<0>),
     /// Upgrade the Wasm Runtime
     RuntimeUpgrade(runtime_upgrade::RuntimeUpgrade),
-    // TODO add a third variant here to represent a second token.
-    // Your new variant should be called `SecondToken` and should use token id 1.
-    // The money piece is "instantiable" which means we can add multiple instances of it to
-    // a single runtime. This is accomplished by the generic constant.
+    /// Checks monetary transactions in a second fungible cryptocurrency
+    SecondToken(money::MoneyConstraintChecker<1>),
+    /// Open an order offering token 0 in exchange for token 1
+    MakeOrder01(dex::MakeOrder<DexConfig01>),
+    /// Open an order offering token 1 in exchange for token 0
+    MakeOrder10(dex::MakeOrder<dex::OppositeSide<DexConfig01>>),
+    /// Match orders between tokens 0 and 1 against one another
+    MatchOrders(dex::MatchOrders<DexConfig01>),
+    /// Cancel an order offering token 0 and take the token back
+    CancelOrder01(dex::CancelOrder<DexConfig01>),
+    /// Cancel an order offering token 1 and take the token back
+    CancelOrder10(dex::CancelOrder<dex::OppositeSide<DexConfig01>>),
+    /// Open an iceberg order offering token 0 in exchange for token 1
+    MakeIceberg01(dex::MakeIceberg<DexConfig01>),
+    /// Open an iceberg order offering token 1 in exchange for token 0
+    MakeIceberg10(dex::MakeIceberg<dex::OppositeSide<DexConfig01>>),
+    /// Match the visible tranche of an iceberg order offering token 0
+    MatchIceberg01(dex::MatchIceberg<DexConfig01>),
+    /// Match the visible tranche of an iceberg order offering token 1
+    MatchIceberg10(dex::MatchIceberg<dex::OppositeSide<DexConfig01>>),
+    /// Commit to a hidden order offering token 0 in exchange for token 1
+    CommitOrder01(dex::CommitOrder<DexConfig01>),
+    /// Commit to a hidden order offering token 1 in exchange for token 0
+    CommitOrder10(dex::CommitOrder<dex::OppositeSide<DexConfig01>>),
+    /// Reveal a committed order offering token 0 in exchange for token 1
+    RevealOrder01(dex::RevealOrder<DexConfig01>),
+    /// Reveal a committed order offering token 1 in exchange for token 0
+    RevealOrder10(dex::RevealOrder<dex::OppositeSide<DexConfig01>>),
+    /// Take back token 0 from an order commitment that was never revealed
+    ReclaimCommitment01(dex::ReclaimCommitment<DexConfig01>),
+    /// Take back token 1 from an order commitment that was never revealed
+    ReclaimCommitment10(dex::ReclaimCommitment<dex::OppositeSide<DexConfig01>>),
+    /// Breed and transfer kitties
+    Kitties(kitties::KittiesConstraintChecker),
+    /// List a kitty for sale in exchange for token 0
+    ListKitty(dex::ListNft<KittyDexConfig>),
+    /// Buy a listed kitty with token 0
+    BuyKitty(dex::BuyNft<KittyDexConfig>),
+    /// Claim and revoke the existence of documents
+    Poe(poe::PoeConstraintChecker),
+    /// Set the time of the current block. Only valid as an inherent.
+    Timestamp(timestamp::SetTimestamp),
+    /// Update the dex parameters with the approval of the council
+    UpdateDexParameters(governance::UpdateParameters<dex::DexParameters>),
+    /// Pause or resume trading between tokens 0 and 1 with the approval of the admin
+    UpdateTradingStatus01(governance::UpdateParameters<dex::TradingStatus<DexConfig01>>),
+    /// List a pair for trading with the approval of the council
+    ListPair(dex::ListPair),
+    /// Checks monetary transactions in token 2, which wraps the sibling chain's token 0
+    WrappedToken(money::MoneyConstraintChecker<2>),
+    /// Lock token 0 and send it to the sibling chain
+    SendToSibling0(bridge::SendToSibling<BridgeConfig0>),
+    /// Release token 0 that the sibling chain sent back
+    ReceiveFromSibling0(bridge::ReceiveFromSibling<BridgeConfig0>),
+    /// Burn token 2 and send the sibling chain's token 0 back to it
+    SendToSibling2(bridge::SendToSibling<BridgeConfig2>),
+    /// Mint token 2 for token 0 that the sibling chain sent
+    ReceiveFromSibling2(bridge::ReceiveFromSibling<BridgeConfig2>),
+    /// Tell the bridge about a newer sibling block with the approval of the relayers
+    RelaySiblingHeader(bridge::RelaySiblingHeader),
+    /// Checks monetary transactions in token 3, the debt token minted against token 0
+    DebtToken(money::MoneyConstraintChecker<3>),
+    /// Lock token 0 in a vault and mint token 3 against it
+    OpenVault03(cdp::OpenVault<CdpConfig03>),
+    /// Change the collateral and debt of a vault with the approval of its owner
+    AdjustVault03(cdp::AdjustVault<CdpConfig03>),
+    /// Repay the debt of an undercollateralized vault and take its collateral
+    Liquidate03(cdp::Liquidate<CdpConfig03>),
+    /// Update the price of token 0 in token 3 with the approval of the oracle
+    UpdateCollateralPrice03(governance::UpdateParameters<cdp::PriceFeed<CdpConfig03>>),
+    /// Deposit token 1 in the lending pool
+    Supply01(lending::Supply<LendingConfig01>),
+    /// Withdraw deposits of token 1 with their interest from the lending pool
+    Withdraw01(lending::Withdraw<LendingConfig01>),
+    /// Borrow token 1 from the lending pool against token 0
+    Borrow01(lending::Borrow<LendingConfig01>),
+    /// Repay a loan of token 1 and take back its token 0
+    Repay01(lending::Repay<LendingConfig01>),
+    /// Repay an undercollateralized loan and sell its token 0 in the dex
+    Liquidate01(lending::Liquidate<LendingConfig01>),
+    /// Update the price of token 0 in token 1 for loans with the approval of the oracle
+    UpdateLendingPrice01(governance::UpdateParameters<lending::PriceFeed<LendingConfig01>>),
+    /// Lock token 0 so that it unlocks over a schedule
+    LockCoins0(vesting::LockCoins<VestingConfig0>),
+    /// Release token 0 that has unlocked
+    Release0(vesting::Release<VestingConfig0>),
+    /// Lock token 1 so that it unlocks over a schedule
+    LockCoins1(vesting::LockCoins<VestingConfig1>),
+    /// Release token 1 that has unlocked
+    Release1(vesting::Release<VestingConfig1>),
+    /// Lock token 0 in a stream that pays it out block by block
+    OpenStream0(stream::OpenStream<StreamConfig0>),
+    /// Pay out the token 0 that has accrued in a stream
+    WithdrawFromStream0(stream::WithdrawFromStream<StreamConfig0>),
+    /// Stop a stream of token 0 with the approval of its payer
+    CancelStream0(stream::CancelStream<StreamConfig0>),
+    /// Checks monetary transactions in the registered assets
+    MultiAsset(money::MultiAssetConstraintChecker),
+    /// Open an order offering asset A in exchange for asset B
+    MakeAssetOrderAB(dex::MakeOrder<AssetDexConfig>),
+    /// Open an order offering asset B in exchange for asset A
+    MakeAssetOrderBA(dex::MakeOrder<dex::OppositeSide<AssetDexConfig>>),
+    /// Match orders between assets A and B against one another
+    MatchAssetOrders(dex::MatchOrders<AssetDexConfig>),
+    /// Cancel an order offering asset A and take the asset back
+    CancelAssetOrderAB(dex::CancelOrder<AssetDexConfig>),
+    /// Cancel an order offering asset B and take the asset back
+    CancelAssetOrderBA(dex::CancelOrder<dex::OppositeSide<AssetDexConfig>>),
+    /// Propose a transaction for several signatories to approve
+    ProposeTransaction(multisig::ProposeTransaction<DeskConfig>),
+    /// Add the approvals of more signatories to a proposal
+    ApproveProposal(multisig::ApproveProposal<DeskConfig>),
+    /// Discard a proposal with the approval of one of its signatories
+    DiscardProposal(multisig::DiscardProposal<DeskConfig>),
+    /// Ask to hand a recoverable verifier's coins to a new owner with the approval of guardians
+    StartRecovery(recovery::StartRecovery<SocialRecoveryConfig>),
+    /// Add the approvals of more guardians to a recovery
+    ApproveRecovery(recovery::ApproveRecovery<SocialRecoveryConfig>),
+    /// Stop a recovery with the approval of the owner, before its delay has passed
+    CancelRecovery(recovery::CancelRecovery<SocialRecoveryConfig>),
+    /// Sweep expired or out of bond orders between tokens 0 and 1 off the book
+    SweepExpired(dex::SweepExpired<DexConfig01>),
+    /// Sweep expired or out of bond orders between assets A and B off the book
+    SweepExpiredAssetOrders(dex::SweepExpired<AssetDexConfig>),
+    /// Mint test coins of token 0 from its faucet
+    Drip0(faucet::Drip<FaucetConfig0>),
+    /// Mint test coins of token 1 from its faucet
+    Drip1(faucet::Drip<FaucetConfig1>),
+    /// Observe the average price of token 0 in token 1 in its oracle
+    ObserveTwap01(twap::Observe<TwapConfig01>),
+    /// Buy token 0 auctioned off by a liquidation, with token 1
+    BuyAuction01(dex::BuyAuction<DexConfig01>),
+    /// Claim the rebates earned by making orders between tokens 0 and 1
+    ClaimRebate01(dex::ClaimRebate<DexConfig01>),
+    /// Claim the rebates earned by making orders between assets A and B
+    ClaimAssetRebate(dex::ClaimRebate<AssetDexConfig>),
+}
+
+impl OuterConstraintChecker {
+    /// The `Cash` type ids that this constraint checker is allowed to create out of thin
+    /// air, given the UTXOs that its transaction spends.
+    ///
+    /// Transactions using these checkers are exempt from the runtime-level value
+    /// conservation check for these types only. See the [`conservation`] module.
+    pub fn may_mint(&self, inputs: &[Output]) -> Vec<[u8; 4]> {
+        match self {
+            Self::Money(money::MoneyConstraintChecker::Mint)
+            | Self::ReceiveFromSibling0(_)
+            | Self::Drip0(_) => vec![<money::Coin<0> as UtxoData>::TYPE_ID],
+            Self::SecondToken(money::MoneyConstraintChecker::Mint) | Self::Drip1(_) => {
+                vec![<money::Coin<1> as UtxoData>::TYPE_ID]
+            }
+            Self::ReceiveFromSibling2(_) => vec![<money::Coin<2> as UtxoData>::TYPE_ID],
+            // An asset is minted by spending its info.
+            Self::MultiAsset(money::MultiAssetConstraintChecker::Mint) => inputs
+                .iter()
+                .filter_map(|input| input.payload.extract::<money::AssetInfo>().ok())
+                .map(|info| money::multi_asset::asset_type_id(info.id))
+                .collect(),
+            // Vaults mint their debt, but still hold all of their collateral.
+            Self::OpenVault03(_) | Self::AdjustVault03(_) => {
+                vec![<money::Coin<3> as UtxoData>::TYPE_ID]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether this constraint checker matches dex orders against each other.
+    pub fn is_match(&self) -> bool {
+        matches!(
+            self,
+            Self::MatchOrders(_)
+                | Self::MatchIceberg01(_)
+                | Self::MatchIceberg10(_)
+                | Self::MatchAssetOrders(_)
+        )
+    }
+}
+
+/// All the kinds of data that UTXOs in this runtime may hold.
+///
+/// This gives a typed view of any UTXO's payload, routed by its `TYPE_ID`.
+#[derive(TuxedoData)]
+pub enum OuterData {
+    Coin0(money::Coin<0>),
+    Coin1(money::Coin<1>),
+    MintAuthority0(money::MintAuthority<0>),
+    MintAuthority1(money::MintAuthority<1>),
+    Order01(dex::Order<DexConfig01>),
+    Order10(dex::Order<dex::OppositeSide<DexConfig01>>),
+    Iceberg01(dex::IcebergOrder<DexConfig01>),
+    Iceberg10(dex::IcebergOrder<dex::OppositeSide<DexConfig01>>),
+    Commitment01(dex::OrderCommitment<DexConfig01>),
+    Commitment10(dex::OrderCommitment<dex::OppositeSide<DexConfig01>>),
+    Kitty(kitties::KittyData),
+    KittyListing(dex::NftOrder<KittyDexConfig>),
+    Claim(poe::ClaimData),
+    Timestamp(timestamp::Timestamp),
+    DexParameters(dex::DexParameters),
+    TradingStatus01(dex::TradingStatus<DexConfig01>),
+    PairRegistry(dex::PairRegistry),
+    Coin2(money::Coin<2>),
+    Outbound0(bridge::Outbound<BridgeConfig0>),
+    Outbound2(bridge::Outbound<BridgeConfig2>),
+    SiblingHeader(bridge::SiblingHeader),
+    Coin3(money::Coin<3>),
+    Vault03(cdp::Vault<CdpConfig03>),
+    PriceFeed03(cdp::PriceFeed<CdpConfig03>),
+    LendingPool01(lending::Pool<LendingConfig01>),
+    Deposit01(lending::Deposit<LendingConfig01>),
+    Loan01(lending::Loan<LendingConfig01>),
+    LendingPriceFeed01(lending::PriceFeed<LendingConfig01>),
+    Vested0(vesting::Vested<money::Coin<0>>),
+    Vested1(vesting::Vested<money::Coin<1>>),
+    Stream0(stream::Stream<StreamConfig0>),
+    MultiAsset(money::MultiAsset),
+    AssetInfo(money::AssetInfo),
+    AssetOrderAB(dex::Order<AssetDexConfig>),
+    AssetOrderBA(dex::Order<dex::OppositeSide<AssetDexConfig>>),
+    Proposal(multisig::Proposal<DeskConfig>),
+    Recovery(recovery::Recovery<SocialRecoveryConfig>),
+    Faucet0(faucet::Faucet<FaucetConfig0>),
+    Faucet1(faucet::Faucet<FaucetConfig1>),
+    TwapOracle01(twap::TwapOracle<TwapConfig01>),
+    Auction01(dex::DutchAuction<DexConfig01>),
+    RebatePool01(dex::RebatePool<DexConfig01>),
+    MakerCredit01(dex::MakerCredit<DexConfig01>),
+    AssetRebatePool(dex::RebatePool<AssetDexConfig>),
+    AssetMakerCredit(dex::MakerCredit<AssetDexConfig>),
+}
 
-    // TODO add a fourth and fifth variant here to represent making a dex orders.
+/// The `Cash` type id and value of a payload if it is, or holds, any of the
+/// `Cash` types used in this runtime.
+///
+/// Open dex orders, including iceberg orders and order commitments, count as holding
+/// their collateral so that making and matching orders conserves value. The collateral of
+/// a plain order is its offer and its bond.
+pub fn cash_value(payload: &DynamicallyTypedData) -> Option<([u8; 4], u128)> {
+    match OuterData::try_from(payload).ok()? {
+        OuterData::Coin0(coin) => Some((<money::Coin<0> as UtxoData>::TYPE_ID, coin.value())),
+        OuterData::Coin1(coin) => Some((<money::Coin<1> as UtxoData>::TYPE_ID, coin.value())),
+        OuterData::Coin2(coin) => Some((<money::Coin<2> as UtxoData>::TYPE_ID, coin.value())),
+        OuterData::Coin3(coin) => Some((<money::Coin<3> as UtxoData>::TYPE_ID, coin.value())),
+        OuterData::Order01(order) => Some((
+            <money::Coin<0> as UtxoData>::TYPE_ID,
+            order.offer_amount.saturating_add(order.bond),
+        )),
+        OuterData::Order10(order) => Some((
+            <money::Coin<1> as UtxoData>::TYPE_ID,
+            order.offer_amount.saturating_add(order.bond),
+        )),
+        OuterData::Iceberg01(order) => {
+            Some((<money::Coin<0> as UtxoData>::TYPE_ID, order.collateral))
+        }
+        OuterData::Iceberg10(order) => {
+            Some((<money::Coin<1> as UtxoData>::TYPE_ID, order.collateral))
+        }
+        OuterData::Commitment01(commitment) => {
+            Some((<money::Coin<0> as UtxoData>::TYPE_ID, commitment.collateral))
+        }
+        OuterData::Commitment10(commitment) => {
+            Some((<money::Coin<1> as UtxoData>::TYPE_ID, commitment.collateral))
+        }
+        OuterData::MintAuthority0(_)
+        | OuterData::MintAuthority1(_)
+        | OuterData::Kitty(_)
+        | OuterData::KittyListing(_)
+        | OuterData::Claim(_)
+        | OuterData::Timestamp(_)
+        | OuterData::DexParameters(_)
+        | OuterData::TradingStatus01(_)
+        | OuterData::PairRegistry(_)
+        // Transfers to the sibling hold value that has left this chain.
+        | OuterData::Outbound0(_)
+        | OuterData::Outbound2(_)
+        | OuterData::SiblingHeader(_) => None,
+        // Vaults hold their collateral. The debt they back is minted, not held.
+        OuterData::Vault03(vault) => {
+            Some((<money::Coin<0> as UtxoData>::TYPE_ID, vault.collateral))
+        }
+        OuterData::PriceFeed03(_) => None,
+        // The pool holds the lent token that is not borrowed, and loans their collateral.
+        // Deposits are claims on the pool rather than value of their own.
+        OuterData::LendingPool01(pool) => {
+            Some((<money::Coin<1> as UtxoData>::TYPE_ID, pool.cash))
+        }
+        OuterData::Loan01(loan) => {
+            Some((<money::Coin<0> as UtxoData>::TYPE_ID, loan.collateral))
+        }
+        OuterData::Deposit01(_) | OuterData::LendingPriceFeed01(_) => None,
+        // Vested coins hold what they have not released yet, whether or not it has unlocked.
+        OuterData::Vested0(vested) => Some((<money::Coin<0> as UtxoData>::TYPE_ID, vested.held())),
+        OuterData::Vested1(vested) => Some((<money::Coin<1> as UtxoData>::TYPE_ID, vested.held())),
+        // Streams hold what has not been withdrawn yet.
+        OuterData::Stream0(stream) => Some((
+            <money::Coin<0> as UtxoData>::TYPE_ID,
+            stream.total.saturating_sub(stream.withdrawn),
+        )),
+        // Each registered asset is accounted for separately.
+        OuterData::MultiAsset(coin) => Some((money::multi_asset::asset_type_id(coin.asset), coin.value)),
+        OuterData::AssetOrderAB(order) => Some((
+            money::multi_asset::asset_type_id(asset_pair().0),
+            order.offer_amount.saturating_add(order.bond),
+        )),
+        OuterData::AssetOrderBA(order) => Some((
+            money::multi_asset::asset_type_id(asset_pair().1),
+            order.offer_amount.saturating_add(order.bond),
+        )),
+        OuterData::AssetInfo(_) | OuterData::Proposal(_) | OuterData::Recovery(_) => None,
+        // Faucets mint the coins they hand out, so they hold nothing.
+        OuterData::Faucet0(_) | OuterData::Faucet1(_) => None,
+        OuterData::TwapOracle01(_) => None,
+        // Auctions hold their lot until it is bought.
+        OuterData::Auction01(auction) => {
+            Some((<money::Coin<0> as UtxoData>::TYPE_ID, auction.lot))
+        }
+        // Rebate pools hold fees in both tokens of their pair. See `cash_values`. Maker
+        // credits are claims on a pool rather than value of their own.
+        OuterData::RebatePool01(_)
+        | OuterData::AssetRebatePool(_)
+        | OuterData::MakerCredit01(_)
+        | OuterData::AssetMakerCredit(_) => None,
+    }
+}
 
-    // TODO add a sixth variant here to represent matching dex orders together.
+/// The `Cash` type ids and values of everything a payload is, or holds.
+///
+/// This is [`cash_value`], except that the dex's rebate pools hold the fees they collected
+/// in both tokens of their pair.
+pub fn cash_values(payload: &DynamicallyTypedData) -> Vec<([u8; 4], u128)> {
+    match OuterData::try_from(payload) {
+        Ok(OuterData::RebatePool01(pool)) => vec![
+            (<money::Coin<0> as UtxoData>::TYPE_ID, pool.fees_a),
+            (<money::Coin<1> as UtxoData>::TYPE_ID, pool.fees_b),
+        ],
+        Ok(OuterData::AssetRebatePool(pool)) => {
+            let (asset_a, asset_b) = asset_pair();
+            vec![
+                (money::multi_asset::asset_type_id(asset_a), pool.fees_a),
+                (money::multi_asset::asset_type_id(asset_b), pool.fees_b),
+            ]
+        }
+        _ => cash_value(payload).into_iter().collect(),
+    }
 }
 
 /// The main struct in this module.
@@ -229,6 +1374,440 @@ pub struct Runtime;
 // in a `--dev` node. You may enable more authorities to test more interesting networks, or replace
 // these IDs entirely.
 impl Runtime {
+    /// The number of the block that transactions are currently being checked in.
+    ///
+    /// During block authoring and import this is the number of the block being processed.
+    /// During transaction pool validation it is the number of the next block, which is the
+    /// earliest block the transaction could be included in.
+    pub fn block_number() -> BlockNumber {
+        sp_io::storage::get(BLOCK_NUMBER_KEY)
+            .and_then(|encoded| BlockNumber::decode(&mut &encoded[..]).ok())
+            .unwrap_or_default()
+    }
+
+    /// The chain context that pieces may observe while checking transactions.
+    ///
+    /// The timestamp is that of the most recent block whose timestamp inherent has been
+    /// applied. While pool-validating, that is the current best block rather than the
+    /// block the transaction will eventually be included in.
+    pub fn context() -> dex::ConstraintCheckingContext {
+        dex::ConstraintCheckingContext {
+            block_number: Self::block_number(),
+            timestamp: timestamp::current()
+                .map(|timestamp| timestamp.time)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The hash of this chain's genesis block.
+    ///
+    /// Signatures made with the runtime's own verifiers commit to this hash so that
+    /// they cannot be replayed on other chains. See [`verifiers::signing_payload`].
+    pub fn genesis_hash() -> H256 {
+        sp_io::storage::get(GENESIS_HASH_KEY)
+            .and_then(|encoded| H256::decode(&mut &encoded[..]).ok())
+            .unwrap_or_default()
+    }
+
+    /// Record the hash of the genesis block, unless it is already known.
+    fn record_genesis_hash(hash: H256) {
+        if !sp_io::storage::exists(GENESIS_HASH_KEY) {
+            sp_io::storage::set(GENESIS_HASH_KEY, &hash.encode());
+        }
+    }
+
+    /// Prepare the runtime-level state for the block with the given header.
+    fn begin_block(header: &Header) {
+        Self::set_block_number(header.number);
+        journal::prune(header.number);
+        migrations::on_block();
+        if header.number == 1 {
+            Self::record_genesis_hash(header.parent_hash);
+        }
+    }
+
+    /// Record the number of the block that transactions are about to be checked in.
+    fn set_block_number(number: BlockNumber) {
+        sp_io::storage::set(BLOCK_NUMBER_KEY, &number.encode());
+    }
+
+    /// Where the latest timestamp UTXO is stored.
+    fn timestamp_utxo() -> Option<OutputRef> {
+        sp_io::storage::get(TIMESTAMP_UTXO_KEY)
+            .and_then(|encoded| OutputRef::decode(&mut &encoded[..]).ok())
+    }
+
+    /// Build the inherent transaction that replaces the previous timestamp with the given time.
+    pub fn timestamp_inherent(time: u64) -> Option<Transaction> {
+        let previous = Self::timestamp_utxo()?;
+        let new = timestamp::Timestamp {
+            time,
+            block: Self::block_number(),
+        };
+
+        Some(Transaction {
+            inputs: vec![Input {
+                output_ref: previous,
+                redeemer: Vec::new(),
+            }],
+            outputs: vec![Output {
+                verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                payload: new.into(),
+            }],
+            checker: OuterConstraintChecker::Timestamp(timestamp::SetTimestamp),
+        })
+    }
+
+    /// The timestamp set by a transaction, if it is a timestamp inherent.
+    fn timestamp_set_by(transaction: &Transaction) -> Option<timestamp::Timestamp> {
+        match transaction.checker {
+            OuterConstraintChecker::Timestamp(_) => transaction
+                .outputs
+                .first()
+                .and_then(|output| output.payload.extract().ok()),
+            _ => None,
+        }
+    }
+
+    /// Fetch a UTXO from storage without consuming it.
+    fn peek_utxo(output_ref: &OutputRef) -> Option<Output> {
+        sp_io::storage::get(&output_ref.encode())
+            .and_then(|encoded| Output::decode(&mut &encoded[..]).ok())
+    }
+
+    /// Fetch the UTXOs that a transaction's inputs spend, where they exist.
+    ///
+    /// The runtime-level checks, the weight, the journal and the rejection reason all need
+    /// these, so they are read once per transaction and shared, rather than each of them
+    /// reading every input again. Each call to the runtime starts from fresh memory, so
+    /// they cannot be kept any longer than that.
+    fn peek_inputs(transaction: &Transaction) -> Vec<Option<Output>> {
+        transaction
+            .inputs
+            .iter()
+            .map(|input| Self::peek_utxo(&input.output_ref))
+            .collect()
+    }
+
+    /// Checks that apply to every transaction regardless of which piece it belongs to.
+    ///
+    /// These run when authoring, importing, and pool-validating transactions, before the
+    /// transaction is handed to the Tuxedo executive. They are given the UTXOs that the
+    /// inputs spend, from [`Runtime::peek_inputs`].
+    fn runtime_checks(
+        transaction: &Transaction,
+        peeked: &[Option<Output>],
+    ) -> Result<(), TransactionValidityError> {
+        let length = transaction.encoded_size();
+        if length > MAX_TRANSACTION_LENGTH {
+            log::warn!(target: LOG_TARGET, "Transaction is {} bytes long", length);
+            return Err(InvalidTransaction::ExhaustsResources.into());
+        }
+
+        if let Some(new) = Self::timestamp_set_by(transaction) {
+            if new.block != Self::block_number() {
+                log::warn!(target: LOG_TARGET, "Timestamp is for block {}", new.block);
+                return Err(InvalidTransaction::Custom(TIMESTAMP_WRONG_BLOCK).into());
+            }
+        }
+
+        // The executive rejects these as well, but the runtime reports them with a code of
+        // its own, so that wallets and node operators can tell them apart from other bugs.
+        let inputs = &transaction.inputs;
+        let duplicate = (1..inputs.len()).find(|&index| {
+            inputs[..index]
+                .iter()
+                .any(|earlier| earlier.output_ref == inputs[index].output_ref)
+        });
+        if let Some(index) = duplicate {
+            log::warn!(target: LOG_TARGET, "Input {} is spent twice", index);
+            return Err(InvalidTransaction::Custom(DUPLICATE_INPUT).into());
+        }
+
+        tips::find_tip(&transaction.outputs).map_err(|e| {
+            log::warn!(target: LOG_TARGET, "Invalid tip: {:?}", e);
+            InvalidTransaction::Custom(INVALID_TIP)
+        })?;
+
+        // If some inputs are missing the executive will reject the transaction,
+        // or mark it as future in the pool, so there is nothing to check yet.
+        let maybe_inputs: Option<Vec<Output>> = peeked.iter().cloned().collect();
+
+        if let Some(inputs) = maybe_inputs {
+            let weight = weights::transaction_weight(transaction, &inputs);
+            if weight > weights::MAX_BLOCK_WEIGHT {
+                log::warn!(target: LOG_TARGET, "Transaction weighs {}", weight);
+                return Err(InvalidTransaction::ExhaustsResources.into());
+            }
+
+            let mintable = transaction.checker.may_mint(&inputs);
+            conservation::check(&inputs, &transaction.outputs, &mintable, cash_values).map_err(
+                |e| {
+                    log::warn!(target: LOG_TARGET, "Value conservation violated: {:?}", e);
+                    InvalidTransaction::Custom(VALUE_NOT_CONSERVED)
+                },
+            )?;
+
+            canonical::check(&transaction.inputs, &inputs).map_err(|e| {
+                log::warn!(target: LOG_TARGET, "Orders are not in canonical order: {:?}", e);
+                InvalidTransaction::Custom(ORDERS_NOT_CANONICAL)
+            })?;
+
+            if let Some(limit) = order_limits::MAX_OPEN_ORDERS_PER_OWNER {
+                order_limits::check(&inputs, &transaction.outputs, limit).map_err(|e| {
+                    log::warn!(target: LOG_TARGET, "Open order limit violated: {:?}", e);
+                    InvalidTransaction::Custom(ORDER_LIMIT_VIOLATED)
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply an extrinsic after performing the runtime-level checks.
+    pub fn apply_extrinsic(transaction: Transaction) -> ApplyExtrinsicResult {
+        let peeked = Self::peek_inputs(&transaction);
+        Self::runtime_checks(&transaction, &peeked)?;
+        let is_timestamp = matches!(transaction.checker, OuterConstraintChecker::Timestamp(_));
+        let tip = tips::find_tip(&transaction.outputs)
+            .ok()
+            .flatten()
+            .map(|(index, _)| transaction.output_ref(index));
+        let output_ref = transaction.output_ref(0);
+        let spent: Vec<(OutputRef, Output)> = transaction
+            .inputs
+            .iter()
+            .zip(peeked.iter().cloned())
+            .filter_map(|(input, utxo)| Some((input.output_ref.clone(), utxo?)))
+            .collect();
+
+        let weight = weights::transaction_weight(&transaction, spent.iter().map(|(_, utxo)| utxo));
+        if !weights::fits(weight) {
+            log::warn!(target: LOG_TARGET, "Transaction weighs {}, more than is left", weight);
+            return Err(InvalidTransaction::ExhaustsResources.into());
+        }
+
+        let result = Executive::apply_extrinsic(transaction.clone());
+        if result.is_err() {
+            if let Some(reason) = Self::rejection_reason(&transaction, &peeked) {
+                return Err(reason.into());
+            }
+        }
+        if matches!(result, Ok(Ok(()))) {
+            witness::segregate(&transaction);
+            utxo_set::apply(&transaction);
+            migrations::note_created(&transaction);
+            weights::record(weight);
+            order_limits::apply(spent.iter().map(|(_, utxo)| utxo), &transaction.outputs);
+            stats::record(&transaction, &spent, Self::context().timestamp);
+            record_twap_trades(&transaction, &spent, Self::context().timestamp);
+            journal::record(Self::block_number(), spent);
+            if is_timestamp {
+                sp_io::storage::set(TIMESTAMP_UTXO_KEY, &output_ref.encode());
+            }
+            // A drip recreates its faucet as its first output.
+            let dripped = match transaction.checker {
+                OuterConstraintChecker::Drip0(_) => Some(0),
+                OuterConstraintChecker::Drip1(_) => Some(1),
+                _ => None,
+            };
+            if let Some(coin_id) = dripped {
+                sp_io::storage::set(&faucet_utxo_key(coin_id), &output_ref.encode());
+            }
+            // Matches that pay a fee recreate the open rebate pool among their outputs.
+            for (index, output) in transaction.outputs.iter().enumerate() {
+                if let Some(key) = open_rebate_pool_key(output) {
+                    sp_io::storage::set(&key, &transaction.output_ref(index as u32).encode());
+                }
+            }
+            // An observation recreates its oracle as its only output.
+            if matches!(
+                transaction.checker,
+                OuterConstraintChecker::ObserveTwap01(_)
+            ) {
+                sp_io::storage::set(TWAP_ORACLE_UTXO_KEY, &output_ref.encode());
+            }
+            if let Some(tip) = tip {
+                tips::record_pending(tip);
+            }
+        }
+        result
+    }
+
+    /// Validate a transaction for the pool after performing the runtime-level checks.
+    fn validate_transaction(
+        source: TransactionSource,
+        transaction: Transaction,
+        block_hash: <Block as BlockT>::Hash,
+    ) -> TransactionValidity {
+        // Transactions in the pool will be included in the next block at the earliest.
+        // These storage changes are discarded once validation completes.
+        if Self::block_number() == 0 {
+            // Validating against genesis itself, so its hash is the one we were given.
+            Self::record_genesis_hash(block_hash);
+        }
+        Self::set_block_number(Self::block_number() + 1);
+
+        // Timestamps are set by block authors as inherents. They never come from the pool.
+        if matches!(transaction.checker, OuterConstraintChecker::Timestamp(_)) {
+            return Err(InvalidTransaction::MandatoryValidation.into());
+        }
+
+        let peeked = Self::peek_inputs(&transaction);
+        Self::runtime_checks(&transaction, &peeked)?;
+
+        // An output that was spent recently will never exist again, so there is no point in
+        // keeping transactions that spend it in the pool until it does. Rejecting them
+        // outright, rather than as future transactions, also lets the node penalize the
+        // peers that keep sending them, like those replaying stale matches after a big fill.
+        let stale = transaction.inputs.iter().zip(&peeked).any(|(input, utxo)| {
+            utxo.is_none() && journal::spent_recently(&input.output_ref, Self::block_number())
+        });
+        if stale {
+            log::warn!(target: LOG_TARGET, "Transaction spends an output that was already spent");
+            return Err(InvalidTransaction::Stale.into());
+        }
+        let matches_orders = transaction.checker.is_match();
+
+        // Transactions that tip the block author are prioritized by the value of their tip.
+        let tip = tips::find_tip(&transaction.outputs)
+            .ok()
+            .flatten()
+            .map(|(_, value)| value)
+            .unwrap_or_default();
+
+        let mut valid = Executive::validate_transaction(source, transaction.clone(), block_hash)
+            .map_err(|e| Self::rejection_reason(&transaction, &peeked).map_or(e, Into::into))?;
+        // The executive tags the outputs with the hash of the entire transaction, but they
+        // will be stored under the refs made from its id.
+        valid.provides = transaction
+            .output_refs()
+            .iter()
+            .map(Encode::encode)
+            .collect();
+        valid.priority = valid
+            .priority
+            .saturating_add(tip.try_into().unwrap_or(TransactionPriority::MAX));
+        if matches_orders {
+            valid.longevity = valid.longevity.min(MATCH_LONGEVITY);
+        }
+        Ok(valid)
+    }
+
+    /// Why the constraint checker rejects a transaction that the executive rejected.
+    ///
+    /// The executive reports every checker error with the same code, so this checks the
+    /// transaction again to find the error, and reports it as described in the
+    /// [`error_codes`] module. The check runs in a storage transaction that is rolled back,
+    /// so it has no side effects, even if the executive rejected it for another reason.
+    fn rejection_reason(
+        transaction: &Transaction,
+        peeked: &[Option<Output>],
+    ) -> Option<InvalidTransaction> {
+        let inputs: Vec<Output> = peeked.iter().cloned().collect::<Option<_>>()?;
+
+        sp_io::storage::start_transaction();
+        let result = transaction.checker.check(&inputs, &transaction.outputs);
+        sp_io::storage::rollback_transaction();
+
+        let error = result.err()?;
+        log::warn!(target: LOG_TARGET, "Rejected by the constraint checker: {:?}", error);
+        Some(error.into_invalid_transaction())
+    }
+
+    /// Check that the timestamp in a block being imported is not too far ahead of our own clock.
+    fn check_inherents(
+        block: Block,
+        data: sp_inherents::InherentData,
+    ) -> sp_inherents::CheckInherentsResult {
+        let mut result = sp_inherents::CheckInherentsResult::new();
+
+        let Ok(Some(now)) =
+            data.get_data::<sp_timestamp::InherentType>(&sp_timestamp::INHERENT_IDENTIFIER)
+        else {
+            return result;
+        };
+
+        let block_time = block.extrinsics.first().and_then(Self::timestamp_set_by);
+        if let Some(block_time) = block_time {
+            if block_time.time > *now + MAX_TIMESTAMP_DRIFT {
+                result
+                    .put_error(
+                        sp_timestamp::INHERENT_IDENTIFIER,
+                        &sp_timestamp::InherentError::TooFarInFuture,
+                    )
+                    .expect("Timestamp inherent errors are small enough to encode");
+            }
+        }
+
+        result
+    }
+
+    /// Start authoring the block with the given header.
+    ///
+    /// Together with [`Runtime::timestamp_inherent`], [`Runtime::apply_extrinsic`] and
+    /// [`Runtime::finalize_block`], this is what the block builder calls through the runtime
+    /// APIs. They are public so that tests can build blocks with the real runtime.
+    pub fn initialize_block(header: &Header) {
+        Self::begin_block(header);
+        Executive::open_block(header);
+        tips::record_author(header);
+    }
+
+    /// Execute an entire block during import.
+    ///
+    /// This mirrors the executive's own `execute_block`, but routes every extrinsic through
+    /// [`Runtime::apply_extrinsic`] so that imported blocks are held to the same runtime-level
+    /// checks as the blocks we author.
+    fn execute_block(block: Block) {
+        Self::begin_block(&block.header);
+        Executive::open_block(&block.header);
+        tips::record_author(&block.header);
+
+        assert!(
+            block
+                .extrinsics
+                .first()
+                .and_then(Self::timestamp_set_by)
+                .is_some(),
+            "Block does not begin with a timestamp inherent"
+        );
+
+        for extrinsic in block.extrinsics.iter() {
+            if let Err(e) = Self::apply_extrinsic(extrinsic.clone()) {
+                panic!("Extrinsic in block failed to apply: {:?}", e);
+            }
+        }
+
+        tips::assign_to_author();
+        weights::clear();
+        let commitment = utxo_set::commitment();
+        let header = Executive::close_block();
+        assert_eq!(
+            header.extrinsics_root, block.header.extrinsics_root,
+            "Extrinsics root mismatch"
+        );
+        assert_eq!(
+            header.state_root, block.header.state_root,
+            "State root mismatch"
+        );
+        assert_eq!(
+            utxo_set::commitment_in(&block.header.digest),
+            Some(commitment),
+            "UTXO set commitment mismatch"
+        );
+    }
+
+    /// Finalize the block being authored, adding the UTXO set commitment to its header.
+    pub fn finalize_block() -> Header {
+        tips::assign_to_author();
+        weights::clear();
+        let commitment = utxo_set::commitment();
+        let mut header = Executive::close_block();
+        header.digest.push(utxo_set::digest_item(commitment));
+        header
+    }
+
     /// Aura authority IDs
     fn aura_authorities() -> Vec<AuraId> {
         use hex_literal::hex;
@@ -292,35 +1871,39 @@ impl_runtime_apis! {
         }
 
         fn execute_block(block: Block) {
-            Executive::execute_block(block)
+            Runtime::execute_block(block)
         }
 
         fn initialize_block(header: &<Block as BlockT>::Header) {
-            Executive::open_block(header)
+            Runtime::initialize_block(header)
         }
     }
 
     // https://substrate.dev/rustdocs/master/sc_block_builder/trait.BlockBuilderApi.html
     impl sp_block_builder::BlockBuilder<Block> for Runtime {
         fn apply_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> ApplyExtrinsicResult {
-            Executive::apply_extrinsic(extrinsic)
+            Runtime::apply_extrinsic(extrinsic)
         }
 
         fn finalize_block() -> <Block as BlockT>::Header {
-            Executive::close_block()
+            Runtime::finalize_block()
         }
 
-        fn inherent_extrinsics(_data: sp_inherents::InherentData) -> Vec<<Block as BlockT>::Extrinsic> {
-            // Tuxedo does not yet support inherents
-            Default::default()
+        fn inherent_extrinsics(data: sp_inherents::InherentData) -> Vec<<Block as BlockT>::Extrinsic> {
+            // The only inherent is the timestamp
+            data.get_data::<sp_timestamp::InherentType>(&sp_timestamp::INHERENT_IDENTIFIER)
+                .ok()
+                .flatten()
+                .and_then(|now| Runtime::timestamp_inherent(*now))
+                .into_iter()
+                .collect()
         }
 
         fn check_inherents(
-            _block: Block,
-            _data: sp_inherents::InherentData
+            block: Block,
+            data: sp_inherents::InherentData
         ) -> sp_inherents::CheckInherentsResult {
-            // Tuxedo does not yet support inherents
-            Default::default()
+            Runtime::check_inherents(block, data)
         }
     }
 
@@ -330,11 +1913,70 @@ impl_runtime_apis! {
             tx: <Block as BlockT>::Extrinsic,
             block_hash: <Block as BlockT>::Hash,
         ) -> TransactionValidity {
-            Executive::validate_transaction(source, tx, block_hash)
+            Runtime::validate_transaction(source, tx, block_hash)
+        }
+    }
+
+    impl journal::SpentOutputsApi<Block> for Runtime {
+        fn spent_in(block: BlockNumber) -> Vec<(OutputRef, Output)> {
+            journal::spent_in(block)
+        }
+    }
+
+    impl utxo_set::UtxoProofApi<Block> for Runtime {
+        fn prove(output_ref: OutputRef) -> Option<utxo_set::UtxoProof> {
+            utxo_set::prove(&output_ref)
+        }
+    }
+
+    impl try_state::TryStateApi<Block> for Runtime {
+        fn try_state() -> Vec<try_state::TryStateError> {
+            try_state::try_state()
+        }
+    }
+
+    impl dex_metadata::DexMetadataApi<Block> for Runtime {
+        fn dex_metadata() -> Vec<u8> {
+            dex_metadata::dex_metadata().encode()
+        }
+    }
+
+    impl dry_run::DryRunApi<Block> for Runtime {
+        fn dry_run_match(
+            transaction: Transaction,
+        ) -> Result<TransactionPriority, dry_run::DryRunError> {
+            dry_run::dry_run_match(&transaction)
         }
     }
 
-    // Tuxedo does not yet support metadata
+    impl quote::DexApi<Block> for Runtime {
+        fn quote(pair: (u8, u8), side: dex::Side, amount: u128) -> Option<quote::Quote> {
+            quote::quote(pair, side, amount)
+        }
+    }
+
+    impl stats::StatsApi<Block> for Runtime {
+        fn ticker(pair: (u8, u8)) -> Option<stats::Ticker> {
+            stats::ticker(pair)
+        }
+    }
+
+    impl sealed_matches::SealedMatchApi<Block> for Runtime {
+        fn verify_decryption(
+            ciphertext: Vec<u8>,
+            plaintext: Vec<u8>,
+            proof: sealed_matches::DecryptionProof,
+        ) -> Result<Transaction, sealed_matches::DecryptionError> {
+            sealed_matches::verify_decryption(
+                &sealed_matches::committee(),
+                &ciphertext,
+                &plaintext,
+                &proof,
+            )
+        }
+    }
+
+    // Tuxedo does not yet support metadata. See the `dex_metadata` module for the dex types.
     impl sp_api::Metadata<Block> for Runtime {
         fn metadata() -> OpaqueMetadata {
             OpaqueMetadata::new(Default::default())
@@ -443,7 +2085,7 @@ mod tests {
 
             // Grab genesis value from storage and assert it is correct
             let genesis_utxo = Output {
-                verifier: OuterVerifier::SigCheck(SigCheck {
+                verifier: OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
                     owner_pubkey: shawn_pub_key.into(),
                 }),
                 payload: DynamicallyTypedData {
@@ -452,11 +2094,7 @@ mod tests {
                 },
             };
 
-            let output_ref = OutputRef {
-                // Genesis UTXOs don't come from any real transaction, so just uze the zero hash
-                tx_hash: <Header as sp_api::HeaderT>::Hash::zero(),
-                index: 0 as u32,
-            };
+            let output_ref = genesis_output_ref(0);
 
             let encoded_utxo =
                 sp_io::storage::get(&output_ref.encode()).expect("Retrieve Genesis UTXO");
@@ -477,9 +2115,16 @@ mod tests {
                 .unwrap();
 
             let genesis_multi_sig_utxo = Output {
-                verifier: OuterVerifier::ThresholdMultiSignature(ThresholdMultiSignature {
+                verifier: OuterVerifier::Compound(CompoundVerifier::Threshold {
                     threshold: 1,
-                    signatories: vec![shawn_pub_key.into(), andrew_pub_key.into()],
+                    verifiers: vec![
+                        OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+                            owner_pubkey: shawn_pub_key.into(),
+                        }),
+                        OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+                            owner_pubkey: andrew_pub_key.into(),
+                        }),
+                    ],
                 }),
                 payload: DynamicallyTypedData {
                     data: 100u128.encode(),
@@ -487,11 +2132,7 @@ mod tests {
                 },
             };
 
-            let output_ref = OutputRef {
-                // Genesis UTXOs don't come from any real transaction, so just uze the zero hash
-                tx_hash: <Header as sp_api::HeaderT>::Hash::zero(),
-                index: 1 as u32,
-            };
+            let output_ref = genesis_output_ref(1);
 
             let encoded_utxo =
                 sp_io::storage::get(&output_ref.encode()).expect("Retrieve Genesis MultiSig UTXO");
@@ -499,4 +2140,532 @@ mod tests {
             assert_eq!(utxo, genesis_multi_sig_utxo);
         })
     }
+
+    #[test]
+    fn genesis_claims_are_indexed() {
+        new_test_ext().execute_with(|| {
+            assert!(poe::is_claimed(&H256::repeat_byte(0xaa)));
+            assert!(!poe::is_claimed(&H256::repeat_byte(0xbb)));
+        })
+    }
+
+    #[test]
+    fn genesis_dex_parameters_are_recorded() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(
+                governance::current::<dex::DexParameters>(),
+                Some(dex::DexParameters::defaults::<DexConfig01>())
+            );
+            assert_eq!(
+                <DexConfig01 as dex::DexConfig>::parameters(),
+                dex::DexParameters::defaults::<DexConfig01>()
+            );
+        })
+    }
+
+    #[test]
+    fn genesis_trading_status_is_recorded() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(
+                governance::current::<dex::TradingStatus<DexConfig01>>().map(|s| s.paused),
+                Some(false)
+            );
+            assert!(!<DexConfig01 as dex::DexConfig>::trading_paused());
+        })
+    }
+
+    #[test]
+    fn genesis_faucets_are_recorded() {
+        new_test_ext().execute_with(|| {
+            for coin_id in [0, 1] {
+                let output_ref = sp_io::storage::get(&faucet_utxo_key(coin_id))
+                    .and_then(|encoded| OutputRef::decode(&mut &encoded[..]).ok())
+                    .expect("Genesis faucet is recorded");
+                let utxo = Runtime::peek_utxo(&output_ref).expect("Faucet is in the UTXO set");
+                assert_eq!(faucet_coin(&utxo), Some(coin_id));
+            }
+        })
+    }
+
+    #[test]
+    fn genesis_asset_pair_must_trade_two_assets() {
+        new_test_ext().execute_with(|| {
+            assert!(!<AssetDexConfig as dex::DexConfig>::same_token());
+        });
+
+        let config = GenesisConfig {
+            asset_pair: (1, 1),
+            ..Default::default()
+        };
+        assert!(config.build_storage().is_err());
+    }
+
+    #[test]
+    fn genesis_twap_oracle_is_recorded() {
+        new_test_ext().execute_with(|| {
+            let output_ref = sp_io::storage::get(TWAP_ORACLE_UTXO_KEY)
+                .and_then(|encoded| OutputRef::decode(&mut &encoded[..]).ok())
+                .expect("Genesis oracle is recorded");
+            let utxo = Runtime::peek_utxo(&output_ref).expect("Oracle is in the UTXO set");
+            let oracle = utxo
+                .payload
+                .extract::<twap::TwapOracle<TwapConfig01>>()
+                .expect("Genesis oracle is an oracle");
+            assert_eq!(oracle.observations, vec![]);
+            // It has observed nothing yet, so loans are still valued at the oracle's price.
+            assert_eq!(market_twap(), None);
+        })
+    }
+
+    #[test]
+    fn genesis_rebate_pools_are_recorded() {
+        fn recorded_pool(key: Vec<u8>) -> Output {
+            sp_io::storage::get(&key)
+                .and_then(|encoded| OutputRef::decode(&mut &encoded[..]).ok())
+                .and_then(|output_ref| Runtime::peek_utxo(&output_ref))
+                .expect("Genesis pool is recorded")
+        }
+
+        new_test_ext().execute_with(|| {
+            let pool = recorded_pool(rebate_pool_utxo_key::<DexConfig01>())
+                .payload
+                .extract::<dex::RebatePool<DexConfig01>>()
+                .expect("Genesis pool of tokens 0 and 1 is a pool");
+            assert!(pool == dex::RebatePool::open(0));
+
+            let pool = recorded_pool(rebate_pool_utxo_key::<AssetDexConfig>())
+                .payload
+                .extract::<dex::RebatePool<AssetDexConfig>>()
+                .expect("Genesis pool of the assets is a pool");
+            assert!(pool == dex::RebatePool::open(0));
+        })
+    }
+
+    #[test]
+    fn genesis_storage_needs_no_migration() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(migrations::migrated_spec_version(), VERSION.spec_version);
+            migrations::on_block();
+            assert!(!migrations::is_running());
+        })
+    }
+
+    #[test]
+    fn timestamp_inherent_replaces_genesis_timestamp() {
+        new_test_ext().execute_with(|| {
+            Runtime::set_block_number(1);
+            let inherent =
+                Runtime::timestamp_inherent(3000).expect("Genesis timestamp is recorded");
+
+            assert_eq!(inherent.inputs[0].output_ref, genesis_output_ref(6));
+            assert_eq!(
+                Runtime::timestamp_set_by(&inherent),
+                Some(timestamp::Timestamp {
+                    time: 3000,
+                    block: 1
+                })
+            );
+        })
+    }
+
+    #[test]
+    fn oversized_matches_are_rejected() {
+        let match_of = |order_count: u32| Transaction {
+            inputs: (0..order_count)
+                .map(|index| Input {
+                    output_ref: OutputRef {
+                        tx_hash: H256::repeat_byte(1),
+                        index,
+                    },
+                    redeemer: [0u8; 64].to_vec(),
+                })
+                .collect(),
+            outputs: (0..order_count)
+                .map(|_| Output {
+                    verifier: OuterVerifier::SigCheck(SigCheck {
+                        owner_pubkey: H256::repeat_byte(2),
+                    }),
+                    payload: money::Coin::<0>(1).into(),
+                })
+                .collect(),
+            checker: OuterConstraintChecker::MatchOrders(Default::default()),
+        };
+
+        new_test_ext().execute_with(|| {
+            let too_long = match_of(500);
+            assert_eq!(
+                Runtime::runtime_checks(&too_long, &Runtime::peek_inputs(&too_long)),
+                Err(InvalidTransaction::ExhaustsResources.into())
+            );
+            assert!(match_of(100).encoded_size() <= MAX_TRANSACTION_LENGTH);
+        })
+    }
+
+    #[test]
+    fn inputs_cannot_be_spent_twice() {
+        let input = Input {
+            output_ref: genesis_output_ref(0),
+            redeemer: Vec::new(),
+        };
+        let transaction = Transaction {
+            inputs: vec![input.clone(), input],
+            outputs: vec![],
+            checker: OuterConstraintChecker::Money(money::MoneyConstraintChecker::Spend),
+        };
+
+        new_test_ext().execute_with(|| {
+            assert_eq!(
+                Runtime::runtime_checks(&transaction, &Runtime::peek_inputs(&transaction)),
+                Err(InvalidTransaction::Custom(DUPLICATE_INPUT).into())
+            );
+        })
+    }
+
+    #[test]
+    fn output_refs_share_the_transaction_hash() {
+        let transaction = Transaction {
+            inputs: vec![],
+            outputs: vec![
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: money::Coin::<0>(1).into(),
+                },
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: money::Coin::<0>(2).into(),
+                },
+            ],
+            checker: OuterConstraintChecker::Money(money::MoneyConstraintChecker::Mint),
+        };
+
+        let refs = transaction.output_refs();
+        assert_eq!(
+            refs,
+            vec![transaction.output_ref(0), transaction.output_ref(1)]
+        );
+        assert_eq!(refs[0].tx_hash, witness::txid(&transaction));
+        assert_eq!(refs[1].index, 1);
+    }
+
+    /// Store an order offering 1020 of token 0 for 1500 of token 1, and one offering 1600
+    /// of token 1 for 990 of token 0, and build a match of them against the open rebate pool.
+    ///
+    /// The match leaves 30 of token 0 and 100 of token 1, of which a fee of 1% is 10 of
+    /// token 0 and 15 of token 1. It pays the given fees into the pool, and the given amount
+    /// of token 1 to the matcher, who leaves the rest of token 0 unclaimed.
+    fn fee_paying_match(fees_a: u128, fees_b: u128, matcher_takes: u128) -> Transaction {
+        fn order<T: dex::DexConfig<Verifier = OuterVerifier>>(
+            offer_amount: u128,
+            ask_amount: u128,
+            payout_to: OuterVerifier,
+        ) -> Output {
+            Output {
+                verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                payload: dex::Order::<T>::new(offer_amount, ask_amount, payout_to).into(),
+            }
+        }
+        fn output(payload: impl Into<DynamicallyTypedData>, verifier: &OuterVerifier) -> Output {
+            Output {
+                payload: payload.into(),
+                verifier: verifier.clone(),
+            }
+        }
+        let owner = |byte| {
+            OuterVerifier::SigCheck(SigCheck {
+                owner_pubkey: H256::repeat_byte(byte),
+            })
+        };
+        let (maker_01, maker_10, matcher) = (owner(1), owner(2), owner(3));
+        let credit = |volume| dex::MakerCredit::<DexConfig01> {
+            epoch: 0,
+            volume,
+            _ph_data: Default::default(),
+        };
+
+        let order_refs = [1, 2].map(|byte| OutputRef {
+            tx_hash: H256::repeat_byte(byte),
+            index: 0,
+        });
+        let orders = [
+            order::<DexConfig01>(1020, 1500, maker_01.clone()),
+            order::<dex::OppositeSide<DexConfig01>>(1600, 990, maker_10.clone()),
+        ];
+        for (order_ref, order) in order_refs.iter().zip(&orders) {
+            sp_io::storage::set(&order_ref.encode(), &order.encode());
+        }
+        let pool_ref = sp_io::storage::get(&rebate_pool_utxo_key::<DexConfig01>())
+            .and_then(|encoded| OutputRef::decode(&mut &encoded[..]).ok())
+            .expect("Genesis pool is recorded");
+
+        Transaction {
+            inputs: order_refs
+                .iter()
+                .chain([&pool_ref])
+                .map(|output_ref| Input {
+                    output_ref: output_ref.clone(),
+                    redeemer: Vec::new(),
+                })
+                .collect(),
+            outputs: vec![
+                output(money::Coin::<1>(1500), &maker_01),
+                output(money::Coin::<0>(990), &maker_10),
+                output(credit(1020), &maker_01),
+                output(credit(990), &maker_10),
+                output(
+                    dex::RebatePool::<DexConfig01> {
+                        fees_a,
+                        fees_b,
+                        volume: 2010,
+                        ..dex::RebatePool::open(0)
+                    },
+                    &OuterVerifier::UpForGrabs(UpForGrabs),
+                ),
+                output(money::Coin::<1>(matcher_takes), &matcher),
+            ],
+            checker: OuterConstraintChecker::MatchOrders(Default::default()),
+        }
+    }
+
+    #[test]
+    fn matches_pay_their_fees_into_the_genesis_rebate_pool() {
+        new_test_ext().execute_with(|| {
+            // The council turns on a fee of 1%.
+            let parameters = dex::DexParameters {
+                taker_fee_ppm: 10_000,
+                ..dex::DexParameters::defaults::<DexConfig01>()
+            };
+            sp_io::storage::set(
+                &governance::parameters_key::<dex::DexParameters>(),
+                &parameters.encode(),
+            );
+
+            assert_eq!(dry_run::dry_run_match(&fee_paying_match(10, 15, 85)), Ok(0));
+
+            // The fees in the pool count towards conservation, so the matcher cannot keep
+            // them as well.
+            assert_eq!(
+                dry_run::dry_run_match(&fee_paying_match(10, 15, 100)),
+                Err(dry_run::DryRunError::RuntimeCheck(
+                    InvalidTransaction::Custom(VALUE_NOT_CONSERVED).into()
+                ))
+            );
+        })
+    }
+
+    #[test]
+    fn council_votes_for_fees_keep_matches_going() {
+        new_test_ext().execute_with(|| {
+            let index = GenesisConfig::default()
+                .genesis_utxos
+                .iter()
+                .position(|utxo| utxo.payload.extract::<dex::DexParameters>().is_ok())
+                .expect("Genesis has dex parameters");
+            let current = Runtime::peek_utxo(&genesis_output_ref(index as u32))
+                .expect("Genesis parameters are in the UTXO set");
+            let vote = Output {
+                verifier: current.verifier.clone(),
+                payload: dex::DexParameters {
+                    taker_fee_ppm: 10_000,
+                    ..dex::DexParameters::defaults::<DexConfig01>()
+                }
+                .into(),
+            };
+            let update = OuterConstraintChecker::UpdateDexParameters(Default::default());
+            assert!(update.check(&[current], &[vote]).is_ok());
+            assert_eq!(
+                <DexConfig01 as dex::DexConfig>::parameters().taker_fee_ppm,
+                10_000
+            );
+
+            // Matches pay the new fee into the genesis pool rather than being halted.
+            assert_eq!(dry_run::dry_run_match(&fee_paying_match(10, 15, 85)), Ok(0));
+        })
+    }
+
+    #[test]
+    fn referrers_are_paid_out_of_the_surplus_of_fee_paying_matches() {
+        let referrer = OuterVerifier::SigCheck(SigCheck {
+            owner_pubkey: H256::repeat_byte(9),
+        });
+
+        new_test_ext().execute_with(|| {
+            let parameters = dex::DexParameters {
+                taker_fee_ppm: 10_000,
+                ..dex::DexParameters::defaults::<DexConfig01>()
+            };
+            sp_io::storage::set(
+                &governance::parameters_key::<dex::DexParameters>(),
+                &parameters.encode(),
+            );
+
+            // A fifth of the fee is 2 of token 0 and 3 of token 1, which leaves the matcher
+            // 82 of token 1. The makers still get the whole fee.
+            let referred = |referral_b| {
+                let mut transaction = fee_paying_match(10, 15, 82);
+                transaction.checker = OuterConstraintChecker::MatchOrders(dex::MatchOrders {
+                    referrer: Some(referrer.clone()),
+                    _ph_data: Default::default(),
+                });
+                transaction.outputs.splice(
+                    5..5,
+                    [
+                        Output {
+                            verifier: referrer.clone(),
+                            payload: money::Coin::<0>(2).into(),
+                        },
+                        Output {
+                            verifier: referrer.clone(),
+                            payload: money::Coin::<1>(referral_b).into(),
+                        },
+                    ],
+                );
+                transaction
+            };
+            assert_eq!(dry_run::dry_run_match(&referred(3)), Ok(0));
+
+            // The referrer must be paid its whole share.
+            assert_eq!(
+                dry_run::dry_run_match(&referred(2)),
+                Err(dry_run::DryRunError::Dex(dex::DexError::ReferralNotPaid))
+            );
+        })
+    }
+
+    #[test]
+    fn rebates_are_claimed_out_of_the_fees_in_the_pool() {
+        let maker = OuterVerifier::SigCheck(SigCheck {
+            owner_pubkey: H256::repeat_byte(1),
+        });
+        let up_for_grabs = OuterVerifier::UpForGrabs(UpForGrabs);
+        let pool = |fees_a, fees_b, volume| dex::RebatePool::<DexConfig01> {
+            closed: true,
+            fees_a,
+            fees_b,
+            volume,
+            ..dex::RebatePool::open(0)
+        };
+        let credit = dex::MakerCredit::<DexConfig01> {
+            epoch: 0,
+            volume: 1000,
+            _ph_data: Default::default(),
+        };
+
+        new_test_ext().execute_with(|| {
+            // A closed pool of 10 of token 0 and 15 of token 1, of which the credit is worth
+            // 5 and 7.
+            let pool_ref = OutputRef {
+                tx_hash: H256::repeat_byte(1),
+                index: 0,
+            };
+            let credit_ref = OutputRef {
+                tx_hash: H256::repeat_byte(2),
+                index: 0,
+            };
+            let spent = vec![
+                Output {
+                    verifier: up_for_grabs.clone(),
+                    payload: pool(10, 15, 1990).into(),
+                },
+                Output {
+                    verifier: maker.clone(),
+                    payload: credit.into(),
+                },
+            ];
+            sp_io::storage::set(&pool_ref.encode(), &spent[0].encode());
+            sp_io::storage::set(&credit_ref.encode(), &spent[1].encode());
+
+            let claim_of = |token_0| Transaction {
+                inputs: [&pool_ref, &credit_ref]
+                    .map(|output_ref| Input {
+                        output_ref: output_ref.clone(),
+                        redeemer: Vec::new(),
+                    })
+                    .to_vec(),
+                outputs: vec![
+                    Output {
+                        verifier: up_for_grabs.clone(),
+                        payload: pool(5, 8, 990).into(),
+                    },
+                    Output {
+                        verifier: maker.clone(),
+                        payload: money::Coin::<0>(token_0).into(),
+                    },
+                    Output {
+                        verifier: maker.clone(),
+                        payload: money::Coin::<1>(7).into(),
+                    },
+                ],
+                checker: OuterConstraintChecker::ClaimRebate01(Default::default()),
+            };
+
+            let claim = claim_of(5);
+            assert_eq!(
+                Runtime::runtime_checks(&claim, &Runtime::peek_inputs(&claim)),
+                Ok(())
+            );
+            assert!(claim.checker.check(&spent, &claim.outputs).is_ok());
+
+            // The fees left in the pool still count, so claiming more would create value.
+            let greedy = claim_of(6);
+            assert_eq!(
+                Runtime::runtime_checks(&greedy, &Runtime::peek_inputs(&greedy)),
+                Err(InvalidTransaction::Custom(VALUE_NOT_CONSERVED).into())
+            );
+        })
+    }
+
+    #[test]
+    fn vaults_may_mint_only_their_debt() {
+        let owner = OuterVerifier::SigCheck(SigCheck {
+            owner_pubkey: H256::repeat_byte(1),
+        });
+        let collateral_ref = OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index: 0,
+        };
+        let open_vault = |extra_collateral| Transaction {
+            inputs: vec![Input {
+                output_ref: collateral_ref.clone(),
+                redeemer: Vec::new(),
+            }],
+            outputs: vec![
+                Output {
+                    verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    payload: cdp::Vault::<CdpConfig03> {
+                        collateral: 100 + extra_collateral,
+                        debt: 50,
+                        owner: owner.clone(),
+                        _ph_data: Default::default(),
+                    }
+                    .into(),
+                },
+                Output {
+                    verifier: owner.clone(),
+                    payload: money::Coin::<3>(50).into(),
+                },
+            ],
+            checker: OuterConstraintChecker::OpenVault03(Default::default()),
+        };
+
+        new_test_ext().execute_with(|| {
+            let collateral = Output {
+                verifier: owner.clone(),
+                payload: money::Coin::<0>(100).into(),
+            };
+            sp_io::storage::set(&collateral_ref.encode(), &collateral.encode());
+
+            let honest = open_vault(0);
+            assert_eq!(
+                Runtime::runtime_checks(&honest, &Runtime::peek_inputs(&honest)),
+                Ok(())
+            );
+
+            // The debt token is exempt from conservation, but the collateral is not.
+            let inflated = open_vault(1);
+            assert_eq!(
+                Runtime::runtime_checks(&inflated, &Runtime::peek_inputs(&inflated)),
+                Err(InvalidTransaction::Custom(VALUE_NOT_CONSERVED).into())
+            );
+        })
+    }
 }
@@ -0,0 +1,354 @@
+//! Migrations of the stored UTXOs after a runtime upgrade.
+//!
+//! When a runtime upgrade changes how some piece encodes its data, the UTXOs already in
+//! storage still hold the old encoding. Types that follow the versioning convention of
+//! [`dex::versioning`] are migrated lazily when they are spent, but anything else has to
+//! be rewritten in storage. Each such change ships a [`Migration`] in [`MIGRATIONS`],
+//! tagged with the spec version that introduced it.
+//!
+//! The runtime records the spec version its storage was last migrated to. When a block
+//! begins under a newer spec version, the runtime has just been upgraded, and a run
+//! starts over every UTXO with all the migrations introduced since. The UTXO set can be
+//! large, so a run is spread over as many blocks as it takes, visiting at most
+//! [`MAX_MIGRATED_PER_BLOCK`] UTXOs at the start of each block, in the order of their
+//! leaves in the [`crate::utxo_set`]. Rewritten UTXOs get new leaves, so the UTXO set
+//! commitment stays correct.
+//!
+//! Transactions keep being applied while a run is in progress. The outputs they create are
+//! already in the new encoding, so they are noted and skipped when the run reaches them.
+//! UTXOs that the run has not reached yet may not decode until it does. Runtime upgrades
+//! must not be enacted until the previous run has finished, see [`is_running`].
+
+use crate::{Output, OutputRefs, Transaction, VERSION};
+use dex::{DexConfig, Order};
+use parity_scale_codec::{Decode, Encode};
+use sp_std::prelude::*;
+use tuxedo_core::{dynamic_typing::UtxoData, types::OutputRef};
+
+/// The storage key of the spec version that storage was last migrated to.
+pub const MIGRATED_SPEC_VERSION_KEY: &[u8] = b"migrated_spec_version";
+
+/// The storage key of the progress of the run in progress, if any.
+pub const MIGRATION_CURSOR_KEY: &[u8] = b"migration_cursor";
+
+/// The prefix of the storage keys that mark the outputs created during a run, which
+/// the run skips.
+pub const MIGRATION_SKIP_PREFIX: &[u8] = b"migration_skip";
+
+/// How many UTXOs a run visits at the start of each block.
+pub const MAX_MIGRATED_PER_BLOCK: u32 = 100;
+
+/// A change to the stored UTXOs, shipped with the runtime upgrade that requires it.
+pub struct Migration {
+    /// The spec version that introduced the migration. It runs on chains whose storage
+    /// was migrated to an earlier version.
+    pub since: u32,
+    /// Rewrite a UTXO in place, returning whether it changed.
+    pub migrate: fn(&mut Output) -> bool,
+}
+
+/// All the migrations that this runtime knows, oldest first.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    since: 2,
+    migrate: version_orders,
+}];
+
+/// The progress of a migration run.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct MigrationCursor {
+    /// The spec version that storage was migrated to before the run. The run applies the
+    /// migrations introduced after it.
+    pub from: u32,
+    /// The reference of the last UTXO that the run visited, or `None` if it has not
+    /// visited any yet.
+    pub last: Option<OutputRef>,
+}
+
+/// The spec version that storage was last migrated to.
+///
+/// Chains whose storage predates the migration runner have never been migrated, so every
+/// migration applies to them.
+pub fn migrated_spec_version() -> u32 {
+    sp_io::storage::get(MIGRATED_SPEC_VERSION_KEY)
+        .and_then(|encoded| u32::decode(&mut &encoded[..]).ok())
+        .unwrap_or_default()
+}
+
+/// The progress of the run in progress, if any.
+pub fn cursor() -> Option<MigrationCursor> {
+    sp_io::storage::get(MIGRATION_CURSOR_KEY)
+        .and_then(|encoded| MigrationCursor::decode(&mut &encoded[..]).ok())
+}
+
+/// Whether a migration run is in progress.
+pub fn is_running() -> bool {
+    cursor().is_some()
+}
+
+/// The storage key that marks an output as created during the run.
+fn skip_key(output_ref: &OutputRef) -> Vec<u8> {
+    [MIGRATION_SKIP_PREFIX, &output_ref.encode()].concat()
+}
+
+/// Start a run if the runtime has been upgraded, and take the next step of the run in
+/// progress. Called at the start of every block.
+pub(crate) fn on_block() {
+    let from = migrated_spec_version();
+    if from < VERSION.spec_version {
+        sp_io::storage::set(MIGRATED_SPEC_VERSION_KEY, &VERSION.spec_version.encode());
+        if MIGRATIONS.iter().any(|migration| migration.since > from) {
+            let cursor = MigrationCursor { from, last: None };
+            sp_io::storage::set(MIGRATION_CURSOR_KEY, &cursor.encode());
+        }
+    }
+
+    if let Some(cursor) = cursor() {
+        step(cursor, MAX_MIGRATED_PER_BLOCK);
+    }
+}
+
+/// Note the outputs of a transaction that was applied during a run, so that the run does
+/// not migrate them again.
+pub(crate) fn note_created(transaction: &Transaction) {
+    if !is_running() {
+        return;
+    }
+    for output_ref in transaction.output_refs() {
+        sp_io::storage::set(&skip_key(&output_ref), &[]);
+    }
+}
+
+/// Visit up to `limit` UTXOs after the cursor, finishing the run if there are none left.
+fn step(mut cursor: MigrationCursor, limit: u32) {
+    let migrations: Vec<_> = MIGRATIONS
+        .iter()
+        .filter(|migration| migration.since > cursor.from)
+        .collect();
+
+    let mut key = match &cursor.last {
+        Some(last) => crate::utxo_set::leaf_key(last),
+        None => crate::utxo_set::UTXO_LEAF_PREFIX.to_vec(),
+    };
+    for _ in 0..limit {
+        let Some(next) = sp_io::storage::next_key(&key)
+            .filter(|next| next.starts_with(crate::utxo_set::UTXO_LEAF_PREFIX))
+        else {
+            // Every UTXO has been visited.
+            sp_io::storage::clear(MIGRATION_CURSOR_KEY);
+            let _ = sp_io::storage::clear_prefix(MIGRATION_SKIP_PREFIX, None);
+            return;
+        };
+        key = next;
+
+        let Ok(output_ref) =
+            OutputRef::decode(&mut &key[crate::utxo_set::UTXO_LEAF_PREFIX.len()..])
+        else {
+            continue;
+        };
+        cursor.last = Some(output_ref.clone());
+
+        if sp_io::storage::exists(&skip_key(&output_ref)) {
+            sp_io::storage::clear(&skip_key(&output_ref));
+            continue;
+        }
+        let Some(mut output) = sp_io::storage::get(&output_ref.encode())
+            .and_then(|encoded| Output::decode(&mut &encoded[..]).ok())
+        else {
+            continue;
+        };
+
+        let mut changed = false;
+        for migration in &migrations {
+            changed |= (migration.migrate)(&mut output);
+        }
+        if changed {
+            sp_io::storage::set(&output_ref.encode(), &output.encode());
+            crate::utxo_set::insert(&output_ref, &output);
+        }
+    }
+
+    sp_io::storage::set(MIGRATION_CURSOR_KEY, &cursor.encode());
+}
+
+/// Prefix an order from before orders were versioned with its version.
+///
+/// The fields of those orders are exactly the fields of the first version, so the
+/// versioned decoding migrates them further when they are spent.
+fn version_order<T: DexConfig>(output: &mut Output) -> bool {
+    if output.payload.type_id != <Order<T> as UtxoData>::TYPE_ID {
+        return false;
+    }
+    output.payload.data.insert(0, 1);
+    true
+}
+
+/// Spec version 2 versioned the encoding of dex orders.
+fn version_orders(output: &mut Output) -> bool {
+    version_order::<crate::DexConfig01>(output)
+        || version_order::<dex::OppositeSide<crate::DexConfig01>>(output)
+        || version_order::<crate::AssetDexConfig>(output)
+        || version_order::<dex::OppositeSide<crate::AssetDexConfig>>(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DexConfig01, OuterConstraintChecker, OuterVerifier};
+    use sp_core::H256;
+    use tuxedo_core::verifier::UpForGrabs;
+
+    fn output_ref(byte: u8) -> OutputRef {
+        OutputRef {
+            tx_hash: H256::repeat_byte(byte),
+            index: 0,
+        }
+    }
+
+    fn coin(value: u128) -> Output {
+        Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: money::Coin::<0>(value).into(),
+        }
+    }
+
+    /// An order offering 10 for 5, encoded the way orders were before they were versioned.
+    fn unversioned_order() -> Output {
+        let mut output = coin(0);
+        output.payload.type_id = <Order<DexConfig01> as UtxoData>::TYPE_ID;
+        output.payload.data = (
+            10u128,
+            5u128,
+            OuterVerifier::UpForGrabs(UpForGrabs),
+            dex::PayoutKind::Coin,
+            H256::zero(),
+        )
+            .encode();
+        output
+    }
+
+    fn store(output_ref: &OutputRef, output: &Output) {
+        sp_io::storage::set(&output_ref.encode(), &output.encode());
+        crate::utxo_set::insert(output_ref, output);
+    }
+
+    fn stored(output_ref: &OutputRef) -> Output {
+        Output::decode(&mut &sp_io::storage::get(&output_ref.encode()).unwrap()[..]).unwrap()
+    }
+
+    /// Pretend that storage was last migrated to the first spec version.
+    fn upgrade_from_first_version() {
+        sp_io::storage::set(MIGRATED_SPEC_VERSION_KEY, &1u32.encode());
+    }
+
+    #[test]
+    fn up_to_date_storage_starts_no_run() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            sp_io::storage::set(MIGRATED_SPEC_VERSION_KEY, &VERSION.spec_version.encode());
+            store(&output_ref(1), &unversioned_order());
+
+            on_block();
+            assert!(!is_running());
+            assert_eq!(stored(&output_ref(1)), unversioned_order());
+        });
+    }
+
+    #[test]
+    fn unversioned_orders_are_migrated() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            upgrade_from_first_version();
+            store(&output_ref(1), &unversioned_order());
+            store(&output_ref(2), &coin(7));
+
+            on_block();
+            assert!(!is_running());
+            assert_eq!(migrated_spec_version(), VERSION.spec_version);
+
+            let migrated = stored(&output_ref(1));
+            let order: Order<DexConfig01> = migrated.payload.extract().unwrap();
+            assert_eq!((order.offer_amount, order.ask_amount), (10, 5));
+            assert_eq!(order.expiry, None);
+            assert_eq!(stored(&output_ref(2)), coin(7));
+
+            // The leaf commits to the migrated UTXO.
+            let leaf = crate::utxo_set::leaves()
+                .into_iter()
+                .find(|(r, _)| *r == output_ref(1))
+                .unwrap();
+            assert_eq!(
+                leaf.1,
+                crate::utxo_set::leaf_hash(&output_ref(1), &migrated)
+            );
+        });
+    }
+
+    #[test]
+    fn runs_are_spread_over_blocks() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            upgrade_from_first_version();
+            store(&output_ref(1), &unversioned_order());
+            store(&output_ref(2), &unversioned_order());
+            sp_io::storage::set(
+                MIGRATION_CURSOR_KEY,
+                &MigrationCursor {
+                    from: 1,
+                    last: None,
+                }
+                .encode(),
+            );
+            sp_io::storage::set(MIGRATED_SPEC_VERSION_KEY, &VERSION.spec_version.encode());
+
+            step(cursor().unwrap(), 1);
+            assert_eq!(cursor().unwrap().last, Some(output_ref(1)));
+            assert!(stored(&output_ref(1))
+                .payload
+                .extract::<Order<DexConfig01>>()
+                .is_ok());
+            assert_eq!(stored(&output_ref(2)), unversioned_order());
+
+            step(cursor().unwrap(), 1);
+            step(cursor().unwrap(), 1);
+            assert!(!is_running());
+            assert!(stored(&output_ref(2))
+                .payload
+                .extract::<Order<DexConfig01>>()
+                .is_ok());
+        });
+    }
+
+    #[test]
+    fn outputs_created_during_a_run_are_skipped() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            upgrade_from_first_version();
+            on_block();
+
+            // Storage holds nothing yet, so the first block finished the run. Start another
+            // one by hand, as if there were many UTXOs left.
+            sp_io::storage::set(
+                MIGRATION_CURSOR_KEY,
+                &MigrationCursor {
+                    from: 1,
+                    last: None,
+                }
+                .encode(),
+            );
+
+            let order = Output {
+                verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                payload: Order::<DexConfig01>::new(10, 5, OuterVerifier::UpForGrabs(UpForGrabs))
+                    .into(),
+            };
+            let transaction = Transaction {
+                inputs: Vec::new(),
+                outputs: vec![order.clone()],
+                checker: OuterConstraintChecker::MakeOrder01(Default::default()),
+            };
+            let created = transaction.output_ref(0);
+            store(&created, &order);
+            note_created(&transaction);
+
+            on_block();
+            assert!(!is_running());
+            assert_eq!(stored(&created), order);
+        });
+    }
+}
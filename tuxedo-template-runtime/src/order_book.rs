@@ -0,0 +1,418 @@
+//! Changes to the order book, as seen from outside the runtime.
+//!
+//! Clients that display the order book want to know what changed in each block rather
+//! than re-reading every open order. This module turns the transactions of a block, and
+//! the outputs they spent according to the [`journal`](crate::journal), into a list of
+//! [`OrderBookDelta`]s, and the `MatchOrders` transactions among them into a list of
+//! [`Fill`]s. It has no effect on the chain itself; the node uses it to feed its order
+//! book and trade subscriptions.
+//!
+//! Orders leave the book by being matched, cancelled or swept. A spent order is reported as
+//! cancelled if a `CancelOrder` or `SweepExpired` transaction in the block spent it, and as
+//! filled otherwise.
+
+use crate::{OuterConstraintChecker, OuterData, Output, OutputRefs, Transaction};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::prelude::*;
+use tuxedo_core::{traits::Cash, types::OutputRef};
+
+/// The pairs of token ids that this runtime has an order book for.
+pub const TRADED_PAIRS: &[(u8, u8)] =
+    &[(<money::Coin<0> as Cash>::ID, <money::Coin<1> as Cash>::ID)];
+
+/// Whether this runtime has an order book for the given pair of tokens, in either order.
+pub fn is_traded(pair: (u8, u8)) -> bool {
+    TRADED_PAIRS
+        .iter()
+        .any(|&(a, b)| (a, b) == pair || (b, a) == pair)
+}
+
+/// The terms of an order, independent of which side of the pair it is on.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct OrderTerms {
+    /// The id of the token that is offered
+    pub offer_token: u8,
+    /// The amount of the offered token
+    pub offer_amount: u128,
+    /// The id of the token that is asked in exchange
+    pub ask_token: u8,
+    /// The amount of the asked token
+    pub ask_amount: u128,
+}
+
+impl OrderTerms {
+    /// Whether this order trades between the given two tokens, in either direction.
+    pub fn is_in_pair(&self, pair: (u8, u8)) -> bool {
+        (self.offer_token, self.ask_token) == pair || (self.ask_token, self.offer_token) == pair
+    }
+}
+
+/// The terms of the order held in a UTXO, if it holds one.
+pub fn order_terms(output: &Output) -> Option<OrderTerms> {
+    match OuterData::try_from(&output.payload).ok()? {
+        OuterData::Order01(order) => Some(OrderTerms {
+            offer_token: <money::Coin<0> as Cash>::ID,
+            offer_amount: order.offer_amount,
+            ask_token: <money::Coin<1> as Cash>::ID,
+            ask_amount: order.ask_amount,
+        }),
+        OuterData::Order10(order) => Some(OrderTerms {
+            offer_token: <money::Coin<1> as Cash>::ID,
+            offer_amount: order.offer_amount,
+            ask_token: <money::Coin<0> as Cash>::ID,
+            ask_amount: order.ask_amount,
+        }),
+        _ => None,
+    }
+}
+
+/// The token id and value of the coin held in a UTXO, if it holds one.
+pub fn coin_value(output: &Output) -> Option<(u8, u128)> {
+    match OuterData::try_from(&output.payload).ok()? {
+        OuterData::Coin0(coin) => Some((<money::Coin<0> as Cash>::ID, coin.value())),
+        OuterData::Coin1(coin) => Some((<money::Coin<1> as Cash>::ID, coin.value())),
+        OuterData::Coin2(coin) => Some((<money::Coin<2> as Cash>::ID, coin.value())),
+        OuterData::Coin3(coin) => Some((<money::Coin<3> as Cash>::ID, coin.value())),
+        _ => None,
+    }
+}
+
+/// Whether a UTXO is part of the market for the given pair, either as an open order in
+/// the pair or as a coin of one of its tokens.
+pub fn is_in_market(output: &Output, pair: (u8, u8)) -> bool {
+    match (order_terms(output), coin_value(output)) {
+        (Some(terms), _) => terms.is_in_pair(pair),
+        (None, Some((token, _))) => token == pair.0 || token == pair.1,
+        (None, None) => false,
+    }
+}
+
+/// A single change to the order book.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum OrderBookDelta {
+    /// A new order was opened and stored at the given location.
+    Opened {
+        order_ref: OutputRef,
+        terms: OrderTerms,
+    },
+    /// The order stored at the given location was matched and has left the book.
+    Filled {
+        order_ref: OutputRef,
+        terms: OrderTerms,
+    },
+    /// The order stored at the given location was cancelled by its maker and has left
+    /// the book.
+    Cancelled {
+        order_ref: OutputRef,
+        terms: OrderTerms,
+    },
+}
+
+impl OrderBookDelta {
+    /// The terms of the order this delta is about.
+    pub fn terms(&self) -> &OrderTerms {
+        match self {
+            Self::Opened { terms, .. }
+            | Self::Filled { terms, .. }
+            | Self::Cancelled { terms, .. } => terms,
+        }
+    }
+}
+
+/// All the changes a block made to the order book.
+///
+/// `spent` must be the outputs the block spent, as journaled by the runtime. Orders that
+/// were opened and filled within the same block show up as both.
+pub fn deltas(transactions: &[Transaction], spent: &[(OutputRef, Output)]) -> Vec<OrderBookDelta> {
+    let opened = transactions.iter().flat_map(|transaction| {
+        transaction
+            .output_refs()
+            .into_iter()
+            .zip(&transaction.outputs)
+            .filter_map(|(order_ref, output)| {
+                order_terms(output).map(|terms| OrderBookDelta::Opened { order_ref, terms })
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let cancelled_refs: Vec<&OutputRef> = transactions
+        .iter()
+        .filter(|transaction| {
+            matches!(
+                transaction.checker,
+                OuterConstraintChecker::CancelOrder01(_)
+                    | OuterConstraintChecker::CancelOrder10(_)
+                    | OuterConstraintChecker::SweepExpired(_)
+            )
+        })
+        .flat_map(|transaction| &transaction.inputs)
+        .map(|input| &input.output_ref)
+        .collect();
+
+    let closed = spent.iter().filter_map(|(order_ref, output)| {
+        let terms = order_terms(output)?;
+        let order_ref = order_ref.clone();
+        Some(if cancelled_refs.contains(&&order_ref) {
+            OrderBookDelta::Cancelled { order_ref, terms }
+        } else {
+            OrderBookDelta::Filled { order_ref, terms }
+        })
+    });
+
+    opened.chain(closed).collect()
+}
+
+/// An order that was executed by a `MatchOrders` transaction.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Fill {
+    /// Where the order was stored before it was matched
+    pub order_ref: OutputRef,
+    /// The terms the order was executed at
+    pub terms: OrderTerms,
+}
+
+impl Fill {
+    /// The amounts of the base and quote token exchanged by this fill, where the first
+    /// token of `pair` is the base.
+    ///
+    /// Returns `None` if the order was not in the given pair.
+    pub fn amounts_in(&self, pair: (u8, u8)) -> Option<(u128, u128)> {
+        let terms = &self.terms;
+        if (terms.offer_token, terms.ask_token) == pair {
+            Some((terms.offer_amount, terms.ask_amount))
+        } else if (terms.ask_token, terms.offer_token) == pair {
+            Some((terms.ask_amount, terms.offer_amount))
+        } else {
+            None
+        }
+    }
+}
+
+/// All the orders executed by the `MatchOrders` transactions in a block, in the order
+/// they were matched.
+///
+/// `spent` must be the outputs the block spent, as journaled by the runtime. It is used
+/// to look up the orders, which the transactions only reference.
+pub fn fills(transactions: &[Transaction], spent: &[(OutputRef, Output)]) -> Vec<Fill> {
+    transactions
+        .iter()
+        .filter(|transaction| matches!(transaction.checker, OuterConstraintChecker::MatchOrders(_)))
+        .flat_map(|transaction| &transaction.inputs)
+        .filter_map(|input| {
+            let (order_ref, order) = spent
+                .iter()
+                .find(|(output_ref, _)| *output_ref == input.output_ref)?;
+            order_terms(order).map(|terms| Fill {
+                order_ref: order_ref.clone(),
+                terms,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DexConfig01, OuterConstraintChecker, OuterVerifier};
+    use sp_core::H256;
+    use tuxedo_core::{types::Input, verifier::UpForGrabs};
+
+    fn order_01(offer_amount: u128, ask_amount: u128) -> Output {
+        Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: dex::Order::<DexConfig01>::new(
+                offer_amount,
+                ask_amount,
+                OuterVerifier::UpForGrabs(UpForGrabs),
+            )
+            .into(),
+        }
+    }
+
+    fn coin(value: u128) -> Output {
+        Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: money::Coin::<0>(value).into(),
+        }
+    }
+
+    #[test]
+    fn order_terms_are_read_from_either_side() {
+        let order_10 = Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: dex::Order::<dex::OppositeSide<DexConfig01>>::new(
+                3,
+                4,
+                OuterVerifier::UpForGrabs(UpForGrabs),
+            )
+            .into(),
+        };
+
+        let terms = order_terms(&order_10).expect("Output is an order");
+        assert_eq!((terms.offer_token, terms.ask_token), (1, 0));
+        assert!(terms.is_in_pair((0, 1)));
+        assert!(terms.is_in_pair((1, 0)));
+        assert!(!terms.is_in_pair((0, 2)));
+
+        assert_eq!(order_terms(&coin(5)), None);
+    }
+
+    #[test]
+    fn matched_orders_become_fills() {
+        let buy = OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index: 0,
+        };
+        let sell = OutputRef {
+            tx_hash: H256::repeat_byte(2),
+            index: 0,
+        };
+        let order_10 = Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: dex::Order::<dex::OppositeSide<DexConfig01>>::new(
+                20,
+                10,
+                OuterVerifier::UpForGrabs(UpForGrabs),
+            )
+            .into(),
+        };
+        let match_orders = Transaction {
+            inputs: vec![
+                Input {
+                    output_ref: buy.clone(),
+                    redeemer: Vec::new(),
+                },
+                Input {
+                    output_ref: sell.clone(),
+                    redeemer: Vec::new(),
+                },
+            ],
+            outputs: vec![coin(20), coin(10)],
+            checker: OuterConstraintChecker::MatchOrders(Default::default()),
+        };
+
+        let fills = fills(
+            &[match_orders],
+            &[(sell, order_10), (buy.clone(), order_01(10, 20))],
+        );
+
+        assert_eq!(fills.len(), 2);
+        assert_eq!(fills[0].order_ref, buy);
+        assert_eq!(fills[0].amounts_in((0, 1)), Some((10, 20)));
+        assert_eq!(fills[1].amounts_in((0, 1)), Some((10, 20)));
+        assert_eq!(fills[1].amounts_in((1, 0)), Some((20, 10)));
+        assert_eq!(fills[1].amounts_in((0, 2)), None);
+    }
+
+    #[test]
+    fn spent_orders_outside_matches_are_not_fills() {
+        let order_ref = OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index: 0,
+        };
+        let spend = Transaction {
+            inputs: vec![Input {
+                output_ref: order_ref.clone(),
+                redeemer: Vec::new(),
+            }],
+            outputs: Vec::new(),
+            checker: OuterConstraintChecker::Money(money::MoneyConstraintChecker::Spend),
+        };
+
+        assert_eq!(fills(&[spend], &[(order_ref, order_01(1, 1))]), vec![]);
+    }
+
+    #[test]
+    fn orders_and_coins_of_the_pair_are_in_its_market() {
+        assert!(is_in_market(&order_01(10, 20), (0, 1)));
+        assert!(is_in_market(&order_01(10, 20), (1, 0)));
+        assert!(is_in_market(&coin(5), (0, 1)));
+        assert_eq!(coin_value(&coin(5)), Some((0, 5)));
+
+        assert!(!is_in_market(&order_01(10, 20), (0, 2)));
+        assert!(!is_in_market(&coin(5), (1, 2)));
+
+        let claim = Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: poe::ClaimData {
+                claim: H256::repeat_byte(0xaa),
+            }
+            .into(),
+        };
+        assert!(!is_in_market(&claim, (0, 1)));
+    }
+
+    #[test]
+    fn only_the_dex_pair_is_traded() {
+        assert!(is_traded((0, 1)));
+        assert!(is_traded((1, 0)));
+        assert!(!is_traded((0, 2)));
+    }
+
+    #[test]
+    fn new_orders_and_spent_orders_become_deltas() {
+        let make_order = Transaction {
+            inputs: Vec::new(),
+            outputs: vec![order_01(10, 20)],
+            checker: OuterConstraintChecker::MakeOrder01(Default::default()),
+        };
+        let spent_order = OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index: 0,
+        };
+        let spent_coin = OutputRef {
+            tx_hash: H256::repeat_byte(2),
+            index: 0,
+        };
+
+        let deltas = deltas(
+            &[make_order.clone()],
+            &[(spent_order.clone(), order_01(5, 6)), (spent_coin, coin(5))],
+        );
+
+        assert_eq!(
+            deltas,
+            vec![
+                OrderBookDelta::Opened {
+                    order_ref: make_order.output_ref(0),
+                    terms: order_terms(&order_01(10, 20)).unwrap(),
+                },
+                OrderBookDelta::Filled {
+                    order_ref: spent_order,
+                    terms: order_terms(&order_01(5, 6)).unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cancelled_orders_become_cancelled_deltas() {
+        let order_ref = OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index: 0,
+        };
+        let cancel = Transaction {
+            inputs: vec![Input {
+                output_ref: order_ref.clone(),
+                redeemer: Vec::new(),
+            }],
+            outputs: vec![coin(5)],
+            checker: OuterConstraintChecker::CancelOrder01(Default::default()),
+        };
+
+        assert_eq!(
+            deltas(&[cancel.clone()], &[(order_ref.clone(), order_01(5, 6))]),
+            vec![OrderBookDelta::Cancelled {
+                order_ref: order_ref.clone(),
+                terms: order_terms(&order_01(5, 6)).unwrap(),
+            }]
+        );
+        assert_eq!(fills(&[cancel], &[(order_ref, order_01(5, 6))]), vec![]);
+    }
+}
@@ -0,0 +1,248 @@
+//! A limit on how many dex orders each account may have open at once.
+//!
+//! On a permissionless chain anyone can fill the book with small orders, which makes it
+//! slower to read and to match against. Every dex order names its owner, so the runtime
+//! keeps an index of how many open orders each owner has, and refuses transactions that
+//! would take an owner over [`MAX_OPEN_ORDERS_PER_OWNER`]. The limit is optional. Setting
+//! it to `None` turns the check off, but the index is maintained either way so that the
+//! limit can be turned on later by a runtime upgrade.
+//!
+//! A limit on owners only helps if owners cannot be made up. So while the limit is on, a
+//! transaction may only open orders for owners whose `SigCheck` verifier protects one of
+//! its inputs, which means the owner, or a delegate of theirs, signed the transaction.
+
+use crate::{OuterData, OuterVerifier, Output};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_std::{collections::btree_map::BTreeMap, prelude::*};
+
+/// The most orders that one owner may have open at once, or `None` for no limit.
+pub const MAX_OPEN_ORDERS_PER_OWNER: Option<u32> = Some(64);
+
+/// The prefix of the storage keys under which the number of open orders of each owner
+/// is indexed.
+pub const OPEN_ORDERS_PREFIX: &[u8] = b"open_orders";
+
+/// All the ways a transaction can break the open order limit.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum OrderLimitError {
+    /// The transaction opens an order for an owner who did not sign any of its inputs.
+    UnsignedOwner { owner: H256 },
+    /// The transaction leaves the owner with more open orders than the limit.
+    TooManyOpenOrders { owner: H256 },
+}
+
+/// The storage key of the number of open orders of the given owner.
+pub fn open_orders_key(owner: &H256) -> Vec<u8> {
+    (OPEN_ORDERS_PREFIX, owner).encode()
+}
+
+/// The number of orders the given owner currently has open.
+pub fn open_orders(owner: &H256) -> u32 {
+    sp_io::storage::get(&open_orders_key(owner))
+        .and_then(|encoded| u32::decode(&mut &encoded[..]).ok())
+        .unwrap_or_default()
+}
+
+/// The owner of the order held in a UTXO, if it holds one.
+pub fn order_owner(output: &Output) -> Option<H256> {
+    match OuterData::try_from(&output.payload).ok()? {
+        OuterData::Order01(order) => Some(order.owner),
+        OuterData::Order10(order) => Some(order.owner),
+        _ => None,
+    }
+}
+
+/// Whether UTXOs protected by the two verifiers belong to the same owner: the verifiers
+/// are the same, or the same key signs for both.
+pub fn same_owner(a: &OuterVerifier, b: &OuterVerifier) -> bool {
+    a == b || signer(a).map_or(false, |key| signer(b) == Some(key))
+}
+
+/// How many orders each owner closes and opens in a transaction, in that order.
+fn changes_by_owner<'a>(
+    spent: impl IntoIterator<Item = &'a Output>,
+    created: impl IntoIterator<Item = &'a Output>,
+) -> BTreeMap<H256, (u32, u32)> {
+    let mut changes: BTreeMap<H256, (u32, u32)> = BTreeMap::new();
+    for owner in spent.into_iter().filter_map(order_owner) {
+        let change = changes.entry(owner).or_default();
+        change.0 = change.0.saturating_add(1);
+    }
+    for owner in created.into_iter().filter_map(order_owner) {
+        let change = changes.entry(owner).or_default();
+        change.1 = change.1.saturating_add(1);
+    }
+    changes
+}
+
+/// The key that must have signed to spend a UTXO protected by the given verifier, if any.
+///
+/// Delegates sign for the owner who delegated to them.
+fn signer(verifier: &OuterVerifier) -> Option<H256> {
+    match verifier {
+        OuterVerifier::SigCheck(sig_check) => Some(sig_check.owner_pubkey),
+        OuterVerifier::Sr25519SigCheck(sig_check) => Some(sig_check.owner_pubkey),
+        OuterVerifier::Delegated(delegated) => signer(&delegated.owner),
+        _ => None,
+    }
+}
+
+/// Ensure that a transaction with the given inputs and outputs keeps every owner it opens
+/// orders for within the limit, and that those owners signed it.
+pub fn check(inputs: &[Output], outputs: &[Output], limit: u32) -> Result<(), OrderLimitError> {
+    let signers: Vec<H256> = inputs
+        .iter()
+        .filter_map(|input| signer(&input.verifier))
+        .collect();
+
+    for (owner, (closed, opened)) in changes_by_owner(inputs, outputs) {
+        if opened == 0 {
+            continue;
+        }
+        if !signers.contains(&owner) {
+            return Err(OrderLimitError::UnsignedOwner { owner });
+        }
+        let open_after = open_orders(&owner)
+            .saturating_sub(closed)
+            .saturating_add(opened);
+        if open_after > limit {
+            return Err(OrderLimitError::TooManyOpenOrders { owner });
+        }
+    }
+
+    Ok(())
+}
+
+/// Update the index for a transaction that spent and created the given outputs.
+pub(crate) fn apply<'a>(
+    spent: impl IntoIterator<Item = &'a Output>,
+    created: impl IntoIterator<Item = &'a Output>,
+) {
+    for (owner, (closed, opened)) in changes_by_owner(spent, created) {
+        let count = open_orders(&owner)
+            .saturating_sub(closed)
+            .saturating_add(opened);
+        if count == 0 {
+            sp_io::storage::clear(&open_orders_key(&owner));
+        } else {
+            sp_io::storage::set(&open_orders_key(&owner), &count.encode());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DexConfig01;
+    use tuxedo_core::verifier::{SigCheck, UpForGrabs};
+
+    fn signed_by(owner: H256) -> OuterVerifier {
+        OuterVerifier::SigCheck(SigCheck {
+            owner_pubkey: owner,
+        })
+    }
+
+    fn order(owner: H256) -> Output {
+        Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: dex::Order::<DexConfig01> {
+                owner,
+                ..dex::Order::new(10, 20, signed_by(owner))
+            }
+            .into(),
+        }
+    }
+
+    fn coin(owner: H256) -> Output {
+        Output {
+            verifier: signed_by(owner),
+            payload: money::Coin::<0>(10).into(),
+        }
+    }
+
+    #[test]
+    fn opening_and_closing_orders_is_indexed() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let owner = H256::repeat_byte(1);
+            apply(&[coin(owner)], &[order(owner), order(owner)]);
+            assert_eq!(open_orders(&owner), 2);
+
+            apply(&[order(owner)], &[coin(owner)]);
+            assert_eq!(open_orders(&owner), 1);
+
+            apply(&[order(owner)], &[]);
+            assert_eq!(open_orders(&owner), 0);
+            assert!(!sp_io::storage::exists(&open_orders_key(&owner)));
+        });
+    }
+
+    #[test]
+    fn owners_may_open_orders_up_to_the_limit() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let owner = H256::repeat_byte(1);
+            apply(&[], &[order(owner)]);
+
+            assert_eq!(check(&[coin(owner)], &[order(owner)], 2), Ok(()));
+            assert_eq!(
+                check(&[coin(owner)], &[order(owner), order(owner)], 2),
+                Err(OrderLimitError::TooManyOpenOrders { owner })
+            );
+        });
+    }
+
+    #[test]
+    fn closing_an_order_makes_room_for_another() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let owner = H256::repeat_byte(1);
+            apply(&[], &[order(owner)]);
+
+            assert_eq!(
+                check(&[order(owner), coin(owner)], &[order(owner)], 1),
+                Ok(())
+            );
+        });
+    }
+
+    #[test]
+    fn orders_can_only_be_opened_by_their_owner() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let owner = H256::repeat_byte(1);
+            let someone_else = H256::repeat_byte(2);
+
+            assert_eq!(
+                check(&[coin(someone_else)], &[order(owner)], 10),
+                Err(OrderLimitError::UnsignedOwner { owner })
+            );
+        });
+    }
+
+    #[test]
+    fn matching_does_not_need_the_owners_signatures() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let owner = H256::repeat_byte(1);
+            apply(&[], &[order(owner)]);
+
+            assert_eq!(check(&[order(owner)], &[coin(owner)], 0), Ok(()));
+        });
+    }
+
+    #[test]
+    fn verifiers_signed_for_by_the_same_key_have_the_same_owner() {
+        let owner = H256::repeat_byte(1);
+        let someone_else = H256::repeat_byte(2);
+        let sr25519 = OuterVerifier::Sr25519SigCheck(crate::verifiers::Sr25519SigCheck {
+            owner_pubkey: owner,
+        });
+        let up_for_grabs = OuterVerifier::UpForGrabs(UpForGrabs);
+
+        assert!(same_owner(&signed_by(owner), &sr25519));
+        assert!(!same_owner(&signed_by(owner), &signed_by(someone_else)));
+        assert!(!same_owner(&up_for_grabs, &signed_by(owner)));
+        assert!(same_owner(&up_for_grabs, &up_for_grabs));
+    }
+}
@@ -0,0 +1,160 @@
+//! Quotes of what a hypothetical order would get from the resting book.
+//!
+//! Before locking collateral in an order, users want to know what it would fetch. A
+//! [`Quote`] walks the open orders on the other side of the market, best price first, and
+//! fills every one of them that the hypothetical order can still pay for in full, since
+//! resting orders are only ever matched whole. Whatever the hypothetical order has left
+//! after that would rest on the book itself.
+//!
+//! Quotes read the whole UTXO set, so they are only meant for the [`DexApi`], off chain.
+
+use crate::{
+    order_book::{self, Fill},
+    utxo_set, Runtime,
+};
+use dex::{Price, Rounding, Side};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::U256;
+use sp_std::prelude::*;
+
+/// What an order would get from the resting book.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default, TypeInfo)]
+pub struct Quote {
+    /// The resting orders that the order would fill, best price first
+    pub fills: Vec<Fill>,
+    /// The amount of the offered token that the order would pay to those orders
+    pub paid: u128,
+    /// The amount of the asked token that the order would receive from them
+    pub received: u128,
+    /// The amount of the offered token that would be left over to rest on the book
+    pub leftover: u128,
+    /// The average price of the fills, in the second token of the pair per unit of the
+    /// first, or `None` if nothing would be filled
+    pub average_price: Option<Price>,
+}
+
+/// Quote an order in the given pair, offering `amount` of the token on the given side.
+///
+/// Returns `None` if the runtime has no order book for the pair.
+pub fn quote(pair: (u8, u8), side: Side, amount: u128) -> Option<Quote> {
+    if !order_book::is_traded(pair) {
+        return None;
+    }
+    let (offer_token, ask_token) = match side {
+        Side::OffersA => pair,
+        Side::OffersB => (pair.1, pair.0),
+    };
+
+    // The resting orders that offer what we ask for, and ask for what we offer.
+    let mut resting: Vec<Fill> = utxo_set::leaves()
+        .into_iter()
+        .filter_map(|(order_ref, _)| {
+            let terms = order_book::order_terms(&Runtime::peek_utxo(&order_ref)?)?;
+            (terms.offer_token == ask_token && terms.ask_token == offer_token)
+                .then_some(Fill { order_ref, terms })
+        })
+        .collect();
+
+    // Best first means the most offered per unit asked.
+    resting.sort_by(|a, b| {
+        let a_rate = U256::from(a.terms.offer_amount) * U256::from(b.terms.ask_amount);
+        let b_rate = U256::from(b.terms.offer_amount) * U256::from(a.terms.ask_amount);
+        b_rate.cmp(&a_rate)
+    });
+
+    let mut quote = Quote {
+        leftover: amount,
+        ..Default::default()
+    };
+    for fill in resting {
+        if fill.terms.ask_amount > quote.leftover {
+            continue;
+        }
+        quote.leftover -= fill.terms.ask_amount;
+        quote.paid += fill.terms.ask_amount;
+        quote.received = quote.received.saturating_add(fill.terms.offer_amount);
+        quote.fills.push(fill);
+    }
+
+    let (amount_a, amount_b) = match side {
+        Side::OffersA => (quote.paid, quote.received),
+        Side::OffersB => (quote.received, quote.paid),
+    };
+    quote.average_price = Price::from_amounts(amount_b, amount_a, Rounding::Down);
+
+    Some(quote)
+}
+
+sp_api::decl_runtime_apis! {
+    /// Quotes from the dex's resting book.
+    pub trait DexApi {
+        /// Quote an order in the given pair, offering `amount` of the token on the given
+        /// side, or `None` if there is no order book for the pair.
+        fn quote(pair: (u8, u8), side: Side, amount: u128) -> Option<Quote>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DexConfig01, OuterVerifier, Output};
+    use sp_core::H256;
+    use tuxedo_core::{types::OutputRef, verifier::UpForGrabs};
+
+    fn rest<T: dex::DexConfig<Verifier = OuterVerifier>>(
+        index: u32,
+        offer_amount: u128,
+        ask_amount: u128,
+    ) -> OutputRef {
+        let output_ref = OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index,
+        };
+        let order = dex::Order::<T>::new(
+            offer_amount,
+            ask_amount,
+            OuterVerifier::UpForGrabs(UpForGrabs),
+        );
+        let output = Output {
+            payload: order.into(),
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+        };
+        sp_io::storage::set(&output_ref.encode(), &output.encode());
+        utxo_set::insert(&output_ref, &output);
+        output_ref
+    }
+
+    #[test]
+    fn best_orders_are_filled_first_and_the_rest_is_left_over() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            // Orders offering token 1 for token 0, at 2, 3 and 1 of token 1 per token 0.
+            let at_two = rest::<dex::OppositeSide<DexConfig01>>(0, 20, 10);
+            let at_three = rest::<dex::OppositeSide<DexConfig01>>(1, 30, 10);
+            rest::<dex::OppositeSide<DexConfig01>>(2, 10, 10);
+            // An order on our own side, which we cannot fill.
+            rest::<DexConfig01>(3, 10, 10);
+
+            let quote = quote((0, 1), Side::OffersA, 25).unwrap();
+            let filled: Vec<OutputRef> = quote.fills.iter().map(|f| f.order_ref.clone()).collect();
+            assert_eq!(filled, vec![at_three, at_two]);
+            assert_eq!(quote.paid, 20);
+            assert_eq!(quote.received, 50);
+            assert_eq!(quote.leftover, 5);
+            assert_eq!(
+                quote.average_price,
+                Price::from_amounts(50, 20, Rounding::Down)
+            );
+        });
+    }
+
+    #[test]
+    fn unknown_pairs_are_not_quoted() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            assert_eq!(quote((0, 9), Side::OffersA, 10), None);
+        });
+    }
+}
@@ -0,0 +1,231 @@
+//! Verifying the decryption of sealed match transactions.
+//!
+//! A large `MatchOrders` transaction is visible in the pool before it is included, so a
+//! block author can trade ahead of it and behind it. To avoid that, a matcher may instead
+//! encrypt the transaction to a decryption committee, and hand only the ciphertext to the
+//! authors. At authorship the committee decrypts it, and its members sign a
+//! [`DecryptionProof`] that ties the ciphertext to the plaintext, so that the author can
+//! neither change the transaction nor claim that it decrypted to something else.
+//!
+//! This module is the runtime side of that flow: it checks decryption proofs against the
+//! [`committee`] and hands back the decrypted match, and exposes the check to the node
+//! through [`SealedMatchApi`]. The encryption itself, and the encrypted pool on the node,
+//! live outside the runtime.
+
+use crate::{OuterConstraintChecker, Transaction};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::{sr25519, H256};
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use sp_std::prelude::*;
+
+/// Domain separator for the payload committee members sign.
+const DECRYPTION_CONTEXT: &[u8] = b"dex-sealed-match";
+
+/// The keys that may decrypt sealed matches, and how many of them must agree.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Committee {
+    pub members: Vec<H256>,
+    pub threshold: u32,
+}
+
+/// The development committee, made of the same well-known keys as the genesis council.
+pub fn committee() -> Committee {
+    Committee {
+        members: vec![
+            hex_literal::hex!("d2bf4b844dfefd6772a8843e669f943408966a977e3ae2af1dd78e0f55f4df67")
+                .into(),
+            hex_literal::hex!("baa81e58b1b4d053c2e86d93045765036f9d265c7dfe8b9693bbc2c0f048d93a")
+                .into(),
+        ],
+        threshold: 2,
+    }
+}
+
+/// A committee member's signature on a decryption.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct DecryptionShare {
+    /// The index of the member in the committee
+    pub member: u32,
+    /// The member's signature on the [`signing_payload`]
+    pub signature: sr25519::Signature,
+}
+
+/// The committee's statement that a ciphertext decrypts to a plaintext.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct DecryptionProof {
+    pub shares: Vec<DecryptionShare>,
+}
+
+/// All the ways a decryption proof can fail.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum DecryptionError {
+    /// A share names a committee member that does not exist.
+    UnknownMember,
+    /// The same member signed more than once.
+    DuplicateMember,
+    /// A share's signature does not sign the ciphertext and plaintext.
+    BadSignature,
+    /// Fewer members signed than the committee's threshold.
+    BelowThreshold,
+    /// The plaintext is not a transaction.
+    NotATransaction,
+    /// The plaintext is a transaction, but not a `MatchOrders` one.
+    NotAMatch,
+}
+
+/// What committee members sign to say that the ciphertext decrypts to the plaintext.
+pub fn signing_payload(ciphertext: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let ciphertext_hash: H256 = BlakeTwo256::hash(ciphertext);
+    let plaintext_hash: H256 = BlakeTwo256::hash(plaintext);
+    (DECRYPTION_CONTEXT, ciphertext_hash, plaintext_hash).encode()
+}
+
+/// Check that enough members of the committee signed that the ciphertext decrypts to the
+/// plaintext, and return the match transaction it holds.
+pub fn verify_decryption(
+    committee: &Committee,
+    ciphertext: &[u8],
+    plaintext: &[u8],
+    proof: &DecryptionProof,
+) -> Result<Transaction, DecryptionError> {
+    let payload = signing_payload(ciphertext, plaintext);
+    let mut signers: Vec<u32> = Vec::with_capacity(proof.shares.len());
+    for share in &proof.shares {
+        let member = committee
+            .members
+            .get(share.member as usize)
+            .ok_or(DecryptionError::UnknownMember)?;
+        if signers.contains(&share.member) {
+            return Err(DecryptionError::DuplicateMember);
+        }
+        let public = sr25519::Public::from_h256(*member);
+        if !sp_io::crypto::sr25519_verify(&share.signature, &payload, &public) {
+            return Err(DecryptionError::BadSignature);
+        }
+        signers.push(share.member);
+    }
+    if (signers.len() as u32) < committee.threshold {
+        return Err(DecryptionError::BelowThreshold);
+    }
+
+    let transaction = Transaction::decode_all(&mut &plaintext[..])
+        .map_err(|_| DecryptionError::NotATransaction)?;
+    match transaction.checker {
+        OuterConstraintChecker::MatchOrders(_) => Ok(transaction),
+        _ => Err(DecryptionError::NotAMatch),
+    }
+}
+
+sp_api::decl_runtime_apis! {
+    /// Checking decrypted sealed matches before including them.
+    pub trait SealedMatchApi {
+        /// The match a sealed transaction decrypts to, if the committee proved it.
+        fn verify_decryption(
+            ciphertext: Vec<u8>,
+            plaintext: Vec<u8>,
+            proof: DecryptionProof,
+        ) -> Result<Transaction, DecryptionError>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::Pair;
+
+    fn members() -> Vec<sr25519::Pair> {
+        (1..=3u8)
+            .map(|seed| sr25519::Pair::from_seed(&[seed; 32]))
+            .collect()
+    }
+
+    fn test_committee(threshold: u32) -> Committee {
+        Committee {
+            members: members().iter().map(|pair| pair.public().into()).collect(),
+            threshold,
+        }
+    }
+
+    fn a_match() -> Vec<u8> {
+        Transaction {
+            inputs: vec![],
+            outputs: vec![],
+            checker: OuterConstraintChecker::MatchOrders(Default::default()),
+        }
+        .encode()
+    }
+
+    fn proof(signers: &[u32], ciphertext: &[u8], plaintext: &[u8]) -> DecryptionProof {
+        let members = members();
+        let payload = signing_payload(ciphertext, plaintext);
+        DecryptionProof {
+            shares: signers
+                .iter()
+                .map(|&member| DecryptionShare {
+                    member,
+                    signature: members[member as usize].sign(&payload),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn threshold_of_members_proves_a_decryption() {
+        let plaintext = a_match();
+        let proof = proof(&[0, 2], b"ciphertext", &plaintext);
+        let decrypted = verify_decryption(&test_committee(2), b"ciphertext", &plaintext, &proof);
+        assert_eq!(decrypted.map(|tx| tx.encode()), Ok(plaintext));
+    }
+
+    #[test]
+    fn too_few_members_do_not() {
+        let plaintext = a_match();
+        let proof = proof(&[1], b"ciphertext", &plaintext);
+        assert_eq!(
+            verify_decryption(&test_committee(2), b"ciphertext", &plaintext, &proof),
+            Err(DecryptionError::BelowThreshold)
+        );
+    }
+
+    #[test]
+    fn members_cannot_sign_twice() {
+        let plaintext = a_match();
+        let proof = proof(&[1, 1], b"ciphertext", &plaintext);
+        assert_eq!(
+            verify_decryption(&test_committee(2), b"ciphertext", &plaintext, &proof),
+            Err(DecryptionError::DuplicateMember)
+        );
+    }
+
+    #[test]
+    fn proofs_are_bound_to_the_ciphertext() {
+        let plaintext = a_match();
+        let proof = proof(&[0, 1], b"ciphertext", &plaintext);
+        assert_eq!(
+            verify_decryption(&test_committee(2), b"other ciphertext", &plaintext, &proof),
+            Err(DecryptionError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn only_matches_can_be_sealed() {
+        let plaintext = Transaction {
+            inputs: vec![],
+            outputs: vec![],
+            checker: OuterConstraintChecker::MakeOrder01(Default::default()),
+        }
+        .encode();
+        let proof = proof(&[0, 1], b"ciphertext", &plaintext);
+        assert_eq!(
+            verify_decryption(&test_committee(2), b"ciphertext", &plaintext, &proof),
+            Err(DecryptionError::NotAMatch)
+        );
+    }
+}
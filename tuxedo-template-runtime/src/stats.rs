@@ -0,0 +1,274 @@
+//! Ticker data for each market, kept by the runtime as matches are applied.
+//!
+//! Whenever a match is applied, the runtime adds its fills to the statistics of the markets
+//! they are in. Each market keeps its last price and one bucket per hour of the trailing
+//! day, so a [`Ticker`] with the 24 hour volume and price range can be read through the
+//! [`StatsApi`] without indexing the chain. Buckets are keyed by the hour of the block's
+//! timestamp, and are dropped once they fall out of the trailing day.
+//!
+//! Markets are identified by their pair in [`TRADED_PAIRS`], whose first token is the base
+//! that volumes are measured in and prices are given per unit of.
+
+use crate::{
+    order_book::{self, TRADED_PAIRS},
+    Output, Runtime, Transaction,
+};
+use dex::{Price, Rounding};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::prelude::*;
+use tuxedo_core::types::OutputRef;
+
+/// The prefix of the storage keys under which each market's statistics are recorded.
+pub const STATS_PREFIX: &[u8] = b"dex_stats";
+
+/// The length of an hour, in milliseconds.
+const HOUR: u64 = 60 * 60 * 1000;
+
+/// How many hours of trading a ticker covers.
+pub const TICKER_HOURS: u64 = 24;
+
+/// The trading in one market during one hour.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct HourlyStats {
+    /// The number of hours since the epoch at the start of this hour
+    pub hour: u64,
+    /// The amount of the base token that changed hands
+    pub base_volume: u128,
+    /// The amount of the quote token that changed hands
+    pub quote_volume: u128,
+    /// The highest price traded at
+    pub high: Price,
+    /// The lowest price traded at
+    pub low: Price,
+}
+
+/// The recorded trading in one market.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default, TypeInfo)]
+pub struct PairStats {
+    /// The price of the most recent trade
+    pub last_price: Option<Price>,
+    /// The hours of the trailing day in which there was trading, oldest first
+    pub hours: Vec<HourlyStats>,
+}
+
+/// A summary of the trading in one market over the trailing day.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Default, TypeInfo)]
+pub struct Ticker {
+    /// The price of the most recent trade, however long ago it was
+    pub last_price: Option<Price>,
+    /// The amount of the base token that changed hands
+    pub base_volume: u128,
+    /// The amount of the quote token that changed hands
+    pub quote_volume: u128,
+    /// The highest price traded at, if there was any trading
+    pub high: Option<Price>,
+    /// The lowest price traded at, if there was any trading
+    pub low: Option<Price>,
+}
+
+/// The storage key of the statistics of the given market.
+fn stats_key(pair: (u8, u8)) -> Vec<u8> {
+    (STATS_PREFIX, pair).encode()
+}
+
+/// The recorded statistics of the given market.
+pub fn pair_stats(pair: (u8, u8)) -> PairStats {
+    sp_io::storage::get(&stats_key(pair))
+        .and_then(|encoded| PairStats::decode(&mut &encoded[..]).ok())
+        .unwrap_or_default()
+}
+
+/// The first hour that is still in the trailing day at the given time.
+fn first_hour(now: u64) -> u64 {
+    (now / HOUR).saturating_sub(TICKER_HOURS - 1)
+}
+
+/// Record the trades made by a match that was just applied at the given time.
+///
+/// `spent` must be the outputs that the transaction spent.
+pub(crate) fn record(transaction: &Transaction, spent: &[(OutputRef, Output)], now: u64) {
+    let fills = order_book::fills(sp_std::slice::from_ref(transaction), spent);
+    if fills.is_empty() {
+        return;
+    }
+
+    for &pair in TRADED_PAIRS {
+        let trades: Vec<(u128, u128)> = fills
+            .iter()
+            .filter_map(|fill| fill.amounts_in(pair))
+            .filter(|&(base, _)| base > 0)
+            .collect();
+        if trades.is_empty() {
+            continue;
+        }
+
+        let mut stats = pair_stats(pair);
+        stats.hours.retain(|bucket| bucket.hour >= first_hour(now));
+        for (base, quote) in trades {
+            let Some(price) = Price::from_amounts(quote, base, Rounding::Down) else {
+                continue;
+            };
+            stats.last_price = Some(price);
+
+            let hour = now / HOUR;
+            match stats.hours.last_mut() {
+                Some(bucket) if bucket.hour == hour => {
+                    bucket.base_volume = bucket.base_volume.saturating_add(base);
+                    bucket.quote_volume = bucket.quote_volume.saturating_add(quote);
+                    bucket.high = bucket.high.max(price);
+                    bucket.low = bucket.low.min(price);
+                }
+                _ => stats.hours.push(HourlyStats {
+                    hour,
+                    base_volume: base,
+                    quote_volume: quote,
+                    high: price,
+                    low: price,
+                }),
+            }
+        }
+        sp_io::storage::set(&stats_key(pair), &stats.encode());
+    }
+}
+
+/// The ticker of the given market over the day before the given time.
+///
+/// Returns `None` if the runtime has no market for the pair in that order.
+pub fn ticker_at(pair: (u8, u8), now: u64) -> Option<Ticker> {
+    if !TRADED_PAIRS.contains(&pair) {
+        return None;
+    }
+
+    let stats = pair_stats(pair);
+    let day = stats
+        .hours
+        .iter()
+        .filter(|bucket| bucket.hour >= first_hour(now));
+    Some(day.fold(
+        Ticker {
+            last_price: stats.last_price,
+            ..Default::default()
+        },
+        |ticker, bucket| {
+            Ticker {
+                last_price: ticker.last_price,
+                base_volume: ticker.base_volume.saturating_add(bucket.base_volume),
+                quote_volume: ticker.quote_volume.saturating_add(bucket.quote_volume),
+                high: Some(
+                    ticker
+                        .high
+                        .map_or(bucket.high, |high| high.max(bucket.high)),
+                ),
+                low: Some(ticker.low.map_or(bucket.low, |low| low.min(bucket.low))),
+            }
+        },
+    ))
+}
+
+/// The ticker of the given market over the trailing day, as of the latest block.
+pub fn ticker(pair: (u8, u8)) -> Option<Ticker> {
+    ticker_at(pair, Runtime::context().timestamp)
+}
+
+sp_api::decl_runtime_apis! {
+    /// Ticker data for the dex markets.
+    pub trait StatsApi {
+        /// The ticker of the given market over the trailing day, or `None` if the runtime
+        /// has no market for the pair in that order.
+        fn ticker(pair: (u8, u8)) -> Option<Ticker>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DexConfig01, OuterConstraintChecker, OuterVerifier};
+    use sp_core::H256;
+    use tuxedo_core::{types::Input, verifier::UpForGrabs};
+
+    /// A match of one order offering `base` of token 0 for `quote` of token 1, and the
+    /// order it spent.
+    fn trade(index: u32, base: u128, quote: u128) -> (Transaction, Vec<(OutputRef, Output)>) {
+        let order_ref = OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index,
+        };
+        let order =
+            dex::Order::<DexConfig01>::new(base, quote, OuterVerifier::UpForGrabs(UpForGrabs));
+        let transaction = Transaction {
+            inputs: vec![Input {
+                output_ref: order_ref.clone(),
+                redeemer: Vec::new(),
+            }],
+            outputs: vec![],
+            checker: OuterConstraintChecker::MatchOrders(Default::default()),
+        };
+        let spent = Output {
+            payload: order.into(),
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+        };
+        (transaction, vec![(order_ref, spent)])
+    }
+
+    fn price(quote: u128, base: u128) -> Price {
+        Price::from_amounts(quote, base, Rounding::Down).unwrap()
+    }
+
+    #[test]
+    fn tickers_add_up_the_trailing_day() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let (tx, spent) = trade(0, 10, 20);
+            record(&tx, &spent, 0);
+            let (tx, spent) = trade(1, 10, 50);
+            record(&tx, &spent, 5 * HOUR);
+            let (tx, spent) = trade(2, 20, 30);
+            record(&tx, &spent, 5 * HOUR + 1);
+
+            assert_eq!(
+                ticker_at((0, 1), 23 * HOUR),
+                Some(Ticker {
+                    last_price: Some(price(30, 20)),
+                    base_volume: 40,
+                    quote_volume: 100,
+                    high: Some(price(50, 10)),
+                    low: Some(price(30, 20)),
+                })
+            );
+
+            // The first trade falls out of the trailing day.
+            assert_eq!(
+                ticker_at((0, 1), 24 * HOUR),
+                Some(Ticker {
+                    last_price: Some(price(30, 20)),
+                    base_volume: 30,
+                    quote_volume: 80,
+                    high: Some(price(50, 10)),
+                    low: Some(price(30, 20)),
+                })
+            );
+        });
+    }
+
+    #[test]
+    fn quiet_markets_keep_their_last_price() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let (tx, spent) = trade(0, 10, 20);
+            record(&tx, &spent, 0);
+
+            assert_eq!(
+                ticker_at((0, 1), 100 * HOUR),
+                Some(Ticker {
+                    last_price: Some(price(20, 10)),
+                    ..Default::default()
+                })
+            );
+            assert_eq!(ticker_at((1, 0), 0), None);
+        });
+    }
+}
@@ -0,0 +1,199 @@
+//! Tips for block authors.
+//!
+//! A transaction may designate at most one of its outputs as a tip by protecting it with
+//! the [`BlockAuthorTip`] verifier. The tip must be a coin of token 0. The transaction pool
+//! adds the tip's value to the transaction's priority, so transactions that tip more are
+//! included sooner.
+//!
+//! While a block is built or imported, the runtime remembers where each tip was stored.
+//! When the block is finalized it hands all of them to the block author by replacing their
+//! verifiers with an [`Sr25519SigCheck`] for the author's Aura key. If the author cannot be
+//! determined from the header, the tips stay locked forever, which amounts to burning them.
+
+use crate::{
+    verifiers::{BlockAuthorTip, Sr25519SigCheck},
+    OuterVerifier, Output, Runtime,
+};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_consensus_aura::{digests::CompatibleDigestItem, sr25519::AuthoritySignature};
+use sp_core::H256;
+use sp_runtime::DigestItem;
+use sp_std::prelude::*;
+use tuxedo_core::{traits::Cash, types::OutputRef};
+
+/// The storage key under which the author of the current block is recorded.
+const BLOCK_AUTHOR_KEY: &[u8] = b"block_author";
+
+/// The storage key under which the tips paid so far in the current block are recorded.
+const PENDING_TIPS_KEY: &[u8] = b"pending_tips";
+
+/// All the ways a transaction can misuse the tip convention.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum TipError {
+    /// More than one output is protected by the `BlockAuthorTip` verifier.
+    MultipleTips,
+    /// The output protected by the `BlockAuthorTip` verifier is not a coin of token 0.
+    TipNotInToken0,
+}
+
+/// The index and value of the tip among the given outputs, if there is one.
+pub fn find_tip(outputs: &[Output]) -> Result<Option<(u32, u128)>, TipError> {
+    let mut tips = outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, output)| output.verifier == OuterVerifier::BlockAuthorTip(BlockAuthorTip));
+
+    let Some((index, tip)) = tips.next() else {
+        return Ok(None);
+    };
+    if tips.next().is_some() {
+        return Err(TipError::MultipleTips);
+    }
+
+    let coin = tip
+        .payload
+        .extract::<money::Coin<0>>()
+        .map_err(|_| TipError::TipNotInToken0)?;
+
+    Ok(Some((index as u32, coin.value())))
+}
+
+/// Record the author of the block with the given header, according to its Aura pre-digest.
+pub(crate) fn record_author(header: &crate::Header) {
+    let slot = header.digest.logs().iter().find_map(|log| {
+        <DigestItem as CompatibleDigestItem<AuthoritySignature>>::as_aura_pre_digest(log)
+    });
+    let authorities = Runtime::aura_authorities();
+
+    match slot {
+        Some(slot) if !authorities.is_empty() => {
+            let author = &authorities[(*slot % authorities.len() as u64) as usize];
+            let author: &[u8] = author.as_ref();
+            sp_io::storage::set(BLOCK_AUTHOR_KEY, &H256::from_slice(author).encode());
+        }
+        _ => sp_io::storage::clear(BLOCK_AUTHOR_KEY),
+    }
+}
+
+/// Remember that a tip was just stored at the given location.
+pub(crate) fn record_pending(tip: OutputRef) {
+    let mut pending = pending();
+    pending.push(tip);
+    sp_io::storage::set(PENDING_TIPS_KEY, &pending.encode());
+}
+
+/// The tips paid so far in the current block.
+fn pending() -> Vec<OutputRef> {
+    sp_io::storage::get(PENDING_TIPS_KEY)
+        .and_then(|encoded| Vec::<OutputRef>::decode(&mut &encoded[..]).ok())
+        .unwrap_or_default()
+}
+
+/// Hand every tip paid in the current block to the block author.
+///
+/// A tip cannot be spent before it is assigned, because its verifier never verifies,
+/// so every pending tip is still in storage.
+pub(crate) fn assign_to_author() {
+    let author = sp_io::storage::get(BLOCK_AUTHOR_KEY)
+        .and_then(|encoded| H256::decode(&mut &encoded[..]).ok());
+
+    if let Some(author) = author {
+        for output_ref in pending() {
+            let key = output_ref.encode();
+            let Some(mut tip) = sp_io::storage::get(&key)
+                .and_then(|encoded| Output::decode(&mut &encoded[..]).ok())
+            else {
+                continue;
+            };
+
+            tip.verifier = OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+                owner_pubkey: author,
+            });
+            sp_io::storage::set(&key, &tip.encode());
+            crate::utxo_set::insert(&output_ref, &tip);
+        }
+    }
+
+    sp_io::storage::clear(PENDING_TIPS_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tuxedo_core::verifier::UpForGrabs;
+
+    fn tip(payload: impl Into<tuxedo_core::dynamic_typing::DynamicallyTypedData>) -> Output {
+        Output {
+            payload: payload.into(),
+            verifier: OuterVerifier::BlockAuthorTip(BlockAuthorTip),
+        }
+    }
+
+    fn coin(value: u128) -> Output {
+        Output {
+            payload: money::Coin::<0>(value).into(),
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+        }
+    }
+
+    #[test]
+    fn no_tip_is_fine() {
+        assert_eq!(find_tip(&[coin(5)]), Ok(None));
+    }
+
+    #[test]
+    fn tip_is_found() {
+        assert_eq!(
+            find_tip(&[coin(5), tip(money::Coin::<0>(3))]),
+            Ok(Some((1, 3)))
+        );
+    }
+
+    #[test]
+    fn two_tips_fail() {
+        assert_eq!(
+            find_tip(&[tip(money::Coin::<0>(3)), tip(money::Coin::<0>(4))]),
+            Err(TipError::MultipleTips)
+        );
+    }
+
+    #[test]
+    fn tip_in_other_token_fails() {
+        assert_eq!(
+            find_tip(&[tip(money::Coin::<1>(3))]),
+            Err(TipError::TipNotInToken0)
+        );
+    }
+
+    #[test]
+    fn tips_are_assigned_to_author() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let author = H256::repeat_byte(7);
+            sp_io::storage::set(BLOCK_AUTHOR_KEY, &author.encode());
+
+            let output_ref = OutputRef {
+                tx_hash: H256::repeat_byte(1),
+                index: 0,
+            };
+            sp_io::storage::set(&output_ref.encode(), &tip(money::Coin::<0>(3)).encode());
+            record_pending(output_ref.clone());
+
+            assign_to_author();
+
+            let stored = sp_io::storage::get(&output_ref.encode())
+                .and_then(|encoded| Output::decode(&mut &encoded[..]).ok())
+                .expect("Tip is still stored");
+            assert_eq!(
+                stored.verifier,
+                OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+                    owner_pubkey: author
+                })
+            );
+            assert!(pending().is_empty());
+        });
+    }
+}
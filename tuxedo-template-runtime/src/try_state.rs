@@ -0,0 +1,348 @@
+//! Sanity checks of the dex state, in the spirit of the `try-state` hooks of FRAME.
+//!
+//! The runtime keeps indices next to the UTXO set, and trusts that they agree with it: the
+//! leaves of the [`utxo_set`] commitment and the open order counts of [`order_limits`].
+//! Value conservation trusts that [`cash_value`] counts the collateral of every kind of
+//! order. A bug in any of them would go unnoticed until it is exploited, so [`try_state`]
+//! walks the entire state and reports every way it breaks these invariants:
+//! - No open order offers nothing, or asks for nothing unless its dex allows giveaways.
+//! - Every UTXO has a leaf that commits to it, and every leaf commits to a UTXO.
+//! - The open order count of every owner is the number of orders they have open.
+//! - For each token, the collateral that value conservation counts as locked in open orders
+//!   is what those orders hold by their own terms.
+//!
+//! Node operators run it against live state through the [`TryStateApi`], with the node's
+//! `try-state` subcommand. It touches every storage item, so it is far too slow to run
+//! while building blocks.
+
+use crate::{asset_pair, cash_value, order_limits, utxo_set, OuterData, Output};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_std::{collections::btree_map::BTreeMap, prelude::*};
+use tuxedo_core::{dynamic_typing::UtxoData, types::OutputRef};
+
+/// All the ways the state can break the invariants of the dex.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum TryStateError {
+    /// The open order stored here offers nothing, or asks for nothing.
+    ZeroAmountOrder { order_ref: OutputRef },
+    /// The UTXO stored here has no leaf in the UTXO set commitment.
+    MissingLeaf { output_ref: OutputRef },
+    /// The leaf for this ref commits to a UTXO that is not stored here.
+    StaleLeaf { output_ref: OutputRef },
+    /// The open order count of the owner is not the number of orders they have open.
+    OpenOrdersMismatch {
+        owner: H256,
+        indexed: u32,
+        open: u32,
+    },
+    /// Value conservation counts a different amount of the token with this type id as
+    /// locked in open orders than the orders hold.
+    CollateralMismatch {
+        type_id: [u8; 4],
+        locked: u128,
+        held: u128,
+    },
+}
+
+/// Every UTXO in storage, by its ref.
+///
+/// Every UTXO is stored directly under its encoded output ref. Other storage items have keys
+/// of different shapes, so they fail to decode as a whole.
+fn utxos() -> BTreeMap<OutputRef, Output> {
+    let mut utxos = BTreeMap::new();
+    let mut key = Vec::new();
+    while let Some(next) = sp_io::storage::next_key(&key) {
+        let utxo = OutputRef::decode_all(&mut &next[..]).ok().zip(
+            sp_io::storage::get(&next)
+                .and_then(|encoded| Output::decode_all(&mut &encoded[..]).ok()),
+        );
+        if let Some((output_ref, output)) = utxo {
+            utxos.insert(output_ref, output);
+        }
+        key = next;
+    }
+    utxos
+}
+
+/// The open order count of every owner that has one.
+fn indexed_open_orders() -> BTreeMap<H256, u32> {
+    let prefix = order_limits::OPEN_ORDERS_PREFIX.encode();
+    let mut counts = BTreeMap::new();
+    let mut key = prefix.clone();
+    while let Some(next) = sp_io::storage::next_key(&key) {
+        if !next.starts_with(&prefix) {
+            break;
+        }
+        let count = H256::decode_all(&mut &next[prefix.len()..]).ok().zip(
+            sp_io::storage::get(&next).and_then(|encoded| u32::decode_all(&mut &encoded[..]).ok()),
+        );
+        if let Some((owner, count)) = count {
+            counts.insert(owner, count);
+        }
+        key = next;
+    }
+    counts
+}
+
+/// Whether the open order held in a UTXO is for nothing, or `None` if it holds no order.
+fn is_zero_order(output: &Output) -> Option<bool> {
+    Some(match OuterData::try_from(&output.payload).ok()? {
+        OuterData::Order01(order) => order.ensure_not_zero().is_err(),
+        OuterData::Order10(order) => order.ensure_not_zero().is_err(),
+        OuterData::AssetOrderAB(order) => order.ensure_not_zero().is_err(),
+        OuterData::AssetOrderBA(order) => order.ensure_not_zero().is_err(),
+        OuterData::Iceberg01(order) => order.offer_amount == 0 || order.ask_amount == 0,
+        OuterData::Iceberg10(order) => order.offer_amount == 0 || order.ask_amount == 0,
+        OuterData::Commitment01(commitment) => commitment.collateral == 0,
+        OuterData::Commitment10(commitment) => commitment.collateral == 0,
+        OuterData::Auction01(auction) => auction.lot == 0,
+        _ => return None,
+    })
+}
+
+/// The type id of the token that the open order held in a UTXO locks, and how much of it
+/// the order holds by its own terms, if it holds an order.
+///
+/// Plain orders hold their offer and their bond. Iceberg orders and order commitments
+/// record their collateral, and auctions their lot.
+fn order_collateral(output: &Output) -> Option<([u8; 4], u128)> {
+    let token_a = <money::Coin<0> as UtxoData>::TYPE_ID;
+    let token_b = <money::Coin<1> as UtxoData>::TYPE_ID;
+    let (asset_a, asset_b) = asset_pair();
+    Some(match OuterData::try_from(&output.payload).ok()? {
+        OuterData::Order01(order) => (token_a, order.offer_amount.saturating_add(order.bond)),
+        OuterData::Order10(order) => (token_b, order.offer_amount.saturating_add(order.bond)),
+        OuterData::AssetOrderAB(order) => (
+            money::multi_asset::asset_type_id(asset_a),
+            order.offer_amount.saturating_add(order.bond),
+        ),
+        OuterData::AssetOrderBA(order) => (
+            money::multi_asset::asset_type_id(asset_b),
+            order.offer_amount.saturating_add(order.bond),
+        ),
+        OuterData::Iceberg01(order) => (token_a, order.collateral),
+        OuterData::Iceberg10(order) => (token_b, order.collateral),
+        OuterData::Commitment01(commitment) => (token_a, commitment.collateral),
+        OuterData::Commitment10(commitment) => (token_b, commitment.collateral),
+        OuterData::Auction01(auction) => (token_a, auction.lot),
+        _ => return None,
+    })
+}
+
+/// Every way the current state breaks the invariants of the dex, or nothing if it is sane.
+pub fn try_state() -> Vec<TryStateError> {
+    let mut errors = Vec::new();
+    let utxos = utxos();
+
+    for (order_ref, _) in utxos
+        .iter()
+        .filter(|(_, utxo)| is_zero_order(utxo) == Some(true))
+    {
+        errors.push(TryStateError::ZeroAmountOrder {
+            order_ref: order_ref.clone(),
+        });
+    }
+
+    let leaves: BTreeMap<OutputRef, H256> = utxo_set::leaves().into_iter().collect();
+    for output_ref in utxos
+        .keys()
+        .filter(|output_ref| !leaves.contains_key(output_ref))
+    {
+        errors.push(TryStateError::MissingLeaf {
+            output_ref: output_ref.clone(),
+        });
+    }
+    for (output_ref, leaf) in &leaves {
+        let committed = utxos
+            .get(output_ref)
+            .map_or(false, |utxo| utxo_set::leaf_hash(output_ref, utxo) == *leaf);
+        if !committed {
+            errors.push(TryStateError::StaleLeaf {
+                output_ref: output_ref.clone(),
+            });
+        }
+    }
+
+    let mut open_orders: BTreeMap<H256, (u32, u32)> = BTreeMap::new();
+    for (owner, indexed) in indexed_open_orders() {
+        open_orders.entry(owner).or_default().0 = indexed;
+    }
+    for owner in utxos.values().filter_map(order_limits::order_owner) {
+        let open = &mut open_orders.entry(owner).or_default().1;
+        *open = open.saturating_add(1);
+    }
+    for (owner, (indexed, open)) in open_orders {
+        if indexed != open {
+            errors.push(TryStateError::OpenOrdersMismatch {
+                owner,
+                indexed,
+                open,
+            });
+        }
+    }
+
+    let mut collateral: BTreeMap<[u8; 4], (u128, u128)> = BTreeMap::new();
+    for utxo in utxos.values() {
+        let Some((type_id, held)) = order_collateral(utxo) else {
+            continue;
+        };
+        let totals = collateral.entry(type_id).or_default();
+        totals.1 = totals.1.saturating_add(held);
+        if let Some((locked_type_id, locked)) = cash_value(&utxo.payload) {
+            let totals = collateral.entry(locked_type_id).or_default();
+            totals.0 = totals.0.saturating_add(locked);
+        }
+    }
+    for (type_id, (locked, held)) in collateral {
+        if locked != held {
+            errors.push(TryStateError::CollateralMismatch {
+                type_id,
+                locked,
+                held,
+            });
+        }
+    }
+
+    errors
+}
+
+sp_api::decl_runtime_apis! {
+    /// Sanity checks of the dex state.
+    pub trait TryStateApi {
+        /// Every way the current state breaks the invariants of the dex, or nothing if it
+        /// is sane.
+        fn try_state() -> Vec<TryStateError>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DexConfig01, GenesisConfig, OuterVerifier};
+    use sp_runtime::BuildStorage;
+    use tuxedo_core::verifier::UpForGrabs;
+
+    fn genesis_ext() -> sp_io::TestExternalities {
+        GenesisConfig::default()
+            .build_storage()
+            .expect("System builds valid default genesis config")
+            .into()
+    }
+
+    fn order(offer_amount: u128, owner: H256) -> Output {
+        Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: dex::Order::<DexConfig01> {
+                owner,
+                ..dex::Order::new(offer_amount, 5, OuterVerifier::UpForGrabs(UpForGrabs))
+            }
+            .into(),
+        }
+    }
+
+    fn order_ref(byte: u8) -> OutputRef {
+        OutputRef {
+            tx_hash: H256::repeat_byte(byte),
+            index: 0,
+        }
+    }
+
+    #[test]
+    fn genesis_is_sane() {
+        genesis_ext().execute_with(|| {
+            assert_eq!(try_state(), vec![]);
+        });
+    }
+
+    #[test]
+    fn orders_must_be_indexed() {
+        genesis_ext().execute_with(|| {
+            let owner = H256::repeat_byte(9);
+            let output_ref = order_ref(1);
+            sp_io::storage::set(&output_ref.encode(), &order(10, owner).encode());
+
+            assert_eq!(
+                try_state(),
+                vec![
+                    TryStateError::MissingLeaf {
+                        output_ref: output_ref.clone()
+                    },
+                    TryStateError::OpenOrdersMismatch {
+                        owner,
+                        indexed: 0,
+                        open: 1
+                    },
+                ]
+            );
+
+            utxo_set::insert(&output_ref, &order(10, owner));
+            order_limits::apply(&[], &[order(10, owner)]);
+            assert_eq!(try_state(), vec![]);
+        });
+    }
+
+    #[test]
+    fn zero_amount_orders_are_reported() {
+        genesis_ext().execute_with(|| {
+            let owner = H256::repeat_byte(9);
+            let output_ref = order_ref(1);
+            sp_io::storage::set(&output_ref.encode(), &order(0, owner).encode());
+            utxo_set::insert(&output_ref, &order(0, owner));
+            order_limits::apply(&[], &[order(0, owner)]);
+
+            assert_eq!(
+                try_state(),
+                vec![TryStateError::ZeroAmountOrder {
+                    order_ref: output_ref
+                }]
+            );
+        });
+    }
+
+    #[test]
+    fn empty_auctions_are_reported() {
+        genesis_ext().execute_with(|| {
+            let output_ref = order_ref(1);
+            let auction = Output {
+                verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                payload: dex::DutchAuction::<DexConfig01> {
+                    lot: 0,
+                    start_price: dex::Price::ONE,
+                    floor_price: dex::Price::ONE,
+                    started_at: 0,
+                    duration: 0,
+                    payout_verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+                    _ph_data: Default::default(),
+                }
+                .into(),
+            };
+            sp_io::storage::set(&output_ref.encode(), &auction.encode());
+            utxo_set::insert(&output_ref, &auction);
+
+            assert_eq!(
+                try_state(),
+                vec![TryStateError::ZeroAmountOrder {
+                    order_ref: output_ref
+                }]
+            );
+        });
+    }
+
+    #[test]
+    fn changed_utxos_leave_their_leaves_stale() {
+        genesis_ext().execute_with(|| {
+            let owner = H256::repeat_byte(9);
+            let output_ref = order_ref(1);
+            utxo_set::insert(&output_ref, &order(10, owner));
+            order_limits::apply(&[], &[order(10, owner)]);
+            sp_io::storage::set(&output_ref.encode(), &order(20, owner).encode());
+
+            assert_eq!(try_state(), vec![TryStateError::StaleLeaf { output_ref }]);
+        });
+    }
+}
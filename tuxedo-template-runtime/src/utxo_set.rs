@@ -0,0 +1,349 @@
+//! A commitment to the entire UTXO set, included in every block header.
+//!
+//! The runtime keeps one leaf for every UTXO in storage, under keys that sort by the
+//! SCALE encoding of the UTXO's `OutputRef`. Each leaf is the hash of the UTXO's reference together with the UTXO
+//! itself. When a block is finalized, the runtime builds a binary merkle tree over all
+//! the leaves in that order and puts the root in a digest item of the header.
+//!
+//! Light clients and bridges that trust a header can therefore check a proof that some
+//! UTXO, like an open order, exists without holding any state themselves. Full nodes
+//! produce such proofs through the [`UtxoProofApi`], and anybody can check them with
+//! [`verify_proof`], which also works in `no_std` environments.
+//!
+//! Building the tree touches every leaf, so finalizing a block costs time linear in the
+//! size of the UTXO set.
+
+use crate::{Output, OutputRefs, Transaction};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::{ConsensusEngineId, Digest, DigestItem};
+use sp_std::prelude::*;
+use tuxedo_core::types::OutputRef;
+
+/// The prefix of the storage keys under which the leaves are stored.
+pub const UTXO_LEAF_PREFIX: &[u8] = b"utxo_leaf";
+
+/// The engine id of the header digest item that carries the UTXO set commitment.
+pub const UTXO_COMMITMENT_ENGINE_ID: ConsensusEngineId = *b"utxo";
+
+/// The storage key of the leaf for the UTXO with the given reference.
+///
+/// The reference is appended to the prefix without any length prefix of its own so that
+/// leaves sort by reference.
+pub fn leaf_key(output_ref: &OutputRef) -> Vec<u8> {
+    [UTXO_LEAF_PREFIX, &output_ref.encode()].concat()
+}
+
+/// The leaf committing to a single UTXO.
+pub fn leaf_hash(output_ref: &OutputRef, output: &Output) -> H256 {
+    sp_io::hashing::blake2_256(&(output_ref, output).encode()).into()
+}
+
+/// The parent of two nodes in the merkle tree.
+pub fn node_hash(left: &H256, right: &H256) -> H256 {
+    sp_io::hashing::blake2_256(&[left.as_bytes(), right.as_bytes()].concat()).into()
+}
+
+/// The root of the binary merkle tree over the given leaves.
+///
+/// Each level pairs up adjacent nodes. A node left without a partner at the end of a
+/// level moves up unchanged. The root of an empty tree is the zero hash.
+pub fn merkle_root(leaves: &[H256]) -> H256 {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!("chunks of two have one or two elements"),
+            })
+            .collect();
+    }
+    level.first().copied().unwrap_or_default()
+}
+
+/// Add or replace the leaf for a UTXO.
+pub(crate) fn insert(output_ref: &OutputRef, output: &Output) {
+    sp_io::storage::set(
+        &leaf_key(output_ref),
+        &leaf_hash(output_ref, output).encode(),
+    );
+}
+
+/// Remove the leaf for a UTXO that has been spent.
+pub(crate) fn remove(output_ref: &OutputRef) {
+    sp_io::storage::clear(&leaf_key(output_ref));
+}
+
+/// Update the leaves after a transaction has been successfully applied.
+pub(crate) fn apply(transaction: &Transaction) {
+    for input in &transaction.inputs {
+        remove(&input.output_ref);
+    }
+    for (output_ref, output) in transaction.output_refs().iter().zip(&transaction.outputs) {
+        insert(output_ref, output);
+    }
+}
+
+/// All the leaves, sorted by the encoded reference of the UTXO they commit to.
+pub fn leaves() -> Vec<(OutputRef, H256)> {
+    let mut leaves = Vec::new();
+    let mut key = UTXO_LEAF_PREFIX.to_vec();
+
+    while let Some(next) = sp_io::storage::next_key(&key) {
+        if !next.starts_with(UTXO_LEAF_PREFIX) {
+            break;
+        }
+
+        let leaf = OutputRef::decode(&mut &next[UTXO_LEAF_PREFIX.len()..])
+            .ok()
+            .zip(
+                sp_io::storage::get(&next).and_then(|encoded| H256::decode(&mut &encoded[..]).ok()),
+            );
+        if let Some(leaf) = leaf {
+            leaves.push(leaf);
+        }
+        key = next;
+    }
+
+    leaves
+}
+
+/// Evidence that a UTXO is part of the set committed to in some header.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct UtxoProof {
+    /// The UTXO that is proven to exist
+    pub output: Output,
+    /// The position of the UTXO's leaf among all the leaves
+    pub leaf_index: u32,
+    /// The total number of leaves in the tree
+    pub leaf_count: u32,
+    /// The siblings of the nodes on the path from the leaf to the root, bottom first.
+    /// Nodes that moved up a level unchanged have no sibling.
+    pub siblings: Vec<H256>,
+}
+
+/// Prove that the UTXO with the given reference is part of the current UTXO set.
+///
+/// The proof is valid against the commitment in the header of the block whose state this
+/// is called on.
+pub fn prove(output_ref: &OutputRef) -> Option<UtxoProof> {
+    let output = sp_io::storage::get(&output_ref.encode())
+        .and_then(|encoded| Output::decode(&mut &encoded[..]).ok())?;
+
+    let leaves = leaves();
+    let leaf_index = leaves.iter().position(|(r, _)| r == output_ref)?;
+    let mut level: Vec<H256> = leaves.into_iter().map(|(_, leaf)| leaf).collect();
+
+    let leaf_count = level.len() as u32;
+    let mut index = leaf_index;
+    let mut siblings = Vec::new();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            siblings.push(level[sibling]);
+        }
+        index /= 2;
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!("chunks of two have one or two elements"),
+            })
+            .collect();
+    }
+
+    Some(UtxoProof {
+        output,
+        leaf_index: leaf_index as u32,
+        leaf_count,
+        siblings,
+    })
+}
+
+/// Check that a proof shows the UTXO with the given reference to be part of the UTXO set
+/// with the given commitment.
+pub fn verify_proof(commitment: H256, output_ref: &OutputRef, proof: &UtxoProof) -> bool {
+    if proof.leaf_index >= proof.leaf_count {
+        return false;
+    }
+
+    let mut node = leaf_hash(output_ref, &proof.output);
+    let mut index = proof.leaf_index;
+    let mut width = proof.leaf_count;
+    let mut siblings = proof.siblings.iter();
+
+    while width > 1 {
+        let has_sibling = index % 2 == 1 || index + 1 < width;
+        if has_sibling {
+            let Some(sibling) = siblings.next() else {
+                return false;
+            };
+            node = if index % 2 == 0 {
+                node_hash(&node, sibling)
+            } else {
+                node_hash(sibling, &node)
+            };
+        }
+        index /= 2;
+        width = (width + 1) / 2;
+    }
+
+    siblings.next().is_none() && node == commitment
+}
+
+sp_api::decl_runtime_apis! {
+    /// Proofs that UTXOs exist, which can be checked against the UTXO set commitment
+    /// in a block header without access to any state.
+    pub trait UtxoProofApi {
+        /// Prove that the UTXO with the given reference exists, if it does.
+        fn prove(output_ref: OutputRef) -> Option<UtxoProof>;
+    }
+}
+
+/// The commitment to the current UTXO set.
+pub fn commitment() -> H256 {
+    let leaves: Vec<H256> = leaves().into_iter().map(|(_, leaf)| leaf).collect();
+    merkle_root(&leaves)
+}
+
+/// The header digest item carrying the given commitment.
+pub fn digest_item(commitment: H256) -> DigestItem {
+    DigestItem::Consensus(UTXO_COMMITMENT_ENGINE_ID, commitment.encode())
+}
+
+/// The commitment carried in a header digest, if there is one.
+pub fn commitment_in(digest: &Digest) -> Option<H256> {
+    digest.logs().iter().find_map(|log| match log {
+        DigestItem::Consensus(id, data) if *id == UTXO_COMMITMENT_ENGINE_ID => {
+            H256::decode(&mut &data[..]).ok()
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OuterVerifier;
+    use tuxedo_core::verifier::UpForGrabs;
+
+    fn utxo(value: u128) -> Output {
+        Output {
+            verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            payload: money::Coin::<0>(value).into(),
+        }
+    }
+
+    fn output_ref(byte: u8, index: u32) -> OutputRef {
+        OutputRef {
+            tx_hash: H256::repeat_byte(byte),
+            index,
+        }
+    }
+
+    #[test]
+    fn empty_tree_has_zero_root() {
+        assert_eq!(merkle_root(&[]), H256::zero());
+    }
+
+    #[test]
+    fn single_leaf_is_the_root() {
+        let leaf = H256::repeat_byte(3);
+        assert_eq!(merkle_root(&[leaf]), leaf);
+    }
+
+    #[test]
+    fn odd_leaf_moves_up_unchanged() {
+        let (a, b, c) = (
+            H256::repeat_byte(1),
+            H256::repeat_byte(2),
+            H256::repeat_byte(3),
+        );
+        assert_eq!(merkle_root(&[a, b, c]), node_hash(&node_hash(&a, &b), &c));
+    }
+
+    #[test]
+    fn leaves_are_sorted_by_reference() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            insert(&output_ref(2, 0), &utxo(1));
+            insert(&output_ref(1, 1), &utxo(2));
+            insert(&output_ref(1, 0), &utxo(3));
+
+            let refs: Vec<OutputRef> = leaves().into_iter().map(|(r, _)| r).collect();
+            assert_eq!(
+                refs,
+                vec![output_ref(1, 0), output_ref(1, 1), output_ref(2, 0)]
+            );
+        });
+    }
+
+    #[test]
+    fn spending_changes_the_commitment() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            insert(&output_ref(1, 0), &utxo(1));
+            let just_one = commitment();
+            insert(&output_ref(2, 0), &utxo(2));
+            assert_ne!(commitment(), just_one);
+
+            remove(&output_ref(2, 0));
+            assert_eq!(commitment(), just_one);
+        });
+    }
+
+    #[test]
+    fn commitment_round_trips_through_digest() {
+        let mut digest = Digest::default();
+        assert_eq!(commitment_in(&digest), None);
+
+        digest.push(digest_item(H256::repeat_byte(9)));
+        assert_eq!(commitment_in(&digest), Some(H256::repeat_byte(9)));
+    }
+
+    #[test]
+    fn proofs_verify_for_every_leaf() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let refs: Vec<OutputRef> = (0..5).map(|i| output_ref(1, i)).collect();
+            for (value, r) in refs.iter().enumerate() {
+                let output = utxo(value as u128);
+                sp_io::storage::set(&r.encode(), &output.encode());
+                insert(r, &output);
+            }
+            let root = commitment();
+
+            for r in &refs {
+                let proof = prove(r).expect("UTXO exists");
+                assert!(verify_proof(root, r, &proof));
+            }
+        });
+    }
+
+    #[test]
+    fn proof_for_tampered_output_fails() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            for i in 0..3 {
+                let r = output_ref(1, i);
+                sp_io::storage::set(&r.encode(), &utxo(1).encode());
+                insert(&r, &utxo(1));
+            }
+            let root = commitment();
+
+            let mut proof = prove(&output_ref(1, 2)).expect("UTXO exists");
+            proof.output = utxo(1_000);
+            assert!(!verify_proof(root, &output_ref(1, 2), &proof));
+        });
+    }
+
+    #[test]
+    fn missing_utxo_cannot_be_proven() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            assert_eq!(prove(&output_ref(1, 0)), None);
+        });
+    }
+}
@@ -0,0 +1,976 @@
+//! Additional verifiers used by this runtime.
+//!
+//! Tuxedo core already ships with the most common verifiers:
+//! * [`SigCheck`](tuxedo_core::verifier::SigCheck) - a single sr25519 signature.
+//! * [`ThresholdMultiSignature`](tuxedo_core::verifier::ThresholdMultiSignature) - an m-of-n
+//!   multisignature over sr25519 keys.
+//! * [`UpForGrabs`](tuxedo_core::verifier::UpForGrabs) - anyone can spend.
+//!
+//! This module rounds out the set of signature schemes with ed25519 and ecdsa so that users
+//! whose keys come from other ecosystems can still own coins and receive dex payouts.
+//! [`Sr25519SigCheck`] is like `SigCheck`, except that its signatures are over the short
+//! [`signing_payload`] that hardware wallets can sign, rather than the whole transaction.
+//! It also provides a [`TimeLock`] wrapper that delays spending until a given block height,
+//! and a [`CompoundVerifier`] that combines several verifiers with and / or / threshold logic.
+//! [`MultisigProposal`] lets several signatories approve a spend in separate transactions,
+//! and [`Recoverable`] lets guardians hand coins to a new owner when the owner loses their key.
+//! [`Delegated`] lets a delegate key sign only some kinds of transactions, like opening orders.
+//! Finally, [`BlockAuthorTip`] marks an output as a tip for whoever authors the block.
+
+use crate::{
+    BlockNumber, DeskConfig, OuterConstraintChecker, OuterData, OuterVerifier, Output, Runtime,
+    SocialRecoveryConfig, Transaction,
+};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::{ecdsa, ed25519, sr25519, H256};
+use sp_std::{boxed::Box, vec::Vec};
+use tuxedo_core::{types::OutputRef, Verifier};
+
+/// The bytes that every [`signing_payload`] starts with.
+///
+/// They keep signatures over transactions apart from signatures over anything else the
+/// same key signs, like the `<Bytes>`-wrapped messages of wallets.
+pub const SIGNING_PAYLOAD_TAG: &[u8] = b"tuxedo-transaction:";
+
+/// The longest simplified transaction, in bytes, that is signed as it is.
+///
+/// Longer transactions are signed by their blake2-256 hash instead, so that no payload is
+/// longer than a hardware wallet can take. This is the same bound that FRAME chains use.
+pub const MAX_UNHASHED_TRANSACTION_LENGTH: usize = 256;
+
+/// The message that the signature verifiers in this module expect to be signed.
+///
+/// It binds the simplified transaction to this chain's genesis hash, so that a signed
+/// transaction cannot be replayed on another chain built from the same pieces.
+pub fn signing_payload(simplified_tx: &[u8]) -> Vec<u8> {
+    signing_payload_on(Runtime::genesis_hash(), simplified_tx)
+}
+
+/// The [`signing_payload`] of a simplified transaction on the chain with the given genesis hash.
+///
+/// The payload is the [`SIGNING_PAYLOAD_TAG`], then the genesis hash, then either a zero byte
+/// and the transaction, or a one byte and the transaction's blake2-256 hash if it is longer
+/// than [`MAX_UNHASHED_TRANSACTION_LENGTH`]. So it is never longer than 308 bytes.
+///
+/// Wallets use this directly, since they learn the genesis hash from the node.
+pub fn signing_payload_on(genesis_hash: H256, simplified_tx: &[u8]) -> Vec<u8> {
+    let mut payload = SIGNING_PAYLOAD_TAG.to_vec();
+    payload.extend_from_slice(genesis_hash.as_bytes());
+    if simplified_tx.len() > MAX_UNHASHED_TRANSACTION_LENGTH {
+        payload.push(1);
+        payload.extend_from_slice(&sp_io::hashing::blake2_256(simplified_tx));
+    } else {
+        payload.push(0);
+        payload.extend_from_slice(simplified_tx);
+    }
+    payload
+}
+
+/// Require a signature from the private key corresponding to the given sr25519 public key.
+///
+/// Unlike `SigCheck`, the signature must be over the [`signing_payload`], so keys kept
+/// on hardware wallets can sign transactions of any size.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Sr25519SigCheck {
+    pub owner_pubkey: H256,
+}
+
+impl Verifier for Sr25519SigCheck {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+        let sig = match sr25519::Signature::try_from(redeemer) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+
+        sp_io::crypto::sr25519_verify(
+            &sig,
+            &signing_payload(simplified_tx),
+            &sr25519::Public::from_h256(self.owner_pubkey),
+        )
+    }
+}
+
+/// Require a signature from the private key corresponding to the given ed25519 public key.
+///
+/// The signature must be over the [`signing_payload`], not the bare simplified transaction.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Ed25519SigCheck {
+    pub owner_pubkey: H256,
+}
+
+impl Verifier for Ed25519SigCheck {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+        let sig = match ed25519::Signature::try_from(redeemer) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+
+        sp_io::crypto::ed25519_verify(
+            &sig,
+            &signing_payload(simplified_tx),
+            &ed25519::Public::from_h256(self.owner_pubkey),
+        )
+    }
+}
+
+/// Require a signature from the private key corresponding to the given ecdsa public key.
+///
+/// Unlike the other signature schemes, ecdsa public keys are 33 bytes (compressed) so
+/// they do not fit in an `H256`. The signature must be over the [`signing_payload`].
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct EcdsaSigCheck {
+    pub owner_pubkey: ecdsa::Public,
+}
+
+impl Verifier for EcdsaSigCheck {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+        let sig = match ecdsa::Signature::try_from(redeemer) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+
+        sp_io::crypto::ecdsa_verify(&sig, &signing_payload(simplified_tx), &self.owner_pubkey)
+    }
+}
+
+/// Wrap another verifier so that it may only be satisfied once the chain has reached
+/// a given block height.
+///
+/// This is useful for refunding expired orders or hash time-locked contracts, as well as
+/// for vesting-style coins. The inner verifier still has to be satisfied by the redeemer.
+///
+/// The block height is the one recorded by the runtime via [`Runtime::block_number`].
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct TimeLock<V> {
+    /// The verifier that must be satisfied once the lock has expired.
+    pub verifier: Box<V>,
+    /// The first block height at which the UTXO may be spent.
+    pub unlocks_at: BlockNumber,
+}
+
+impl<V: Verifier> Verifier for TimeLock<V> {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+        Runtime::block_number() >= self.unlocks_at && self.verifier.verify(simplified_tx, redeemer)
+    }
+}
+
+/// Combine several verifiers into one.
+///
+/// This allows, for example, a dex order's `payout_verifier` to express "either of my two keys"
+/// or "2-of-3 treasury keys" where each key may use a different scheme.
+///
+/// The redeemer is a SCALE encoded `Vec<(u32, Vec<u8>)>` of (index, inner redeemer) pairs. The
+/// indices refer to positions in the list of inner verifiers and must be strictly increasing so
+/// that no inner verifier can be counted twice.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum CompoundVerifier<V> {
+    /// Every one of the inner verifiers must be satisfied.
+    And(Vec<V>),
+    /// At least one of the inner verifiers must be satisfied.
+    Or(Vec<V>),
+    /// At least `threshold` of the inner verifiers must be satisfied.
+    Threshold { threshold: u32, verifiers: Vec<V> },
+}
+
+impl<V> CompoundVerifier<V> {
+    /// The inner verifiers and how many of them must be satisfied.
+    fn requirement(&self) -> (&[V], usize) {
+        match self {
+            Self::And(verifiers) => (verifiers, verifiers.len()),
+            Self::Or(verifiers) => (verifiers, 1),
+            Self::Threshold {
+                threshold,
+                verifiers,
+            } => (verifiers, *threshold as usize),
+        }
+    }
+}
+
+impl<V: Verifier> Verifier for CompoundVerifier<V> {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+        let (verifiers, required) = self.requirement();
+
+        // A compound verifier that requires nothing would be up for grabs by accident.
+        if required == 0 || required > verifiers.len() {
+            return false;
+        }
+
+        let inner_redeemers = match Vec::<(u32, Vec<u8>)>::decode(&mut &redeemer[..]) {
+            Ok(r) => r,
+            Err(_) => return false,
+        };
+
+        let mut last_index = None;
+        let mut satisfied = 0;
+        for (index, inner_redeemer) in inner_redeemers {
+            if last_index.map_or(false, |last| index <= last) {
+                return false;
+            }
+            last_index = Some(index);
+
+            match verifiers.get(index as usize) {
+                Some(verifier) if verifier.verify(simplified_tx, &inner_redeemer) => satisfied += 1,
+                _ => return false,
+            }
+        }
+
+        satisfied >= required
+    }
+}
+
+/// Require a multisig proposal, approved by enough of the given signatories, that names
+/// the spending transaction.
+///
+/// Unlike a threshold multisignature, the signatories need not sign the same transaction.
+/// Each of them approves the proposal in a transaction of their own, with the multisig piece,
+/// and the proposal names the spending transaction by the blake2-256 hash of its simplified
+/// encoding. The redeemer is the SCALE encoded `OutputRef` of the approved proposal.
+///
+/// Trading desks keep the collateral of their large orders behind this verifier, and make it
+/// the orders' `cancel_verifier`, so that opening and cancelling them needs their approval.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct MultisigProposal {
+    /// The verifiers of the signatories who may approve spends
+    pub signatories: Vec<OuterVerifier>,
+    /// How many of the signatories must approve each spend
+    pub threshold: u32,
+}
+
+impl Verifier for MultisigProposal {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+        let Ok(proposal_ref) = OutputRef::decode(&mut &redeemer[..]) else {
+            return false;
+        };
+        let Some(proposal) = Runtime::peek_utxo(&proposal_ref).and_then(|utxo| {
+            utxo.payload
+                .extract::<multisig::Proposal<DeskConfig>>()
+                .ok()
+        }) else {
+            return false;
+        };
+
+        proposal.signatories == self.signatories
+            && proposal.threshold == self.threshold
+            && proposal.is_approved()
+            && proposal.transaction == H256::from(sp_io::hashing::blake2_256(simplified_tx))
+    }
+}
+
+/// Let an owner's guardians hand the owner's coins to a new owner, should the owner lose their
+/// key.
+///
+/// The owner spends as if the coins were protected by the owner's verifier alone. Once a
+/// recovery by the recovery piece names this owner, guardians and threshold, has been approved
+/// by enough of the guardians, and has waited out at least this verifier's delay, its new owner
+/// may spend in place of the owner. Payouts and refunds of orders follow the verifier, so they
+/// are recovered too.
+///
+/// The redeemer is a SCALE encoded [`RecoverableRedeemer`]. Recovery does not stop the old key
+/// from working, so new owners should move the coins to verifiers of their own.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Recoverable {
+    /// The verifier that controls the coins unless they are recovered
+    pub owner: Box<OuterVerifier>,
+    /// The verifiers of the guardians who may approve recoveries
+    pub guardians: Vec<OuterVerifier>,
+    /// How many of the guardians must approve a recovery
+    pub threshold: u32,
+    /// The fewest blocks that the owner has to cancel a recovery once it is approved
+    pub delay: u32,
+}
+
+/// How a [`Recoverable`] verifier is satisfied.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum RecoverableRedeemer {
+    /// The owner spends, with a redeemer for the owner's verifier
+    Owner(Vec<u8>),
+    /// The new owner of a ready recovery spends, with a redeemer for the new owner's verifier
+    Recovered {
+        recovery: OutputRef,
+        redeemer: Vec<u8>,
+    },
+}
+
+impl Verifier for Recoverable {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+        let (recovery_ref, redeemer) = match RecoverableRedeemer::decode(&mut &redeemer[..]) {
+            Ok(RecoverableRedeemer::Owner(redeemer)) => {
+                return self.owner.verify(simplified_tx, &redeemer)
+            }
+            Ok(RecoverableRedeemer::Recovered { recovery, redeemer }) => (recovery, redeemer),
+            Err(_) => return false,
+        };
+        let Some(recovery) = Runtime::peek_utxo(&recovery_ref).and_then(|utxo| {
+            utxo.payload
+                .extract::<recovery::Recovery<SocialRecoveryConfig>>()
+                .ok()
+        }) else {
+            return false;
+        };
+
+        recovery.owner == *self.owner
+            && recovery.guardians == self.guardians
+            && recovery.threshold == self.threshold
+            && recovery.delay >= self.delay
+            && recovery.is_ready(Runtime::block_number())
+            && recovery.new_owner.verify(simplified_tx, &redeemer)
+    }
+}
+
+/// The kinds of transactions that a [`Delegated`] verifier's delegate may sign.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, TypeInfo)]
+pub enum Permission {
+    /// Open dex orders, which must pay out to the owner
+    MakeOrder,
+    /// Cancel dex orders, whose refunds always go to their payout verifiers
+    CancelOrder,
+}
+
+impl Permission {
+    /// The permission that a delegate needs to sign a transaction with the given checker, if
+    /// delegates may sign it at all.
+    fn needed_for(checker: &OuterConstraintChecker) -> Option<Self> {
+        match checker {
+            OuterConstraintChecker::MakeOrder01(_)
+            | OuterConstraintChecker::MakeOrder10(_)
+            | OuterConstraintChecker::MakeAssetOrderAB(_)
+            | OuterConstraintChecker::MakeAssetOrderBA(_) => Some(Self::MakeOrder),
+            OuterConstraintChecker::CancelOrder01(_)
+            | OuterConstraintChecker::CancelOrder10(_)
+            | OuterConstraintChecker::CancelAssetOrderAB(_)
+            | OuterConstraintChecker::CancelAssetOrderBA(_) => Some(Self::CancelOrder),
+            _ => None,
+        }
+    }
+}
+
+/// Let a delegate spend an owner's coins, but only in transactions of the permitted kinds.
+///
+/// This lets a trading bot's hot key open and cancel orders with an owner's coins, without
+/// being able to spend them in any other way. The delegate's permission is checked by decoding
+/// the simplified transaction and looking at its constraint checker. Orders opened by the
+/// delegate must pay out to the owner, or to this verifier. The delegate can still trade at bad
+/// prices, and lose any collateral beyond an order's offer, so it must be trusted that far.
+///
+/// The redeemer is a SCALE encoded [`DelegatedRedeemer`].
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Delegated {
+    /// The verifier that may spend the coins in any way
+    pub owner: Box<OuterVerifier>,
+    /// The verifier that may spend the coins in permitted transactions only
+    pub delegate: Box<OuterVerifier>,
+    /// The kinds of transactions that the delegate may sign
+    pub permissions: Vec<Permission>,
+}
+
+/// How a [`Delegated`] verifier is satisfied.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum DelegatedRedeemer {
+    /// The owner spends, with a redeemer for the owner's verifier
+    Owner(Vec<u8>),
+    /// The delegate spends, with a redeemer for the delegate's verifier
+    Delegate(Vec<u8>),
+}
+
+impl Delegated {
+    /// Whether the delegate may sign the given transaction.
+    fn permits(&self, transaction: &Transaction) -> bool {
+        let Some(permission) = Permission::needed_for(&transaction.checker) else {
+            return false;
+        };
+        let pays_owner = transaction
+            .outputs
+            .iter()
+            .all(|output| self.pays_owner(output));
+
+        // Orders that the delegate opens must not pay the delegate, or anyone else.
+        self.permissions.contains(&permission)
+            && (permission != Permission::MakeOrder || pays_owner)
+    }
+
+    /// Whether the given output is an order that pays out to the owner.
+    fn pays_owner(&self, output: &Output) -> bool {
+        let payout_verifier = match OuterData::try_from(&output.payload) {
+            Ok(OuterData::Order01(order)) => order.payout_verifier,
+            Ok(OuterData::Order10(order)) => order.payout_verifier,
+            Ok(OuterData::AssetOrderAB(order)) => order.payout_verifier,
+            Ok(OuterData::AssetOrderBA(order)) => order.payout_verifier,
+            _ => return false,
+        };
+        payout_verifier == *self.owner || payout_verifier == OuterVerifier::Delegated(self.clone())
+    }
+}
+
+impl Verifier for Delegated {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+        match DelegatedRedeemer::decode(&mut &redeemer[..]) {
+            Ok(DelegatedRedeemer::Owner(redeemer)) => self.owner.verify(simplified_tx, &redeemer),
+            Ok(DelegatedRedeemer::Delegate(redeemer)) => {
+                Transaction::decode(&mut &simplified_tx[..])
+                    .map_or(false, |transaction| self.permits(&transaction))
+                    && self.delegate.verify(simplified_tx, &redeemer)
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// Marks an output as a tip for the author of the block that includes it.
+///
+/// Nobody knows who that author will be when the transaction is signed, so this
+/// verifier never verifies. When the block is finalized the runtime replaces it with
+/// a [`SigCheck`](tuxedo_core::verifier::SigCheck) for the actual author. See the
+/// [`tips`](crate::tips) module.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct BlockAuthorTip;
+
+impl Verifier for BlockAuthorTip {
+    fn verify(&self, _simplified_tx: &[u8], _redeemer: &[u8]) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::Pair;
+    use tuxedo_core::verifier::TestVerifier;
+
+    fn on_chain<R>(genesis_hash: H256, f: impl FnOnce() -> R) -> R {
+        sp_io::TestExternalities::default().execute_with(|| {
+            Runtime::record_genesis_hash(genesis_hash);
+            f()
+        })
+    }
+
+    fn at_block<R>(number: BlockNumber, f: impl FnOnce() -> R) -> R {
+        sp_io::TestExternalities::default().execute_with(|| {
+            Runtime::set_block_number(number);
+            f()
+        })
+    }
+
+    #[test]
+    fn ed25519_sig_check_with_good_sig() {
+        on_chain(H256::repeat_byte(1), || {
+            let pair = ed25519::Pair::from_seed(&[0u8; 32]);
+            let simplified_tx = b"hello world".as_slice();
+            let sig = pair.sign(&signing_payload(simplified_tx));
+            let redeemer: &[u8] = sig.as_ref();
+
+            let sig_check = Ed25519SigCheck {
+                owner_pubkey: pair.public().0.into(),
+            };
+
+            assert!(sig_check.verify(simplified_tx, redeemer));
+        })
+    }
+
+    #[test]
+    fn ed25519_sig_check_with_bad_sig() {
+        on_chain(H256::repeat_byte(1), || {
+            let simplified_tx = b"hello world".as_slice();
+            let redeemer = b"bogus_signature".as_slice();
+
+            let sig_check = Ed25519SigCheck {
+                owner_pubkey: H256::zero(),
+            };
+
+            assert!(!sig_check.verify(simplified_tx, redeemer));
+        })
+    }
+
+    #[test]
+    fn ed25519_sig_check_with_wrong_key() {
+        on_chain(H256::repeat_byte(1), || {
+            let pair = ed25519::Pair::from_seed(&[0u8; 32]);
+            let other = ed25519::Pair::from_seed(&[1u8; 32]);
+            let simplified_tx = b"hello world".as_slice();
+            let sig = pair.sign(&signing_payload(simplified_tx));
+            let redeemer: &[u8] = sig.as_ref();
+
+            let sig_check = Ed25519SigCheck {
+                owner_pubkey: other.public().0.into(),
+            };
+
+            assert!(!sig_check.verify(simplified_tx, redeemer));
+        })
+    }
+
+    #[test]
+    fn ed25519_sig_check_replayed_on_other_chain_fails() {
+        let pair = ed25519::Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let sig = on_chain(H256::repeat_byte(1), || {
+            pair.sign(&signing_payload(simplified_tx))
+        });
+        let redeemer: &[u8] = sig.as_ref();
+
+        let sig_check = Ed25519SigCheck {
+            owner_pubkey: pair.public().0.into(),
+        };
+
+        on_chain(H256::repeat_byte(2), || {
+            assert!(!sig_check.verify(simplified_tx, redeemer));
+        })
+    }
+
+    #[test]
+    fn sr25519_sig_check_with_good_sig() {
+        on_chain(H256::repeat_byte(1), || {
+            let pair = sr25519::Pair::from_seed(&[0u8; 32]);
+            let simplified_tx = b"hello world".as_slice();
+            let sig = pair.sign(&signing_payload(simplified_tx));
+            let redeemer: &[u8] = sig.as_ref();
+
+            let sig_check = Sr25519SigCheck {
+                owner_pubkey: pair.public().0.into(),
+            };
+
+            assert!(sig_check.verify(simplified_tx, redeemer));
+        })
+    }
+
+    #[test]
+    fn sr25519_sig_check_over_the_bare_transaction_fails() {
+        on_chain(H256::repeat_byte(1), || {
+            let pair = sr25519::Pair::from_seed(&[0u8; 32]);
+            let simplified_tx = b"hello world".as_slice();
+            let sig = pair.sign(simplified_tx);
+            let redeemer: &[u8] = sig.as_ref();
+
+            let sig_check = Sr25519SigCheck {
+                owner_pubkey: pair.public().0.into(),
+            };
+
+            assert!(!sig_check.verify(simplified_tx, redeemer));
+        })
+    }
+
+    #[test]
+    fn long_transactions_are_signed_by_their_hash() {
+        let genesis_hash = H256::repeat_byte(1);
+        let short = [7u8; MAX_UNHASHED_TRANSACTION_LENGTH];
+        let long = [7u8; MAX_UNHASHED_TRANSACTION_LENGTH + 1];
+
+        let payload = signing_payload_on(genesis_hash, &short);
+        assert!(payload.starts_with(SIGNING_PAYLOAD_TAG));
+        assert!(payload.ends_with(&short));
+
+        let payload = signing_payload_on(genesis_hash, &long);
+        assert!(payload.ends_with(&sp_io::hashing::blake2_256(&long)));
+        assert_eq!(payload.len(), SIGNING_PAYLOAD_TAG.len() + 65);
+    }
+
+    #[test]
+    fn ecdsa_sig_check_with_good_sig() {
+        on_chain(H256::repeat_byte(1), || {
+            let pair = ecdsa::Pair::from_seed(&[0u8; 32]);
+            let simplified_tx = b"hello world".as_slice();
+            let sig = pair.sign(&signing_payload(simplified_tx));
+            let redeemer: &[u8] = sig.as_ref();
+
+            let sig_check = EcdsaSigCheck {
+                owner_pubkey: pair.public(),
+            };
+
+            assert!(sig_check.verify(simplified_tx, redeemer));
+        })
+    }
+
+    #[test]
+    fn ecdsa_sig_check_with_bad_sig() {
+        on_chain(H256::repeat_byte(1), || {
+            let pair = ecdsa::Pair::from_seed(&[0u8; 32]);
+            let simplified_tx = b"hello world".as_slice();
+            let redeemer = b"bogus_signature".as_slice();
+
+            let sig_check = EcdsaSigCheck {
+                owner_pubkey: pair.public(),
+            };
+
+            assert!(!sig_check.verify(simplified_tx, redeemer));
+        })
+    }
+
+    #[test]
+    fn time_lock_before_unlock_height_fails() {
+        let time_lock = TimeLock {
+            verifier: Box::new(TestVerifier { verifies: true }),
+            unlocks_at: 10,
+        };
+
+        at_block(9, || assert!(!time_lock.verify(&[], &[])));
+    }
+
+    #[test]
+    fn time_lock_at_unlock_height_passes() {
+        let time_lock = TimeLock {
+            verifier: Box::new(TestVerifier { verifies: true }),
+            unlocks_at: 10,
+        };
+
+        at_block(10, || assert!(time_lock.verify(&[], &[])));
+    }
+
+    #[test]
+    fn time_lock_after_unlock_height_still_requires_inner_verifier() {
+        let time_lock = TimeLock {
+            verifier: Box::new(TestVerifier { verifies: false }),
+            unlocks_at: 10,
+        };
+
+        at_block(20, || assert!(!time_lock.verify(&[], &[])));
+    }
+
+    fn compound_redeemer(indices: &[u32]) -> Vec<u8> {
+        indices
+            .iter()
+            .map(|i| (*i, Vec::<u8>::new()))
+            .collect::<Vec<_>>()
+            .encode()
+    }
+
+    fn yes() -> TestVerifier {
+        TestVerifier { verifies: true }
+    }
+
+    fn no() -> TestVerifier {
+        TestVerifier { verifies: false }
+    }
+
+    #[test]
+    fn compound_and_requires_all() {
+        let verifier = CompoundVerifier::And(vec![yes(), yes()]);
+
+        assert!(verifier.verify(&[], &compound_redeemer(&[0, 1])));
+        assert!(!verifier.verify(&[], &compound_redeemer(&[0])));
+    }
+
+    #[test]
+    fn compound_and_fails_when_any_inner_fails() {
+        let verifier = CompoundVerifier::And(vec![yes(), no()]);
+
+        assert!(!verifier.verify(&[], &compound_redeemer(&[0, 1])));
+    }
+
+    #[test]
+    fn compound_or_requires_one() {
+        let verifier = CompoundVerifier::Or(vec![no(), yes()]);
+
+        assert!(verifier.verify(&[], &compound_redeemer(&[1])));
+        assert!(!verifier.verify(&[], &compound_redeemer(&[0])));
+        assert!(!verifier.verify(&[], &compound_redeemer(&[])));
+    }
+
+    #[test]
+    fn compound_threshold_two_of_three() {
+        let verifier = CompoundVerifier::Threshold {
+            threshold: 2,
+            verifiers: vec![yes(), yes(), yes()],
+        };
+
+        assert!(verifier.verify(&[], &compound_redeemer(&[0, 2])));
+        assert!(!verifier.verify(&[], &compound_redeemer(&[1])));
+    }
+
+    #[test]
+    fn compound_rejects_repeated_indices() {
+        let verifier = CompoundVerifier::Threshold {
+            threshold: 2,
+            verifiers: vec![yes(), yes(), yes()],
+        };
+
+        assert!(!verifier.verify(&[], &compound_redeemer(&[1, 1])));
+        assert!(!verifier.verify(&[], &compound_redeemer(&[2, 1])));
+    }
+
+    #[test]
+    fn compound_rejects_out_of_bounds_index() {
+        let verifier = CompoundVerifier::Or(vec![yes()]);
+
+        assert!(!verifier.verify(&[], &compound_redeemer(&[3])));
+    }
+
+    #[test]
+    fn compound_with_nothing_required_fails() {
+        let verifier = CompoundVerifier::<TestVerifier>::Threshold {
+            threshold: 0,
+            verifiers: vec![],
+        };
+
+        assert!(!verifier.verify(&[], &compound_redeemer(&[])));
+    }
+
+    #[test]
+    fn compound_rejects_undecodable_redeemer() {
+        let verifier = CompoundVerifier::Or(vec![yes()]);
+
+        assert!(!verifier.verify(&[], b"bogus".as_slice()));
+    }
+
+    #[test]
+    fn compound_nests_with_real_signatures() {
+        let first = ed25519::Pair::from_seed(&[0u8; 32]);
+        let second = ed25519::Pair::from_seed(&[1u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+
+        let either_key = CompoundVerifier::Or(vec![
+            Ed25519SigCheck {
+                owner_pubkey: first.public().0.into(),
+            },
+            Ed25519SigCheck {
+                owner_pubkey: second.public().0.into(),
+            },
+        ]);
+
+        let sig = second.sign(simplified_tx);
+        let redeemer = vec![(1u32, sig.as_ref().to_vec())].encode();
+
+        assert!(either_key.verify(simplified_tx, &redeemer));
+    }
+
+    #[test]
+    fn compound_scale_encoding_round_trips() {
+        let verifier = CompoundVerifier::Threshold {
+            threshold: 2,
+            verifiers: vec![
+                CompoundVerifier::Or(vec![yes(), no()]),
+                CompoundVerifier::And(vec![yes()]),
+                CompoundVerifier::And(vec![]),
+            ],
+        };
+
+        let encoded = verifier.encode();
+        let decoded = CompoundVerifier::<CompoundVerifier<TestVerifier>>::decode(&mut &encoded[..])
+            .expect("Can decode compound verifier");
+
+        assert_eq!(decoded, verifier);
+    }
+
+    #[test]
+    fn compound_type_info_lists_all_variants() {
+        use scale_info::{TypeDef, TypeInfo};
+
+        let info = CompoundVerifier::<TestVerifier>::type_info();
+        let variant_names: Vec<_> = match info.type_def {
+            TypeDef::Variant(v) => v.variants.iter().map(|v| v.name).collect(),
+            _ => panic!("Compound verifier should be an enum"),
+        };
+
+        assert_eq!(variant_names, vec!["And", "Or", "Threshold"]);
+    }
+
+    /// Store a proposal between Alice and Bob for the given transaction, approved by the
+    /// given signatories, and return where it is.
+    fn store_proposal(simplified_tx: &[u8], approvals: Vec<u32>) -> OutputRef {
+        let proposal_ref = OutputRef {
+            tx_hash: H256::repeat_byte(9),
+            index: approvals.len() as u32,
+        };
+        let proposal = crate::Output {
+            payload: multisig::Proposal::<DeskConfig> {
+                signatories: desk().signatories,
+                threshold: desk().threshold,
+                transaction: sp_io::hashing::blake2_256(simplified_tx).into(),
+                approvals,
+            }
+            .into(),
+            verifier: OuterVerifier::UpForGrabs(tuxedo_core::verifier::UpForGrabs),
+        };
+        sp_io::storage::set(&proposal_ref.encode(), &proposal.encode());
+        proposal_ref
+    }
+
+    fn desk() -> MultisigProposal {
+        let signatory = |seed| {
+            OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+                owner_pubkey: H256::repeat_byte(seed),
+            })
+        };
+        MultisigProposal {
+            signatories: vec![signatory(1), signatory(2)],
+            threshold: 2,
+        }
+    }
+
+    #[test]
+    fn multisig_proposal_needs_an_approved_proposal_for_the_transaction() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let simplified_tx = b"hello world".as_slice();
+            let approved = store_proposal(simplified_tx, vec![0, 1]);
+            let half_approved = store_proposal(simplified_tx, vec![1]);
+
+            assert!(desk().verify(simplified_tx, &approved.encode()));
+            assert!(!desk().verify(b"goodbye world", &approved.encode()));
+            assert!(!desk().verify(simplified_tx, &half_approved.encode()));
+            assert!(!desk().verify(simplified_tx, b"bogus".as_slice()));
+
+            let other_desk = MultisigProposal {
+                threshold: 1,
+                ..desk()
+            };
+            assert!(!other_desk.verify(simplified_tx, &approved.encode()));
+        })
+    }
+
+    fn recoverable() -> Recoverable {
+        Recoverable {
+            owner: Box::new(OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+                owner_pubkey: H256::repeat_byte(1),
+            })),
+            guardians: desk().signatories,
+            threshold: 1,
+            delay: 10,
+        }
+    }
+
+    /// Store a recovery of the [`recoverable`] coins to a new owner that anyone can satisfy,
+    /// approved in the given block, and return where it is.
+    fn store_recovery(approved_at: Option<u32>) -> OutputRef {
+        let recovery_ref = OutputRef {
+            tx_hash: H256::repeat_byte(8),
+            index: 0,
+        };
+        let recoverable = recoverable();
+        let recovery = crate::Output {
+            payload: recovery::Recovery::<SocialRecoveryConfig> {
+                owner: *recoverable.owner,
+                new_owner: OuterVerifier::UpForGrabs(tuxedo_core::verifier::UpForGrabs),
+                guardians: recoverable.guardians,
+                threshold: recoverable.threshold,
+                delay: recoverable.delay,
+                approvals: vec![0],
+                approved_at,
+            }
+            .into(),
+            verifier: OuterVerifier::UpForGrabs(tuxedo_core::verifier::UpForGrabs),
+        };
+        sp_io::storage::set(&recovery_ref.encode(), &recovery.encode());
+        recovery_ref
+    }
+
+    #[test]
+    fn recoverable_coins_go_to_the_new_owner_after_the_delay() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let recovered = RecoverableRedeemer::Recovered {
+                recovery: store_recovery(Some(20)),
+                redeemer: Vec::new(),
+            }
+            .encode();
+
+            Runtime::set_block_number(29);
+            assert!(!recoverable().verify(&[], &recovered));
+            Runtime::set_block_number(30);
+            assert!(recoverable().verify(&[], &recovered));
+
+            let impatient = Recoverable {
+                delay: 11,
+                ..recoverable()
+            };
+            assert!(!impatient.verify(&[], &recovered));
+        })
+    }
+
+    #[test]
+    fn recoverable_coins_are_the_owners_until_recovered() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let pair = sr25519::Pair::from_seed(&[0u8; 32]);
+            let owned = Recoverable {
+                owner: Box::new(OuterVerifier::SigCheck(tuxedo_core::verifier::SigCheck {
+                    owner_pubkey: pair.public().0.into(),
+                })),
+                ..recoverable()
+            };
+            let simplified_tx = b"hello world".as_slice();
+            let sig = pair.sign(simplified_tx);
+            let redeemer = RecoverableRedeemer::Owner(sig.encode()).encode();
+
+            assert!(owned.verify(simplified_tx, &redeemer));
+            assert!(!owned.verify(b"goodbye world", &redeemer));
+
+            let unapproved = RecoverableRedeemer::Recovered {
+                recovery: store_recovery(None),
+                redeemer: Vec::new(),
+            };
+            Runtime::set_block_number(1_000);
+            assert!(!recoverable().verify(&[], &unapproved.encode()));
+        })
+    }
+
+    fn delegated(permissions: Vec<Permission>) -> Delegated {
+        Delegated {
+            owner: Box::new(OuterVerifier::Sr25519SigCheck(Sr25519SigCheck {
+                owner_pubkey: H256::repeat_byte(1),
+            })),
+            delegate: Box::new(OuterVerifier::UpForGrabs(tuxedo_core::verifier::UpForGrabs)),
+            permissions,
+        }
+    }
+
+    /// The simplified encoding of a transaction opening an order that pays the given verifier.
+    fn make_order(payout_verifier: OuterVerifier) -> Vec<u8> {
+        let order = dex::Order::<crate::DexConfig01> {
+            owner: H256::repeat_byte(1),
+            ..dex::Order::new(10, 20, payout_verifier)
+        };
+        Transaction {
+            inputs: Vec::new(),
+            outputs: vec![crate::Output {
+                payload: order.into(),
+                verifier: OuterVerifier::UpForGrabs(tuxedo_core::verifier::UpForGrabs),
+            }],
+            checker: OuterConstraintChecker::MakeOrder01(Default::default()),
+        }
+        .encode()
+    }
+
+    #[test]
+    fn delegates_may_open_orders_that_pay_the_owner() {
+        let verifier = delegated(vec![Permission::MakeOrder]);
+        let redeemer = DelegatedRedeemer::Delegate(Vec::new()).encode();
+
+        assert!(verifier.verify(&make_order(*verifier.owner.clone()), &redeemer));
+        assert!(verifier.verify(
+            &make_order(OuterVerifier::Delegated(verifier.clone())),
+            &redeemer
+        ));
+        assert!(!verifier.verify(&make_order(*verifier.delegate.clone()), &redeemer));
+    }
+
+    #[test]
+    fn delegates_may_only_sign_permitted_transactions() {
+        let redeemer = DelegatedRedeemer::Delegate(Vec::new()).encode();
+        let can_only_cancel = delegated(vec![Permission::CancelOrder]);
+        assert!(!can_only_cancel.verify(&make_order(*can_only_cancel.owner.clone()), &redeemer));
+
+        let spend = Transaction {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            checker: OuterConstraintChecker::Money(money::MoneyConstraintChecker::Spend),
+        };
+        let everything = delegated(vec![Permission::MakeOrder, Permission::CancelOrder]);
+        assert!(!everything.verify(&spend.encode(), &redeemer));
+        assert!(!everything.verify(b"not a transaction", &redeemer));
+    }
+
+    #[test]
+    fn block_author_tip_never_verifies() {
+        assert!(!BlockAuthorTip.verify(b"hello world", &[]));
+    }
+}
@@ -0,0 +1,255 @@
+//! Weights: how long transactions take to execute, so blocks can be packed safely.
+//!
+//! The length limit keeps any single transaction small, but a block author could still fill
+//! a block with many transactions that each take long to check. So every transaction also
+//! has a weight, an estimate of its execution time in nanoseconds on reference hardware. It
+//! is made up of
+//!
+//! * a base cost for decoding, hashing, and dispatching the transaction,
+//! * a cost per input for reading and removing the UTXO it spends,
+//! * a cost per output for storing the UTXO it creates,
+//! * a cost per signature that the inputs' verifiers check, and
+//! * a cost for the constraint checker, for checkers whose cost stands out.
+//!
+//! While a block is built or imported, the runtime adds up the weight of the transactions
+//! applied so far. A transaction that would take the block over [`MAX_BLOCK_WEIGHT`] is
+//! rejected as exhausting the block's resources, which tells the block builder to leave it
+//! for a later block. The checker costs come from the dex benchmarks (`cargo bench -p dex`),
+//! and like [`MAX_TRANSACTION_LENGTH`](crate::MAX_TRANSACTION_LENGTH) they should be
+//! recalibrated from them when the checkers change.
+
+use crate::{
+    verifiers::CompoundVerifier, OuterConstraintChecker, OuterVerifier, Output, Transaction,
+    BLOCK_TIME,
+};
+use parity_scale_codec::{Decode, Encode};
+
+/// An estimate of execution time, in nanoseconds on reference hardware.
+pub type Weight = u64;
+
+/// The most weight that a block's transactions may have in total.
+///
+/// This is a third of the block time, leaving the rest for importing and propagating the
+/// block.
+pub const MAX_BLOCK_WEIGHT: Weight = BLOCK_TIME * 1_000_000 / 3;
+
+/// The cost of every transaction, for decoding, hashing, and dispatching it.
+pub const BASE_TRANSACTION_WEIGHT: Weight = 50_000;
+
+/// The cost of reading and removing the UTXO that an input spends.
+pub const WEIGHT_PER_INPUT: Weight = 25_000;
+
+/// The cost of storing the UTXO that an output creates.
+pub const WEIGHT_PER_OUTPUT: Weight = 25_000;
+
+/// The cost of checking one signature.
+pub const WEIGHT_PER_SIGNATURE: Weight = 50_000;
+
+/// The cost of `MakeOrder` for each coin of collateral it adds up.
+pub const MAKE_ORDER_WEIGHT_PER_INPUT: Weight = 1_000;
+
+/// The cost of `MatchOrders` for each order it matches, including checking its payout.
+pub const MATCH_ORDERS_WEIGHT_PER_ORDER: Weight = 5_000;
+
+/// The storage key under which the weight of the block's transactions so far is recorded.
+const BLOCK_WEIGHT_KEY: &[u8] = b"block_weight";
+
+/// The number of signatures that spending a UTXO with the given verifier may check.
+///
+/// Multi-signatures and compound verifiers are counted as if they checked every
+/// signature they could.
+pub fn signatures(verifier: &OuterVerifier) -> u64 {
+    match verifier {
+        OuterVerifier::SigCheck(_)
+        | OuterVerifier::Ed25519SigCheck(_)
+        | OuterVerifier::EcdsaSigCheck(_)
+        | OuterVerifier::Sr25519SigCheck(_) => 1,
+        OuterVerifier::ThresholdMultiSignature(multisig) => multisig.signatories.len() as u64,
+        OuterVerifier::TimeLock(lock) => signatures(&lock.verifier),
+        // Whoever spends is either the owner or the new owner, who reads the recovery too.
+        OuterVerifier::Recoverable(recoverable) => signatures(&recoverable.owner) + 1,
+        OuterVerifier::Delegated(delegated) => {
+            signatures(&delegated.owner).max(signatures(&delegated.delegate))
+        }
+        OuterVerifier::Compound(
+            CompoundVerifier::And(verifiers)
+            | CompoundVerifier::Or(verifiers)
+            | CompoundVerifier::Threshold { verifiers, .. },
+        ) => verifiers.iter().map(signatures).sum(),
+        // The signatures were checked when the proposal was approved, so only its
+        // storage read and the hash of the transaction are left.
+        OuterVerifier::MultisigProposal(_) => 1,
+        OuterVerifier::UpForGrabs(_) | OuterVerifier::BlockAuthorTip(_) => 0,
+    }
+}
+
+/// The cost of the transaction's constraint checker, beyond the costs per input and output.
+fn checker_weight(transaction: &Transaction) -> Weight {
+    let inputs = transaction.inputs.len() as Weight;
+    match transaction.checker {
+        OuterConstraintChecker::MakeOrder01(_)
+        | OuterConstraintChecker::MakeOrder10(_)
+        | OuterConstraintChecker::MakeAssetOrderAB(_)
+        | OuterConstraintChecker::MakeAssetOrderBA(_) => inputs * MAKE_ORDER_WEIGHT_PER_INPUT,
+        OuterConstraintChecker::MatchOrders(_) | OuterConstraintChecker::MatchAssetOrders(_) => {
+            inputs * MATCH_ORDERS_WEIGHT_PER_ORDER
+        }
+        _ => 0,
+    }
+}
+
+/// The weight of a transaction, given the UTXOs that its inputs spend.
+///
+/// Inputs whose UTXOs are not given, because they do not exist yet, are counted as
+/// checking one signature.
+pub fn transaction_weight<'a>(
+    transaction: &Transaction,
+    spent: impl IntoIterator<Item = &'a Output>,
+) -> Weight {
+    let (known, checked) = spent.into_iter().fold((0, 0), |(known, checked), utxo| {
+        (known + 1, checked + signatures(&utxo.verifier))
+    });
+    let unknown = (transaction.inputs.len() as u64).saturating_sub(known);
+
+    BASE_TRANSACTION_WEIGHT
+        .saturating_add(transaction.inputs.len() as Weight * WEIGHT_PER_INPUT)
+        .saturating_add(transaction.outputs.len() as Weight * WEIGHT_PER_OUTPUT)
+        .saturating_add((checked + unknown).saturating_mul(WEIGHT_PER_SIGNATURE))
+        .saturating_add(checker_weight(transaction))
+}
+
+/// The weight of the transactions applied in the current block so far.
+pub fn block_weight() -> Weight {
+    sp_io::storage::get(BLOCK_WEIGHT_KEY)
+        .and_then(|encoded| Weight::decode(&mut &encoded[..]).ok())
+        .unwrap_or_default()
+}
+
+/// Whether a transaction of the given weight still fits in the current block.
+pub fn fits(weight: Weight) -> bool {
+    block_weight().saturating_add(weight) <= MAX_BLOCK_WEIGHT
+}
+
+/// Add the weight of a transaction that was applied to the current block's total.
+pub fn record(weight: Weight) {
+    sp_io::storage::set(
+        BLOCK_WEIGHT_KEY,
+        &block_weight().saturating_add(weight).encode(),
+    );
+}
+
+/// Forget the current block's total, once the block is finished.
+pub fn clear() {
+    sp_io::storage::clear(BLOCK_WEIGHT_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DexConfig01;
+    use tuxedo_core::{
+        types::{Input, OutputRef},
+        verifier::{SigCheck, ThresholdMultiSignature, UpForGrabs},
+    };
+
+    fn sig_check() -> OuterVerifier {
+        OuterVerifier::SigCheck(SigCheck {
+            owner_pubkey: Default::default(),
+        })
+    }
+
+    fn utxo(verifier: OuterVerifier) -> Output {
+        Output {
+            payload: money::Coin::<0>(1).into(),
+            verifier,
+        }
+    }
+
+    fn transaction(inputs: usize, outputs: usize, checker: OuterConstraintChecker) -> Transaction {
+        Transaction {
+            inputs: (0..inputs)
+                .map(|_| Input {
+                    output_ref: OutputRef {
+                        tx_hash: Default::default(),
+                        index: 0,
+                    },
+                    redeemer: Vec::new(),
+                })
+                .collect(),
+            outputs: (0..outputs)
+                .map(|_| utxo(OuterVerifier::UpForGrabs(UpForGrabs)))
+                .collect(),
+            checker,
+        }
+    }
+
+    fn spend() -> OuterConstraintChecker {
+        OuterConstraintChecker::Money(money::MoneyConstraintChecker::Spend)
+    }
+
+    #[test]
+    fn signatures_are_counted_through_nested_verifiers() {
+        let multisig = OuterVerifier::ThresholdMultiSignature(ThresholdMultiSignature {
+            threshold: 1,
+            signatories: vec![Default::default(); 3],
+        });
+        let compound = OuterVerifier::Compound(CompoundVerifier::Or(vec![
+            sig_check(),
+            multisig.clone(),
+            OuterVerifier::UpForGrabs(UpForGrabs),
+        ]));
+
+        assert_eq!(signatures(&OuterVerifier::UpForGrabs(UpForGrabs)), 0);
+        assert_eq!(signatures(&sig_check()), 1);
+        assert_eq!(signatures(&multisig), 3);
+        assert_eq!(signatures(&compound), 4);
+    }
+
+    #[test]
+    fn weight_grows_with_inputs_outputs_and_signatures() {
+        let spent = [
+            utxo(sig_check()),
+            utxo(OuterVerifier::UpForGrabs(UpForGrabs)),
+        ];
+        assert_eq!(
+            transaction_weight(&transaction(2, 3, spend()), &spent),
+            BASE_TRANSACTION_WEIGHT
+                + 2 * WEIGHT_PER_INPUT
+                + 3 * WEIGHT_PER_OUTPUT
+                + WEIGHT_PER_SIGNATURE
+        );
+
+        // The UTXO of the second input is unknown, so it counts as a signature.
+        assert_eq!(
+            transaction_weight(&transaction(2, 3, spend()), &spent[..1]),
+            BASE_TRANSACTION_WEIGHT
+                + 2 * WEIGHT_PER_INPUT
+                + 3 * WEIGHT_PER_OUTPUT
+                + 2 * WEIGHT_PER_SIGNATURE
+        );
+    }
+
+    #[test]
+    fn matching_orders_costs_more_per_order() {
+        let matching =
+            OuterConstraintChecker::MatchOrders(dex::MatchOrders::<DexConfig01>::default());
+        assert_eq!(
+            transaction_weight(&transaction(10, 10, matching), &[])
+                - transaction_weight(&transaction(10, 10, spend()), &[]),
+            10 * MATCH_ORDERS_WEIGHT_PER_ORDER
+        );
+    }
+
+    #[test]
+    fn blocks_fill_up_to_the_max_weight() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            assert!(fits(MAX_BLOCK_WEIGHT));
+            record(MAX_BLOCK_WEIGHT - 10);
+            assert!(fits(10));
+            assert!(!fits(11));
+
+            clear();
+            assert_eq!(block_weight(), 0);
+        });
+    }
+}
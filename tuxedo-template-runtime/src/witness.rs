@@ -0,0 +1,118 @@
+//! Transaction ids that leave out the redeemers.
+//!
+//! Each input carries the redeemer that satisfies the verifier of the UTXO it spends, so
+//! the hash of an entire transaction commits to its signatures as well. Anyone who relays
+//! a transaction can change that hash without changing what the transaction does, by
+//! swapping a redeemer for another one that its verifier also accepts. Some signature
+//! schemes let anyone derive a second valid signature from the first, and verifiers like
+//! `UpForGrabs` accept any redeemer at all. The outputs of the relayed copy then land under
+//! refs that their owners don't know, and whatever they had already built on top of them,
+//! like the cancel of an order they just made, can never be valid.
+//!
+//! So the runtime refers to outputs by the transaction's id instead: the hash of the
+//! transaction with all of its redeemers left empty. That is exactly what verifiers check
+//! signatures against, so nobody can change the id without the signers' keys. The executive
+//! still stores new outputs under the hash of the entire transaction, so right after it
+//! applies a transaction the runtime moves them to the refs made from the id. Transactions
+//! in the pool provide those refs too, so relayed copies of a transaction compete for the
+//! same tags and the pool keeps only one of them.
+
+use crate::Transaction;
+use parity_scale_codec::Encode;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use sp_std::prelude::*;
+use tuxedo_core::types::{OutputRef, Transaction as TuxedoTransaction};
+
+/// The transaction with all of its redeemers left empty, which is what verifiers check.
+pub fn stripped<V: Clone, C: Clone>(
+    transaction: &TuxedoTransaction<V, C>,
+) -> TuxedoTransaction<V, C> {
+    let mut stripped = transaction.clone();
+    for input in stripped.inputs.iter_mut() {
+        input.redeemer = Vec::new();
+    }
+    stripped
+}
+
+/// The id of a transaction, which the refs of its outputs are made from.
+pub fn txid<V: Encode + Clone, C: Encode + Clone>(transaction: &TuxedoTransaction<V, C>) -> H256 {
+    BlakeTwo256::hash_of(&stripped(transaction).encode())
+}
+
+/// Move the outputs that the executive just stored for a transaction from the refs made
+/// from the hash of the entire transaction to the refs made from its id.
+///
+/// Transactions whose redeemers are all empty already have the same hash and id, so their
+/// outputs stay where they are.
+pub fn segregate(transaction: &Transaction) {
+    let id = txid(transaction);
+    let full_hash = BlakeTwo256::hash_of(&transaction.encode());
+    if id == full_hash {
+        return;
+    }
+    for index in 0..transaction.outputs.len() as u32 {
+        let stored_at = OutputRef {
+            tx_hash: full_hash,
+            index,
+        }
+        .encode();
+        if let Some(utxo) = sp_io::storage::get(&stored_at) {
+            sp_io::storage::clear(&stored_at);
+            sp_io::storage::set(&OutputRef { tx_hash: id, index }.encode(), &utxo);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OuterConstraintChecker, OuterVerifier, Output, OutputRefs};
+    use tuxedo_core::{types::Input, verifier::UpForGrabs};
+
+    fn transaction(redeemer: Vec<u8>) -> Transaction {
+        Transaction {
+            inputs: vec![Input {
+                output_ref: OutputRef {
+                    tx_hash: H256::repeat_byte(1),
+                    index: 0,
+                },
+                redeemer,
+            }],
+            outputs: vec![Output {
+                payload: money::Coin::<0>(1).into(),
+                verifier: OuterVerifier::UpForGrabs(UpForGrabs),
+            }],
+            checker: OuterConstraintChecker::Money(money::MoneyConstraintChecker::Spend),
+        }
+    }
+
+    #[test]
+    fn redeemers_do_not_change_the_id() {
+        let signed = transaction(vec![1, 2, 3]);
+        let malleated = transaction(vec![4, 5, 6]);
+        assert_ne!(signed.encode(), malleated.encode());
+        assert_eq!(txid(&signed), txid(&malleated));
+        assert_eq!(signed.output_refs(), malleated.output_refs());
+    }
+
+    #[test]
+    fn outputs_are_moved_to_the_refs_made_from_the_id() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            let transaction = transaction(vec![1, 2, 3]);
+            let stored_at = OutputRef {
+                tx_hash: BlakeTwo256::hash_of(&transaction.encode()),
+                index: 0,
+            }
+            .encode();
+            sp_io::storage::set(&stored_at, &transaction.outputs[0].encode());
+
+            segregate(&transaction);
+            assert_eq!(sp_io::storage::get(&stored_at), None);
+            assert_eq!(
+                sp_io::storage::get(&transaction.output_ref(0).encode()).map(|utxo| utxo.to_vec()),
+                Some(transaction.outputs[0].encode())
+            );
+        });
+    }
+}
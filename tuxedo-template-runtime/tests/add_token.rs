@@ -1,18 +1,7 @@
-use tuxedo_template_runtime::OuterConstraintChecker::{self, *};
-
-#[test]
-fn has_three_variants() {
-    fn _match_outer_constraint_checker(c: OuterConstraintChecker) {
-        match c {
-            Money(_) => (),
-            RuntimeUpgrade(_) => (),
-            SecondToken(_) => (),
-        }
-    }
-}
+use tuxedo_template_runtime::OuterConstraintChecker::*;
 
 #[test]
 fn second_token_is_id_1() {
     let coin_1_checker = money::MoneyConstraintChecker::<1>::Mint;
     let _outer_checker = SecondToken(coin_1_checker);
-}
\ No newline at end of file
+}
@@ -1,17 +1,6 @@
-use tuxedo_template_runtime::OuterConstraintChecker::{self, *};
+use tuxedo_template_runtime::{dex, DexConfig01, OuterConstraintChecker::*};
 
 #[test]
-fn has_six_variants() {
-    fn _match_outer_constraint_checker(c: OuterConstraintChecker) {
-        match c {
-            Money(_) => (),
-            RuntimeUpgrade(_) => (),
-            SecondToken(_) => (),
-            MakeOrder01(_) => (),
-            MakeOrder10(_) => (),
-            MatchOrders(_) => (),
-        }
-    }
+fn orders_of_the_pair_can_be_matched() {
+    let _outer_checker = MatchOrders(dex::MatchOrders::<DexConfig01>::default());
 }
-
-//TODO is there some way to make sure that the config is the expected one?
\ No newline at end of file
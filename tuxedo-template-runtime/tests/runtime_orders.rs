@@ -1,16 +1,17 @@
-use tuxedo_template_runtime::OuterConstraintChecker::{self, *};
+use tuxedo_template_runtime::{dex, DexConfig01, OuterConstraintChecker::*};
 
 #[test]
-fn has_five_variants() {
-    fn _match_outer_constraint_checker(c: OuterConstraintChecker) {
-        match c {
-            Money(_) => (),
-            RuntimeUpgrade(_) => (),
-            SecondToken(_) => (),
-            MakeOrder01(_) => (),
-            MakeOrder10(_) => (),
-        }
-    }
+fn orders_can_be_made_on_both_sides_of_the_pair() {
+    let _offers_0 = MakeOrder01(dex::MakeOrder::<DexConfig01>::default());
+    let _offers_1 = MakeOrder10(dex::MakeOrder::<dex::OppositeSide<DexConfig01>>::default());
 }
 
-//TODO is there some way to make sure that the config is the expected one?
\ No newline at end of file
+#[test]
+fn dex_trades_tokens_0_and_1() {
+    fn _token_a(coin: <DexConfig01 as dex::DexConfig>::A) -> money::Coin<0> {
+        coin
+    }
+    fn _token_b(coin: <DexConfig01 as dex::DexConfig>::B) -> money::Coin<1> {
+        coin
+    }
+}
@@ -0,0 +1,234 @@
+//! Utilities for testing Tuxedo pieces end to end.
+//!
+//! Piece tests usually call a constraint checker's `check` function directly with some
+//! hand made inputs and outputs. That covers the checker's logic, but not how a piece
+//! behaves over a series of transactions, like making an order, matching it, and then
+//! spending the payout.
+//!
+//! This crate provides a [`MockUtxoSet`] that applies whole transactions the way the
+//! Tuxedo executive does: every input must exist and satisfy its verifier, the constraint
+//! checker must pass, and then the inputs are consumed and the outputs stored. A
+//! [`MockBlockBuilder`] applies a batch of transactions as a block, so that tests can
+//! also check what a block does as a whole.
+//!
+//! Everything lives in memory, so no externalities are needed.
+
+use std::collections::BTreeMap;
+
+use parity_scale_codec::Encode;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use tuxedo_core::{
+    types::{Output, OutputRef, Transaction},
+    ConstraintChecker, Verifier,
+};
+
+/// All the reasons the mock UTXO set may refuse to apply a transaction.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ApplyError<E> {
+    /// The same input is consumed twice by the transaction.
+    DuplicateInput(OutputRef),
+    /// An input refers to an output that does not exist, or was already spent.
+    MissingInput(OutputRef),
+    /// The verifier protecting the input at the given index was not satisfied.
+    VerifierFailed(usize),
+    /// The constraint checker rejected the transaction.
+    CheckerFailed(E),
+    /// An output of the transaction would overwrite an existing output.
+    PreExistingOutput(OutputRef),
+}
+
+/// A UTXO set that lives in memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockUtxoSet<V> {
+    /// The unspent outputs, keyed by their encoded reference like in runtime storage
+    utxos: BTreeMap<Vec<u8>, (OutputRef, Output<V>)>,
+    /// How many outputs have been stored directly with `insert_genesis`
+    genesis_count: u32,
+}
+
+impl<V> Default for MockUtxoSet<V> {
+    fn default() -> Self {
+        Self {
+            utxos: BTreeMap::new(),
+            genesis_count: 0,
+        }
+    }
+}
+
+impl<V: Verifier> MockUtxoSet<V> {
+    /// An empty UTXO set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store an output directly, without any checks, as genesis would.
+    ///
+    /// Returns the reference under which it was stored.
+    pub fn insert_genesis(&mut self, output: Output<V>) -> OutputRef {
+        let output_ref = OutputRef {
+            tx_hash: Default::default(),
+            index: self.genesis_count,
+        };
+        self.genesis_count += 1;
+        self.utxos
+            .insert(output_ref.encode(), (output_ref.clone(), output));
+        output_ref
+    }
+
+    /// The output stored under the given reference, if it is unspent.
+    pub fn get(&self, output_ref: &OutputRef) -> Option<&Output<V>> {
+        self.utxos
+            .get(&output_ref.encode())
+            .map(|(_, output)| output)
+    }
+
+    /// Whether the output stored under the given reference is unspent.
+    pub fn contains(&self, output_ref: &OutputRef) -> bool {
+        self.utxos.contains_key(&output_ref.encode())
+    }
+
+    /// The number of unspent outputs.
+    pub fn len(&self) -> usize {
+        self.utxos.len()
+    }
+
+    /// Whether there are no unspent outputs at all.
+    pub fn is_empty(&self) -> bool {
+        self.utxos.is_empty()
+    }
+
+    /// All the unspent outputs, sorted by encoded reference.
+    pub fn iter(&self) -> impl Iterator<Item = (&OutputRef, &Output<V>)> {
+        self.utxos
+            .values()
+            .map(|(output_ref, output)| (output_ref, output))
+    }
+
+    /// Apply a transaction, or leave the set untouched if it is invalid.
+    ///
+    /// Returns the references under which the transaction's outputs were stored.
+    pub fn apply<C: ConstraintChecker<V>>(
+        &mut self,
+        transaction: &Transaction<V, C>,
+    ) -> Result<Vec<OutputRef>, ApplyError<C::Error>> {
+        // Verifiers sign over the transaction with all the redeemers stripped.
+        let mut stripped = transaction.clone();
+        for input in stripped.inputs.iter_mut() {
+            input.redeemer = Vec::new();
+        }
+        let simplified_tx = stripped.encode();
+
+        let mut input_utxos = Vec::with_capacity(transaction.inputs.len());
+        for (index, input) in transaction.inputs.iter().enumerate() {
+            if transaction.inputs[..index]
+                .iter()
+                .any(|earlier| earlier.output_ref == input.output_ref)
+            {
+                return Err(ApplyError::DuplicateInput(input.output_ref.clone()));
+            }
+            let utxo = self
+                .get(&input.output_ref)
+                .ok_or_else(|| ApplyError::MissingInput(input.output_ref.clone()))?;
+            if !utxo.verifier.verify(&simplified_tx, &input.redeemer) {
+                return Err(ApplyError::VerifierFailed(index));
+            }
+            input_utxos.push(utxo.clone());
+        }
+
+        transaction
+            .checker
+            .check(&input_utxos, &transaction.outputs)
+            .map_err(ApplyError::CheckerFailed)?;
+
+        let tx_hash = BlakeTwo256::hash_of(&transaction.encode());
+        let output_refs: Vec<OutputRef> = (0..transaction.outputs.len() as u32)
+            .map(|index| OutputRef { tx_hash, index })
+            .collect();
+        if let Some(existing) = output_refs.iter().find(|r| self.contains(r)) {
+            return Err(ApplyError::PreExistingOutput(existing.clone()));
+        }
+
+        for input in &transaction.inputs {
+            self.utxos.remove(&input.output_ref.encode());
+        }
+        for (output_ref, output) in output_refs.iter().zip(&transaction.outputs) {
+            self.utxos
+                .insert(output_ref.encode(), (output_ref.clone(), output.clone()));
+        }
+
+        Ok(output_refs)
+    }
+}
+
+/// The result of applying a block with a [`MockBlockBuilder`].
+#[derive(Debug)]
+pub struct MockBlock<E> {
+    /// The number of the block
+    pub number: u32,
+    /// The outcome of each transaction, in order
+    pub results: Vec<Result<Vec<OutputRef>, ApplyError<E>>>,
+}
+
+impl<E: core::fmt::Debug> MockBlock<E> {
+    /// The output references created by the transaction at the given index.
+    ///
+    /// Panics if that transaction failed.
+    #[track_caller]
+    pub fn outputs_of(&self, index: usize) -> &[OutputRef] {
+        self.results[index]
+            .as_ref()
+            .expect("Transaction was applied successfully")
+    }
+}
+
+/// Builds a chain of blocks on top of a [`MockUtxoSet`].
+pub struct MockBlockBuilder<V, C> {
+    utxo_set: MockUtxoSet<V>,
+    pending: Vec<Transaction<V, C>>,
+    number: u32,
+}
+
+impl<V: Verifier, C: ConstraintChecker<V>> MockBlockBuilder<V, C> {
+    /// Start building blocks on top of the given UTXO set, as its genesis.
+    pub fn new(utxo_set: MockUtxoSet<V>) -> Self {
+        Self {
+            utxo_set,
+            pending: Vec::new(),
+            number: 0,
+        }
+    }
+
+    /// Queue a transaction for the next block.
+    pub fn push(&mut self, transaction: Transaction<V, C>) -> &mut Self {
+        self.pending.push(transaction);
+        self
+    }
+
+    /// Apply all the queued transactions as the next block.
+    ///
+    /// Like a real block author, the builder skips transactions that fail and keeps
+    /// applying the rest.
+    pub fn build(&mut self) -> MockBlock<C::Error> {
+        self.number += 1;
+        let results = self
+            .pending
+            .drain(..)
+            .map(|transaction| self.utxo_set.apply(&transaction))
+            .collect();
+
+        MockBlock {
+            number: self.number,
+            results,
+        }
+    }
+
+    /// The current UTXO set.
+    pub fn utxo_set(&self) -> &MockUtxoSet<V> {
+        &self.utxo_set
+    }
+
+    /// The number of the most recently built block, or zero at genesis.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+}
@@ -0,0 +1,186 @@
+//! Make, match, and spend dex orders against the mock UTXO set.
+
+use dex::{DexConfig, DexError, MakeOrder, MatchOrders, OppositeSide, Order};
+use money::{Coin, ConstraintCheckerError, MoneyConstraintChecker};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_runtime::transaction_validity::TransactionPriority;
+use tuxedo_core::{
+    types::{Input, Output, OutputRef, Transaction},
+    verifier::TestVerifier,
+    ConstraintChecker,
+};
+use tuxedo_test_utils::{ApplyError, MockBlockBuilder, MockUtxoSet};
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct TestConfig;
+impl DexConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+/// The constraint checkers of a tiny runtime with two tokens and a dex between them.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+enum TestChecker {
+    Spend0(MoneyConstraintChecker<0>),
+    Spend1(MoneyConstraintChecker<1>),
+    MakeOrder01(MakeOrder<TestConfig>),
+    MakeOrder10(MakeOrder<OppositeSide<TestConfig>>),
+    MatchOrders(MatchOrders<TestConfig>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TestCheckerError {
+    Money(ConstraintCheckerError),
+    Dex(DexError),
+}
+
+impl ConstraintChecker<TestVerifier> for TestChecker {
+    type Error = TestCheckerError;
+
+    fn check(
+        &self,
+        inputs: &[Output<TestVerifier>],
+        outputs: &[Output<TestVerifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        match self {
+            Self::Spend0(checker) => {
+                ConstraintChecker::<TestVerifier>::check(checker, inputs, outputs)
+                    .map_err(TestCheckerError::Money)
+            }
+            Self::Spend1(checker) => {
+                ConstraintChecker::<TestVerifier>::check(checker, inputs, outputs)
+                    .map_err(TestCheckerError::Money)
+            }
+            Self::MakeOrder01(checker) => {
+                ConstraintChecker::<TestVerifier>::check(checker, inputs, outputs)
+                    .map_err(TestCheckerError::Dex)
+            }
+            Self::MakeOrder10(checker) => {
+                ConstraintChecker::<TestVerifier>::check(checker, inputs, outputs)
+                    .map_err(TestCheckerError::Dex)
+            }
+            Self::MatchOrders(checker) => checker
+                .check(inputs, outputs)
+                .map_err(TestCheckerError::Dex),
+        }
+    }
+}
+
+fn owned_by(
+    verifies: bool,
+    payload: impl Into<tuxedo_core::dynamic_typing::DynamicallyTypedData>,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier: TestVerifier { verifies },
+    }
+}
+
+fn spend(output_ref: &OutputRef) -> Input {
+    Input {
+        output_ref: output_ref.clone(),
+        redeemer: Vec::new(),
+    }
+}
+
+#[test]
+fn make_match_and_spend_payout() {
+    let mut genesis = MockUtxoSet::new();
+    let alice_coin = genesis.insert_genesis(owned_by(true, Coin::<0>(100)));
+    let bob_coin = genesis.insert_genesis(owned_by(true, Coin::<1>(150)));
+    let mut chain = MockBlockBuilder::new(genesis);
+
+    // Alice offers 100 of token 0 for 150 of token 1, and Bob the reverse.
+    chain.push(Transaction {
+        inputs: vec![spend(&alice_coin)],
+        outputs: vec![owned_by(
+            true,
+            Order::<TestConfig>::new(100, 150, TestVerifier { verifies: true }),
+        )],
+        checker: TestChecker::MakeOrder01(Default::default()),
+    });
+    chain.push(Transaction {
+        inputs: vec![spend(&bob_coin)],
+        outputs: vec![owned_by(
+            true,
+            Order::<OppositeSide<TestConfig>>::new(150, 100, TestVerifier { verifies: true }),
+        )],
+        checker: TestChecker::MakeOrder10(Default::default()),
+    });
+    let block = chain.build();
+    let alice_order = block.outputs_of(0)[0].clone();
+    let bob_order = block.outputs_of(1)[0].clone();
+    assert_eq!(chain.utxo_set().len(), 2);
+
+    // Anyone may match the two orders, paying each maker what they asked for.
+    chain.push(Transaction {
+        inputs: vec![spend(&alice_order), spend(&bob_order)],
+        outputs: vec![
+            owned_by(true, Coin::<1>(150)),
+            owned_by(true, Coin::<0>(100)),
+        ],
+        checker: TestChecker::MatchOrders(Default::default()),
+    });
+    let block = chain.build();
+    let alice_payout = block.outputs_of(0)[0].clone();
+    assert!(!chain.utxo_set().contains(&alice_order));
+    assert!(!chain.utxo_set().contains(&bob_order));
+
+    // Alice can spend her payout like any other coin.
+    chain.push(Transaction {
+        inputs: vec![spend(&alice_payout)],
+        outputs: vec![owned_by(true, Coin::<1>(150))],
+        checker: TestChecker::Spend1(MoneyConstraintChecker::Spend),
+    });
+    let block = chain.build();
+    assert!(block.results[0].is_ok());
+    assert_eq!(block.number, 3);
+    assert_eq!(chain.utxo_set().len(), 2);
+}
+
+#[test]
+fn failed_transactions_leave_the_set_untouched() {
+    let mut utxo_set = MockUtxoSet::new();
+    let locked_coin = utxo_set.insert_genesis(owned_by(false, Coin::<0>(100)));
+    let coin = utxo_set.insert_genesis(owned_by(true, Coin::<0>(100)));
+    let before = utxo_set.clone();
+
+    let spend_locked = Transaction {
+        inputs: vec![spend(&locked_coin)],
+        outputs: vec![owned_by(true, Coin::<0>(100))],
+        checker: TestChecker::Spend0(MoneyConstraintChecker::Spend),
+    };
+    assert_eq!(
+        utxo_set.apply(&spend_locked),
+        Err(ApplyError::VerifierFailed(0))
+    );
+
+    let undercollateralized = Transaction {
+        inputs: vec![spend(&coin)],
+        outputs: vec![owned_by(
+            true,
+            Order::<TestConfig>::new(101, 1, TestVerifier { verifies: true }),
+        )],
+        checker: TestChecker::MakeOrder01(Default::default()),
+    };
+    assert_eq!(
+        utxo_set.apply(&undercollateralized),
+        Err(ApplyError::CheckerFailed(TestCheckerError::Dex(
+            DexError::NotEnoughCollateralToOpenOrder
+        )))
+    );
+
+    let double_spend = Transaction {
+        inputs: vec![spend(&coin), spend(&coin)],
+        outputs: vec![owned_by(true, Coin::<0>(200))],
+        checker: TestChecker::Spend0(MoneyConstraintChecker::Spend),
+    };
+    assert_eq!(
+        utxo_set.apply(&double_spend),
+        Err(ApplyError::DuplicateInput(coin))
+    );
+
+    assert_eq!(utxo_set, before);
+}
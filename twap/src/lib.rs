@@ -0,0 +1,293 @@
+//! Time-weighted average prices of a dex market, as an on-chain price source.
+//!
+//! The runtime feeds every trade of the market to this piece as matches are applied, with
+//! [`record_trade`]. The piece keeps an [`Accumulator`] of the market's price in storage:
+//! the sum over time of the price of the latest trade. Anyone may [`Observe`] it by
+//! spending the market's [`TwapOracle`] UTXO, which is protected by a verifier that anyone
+//! can satisfy, and recreating it with one more observation of the accumulator. The
+//! average price between two observations is the difference of their accumulators over
+//! the time between them, so [`TwapOracle::twap`] gives the average price over the last
+//! [`TwapConfig::WINDOW`] without keeping any trade history.
+//!
+//! A single trade can move the last price anywhere, but it only moves the average by how
+//! long that price stands, so pieces that value collateral, such as CDPs and liquidations,
+//! can rely on the average without trusting an external oracle. The oracle forgets the
+//! observations that no longer matter for the window, and observations must be at least
+//! [`TwapConfig::OBSERVATION_INTERVAL`] apart, so it stays small.
+//!
+//! The time is not known to constraint checkers, so the runtime hands it to this piece
+//! through [`TwapConfig::now`]. Observations name their own time, which may not be in the
+//! future nor before the latest trade, so that they can be built before the block that
+//! includes them.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use dex::{book_type_id, DexConfig, Price};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The configuration of the average price of one dex market.
+pub trait TwapConfig {
+    /// The type of verifiers that protect the oracle.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The dex market whose trades are averaged. Prices are in its token B per unit of its
+    /// token A.
+    type Market: DexConfig<Verifier = Self::Verifier>;
+
+    /// The length of time that the average covers, in milliseconds.
+    const WINDOW: u64 = 60 * 60 * 1000;
+    /// The shortest time between two observations, in milliseconds.
+    const OBSERVATION_INTERVAL: u64 = 5 * 60 * 1000;
+
+    /// The verifier that protects the oracle, which anyone must be able to satisfy.
+    fn open_verifier() -> Self::Verifier;
+
+    /// The timestamp of the block that transactions are being checked in, in milliseconds.
+    fn now() -> u64;
+
+    /// The accumulator of the market's trades, or `None` if there have been none.
+    /// Typically this should just be [`recorded_accumulator`].
+    fn accumulator() -> Option<Accumulator>;
+}
+
+/// The sum over time of the price of the latest trade in a market.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Copy, Debug, Default, TypeInfo)]
+pub struct Accumulator {
+    /// The sum of the prices that stood until the latest trade, each times how many
+    /// milliseconds it stood. It wraps around on overflow, so only differences mean anything.
+    pub cumulative: u128,
+    /// The price of the latest trade
+    pub price: Price,
+    /// The time of the latest trade, in milliseconds
+    pub updated_at: u64,
+}
+
+impl Accumulator {
+    /// The sum of the prices until the given time, which must not be before the latest
+    /// trade.
+    pub fn at(&self, timestamp: u64) -> u128 {
+        let elapsed = u128::from(timestamp.saturating_sub(self.updated_at));
+        self.cumulative
+            .wrapping_add(self.price.0.wrapping_mul(elapsed))
+    }
+
+    /// The accumulator after a trade at the given price and time.
+    pub fn after_trade(&self, price: Price, timestamp: u64) -> Self {
+        Self {
+            cumulative: self.at(timestamp),
+            price,
+            updated_at: timestamp.max(self.updated_at),
+        }
+    }
+
+    /// An observation of the accumulator at the given time, or `None` if there was a trade
+    /// after it.
+    ///
+    /// Clients build the oracle output of an observation with this.
+    pub fn observe(&self, timestamp: u64) -> Option<Observation> {
+        (timestamp >= self.updated_at).then(|| Observation {
+            timestamp,
+            cumulative: self.at(timestamp),
+        })
+    }
+}
+
+/// The prefix of the storage keys under which the accumulators of the markets are recorded.
+pub const ACCUMULATOR_PREFIX: &[u8] = b"twap_accumulator";
+
+/// The storage key at which the accumulator of the given configuration's market is recorded.
+pub fn accumulator_key<T: TwapConfig>() -> Vec<u8> {
+    (ACCUMULATOR_PREFIX, <TwapOracle<T> as UtxoData>::TYPE_ID).encode()
+}
+
+/// The recorded accumulator of the given configuration's market, if it has had any trades.
+pub fn recorded_accumulator<T: TwapConfig>() -> Option<Accumulator> {
+    sp_io::storage::get(&accumulator_key::<T>())
+        .and_then(|encoded| Accumulator::decode(&mut &encoded[..]).ok())
+}
+
+/// Record a trade in the given configuration's market at the given price and time.
+///
+/// The runtime calls this for every trade as matches are applied. Of several trades at the
+/// same time, the last one sets the price from then on.
+pub fn record_trade<T: TwapConfig>(price: Price, timestamp: u64) {
+    let accumulator = match recorded_accumulator::<T>() {
+        Some(accumulator) => accumulator.after_trade(price, timestamp),
+        None => Accumulator {
+            cumulative: 0,
+            price,
+            updated_at: timestamp,
+        },
+    };
+    sp_io::storage::set(&accumulator_key::<T>(), &accumulator.encode());
+}
+
+/// The accumulator of a market as it was seen at some time.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Copy, Debug, TypeInfo)]
+pub struct Observation {
+    /// The time of the observation, in milliseconds
+    pub timestamp: u64,
+    /// The sum of the prices until then, as given by [`Accumulator::at`]
+    pub cumulative: u128,
+}
+
+/// The average price between two observations, or `None` unless the second one is later.
+pub fn average(from: &Observation, to: &Observation) -> Option<Price> {
+    let elapsed = to
+        .timestamp
+        .checked_sub(from.timestamp)
+        .filter(|elapsed| *elapsed > 0)?;
+    Some(Price(
+        to.cumulative.wrapping_sub(from.cumulative) / u128::from(elapsed),
+    ))
+}
+
+/// The oracle of the average price of one market, with the observations that the average
+/// over the window needs.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct TwapOracle<T: TwapConfig> {
+    /// The observations, oldest first
+    pub observations: Vec<Observation>,
+    pub _ph_data: PhantomData<T>,
+}
+
+impl<T: TwapConfig> UtxoData for TwapOracle<T> {
+    // The oracle belongs with its market's book, so books of different instances over the
+    // same pair have their own oracles.
+    const TYPE_ID: [u8; 4] = book_type_id::<T::Market>(b't');
+}
+
+impl<T: TwapConfig> TwapOracle<T> {
+    /// The oracle after recording the given observation.
+    ///
+    /// It keeps the newest observation that is at least a window older than the given one,
+    /// and those after it, and forgets the rest. Clients build the oracle output of an
+    /// observation with this.
+    pub fn after_observing(&self, observation: Observation) -> Self {
+        let window_start = observation.timestamp.saturating_sub(T::WINDOW);
+        let mut observations = self.observations.clone();
+        observations.push(observation);
+        let first_needed = observations
+            .iter()
+            .rposition(|past| past.timestamp <= window_start)
+            .unwrap_or(0);
+        observations.drain(..first_needed);
+        Self {
+            observations,
+            _ph_data: PhantomData,
+        }
+    }
+
+    /// The average price over the window that ends at the newest observation, or `None`
+    /// unless the observations cover the whole window.
+    pub fn twap(&self) -> Option<Price> {
+        let newest = self.observations.last()?;
+        let window_start = newest.timestamp.checked_sub(T::WINDOW)?;
+        let oldest = self
+            .observations
+            .iter()
+            .rev()
+            .find(|past| past.timestamp <= window_start)?;
+        average(oldest, newest)
+    }
+}
+
+/// All the things that can go wrong while checking observations.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum TwapError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// The single input, and the single output, must be the oracle.
+    OracleMissing,
+    /// The recreated oracle is not protected by the open verifier.
+    NotOpen,
+    /// The market has had no trades, so there is nothing to observe.
+    NoTrades,
+    /// The observation is later than the current block.
+    ObservationInFuture,
+    /// There was a trade after the time of the observation.
+    ObservationStale,
+    /// The observation is too soon after the previous one.
+    ObservationTooSoon,
+    /// The recreated oracle must record the observation of the accumulator at its time,
+    /// and forget the observations that no longer matter, but keep everything else.
+    OracleChanged,
+}
+
+impl From<DynamicTypingError> for TwapError {
+    fn from(_value: DynamicTypingError) -> Self {
+        TwapError::TypeError
+    }
+}
+
+/// Record an observation of the market's accumulator in its oracle.
+///
+/// The single input is the oracle, and the single output is the oracle after this
+/// observation, as given by [`TwapOracle::after_observing`]. The observation is the newest
+/// one in the output. Its time may not be later than the current block, nor earlier than
+/// the market's latest trade, and must be at least [`TwapConfig::OBSERVATION_INTERVAL`]
+/// after the previous observation.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Observe<T: TwapConfig>(pub PhantomData<T>);
+
+impl<T: TwapConfig> ConstraintChecker<T::Verifier> for Observe<T> {
+    type Error = TwapError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, TwapError::OracleMissing);
+        let oracle: TwapOracle<T> = inputs[0].payload.extract()?;
+        ensure!(outputs.len() == 1, TwapError::OracleMissing);
+        ensure!(
+            outputs[0].verifier == T::open_verifier(),
+            TwapError::NotOpen
+        );
+        let next: TwapOracle<T> = outputs[0].payload.extract()?;
+        let timestamp = next
+            .observations
+            .last()
+            .ok_or(TwapError::OracleChanged)?
+            .timestamp;
+
+        let accumulator = T::accumulator().ok_or(TwapError::NoTrades)?;
+        ensure!(timestamp <= T::now(), TwapError::ObservationInFuture);
+        let observation = accumulator
+            .observe(timestamp)
+            .ok_or(TwapError::ObservationStale)?;
+        if let Some(previous) = oracle.observations.last() {
+            ensure!(
+                timestamp >= previous.timestamp.saturating_add(T::OBSERVATION_INTERVAL),
+                TwapError::ObservationTooSoon
+            );
+        }
+        ensure!(
+            next.observations == oracle.after_observing(observation).observations,
+            TwapError::OracleChanged
+        );
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,258 @@
+//! Unit tests for the TWAP piece
+
+use super::*;
+use money::Coin;
+use sp_io::TestExternalities;
+use std::cell::Cell;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+thread_local! {
+    static NOW: Cell<u64> = Cell::new(0);
+}
+
+fn set_now(now: u64) {
+    NOW.with(|n| n.set(now));
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct Market;
+impl DexConfig for Market {
+    type Verifier = TestVerifier;
+    type A = Coin<0>;
+    type B = Coin<1>;
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct TestConfig;
+impl TwapConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type Market = Market;
+
+    const WINDOW: u64 = 100;
+    const OBSERVATION_INTERVAL: u64 = 10;
+
+    fn open_verifier() -> TestVerifier {
+        ANYONE
+    }
+
+    fn now() -> u64 {
+        NOW.with(|n| n.get())
+    }
+
+    fn accumulator() -> Option<Accumulator> {
+        recorded_accumulator::<Self>()
+    }
+}
+
+/// The verifier that protects the oracle.
+const ANYONE: TestVerifier = TestVerifier { verifies: true };
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn oracle(observations: Vec<Observation>) -> TwapOracle<TestConfig> {
+    TwapOracle {
+        observations,
+        _ph_data: PhantomData,
+    }
+}
+
+fn observation(timestamp: u64, cumulative: u128) -> Observation {
+    Observation {
+        timestamp,
+        cumulative,
+    }
+}
+
+fn observe(
+    input: TwapOracle<TestConfig>,
+    output_oracle: TwapOracle<TestConfig>,
+) -> Result<TransactionPriority, TwapError> {
+    Observe::<TestConfig>::default()
+        .check(&[output(input, ANYONE)], &[output(output_oracle, ANYONE)])
+}
+
+#[test]
+fn accumulator_sums_prices_over_time() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(recorded_accumulator::<TestConfig>(), None);
+
+        record_trade::<TestConfig>(Price(10), 100);
+        record_trade::<TestConfig>(Price(30), 120);
+        // The last trade at a time sets the price.
+        record_trade::<TestConfig>(Price(20), 120);
+
+        let accumulator = recorded_accumulator::<TestConfig>().unwrap();
+        assert_eq!(
+            accumulator,
+            Accumulator {
+                cumulative: 200,
+                price: Price(20),
+                updated_at: 120,
+            }
+        );
+        assert_eq!(accumulator.at(130), 400);
+        assert_eq!(accumulator.observe(119), None);
+        assert_eq!(accumulator.observe(130), Some(observation(130, 400)));
+    });
+}
+
+#[test]
+fn average_is_the_difference_over_the_time_between() {
+    assert_eq!(
+        average(&observation(100, 1000), &observation(150, 2000)),
+        Some(Price(20))
+    );
+    assert_eq!(
+        average(&observation(100, 1000), &observation(100, 1000)),
+        None
+    );
+    // Accumulators wrap around, and only their differences count.
+    assert_eq!(
+        average(&observation(0, u128::MAX), &observation(10, 99)),
+        Some(Price(10))
+    );
+}
+
+#[test]
+fn twap_needs_the_whole_window() {
+    let partial = oracle(vec![observation(50, 0), observation(120, 700)]);
+    assert_eq!(partial.twap(), None);
+
+    let covered = oracle(vec![
+        observation(10, 0),
+        observation(50, 400),
+        observation(120, 1100),
+    ]);
+    // From the newest observation that is a whole window old.
+    assert_eq!(covered.twap(), Some(Price(10)));
+}
+
+#[test]
+fn oracle_forgets_what_the_window_no_longer_needs() {
+    let oracle = oracle(vec![
+        observation(0, 0),
+        observation(30, 300),
+        observation(60, 600),
+    ]);
+    let next = oracle.after_observing(observation(140, 1400));
+    assert_eq!(
+        next.observations,
+        vec![
+            observation(30, 300),
+            observation(60, 600),
+            observation(140, 1400)
+        ]
+    );
+    assert_eq!(next.twap(), Some(Price(10)));
+}
+
+#[test]
+fn observing_works() {
+    TestExternalities::default().execute_with(|| {
+        record_trade::<TestConfig>(Price(10), 100);
+        set_now(200);
+
+        let first = oracle(vec![]).after_observing(observation(150, 500));
+        assert_eq!(observe(oracle(vec![]), first.clone()), Ok(0));
+
+        let second = first.after_observing(observation(200, 1000));
+        assert_eq!(observe(first, second), Ok(0));
+    });
+}
+
+#[test]
+fn observing_before_any_trade_fails() {
+    TestExternalities::default().execute_with(|| {
+        set_now(200);
+        let next = oracle(vec![observation(150, 0)]);
+        assert_eq!(observe(oracle(vec![]), next), Err(TwapError::NoTrades));
+    });
+}
+
+#[test]
+fn observing_the_future_fails() {
+    TestExternalities::default().execute_with(|| {
+        record_trade::<TestConfig>(Price(10), 100);
+        set_now(200);
+        let next = oracle(vec![observation(201, 1010)]);
+        assert_eq!(
+            observe(oracle(vec![]), next),
+            Err(TwapError::ObservationInFuture)
+        );
+    });
+}
+
+#[test]
+fn observing_before_the_latest_trade_fails() {
+    TestExternalities::default().execute_with(|| {
+        record_trade::<TestConfig>(Price(10), 100);
+        set_now(200);
+        let next = oracle(vec![observation(99, 0)]);
+        assert_eq!(
+            observe(oracle(vec![]), next),
+            Err(TwapError::ObservationStale)
+        );
+    });
+}
+
+#[test]
+fn observing_too_soon_fails() {
+    TestExternalities::default().execute_with(|| {
+        record_trade::<TestConfig>(Price(10), 100);
+        set_now(200);
+        let previous = oracle(vec![observation(191, 910)]);
+        let next = previous.after_observing(observation(200, 1000));
+        assert_eq!(observe(previous, next), Err(TwapError::ObservationTooSoon));
+    });
+}
+
+#[test]
+fn observing_the_wrong_accumulator_fails() {
+    TestExternalities::default().execute_with(|| {
+        record_trade::<TestConfig>(Price(10), 100);
+        set_now(200);
+        let next = oracle(vec![observation(200, 999)]);
+        assert_eq!(observe(oracle(vec![]), next), Err(TwapError::OracleChanged));
+    });
+}
+
+#[test]
+fn rewriting_past_observations_fails() {
+    TestExternalities::default().execute_with(|| {
+        record_trade::<TestConfig>(Price(10), 100);
+        set_now(200);
+        let previous = oracle(vec![observation(150, 500)]);
+        let next = oracle(vec![observation(150, 0), observation(200, 1000)]);
+        assert_eq!(observe(previous, next), Err(TwapError::OracleChanged));
+    });
+}
+
+#[test]
+fn recreating_the_oracle_closed_fails() {
+    TestExternalities::default().execute_with(|| {
+        record_trade::<TestConfig>(Price(10), 100);
+        set_now(200);
+        let next = oracle(vec![observation(200, 1000)]);
+        let result = Observe::<TestConfig>::default().check(
+            &[output(oracle(vec![]), ANYONE)],
+            &[output(next, TestVerifier { verifies: false })],
+        );
+        assert_eq!(result, Err(TwapError::NotOpen));
+    });
+}
+
+#[test]
+fn observing_without_the_oracle_fails() {
+    TestExternalities::default().execute_with(|| {
+        let result = Observe::<TestConfig>::default().check(&[], &[output(oracle(vec![]), ANYONE)]);
+        assert_eq!(result, Err(TwapError::OracleMissing));
+    });
+}
@@ -0,0 +1,236 @@
+//! Coins that are locked, and unlock bit by bit over a schedule.
+//!
+//! A [`Vested`] UTXO holds an amount of some coin that unlocks linearly over a
+//! [`VestingSchedule`]. Until a block the schedule starts in nothing is unlocked, and
+//! once its duration has passed everything is. In between, the holder may release what
+//! has unlocked so far as ordinary coins, and the rest stays locked in a new `Vested` UTXO
+//! with the same schedule.
+//!
+//! Coins are locked with [`LockCoins`], or by a dex order that asks to be paid out in a
+//! vested form, which is how token sales with lockups work. The current block is not
+//! known to constraint checkers, so the runtime hands it to this piece through
+//! [`VestingConfig::block_number`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{marker::PhantomData, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::{DynamicTypingError, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    traits::Cash,
+    types::Output,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The configuration of vesting for one coin.
+pub trait VestingConfig {
+    /// The type of verifiers that can own vested coins.
+    /// Typically this should just be the outer verifier type of the runtime.
+    type Verifier: Verifier + PartialEq;
+    /// The coin that is vested
+    type Token: Cash + UtxoData;
+
+    /// The number of the block that transactions are being checked in.
+    fn block_number() -> u32;
+}
+
+/// When locked coins unlock.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, Default, TypeInfo)]
+pub struct VestingSchedule {
+    /// The block in which the coins start to unlock
+    pub start: u32,
+    /// The number of blocks it takes for all the coins to unlock
+    pub duration: u32,
+}
+
+impl VestingSchedule {
+    /// How much of the given total has unlocked in the given block.
+    pub fn unlocked(&self, total: u128, now: u32) -> u128 {
+        let elapsed = now.saturating_sub(self.start);
+        if elapsed >= self.duration {
+            return total;
+        }
+        // Split the total so that the products cannot overflow. The remainder is smaller
+        // than the duration, so its product with the elapsed blocks fits in 64 bits.
+        let (elapsed, duration) = (elapsed as u128, self.duration as u128);
+        total / duration * elapsed + total % duration * elapsed / duration
+    }
+}
+
+/// Coins of type `C` that unlock over a schedule.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, TypeInfo)]
+pub struct Vested<C> {
+    /// The amount that was locked
+    pub total: u128,
+    /// The amount that has been released already
+    pub released: u128,
+    /// When the locked amount unlocks
+    pub schedule: VestingSchedule,
+    pub _ph_data: PhantomData<C>,
+}
+
+impl<C: Cash> UtxoData for Vested<C> {
+    const TYPE_ID: [u8; 4] = [b'v', b'e', b's', C::ID];
+}
+
+impl<C> Vested<C> {
+    /// Freshly locked coins, of which nothing has been released yet.
+    pub fn new(total: u128, schedule: VestingSchedule) -> Self {
+        Self {
+            total,
+            released: 0,
+            schedule,
+            _ph_data: Default::default(),
+        }
+    }
+
+    /// The amount that is still held, whether or not it has unlocked.
+    pub fn held(&self) -> u128 {
+        self.total.saturating_sub(self.released)
+    }
+
+    /// The amount that may be released in the given block.
+    pub fn releasable(&self, now: u32) -> u128 {
+        self.schedule
+            .unlocked(self.total, now)
+            .saturating_sub(self.released)
+    }
+}
+
+/// All the things that can go wrong while checking vesting transactions.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Hash, Debug, TypeInfo)]
+pub enum VestingError {
+    /// Some dynamically typed data was not of the expected type
+    TypeError,
+    /// Some amount does not fit in a `u128`.
+    AmountOverflow,
+    /// Locking coins must create exactly one output, which is the vested coins.
+    WrongOutputCount,
+    /// Newly vested coins must not have released anything yet.
+    AlreadyReleased,
+    /// The vested coins are worth more than the coins that were locked.
+    LockExceedsCoins,
+    /// Releasing must consume exactly one input, which is the vested coins.
+    VestedMissing,
+    /// The released coins are worth more than what has unlocked so far.
+    ReleaseExceedsUnlocked,
+    /// Coins that have not unlocked yet must stay locked, under the same schedule and
+    /// verifier, in the last output.
+    RemainderNotLocked,
+}
+
+impl From<DynamicTypingError> for VestingError {
+    fn from(_value: DynamicTypingError) -> Self {
+        VestingError::TypeError
+    }
+}
+
+/// The total value of the coins of type `C` among the given UTXOs, which must all be such
+/// coins.
+fn coin_total<C: Cash + UtxoData, V>(utxos: &[Output<V>]) -> Result<u128, VestingError> {
+    let mut total = 0u128;
+    for utxo in utxos {
+        let coin: C = utxo.payload.extract()?;
+        total = total
+            .checked_add(coin.value())
+            .ok_or(VestingError::AmountOverflow)?;
+    }
+    Ok(total)
+}
+
+/// Lock coins under a vesting schedule.
+///
+/// All the inputs are coins, and the single output is the vested coins, worth at most
+/// the inputs. Whoever satisfies the output's verifier may release them as they unlock.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct LockCoins<T: VestingConfig>(pub PhantomData<T>);
+
+impl<T: VestingConfig> ConstraintChecker<T::Verifier> for LockCoins<T> {
+    type Error = VestingError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(outputs.len() == 1, VestingError::WrongOutputCount);
+        let vested: Vested<T::Token> = outputs[0].payload.extract()?;
+        ensure!(vested.released == 0, VestingError::AlreadyReleased);
+
+        let locked = coin_total::<T::Token, _>(inputs)?;
+        ensure!(vested.total <= locked, VestingError::LockExceedsCoins);
+
+        Ok(0)
+    }
+}
+
+/// Release the coins that have unlocked so far.
+///
+/// The single input is the vested coins. The outputs are the released coins, followed by
+/// the vested coins that are still locked, unless everything has unlocked already. Those
+/// must keep the schedule and verifier, and record what has been released.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, CloneNoBound, DebugNoBound, DefaultNoBound, TypeInfo)]
+pub struct Release<T: VestingConfig>(pub PhantomData<T>);
+
+impl<T: VestingConfig> ConstraintChecker<T::Verifier> for Release<T> {
+    type Error = VestingError;
+
+    fn check(
+        &self,
+        inputs: &[Output<T::Verifier>],
+        outputs: &[Output<T::Verifier>],
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, VestingError::VestedMissing);
+        let vested: Vested<T::Token> = inputs[0].payload.extract()?;
+        let now = T::block_number();
+
+        // The last output may carry on with what is still locked.
+        let (coins, remainder) = match outputs.split_last() {
+            Some((last, coins)) => match last.payload.extract::<Vested<T::Token>>() {
+                Ok(remainder) => (coins, Some((remainder, &last.verifier))),
+                Err(_) => (outputs, None),
+            },
+            None => (outputs, None),
+        };
+
+        let released = coin_total::<T::Token, _>(coins)?;
+        ensure!(
+            released <= vested.releasable(now),
+            VestingError::ReleaseExceedsUnlocked
+        );
+        let released_so_far = vested
+            .released
+            .checked_add(released)
+            .ok_or(VestingError::AmountOverflow)?;
+
+        match remainder {
+            Some((remainder, verifier)) => ensure!(
+                remainder.total == vested.total
+                    && remainder.released == released_so_far
+                    && remainder.schedule == vested.schedule
+                    && *verifier == inputs[0].verifier,
+                VestingError::RemainderNotLocked
+            ),
+            None => ensure!(
+                vested.schedule.unlocked(vested.total, now) == vested.total,
+                VestingError::RemainderNotLocked
+            ),
+        }
+
+        Ok(0)
+    }
+}
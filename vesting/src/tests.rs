@@ -0,0 +1,135 @@
+//! Unit tests for the vesting piece
+
+use super::*;
+use money::Coin;
+use std::cell::Cell;
+use tuxedo_core::{dynamic_typing::DynamicallyTypedData, verifier::TestVerifier};
+
+thread_local! {
+    static BLOCK: Cell<u32> = Cell::new(0);
+}
+
+fn set_block(block: u32) {
+    BLOCK.with(|b| b.set(block));
+}
+
+#[derive(PartialEq, Eq, TypeInfo)]
+struct TestConfig;
+impl VestingConfig for TestConfig {
+    type Verifier = TestVerifier;
+    type Token = Coin<0>;
+
+    fn block_number() -> u32 {
+        BLOCK.with(|b| b.get())
+    }
+}
+
+const HOLDER: TestVerifier = TestVerifier { verifies: true };
+const SOMEONE_ELSE: TestVerifier = TestVerifier { verifies: false };
+
+/// Unlocks from block 100 to block 200.
+const SCHEDULE: VestingSchedule = VestingSchedule {
+    start: 100,
+    duration: 100,
+};
+
+fn output(
+    payload: impl Into<DynamicallyTypedData>,
+    verifier: TestVerifier,
+) -> Output<TestVerifier> {
+    Output {
+        payload: payload.into(),
+        verifier,
+    }
+}
+
+fn coin(amount: u128) -> Output<TestVerifier> {
+    output(Coin::<0>(amount), HOLDER)
+}
+
+fn vested(total: u128, released: u128) -> Output<TestVerifier> {
+    output(
+        Vested::<Coin<0>> {
+            total,
+            released,
+            schedule: SCHEDULE,
+            _ph_data: Default::default(),
+        },
+        HOLDER,
+    )
+}
+
+fn lock(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, VestingError> {
+    LockCoins::<TestConfig>::default().check(inputs, outputs)
+}
+
+fn release(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, VestingError> {
+    Release::<TestConfig>::default().check(inputs, outputs)
+}
+
+#[test]
+fn schedules_unlock_linearly() {
+    assert_eq!(SCHEDULE.unlocked(1_000, 50), 0);
+    assert_eq!(SCHEDULE.unlocked(1_000, 100), 0);
+    assert_eq!(SCHEDULE.unlocked(1_000, 125), 250);
+    assert_eq!(SCHEDULE.unlocked(1_000, 200), 1_000);
+    assert_eq!(SCHEDULE.unlocked(u128::MAX, 150), u128::MAX / 2);
+}
+
+#[test]
+fn locking_coins_works() {
+    assert_eq!(lock(&[coin(60), coin(40)], &[vested(100, 0)]), Ok(0));
+    assert_eq!(
+        lock(&[coin(60)], &[vested(100, 0)]),
+        Err(VestingError::LockExceedsCoins)
+    );
+    assert_eq!(
+        lock(&[coin(100)], &[vested(100, 10)]),
+        Err(VestingError::AlreadyReleased)
+    );
+}
+
+#[test]
+fn unlocked_coins_can_be_released() {
+    set_block(150);
+    assert_eq!(
+        release(&[vested(1_000, 100)], &[coin(400), vested(1_000, 500)]),
+        Ok(0)
+    );
+    assert_eq!(
+        release(&[vested(1_000, 100)], &[coin(401), vested(1_000, 501)]),
+        Err(VestingError::ReleaseExceedsUnlocked)
+    );
+}
+
+#[test]
+fn locked_coins_must_stay_locked() {
+    set_block(150);
+    assert_eq!(
+        release(&[vested(1_000, 0)], &[coin(500)]),
+        Err(VestingError::RemainderNotLocked)
+    );
+    assert_eq!(
+        release(&[vested(1_000, 0)], &[coin(500), vested(1_000, 400)]),
+        Err(VestingError::RemainderNotLocked)
+    );
+
+    let mut stolen = vested(1_000, 500);
+    stolen.verifier = SOMEONE_ELSE;
+    assert_eq!(
+        release(&[vested(1_000, 0)], &[coin(500), stolen]),
+        Err(VestingError::RemainderNotLocked)
+    );
+}
+
+#[test]
+fn fully_unlocked_coins_need_no_remainder() {
+    set_block(200);
+    assert_eq!(release(&[vested(1_000, 300)], &[coin(700)]), Ok(0));
+}